@@ -35,7 +35,7 @@ fn main() {
     struct Drag;
     impl Drag {
         fn get_cl(&self, airstate: AirState) -> f64 {
-            const C_L_ALPHA: f64 = 2.0*std::f64::consts::PI as f64;
+            const C_L_ALPHA: f64 = 2.0*std::f64::consts::PI;
             const C_L0: f64 = 0.1;
             
             C_L_ALPHA * (AR/(AR+2.0)) * airstate.alpha + C_L0
@@ -46,7 +46,7 @@ fn main() {
             const C_D_MIN: f64 = 0.06;
             let c_l = self.get_cl(airstate);
             
-            let c_d_i = c_l.powi(2) / (std::f64::consts::PI as f64 * AR);
+            let c_d_i = c_l.powi(2) / (std::f64::consts::PI * AR);
             let c_d = C_D_MIN + c_d_i;
             
             let drag = airstate.q * S * c_d;
@@ -63,10 +63,7 @@ fn main() {
 
     let a_body = AeroBody::new(k_body);
     
-    let mut vehicle = AffectedBody {
-        body: a_body,
-        effectors: vec![Box::new(Lift),Box::new(Drag),Box::new(Thrust)],
-        };
+    let mut vehicle = AffectedBody::new(a_body,vec![Box::new(Lift),Box::new(Drag),Box::new(Thrust)]);
     
     let delta_t = 0.01;
     let mut time = 0.0;
@@ -0,0 +1,124 @@
+#ifndef AERSO_H
+#define AERSO_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Status returned by the C interface
+typedef enum AersoStatus {
+  // Success
+  AERSO_OK = 0,
+  // A required pointer was null
+  AERSO_NULL_POINTER = 1,
+  // An argument was invalid, such as a duplicate effector name
+  AERSO_INVALID_ARGUMENT = 2,
+  // The simulation panicked, and the body should be freed
+  AERSO_PANIC = 3,
+} AersoStatus;
+
+// Opaque vehicle handle
+typedef struct AersoBody AersoBody;
+
+// Effector callback
+//
+// Called with the user data, the airstate (6 elements), the body rates (3 elements) and the inputs,
+// and writes the body-frame force (N) and torque (N·m) into the 3-element `force` and `torque` arrays.
+typedef void (*AersoEffectFn)(void *user_data,
+                              const double *airstate,
+                              const double *rates,
+                              const double *inputs,
+                              size_t input_count,
+                              double *force,
+                              double *torque);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Return the library version as a static C string
+const char *aerso_version(void);
+
+// Create a new body with no effectors, returning null if the arguments are invalid
+//
+// # Safety
+//
+// `inertia` must point to 9 elements of the row-major inertia matrix (kg·m²), and `state` to 13
+// statevector elements.
+struct AersoBody *aerso_body_new(double mass, const double *inertia, const double *state);
+
+// Free a body created by [aerso_body_new]
+//
+// # Safety
+//
+// `body` must be null or a body which has not been freed.
+void aerso_body_free(struct AersoBody *body);
+
+// Set a constant North-East-Down wind (m·s<sup>-1</sup>)
+//
+// # Safety
+//
+// `body` must be a live body, and `wind` must point to 3 elements.
+enum AersoStatus aerso_body_set_wind(struct AersoBody *body, const double *wind);
+
+// Add an effector called `name`, which calls `callback` with `user_data` each step
+//
+// # Safety
+//
+// `body` must be a live body and `name` a valid C string. The callback must write 3 elements to each of
+// `force` and `torque`, must not unwind, and `user_data` must remain valid while the body is live.
+enum AersoStatus aerso_body_add_effector(struct AersoBody *body,
+                                         const char *name,
+                                         AersoEffectFn callback,
+                                         void *user_data);
+
+// Enable or disable the effector called `name`
+//
+// # Safety
+//
+// `body` must be a live body and `name` a valid C string.
+enum AersoStatus aerso_body_set_effector_enabled(struct AersoBody *body,
+                                                 const char *name,
+                                                 int enabled);
+
+// Propagate the body by `delta_t` (s) with `input_count` inputs
+//
+// # Safety
+//
+// `body` must be a live body, and `inputs` must point to `input_count` elements, or may be null if
+// `input_count` is zero.
+enum AersoStatus aerso_step(struct AersoBody *body,
+                            double delta_t,
+                            const double *inputs,
+                            size_t input_count);
+
+// Write the 13-element statevector into `state`
+//
+// # Safety
+//
+// `body` must be a live body, and `state` must point to 13 writable elements.
+enum AersoStatus aerso_body_get_state(struct AersoBody *body, double *state);
+
+// Set the statevector from the 13 elements of `state`
+//
+// # Safety
+//
+// `body` must be a live body, and `state` must point to 13 elements.
+enum AersoStatus aerso_body_set_state(struct AersoBody *body, const double *state);
+
+// Write the 6-element airstate into `airstate`
+//
+// # Safety
+//
+// `body` must be a live body, and `airstate` must point to 6 writable elements.
+enum AersoStatus aerso_body_get_airstate(struct AersoBody *body, double *airstate);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* AERSO_H */
@@ -0,0 +1,55 @@
+"""Gymnasium environment for aerso vehicles
+
+Requires the aerso extension module, built with the ``python`` feature, and ``gymnasium``.
+
+Example::
+
+    env = AersoEnv(make_vehicle, action_low=[-0.3, -0.3, -0.3, 0.0], action_high=[0.3, 0.3, 0.3, 1.0],
+                   reward=lambda observation, action: -abs(observation[2] + 1000.0))
+    observation, info = env.reset(seed=0)
+    observation, reward, terminated, truncated, info = env.step(env.action_space.sample())
+"""
+
+import gymnasium as gym
+import numpy as np
+
+import aerso
+
+
+class AersoEnv(gym.Env):
+    """Flight-dynamics environment stepping an ``aerso.AffectedBody``
+
+    Args:
+        make_vehicle: Callable returning a new ``aerso.AffectedBody`` for each episode
+        action_low: Lower bound of each input channel
+        action_high: Upper bound of each input channel
+        delta_t: Timestep of each step (s)
+        max_steps: Steps after which an episode is truncated
+        observe: Optional ``observe(vehicle)`` returning the observation
+        reward: Optional ``reward(observation, action)`` returning the reward
+        terminate: Optional ``terminate(observation)`` returning whether the episode has ended
+    """
+
+    metadata = {"render_modes": []}
+
+    def __init__(self, make_vehicle, action_low, action_high, delta_t=0.01, max_steps=1000,
+                 observe=None, reward=None, terminate=None):
+        action_low = np.asarray(action_low, dtype=np.float64)
+        action_high = np.asarray(action_high, dtype=np.float64)
+        self._env = aerso.FlightEnv(make_vehicle, len(action_low), delta_t, max_steps, observe, reward, terminate)
+        observation, _ = self._env.reset()
+        self.action_space = gym.spaces.Box(action_low, action_high, dtype=np.float64)
+        self.observation_space = gym.spaces.Box(-np.inf, np.inf, shape=observation.shape, dtype=np.float64)
+
+    @property
+    def vehicle(self):
+        """Vehicle of the current episode"""
+        return self._env.vehicle
+
+    def reset(self, *, seed=None, options=None):
+        super().reset(seed=seed)
+        return self._env.reset(seed, options)
+
+    def step(self, action):
+        action = np.clip(np.asarray(action, dtype=np.float64), self.action_space.low, self.action_space.high)
+        return self._env.step(action.tolist())
@@ -1,7 +1,9 @@
 use crate::Body;
-use crate::types::{Vector3,Matrix3,UnitQuaternion,Frame,Force,Torque,StateView};
+use crate::types::{Vector3,Matrix3,UnitQuaternion,Frame,Force,Torque,StateView,Isometry3};
 
-use crate::types::{Float,DefaultFloatRepr};
+use crate::types::{Float,DefaultFloatRepr,constant};
+use crate::Error;
+use crate::snapshot::{WindState,Snapshot};
 
 /// Trait for general wind model
 pub trait WindModel<T: Float = DefaultFloatRepr> {
@@ -13,6 +15,26 @@ pub trait WindModel<T: Float = DefaultFloatRepr> {
     /// Advance time of the wind model by `delta_t` seconds
     fn step(&mut self, delta_t: T);
     
+    /// Export the internal state of the wind model, such as the gusts and random number generator of a
+    /// stochastic model, so it can be checkpointed alongside the body state
+    /// 
+    /// Defaults to the empty state of a stateless model
+    fn snapshot(&self) -> WindState {
+        WindState::default()
+    }
+    
+    /// Restore an internal state exported by [WindModel::snapshot]
+    /// 
+    /// Defaults to accepting only the empty state of a stateless model
+    /// 
+    /// # Errors
+    /// 
+    /// Returns [Error::SnapshotMismatch] if `state` was not exported by this kind of model. A model composed of
+    /// other models may be partially restored when an error is returned.
+    fn restore(&mut self, state: &WindState) -> Result<(),Error> {
+        state.check(0,0,0)
+    }
+    
 }
 
 /// Trait for general density model
@@ -20,19 +42,138 @@ pub trait DensityModel<T: Float = DefaultFloatRepr> {
 
     /// Return the current density at the specified position (kg.m^-3)
     fn get_density(&self, position: &Vector3<T>) -> T;
+    
+    /// Return the current static temperature at the specified position (K)
+    /// 
+    /// Defaults to the ISA standard sea-level temperature
+    fn get_temperature(&self, _position: &Vector3<T>) -> T {
+        constant::<T>(ISA_STANDARD_TEMPERATURE)
+    }
+    
+    /// Return the current static pressure at the specified position (Pa)
+    /// 
+    /// Defaults to the ideal gas pressure of the density and temperature, so it is consistent with both
+    fn get_pressure(&self, position: &Vector3<T>) -> T {
+        self.get_density(position) * constant::<T>(R_AIR) * self.get_temperature(position)
+    }
+    
+    /// Return the current dynamic viscosity at the specified position (Pa·s)
+    /// 
+    /// Defaults to [Sutherland's law](sutherland_viscosity) at the static temperature
+    fn get_viscosity(&self, position: &Vector3<T>) -> T {
+        sutherland_viscosity(self.get_temperature(position))
+    }
+
+}
 
+/// Return the dynamic viscosity of air at `temperature` (K) from Sutherland's law (Pa·s)
+/// 
+/// Uses the ISA constants, `μ = β T^1.5 / (T + S)` with `β = 1.458e-6` kg·m<sup>-1</sup>·s<sup>-1</sup>·K<sup>-0.5</sup>
+/// and `S = 110.4` K, giving 1.789e-5 Pa·s at sea level.
+pub fn sutherland_viscosity<T: Float>(temperature: T) -> T {
+    let beta = constant::<T>(1.458e-6);
+    let s = constant::<T>(110.4);
+    beta * <T as num_traits::Float>::powf(temperature,constant::<T>(1.5)) / (temperature + s)
 }
 
+/// ISA standard sea-level temperature (K)
+pub(crate) const ISA_STANDARD_TEMPERATURE: f64 = 288.15;
+/// ISA standard sea-level pressure (Pa)
+pub(crate) const ISA_STANDARD_PRESSURE: f64 = 101_325.0;
+/// Ratio of specific heats for dry air
+pub(crate) const GAMMA_AIR: f64 = 1.4;
+/// Specific gas constant for dry air (J·kg<sup>-1</sup>·K<sup>-1</sup>)
+pub(crate) const R_AIR: f64 = 287.05287;
+
 /// Built-in [DensityModel] for ISA standard density at sea level
 /// 
 /// This model does not vary density with altitude.
+#[derive(Copy,Clone,Debug)]
 pub struct StandardDensity;
 impl StandardDensity {
     const ISA_STANDARD_DENSITY: f64 = 1.225;
 }
 impl<T: Float> DensityModel<T> for StandardDensity {
     fn get_density(&self, _position: &Vector3<T>) -> T {
-        T::from(Self::ISA_STANDARD_DENSITY).unwrap()
+        constant::<T>(Self::ISA_STANDARD_DENSITY)
+    }
+}
+
+/// Built-in [DensityModel] for the ISA standard atmosphere up to 20 km
+/// 
+/// Temperature falls linearly with altitude in the troposphere and is constant in the lower stratosphere
+/// above 11 km, with pressure in hydrostatic balance and density from the ideal gas law. The sea-level
+/// pressure and temperature may be offset from standard to model non-standard days.
+#[derive(Copy,Clone,Debug)]
+pub struct StandardAtmosphere<T: Float = DefaultFloatRepr> {
+    /// Sea-level pressure (Pa)
+    sea_level_pressure: T,
+    /// Temperature offset from ISA at every altitude (K)
+    temperature_offset: T,
+}
+
+impl<T: Float> Default for StandardAtmosphere<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> StandardAtmosphere<T> {
+    /// Temperature lapse rate in the troposphere (K·m<sup>-1</sup>)
+    const LAPSE_RATE: f64 = 0.0065;
+    /// Altitude of the tropopause (m)
+    const TROPOPAUSE: f64 = 11_000.0;
+    
+    /// Create a new [StandardAtmosphere] with ISA standard sea-level conditions
+    pub fn new() -> Self {
+        StandardAtmosphere {
+            sea_level_pressure: constant::<T>(ISA_STANDARD_PRESSURE),
+            temperature_offset: T::zero(),
+        }
+    }
+    
+    /// Set the sea-level pressure (Pa)
+    pub fn with_sea_level_pressure(mut self, pressure: T) -> Self {
+        self.sea_level_pressure = pressure;
+        self
+    }
+    
+    /// Offset the temperature from ISA at every altitude by `offset` (K)
+    pub fn with_temperature_offset(mut self, offset: T) -> Self {
+        self.temperature_offset = offset;
+        self
+    }
+    
+    /// Return the ISA standard temperature at `altitude` (K)
+    fn standard_temperature(altitude: T) -> T {
+        let altitude = <T as num_traits::Float>::min(altitude,constant::<T>(Self::TROPOPAUSE));
+        constant::<T>(ISA_STANDARD_TEMPERATURE) - constant::<T>(Self::LAPSE_RATE) * altitude
+    }
+}
+
+impl<T: Float> DensityModel<T> for StandardAtmosphere<T> {
+    fn get_density(&self, position: &Vector3<T>) -> T {
+        self.get_pressure(position) / (constant::<T>(R_AIR) * self.get_temperature(position))
+    }
+    
+    fn get_temperature(&self, position: &Vector3<T>) -> T {
+        Self::standard_temperature(-position[2]) + self.temperature_offset
+    }
+    
+    fn get_pressure(&self, position: &Vector3<T>) -> T {
+        let g = constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY);
+        let r = constant::<T>(R_AIR);
+        let altitude = -position[2];
+        let tropopause = constant::<T>(Self::TROPOPAUSE);
+        let exponent = g / (r * constant::<T>(Self::LAPSE_RATE));
+        let t0 = constant::<T>(ISA_STANDARD_TEMPERATURE);
+        let troposphere = |altitude: T| self.sea_level_pressure * <T as num_traits::Float>::powf(Self::standard_temperature(altitude) / t0,exponent);
+        if altitude <= tropopause {
+            troposphere(altitude)
+        } else {
+            let t11 = Self::standard_temperature(tropopause);
+            troposphere(tropopause) * <T as num_traits::Float>::exp(-g * (altitude - tropopause) / (r * t11))
+        }
     }
 }
 
@@ -47,6 +188,10 @@ pub struct AirState<T: Float = DefaultFloatRepr> {
     pub airspeed: T,
     /// Dynamic pressure (Pa) (kg·m<sup>-1</sup>·s<sup>2</sup>)
     pub q: T,
+    /// Mach number
+    pub mach: T,
+    /// Air density (kg·m<sup>-3</sup>)
+    pub density: T,
 }
 
 /// Represent a body in an atmosphere
@@ -58,6 +203,8 @@ pub struct AeroBody<T: Float = DefaultFloatRepr, W: WindModel<T> = ConstantWind<
     wind_model: W,
     /// Optional density model
     density_model: D,
+    /// Elapsed simulation time (s), advanced by each step
+    time: T,
 }
 
 use crate::wind_models::ConstantWind;
@@ -99,19 +246,21 @@ impl<T: Float, W: WindModel<T>, D: DensityModel<T>> AeroBody<T,W,D> {
             body,
             wind_model,
             density_model,
+            time: T::zero(),
         }
     }
     
     /// Return an [AirState] representing the current aerodynamic state of the body
     /// 
-    /// The [AirState] includes the angles of attack (`alpha`) and sideslip (`beta`), the `airspeed` and the dynamic pressure, (`q`).
+    /// The [AirState] includes the angles of attack (`alpha`) and sideslip (`beta`), the `airspeed`, the dynamic pressure, (`q`)
+    /// the Mach number (`mach`) and the local air `density`.
     /// 
     /// It is calculated using the supplied wind and density models.
     pub fn get_airstate(&self) -> AirState<T> {
         
         let current_world_wind = self.wind_model.get_wind(&self.body.position());
         
-        let current_body_wind = self.body.velocity() - Body::get_dcm(&self.body.statevector()) * current_world_wind;
+        let current_body_wind = self.body.velocity() - self.body.dcm() * current_world_wind;
         
         let u = current_body_wind[0];
         let v = current_body_wind[1];
@@ -127,22 +276,121 @@ impl<T: Float, W: WindModel<T>, D: DensityModel<T>> AeroBody<T,W,D> {
         
         let beta = if airspeed != T::zero() { <T as num_traits::Float>::asin( v / airspeed ) } else { T::zero() };
         
-        let q = T::from(0.5).unwrap() * self.density_model.get_density(&self.body.position()) * <T as num_traits::Float>::powi(airspeed,2);
+        let density = self.density_model.get_density(&self.body.position());
+        let q = constant::<T>(0.5) * density * <T as num_traits::Float>::powi(airspeed,2);
+        
+        let temperature = self.density_model.get_temperature(&self.body.position());
+        let speed_of_sound = <T as num_traits::Float>::sqrt( constant::<T>(GAMMA_AIR * R_AIR) * temperature );
+        let mach = airspeed / speed_of_sound;
         
         AirState {
             alpha,
             beta,
             airspeed,
             q,
+            mach,
+            density,
         }
     }
     
+    /// Return the static pressure at the body position (Pa)
+    pub fn get_pressure(&self) -> T {
+        self.density_model.get_pressure(&self.body.position())
+    }
+    
+    /// Return the static temperature at the body position (K)
+    pub fn get_temperature(&self) -> T {
+        self.density_model.get_temperature(&self.body.position())
+    }
+    
+    /// Return the dynamic viscosity at the body position (Pa·s)
+    pub fn get_viscosity(&self) -> T {
+        self.density_model.get_viscosity(&self.body.position())
+    }
+    
+    /// Return the Reynolds number of the airflow over the reference `length` (m)
+    pub fn reynolds_number(&self, length: T) -> T {
+        let airstate = self.get_airstate();
+        airstate.density * airstate.airspeed * length / self.get_viscosity()
+    }
+    
+    /// Return the world-frame wind velocity at the body position
+    pub fn get_wind(&self) -> Vector3<T> {
+        self.wind_model.get_wind(&self.body.position())
+    }
+    
+    /// Return the [WindModel]
+    pub fn wind_model(&self) -> &W {
+        &self.wind_model
+    }
+    
+    /// Return the [WindModel] for modification
+    pub fn wind_model_mut(&mut self) -> &mut W {
+        &mut self.wind_model
+    }
+    
+    /// Calculate the statevector derivative under the supplied `forces` and `torques`
+    /// 
+    /// See the documentation for [Body::derivative] for further details
+    pub fn derivative(&self, forces: &[Force<T>], torques: &[Torque<T>]) -> StateVector<T> {
+        self.body.derivative(forces,torques)
+    }
+    
     /// Propagate the body state and wind_model by `delta_t` under the supplied `forces` and `torques`
     /// 
     /// See the documentation for [Body::step] for further details
     pub fn step(&mut self, forces: &[Force<T>], torques: &[Torque<T>], delta_t: T) {
         self.wind_model.step(delta_t);
         self.body.step(forces, torques, delta_t);        
+        self.time += delta_t;
+    }
+    
+    /// Return the elapsed simulation time (s)
+    /// 
+    /// The clock starts from zero and is advanced by each step, so time-dependent models, schedules and
+    /// loggers can share it rather than integrating the timestep separately. It is not changed by
+    /// [AeroBody::set_state].
+    pub fn time(&self) -> T {
+        self.time
+    }
+    
+    /// Set the elapsed simulation time (s), for example when restarting from a saved state
+    pub fn set_time(&mut self, time: T) {
+        self.time = time;
+    }
+    
+    /// Return a [Snapshot] of the statevector, simulation time and wind model state
+    pub fn snapshot(&self) -> Snapshot<T> {
+        Snapshot {
+            statevector: self.body.statevector(),
+            time: self.time,
+            wind: self.wind_model.snapshot(),
+            effectors: Vec::new(),
+            contacts: Vec::new(),
+        }
+    }
+    
+    /// Restore the statevector, simulation time and wind model state from `snapshot`
+    /// 
+    /// Stepping after a restore reproduces the steps taken after the snapshot. The accelerations are reset
+    /// as by [AeroBody::set_state]. The effector states are restored by [AffectedBody::restore](crate::AffectedBody::restore).
+    /// 
+    /// # Errors
+    /// 
+    /// Returns [Error::SnapshotMismatch] if the wind state does not match the wind model, in which case the
+    /// statevector and time are unchanged.
+    pub fn restore(&mut self, snapshot: &Snapshot<T>) -> Result<(),Error> {
+        self.wind_model.restore(&snapshot.wind)?;
+        self.body.set_state(snapshot.statevector);
+        self.time = snapshot.time;
+        Ok(())
+    }
+    
+    /// Get the world-to-body DCM of the current attitude
+    /// 
+    /// See [Body::dcm] for more details
+    pub fn dcm(&self) -> Matrix3<T> {
+        self.body.dcm()
     }
     
     /// Get body-frame acceleration at the start of the previous timestep
@@ -152,12 +400,26 @@ impl<T: Float, W: WindModel<T>, D: DensityModel<T>> AeroBody<T,W,D> {
         self.body.acceleration()
     }
     
+    /// Get body-frame angular acceleration at the start of the previous timestep
+    /// 
+    /// See [Body::angular_acceleration] for more details
+    pub fn angular_acceleration(&self) -> Vector3<T> {
+        self.body.angular_acceleration()
+    }
+    
     /// Set the statevector for the underlying [Body]
     /// 
     /// The statevector is in the order: \[position,velocity(body),attitude_quaternion(i,j,k,w),axis_rates(body)\]
     pub fn set_state(&mut self, new_state: StateVector<T>) {
         self.body.set_state(new_state);
     }
+    
+    /// Set the position and attitude from `pose`, the body-to-world isometry
+    /// 
+    /// The body-frame velocity and axis rates are kept. See [Body::set_pose] for more details
+    pub fn set_pose(&mut self, pose: &Isometry3<T>) {
+        self.body.set_pose(pose);
+    }
 }
 
 use crate::types::StateVector;
@@ -184,6 +446,21 @@ impl<W: WindModel<T>, D: DensityModel<T>, T: Float> StateView<T> for AeroBody<T,
     }
 }
 
+/// Airstate fixtures for unit tests
+#[cfg(test)]
+pub(crate) mod test_airstate {
+    use crate::AirState;
+
+    /// Return the airstate at angle of attack `alpha` (rad) and `airspeed` (m·s<sup>-1</sup>) in sea-level air
+    ///
+    /// Sideslip is zero, and the dynamic pressure and Mach number follow from the airspeed. Use struct
+    /// update syntax to change the other fields.
+    pub(crate) fn airstate(alpha: f64, airspeed: f64) -> AirState {
+        const DENSITY: f64 = 1.225;
+        AirState { alpha, beta: 0.0, airspeed, q: 0.5 * DENSITY * airspeed * airspeed, mach: airspeed / 340.0, density: DENSITY }
+    }
+}
+
 mod test {
     
     use super::*;
@@ -201,6 +478,22 @@ mod test {
         Body::new( 1.0, Matrix3::identity(), initial_position, initial_velocity, initial_attitude, initial_rates)
     }
 
+    #[rstest]
+    fn test_clock(body: Body<f64>) {
+        let mut vehicle = AeroBody::new(body);
+        assert_eq!(vehicle.time(),0.0);
+        for _ in 0..4 {
+            vehicle.step(&[],&[],0.25);
+        }
+        assert_eq!(vehicle.time(),1.0);
+        // Resetting the state keeps the clock
+        vehicle.set_state(body.statevector());
+        assert_eq!(vehicle.time(),1.0);
+        vehicle.set_time(10.0);
+        vehicle.step(&[],&[],0.5);
+        assert_eq!(vehicle.time(),10.5);
+    }
+    
     #[rstest]
     fn test_zero(body: Body<f64>) {
         use approx::assert_relative_eq;
@@ -314,4 +607,51 @@ mod test {
         assert_relative_eq!(airstate.beta,-45.0f64.to_radians());
     }
 
+    #[rstest]
+    fn test_mach(body: Body<f64>) {
+        use approx::assert_relative_eq;
+        
+        let wind = Vector3::new(-100.0,0.0,0.0);
+        let wind_model = ConstantWind::new(wind);
+        let vehicle = AeroBody::with_wind_model(body,wind_model);
+        
+        let airstate = vehicle.get_airstate();
+        
+        let speed_of_sound = (GAMMA_AIR * R_AIR * ISA_STANDARD_TEMPERATURE).sqrt();
+        assert_relative_eq!(airstate.mach,100.0/speed_of_sound);
+    }
+
+    #[test]
+    fn test_standard_atmosphere() {
+        use approx::assert_relative_eq;
+        
+        let atmosphere = StandardAtmosphere::new();
+        
+        assert_relative_eq!(atmosphere.get_density(&Vector3::zeros()),StandardDensity::ISA_STANDARD_DENSITY,max_relative=1e-4);
+        assert_relative_eq!(atmosphere.get_pressure(&Vector3::new(0.0,0.0,-5000.0)),54_019.9,max_relative=1e-5);
+        assert_relative_eq!(atmosphere.get_temperature(&Vector3::new(0.0,0.0,-15000.0)),216.65);
+        assert_relative_eq!(atmosphere.get_pressure(&Vector3::new(0.0,0.0,-15000.0)),12_044.6,max_relative=1e-5);
+        
+        // Pressure is continuous at the tropopause
+        let below = atmosphere.get_pressure(&Vector3::new(0.0,0.0,-10_999.9));
+        let above = atmosphere.get_pressure(&Vector3::new(0.0,0.0,-11_000.1));
+        assert_relative_eq!(below,above,max_relative=1e-4);
+    }
+
+    #[rstest]
+    fn test_viscosity(body: Body<f64>) {
+        use approx::assert_relative_eq;
+        
+        assert_relative_eq!(sutherland_viscosity(ISA_STANDARD_TEMPERATURE),1.7894e-5,max_relative=1e-4);
+        assert_relative_eq!(sutherland_viscosity(216.65),1.4216e-5,max_relative=1e-4);
+        
+        // Viscosity falls with the temperature aloft
+        let atmosphere = StandardAtmosphere::new();
+        assert_relative_eq!(atmosphere.get_viscosity(&Vector3::new(0.0,0.0,-15000.0)),1.4216e-5,max_relative=1e-4);
+        
+        // A 1 m chord at 50 m/s at sea level
+        let vehicle = AeroBody::with_wind_model(body,ConstantWind::new(Vector3::new(-50.0,0.0,0.0)));
+        assert_relative_eq!(vehicle.reynolds_number(1.0),1.225 * 50.0 / 1.7894e-5,max_relative=1e-4);
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,127 @@
+//! Attitude and angle utilities
+//!
+//! Helpers for angle wrapping and for conversion between the attitude representations used in the crate,
+//! so controllers do not need to reimplement them.
+//!
+//! Conventions follow the rest of the crate:
+//! * The world frame is North-East-Down and the body frame is forward-right-down
+//! * Attitude quaternions rotate body-frame vectors into the world frame, as stored in the statevector
+//! * Euler angles are roll, pitch and yaw (rad), applied in the order yaw, pitch, roll from the world frame
+//! * Direction Cosine Matrices (DCMs) transform world-frame vectors into the body frame, as [Body::get_dcm]
+//!
+//! ```
+//! use aerso::attitude::{euler_to_dcm,angle_difference,wrap_pi};
+//! use aerso::types::Vector3;
+//!
+//! // A vehicle heading East sees North on its left
+//! let dcm = euler_to_dcm(0.0,0.0,std::f64::consts::FRAC_PI_2);
+//! let north = dcm * Vector3::x();
+//! assert!((north - Vector3::new(0.0,-1.0,0.0)).norm() < 1e-12);
+//!
+//! // The shortest turn from 350° to 10° is 20° to the right
+//! assert!((angle_difference(350_f64.to_radians(),10_f64.to_radians()) - 20_f64.to_radians()).abs() < 1e-12);
+//! assert!(wrap_pi(3.0 * std::f64::consts::PI) < 0.0);
+//! ```
+
+use crate::Body;
+use crate::types::{Matrix3,UnitQuaternion,Float,constant};
+
+/// Wrap `angle` (rad) to `[-π, π)`
+pub fn wrap_pi<T: Float>(angle: T) -> T {
+    let pi = constant::<T>(std::f64::consts::PI);
+    let two_pi = pi + pi;
+    angle - two_pi * <T as num_traits::Float>::floor((angle + pi) / two_pi)
+}
+
+/// Wrap `angle` (rad) to `[0, 2π)`
+pub fn wrap_two_pi<T: Float>(angle: T) -> T {
+    let two_pi = constant::<T>(std::f64::consts::TAU);
+    let wrapped = angle - two_pi * <T as num_traits::Float>::floor(angle / two_pi);
+    // Rounding can land a small negative angle on 2π
+    if wrapped >= two_pi { T::zero() } else { wrapped }
+}
+
+/// Return the shortest signed angle (rad) which turns `from` to `to`, in `[-π, π)`
+///
+/// Positive differences are clockwise when viewed from above for headings.
+pub fn angle_difference<T: Float>(from: T, to: T) -> T {
+    wrap_pi(to - from)
+}
+
+/// Return the attitude quaternion of the Euler angles `roll`, `pitch` and `yaw` (rad)
+pub fn euler_to_quaternion<T: Float>(roll: T, pitch: T, yaw: T) -> UnitQuaternion<T> {
+    UnitQuaternion::from_euler_angles(roll,pitch,yaw)
+}
+
+/// Return the Euler angles `(roll, pitch, yaw)` (rad) of the attitude quaternion `attitude`
+///
+/// Roll and yaw are in `[-π, π]` and pitch is in `[-π/2, π/2]`.
+pub fn quaternion_to_euler<T: Float>(attitude: &UnitQuaternion<T>) -> (T,T,T) {
+    attitude.euler_angles()
+}
+
+/// Return the world-to-body DCM of the attitude quaternion `attitude`
+pub fn quaternion_to_dcm<T: Float>(attitude: &UnitQuaternion<T>) -> Matrix3<T> {
+    let mut state = crate::types::StateVector::zeros();
+    state.fixed_rows_mut::<4>(6).copy_from(&attitude.coords);
+    Body::get_dcm(&state)
+}
+
+/// Return the attitude quaternion of the world-to-body DCM `dcm`
+pub fn dcm_to_quaternion<T: Float>(dcm: &Matrix3<T>) -> UnitQuaternion<T> {
+    UnitQuaternion::from_matrix(&dcm.transpose())
+}
+
+/// Return the world-to-body DCM of the Euler angles `roll`, `pitch` and `yaw` (rad)
+pub fn euler_to_dcm<T: Float>(roll: T, pitch: T, yaw: T) -> Matrix3<T> {
+    quaternion_to_dcm(&euler_to_quaternion(roll,pitch,yaw))
+}
+
+/// Return the Euler angles `(roll, pitch, yaw)` (rad) of the world-to-body DCM `dcm`
+pub fn dcm_to_euler<T: Float>(dcm: &Matrix3<T>) -> (T,T,T) {
+    quaternion_to_euler(&dcm_to_quaternion(dcm))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{Vector3,StateVector};
+    use approx::assert_relative_eq;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_angles() {
+        assert_relative_eq!(wrap_pi(PI),-PI);
+        assert_relative_eq!(wrap_pi(-PI),-PI);
+        assert_relative_eq!(wrap_pi(2.5 * PI),0.5 * PI,max_relative=1e-12);
+        assert_relative_eq!(wrap_pi(-0.1),-0.1,max_relative=1e-12);
+        assert_relative_eq!(wrap_two_pi(-0.5 * PI),1.5 * PI,max_relative=1e-12);
+        assert_relative_eq!(wrap_two_pi(4.0 * PI + 0.1),0.1,max_relative=1e-9);
+        assert_eq!(wrap_two_pi(-1e-18),0.0);
+        assert_relative_eq!(angle_difference(0.1,2.0 * PI - 0.1),-0.2,max_relative=1e-9);
+        assert_relative_eq!(angle_difference(-3.0,3.0),6.0 - 2.0 * PI,max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let (roll, pitch, yaw) = (0.3,-0.4,2.5);
+        let attitude = euler_to_quaternion(roll,pitch,yaw);
+        let (r, p, y) = quaternion_to_euler(&attitude);
+        assert_relative_eq!(r,roll,max_relative=1e-12);
+        assert_relative_eq!(p,pitch,max_relative=1e-12);
+        assert_relative_eq!(y,yaw,max_relative=1e-12);
+
+        // The DCM matches the one used by the equations of motion, and inverts the quaternion rotation
+        let mut state = StateVector::zeros();
+        state.fixed_rows_mut::<4>(6).copy_from(&attitude.coords);
+        let dcm = euler_to_dcm(roll,pitch,yaw);
+        assert!((dcm - Body::get_dcm(&state)).norm() < 1e-12);
+        let vector = Vector3::new(1.0,2.0,3.0);
+        assert!((dcm * (attitude * vector) - vector).norm() < 1e-12);
+        assert!(dcm_to_quaternion(&dcm).angle_to(&attitude) < 1e-9);
+        let (r, p, y) = dcm_to_euler(&dcm);
+        assert_relative_eq!(r,roll,max_relative=1e-9);
+        assert_relative_eq!(p,pitch,max_relative=1e-9);
+        assert_relative_eq!(y,yaw,max_relative=1e-9);
+    }
+}
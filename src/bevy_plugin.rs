@@ -0,0 +1,212 @@
+//! Bevy integration
+//!
+//! Enabled by the `bevy` feature, [FlightPlugin] steps [AffectedBody] flight models in Bevy's
+//! `FixedUpdate` schedule and writes their states to `Transform` components, so the crate can be used as
+//! the flight model of a game or visualisation without conversion glue.
+//!
+//! Flight models are not `Send`, so they are kept in the non-send [FlightModels] resource, keyed by the
+//! entity they drive. The entity holds the [FlightInputs] to apply each step, a `Transform` which is
+//! overwritten after each step, and a [FlightState] copy of the latest state for systems which cannot
+//! access the non-send resource. Models are dropped when their entity loses its [FlightInputs].
+//!
+//! Positions and attitudes are converted from North-East-Down and forward-right-down body axes to Bevy's
+//! Y-up convention, with North along −Z and East along +X, and the body forward axis along the
+//! transform's forward (−Z) direction.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel};
+use crate::aero::StandardDensity;
+use crate::types::{Vector3,UnitQuaternion,StateVector,StateView,DefaultFloatRepr};
+use crate::wind_models::ConstantWind;
+
+/// Convert a North-East-Down vector to Bevy's Y-up axes
+pub fn ned_to_bevy(vector: &Vector3<DefaultFloatRepr>) -> Vec3 {
+    Vec3::new(vector[1] as f32,-vector[2] as f32,-vector[0] as f32)
+}
+
+/// Convert a body-to-world attitude to a Bevy rotation
+///
+/// Both the world and body axes are converted, so the body forward axis becomes the rotated
+/// transform's forward (−Z) direction.
+pub fn attitude_to_bevy(attitude: &UnitQuaternion<DefaultFloatRepr>) -> Quat {
+    let vector = ned_to_bevy(&attitude.vector().into_owned());
+    Quat::from_xyzw(vector.x,vector.y,vector.z,attitude.scalar() as f32)
+}
+
+/// Inputs applied to the flight model of an entity each step
+#[derive(Component,Clone,Debug)]
+pub struct FlightInputs<I: Send + Sync + 'static = Vec<DefaultFloatRepr>>(pub I);
+
+/// Latest state of the flight model of an entity
+#[derive(Component,Clone,Copy)]
+pub struct FlightState {
+    /// Statevector
+    pub statevector: StateVector<DefaultFloatRepr>,
+    /// Airstate
+    pub airstate: AirState<DefaultFloatRepr>,
+}
+
+/// Flight models keyed by the entity they drive
+pub struct FlightModels<I: 'static = Vec<DefaultFloatRepr>, W: WindModel<DefaultFloatRepr> + 'static = ConstantWind<DefaultFloatRepr>, D: DensityModel<DefaultFloatRepr> + 'static = StandardDensity> {
+    /// Models by entity
+    models: HashMap<Entity,AffectedBody<I,DefaultFloatRepr,W,D>>,
+}
+
+impl<I, W: WindModel<DefaultFloatRepr>, D: DensityModel<DefaultFloatRepr>> Default for FlightModels<I,W,D> {
+    fn default() -> Self {
+        FlightModels { models: HashMap::new() }
+    }
+}
+
+impl<I, W: WindModel<DefaultFloatRepr>, D: DensityModel<DefaultFloatRepr>> FlightModels<I,W,D> {
+    /// Drive `entity` with `vehicle`, returning any model it replaces
+    pub fn insert(&mut self, entity: Entity, vehicle: AffectedBody<I,DefaultFloatRepr,W,D>) -> Option<AffectedBody<I,DefaultFloatRepr,W,D>> {
+        self.models.insert(entity,vehicle)
+    }
+
+    /// Remove and return the model driving `entity`
+    pub fn remove(&mut self, entity: Entity) -> Option<AffectedBody<I,DefaultFloatRepr,W,D>> {
+        self.models.remove(&entity)
+    }
+
+    /// Return the model driving `entity`
+    pub fn get(&self, entity: Entity) -> Option<&AffectedBody<I,DefaultFloatRepr,W,D>> {
+        self.models.get(&entity)
+    }
+
+    /// Return the model driving `entity` mutably
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut AffectedBody<I,DefaultFloatRepr,W,D>> {
+        self.models.get_mut(&entity)
+    }
+
+    /// Return the number of models
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    /// Return whether there are no models
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+}
+
+/// Marker for the model types of a [FlightPlugin], which is `Send` and `Sync` whatever the types
+type ModelTypes<I,W,D> = fn() -> (I,W,D);
+
+/// Plugin stepping [FlightModels] in the `FixedUpdate` schedule
+pub struct FlightPlugin<I = Vec<DefaultFloatRepr>, W = ConstantWind<DefaultFloatRepr>, D = StandardDensity> {
+    /// Fixed timestep (s), or `None` to keep the app's
+    timestep: Option<f64>,
+    /// Model types
+    models: PhantomData<ModelTypes<I,W,D>>,
+}
+
+impl<I, W, D> Default for FlightPlugin<I,W,D> {
+    fn default() -> Self {
+        FlightPlugin { timestep: None, models: PhantomData }
+    }
+}
+
+impl<I, W, D> FlightPlugin<I,W,D> {
+    /// Create a new [FlightPlugin] using the app's fixed timestep
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the app's fixed timestep to `timestep` (s)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timestep` is not positive.
+    pub fn with_timestep(mut self, timestep: f64) -> Self {
+        if timestep <= 0.0 {
+            panic!("Fixed timestep must be positive");
+        }
+        self.timestep = Some(timestep);
+        self
+    }
+}
+
+impl<I, W, D> Plugin for FlightPlugin<I,W,D>
+    where I: Send + Sync + 'static, W: WindModel<DefaultFloatRepr> + 'static, D: DensityModel<DefaultFloatRepr> + 'static {
+    fn build(&self, app: &mut App) {
+        if let Some(timestep) = self.timestep {
+            app.insert_resource(Time::<Fixed>::from_seconds(timestep));
+        }
+        app.init_resource::<Time<Fixed>>()
+            .insert_non_send_resource(FlightModels::<I,W,D>::default())
+            .add_systems(FixedUpdate,(remove_flight_models::<I,W,D>,step_flight_models::<I,W,D>).chain());
+    }
+}
+
+/// Drop the models of entities which have lost their [FlightInputs]
+fn remove_flight_models<I, W, D>(mut models: NonSendMut<FlightModels<I,W,D>>, mut removed: RemovedComponents<FlightInputs<I>>)
+    where I: Send + Sync + 'static, W: WindModel<DefaultFloatRepr> + 'static, D: DensityModel<DefaultFloatRepr> + 'static {
+    for entity in removed.read() {
+        models.remove(entity);
+    }
+}
+
+/// Step each model by the fixed timestep and write its state to the entity
+fn step_flight_models<I, W, D>(mut commands: Commands, mut models: NonSendMut<FlightModels<I,W,D>>, time: Res<Time<Fixed>>, mut query: Query<(Entity,&FlightInputs<I>,&mut Transform)>)
+    where I: Send + Sync + 'static, W: WindModel<DefaultFloatRepr> + 'static, D: DensityModel<DefaultFloatRepr> + 'static {
+    let delta_t = time.timestep().as_secs_f64() as DefaultFloatRepr;
+    for (entity, inputs, mut transform) in query.iter_mut() {
+        if let Some(vehicle) = models.get_mut(entity) {
+            vehicle.step(delta_t,&inputs.0);
+            transform.translation = ned_to_bevy(&vehicle.position());
+            transform.rotation = attitude_to_bevy(&vehicle.attitude());
+            commands.entity(entity).insert(FlightState { statevector: vehicle.statevector(), airstate: vehicle.get_airstate() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_conversion() {
+        let north = ned_to_bevy(&Vector3::new(1.0,0.0,0.0));
+        assert_eq!(north,Vec3::NEG_Z);
+        assert_eq!(ned_to_bevy(&Vector3::new(0.0,0.0,-10.0)),Vec3::new(0.0,10.0,0.0));
+
+        // Yawed to the East and pitched up, the nose points East and up
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.3,std::f64::consts::FRAC_PI_2 as DefaultFloatRepr);
+        let forward = attitude_to_bevy(&attitude) * Vec3::NEG_Z;
+        let expected = ned_to_bevy(&(attitude * Vector3::new(1.0,0.0,0.0)));
+        assert!((forward - expected).length() < 1e-6);
+        assert!(forward.x > 0.9 && forward.y > 0.2);
+        // The body right axis stays level, pointing South
+        assert!((attitude_to_bevy(&attitude) * Vec3::X - Vec3::Z).length() < 1e-6);
+    }
+
+    #[test]
+    fn test_plugin() {
+        let mut app = App::new();
+        app.add_plugins(FlightPlugin::<Vec<DefaultFloatRepr>>::new().with_timestep(0.01));
+        let entity = app.world_mut().spawn((FlightInputs(vec![0.0,0.0,0.0,0.5]),Transform::default())).id();
+        let mut reference = test_aircraft::vehicle(1000.0);
+        app.world_mut().non_send_resource_mut::<FlightModels>().insert(entity,test_aircraft::vehicle(1000.0));
+
+        for _ in 0..50 {
+            app.world_mut().run_schedule(FixedUpdate);
+            reference.step(0.01,&vec![0.0,0.0,0.0,0.5]);
+        }
+        let transform = *app.world().get::<Transform>(entity).unwrap();
+        let expected = ned_to_bevy(&reference.position());
+        assert!((transform.translation - expected).length() < 1e-3);
+        assert_relative_eq!(transform.translation.y,1000.0,max_relative=0.01);
+        assert_eq!(app.world().get::<FlightState>(entity).unwrap().statevector,reference.statevector());
+
+        app.world_mut().entity_mut(entity).remove::<FlightInputs>();
+        app.world_mut().run_schedule(FixedUpdate);
+        assert!(app.world().non_send_resource::<FlightModels>().is_empty());
+    }
+}
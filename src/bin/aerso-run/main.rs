@@ -0,0 +1,149 @@
+//! Scenario runner
+//!
+//! `aerso-run` loads a scenario file describing a vehicle, its environment, an input schedule and
+//! termination conditions, runs it and writes the time history as CSV, so models defined in
+//! configuration can be exercised without writing Rust. See [scenario] for the file format.
+//!
+//! ```text
+//! aerso-run <scenario> [--output <csv>]
+//! ```
+//!
+//! The binary is built with the `cli` feature, for example with `cargo run --features cli --bin aerso-run`.
+
+mod scenario;
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use aerso::monte_carlo::Termination;
+use aerso::recorder::Recorder;
+use aerso::types::StateView;
+
+use scenario::{Scenario,Limits,Vehicle};
+
+/// Return the reason `vehicle` should stop, if any limit is exceeded
+fn check_limits(vehicle: &Vehicle, limits: &Limits) -> Option<Termination> {
+    let state = vehicle.statevector();
+    if state.iter().any(|value| !value.is_finite()) {
+        return Some(Termination::Failed("State became non-finite".to_string()));
+    }
+    let altitude = -state[2];
+    let airspeed = vehicle.get_airstate().airspeed;
+    let (bank, _, _) = vehicle.attitude().euler_angles();
+    let exceeded = |name: &str, value: f64, limit: f64| Some(Termination::Stopped(format!("{} {:.3} exceeded limit {}",name,value,limit)));
+    match *limits {
+        Limits { min_altitude: Some(limit), .. } if altitude < limit => exceeded("Altitude",altitude,limit),
+        Limits { max_altitude: Some(limit), .. } if altitude > limit => exceeded("Altitude",altitude,limit),
+        Limits { max_airspeed: Some(limit), .. } if airspeed > limit => exceeded("Airspeed",airspeed,limit),
+        Limits { max_bank: Some(limit), .. } if bank.abs() > limit => exceeded("Bank angle",bank,limit),
+        _ => None,
+    }
+}
+
+/// Run `scenario`, returning the recorded history and the reason the run ended
+fn run(scenario: &mut Scenario) -> (Recorder,Termination) {
+    let steps = (scenario.duration / scenario.timestep).round() as usize;
+    let vehicle = &mut scenario.vehicle;
+    let mut recorder = Recorder::new();
+    recorder.record(0.0,vehicle,vehicle.get_airstate(),vehicle.acceleration());
+    for step in 0..steps {
+        let time = step as f64 * scenario.timestep;
+        vehicle.step(scenario.timestep,&scenario.schedule.inputs(time));
+        recorder.record(time + scenario.timestep,vehicle,vehicle.get_airstate(),vehicle.acceleration());
+        if let Some(termination) = check_limits(vehicle,&scenario.limits) {
+            return (recorder,termination);
+        }
+    }
+    (recorder,Termination::Completed)
+}
+
+/// Parse the command line, returning the scenario path and any output override
+fn arguments(mut args: impl Iterator<Item=String>) -> Result<(String,Option<String>),String> {
+    let mut scenario = None;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.next().ok_or_else(|| format!("Missing path after {}",arg))?),
+            "-h" | "--help" => return Err(String::new()),
+            _ if scenario.is_none() => scenario = Some(arg),
+            _ => return Err(format!("Unexpected argument '{}'",arg)),
+        }
+    }
+    Ok((scenario.ok_or_else(|| "Missing scenario file".to_string())?,output))
+}
+
+fn main() -> ExitCode {
+    let (path, output) = match arguments(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {}",message);
+            }
+            eprintln!("usage: aerso-run <scenario> [--output <csv>]");
+            return ExitCode::from(2);
+        },
+    };
+    let mut scenario = match Scenario::from_file(Path::new(&path)) {
+        Ok(scenario) => scenario,
+        Err(message) => {
+            eprintln!("error: {}: {}",path,message);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let (recorder, termination) = run(&mut scenario);
+    let end = recorder.times().last().unwrap_or(0.0);
+    match &termination {
+        Termination::Completed => println!("Completed at t = {:.3} s",end),
+        Termination::Stopped(reason) => println!("Stopped at t = {:.3} s: {}",end,reason),
+        Termination::Failed(reason) => println!("Failed at t = {:.3} s: {}",end,reason),
+    }
+    let position = scenario.vehicle.position();
+    println!("Final position {:.3}, {:.3}, {:.3} m, airspeed {:.3} m/s",position[0],position[1],position[2],scenario.vehicle.get_airstate().airspeed);
+
+    if let Some(output) = output.or(scenario.output) {
+        if let Err(e) = recorder.write_csv(&output) {
+            eprintln!("error: unable to write {}: {}",output,e);
+            return ExitCode::FAILURE;
+        }
+        println!("Wrote {} samples to {}",recorder.len(),output);
+    }
+    match termination {
+        Termination::Failed(_) => ExitCode::FAILURE,
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arguments() {
+        let args = |args: &[&str]| arguments(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(args(&["glide.ini"]),Ok(("glide.ini".to_string(),None)));
+        assert_eq!(args(&["-o","out.csv","glide.ini"]),Ok(("glide.ini".to_string(),Some("out.csv".to_string()))));
+        assert_eq!(args(&[]),Err("Missing scenario file".to_string()));
+        assert_eq!(args(&["a","b"]),Err("Unexpected argument 'b'".to_string()));
+    }
+
+    #[test]
+    fn test_run() {
+        let contents = "
+            [vehicle]
+            mass = 1.0
+            inertia = 1, 1, 1
+            position = 0, 0, -10
+            [run]
+            duration = 5
+            timestep = 0.01
+            min_altitude = 0
+        ";
+        let mut scenario = Scenario::parse(contents,Path::new("")).unwrap();
+        let (recorder, termination) = run(&mut scenario);
+        // Falling from 10 m takes about 1.43 s
+        let end = recorder.times().last().unwrap();
+        assert!((end - 1.43).abs() < 0.02);
+        assert!(matches!(termination,Termination::Stopped(reason) if reason.starts_with("Altitude")));
+    }
+}
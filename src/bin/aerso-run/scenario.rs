@@ -0,0 +1,491 @@
+//! Scenario files
+//!
+//! Scenarios are INI-style text files of `[section]` headers and `key = value` lines, with `#` comments.
+//! Vectors are comma-separated, angles are in radians and all other quantities in SI units.
+//!
+//! * `[vehicle]` - `mass`, `inertia` (3 diagonal or 9 row-major elements), and optional `position`,
+//!   `velocity` (body frame), `attitude` (roll, pitch, yaw) and `rates`
+//! * `[aero]` - optional [LinearAero], with keys named after the [LinearDerivatives] fields and
+//!   optional `channels` (aileron, elevator, rudder)
+//! * `[engine]` - optional [JetEngine], with `max_thrust`, `tsfc`, `time_constant`, `channel` and
+//!   optional `idle` and `position`
+//! * `[environment]` - optional `wind` as `constant, n, e, d`, `log, d, z0, u_star, bearing` or
+//!   `power, u_r, z_r, bearing[, alpha]`, and the ISA `sea_level_pressure` and `temperature_offset`,
+//!   or a `weather` CSV timeline relative to the scenario file in place of `wind` and
+//!   `temperature_offset`, with optional `turbulence` as `airspeed[, sigma]` and `seed`
+//! * `[inputs]` - `template` inputstate, with an optional `csv` schedule relative to the scenario file and
+//!   any number of `signal` lines as `slot, step, start, amplitude`, `slot, doublet, start, width, amplitude`,
+//!   `slot, multistep, start, unit, amplitude` or `slot, chirp, start, duration, f_start, f_end, amplitude`
+//! * `[run]` - `duration` and `timestep`, with optional `min_altitude`, `max_altitude`, `max_airspeed`
+//!   and `max_bank` termination conditions and an `output` CSV path
+
+use std::path::Path;
+
+use aerso::{Body,AeroBody,AffectedBody,AeroEffect,WindModel,DensityModel};
+use aerso::density_models::StandardAtmosphere;
+use aerso::effector_models::{LinearAero,LinearDerivatives,JetEngine};
+use aerso::schedule::{InputSchedule,Signal};
+use aerso::snapshot::WindState;
+use aerso::types::{Vector3,Matrix3,UnitQuaternion};
+use aerso::weather::{WeatherScript,ScriptedWind,ScriptedAtmosphere};
+use aerso::wind_models::{ConstantWind,LogWind,PowerWind,DrydenConfig};
+
+/// Wind model selected by a scenario
+#[derive(Clone)]
+pub enum Wind {
+    /// [ConstantWind]
+    Constant(ConstantWind<f64>),
+    /// [LogWind]
+    Log(LogWind<f64>),
+    /// [PowerWind]
+    Power(PowerWind<f64>),
+    /// [ScriptedWind]
+    Scripted(ScriptedWind<f64>),
+}
+
+impl WindModel<f64> for Wind {
+    fn get_wind(&self, position: &Vector3<f64>) -> Vector3<f64> {
+        match self {
+            Wind::Constant(model) => model.get_wind(position),
+            Wind::Log(model) => model.get_wind(position),
+            Wind::Power(model) => model.get_wind(position),
+            Wind::Scripted(model) => model.get_wind(position),
+        }
+    }
+
+    fn step(&mut self, delta_t: f64) {
+        match self {
+            Wind::Constant(model) => model.step(delta_t),
+            Wind::Log(model) => model.step(delta_t),
+            Wind::Power(model) => model.step(delta_t),
+            Wind::Scripted(model) => model.step(delta_t),
+        }
+    }
+
+    fn snapshot(&self) -> WindState {
+        match self {
+            Wind::Constant(model) => model.snapshot(),
+            Wind::Log(model) => model.snapshot(),
+            Wind::Power(model) => model.snapshot(),
+            Wind::Scripted(model) => model.snapshot(),
+        }
+    }
+
+    fn restore(&mut self, state: &WindState) -> Result<(),aerso::Error> {
+        match self {
+            Wind::Constant(model) => model.restore(state),
+            Wind::Log(model) => model.restore(state),
+            Wind::Power(model) => model.restore(state),
+            Wind::Scripted(model) => model.restore(state),
+        }
+    }
+}
+
+/// Density model selected by a scenario
+#[derive(Clone)]
+pub enum Atmosphere {
+    /// [StandardAtmosphere]
+    Standard(StandardAtmosphere<f64>),
+    /// [ScriptedAtmosphere]
+    Scripted(ScriptedAtmosphere<f64>),
+}
+
+impl DensityModel<f64> for Atmosphere {
+    fn get_density(&self, position: &Vector3<f64>) -> f64 {
+        match self {
+            Atmosphere::Standard(model) => model.get_density(position),
+            Atmosphere::Scripted(model) => model.get_density(position),
+        }
+    }
+
+    fn get_temperature(&self, position: &Vector3<f64>) -> f64 {
+        match self {
+            Atmosphere::Standard(model) => model.get_temperature(position),
+            Atmosphere::Scripted(model) => model.get_temperature(position),
+        }
+    }
+
+    fn get_pressure(&self, position: &Vector3<f64>) -> f64 {
+        match self {
+            Atmosphere::Standard(model) => model.get_pressure(position),
+            Atmosphere::Scripted(model) => model.get_pressure(position),
+        }
+    }
+}
+
+/// Vehicle flown by a scenario
+pub type Vehicle = AffectedBody<Vec<f64>,f64,Wind,Atmosphere>;
+
+/// Limits ending a run early
+#[derive(Copy,Clone,Debug,Default,PartialEq)]
+pub struct Limits {
+    /// Minimum altitude (m)
+    pub min_altitude: Option<f64>,
+    /// Maximum altitude (m)
+    pub max_altitude: Option<f64>,
+    /// Maximum airspeed (m·s<sup>-1</sup>)
+    pub max_airspeed: Option<f64>,
+    /// Maximum absolute bank angle (rad)
+    pub max_bank: Option<f64>,
+}
+
+/// Parsed scenario
+pub struct Scenario {
+    /// Vehicle in its initial state
+    pub vehicle: Vehicle,
+    /// Input schedule
+    pub schedule: InputSchedule<Vec<f64>,f64>,
+    /// Run duration (s)
+    pub duration: f64,
+    /// Timestep (s)
+    pub timestep: f64,
+    /// Termination conditions
+    pub limits: Limits,
+    /// Output CSV path
+    pub output: Option<String>,
+}
+
+/// `key = value` lines of one section, with their line numbers
+struct Section {
+    /// Section name
+    name: String,
+    /// Entries in file order
+    entries: Vec<(usize,String,String)>,
+}
+
+impl Section {
+    /// Return the value of `key`, if present
+    fn get(&self, key: &str) -> Option<(usize,&str)> {
+        self.entries.iter().find(|(_, k, _)| k == key).map(|(line, _, value)| (*line,value.as_str()))
+    }
+
+    /// Return the values of every `key` line
+    fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item=(usize,&'a str)> {
+        self.entries.iter().filter(move |(_, k, _)| k == key).map(|(line, _, value)| (*line,value.as_str()))
+    }
+
+    /// Return the number at `key`, if present
+    fn number(&self, key: &str) -> Result<Option<f64>,String> {
+        self.get(key).map(|(line, value)| parse_number(line,value)).transpose()
+    }
+
+    /// Return the number at `key`
+    fn required(&self, key: &str) -> Result<f64,String> {
+        self.number(key)?.ok_or_else(|| format!("Missing '{}' in [{}]",key,self.name))
+    }
+
+    /// Return the list of numbers at `key`, if present
+    fn numbers(&self, key: &str) -> Result<Option<Vec<f64>>,String> {
+        self.get(key).map(|(line, value)| parse_numbers(line,value)).transpose()
+    }
+
+    /// Return the 3-vector at `key`, or zero if absent
+    fn vector(&self, key: &str) -> Result<Vector3<f64>,String> {
+        match self.numbers(key)? {
+            None => Ok(Vector3::zeros()),
+            Some(values) if values.len() == 3 => Ok(Vector3::from_column_slice(&values)),
+            Some(values) => Err(format!("'{}' in [{}] has {} values, expected 3",key,self.name,values.len())),
+        }
+    }
+
+    /// Return an error for any key not in `known`
+    fn check_keys(&self, known: &[&str]) -> Result<(),String> {
+        match self.entries.iter().find(|(_, key, _)| !known.contains(&key.as_str())) {
+            Some((line, key, _)) => Err(format!("Line {}: unknown key '{}' in [{}]",line,key,self.name)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse `value` on `line` as a number
+fn parse_number(line: usize, value: &str) -> Result<f64,String> {
+    value.trim().parse::<f64>().map_err(|_| format!("Line {}: invalid number '{}'",line,value.trim()))
+}
+
+/// Parse the comma-separated numbers in `value` on `line`
+fn parse_numbers(line: usize, value: &str) -> Result<Vec<f64>,String> {
+    value.split(',').map(|field| parse_number(line,field)).collect()
+}
+
+/// Split `contents` into sections
+fn sections(contents: &str) -> Result<Vec<Section>,String> {
+    let mut sections: Vec<Section> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            let name = name.trim().to_string();
+            if sections.iter().any(|section| section.name == name) {
+                return Err(format!("Line {}: duplicate section [{}]",number,name));
+            }
+            sections.push(Section { name, entries: Vec::new() });
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| format!("Line {}: expected 'key = value'",number))?;
+        let section = sections.last_mut().ok_or_else(|| format!("Line {}: entry before the first section",number))?;
+        section.entries.push((number,key.trim().to_string(),value.trim().to_string()));
+    }
+    Ok(sections)
+}
+
+/// Keys of an `[aero]` section
+const AERO_KEYS: [&str;28] = [
+    "s","b","c","c_lift_0","c_lift_alpha","c_lift_q","c_lift_elevator","c_drag_0","k",
+    "c_side_beta","c_side_p","c_side_r","c_side_rudder",
+    "c_roll_beta","c_roll_p","c_roll_r","c_roll_aileron","c_roll_rudder",
+    "c_pitch_0","c_pitch_alpha","c_pitch_q","c_pitch_elevator",
+    "c_yaw_beta","c_yaw_p","c_yaw_r","c_yaw_aileron","c_yaw_rudder",
+    "channels",
+];
+
+/// Build the [LinearAero] of an `[aero]` section
+fn aero(section: &Section) -> Result<LinearAero<f64>,String> {
+    section.check_keys(&AERO_KEYS)?;
+    let get = |key: &str| section.number(key).map(|value| value.unwrap_or(0.0));
+    let derivatives = LinearDerivatives {
+        s: section.required("s")?, b: section.required("b")?, c: section.required("c")?,
+        c_lift_0: get("c_lift_0")?, c_lift_alpha: get("c_lift_alpha")?, c_lift_q: get("c_lift_q")?, c_lift_elevator: get("c_lift_elevator")?,
+        c_drag_0: get("c_drag_0")?, k: get("k")?,
+        c_side_beta: get("c_side_beta")?, c_side_p: get("c_side_p")?, c_side_r: get("c_side_r")?, c_side_rudder: get("c_side_rudder")?,
+        c_roll_beta: get("c_roll_beta")?, c_roll_p: get("c_roll_p")?, c_roll_r: get("c_roll_r")?, c_roll_aileron: get("c_roll_aileron")?, c_roll_rudder: get("c_roll_rudder")?,
+        c_pitch_0: get("c_pitch_0")?, c_pitch_alpha: get("c_pitch_alpha")?, c_pitch_q: get("c_pitch_q")?, c_pitch_elevator: get("c_pitch_elevator")?,
+        c_yaw_beta: get("c_yaw_beta")?, c_yaw_p: get("c_yaw_p")?, c_yaw_r: get("c_yaw_r")?, c_yaw_aileron: get("c_yaw_aileron")?, c_yaw_rudder: get("c_yaw_rudder")?,
+    };
+    let aero = LinearAero::new(derivatives);
+    match section.get("channels") {
+        None => Ok(aero),
+        Some((line, value)) => {
+            let channels = value.split(',').map(|field| field.trim().parse::<usize>().map_err(|_| format!("Line {}: invalid channel '{}'",line,field.trim()))).collect::<Result<Vec<usize>,String>>()?;
+            match channels[..] {
+                [aileron, elevator, rudder] => Ok(aero.with_channels(aileron,elevator,rudder)),
+                _ => Err(format!("Line {}: expected 3 channels",line)),
+            }
+        },
+    }
+}
+
+/// Build the [JetEngine] of an `[engine]` section
+fn engine(section: &Section) -> Result<JetEngine<f64>,String> {
+    section.check_keys(&["max_thrust","tsfc","time_constant","channel","idle","position"])?;
+    let channel = section.required("channel")?;
+    if channel < 0.0 || channel.fract() != 0.0 {
+        return Err(format!("Invalid engine channel {}",channel));
+    }
+    let mut engine = JetEngine::new(section.required("max_thrust")?,section.required("tsfc")?,section.required("time_constant")?,channel as usize)
+        .with_position(section.vector("position")?);
+    if let Some(idle) = section.number("idle")? {
+        engine = engine.with_idle(idle);
+    }
+    Ok(engine)
+}
+
+/// Parse the `wind` value on `line`
+fn wind(line: usize, value: &str) -> Result<Wind,String> {
+    let mut fields = value.splitn(2,',');
+    let kind = fields.next().unwrap_or("").trim();
+    let values = fields.next().map(|values| parse_numbers(line,values)).transpose()?.unwrap_or_default();
+    match (kind, values.as_slice()) {
+        ("constant", &[n, e, d]) => Ok(Wind::Constant(ConstantWind::new(Vector3::new(n,e,d)))),
+        ("log", &[d, z0, u_star, bearing]) => Ok(Wind::Log(LogWind::new(d,z0,u_star,bearing))),
+        ("power", &[u_r, z_r, bearing]) => Ok(Wind::Power(PowerWind::new(u_r,z_r,bearing))),
+        ("power", &[u_r, z_r, bearing, alpha]) => Ok(Wind::Power(PowerWind::new_with_alpha(u_r,z_r,bearing,alpha))),
+        _ => Err(format!("Line {}: invalid wind '{}'",line,value)),
+    }
+}
+
+/// Parse the `signal` value on `line`, returning the slot and signal
+fn signal(line: usize, value: &str) -> Result<(usize,Signal<f64>),String> {
+    let fields: Vec<&str> = value.split(',').map(|field| field.trim()).collect();
+    let invalid = || format!("Line {}: invalid signal '{}'",line,value);
+    if fields.len() < 2 {
+        return Err(invalid());
+    }
+    let slot = fields[0].parse::<usize>().map_err(|_| invalid())?;
+    let values = fields[2..].iter().map(|field| parse_number(line,field)).collect::<Result<Vec<f64>,String>>()?;
+    let signal = match (fields[1], values.as_slice()) {
+        ("step", &[start, amplitude]) => Signal::Step { start, amplitude },
+        ("doublet", &[start, width, amplitude]) => Signal::Doublet { start, width, amplitude },
+        ("multistep", &[start, unit, amplitude]) => Signal::Multistep3211 { start, unit, amplitude },
+        ("chirp", &[start, duration, f_start, f_end, amplitude]) => Signal::Chirp { start, duration, f_start, f_end, amplitude },
+        _ => return Err(invalid()),
+    };
+    Ok((slot,signal))
+}
+
+impl Scenario {
+    /// Parse a scenario from `contents`, resolving relative paths against `directory`
+    pub fn parse(contents: &str, directory: &Path) -> Result<Self,String> {
+        let sections = sections(contents)?;
+        if let Some(section) = sections.iter().find(|section| !["vehicle","aero","engine","environment","inputs","run"].contains(&section.name.as_str())) {
+            return Err(format!("Unknown section [{}]",section.name));
+        }
+        let section = |name: &str| sections.iter().find(|section| section.name == name);
+        let empty = |name: &str| Section { name: name.to_string(), entries: Vec::new() };
+
+        let vehicle = section("vehicle").ok_or_else(|| "Missing [vehicle] section".to_string())?;
+        vehicle.check_keys(&["mass","inertia","position","velocity","attitude","rates"])?;
+        let mass = vehicle.required("mass")?;
+        let inertia = match vehicle.numbers("inertia")?.ok_or_else(|| "Missing 'inertia' in [vehicle]".to_string())? {
+            values if values.len() == 3 => Matrix3::from_diagonal(&Vector3::from_column_slice(&values)),
+            values if values.len() == 9 => Matrix3::from_row_slice(&values),
+            values => return Err(format!("'inertia' has {} values, expected 3 or 9",values.len())),
+        };
+        if mass <= 0.0 || inertia.try_inverse().is_none() {
+            return Err("Vehicle mass must be positive and inertia invertible".to_string());
+        }
+        let attitude = vehicle.vector("attitude")?;
+        let body = Body::new(mass,inertia,vehicle.vector("position")?,vehicle.vector("velocity")?,
+            UnitQuaternion::from_euler_angles(attitude[0],attitude[1],attitude[2]),vehicle.vector("rates")?);
+
+        let empty_environment = empty("environment");
+        let environment = section("environment").unwrap_or(&empty_environment);
+        environment.check_keys(&["wind","sea_level_pressure","temperature_offset","weather","turbulence","seed"])?;
+        let mut atmosphere = StandardAtmosphere::new();
+        if let Some(pressure) = environment.number("sea_level_pressure")? {
+            atmosphere = atmosphere.with_sea_level_pressure(pressure);
+        }
+        let (wind, atmosphere) = match environment.get("weather") {
+            Some((line, path)) => {
+                if environment.get("wind").is_some() || environment.get("temperature_offset").is_some() {
+                    return Err(format!("Line {}: 'weather' replaces 'wind' and 'temperature_offset'",line));
+                }
+                let turbulence = match environment.numbers("turbulence")?.as_deref() {
+                    None => DrydenConfig::high_altitude(50.0,1.0),
+                    Some(&[airspeed]) => DrydenConfig::high_altitude(airspeed,1.0),
+                    Some(&[airspeed, sigma]) => DrydenConfig::high_altitude(airspeed,sigma),
+                    Some(_) => return Err("'turbulence' expects airspeed[, sigma]".to_string()),
+                };
+                if turbulence.airspeed <= 0.0 {
+                    return Err("Turbulence airspeed must be positive".to_string());
+                }
+                let path = directory.join(path);
+                let contents = std::fs::read_to_string(&path).map_err(|e| format!("Unable to read {}: {}",path.display(),e))?;
+                let script = WeatherScript::from_csv(turbulence,&contents).map_err(|e| format!("{}: {}",path.display(),e))?;
+                let (wind, atmosphere) = script.with_atmosphere(atmosphere).models(environment.number("seed")?.unwrap_or(0.0) as u64);
+                (Wind::Scripted(wind),Atmosphere::Scripted(atmosphere))
+            },
+            None => {
+                let wind = match environment.get("wind") {
+                    Some((line, value)) => wind(line,value)?,
+                    None => Wind::Constant(ConstantWind::new(Vector3::zeros())),
+                };
+                (wind,Atmosphere::Standard(atmosphere.with_temperature_offset(environment.number("temperature_offset")?.unwrap_or(0.0))))
+            },
+        };
+
+        let mut effectors: Vec<Box<dyn AeroEffect<Vec<f64>,f64>>> = Vec::new();
+        if let Some(section) = section("aero") {
+            effectors.push(Box::new(aero(section)?));
+        }
+        if let Some(section) = section("engine") {
+            effectors.push(Box::new(engine(section)?));
+        }
+        let vehicle = AffectedBody::new(AeroBody::with_density_model(body,wind,atmosphere),effectors);
+
+        let empty_inputs = empty("inputs");
+        let inputs = section("inputs").unwrap_or(&empty_inputs);
+        inputs.check_keys(&["template","csv","signal"])?;
+        let template = inputs.numbers("template")?.unwrap_or_default();
+        let mut schedule = match inputs.get("csv") {
+            Some((_, path)) => {
+                let path = directory.join(path);
+                let contents = std::fs::read_to_string(&path).map_err(|e| format!("Unable to read {}: {}",path.display(),e))?;
+                InputSchedule::from_csv(template.clone(),&contents).map_err(|e| format!("{}: {}",path.display(),e))?
+            },
+            None => InputSchedule::new(template.clone()),
+        };
+        for (line, value) in inputs.get_all("signal") {
+            let (slot, signal) = signal(line,value)?;
+            if slot >= template.len() {
+                return Err(format!("Line {}: signal slot {} is outside the template",line,slot));
+            }
+            schedule = schedule.with_signal(slot,signal);
+        }
+
+        let run = section("run").ok_or_else(|| "Missing [run] section".to_string())?;
+        run.check_keys(&["duration","timestep","min_altitude","max_altitude","max_airspeed","max_bank","output"])?;
+        let timestep = run.required("timestep")?;
+        if timestep <= 0.0 {
+            return Err("Timestep must be positive".to_string());
+        }
+        Ok(Scenario {
+            vehicle,
+            schedule,
+            duration: run.required("duration")?,
+            timestep,
+            limits: Limits {
+                min_altitude: run.number("min_altitude")?,
+                max_altitude: run.number("max_altitude")?,
+                max_airspeed: run.number("max_airspeed")?,
+                max_bank: run.number("max_bank")?,
+            },
+            output: run.get("output").map(|(_, path)| directory.join(path).to_string_lossy().into_owned()),
+        })
+    }
+
+    /// Read and parse the scenario file at `path`
+    pub fn from_file(path: &Path) -> Result<Self,String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {}",path.display(),e))?;
+        Self::parse(&contents,path.parent().unwrap_or_else(|| Path::new("")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aerso::types::StateView;
+
+    const SCENARIO: &str = "
+        # Trimmed glide with an elevator doublet
+        [vehicle]
+        mass = 2.0
+        inertia = 0.1, 0.2, 0.3
+        position = 0, 0, -100
+        velocity = 20, 0, 0
+        attitude = 0, 0.05, 0
+
+        [aero]
+        s = 0.5
+        b = 1.6
+        c = 0.3
+        c_lift_alpha = 4.5
+        c_drag_0 = 0.03
+
+        [environment]
+        wind = constant, -5, 0, 0
+
+        [inputs]
+        template = 0, 0, 0
+        signal = 1, doublet, 1.0, 0.5, 0.05
+
+        [run]
+        duration = 10
+        timestep = 0.01
+        min_altitude = 0
+        output = glide.csv
+    ";
+
+    #[test]
+    fn test_parse() {
+        let scenario = Scenario::parse(SCENARIO,Path::new("out")).unwrap();
+        assert_eq!(scenario.vehicle.position(),Vector3::new(0.0,0.0,-100.0));
+        assert!((scenario.vehicle.get_airstate().airspeed - 25.0).abs() < 0.01);
+        assert_eq!(scenario.schedule.inputs(1.2),vec![0.0,0.05,0.0]);
+        assert_eq!(scenario.limits,Limits { min_altitude: Some(0.0), ..Default::default() });
+        assert_eq!(scenario.output,Some(Path::new("out").join("glide.csv").to_string_lossy().into_owned()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |contents: &str| Scenario::parse(contents,Path::new("")).err().unwrap();
+        assert_eq!(error("[run]\nduration = 1\ntimestep = 0.1"),"Missing [vehicle] section");
+        assert_eq!(error(&SCENARIO.replace("c_drag_0","c_drag_zero")),"Line 15: unknown key 'c_drag_zero' in [aero]");
+        assert_eq!(error(&SCENARIO.replace("timestep = 0.01","timestep = fast")),"Line 26: invalid number 'fast'");
+        assert_eq!(error(&SCENARIO.replace("1, doublet","5, doublet")),"Line 22: signal slot 5 is outside the template");
+        assert_eq!(error(&SCENARIO.replace("constant, -5","gusty, -5")),"Line 18: invalid wind 'gusty, -5, 0, 0'");
+        assert_eq!(error(&SCENARIO.replace("[environment]","[environment]\nweather = weather.csv")),"Line 18: 'weather' replaces 'wind' and 'temperature_offset'");
+    }
+}
@@ -0,0 +1,272 @@
+//! CS-25/FAR-25 gust and turbulence certification profiles
+//!
+//! Environment generators for the discrete-gust (CS 25.341(a)) and continuous-turbulence (CS 25.341(b))
+//! design conditions, so compliance-style load cases can be flown directly.
+//!
+//! Design gust velocities are defined as equivalent airspeeds and scale with altitude and the
+//! [FlightProfile] alleviation factor. The generators convert them to true airspeed in the ISA.
+//! For speeds at V<sub>D</sub>, the regulations take half of the V<sub>C</sub> velocities.
+//!
+//! ```
+//! use aerso::certification::{FlightProfile,DiscreteGust};
+//!
+//! let profile = FlightProfile::new(0.8,0.7,12_000.0);
+//! let gusts: Vec<_> = DiscreteGust::<f64>::gradient_distances(10).into_iter()
+//!     .map(|gradient| DiscreteGust::cs25(&profile,5000.0,gradient,200.0,1.0))
+//!     .collect();
+//! ```
+
+use crate::{WindModel,DensityModel,Error};
+use crate::snapshot::{WindState,export};
+use crate::density_models::StandardAtmosphere;
+use crate::types::{Vector3,Float,constant};
+use crate::wind_models::DrydenConfig;
+
+/// Shortest design gust gradient distance (m)
+const MIN_GRADIENT: f64 = 9.0;
+/// Longest design gust gradient distance (m)
+const MAX_GRADIENT: f64 = 107.0;
+/// Continuous turbulence length scale (m)
+const TURBULENCE_LENGTH: f64 = 762.0;
+
+/// Linearly interpolate `points` of (altitude (m), value) at `altitude`, holding the end values
+fn interpolate(points: &[(f64,f64)], altitude: f64) -> f64 {
+    let index = points.iter().position(|&(point, _)| point > altitude);
+    match index {
+        None => points[points.len() - 1].1,
+        Some(0) => points[0].1,
+        Some(index) => {
+            let ((a0, v0), (a1, v1)) = (points[index - 1],points[index]);
+            v0 + (v1 - v0) * (altitude - a0) / (a1 - a0)
+        },
+    }
+}
+
+/// Return the ratio of true to equivalent airspeed at `altitude` (m) in the ISA
+fn tas_ratio<T: Float>(altitude: T) -> T {
+    let atmosphere = StandardAtmosphere::new();
+    let density = |altitude: T| atmosphere.get_density(&Vector3::new(T::zero(),T::zero(),-altitude));
+    <T as num_traits::Float>::sqrt(density(T::zero()) / density(altitude))
+}
+
+/// Reference discrete gust velocity U<sub>ref</sub> (m·s<sup>-1</sup> EAS) at `altitude` (m), at V<sub>C</sub>
+pub fn reference_gust_velocity<T: Float>(altitude: T) -> T {
+    let points = [(0.0,17.07),(4572.0,13.41),(18_288.0,6.36)];
+    constant::<T>(interpolate(&points,altitude.to_f64().unwrap()))
+}
+
+/// Reference continuous turbulence intensity U<sub>σref</sub> (m·s<sup>-1</sup> TAS) at `altitude` (m), at V<sub>C</sub>
+pub fn reference_turbulence_intensity<T: Float>(altitude: T) -> T {
+    let points = [(0.0,27.43),(7315.0,24.08)];
+    constant::<T>(interpolate(&points,altitude.to_f64().unwrap()))
+}
+
+/// Weights and ceiling setting the flight profile alleviation factor F<sub>g</sub>
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct FlightProfile<T: Float> {
+    /// Maximum landing weight over maximum take-off weight
+    pub landing_ratio: T,
+    /// Maximum zero-fuel weight over maximum take-off weight
+    pub zero_fuel_ratio: T,
+    /// Maximum operating altitude Z<sub>mo</sub> (m)
+    pub max_operating_altitude: T,
+}
+
+impl<T: Float> FlightProfile<T> {
+    /// Create a new [FlightProfile]
+    ///
+    /// # Arguments
+    ///
+    /// * `landing_ratio` - Maximum landing weight over maximum take-off weight, R<sub>1</sub>
+    /// * `zero_fuel_ratio` - Maximum zero-fuel weight over maximum take-off weight, R<sub>2</sub>
+    /// * `max_operating_altitude` - Maximum operating altitude (m)
+    ///
+    /// # Panics
+    ///
+    /// Panics if either ratio is outside `(0, 1]` or the altitude is not positive.
+    pub fn new(landing_ratio: T, zero_fuel_ratio: T, max_operating_altitude: T) -> Self {
+        let ratio = |ratio: T| ratio > T::zero() && ratio <= T::one();
+        if !ratio(landing_ratio) || !ratio(zero_fuel_ratio) || max_operating_altitude <= T::zero() {
+            panic!("Weight ratios must be in (0, 1] and the maximum operating altitude positive");
+        }
+        FlightProfile { landing_ratio, zero_fuel_ratio, max_operating_altitude }
+    }
+
+    /// Return the flight profile alleviation factor F<sub>g</sub> at `altitude` (m)
+    ///
+    /// F<sub>g</sub> rises linearly from `0.5 (Fgz + Fgm)` at sea level to one at the maximum operating altitude.
+    pub fn alleviation(&self, altitude: T) -> T {
+        let quarter_pi = constant::<T>(std::f64::consts::FRAC_PI_4);
+        let fgm = <T as num_traits::Float>::sqrt(self.zero_fuel_ratio * <T as num_traits::Float>::tan(quarter_pi * self.landing_ratio));
+        let fgz = T::one() - self.max_operating_altitude / constant::<T>(76_200.0);
+        let sea_level = constant::<T>(0.5) * (fgz + fgm);
+        let fraction = <T as num_traits::Float>::min(<T as num_traits::Float>::max(altitude / self.max_operating_altitude,T::zero()),T::one());
+        sea_level + (T::one() - sea_level) * fraction
+    }
+}
+
+/// Return the design gust velocity U<sub>ds</sub> (m·s<sup>-1</sup> EAS) at `altitude` (m) for the gust
+/// gradient distance `gradient` (m), at V<sub>C</sub>
+pub fn design_gust_velocity<T: Float>(profile: &FlightProfile<T>, altitude: T, gradient: T) -> T {
+    let scale = <T as num_traits::Float>::powf(gradient / constant::<T>(MAX_GRADIENT),constant::<T>(1.0 / 6.0));
+    reference_gust_velocity(altitude) * profile.alleviation(altitude) * scale
+}
+
+/// Built-in [WindModel] for a discrete "1-cosine" gust
+///
+/// The gust builds up over the gradient distance `H` and decays over the next, with velocity
+/// `U/2 (1 - cos(π s/H))` at penetration distance `s`. Penetration is in time at a constant airspeed
+/// from the start time; the gust is uniform in space.
+#[derive(Copy,Clone,Debug)]
+pub struct DiscreteGust<T: Float> {
+    /// World-frame gust velocity at its peak (m·s<sup>-1</sup>)
+    peak: Vector3<T>,
+    /// Gust gradient distance (m)
+    gradient: T,
+    /// Penetration airspeed (m·s<sup>-1</sup>)
+    airspeed: T,
+    /// Time the gust is entered (s)
+    start: T,
+    /// Time since the model was created (s)
+    time: T,
+}
+
+impl<T: Float> DiscreteGust<T> {
+    /// Create a new [DiscreteGust]
+    ///
+    /// # Arguments
+    ///
+    /// * `peak` - World-frame gust velocity at its peak (m·s<sup>-1</sup> TAS)
+    /// * `gradient` - Gust gradient distance (m)
+    /// * `airspeed` - Penetration airspeed (m·s<sup>-1</sup> TAS)
+    /// * `start` - Time the gust is entered (s)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the gradient distance or airspeed is not positive.
+    pub fn new(peak: Vector3<T>, gradient: T, airspeed: T, start: T) -> Self {
+        if gradient <= T::zero() || airspeed <= T::zero() {
+            panic!("Gust gradient distance and airspeed must be positive");
+        }
+        DiscreteGust { peak, gradient, airspeed, start, time: T::zero() }
+    }
+
+    /// Create the CS 25.341(a) vertical design gust at V<sub>C</sub>, for a vehicle at `altitude` (m) and true
+    /// `airspeed` (m·s<sup>-1</sup>), with gradient distance `gradient` (m) entered at `start` (s)
+    ///
+    /// The gust is upward; negate [DiscreteGust::peak] for the downward case.
+    pub fn cs25(profile: &FlightProfile<T>, altitude: T, gradient: T, airspeed: T, start: T) -> Self {
+        let velocity = design_gust_velocity(profile,altitude,gradient) * tas_ratio(altitude);
+        Self::new(Vector3::new(T::zero(),T::zero(),-velocity),gradient,airspeed,start)
+    }
+
+    /// Return `count` gust gradient distances (m) evenly spanning the regulatory 9 m to 107 m
+    pub fn gradient_distances(count: usize) -> Vec<T> {
+        let step = if count > 1 { (MAX_GRADIENT - MIN_GRADIENT) / (count - 1) as f64 } else { 0.0 };
+        (0..count).map(|index| constant::<T>(MIN_GRADIENT + step * index as f64)).collect()
+    }
+
+    /// Return the peak world-frame gust velocity (m·s<sup>-1</sup>)
+    pub fn peak(&self) -> Vector3<T> {
+        self.peak
+    }
+
+    /// Set the peak world-frame gust velocity (m·s<sup>-1</sup>)
+    pub fn with_peak(mut self, peak: Vector3<T>) -> Self {
+        self.peak = peak;
+        self
+    }
+
+    /// Return the time the gust has fully passed (s)
+    pub fn end(&self) -> T {
+        self.start + (self.gradient + self.gradient) / self.airspeed
+    }
+}
+
+impl<T: Float> WindModel<T> for DiscreteGust<T> {
+    fn get_wind(&self, _position: &Vector3<T>) -> Vector3<T> {
+        let distance = (self.time - self.start) * self.airspeed;
+        if distance <= T::zero() || distance >= self.gradient + self.gradient {
+            return Vector3::zeros();
+        }
+        let pi = constant::<T>(std::f64::consts::PI);
+        let shape = constant::<T>(0.5) * (T::one() - <T as num_traits::Float>::cos(pi * distance / self.gradient));
+        self.peak * shape
+    }
+
+    fn step(&mut self, delta_t: T) {
+        self.time += delta_t;
+    }
+
+    fn snapshot(&self) -> WindState {
+        WindState { values: vec![export(self.time)], ..WindState::default() }
+    }
+
+    fn restore(&mut self, state: &WindState) -> Result<(),Error> {
+        state.check(1,0,0)?;
+        self.time = state.value(0);
+        Ok(())
+    }
+}
+
+/// Return the CS 25.341(b) continuous turbulence at V<sub>C</sub> for a vehicle at `altitude` (m) and true
+/// `airspeed` (m·s<sup>-1</sup>), for [DrydenTurbulence](crate::wind_models::DrydenTurbulence)
+///
+/// The limit intensity U<sub>σ</sub> is used as the isotropic standard deviation with the 762 m (2500 ft)
+/// length scale, so the standard deviation of a simulated load is its limit value. The Dryden spectrum
+/// approximates the von Kármán spectrum of the regulation.
+pub fn continuous_turbulence<T: Float>(profile: &FlightProfile<T>, altitude: T, airspeed: T) -> DrydenConfig<T> {
+    let sigma = reference_turbulence_intensity(altitude) * profile.alleviation(altitude);
+    let length = constant::<T>(TURBULENCE_LENGTH);
+    DrydenConfig::new(airspeed,Vector3::new(sigma,sigma,sigma),Vector3::new(length,length,length))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_design_velocities() {
+        assert_relative_eq!(reference_gust_velocity(0.0),17.07);
+        assert_relative_eq!(reference_gust_velocity(2286.0),15.24,max_relative=1e-9);
+        assert_relative_eq!(reference_gust_velocity(20_000.0),6.36);
+        assert_relative_eq!(reference_turbulence_intensity(10_000.0),24.08);
+
+        let profile = FlightProfile::new(0.8,0.7,12_000.0);
+        let fgm = (0.7 * (std::f64::consts::FRAC_PI_4 * 0.8).tan()).sqrt();
+        let fgz = 1.0 - 12_000.0 / 76_200.0;
+        assert_relative_eq!(profile.alleviation(0.0),0.5 * (fgz + fgm),max_relative=1e-9);
+        assert_relative_eq!(profile.alleviation(15_000.0),1.0);
+        // The longest gradient distance takes the full reference velocity
+        assert_relative_eq!(design_gust_velocity(&profile,0.0,107.0),17.07 * profile.alleviation(0.0),max_relative=1e-9);
+        assert_relative_eq!(design_gust_velocity(&profile,0.0,9.0) / design_gust_velocity(&profile,0.0,107.0),(9.0_f64 / 107.0).powf(1.0 / 6.0),max_relative=1e-9);
+
+        let turbulence = continuous_turbulence(&profile,12_000.0,250.0);
+        assert_relative_eq!(turbulence.sigma[2],24.08,max_relative=1e-9);
+        assert_relative_eq!(turbulence.length[0],762.0);
+    }
+
+    #[test]
+    fn test_discrete_gust() {
+        let profile = FlightProfile::new(0.8,0.7,12_000.0);
+        let gradient = DiscreteGust::<f64>::gradient_distances(3);
+        assert_eq!(gradient,vec![9.0,58.0,107.0]);
+
+        let mut gust = DiscreteGust::cs25(&profile,0.0,50.0,100.0,1.0);
+        let peak = design_gust_velocity(&profile,0.0,50.0);
+        assert_relative_eq!(gust.peak()[2],-peak,max_relative=1e-6);
+        assert_relative_eq!(gust.end(),2.0);
+
+        let mut history = Vec::new();
+        for _ in 0..250 {
+            gust.step(0.01);
+            history.push(gust.get_wind(&Vector3::zeros())[2]);
+        }
+        // Calm before the gust, peaking at the gradient distance and gone after twice it
+        assert_eq!(history[98],0.0);
+        assert_relative_eq!(history[149],-peak,max_relative=1e-6);
+        assert_eq!(history[200],0.0);
+        assert!(history.iter().all(|&velocity| velocity <= 0.0 && velocity >= -peak - 1e-9));
+    }
+}
@@ -0,0 +1,356 @@
+//! Contact with the surface
+//!
+//! A [ContactModel] generates forces and torques on a vehicle from its full statevector, unlike an
+//! [AeroEffect](crate::AeroEffect) which only sees the airstate. Contact models are added to an
+//! [AffectedBody](crate::AffectedBody) with [AffectedBody::add_contact](crate::AffectedBody::add_contact),
+//! and are evaluated at the start of each step alongside the effectors.
+//!
+//! [WaterContact] models seaplane floats and hulls on the water: hydrostatic buoyancy on defined
+//! [FloatHull] geometries, hydrodynamic drag and planing lift, on a calm surface or regular waves.
+
+use crate::{Body,Error};
+use crate::snapshot::{ContactState,export};
+use crate::types::{Vector3,StateVector,StateView,Force,Torque,Float,DefaultFloatRepr,constant};
+
+/// Trait for forces and torques from contact with the surface
+pub trait ContactModel<T: Float = DefaultFloatRepr> {
+    /// Return the `Force` and `Torque` on a vehicle in `state`
+    ///
+    /// # Arguments
+    /// * `state` - The vehicle statevector at the current timestep
+    fn get_contact(&self, state: &StateVector<T>) -> (Force<T>,Torque<T>);
+
+    /// Advance any internal state of the contact by `delta_t`, after `get_contact` has been called with `state`
+    ///
+    /// The default implementation does nothing.
+    fn step(&mut self, _state: &StateVector<T>, _delta_t: T) {}
+
+    /// Export the internal state of the contact advanced by [ContactModel::step], such as a wave phase, so
+    /// it can be checkpointed alongside the body state
+    ///
+    /// Defaults to the empty state of a stateless contact
+    fn snapshot(&self) -> ContactState {
+        ContactState::default()
+    }
+
+    /// Restore an internal state exported by [ContactModel::snapshot]
+    ///
+    /// Defaults to accepting only the empty state of a stateless contact
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::SnapshotMismatch] if `state` was not exported by this kind of contact.
+    fn restore(&mut self, state: &ContactState) -> Result<(),Error> {
+        state.check(0,0,0)
+    }
+}
+
+/// Rectangular float or hull bottom
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct FloatHull<T: Float = DefaultFloatRepr> {
+    /// Keel midpoint relative to centre of mass, body frame (m)
+    pub position: Vector3<T>,
+    /// Length along body X (m)
+    pub length: T,
+    /// Beam along body Y (m)
+    pub beam: T,
+    /// Depth from the keel to the deck, the greatest immersion (m)
+    pub height: T,
+}
+
+impl<T: Float> FloatHull<T> {
+    /// Create a new [FloatHull] with its keel midpoint at `position`, body frame (m)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length`, `beam` or `height` is not positive.
+    pub fn new(position: Vector3<T>, length: T, beam: T, height: T) -> Self {
+        if length <= T::zero() || beam <= T::zero() || height <= T::zero() {
+            panic!("Float dimensions must be positive");
+        }
+        FloatHull { position, length, beam, height }
+    }
+}
+
+/// Regular waves on a [WaterContact] surface
+#[derive(Copy,Clone,Debug,PartialEq)]
+struct Waves<T: Float> {
+    /// Wave amplitude (m)
+    amplitude: T,
+    /// Wavenumber (rad·m<sup>-1</sup>)
+    wavenumber: T,
+    /// Heading the waves travel towards (rad)
+    heading: T,
+}
+
+/// Built-in [ContactModel] for seaplane floats and hulls on the water
+///
+/// Each [FloatHull] is split into segments along its length. A segment whose keel is below the water
+/// surface has immersion `d`, up to the float height, and generates
+///
+/// * buoyancy `ρ_w g l b d`, vertically upwards;
+/// * drag `½ ρ_w C_D l b |V| V` opposing the velocity of the segment through the water;
+/// * planing lift `½ ρ_w V_h² l b a τ`, vertically upwards, for horizontal speed `V_h` and trim angle `τ`
+///   of the body X axis above the horizontal, when the trim is positive,
+///
+/// for segment length `l` and beam `b`, all acting at the segment keel. The moments of the segments
+/// about the centre of mass give the pitch and roll stability on the water.
+///
+/// The mean surface is level at a Down coordinate set by [WaterContact::with_surface] or
+/// [WaterContact::set_surface] for an externally scheduled wave height. Regular deep-water waves set by
+/// [WaterContact::with_waves] are added, advanced in time by [ContactModel::step]. The water is at rest.
+#[derive(Clone,Debug)]
+pub struct WaterContact<T: Float = DefaultFloatRepr> {
+    /// Floats
+    floats: Vec<FloatHull<T>>,
+    /// Segments along each float
+    segments: usize,
+    /// Water density (kg·m<sup>-3</sup>)
+    density: T,
+    /// Hydrodynamic drag coefficient
+    drag_coefficient: T,
+    /// Planing lift curve slope (rad<sup>-1</sup>)
+    lift_slope: T,
+    /// Down coordinate of the mean surface (m)
+    surface: T,
+    /// Regular waves, if any
+    waves: Option<Waves<T>>,
+    /// Wave time (s)
+    time: T,
+}
+
+impl<T: Float> WaterContact<T> {
+    /// Create a new [WaterContact] for `floats` on calm sea water at zero Down
+    ///
+    /// Each float is split into 8 segments, with a drag coefficient of 0.5 and a planing lift curve
+    /// slope of 1.
+    pub fn new(floats: Vec<FloatHull<T>>) -> Self {
+        WaterContact {
+            floats,
+            segments: 8,
+            density: constant::<T>(1025.0),
+            drag_coefficient: constant::<T>(0.5),
+            lift_slope: T::one(),
+            surface: T::zero(),
+            waves: None,
+            time: T::zero(),
+        }
+    }
+
+    /// Split each float into `segments` segments along its length
+    ///
+    /// # Panics
+    ///
+    /// Panics if `segments` is zero.
+    pub fn with_segments(mut self, segments: usize) -> Self {
+        if segments == 0 {
+            panic!("Floats must have at least one segment");
+        }
+        self.segments = segments;
+        self
+    }
+
+    /// Set the water density (kg·m<sup>-3</sup>)
+    pub fn with_density(mut self, density: T) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Set the hydrodynamic drag coefficient and planing lift curve slope (rad<sup>-1</sup>)
+    pub fn with_hydrodynamics(mut self, drag_coefficient: T, lift_slope: T) -> Self {
+        self.drag_coefficient = drag_coefficient;
+        self.lift_slope = lift_slope;
+        self
+    }
+
+    /// Set the Down coordinate of the mean surface (m)
+    pub fn with_surface(mut self, surface: T) -> Self {
+        self.surface = surface;
+        self
+    }
+
+    /// Add regular deep-water waves of `amplitude` and `wavelength` (m), travelling towards `heading` (rad)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `wavelength` is not positive.
+    pub fn with_waves(mut self, amplitude: T, wavelength: T, heading: T) -> Self {
+        if wavelength <= T::zero() {
+            panic!("Wavelength must be positive");
+        }
+        self.waves = Some(Waves { amplitude, wavenumber: T::two_pi() / wavelength, heading });
+        self
+    }
+
+    /// Set the Down coordinate of the mean surface (m)
+    pub fn set_surface(&mut self, surface: T) {
+        self.surface = surface;
+    }
+
+    /// Return the wave height above the mean surface at the world `position` (m)
+    pub fn wave_height(&self, position: &Vector3<T>) -> T {
+        match self.waves {
+            Some(waves) => {
+                // Deep-water dispersion, ω² = g k
+                let frequency = <T as num_traits::Float>::sqrt(constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY) * waves.wavenumber);
+                let (sin_heading, cos_heading) = <T as num_traits::Float>::sin_cos(waves.heading);
+                let distance = position[0] * cos_heading + position[1] * sin_heading;
+                waves.amplitude * <T as num_traits::Float>::cos(waves.wavenumber * distance - frequency * self.time)
+            },
+            None => T::zero(),
+        }
+    }
+
+    /// Return the immersion of the keel at the world `position` (m), not limited by the float height
+    pub fn immersion(&self, position: &Vector3<T>) -> T {
+        position[2] - (self.surface - self.wave_height(position))
+    }
+}
+
+impl<T: Float> ContactModel<T> for WaterContact<T> {
+    fn get_contact(&self, state: &StateVector<T>) -> (Force<T>,Torque<T>) {
+        let dcm = Body::get_dcm(state);
+        let dcm_body = dcm.transpose();
+        let velocity = state.velocity();
+        let rates = state.rates();
+        let gravity = constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY);
+        let half = constant::<T>(0.5);
+        let trim = <T as num_traits::Float>::asin(-(dcm_body * Vector3::x())[2]);
+        let segments = constant::<T>(self.segments as f64);
+
+        let mut force = Vector3::zeros();
+        let mut torque = Vector3::zeros();
+        for float in &self.floats {
+            let length = float.length / segments;
+            let area = length * float.beam;
+            for segment in 0..self.segments {
+                let offset = (constant::<T>(segment as f64) + half) * length - half * float.length;
+                let keel = float.position + Vector3::x() * offset;
+                let immersion = num_traits::clamp(self.immersion(&(state.position() + dcm_body * keel)),T::zero(),float.height);
+                if immersion <= T::zero() {
+                    continue;
+                }
+                let world_velocity = dcm_body * (velocity + rates.cross(&keel));
+                let horizontal_speed_sqd = world_velocity[0] * world_velocity[0] + world_velocity[1] * world_velocity[1];
+                let planing = if trim > T::zero() { half * self.density * horizontal_speed_sqd * area * self.lift_slope * trim } else { T::zero() };
+                let buoyancy = self.density * gravity * area * immersion;
+                let drag = world_velocity * (-half * self.density * self.drag_coefficient * area * world_velocity.norm());
+
+                let segment_force = dcm * (drag - Vector3::z() * (buoyancy + planing));
+                force += segment_force;
+                torque += keel.cross(&segment_force);
+            }
+        }
+        (Force::body_vec(force),Torque::body_vec(torque))
+    }
+
+    fn step(&mut self, _state: &StateVector<T>, delta_t: T) {
+        self.time += delta_t;
+    }
+
+    fn snapshot(&self) -> ContactState {
+        ContactState { values: vec![export(self.time)], ..ContactState::default() }
+    }
+
+    fn restore(&mut self, state: &ContactState) -> Result<(),Error> {
+        state.check(1,0,0)?;
+        self.time = state.value(0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AeroBody,AffectedBody};
+    use crate::types::{Matrix3,UnitQuaternion};
+    use approx::assert_relative_eq;
+
+    /// Twin floats 3 m long and 0.5 m wide, 0.6 m below the centre of mass
+    fn floats() -> Vec<FloatHull> {
+        [-0.8,0.8].iter().map(|&y| FloatHull::new(Vector3::new(0.0,y,0.6),3.0,0.5,0.4)).collect()
+    }
+
+    fn state(down: f64, velocity: Vector3<f64>, attitude: UnitQuaternion<f64>) -> StateVector<f64> {
+        Body::new(1.0,Matrix3::identity(),Vector3::new(0.0,0.0,down),velocity,attitude,Vector3::zeros()).statevector()
+    }
+
+    #[test]
+    fn test_buoyancy() {
+        let water = WaterContact::new(floats());
+        // Keels 0.1 m below the surface
+        let (force,torque) = water.get_contact(&state(-0.5,Vector3::zeros(),UnitQuaternion::identity()));
+        let buoyancy = 1025.0 * physical_constants::STANDARD_ACCELERATION_OF_GRAVITY * 2.0 * 3.0 * 0.5 * 0.1;
+        assert_relative_eq!(force.force.z,-buoyancy,max_relative=1e-9);
+        assert!(torque.torque.norm() < 1e-9);
+        // Out of the water, and limited by the float height
+        assert_eq!(water.get_contact(&state(-1.0,Vector3::zeros(),UnitQuaternion::identity())).0.force.norm(),0.0);
+        let (submerged,_) = water.get_contact(&state(1.0,Vector3::zeros(),UnitQuaternion::identity()));
+        assert_relative_eq!(submerged.force.z,-buoyancy * 4.0,max_relative=1e-9);
+
+        // Nose-down, the bows immerse deeper and pitch the nose back up
+        let (_,restoring) = water.get_contact(&state(-0.5,Vector3::zeros(),UnitQuaternion::from_euler_angles(0.0,-0.05,0.0)));
+        assert!(restoring.torque.y > 0.0);
+
+        // Drag opposes motion through the water, and nose-up trim planes
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.05,0.0);
+        let (planing,_) = water.get_contact(&state(-0.5,attitude.inverse_transform_vector(&Vector3::new(10.0,0.0,0.0)),attitude));
+        let (floating,_) = water.get_contact(&state(-0.5,Vector3::zeros(),attitude));
+        assert!(planing.force.x < 0.0);
+        assert!(planing.force.z < floating.force.z);
+    }
+
+    #[test]
+    fn test_floating_on_waves() {
+        // A 300 kg seaplane settles at the draft where buoyancy balances its weight
+        let body = Body::new(300.0,Matrix3::from_diagonal(&Vector3::new(400.0,300.0,600.0)),Vector3::new(0.0,0.0,-0.7),Vector3::zeros(),UnitQuaternion::identity(),Vector3::zeros());
+        let mut vehicle = AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::new(body),vec![]);
+        vehicle.add_contact(Box::new(WaterContact::new(floats()).with_hydrodynamics(5.0,1.0)));
+        (0..2000).for_each(|_| vehicle.step(0.01,&vec![]));
+        let draft = 300.0 / (1025.0 * 2.0 * 3.0 * 0.5);
+        assert_relative_eq!(vehicle.position()[2] + 0.6,draft,max_relative=0.01);
+
+        // Waves travel along their heading at the deep-water phase speed
+        let mut waves = WaterContact::<f64>::new(floats()).with_waves(0.2,20.0,0.0);
+        assert_relative_eq!(waves.wave_height(&Vector3::zeros()),0.2);
+        let speed = (physical_constants::STANDARD_ACCELERATION_OF_GRAVITY * 20.0 / std::f64::consts::TAU).sqrt();
+        waves.step(&StateVector::zeros(),1.0);
+        assert_relative_eq!(waves.wave_height(&Vector3::new(speed,0.0,0.0)),0.2,max_relative=1e-9);
+        assert_relative_eq!(waves.immersion(&Vector3::new(speed,0.0,0.0)),0.2,max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_wave_restore() {
+        // The wave phase is restored, so the float forces repeat
+        let mut water = WaterContact::new(floats()).with_waves(0.2,20.0,0.3);
+        let floating = state(-0.5,Vector3::zeros(),UnitQuaternion::identity());
+        (0..30).for_each(|_| water.step(&floating,0.01));
+        let checkpoint = water.snapshot();
+        let run = |water: &mut WaterContact| -> Vec<_> {
+            (0..50).map(|_| { water.step(&floating,0.01); water.get_contact(&floating).0.force }).collect()
+        };
+        let forces = run(&mut water);
+        water.restore(&checkpoint).unwrap();
+        assert_eq!(run(&mut water),forces);
+        assert_eq!(water.restore(&ContactState::default()),Err(Error::SnapshotMismatch));
+
+        // A vehicle riding the waves replays from its snapshot
+        let body = Body::new(300.0,Matrix3::from_diagonal(&Vector3::new(400.0,300.0,600.0)),Vector3::new(0.0,0.0,-0.7),Vector3::zeros(),UnitQuaternion::identity(),Vector3::zeros());
+        let mut vehicle = AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::new(body),vec![]);
+        vehicle.add_contact(Box::new(WaterContact::new(floats()).with_waves(0.2,20.0,0.3)));
+        (0..50).for_each(|_| vehicle.step(0.01,&vec![]));
+        let checkpoint = vehicle.snapshot();
+        assert_eq!(checkpoint.contacts.len(),1);
+        let run = |vehicle: &mut AffectedBody<Vec<f64>>| -> Vec<_> {
+            (0..100).map(|_| { vehicle.step(0.01,&vec![]); (vehicle.acceleration(),vehicle.statevector()) }).collect()
+        };
+        let first = run(&mut vehicle);
+        vehicle.restore(&checkpoint).unwrap();
+        assert_eq!(run(&mut vehicle),first);
+
+        // A vehicle without the contact is left unchanged
+        let mut dry = AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::new(body),vec![]);
+        assert_eq!(dry.restore(&checkpoint),Err(Error::SnapshotMismatch));
+        assert_eq!(dry.statevector(),body.statevector());
+    }
+}
@@ -0,0 +1,281 @@
+//! Stability and control derivative extraction
+//!
+//! [StabilityDerivatives::extract] evaluates the enabled effectors of an [AffectedBody] at perturbed
+//! airstates, rates and inputs, and returns the non-dimensional derivatives of the force and moment
+//! coefficients, so an assembled model can be compared against published data or reduced to a
+//! [LinearAero](crate::effector_models::LinearAero) surrogate with [StabilityDerivatives::to_linear].
+//!
+//! Lift and drag are in stability axes, and side force and the moments are in body axes:
+//!
+//! `C_L = (X sin α - Z cos α) / q S`, `C_D = -(X cos α + Z sin α) / q S`, `C_Y = Y / q S`
+//!
+//! `C_l = L / q S b`, `C_m = M / q S c`, `C_n = N / q S b`
+//!
+//! Rate derivatives are with respect to the non-dimensional rates `p b/2V`, `q c/2V` and `r b/2V`.
+//! Every enabled effector contributes, so propulsion effectors should be disabled to extract the
+//! aerodynamic derivatives alone.
+
+use std::ops::IndexMut;
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel};
+use crate::types::Vector3;
+use crate::types::{Float,DefaultFloatRepr,constant};
+use crate::effector_models::LinearDerivatives;
+
+/// Reference geometry used to non-dimensionalise forces, moments and rates
+#[derive(Copy,Clone,Debug)]
+pub struct ReferenceGeometry<T: Float = DefaultFloatRepr> {
+    /// Reference area (m<sup>2</sup>)
+    pub s: T,
+    /// Reference span (m)
+    pub b: T,
+    /// Reference mean chord (m)
+    pub c: T,
+}
+
+/// Force and moment coefficients, or their derivatives with respect to a single variable
+#[derive(Copy,Clone,Debug)]
+pub struct Coefficients<T: Float = DefaultFloatRepr> {
+    /// Lift coefficient
+    pub lift: T,
+    /// Drag coefficient
+    pub drag: T,
+    /// Side force coefficient
+    pub side: T,
+    /// Rolling moment coefficient
+    pub roll: T,
+    /// Pitching moment coefficient
+    pub pitch: T,
+    /// Yawing moment coefficient
+    pub yaw: T,
+}
+
+impl<T: Float> Coefficients<T> {
+    /// Return the coefficients of the body-frame `force` and `torque`
+    fn new(force: Vector3<T>, torque: Vector3<T>, airstate: &AirState<T>, geometry: &ReferenceGeometry<T>) -> Self {
+        let q_s = airstate.q * geometry.s;
+        let (sin_alpha, cos_alpha) = <T as num_traits::Float>::sin_cos(airstate.alpha);
+        Coefficients {
+            lift: (force.x * sin_alpha - force.z * cos_alpha) / q_s,
+            drag: -(force.x * cos_alpha + force.z * sin_alpha) / q_s,
+            side: force.y / q_s,
+            roll: torque.x / (q_s * geometry.b),
+            pitch: torque.y / (q_s * geometry.c),
+            yaw: torque.z / (q_s * geometry.b),
+        }
+    }
+
+    /// Return the central difference `(upper - lower) / 2h`
+    fn difference(upper: &Self, lower: &Self, h: T) -> Self {
+        let d = |upper: T, lower: T| (upper - lower) / (h + h);
+        Coefficients {
+            lift: d(upper.lift,lower.lift),
+            drag: d(upper.drag,lower.drag),
+            side: d(upper.side,lower.side),
+            roll: d(upper.roll,lower.roll),
+            pitch: d(upper.pitch,lower.pitch),
+            yaw: d(upper.yaw,lower.yaw),
+        }
+    }
+}
+
+/// Derivatives of the coefficients with respect to a single inputstate slot
+#[derive(Copy,Clone,Debug)]
+pub struct ControlDerivatives<T: Float = DefaultFloatRepr> {
+    /// Inputstate slot
+    pub slot: usize,
+    /// Value of the slot at the operating point
+    pub value: T,
+    /// Derivatives with respect to the slot
+    pub derivatives: Coefficients<T>,
+}
+
+/// Stability and control derivatives at an operating point
+#[derive(Clone,Debug)]
+pub struct StabilityDerivatives<T: Float = DefaultFloatRepr> {
+    /// Angle of attack at the operating point (rad)
+    pub alpha_0: T,
+    /// Angle of sideslip at the operating point (rad)
+    pub beta_0: T,
+    /// Coefficients at the operating point
+    pub coefficients: Coefficients<T>,
+    /// Derivatives with respect to angle of attack (rad<sup>-1</sup>)
+    pub alpha: Coefficients<T>,
+    /// Derivatives with respect to angle of sideslip (rad<sup>-1</sup>)
+    pub beta: Coefficients<T>,
+    /// Derivatives with respect to non-dimensional roll rate
+    pub p: Coefficients<T>,
+    /// Derivatives with respect to non-dimensional pitch rate
+    pub q: Coefficients<T>,
+    /// Derivatives with respect to non-dimensional yaw rate
+    pub r: Coefficients<T>,
+    /// Derivatives with respect to each control slot
+    pub controls: Vec<ControlDerivatives<T>>,
+}
+
+impl<T: Float> StabilityDerivatives<T> {
+    /// Extract the derivatives of the enabled effectors of `vehicle` at `airstate` with zero rates
+    ///
+    /// # Arguments
+    ///
+    /// * `vehicle` - The vehicle whose effectors are evaluated; its state is only used for the attitude of world-frame effects
+    /// * `geometry` - Reference geometry
+    /// * `airstate` - Airstate at the operating point
+    /// * `inputs` - Inputstate at the operating point
+    /// * `control_slots` - Inputstate slots to find control derivatives for
+    pub fn extract<I: Clone + IndexMut<usize,Output=T>, W: WindModel<T>, D: DensityModel<T>>(vehicle: &AffectedBody<I,T,W,D>, geometry: &ReferenceGeometry<T>, airstate: &AirState<T>, inputs: &I, control_slots: &[usize]) -> Self {
+        let evaluate = |airstate: &AirState<T>, rates: Vector3<T>, inputs: &I| {
+            let (force, torque) = vehicle.total_effect(*airstate,rates,inputs);
+            Coefficients::new(force,torque,airstate,geometry)
+        };
+        let h = <T as num_traits::Float>::cbrt(T::epsilon());
+        let zero = Vector3::zeros();
+
+        let angle = |perturb: &dyn Fn(&mut AirState<T>, T)| {
+            let (mut upper, mut lower) = (*airstate,*airstate);
+            perturb(&mut upper,h);
+            perturb(&mut lower,-h);
+            Coefficients::difference(&evaluate(&upper,zero,inputs),&evaluate(&lower,zero,inputs),h)
+        };
+
+        // Rates for a unit non-dimensional rate about each axis
+        let two_v = airstate.airspeed + airstate.airspeed;
+        let rate_scale = Vector3::new(two_v / geometry.b,two_v / geometry.c,two_v / geometry.b);
+        let rate = |axis: usize| {
+            let mut rates = Vector3::zeros();
+            rates[axis] = h * rate_scale[axis];
+            Coefficients::difference(&evaluate(airstate,rates,inputs),&evaluate(airstate,-rates,inputs),h)
+        };
+
+        let controls = control_slots.iter().map(|&slot| {
+            let value = inputs[slot];
+            let step = h * <T as num_traits::Float>::max(T::one(),<T as num_traits::Float>::abs(value));
+            let (mut upper, mut lower) = (inputs.clone(),inputs.clone());
+            upper[slot] = value + step;
+            lower[slot] = value - step;
+            let derivatives = Coefficients::difference(&evaluate(airstate,zero,&upper),&evaluate(airstate,zero,&lower),step);
+            ControlDerivatives { slot, value, derivatives }
+        }).collect();
+
+        StabilityDerivatives {
+            alpha_0: airstate.alpha,
+            beta_0: airstate.beta,
+            coefficients: evaluate(airstate,zero,inputs),
+            alpha: angle(&|airstate, h| airstate.alpha += h),
+            beta: angle(&|airstate, h| airstate.beta += h),
+            p: rate(0),
+            q: rate(1),
+            r: rate(2),
+            controls,
+        }
+    }
+
+    /// Return the derivatives with respect to inputstate `slot`, if extracted
+    pub fn control(&self, slot: usize) -> Option<&Coefficients<T>> {
+        self.controls.iter().find(|control| control.slot == slot).map(|control| &control.derivatives)
+    }
+
+    /// Return [LinearDerivatives] matching these derivatives at the operating point
+    ///
+    /// The zero-alpha lift and pitching moment are extrapolated from the operating point, and the induced
+    /// drag factor is found from the slope of the drag polar. Control slots which were not extracted
+    /// contribute nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `geometry` - Reference geometry
+    /// * `aileron` - Inputstate slot of the aileron
+    /// * `elevator` - Inputstate slot of the elevator
+    /// * `rudder` - Inputstate slot of the rudder
+    pub fn to_linear(&self, geometry: &ReferenceGeometry<T>, aileron: usize, elevator: usize, rudder: usize) -> LinearDerivatives<T> {
+        let zero = Coefficients { lift: T::zero(), drag: T::zero(), side: T::zero(), roll: T::zero(), pitch: T::zero(), yaw: T::zero() };
+        let control = |slot: usize| self.control(slot).copied().unwrap_or(zero);
+        let value = |slot: usize| self.controls.iter().find(|control| control.slot == slot).map(|control| control.value).unwrap_or(T::zero());
+        let (aileron_d, elevator_d, rudder_d) = (control(aileron),control(elevator),control(rudder));
+
+        let c_lift = self.coefficients.lift;
+        let two = constant::<T>(2.0);
+        let k = if c_lift * self.alpha.lift != T::zero() { self.alpha.drag / (two * c_lift * self.alpha.lift) } else { T::zero() };
+
+        LinearDerivatives {
+            s: geometry.s,
+            b: geometry.b,
+            c: geometry.c,
+            c_lift_0: c_lift - self.alpha.lift * self.alpha_0 - elevator_d.lift * value(elevator),
+            c_lift_alpha: self.alpha.lift,
+            c_lift_q: self.q.lift,
+            c_lift_elevator: elevator_d.lift,
+            c_drag_0: self.coefficients.drag - k * c_lift * c_lift,
+            k,
+            c_side_beta: self.beta.side,
+            c_side_p: self.p.side,
+            c_side_r: self.r.side,
+            c_side_rudder: rudder_d.side,
+            c_roll_beta: self.beta.roll,
+            c_roll_p: self.p.roll,
+            c_roll_r: self.r.roll,
+            c_roll_aileron: aileron_d.roll,
+            c_roll_rudder: rudder_d.roll,
+            c_pitch_0: self.coefficients.pitch - self.alpha.pitch * self.alpha_0 - elevator_d.pitch * value(elevator),
+            c_pitch_alpha: self.alpha.pitch,
+            c_pitch_q: self.q.pitch,
+            c_pitch_elevator: elevator_d.pitch,
+            c_yaw_beta: self.beta.yaw,
+            c_yaw_p: self.p.yaw,
+            c_yaw_r: self.r.yaw,
+            c_yaw_aileron: aileron_d.yaw,
+            c_yaw_rudder: rudder_d.yaw,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    const GEOMETRY: ReferenceGeometry<f64> = ReferenceGeometry { s: 0.5, b: 2.0, c: 0.25 };
+
+    #[test]
+    fn test_recover_linear_derivatives() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        vehicle.disable("1");
+        let inputs = vec![0.0,-0.05,0.0,0.5];
+        let derivatives = StabilityDerivatives::extract(&vehicle,&GEOMETRY,&airstate(0.08,20.0),&inputs,&[0,1,2]);
+        let recovered = derivatives.to_linear(&GEOMETRY,0,1,2);
+        let expected = test_aircraft::aero();
+        let expected = expected.derivatives();
+
+        for (value, expected) in [
+            (recovered.c_lift_0,expected.c_lift_0),
+            (recovered.c_lift_alpha,expected.c_lift_alpha),
+            (recovered.c_lift_elevator,expected.c_lift_elevator),
+            (recovered.c_drag_0,expected.c_drag_0),
+            (recovered.k,expected.k),
+            (recovered.c_side_beta,expected.c_side_beta),
+            (recovered.c_roll_p,expected.c_roll_p),
+            (recovered.c_roll_aileron,expected.c_roll_aileron),
+            (recovered.c_pitch_0,expected.c_pitch_0),
+            (recovered.c_pitch_alpha,expected.c_pitch_alpha),
+            (recovered.c_pitch_q,expected.c_pitch_q),
+            (recovered.c_pitch_elevator,expected.c_pitch_elevator),
+            (recovered.c_yaw_beta,expected.c_yaw_beta),
+            (recovered.c_yaw_r,expected.c_yaw_r),
+            (recovered.c_yaw_rudder,expected.c_yaw_rudder),
+        ] {
+            assert_relative_eq!(value,expected,epsilon=1e-6);
+        }
+    }
+
+    #[test]
+    fn test_thrust_contribution() {
+        // With the throttle enabled, thrust reduces the drag coefficient by T / q S
+        let vehicle = test_aircraft::vehicle(100.0);
+        let inputs = vec![0.0,0.0,0.0,0.5];
+        let derivatives = StabilityDerivatives::extract(&vehicle,&GEOMETRY,&airstate(0.0,20.0),&inputs,&[3]);
+        assert_relative_eq!(derivatives.control(3).unwrap().drag,-20.0 / (245.0 * 0.5),max_relative=1e-6);
+        assert!(derivatives.control(0).is_none());
+    }
+}
@@ -0,0 +1,349 @@
+use std::ops::{Index,IndexMut};
+
+use crate::{AeroEffect,AirState,VehicleGeometry,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Linear dynamics of an [Actuator]
+#[derive(Copy,Clone)]
+pub enum ActuatorDynamics<T: Float = DefaultFloatRepr> {
+    /// Position follows the command instantly (limits still apply)
+    Ideal,
+    /// First-order lag with time constant (s)
+    FirstOrder {
+        /// Time constant (s)
+        time_constant: T,
+    },
+    /// Second-order response
+    SecondOrder {
+        /// Natural frequency (rad·s<sup>-1</sup>)
+        natural_frequency: T,
+        /// Damping ratio
+        damping: T,
+    },
+}
+
+/// Represent an actuator with lag, rate limit, position limits and freeplay
+///
+/// The actuator state is advanced by [Actuator::update], and the delivered position,
+/// including freeplay, is returned by [Actuator::output].
+#[derive(Copy,Clone)]
+pub struct Actuator<T: Float = DefaultFloatRepr> {
+    /// Linear dynamics
+    dynamics: ActuatorDynamics<T>,
+    /// Maximum absolute rate
+    rate_limit: Option<T>,
+    /// Position limits `(min,max)`
+    limits: Option<(T,T)>,
+    /// Total freeplay (backlash) width
+    freeplay: T,
+    /// Actuator position
+    position: T,
+    /// Actuator rate
+    rate: T,
+    /// Delivered position after freeplay
+    output: T,
+}
+
+impl<T: Float> Actuator<T> {
+    /// Largest fraction of the natural period integrated in a single second-order sub-step
+    const MAX_SUBSTEP_FRACTION: f64 = 0.05;
+
+    /// Create a new [Actuator] at zero position with no rate or position limits and no freeplay
+    pub fn new(dynamics: ActuatorDynamics<T>) -> Self {
+        Actuator {
+            dynamics,
+            rate_limit: None,
+            limits: None,
+            freeplay: T::zero(),
+            position: T::zero(),
+            rate: T::zero(),
+            output: T::zero(),
+        }
+    }
+
+    /// Limit the absolute actuator rate to `rate_limit` (units·s<sup>-1</sup>)
+    pub fn with_rate_limit(mut self, rate_limit: T) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Limit the actuator position to `[min,max]`
+    pub fn with_limits(mut self, min: T, max: T) -> Self {
+        if min > max {
+            panic!("Actuator minimum limit must not exceed maximum limit")
+        }
+        self.limits = Some((min,max));
+        self
+    }
+
+    /// Add freeplay (backlash) of total width `freeplay` between the actuator and the output
+    pub fn with_freeplay(mut self, freeplay: T) -> Self {
+        self.freeplay = freeplay;
+        self
+    }
+
+    /// Reset the actuator to rest at `position`
+    pub fn reset(&mut self, position: T) {
+        self.position = self.limit(position);
+        self.rate = T::zero();
+        self.output = self.position;
+    }
+
+    /// Return the actuator position, before freeplay
+    pub fn position(&self) -> T {
+        self.position
+    }
+
+    /// Return the actuator rate
+    pub fn rate(&self) -> T {
+        self.rate
+    }
+
+    /// Return the delivered output position, after freeplay
+    pub fn output(&self) -> T {
+        self.output
+    }
+
+    /// Export the position, rate and output of the actuator
+    pub fn snapshot(&self) -> EffectorState {
+        EffectorState::from_values(&[self.position,self.rate,self.output])
+    }
+
+    /// Restore a state exported by [Actuator::snapshot]
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::SnapshotMismatch] if `state` was not exported by an [Actuator].
+    pub fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(3,0,0)?;
+        self.position = state.value(0);
+        self.rate = state.value(1);
+        self.output = state.value(2);
+        Ok(())
+    }
+
+    fn limit(&self, position: T) -> T {
+        match self.limits {
+            Some((min,max)) => num_traits::clamp(position,min,max),
+            None => position,
+        }
+    }
+
+    fn limit_rate(&self, rate: T) -> T {
+        match self.rate_limit {
+            Some(rate_limit) => num_traits::clamp(rate,-rate_limit,rate_limit),
+            None => rate,
+        }
+    }
+
+    /// Advance the actuator by `delta_t` towards `command`
+    ///
+    /// A `delta_t` which is not positive leaves the actuator unchanged.
+    pub fn update(&mut self, command: T, delta_t: T) {
+        if delta_t <= T::zero() {
+            return;
+        }
+        let command = self.limit(command);
+        let previous = self.position;
+
+        match self.dynamics {
+            ActuatorDynamics::Ideal => {
+                let rate = self.limit_rate((command - self.position) / delta_t);
+                self.position += rate * delta_t;
+            },
+            ActuatorDynamics::FirstOrder { time_constant } => {
+                // Exact discretisation of the lag, then apply the rate limit over the step
+                let step = (command - self.position) * (T::one() - <T as num_traits::Float>::exp(-delta_t / time_constant));
+                self.position += self.limit_rate(step / delta_t) * delta_t;
+            },
+            ActuatorDynamics::SecondOrder { natural_frequency, damping } => {
+                let max_substep = constant::<T>(Self::MAX_SUBSTEP_FRACTION) * T::two_pi() / natural_frequency;
+                let substeps = <T as num_traits::Float>::ceil(delta_t / max_substep).to_usize().unwrap_or(1).max(1);
+                let h = delta_t / constant::<T>(substeps as f64);
+                for _ in 0..substeps {
+                    // Semi-implicit Euler
+                    let acceleration = natural_frequency * natural_frequency * (command - self.position)
+                        - constant::<T>(2.0) * damping * natural_frequency * self.rate;
+                    self.rate = self.limit_rate(self.rate + acceleration * h);
+                    let unlimited = self.position + self.rate * h;
+                    self.position = self.limit(unlimited);
+                    if self.position != unlimited {
+                        // Stopped against a position limit
+                        self.rate = T::zero();
+                    }
+                }
+            },
+        }
+
+        self.position = self.limit(self.position);
+        if !matches!(self.dynamics, ActuatorDynamics::SecondOrder {..}) {
+            self.rate = (self.position - previous) / delta_t;
+        }
+
+        // Backlash: the output only moves once the actuator takes up the freeplay
+        let half_freeplay = self.freeplay * constant::<T>(0.5);
+        if self.position - self.output > half_freeplay {
+            self.output = self.position - half_freeplay;
+        } else if self.output - self.position > half_freeplay {
+            self.output = self.position + half_freeplay;
+        }
+    }
+}
+
+/// [AeroEffect] wrapper which passes an inputstate channel through an [Actuator]
+///
+/// The wrapped effector sees the actuator output in place of the commanded value on `channel`.
+/// The actuator is advanced by [AeroEffect::step] when used in an [AffectedBody](crate::AffectedBody),
+/// or can be advanced manually using [Actuated::update].
+#[derive(Copy,Clone)]
+pub struct Actuated<E, T: Float = DefaultFloatRepr> {
+    /// Wrapped effector
+    effector: E,
+    /// Inputstate index driven by the actuator
+    channel: usize,
+    /// Actuator model
+    actuator: Actuator<T>,
+}
+
+impl<E, T: Float> Actuated<E,T> {
+    /// Wrap `effector` so that `channel` is driven through `actuator`
+    pub fn new(effector: E, channel: usize, actuator: Actuator<T>) -> Self {
+        Actuated {
+            effector,
+            channel,
+            actuator,
+        }
+    }
+
+    /// Return the actuator
+    pub fn actuator(&self) -> &Actuator<T> {
+        &self.actuator
+    }
+
+    /// Return the wrapped effector
+    pub fn effector(&self) -> &E {
+        &self.effector
+    }
+
+    /// Advance the actuator by `delta_t` using the command on `channel` of `inputstate`
+    pub fn update<I: Index<usize,Output=T>>(&mut self, inputstate: &I, delta_t: T) {
+        self.actuator.update(inputstate[self.channel],delta_t);
+    }
+}
+
+impl<I: Clone + IndexMut<usize,Output=T>, T: Float, E: AeroEffect<I,T>> AeroEffect<I,T> for Actuated<E,T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let mut actuated_input = inputstate.clone();
+        actuated_input[self.channel] = self.actuator.output();
+        self.effector.get_effect(airstate,rates,&actuated_input)
+    }
+    
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        // Step the wrapped effector with the input it saw during this timestep
+        let mut actuated_input = inputstate.clone();
+        actuated_input[self.channel] = self.actuator.output();
+        self.effector.step(airstate,rates,&actuated_input,delta_t);
+        self.update(inputstate,delta_t);
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.effector.set_geometry(geometry);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        EffectorState { parts: vec![self.actuator.snapshot(),self.effector.snapshot()], ..EffectorState::default() }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(0,0,2)?;
+        self.actuator.restore(&state.parts[0])?;
+        self.effector.restore(&state.parts[1])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_first_order() {
+        let mut actuator = Actuator::new(ActuatorDynamics::FirstOrder { time_constant: 0.1 });
+        let delta_t = 0.001;
+        for _ in 0..100 {
+            actuator.update(1.0,delta_t);
+        }
+        assert_relative_eq!(actuator.position(),1.0 - (-1.0f64).exp(),epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_rate_and_position_limits() {
+        let mut actuator = Actuator::new(ActuatorDynamics::Ideal)
+            .with_rate_limit(1.0)
+            .with_limits(-0.5,0.5);
+        actuator.update(1.0,0.1);
+        assert_relative_eq!(actuator.position(),0.1);
+        for _ in 0..10 {
+            actuator.update(1.0,0.1);
+        }
+        assert_relative_eq!(actuator.position(),0.5);
+    }
+
+    #[test]
+    fn test_zero_timestep() {
+        for dynamics in [ActuatorDynamics::Ideal,ActuatorDynamics::FirstOrder { time_constant: 0.1 }] {
+            let mut actuator: Actuator = Actuator::new(dynamics).with_rate_limit(1.0);
+            actuator.update(1.0,0.1);
+            actuator.update(1.0,0.0);
+            assert!(actuator.position().is_finite() && actuator.rate().is_finite());
+            // The actuator carries on from where it was
+            actuator.update(1.0,0.1);
+            assert_relative_eq!(actuator.position(),0.2);
+        }
+    }
+
+    #[test]
+    fn test_second_order_settles() {
+        let mut actuator = Actuator::new(ActuatorDynamics::SecondOrder { natural_frequency: 40.0, damping: 0.7 });
+        let mut peak: f64 = 0.0;
+        for _ in 0..1000 {
+            actuator.update(1.0,0.001);
+            peak = peak.max(actuator.position());
+        }
+        assert_relative_eq!(actuator.position(),1.0,epsilon=1e-3);
+        // Underdamped response overshoots by about 4.6% for a damping ratio of 0.7
+        assert!(peak > 1.03 && peak < 1.06);
+    }
+
+    #[test]
+    fn test_freeplay() {
+        let mut actuator = Actuator::new(ActuatorDynamics::Ideal).with_freeplay(0.2);
+        actuator.update(0.05,0.1);
+        assert_relative_eq!(actuator.output(),0.0);
+        actuator.update(0.3,0.1);
+        assert_relative_eq!(actuator.output(),0.2);
+        // Reversing direction takes up the freeplay before the output moves
+        actuator.update(0.2,0.1);
+        assert_relative_eq!(actuator.output(),0.2);
+    }
+
+    #[test]
+    fn test_wrapper() {
+        struct Passthrough;
+        impl AeroEffect<[f64;2]> for Passthrough {
+            fn get_effect(&self, _airstate: AirState, _rates: Vector3, inputstate: &[f64;2]) -> (Force,Torque) {
+                (Force::body(inputstate[0],inputstate[1],0.0),Torque::body(0.0,0.0,0.0))
+            }
+        }
+        let mut actuated = Actuated::new(Passthrough,1,Actuator::new(ActuatorDynamics::Ideal).with_rate_limit(1.0));
+        let airstate = airstate(0.0,0.0);
+        let input = [2.0,1.0];
+        actuated.update(&input,0.5);
+        let (force,_) = actuated.get_effect(airstate,Vector3::zeros(),&input);
+        assert_relative_eq!(force.force.x,2.0);
+        assert_relative_eq!(force.force.y,0.5);
+    }
+}
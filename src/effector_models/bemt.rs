@@ -0,0 +1,284 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState};
+use crate::lookup::Table;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::{PropellerInput,Rotation};
+
+/// Represent the lift and drag polars of an airfoil section
+#[derive(Clone)]
+pub struct Airfoil<T: Float = DefaultFloatRepr> {
+    /// Lift coefficient against angle of attack (rad)
+    lift: Table<T>,
+    /// Drag coefficient against angle of attack (rad)
+    drag: Table<T>,
+}
+
+impl<T: Float> Airfoil<T> {
+    /// Create a new [Airfoil] from 1D lift and drag polars against angle of attack (rad)
+    pub fn new(lift: Table<T>, drag: Table<T>) -> Self {
+        if lift.dimensions() != 1 || drag.dimensions() != 1 {
+            panic!("Airfoil polars must be 1D in angle of attack")
+        }
+        Airfoil { lift, drag }
+    }
+
+    /// Return `(C_l, C_d)` at `alpha` (rad)
+    pub fn coefficients(&self, alpha: T) -> (T,T) {
+        (self.lift.lookup(&[alpha]),self.drag.lookup(&[alpha]))
+    }
+}
+
+/// Represent a single radial station of a rotor blade
+#[derive(Clone)]
+pub struct BladeElement<T: Float = DefaultFloatRepr> {
+    /// Radial position of the element centre (m)
+    pub radius: T,
+    /// Radial width of the element (m)
+    pub width: T,
+    /// Chord (m)
+    pub chord: T,
+    /// Geometric pitch angle relative to the rotor plane (rad)
+    pub twist: T,
+    /// Section aerodynamics
+    pub airfoil: Airfoil<T>,
+}
+
+/// Result of a blade-element momentum solution
+#[derive(Clone)]
+pub struct RotorSolution<T: Float = DefaultFloatRepr> {
+    /// Total thrust (N)
+    pub thrust: T,
+    /// Magnitude of the total shaft torque (N·m)
+    pub torque: T,
+    /// Induced axial velocity at each blade element (m·s<sup>-1</sup>)
+    pub induced_velocity: Vec<T>,
+    /// Area-weighted mean induced velocity over the disk (m·s<sup>-1</sup>)
+    pub mean_induced_velocity: T,
+}
+
+/// Built-in [AeroEffect] for a rotor or propeller using blade-element momentum theory (BEMT)
+///
+/// For each blade element the induced axial velocity is solved so that the element thrust from
+/// blade-element theory matches the thrust from momentum theory over the corresponding annulus,
+/// including the Prandtl tip-loss factor. Swirl in the wake is neglected and the solution is for the
+/// propulsive working state; elements that would produce negative thrust are given zero induced velocity.
+///
+/// Thrust acts along body +X at the rotor position; the reaction torque opposes the rotation.
+/// Forces and torques are returned in the body frame.
+#[derive(Clone)]
+pub struct BladeElementRotor<T: Float = DefaultFloatRepr> {
+    /// Number of blades
+    blades: usize,
+    /// Tip radius (m)
+    radius: T,
+    /// Blade elements
+    elements: Vec<BladeElement<T>>,
+    /// Input channel
+    input: PropellerInput<T>,
+    /// Rotor hub position relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Direction of rotation
+    rotation: Rotation,
+}
+
+impl<T: Float> BladeElementRotor<T> {
+    /// Number of bisection iterations used to solve each element's inflow
+    const INFLOW_ITERATIONS: usize = 60;
+
+    /// Create a new clockwise [BladeElementRotor] at the centre of mass
+    ///
+    /// # Arguments
+    ///
+    /// * `blades` - Number of blades
+    /// * `radius` - Tip radius (m)
+    /// * `elements` - Blade elements from root to tip
+    /// * `input` - The channel driving the rotor
+    pub fn new(blades: usize, radius: T, elements: Vec<BladeElement<T>>, input: PropellerInput<T>) -> Self {
+        if elements.iter().any(|element| element.radius <= T::zero() || element.radius >= radius) {
+            panic!("Blade elements must lie between the hub and the tip")
+        }
+        BladeElementRotor {
+            blades,
+            radius,
+            elements,
+            input,
+            position: Vector3::zeros(),
+            rotation: Rotation::Clockwise,
+        }
+    }
+
+    /// Create a blade of `count` equal-width elements between `root` and the tip
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Root cut-out radius (m)
+    /// * `radius` - Tip radius (m)
+    /// * `count` - Number of elements
+    /// * `chord` - Chord as a function of radius (m)
+    /// * `twist` - Pitch angle as a function of radius (rad)
+    /// * `airfoil` - Section aerodynamics, used for every element
+    pub fn uniform_elements<C: Fn(T) -> T, P: Fn(T) -> T>(root: T, radius: T, count: usize, chord: C, twist: P, airfoil: Airfoil<T>) -> Vec<BladeElement<T>> {
+        let width = (radius - root) / constant::<T>(count as f64);
+        (0..count).map(|idx| {
+            let r = root + width * (constant::<T>(idx as f64) + constant::<T>(0.5));
+            BladeElement { radius: r, width, chord: chord(r), twist: twist(r), airfoil: airfoil.clone() }
+        }).collect()
+    }
+
+    /// Set the rotor hub position relative to the centre of mass, body frame (m)
+    pub fn with_position(mut self, position: Vector3<T>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Set the direction of rotation
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Return the shaft speed (rev·min<sup>-1</sup>) commanded by `inputstate`
+    pub fn rpm<I: Index<usize,Output=T>>(&self, inputstate: &I) -> T {
+        self.input.rpm(inputstate)
+    }
+
+    /// Return the element `(thrust, torque)` for induced velocity `v_i`
+    fn element_loads(&self, element: &BladeElement<T>, axial_velocity: T, v_i: T, omega: T, density: T) -> (T,T) {
+        let u_p = axial_velocity + v_i;
+        let u_t = omega * element.radius;
+        let phi = <T as num_traits::Float>::atan2(u_p,u_t);
+        let (c_l, c_d) = element.airfoil.coefficients(element.twist - phi);
+        let (sin_phi, cos_phi) = <T as num_traits::Float>::sin_cos(phi);
+        let dynamic = constant::<T>(0.5) * density * (u_p * u_p + u_t * u_t) * element.chord * element.width * constant::<T>(self.blades as f64);
+        (
+            dynamic * (c_l * cos_phi - c_d * sin_phi),
+            dynamic * (c_l * sin_phi + c_d * cos_phi) * element.radius,
+        )
+    }
+
+    /// Prandtl tip-loss factor at `element` for inflow angle `phi`
+    fn tip_loss(&self, element: &BladeElement<T>, phi: T) -> T {
+        let sin_phi = <T as num_traits::Float>::sin(phi);
+        if sin_phi <= T::zero() {
+            return T::one();
+        }
+        let f = constant::<T>(self.blades as f64) * (self.radius - element.radius) / (constant::<T>(2.0) * element.radius * sin_phi);
+        constant::<T>(2.0) / T::pi() * <T as num_traits::Float>::acos(<T as num_traits::Float>::exp(-f))
+    }
+
+    /// Solve the rotor inflow for `axial_velocity` (m·s<sup>-1</sup>), `density` (kg·m<sup>-3</sup>) and shaft speed `rpm`
+    pub fn solve(&self, axial_velocity: T, density: T, rpm: T) -> RotorSolution<T> {
+        let omega = rpm * T::two_pi() / constant::<T>(60.0);
+        let mut solution = RotorSolution {
+            thrust: T::zero(),
+            torque: T::zero(),
+            induced_velocity: vec![T::zero(); self.elements.len()],
+            mean_induced_velocity: T::zero(),
+        };
+        if omega <= T::zero() {
+            return solution;
+        }
+
+        let mut area = T::zero();
+        for (element, v_i_out) in self.elements.iter().zip(solution.induced_velocity.iter_mut()) {
+            let annulus = T::two_pi() * element.radius * element.width;
+            // Residual between blade-element and momentum thrust, decreasing in v_i
+            let residual = |v_i: T| {
+                let (blade_thrust, _) = self.element_loads(element,axial_velocity,v_i,omega,density);
+                let phi = <T as num_traits::Float>::atan2(axial_velocity + v_i,omega * element.radius);
+                let momentum_thrust = constant::<T>(2.0) * density * annulus * self.tip_loss(element,phi) * (axial_velocity + v_i) * v_i;
+                blade_thrust - momentum_thrust
+            };
+
+            let mut lower = T::zero();
+            let mut upper = omega * element.radius;
+            let v_i = if residual(lower) <= T::zero() {
+                T::zero()
+            } else {
+                for _ in 0..Self::INFLOW_ITERATIONS {
+                    let mid = (lower + upper) * constant::<T>(0.5);
+                    if residual(mid) > T::zero() { lower = mid } else { upper = mid }
+                }
+                (lower + upper) * constant::<T>(0.5)
+            };
+
+            let (thrust, torque) = self.element_loads(element,axial_velocity,v_i,omega,density);
+            solution.thrust += thrust;
+            solution.torque += torque;
+            solution.mean_induced_velocity += v_i * annulus;
+            area += annulus;
+            *v_i_out = v_i;
+        }
+        if area > T::zero() {
+            solution.mean_induced_velocity /= area;
+        }
+        solution
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for BladeElementRotor<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let axial_airspeed = super::body_airspeed(&airstate)[0];
+        let solution = self.solve(axial_airspeed,airstate.density,self.rpm(inputstate));
+
+        let force = Vector3::new(solution.thrust,T::zero(),T::zero());
+        let reaction = Vector3::new(-self.rotation.sign::<T>() * solution.torque,T::zero(),T::zero());
+
+        (Force::body_vec(force),Torque::body_vec(self.position.cross(&force) + reaction))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    fn rotor() -> BladeElementRotor {
+        // Thin airfoil with linear lift and constant profile drag
+        let airfoil = Airfoil::new(
+            Table::new_1d(vec![-0.3,0.3],vec![-0.3*5.7,0.3*5.7]),
+            Table::new_1d(vec![0.0],vec![0.01]));
+        let radius = 0.5;
+        let elements = BladeElementRotor::uniform_elements(0.05,radius,20,|_| 0.04,|r: f64| 0.05/r,airfoil);
+        BladeElementRotor::new(2,radius,elements,PropellerInput::Rpm(0))
+    }
+
+    #[test]
+    fn test_hover_momentum() {
+        let rotor = rotor();
+        let density = 1.225;
+        let solution = rotor.solve(0.0,density,3000.0);
+        assert!(solution.thrust > 0.0);
+        assert!(solution.torque > 0.0);
+
+        // Ideal twist gives near-uniform inflow close to the momentum theory value
+        let disk_area = std::f64::consts::PI * 0.5f64.powi(2);
+        let v_momentum = (solution.thrust / (2.0 * density * disk_area)).sqrt();
+        assert_relative_eq!(solution.mean_induced_velocity,v_momentum,max_relative=0.25);
+    }
+
+    #[test]
+    fn test_climb_reduces_thrust() {
+        let rotor = rotor();
+        let hover = rotor.solve(0.0,1.225,3000.0);
+        let climb = rotor.solve(5.0,1.225,3000.0);
+        assert!(climb.thrust < hover.thrust);
+    }
+
+    #[test]
+    fn test_effect() {
+        let rotor = rotor();
+        let airstate = airstate(0.0,0.0);
+        let (force,torque) = rotor.get_effect(airstate,Vector3::zeros(),&[3000.0]);
+        let solution = rotor.solve(0.0,1.225,3000.0);
+        assert_relative_eq!(force.force.x,solution.thrust);
+        assert_relative_eq!(torque.torque.x,-solution.torque);
+
+        let (force,_) = rotor.get_effect(airstate,Vector3::zeros(),&[0.0]);
+        assert_relative_eq!(force.force.x,0.0);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::types::{Float,constant};
+
+/// Subsonic compressibility correction for incompressible aerodynamic coefficients
+///
+/// Corrections are singular at Mach 1, so the Mach number is limited to
+/// [Compressibility::MACH_LIMIT] before the correction is applied.
+#[derive(Copy,Clone,Default)]
+pub enum Compressibility {
+    /// No correction
+    #[default]
+    None,
+    /// Prandtl–Glauert rule, `C = C0 / √(1 - M²)`
+    PrandtlGlauert,
+    /// Kármán–Tsien rule, `C = C0 / (√(1 - M²) + M² C0 / (2 (1 + √(1 - M²))))`
+    KarmanTsien,
+}
+
+impl Compressibility {
+    /// Maximum Mach number used when applying a correction
+    pub const MACH_LIMIT: f64 = 0.95;
+
+    /// Correct the incompressible `coefficient` for `mach`
+    pub fn correct<T: Float>(&self, coefficient: T, mach: T) -> T {
+        let mach = num_traits::clamp(mach,T::zero(),constant::<T>(Self::MACH_LIMIT));
+        let beta = <T as num_traits::Float>::sqrt(T::one() - mach * mach);
+        match self {
+            Compressibility::None => coefficient,
+            Compressibility::PrandtlGlauert => coefficient / beta,
+            Compressibility::KarmanTsien => {
+                coefficient / (beta + mach * mach * coefficient / (constant::<T>(2.0) * (T::one() + beta)))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_prandtl_glauert() {
+        assert_relative_eq!(Compressibility::PrandtlGlauert.correct(0.5,0.0),0.5);
+        assert_relative_eq!(Compressibility::PrandtlGlauert.correct(0.5,0.6),0.5/0.8);
+        assert_relative_eq!(Compressibility::None.correct(0.5,0.6),0.5);
+    }
+
+    #[test]
+    fn test_karman_tsien() {
+        // Reduces to Prandtl-Glauert for small coefficients
+        let small = 1e-6;
+        assert_relative_eq!(
+            Compressibility::KarmanTsien.correct(small,0.6),
+            Compressibility::PrandtlGlauert.correct(small,0.6),
+            max_relative=1e-5);
+
+        let mach: f64 = 0.7;
+        let beta = (1.0 - mach * mach).sqrt();
+        let expected = -0.4 / (beta + mach * mach * -0.4 / (2.0 * (1.0 + beta)));
+        assert_relative_eq!(Compressibility::KarmanTsien.correct(-0.4,mach),expected);
+    }
+
+    #[test]
+    fn test_mach_limit() {
+        let limited: f64 = Compressibility::PrandtlGlauert.correct(1.0,1.2);
+        assert!(limited.is_finite());
+        assert_relative_eq!(limited,Compressibility::PrandtlGlauert.correct(1.0,Compressibility::MACH_LIMIT));
+    }
+}
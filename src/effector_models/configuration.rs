@@ -0,0 +1,350 @@
+use std::cell::Cell;
+use std::ops::Index;
+use std::rc::Rc;
+
+use crate::{AeroEffect,AirState,VehicleGeometry,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque,Frame};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::{Actuator,ActuatorDynamics};
+
+/// Flap, gear and sweep positions of a [Configuration]
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct ConfigurationState<T: Float = DefaultFloatRepr> {
+    /// Flap deflection (rad)
+    pub flap: T,
+    /// Gear position, from `0` retracted to `1` down and locked
+    pub gear: T,
+    /// Wing sweep angle (rad)
+    pub sweep: T,
+}
+
+/// Coefficient increments per unit of a configuration position
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct ConfigurationIncrement<T: Float = DefaultFloatRepr> {
+    /// Lift coefficient increment
+    pub c_lift: T,
+    /// Drag coefficient increment
+    pub c_drag: T,
+    /// Pitching moment coefficient increment
+    pub c_pitch: T,
+}
+
+impl<T: Float> ConfigurationIncrement<T> {
+    /// Create a new [ConfigurationIncrement]
+    pub fn new(c_lift: T, c_drag: T, c_pitch: T) -> Self {
+        ConfigurationIncrement { c_lift, c_drag, c_pitch }
+    }
+
+    /// Return the increment scaled by `position`
+    fn scaled(&self, position: T) -> Self {
+        ConfigurationIncrement::new(self.c_lift * position,self.c_drag * position,self.c_pitch * position)
+    }
+
+    /// Return the sum of two increments
+    fn add(&self, other: &Self) -> Self {
+        ConfigurationIncrement::new(self.c_lift + other.c_lift,self.c_drag + other.c_drag,self.c_pitch + other.c_pitch)
+    }
+}
+
+/// [AeroEffect] which moves the flaps, gear and wing sweep of a vehicle through their transitions
+///
+/// Each configuration item follows a command on an inputstate channel through an [Actuator], so that, for
+/// example, the flaps run at a limited rate and the gear takes several seconds to extend. Items without a
+/// channel are held at zero. The [Configuration] produces no force itself; the delivered positions are
+/// shared through each [ConfigurationMonitor], which [Configured] effectors query to add their coefficient
+/// increments. Add the [Configuration] to the [AffectedBody](crate::AffectedBody) before the effectors
+/// which query it, so that they see the positions of the current step.
+pub struct Configuration<T: Float = DefaultFloatRepr> {
+    /// Flap command channel and actuator
+    flap: Option<(usize,Actuator<T>)>,
+    /// Gear command channel and actuator
+    gear: Option<(usize,Actuator<T>)>,
+    /// Sweep command channel and actuator
+    sweep: Option<(usize,Actuator<T>)>,
+    /// Positions shared with each [ConfigurationMonitor]
+    state: Rc<Cell<ConfigurationState<T>>>,
+}
+
+impl<T: Float> Default for Configuration<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Configuration<T> {
+    /// Create a new [Configuration] with flaps, gear and sweep all held at zero
+    pub fn new() -> Self {
+        Configuration {
+            flap: None,
+            gear: None,
+            sweep: None,
+            state: Rc::new(Cell::new(ConfigurationState { flap: T::zero(), gear: T::zero(), sweep: T::zero() })),
+        }
+    }
+
+    /// Drive the flap deflection (rad) from `channel` through `actuator`
+    pub fn with_flap(mut self, channel: usize, actuator: Actuator<T>) -> Self {
+        self.flap = Some((channel,actuator));
+        self.publish();
+        self
+    }
+
+    /// Drive the gear position from `channel` through `actuator`
+    ///
+    /// The actuator is limited to `[0,1]`, and defaults to a 5 s full travel when `None`.
+    pub fn with_gear(mut self, channel: usize, actuator: Option<Actuator<T>>) -> Self {
+        let actuator = actuator.unwrap_or_else(|| Actuator::new(ActuatorDynamics::Ideal).with_rate_limit(constant::<T>(0.2)))
+            .with_limits(T::zero(),T::one());
+        self.gear = Some((channel,actuator));
+        self.publish();
+        self
+    }
+
+    /// Drive the wing sweep angle (rad) from `channel` through `actuator`
+    pub fn with_sweep(mut self, channel: usize, actuator: Actuator<T>) -> Self {
+        self.sweep = Some((channel,actuator));
+        self.publish();
+        self
+    }
+
+    /// Return a [ConfigurationMonitor] sharing the positions
+    pub fn monitor(&self) -> ConfigurationMonitor<T> {
+        ConfigurationMonitor { state: self.state.clone() }
+    }
+
+    /// Return the delivered positions
+    pub fn state(&self) -> ConfigurationState<T> {
+        self.state.get()
+    }
+
+    /// Reset each actuator to rest at the positions of `state`
+    pub fn reset(&mut self, state: ConfigurationState<T>) {
+        for (item, position) in [(&mut self.flap,state.flap),(&mut self.gear,state.gear),(&mut self.sweep,state.sweep)].iter_mut() {
+            if let Some((_, actuator)) = item {
+                actuator.reset(*position);
+            }
+        }
+        self.publish();
+    }
+
+    /// Advance each actuator by `delta_t` towards its command in `inputstate`
+    pub fn update<I: Index<usize,Output=T>>(&mut self, inputstate: &I, delta_t: T) {
+        for (channel, actuator) in [self.flap.as_mut(),self.gear.as_mut(),self.sweep.as_mut()].iter_mut().flatten() {
+            actuator.update(inputstate[*channel],delta_t);
+        }
+        self.publish();
+    }
+
+    /// Share the delivered positions
+    fn publish(&self) {
+        let output = |item: &Option<(usize,Actuator<T>)>| item.as_ref().map_or(T::zero(),|(_, actuator)| actuator.output());
+        self.state.set(ConfigurationState { flap: output(&self.flap), gear: output(&self.gear), sweep: output(&self.sweep) });
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for Configuration<T> {
+    fn get_effect(&self, _airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        (Force::body_vec(Vector3::zeros()),Torque::body_vec(Vector3::zeros()))
+    }
+
+    fn step(&mut self, _airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.update(inputstate,delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        let parts = [&self.flap,&self.gear,&self.sweep].iter().filter_map(|item| item.as_ref()).map(|(_, actuator)| actuator.snapshot()).collect();
+        EffectorState { parts, ..EffectorState::default() }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(0,0,[&self.flap,&self.gear,&self.sweep].iter().filter(|item| item.is_some()).count())?;
+        for ((_, actuator), part) in [self.flap.as_mut(),self.gear.as_mut(),self.sweep.as_mut()].iter_mut().flatten().zip(&state.parts) {
+            actuator.restore(part)?;
+        }
+        self.publish();
+        Ok(())
+    }
+}
+
+/// Handle sharing the [ConfigurationState] of a [Configuration]
+#[derive(Clone)]
+pub struct ConfigurationMonitor<T: Float = DefaultFloatRepr> {
+    /// Shared positions
+    state: Rc<Cell<ConfigurationState<T>>>,
+}
+
+impl<T: Float> ConfigurationMonitor<T> {
+    /// Return the delivered positions
+    pub fn state(&self) -> ConfigurationState<T> {
+        self.state.get()
+    }
+}
+
+/// [AeroEffect] wrapper which adds configuration-dependent coefficient increments to an effector
+///
+/// The increments per radian of flap, per unit of gear and per radian of sweep are scaled by the
+/// positions from a [ConfigurationMonitor] and summed. The resulting lift and drag increments act in the
+/// stability axes and the pitching moment increment about body Y, all on the reference area and chord
+/// at the dynamic pressure of the airstate, and are added to the body-frame forces and torques of the
+/// wrapped effector. World-frame forces and torques are passed through without the increments.
+pub struct Configured<E, T: Float = DefaultFloatRepr> {
+    /// Wrapped effector
+    effector: E,
+    /// Configuration positions
+    configuration: ConfigurationMonitor<T>,
+    /// Reference area (m<sup>2</sup>)
+    area: T,
+    /// Reference chord (m)
+    chord: T,
+    /// Increments per radian of flap
+    flap: ConfigurationIncrement<T>,
+    /// Increments per unit of gear
+    gear: ConfigurationIncrement<T>,
+    /// Increments per radian of sweep
+    sweep: ConfigurationIncrement<T>,
+}
+
+impl<E, T: Float> Configured<E,T> {
+    /// Wrap `effector` with no increments
+    ///
+    /// # Arguments
+    ///
+    /// * `effector` - Clean-configuration aerodynamics
+    /// * `configuration` - Monitor of the [Configuration] of the vehicle
+    /// * `area` - Reference area of the increments (m<sup>2</sup>)
+    /// * `chord` - Reference chord of the increments (m)
+    pub fn new(effector: E, configuration: ConfigurationMonitor<T>, area: T, chord: T) -> Self {
+        let zero = ConfigurationIncrement::new(T::zero(),T::zero(),T::zero());
+        Configured { effector, configuration, area, chord, flap: zero, gear: zero, sweep: zero }
+    }
+
+    /// Set the increments per radian of flap deflection
+    pub fn with_flap_increment(mut self, increment: ConfigurationIncrement<T>) -> Self {
+        self.flap = increment;
+        self
+    }
+
+    /// Set the increments with the gear down and locked
+    pub fn with_gear_increment(mut self, increment: ConfigurationIncrement<T>) -> Self {
+        self.gear = increment;
+        self
+    }
+
+    /// Set the increments per radian of wing sweep
+    pub fn with_sweep_increment(mut self, increment: ConfigurationIncrement<T>) -> Self {
+        self.sweep = increment;
+        self
+    }
+
+    /// Return the wrapped effector
+    pub fn effector(&self) -> &E {
+        &self.effector
+    }
+
+    /// Return the total increments at the current configuration
+    pub fn increment(&self) -> ConfigurationIncrement<T> {
+        let state = self.configuration.state();
+        self.flap.scaled(state.flap).add(&self.gear.scaled(state.gear)).add(&self.sweep.scaled(state.sweep))
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float, E: AeroEffect<I,T>> AeroEffect<I,T> for Configured<E,T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (force, torque) = self.effector.get_effect(airstate,rates,inputstate);
+        let increment = self.increment();
+        let q_s = airstate.q * self.area;
+        let (x, z) = super::stability_to_body(increment.c_lift * q_s,increment.c_drag * q_s,airstate.alpha);
+        let added_force = Vector3::new(x,T::zero(),z);
+        let added_torque = Vector3::new(T::zero(),increment.c_pitch * q_s * self.chord,T::zero());
+
+        let force = match force.frame {
+            Frame::Body => Force::body_vec(force.force + added_force),
+            Frame::World => Force::world_vec(force.force),
+        };
+        let torque = match torque.frame {
+            Frame::Body => Torque::body_vec(torque.torque + added_torque),
+            Frame::World => Torque::world_vec(torque.torque),
+        };
+        (force,torque)
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.effector.step(airstate,rates,inputstate,delta_t);
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.area = geometry.area;
+        self.chord = geometry.chord;
+        self.effector.set_geometry(geometry);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        self.effector.snapshot()
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        self.effector.restore(state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    /// Clean wing with no aerodynamic force
+    struct Clean;
+    impl AeroEffect<[f64;2]> for Clean {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3, _inputstate: &[f64;2]) -> (Force,Torque) {
+            (Force::body_vec(Vector3::zeros()),Torque::body_vec(Vector3::zeros()))
+        }
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut configuration = Configuration::new()
+            .with_flap(0,Actuator::new(ActuatorDynamics::Ideal).with_rate_limit(0.1))
+            .with_gear(1,None);
+        let monitor = configuration.monitor();
+        for _ in 0..20 {
+            AeroEffect::<[f64;2]>::step(&mut configuration,AirState { q: 1000.0, ..airstate(0.0,40.0) },Vector3::zeros(),&[0.5,1.0],0.1);
+        }
+        // Flaps run at 0.1 rad/s towards 0.5 rad, the gear at 0.2 per second to down and locked
+        let state = monitor.state();
+        assert_relative_eq!(state.flap,0.2,max_relative=1e-9);
+        assert_relative_eq!(state.gear,0.4,max_relative=1e-9);
+        assert_eq!(state.sweep,0.0);
+        for _ in 0..40 {
+            configuration.update(&[0.5,2.0],0.1);
+        }
+        assert_eq!(configuration.state().gear,1.0);
+        assert_relative_eq!(configuration.state().flap,0.5,max_relative=1e-9);
+
+        configuration.reset(ConfigurationState { flap: 0.0, gear: 0.0, sweep: 0.3 });
+        assert_eq!(monitor.state(),ConfigurationState { flap: 0.0, gear: 0.0, sweep: 0.0 });
+    }
+
+    #[test]
+    fn test_increments() {
+        let mut configuration = Configuration::new()
+            .with_flap(0,Actuator::new(ActuatorDynamics::Ideal))
+            .with_gear(1,Some(Actuator::new(ActuatorDynamics::Ideal)));
+        let wing = Configured::new(Clean,configuration.monitor(),10.0,1.5)
+            .with_flap_increment(ConfigurationIncrement::new(2.0,0.2,-0.5))
+            .with_gear_increment(ConfigurationIncrement::new(0.0,0.02,0.01));
+        let (force,torque) = wing.get_effect(AirState { q: 1000.0, ..airstate(0.0,40.0) },Vector3::zeros(),&[0.0,0.0]);
+        assert_eq!((force.force.norm(),torque.torque.norm()),(0.0,0.0));
+
+        // Half a radian of flap with the gear down adds lift, drag and a nose-down moment mid-run
+        configuration.update(&[0.5,1.0],0.1);
+        let increment = wing.increment();
+        assert_relative_eq!(increment.c_lift,1.0,max_relative=1e-12);
+        assert_relative_eq!(increment.c_drag,0.12,max_relative=1e-12);
+        let (force,torque) = wing.get_effect(AirState { q: 1000.0, ..airstate(0.0,40.0) },Vector3::zeros(),&[0.5,1.0]);
+        assert!((force.force - Vector3::new(-1200.0,0.0,-10000.0)).norm() < 1e-6);
+        assert_relative_eq!(torque.torque.y,-0.24 * 1000.0 * 10.0 * 1.5,max_relative=1e-9);
+    }
+}
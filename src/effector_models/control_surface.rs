@@ -0,0 +1,159 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState};
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Orientation of a [ControlSurface]
+#[derive(Copy,Clone)]
+pub enum SurfaceOrientation {
+    /// Surface in the body X-Y plane (elevator, aileron, flap), producing lift along body -Z
+    Horizontal,
+    /// Surface in the body X-Z plane (rudder), producing side force along body Y
+    Vertical,
+}
+
+/// Built-in [AeroEffect] for the incremental forces and moments due to a control surface deflection
+///
+/// The deflection is read from a single inputstate channel (rad). The incremental force is
+///
+/// `ΔC_F = C_Fδ δ`, `ΔC_D = C_Dδ δ²`
+///
+/// referenced to the surface area and applied at the surface `position` relative to the centre of mass,
+/// generating moments `position × force`. Forces and torques are returned in the body frame.
+///
+/// The hinge moment, `H = q S c (C_hα α_local + C_hδ δ)` with `α_local` the angle of attack for
+/// horizontal surfaces and the sideslip for vertical surfaces, is available from [ControlSurface::hinge_moment].
+#[derive(Copy,Clone)]
+pub struct ControlSurface<T: Float = DefaultFloatRepr> {
+    /// Surface orientation
+    orientation: SurfaceOrientation,
+    /// Surface area (m<sup>2</sup>)
+    s: T,
+    /// Surface chord aft of the hinge line (m)
+    chord: T,
+    /// Surface aerodynamic centre relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Inputstate index of the deflection
+    channel: usize,
+    /// Lift (or side force) effectiveness (rad<sup>-1</sup>)
+    c_force_delta: T,
+    /// Drag due to deflection (rad<sup>-2</sup>)
+    c_drag_delta: T,
+    /// Hinge moment due to local incidence (rad<sup>-1</sup>)
+    c_hinge_alpha: T,
+    /// Hinge moment due to deflection (rad<sup>-1</sup>)
+    c_hinge_delta: T,
+}
+
+impl<T: Float> ControlSurface<T> {
+    /// Create a new [ControlSurface] with zero hinge moment derivatives
+    ///
+    /// # Arguments
+    ///
+    /// * `orientation` - Surface orientation
+    /// * `s` - Surface area (m<sup>2</sup>)
+    /// * `chord` - Surface chord aft of the hinge line (m)
+    /// * `position` - Surface aerodynamic centre relative to centre of mass, body frame (m)
+    /// * `channel` - Inputstate index of the deflection (rad)
+    /// * `c_force_delta` - Lift (or side force) effectiveness (rad<sup>-1</sup>)
+    /// * `c_drag_delta` - Drag due to deflection (rad<sup>-2</sup>)
+    pub fn new(orientation: SurfaceOrientation, s: T, chord: T, position: Vector3<T>, channel: usize, c_force_delta: T, c_drag_delta: T) -> Self {
+        ControlSurface {
+            orientation,
+            s,
+            chord,
+            position,
+            channel,
+            c_force_delta,
+            c_drag_delta,
+            c_hinge_alpha: T::zero(),
+            c_hinge_delta: T::zero(),
+        }
+    }
+
+    /// Set the hinge moment derivatives
+    ///
+    /// # Arguments
+    ///
+    /// * `c_hinge_alpha` - Hinge moment due to local incidence (rad<sup>-1</sup>)
+    /// * `c_hinge_delta` - Hinge moment due to deflection (rad<sup>-1</sup>)
+    pub fn with_hinge_derivatives(mut self, c_hinge_alpha: T, c_hinge_delta: T) -> Self {
+        self.c_hinge_alpha = c_hinge_alpha;
+        self.c_hinge_delta = c_hinge_delta;
+        self
+    }
+
+    /// Return the inputstate index of the deflection
+    pub fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Return the hinge moment (N·m) for `airstate` and `deflection` (rad)
+    ///
+    /// Positive hinge moments act to increase the deflection.
+    pub fn hinge_moment(&self, airstate: &AirState<T>, deflection: T) -> T {
+        let local_incidence = match self.orientation {
+            SurfaceOrientation::Horizontal => airstate.alpha,
+            SurfaceOrientation::Vertical => airstate.beta,
+        };
+        airstate.q * self.s * self.chord * (self.c_hinge_alpha * local_incidence + self.c_hinge_delta * deflection)
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for ControlSurface<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let deflection = inputstate[self.channel];
+
+        let c_force = self.c_force_delta * deflection;
+        let c_drag = self.c_drag_delta * deflection * deflection;
+
+        let q_s = airstate.q * self.s;
+        let force = match self.orientation {
+            SurfaceOrientation::Horizontal => {
+                let (c_x, c_z) = super::stability_to_body(c_force,c_drag,airstate.alpha);
+                Vector3::new(q_s * c_x, T::zero(), q_s * c_z)
+            },
+            SurfaceOrientation::Vertical => Vector3::new(-q_s * c_drag, q_s * c_force, T::zero()),
+        };
+
+        (Force::body_vec(force),Torque::body_vec(self.position.cross(&force)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_elevator() {
+        let elevator = ControlSurface::new(SurfaceOrientation::Horizontal,0.2,0.1,Vector3::new(-1.0,0.0,0.0),0,0.5,0.0)
+            .with_hinge_derivatives(-0.1,-0.4);
+        let airstate = AirState { beta: 0.05, q: 200.0, ..airstate(0.0,20.0) };
+
+        let (force,torque) = elevator.get_effect(airstate,Vector3::zeros(),&[0.1]);
+        // Lift of q S C_Ld δ = 2 N upwards, acting 1 m aft gives a nose-down moment
+        assert_relative_eq!(force.force.z,-2.0);
+        assert_relative_eq!(torque.torque.y,-2.0);
+
+        assert_relative_eq!(elevator.hinge_moment(&airstate,0.1),200.0 * 0.2 * 0.1 * -0.04);
+    }
+
+    #[test]
+    fn test_rudder() {
+        let rudder = ControlSurface::new(SurfaceOrientation::Vertical,0.1,0.1,Vector3::new(-1.0,0.0,-0.2),0,0.5,0.2)
+            .with_hinge_derivatives(-0.1,-0.4);
+        let airstate = AirState { beta: 0.05, q: 200.0, ..airstate(0.0,20.0) };
+
+        let (force,torque) = rudder.get_effect(airstate,Vector3::zeros(),&[0.1]);
+        assert_relative_eq!(force.force.y,1.0);
+        assert_relative_eq!(force.force.x,-20.0 * 0.2 * 0.01);
+        // Side force aft of the centre of mass gives negative yaw, above it gives positive roll
+        assert_relative_eq!(torque.torque.z,-1.0);
+        assert_relative_eq!(torque.torque.x,0.2);
+
+        assert_relative_eq!(rudder.hinge_moment(&airstate,0.0),200.0 * 0.1 * 0.1 * -0.1 * 0.05);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{AeroEffect,AirState,VehicleGeometry};
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr};
+
+use super::{nondimensional_rates,body_airspeed};
+
+/// Source of the rate damping moments of [Damping]
+#[derive(Copy,Clone)]
+enum RateDamping<T: Float> {
+    /// Moments of `-k ω` with gains `k` about each body axis (N·m·s·rad<sup>-1</sup>)
+    Dimensional(Vector3<T>),
+    /// Moments of `q S [b C_lp p̂, c C_mq q̂, b C_nr r̂]` from the nondimensional rates
+    Aerodynamic {
+        /// Reference area (m<sup>2</sup>)
+        s: T,
+        /// Span (m)
+        b: T,
+        /// Mean aerodynamic chord (m)
+        c: T,
+        /// Damping derivatives `[C_lp, C_mq, C_nr]` (rad<sup>-1</sup>)
+        derivatives: Vector3<T>,
+    },
+}
+
+/// Built-in [AeroEffect] for simple rate and velocity damping
+///
+/// Moments oppose the body rotation rates, either with dimensional gains or scaled with dynamic pressure
+/// in the style of the `C_lp`, `C_mq` and `C_nr` damping derivatives, and optional forces oppose each
+/// component of the body-frame air-relative velocity as `-k_i u_i`. This is a building block for quick
+/// vehicle models and for stabilising toy examples. Forces and torques are returned in the body frame.
+#[derive(Copy,Clone)]
+pub struct Damping<T: Float = DefaultFloatRepr> {
+    /// Rate damping moments
+    rotational: RateDamping<T>,
+    /// Velocity damping gains along each body axis (N·s·m<sup>-1</sup>)
+    linear: Vector3<T>,
+}
+
+impl<T: Float> Damping<T> {
+    /// Create a new [Damping] with dimensional gains
+    ///
+    /// # Arguments
+    ///
+    /// * `rotational` - Rate damping gains about each body axis (N·m·s·rad<sup>-1</sup>)
+    /// * `linear` - Velocity damping gains along each body axis (N·s·m<sup>-1</sup>)
+    pub fn new(rotational: Vector3<T>, linear: Vector3<T>) -> Self {
+        Damping { rotational: RateDamping::Dimensional(rotational), linear }
+    }
+
+    /// Create a new [Damping] from aerodynamic damping derivatives, without velocity damping
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Reference area (m<sup>2</sup>)
+    /// * `b` - Span (m)
+    /// * `c` - Mean aerodynamic chord (m)
+    /// * `c_l_p` - Roll damping derivative, negative for damping (rad<sup>-1</sup>)
+    /// * `c_m_q` - Pitch damping derivative, negative for damping (rad<sup>-1</sup>)
+    /// * `c_n_r` - Yaw damping derivative, negative for damping (rad<sup>-1</sup>)
+    pub fn from_derivatives(s: T, b: T, c: T, c_l_p: T, c_m_q: T, c_n_r: T) -> Self {
+        Damping {
+            rotational: RateDamping::Aerodynamic { s, b, c, derivatives: Vector3::new(c_l_p,c_m_q,c_n_r) },
+            linear: Vector3::zeros(),
+        }
+    }
+
+    /// Set the velocity damping gains along each body axis (N·s·m<sup>-1</sup>)
+    pub fn with_linear(mut self, linear: Vector3<T>) -> Self {
+        self.linear = linear;
+        self
+    }
+}
+
+impl<I, T: Float> AeroEffect<I,T> for Damping<T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let torque = match self.rotational {
+            RateDamping::Dimensional(gains) => -gains.component_mul(&rates),
+            RateDamping::Aerodynamic { s, b, c, derivatives } => {
+                let hat = nondimensional_rates(&rates,airstate.airspeed,b,c);
+                derivatives.component_mul(&hat).component_mul(&Vector3::new(b,c,b)) * (airstate.q * s)
+            },
+        };
+        let force = -self.linear.component_mul(&body_airspeed(&airstate));
+        (Force::body_vec(force),Torque::body_vec(torque))
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        if let RateDamping::Aerodynamic { s, b, c, .. } = &mut self.rotational {
+            *s = geometry.area;
+            *b = geometry.span;
+            *c = geometry.chord;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_dimensional() {
+        let damping = Damping::new(Vector3::new(1.0,2.0,3.0),Vector3::new(0.5,0.0,2.0));
+        // Flying at 10 m/s with 0.1 rad of incidence
+        let airstate = airstate(0.1,10.0);
+        let (force, torque) = AeroEffect::<Vec<f64>>::get_effect(&damping,airstate,Vector3::new(1.0,-1.0,0.5),&vec![]);
+        assert_eq!(torque.torque,Vector3::new(-1.0,2.0,-1.5));
+        assert_relative_eq!(force.force[0],-0.5 * 10.0 * 0.1_f64.cos(),max_relative=1e-9);
+        assert_relative_eq!(force.force[2],-2.0 * 10.0 * 0.1_f64.sin(),max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_derivatives() {
+        let damping = Damping::from_derivatives(2.0,4.0,0.5,-0.4,-12.0,-0.1);
+        let airstate = airstate(0.0,20.0);
+        let rates = Vector3::new(0.2,0.1,-0.3);
+        let (force, torque) = AeroEffect::<Vec<f64>>::get_effect(&damping,airstate,rates,&vec![]);
+        assert_eq!(force.force,Vector3::zeros());
+        // L = q S b C_lp p b / 2V
+        assert_relative_eq!(torque.torque[0],245.0 * 2.0 * 4.0 * -0.4 * 0.2 * 4.0 / 40.0,max_relative=1e-9);
+        assert_relative_eq!(torque.torque[1],245.0 * 2.0 * 0.5 * -12.0 * 0.1 * 0.5 / 40.0,max_relative=1e-9);
+        assert!(torque.torque[2] > 0.0);
+
+        // Aerodynamic damping vanishes at rest
+        let still = AirState { airspeed: 0.0, q: 0.0, ..airstate };
+        let (_, torque) = AeroEffect::<Vec<f64>>::get_effect(&damping,still,rates,&vec![]);
+        assert_eq!(torque.torque,Vector3::zeros());
+    }
+}
@@ -0,0 +1,221 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState};
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::{PropellerInput,Rotation};
+
+/// Built-in [AeroEffect] for a ducted fan
+///
+/// Ducted-fan vehicles differ from open rotors in three ways, each included here:
+///
+/// * Ram effect: the fan thrust `T = k_T ω² - k_ram ω V_in` falls with the inflow velocity `V_in` along
+///   the thrust axis. The induced velocity `v_i` follows from momentum theory for a duct with its exit area
+///   equal to the fan area, `T = ρ A (V_in + v_i) v_i`.
+/// * Momentum drag: the duct turns the in-plane relative wind `V_e` along its axis, giving the drag
+///   `F = -ṁ V_e` with mass flow `ṁ = ρ A (V_in + v_i)`. It acts at the duct lip, `lip_offset` ahead of the
+///   fan along the thrust axis, so a hovering ducted fan pitches away from the relative wind.
+/// * Duct lift: the duct is an annular wing at the angle of attack `α_d` between the relative wind and its
+///   axis, giving lift `q S_d C_Lα sin α_d cos α_d` normal to the relative wind at the fan.
+///
+/// Thrust acts along the unit `axis` in the body frame, body -Z by default. The [Rotation] is viewed from
+/// behind, looking forward along the thrust axis as for a [Propeller](super::Propeller), and the reaction
+/// torque `k_Q ω²` opposes it. Local velocities include the rotational velocity of the fan `ω_body × position`.
+/// Forces and torques are returned in the body frame.
+#[derive(Copy,Clone)]
+pub struct DuctedFan<T: Float = DefaultFloatRepr> {
+    /// Fan radius (m)
+    radius: T,
+    /// Static thrust coefficient (N·s<sup>2</sup>·rad<sup>-2</sup>)
+    k_thrust: T,
+    /// Static torque coefficient (N·m·s<sup>2</sup>·rad<sup>-2</sup>)
+    k_torque: T,
+    /// Ram thrust loss coefficient (N·s<sup>2</sup>·m<sup>-1</sup>·rad<sup>-1</sup>)
+    k_ram: T,
+    /// Duct lifting area (m<sup>2</sup>)
+    duct_area: T,
+    /// Duct lift curve slope (rad<sup>-1</sup>)
+    duct_lift_slope: T,
+    /// Distance of the duct lip ahead of the fan along the thrust axis (m)
+    lip_offset: T,
+    /// Unit thrust axis, body frame
+    axis: Vector3<T>,
+    /// Fan position relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Direction of rotation, viewed from behind
+    rotation: Rotation,
+    /// Input channel
+    input: PropellerInput<T>,
+}
+
+impl<T: Float> DuctedFan<T> {
+    /// Create a new [DuctedFan] thrusting along body -Z, with no ram effect, duct lift or lip offset
+    ///
+    /// # Arguments
+    ///
+    /// * `radius` - Fan radius (m)
+    /// * `k_thrust` - Static thrust coefficient (N·s<sup>2</sup>·rad<sup>-2</sup>)
+    /// * `k_torque` - Static torque coefficient (N·m·s<sup>2</sup>·rad<sup>-2</sup>)
+    /// * `position` - Fan position relative to centre of mass, body frame (m)
+    /// * `rotation` - Direction of rotation, viewed from behind
+    /// * `input` - The channel driving the fan
+    pub fn new(radius: T, k_thrust: T, k_torque: T, position: Vector3<T>, rotation: Rotation, input: PropellerInput<T>) -> Self {
+        DuctedFan {
+            radius,
+            k_thrust,
+            k_torque,
+            k_ram: T::zero(),
+            duct_area: T::zero(),
+            duct_lift_slope: T::zero(),
+            lip_offset: T::zero(),
+            axis: -Vector3::z(),
+            position,
+            rotation,
+            input,
+        }
+    }
+
+    /// Set the thrust axis, body frame
+    ///
+    /// # Panics
+    ///
+    /// Panics if `axis` is zero.
+    pub fn with_axis(mut self, axis: Vector3<T>) -> Self {
+        if axis.norm() == T::zero() {
+            panic!("Ducted fan axis must be non-zero");
+        }
+        self.axis = axis.normalize();
+        self
+    }
+
+    /// Set the ram thrust loss coefficient (N·s<sup>2</sup>·m<sup>-1</sup>·rad<sup>-1</sup>)
+    pub fn with_ram(mut self, k_ram: T) -> Self {
+        self.k_ram = k_ram;
+        self
+    }
+
+    /// Set the duct lifting area `duct_area` (m<sup>2</sup>) and lift curve slope `lift_slope` (rad<sup>-1</sup>)
+    pub fn with_duct_lift(mut self, duct_area: T, lift_slope: T) -> Self {
+        self.duct_area = duct_area;
+        self.duct_lift_slope = lift_slope;
+        self
+    }
+
+    /// Set the distance of the duct lip ahead of the fan along the thrust axis (m)
+    pub fn with_lip_offset(mut self, lip_offset: T) -> Self {
+        self.lip_offset = lip_offset;
+        self
+    }
+
+    /// Return the fan radius (m)
+    pub fn radius(&self) -> T {
+        self.radius
+    }
+
+    /// Return the shaft speed (rad·s<sup>-1</sup>) commanded by `inputstate`
+    fn omega<I: Index<usize,Output=T>>(&self, inputstate: &I) -> T {
+        self.input.rpm(inputstate) * T::two_pi() / constant::<T>(60.0)
+    }
+
+    /// Return `(thrust, mass_flow)` (N, kg·s<sup>-1</sup>) for shaft speed `omega` (rad·s<sup>-1</sup>), inflow
+    /// velocity `inflow` along the thrust axis (m·s<sup>-1</sup>) and air `density` (kg·m<sup>-3</sup>)
+    pub fn thrust(&self, omega: T, inflow: T, density: T) -> (T,T) {
+        if omega <= T::zero() || density <= T::zero() {
+            return (T::zero(),T::zero());
+        }
+        let area = T::pi() * self.radius * self.radius;
+        let thrust = <T as num_traits::Float>::max(self.k_thrust * omega * omega - self.k_ram * omega * inflow,T::zero());
+        // Positive root of v_i² + V_in v_i - T / (ρ A) = 0
+        let four = constant::<T>(4.0);
+        let induced = (<T as num_traits::Float>::sqrt(inflow * inflow + four * thrust / (density * area)) - inflow) / constant::<T>(2.0);
+        (thrust,density * area * <T as num_traits::Float>::max(inflow + induced,T::zero()))
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for DuctedFan<T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let omega = self.omega(inputstate);
+        // Fan velocity relative to the air, body frame
+        let velocity = super::body_airspeed(&airstate) + rates.cross(&self.position);
+        let inflow = velocity.dot(&self.axis);
+        let edgewise = velocity - self.axis * inflow;
+
+        let (thrust, mass_flow) = self.thrust(omega,inflow,airstate.density);
+        let momentum_drag = -edgewise * mass_flow;
+
+        let speed = velocity.norm();
+        let duct_lift = if speed > T::zero() {
+            let flow = velocity / speed;
+            // The component of the axis normal to the relative wind, of length sin α_d, sets the lift direction
+            let normal = self.axis - flow * flow.dot(&self.axis);
+            let cos_alpha = flow.dot(&self.axis);
+            let q = constant::<T>(0.5) * airstate.density * speed * speed;
+            normal * (q * self.duct_area * self.duct_lift_slope * cos_alpha)
+        } else {
+            Vector3::zeros()
+        };
+
+        let fan_force = self.axis * thrust + duct_lift;
+        let lip = self.position + self.axis * self.lip_offset;
+        let reaction = self.axis * (-self.rotation.sign::<T>() * self.k_torque * omega * omega);
+
+        (
+            Force::body_vec(fan_force + momentum_drag),
+            Torque::body_vec(self.position.cross(&fan_force) + lip.cross(&momentum_drag) + reaction)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    fn fan() -> DuctedFan {
+        DuctedFan::new(0.15,2e-5,3e-7,Vector3::zeros(),Rotation::Clockwise,PropellerInput::Rpm(0))
+            .with_ram(2e-4)
+            .with_duct_lift(0.3,3.0)
+            .with_lip_offset(0.2)
+    }
+
+    #[test]
+    fn test_hover() {
+        let fan = fan();
+        let rpm = 6000.0;
+        let omega = rpm * std::f64::consts::PI / 30.0;
+        let (force,torque) = fan.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[rpm]);
+        assert_relative_eq!(force.force.z,-2e-5 * omega * omega,max_relative=1e-9);
+        assert_relative_eq!(force.force.x,0.0);
+        assert_relative_eq!(torque.torque.z,3e-7 * omega * omega,max_relative=1e-9);
+
+        // The duct hovers with the induced velocity √(T / ρA)
+        let area = std::f64::consts::PI * 0.15 * 0.15;
+        let (thrust, mass_flow) = fan.thrust(omega,0.0,1.225);
+        assert_relative_eq!(mass_flow,1.225 * area * (thrust / (1.225 * area)).sqrt(),max_relative=1e-9);
+        // Ram inflow reduces the thrust
+        assert!(fan.thrust(omega,5.0,1.225).0 < thrust);
+        assert_eq!(fan.thrust(0.0,5.0,1.225),(0.0,0.0));
+    }
+
+    #[test]
+    fn test_edgewise_flight() {
+        // Moving forward, the momentum drag at the lip above the fan pitches the vehicle nose-up
+        let fan = fan();
+        let (force,torque) = fan.get_effect(airstate(0.0,5.0),Vector3::zeros(),&[6000.0]);
+        assert!(force.force.x < 0.0);
+        assert!(torque.torque.y > 0.0);
+        assert_relative_eq!(force.force.y,0.0);
+
+        // A forward-thrusting duct at angle of attack lifts like an annular wing
+        let horizontal = DuctedFan::new(0.15,2e-5,3e-7,Vector3::zeros(),Rotation::Clockwise,PropellerInput::Rpm(0))
+            .with_axis(Vector3::x())
+            .with_duct_lift(0.3,3.0);
+        let alpha: f64 = 0.2;
+        let (force,_) = horizontal.get_effect(airstate(alpha,20.0),Vector3::zeros(),&[0.0]);
+        let lift = 0.5 * 1.225 * 400.0 * 0.3 * 3.0 * alpha.sin() * alpha.cos();
+        assert_relative_eq!(force.force.z,-lift * alpha.cos(),max_relative=1e-9);
+        assert_relative_eq!(force.force.x,lift * alpha.sin(),max_relative=1e-9);
+    }
+}
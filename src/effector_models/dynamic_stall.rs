@@ -0,0 +1,184 @@
+use crate::{AeroEffect,AirState,Error,VehicleGeometry};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Built-in [AeroEffect] for a stateful dynamic stall model
+///
+/// Implements the Goman–Khrabrov state-space model, in which the trailing-edge separation point `x`
+/// (1 for fully attached flow, 0 for fully separated) relaxes towards its static value with a lag:
+///
+/// `τ1 dx/dt + x = x0(α - τ2 dα/dt)`, with `x0(α) = ½(1 - tanh(a1 (α - α*)))`
+///
+/// The normal force follows Kirchhoff flow theory, `C_N = C_Nα sin α ((1 + √x)/2)²`, giving lift and
+/// drag hysteresis loops during rapid pitching. Time constants are in convective time units of `c/V`.
+///
+/// The separation state is advanced by [AeroEffect::step] when used in an [AffectedBody](crate::AffectedBody),
+/// or can be advanced manually using [DynamicStall::update].
+/// Forces are returned in the body frame.
+#[derive(Copy,Clone)]
+pub struct DynamicStall<T: Float = DefaultFloatRepr> {
+    /// Reference area (m<sup>2</sup>)
+    s: T,
+    /// Reference chord (m)
+    c: T,
+    /// Normal force curve slope (rad<sup>-1</sup>)
+    c_normal_alpha: T,
+    /// Zero-lift drag coefficient
+    c_drag_0: T,
+    /// Static separation breakpoint angle (rad)
+    alpha_star: T,
+    /// Static separation steepness (rad<sup>-1</sup>)
+    a1: T,
+    /// Separation relaxation time constant (convective units)
+    tau1: T,
+    /// Separation time delay (convective units)
+    tau2: T,
+    /// Separation point state
+    separation: T,
+    /// Angle of attack at the previous update (rad)
+    previous_alpha: Option<T>,
+}
+
+impl<T: Float> DynamicStall<T> {
+    /// Create a new [DynamicStall] model with fully attached flow
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Reference area (m<sup>2</sup>)
+    /// * `c` - Reference chord (m)
+    /// * `c_normal_alpha` - Normal force curve slope (rad<sup>-1</sup>)
+    /// * `c_drag_0` - Zero-lift drag coefficient
+    /// * `alpha_star` - Static separation breakpoint angle (rad), close to the static stall angle
+    /// * `a1` - Static separation steepness (rad<sup>-1</sup>), typically 20–40
+    /// * `tau1` - Separation relaxation time constant (convective units), typically 2–5
+    /// * `tau2` - Separation time delay (convective units), typically 1–5
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(s: T, c: T, c_normal_alpha: T, c_drag_0: T, alpha_star: T, a1: T, tau1: T, tau2: T) -> Self {
+        DynamicStall {
+            s,
+            c,
+            c_normal_alpha,
+            c_drag_0,
+            alpha_star,
+            a1,
+            tau1,
+            tau2,
+            separation: T::one(),
+            previous_alpha: None,
+        }
+    }
+
+    /// Return the static separation point at `alpha`
+    pub fn static_separation(&self, alpha: T) -> T {
+        constant::<T>(0.5) * (T::one() - <T as num_traits::Float>::tanh(self.a1 * (alpha - self.alpha_star)))
+    }
+
+    /// Return the current separation point state
+    pub fn separation(&self) -> T {
+        self.separation
+    }
+
+    /// Advance the separation point state by `delta_t` given the current `airstate`
+    ///
+    /// The state is held when the airspeed is zero.
+    pub fn update(&mut self, airstate: &AirState<T>, delta_t: T) {
+        let alpha_rate = match self.previous_alpha {
+            Some(previous_alpha) if delta_t > T::zero() => (airstate.alpha - previous_alpha) / delta_t,
+            _ => T::zero(),
+        };
+        self.previous_alpha = Some(airstate.alpha);
+
+        if airstate.airspeed <= T::zero() {
+            return;
+        }
+        let convective_time = self.c / airstate.airspeed;
+
+        let target = self.static_separation(airstate.alpha - self.tau2 * convective_time * alpha_rate);
+        // Exact discretisation of the first-order lag for a constant target over the step
+        let decay = <T as num_traits::Float>::exp(-delta_t / (self.tau1 * convective_time));
+        self.separation = target + (self.separation - target) * decay;
+    }
+
+    /// Return the stability-axis `(C_L, C_D)` for `alpha` and the current separation state
+    pub fn coefficients(&self, alpha: T) -> (T,T) {
+        let kirchhoff = <T as num_traits::Float>::powi((T::one() + <T as num_traits::Float>::sqrt(self.separation)) * constant::<T>(0.5),2);
+        let c_normal = self.c_normal_alpha * <T as num_traits::Float>::sin(alpha) * kirchhoff;
+        let c_lift = c_normal * <T as num_traits::Float>::cos(alpha);
+        let c_drag = self.c_drag_0 + c_normal * <T as num_traits::Float>::sin(alpha);
+        (c_lift,c_drag)
+    }
+}
+
+impl<I, T: Float> AeroEffect<I,T> for DynamicStall<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (c_lift, c_drag) = self.coefficients(airstate.alpha);
+        let (c_x, c_z) = super::stability_to_body(c_lift,c_drag,airstate.alpha);
+
+        let q_s = airstate.q * self.s;
+        (Force::body(q_s * c_x, T::zero(), q_s * c_z),Torque::body(T::zero(),T::zero(),T::zero()))
+    }
+    
+    fn step(&mut self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I, delta_t: T) {
+        self.update(&airstate,delta_t);
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.s = geometry.area;
+        self.c = geometry.chord;
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        // The previous angle of attack is appended once the model has been stepped
+        let mut values = vec![self.separation];
+        values.extend(self.previous_alpha);
+        EffectorState::from_values(&values)
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(1,0,0).or_else(|_| state.check(2,0,0))?;
+        self.separation = state.value(0);
+        self.previous_alpha = (state.values.len() == 2).then(|| state.value(1));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    fn model() -> DynamicStall {
+        DynamicStall::new(1.0,0.5,6.0,0.01,15.0f64.to_radians(),30.0,3.0,2.0)
+    }
+
+    #[test]
+    fn test_converges_to_static() {
+        let mut aero = model();
+        let alpha = 25.0f64.to_radians();
+        for _ in 0..1000 {
+            aero.update(&airstate(alpha,20.0),0.01);
+        }
+        assert_relative_eq!(aero.separation(),aero.static_separation(alpha),epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        // Pitch up and back down through stall: lift on the upstroke exceeds lift on the downstroke
+        let mut aero = model();
+        let delta_t = 0.001;
+        let mut lift_up = 0.0;
+        let mut lift_down = 0.0;
+        let period = 0.5;
+        let steps = (period / delta_t) as usize;
+        for step in 0..=steps {
+            let phase = step as f64 / steps as f64;
+            let alpha = (15.0 - 10.0 * (2.0 * std::f64::consts::PI * phase).cos()).to_radians();
+            aero.update(&airstate(alpha,20.0),delta_t);
+            if step == steps / 4 { lift_up = aero.coefficients(alpha).0; }
+            if step == 3 * steps / 4 { lift_down = aero.coefficients(alpha).0; }
+        }
+        assert!(lift_up > lift_down);
+    }
+}
@@ -0,0 +1,337 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,Error};
+use crate::snapshot::EffectorState;
+use crate::lookup::Table;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::Propeller;
+
+/// Represent a brushless DC motor
+#[derive(Copy,Clone)]
+pub struct Motor<T: Float = DefaultFloatRepr> {
+    /// Speed constant (rev·min<sup>-1</sup>·V<sup>-1</sup>)
+    pub kv: T,
+    /// Winding resistance (Ω)
+    pub resistance: T,
+    /// No-load current (A)
+    pub no_load_current: T,
+}
+
+impl<T: Float> Motor<T> {
+    /// Return the torque constant (N·m·A<sup>-1</sup>), equal to the back-EMF constant (V·s·rad<sup>-1</sup>)
+    pub fn torque_constant(&self) -> T {
+        constant::<T>(60.0) / (T::two_pi() * self.kv)
+    }
+
+    /// Return the winding current (A) for terminal `voltage` and shaft speed `omega` (rad·s<sup>-1</sup>)
+    pub fn current(&self, voltage: T, omega: T) -> T {
+        (voltage - omega * self.torque_constant()) / self.resistance
+    }
+
+    /// Return the shaft torque (N·m) for winding `current` (A)
+    pub fn torque(&self, current: T) -> T {
+        // No-load losses only oppose motion in the driving direction
+        let loss_current = <T as num_traits::Float>::min(self.no_load_current,<T as num_traits::Float>::abs(current));
+        (current - <T as num_traits::Float>::signum(current) * loss_current) * self.torque_constant()
+    }
+}
+
+/// Represent a battery pack with state of charge and internal resistance
+#[derive(Clone)]
+pub struct Battery<T: Float = DefaultFloatRepr> {
+    /// Cells in series
+    cells: usize,
+    /// Capacity (A·h)
+    capacity: T,
+    /// Pack internal resistance (Ω)
+    internal_resistance: T,
+    /// Open-circuit cell voltage against state of charge
+    cell_voltage: Table<T>,
+    /// State of charge, from 0 to 1
+    state_of_charge: T,
+}
+
+impl<T: Float> Battery<T> {
+    /// Create a new fully-charged [Battery]
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - Number of cells in series
+    /// * `capacity` - Capacity (A·h)
+    /// * `internal_resistance` - Pack internal resistance (Ω)
+    /// * `cell_voltage` - 1D table of open-circuit cell voltage (V) against state of charge (0 to 1)
+    pub fn new(cells: usize, capacity: T, internal_resistance: T, cell_voltage: Table<T>) -> Self {
+        if cell_voltage.dimensions() != 1 {
+            panic!("Battery cell voltage table must be 1D in state of charge")
+        }
+        Battery {
+            cells,
+            capacity,
+            internal_resistance,
+            cell_voltage,
+            state_of_charge: T::one(),
+        }
+    }
+
+    /// Create a new fully-charged lithium-polymer [Battery] using a typical discharge curve
+    pub fn lipo(cells: usize, capacity: T, internal_resistance: T) -> Self {
+        let soc = [0.0, 0.05, 0.1, 0.2, 0.4, 0.6, 0.8, 0.9, 1.0];
+        let volts = [3.27, 3.61, 3.69, 3.73, 3.78, 3.86, 3.98, 4.08, 4.2];
+        let cell_voltage = Table::new_1d(
+            soc.iter().map(|&x| constant::<T>(x)).collect(),
+            volts.iter().map(|&x| constant::<T>(x)).collect());
+        Battery::new(cells,capacity,internal_resistance,cell_voltage)
+    }
+
+    /// Return the state of charge, from 0 to 1
+    pub fn state_of_charge(&self) -> T {
+        self.state_of_charge
+    }
+
+    /// Set the state of charge, from 0 to 1
+    pub fn set_state_of_charge(&mut self, state_of_charge: T) {
+        self.state_of_charge = num_traits::clamp(state_of_charge,T::zero(),T::one());
+    }
+
+    /// Return the open-circuit pack voltage (V)
+    pub fn open_circuit_voltage(&self) -> T {
+        self.cell_voltage.lookup(&[self.state_of_charge]) * constant::<T>(self.cells as f64)
+    }
+
+    /// Return the terminal voltage (V) when supplying `current` (A)
+    pub fn terminal_voltage(&self, current: T) -> T {
+        <T as num_traits::Float>::max(self.open_circuit_voltage() - current * self.internal_resistance,T::zero())
+    }
+
+    /// Discharge the battery with `current` (A) for `delta_t` (s)
+    pub fn discharge(&mut self, current: T, delta_t: T) {
+        let capacity_coulombs = self.capacity * constant::<T>(3600.0);
+        self.set_state_of_charge(self.state_of_charge - current * delta_t / capacity_coulombs);
+    }
+}
+
+/// Built-in [AeroEffect] for an electric propulsion system of battery, ESC, motor and [Propeller]
+///
+/// The throttle (0 to 1) on `channel` sets the ESC duty cycle, so the motor sees `throttle × V_terminal`.
+/// The shaft speed is an auxiliary state integrated from the difference between motor and
+/// propeller torque with the rotating inertia, and the battery state of charge is integrated from
+/// the battery current, `I_battery = V_motor I_motor / (η_ESC V_terminal)`.
+/// Voltage sag is calculated using the battery current from the previous timestep.
+///
+/// The propeller's own input channel is ignored. Forces and torques are returned in the body frame.
+#[derive(Clone)]
+pub struct ElectricPowertrain<T: Float = DefaultFloatRepr> {
+    /// Driven propeller
+    propeller: Propeller<T>,
+    /// Motor
+    motor: Motor<T>,
+    /// Battery
+    battery: Battery<T>,
+    /// ESC efficiency
+    esc_efficiency: T,
+    /// Rotating inertia of motor and propeller (kg·m<sup>2</sup>)
+    rotor_inertia: T,
+    /// Inputstate index of the throttle
+    channel: usize,
+    /// Shaft speed (rad·s<sup>-1</sup>)
+    omega: T,
+    /// Motor winding current (A)
+    motor_current: T,
+    /// Battery current (A)
+    battery_current: T,
+}
+
+impl<T: Float> ElectricPowertrain<T> {
+    /// Create a new [ElectricPowertrain] at rest
+    ///
+    /// # Arguments
+    ///
+    /// * `propeller` - Propeller driven by the motor
+    /// * `motor` - Motor model
+    /// * `battery` - Battery model
+    /// * `esc_efficiency` - ESC efficiency, from 0 to 1
+    /// * `rotor_inertia` - Rotating inertia of motor and propeller (kg·m<sup>2</sup>)
+    /// * `channel` - Inputstate index of the throttle (0 to 1)
+    pub fn new(propeller: Propeller<T>, motor: Motor<T>, battery: Battery<T>, esc_efficiency: T, rotor_inertia: T, channel: usize) -> Self {
+        ElectricPowertrain {
+            propeller,
+            motor,
+            battery,
+            esc_efficiency,
+            rotor_inertia,
+            channel,
+            omega: T::zero(),
+            motor_current: T::zero(),
+            battery_current: T::zero(),
+        }
+    }
+
+    /// Return the shaft speed (rev·min<sup>-1</sup>)
+    pub fn rpm(&self) -> T {
+        self.omega * constant::<T>(60.0) / T::two_pi()
+    }
+
+    /// Return the motor winding current (A)
+    pub fn motor_current(&self) -> T {
+        self.motor_current
+    }
+
+    /// Return the battery current (A)
+    pub fn battery_current(&self) -> T {
+        self.battery_current
+    }
+
+    /// Return the battery terminal voltage (V)
+    pub fn voltage(&self) -> T {
+        self.battery.terminal_voltage(self.battery_current)
+    }
+
+    /// Return the battery
+    pub fn battery(&self) -> &Battery<T> {
+        &self.battery
+    }
+
+    /// Return the propeller
+    pub fn propeller(&self) -> &Propeller<T> {
+        &self.propeller
+    }
+
+    /// Advance the shaft speed and battery state by `delta_t`
+    ///
+    /// # Arguments
+    ///
+    /// * `airstate` - The vehicle airstate
+    /// * `throttle` - ESC throttle, from 0 to 1
+    /// * `delta_t` - Timestep (s)
+    pub fn update(&mut self, airstate: &AirState<T>, throttle: T, delta_t: T) {
+        let throttle = num_traits::clamp(throttle,T::zero(),T::one());
+        let axial_airspeed = super::body_airspeed(airstate)[0];
+        let terminal_voltage = self.battery.terminal_voltage(self.battery_current);
+        let motor_voltage = throttle * terminal_voltage;
+
+        // Electromechanical time constant of the motor, used to keep the explicit integration stable
+        let k = self.motor.torque_constant();
+        let time_constant = self.rotor_inertia * self.motor.resistance / (k * k);
+        let substeps = <T as num_traits::Float>::ceil(constant::<T>(5.0) * delta_t / time_constant)
+            .to_usize().unwrap_or(1).clamp(1,1000);
+        let h = delta_t / constant::<T>(substeps as f64);
+
+        let mut motor_current = T::zero();
+        for _ in 0..substeps {
+            motor_current = self.motor.current(motor_voltage,self.omega);
+            let rpm = self.omega * constant::<T>(60.0) / T::two_pi();
+            let (_, propeller_torque) = self.propeller.thrust_torque(axial_airspeed,airstate.density,rpm);
+            let omega_dot = (self.motor.torque(motor_current) - propeller_torque) / self.rotor_inertia;
+            self.omega = <T as num_traits::Float>::max(self.omega + omega_dot * h,T::zero());
+        }
+        self.motor_current = motor_current;
+
+        self.battery_current = if terminal_voltage > T::zero() {
+            <T as num_traits::Float>::max(motor_voltage * motor_current / (self.esc_efficiency * terminal_voltage),T::zero())
+        } else {
+            T::zero()
+        };
+        self.battery.discharge(self.battery_current,delta_t);
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for ElectricPowertrain<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        // Drive the propeller at the current shaft speed
+        self.propeller.effect_at_rpm(&airstate,self.rpm())
+    }
+
+    fn step(&mut self, airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.update(&airstate,inputstate[self.channel],delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        EffectorState::from_values(&[self.omega,self.motor_current,self.battery_current,self.battery.state_of_charge])
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(4,0,0)?;
+        self.omega = state.value(0);
+        self.motor_current = state.value(1);
+        self.battery_current = state.value(2);
+        self.battery.state_of_charge = state.value(3);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::effector_models::PropellerInput;
+    use approx::assert_relative_eq;
+
+    fn powertrain() -> ElectricPowertrain {
+        powertrain_with(PropellerInput::Rpm(0))
+    }
+
+    fn powertrain_with(input: PropellerInput) -> ElectricPowertrain {
+        let propeller = Propeller::new(
+            0.254,
+            Table::new_1d(vec![0.0,0.8],vec![0.11,0.0]),
+            Table::new_1d(vec![0.0,0.8],vec![0.05,0.02]),
+            input);
+        let motor = Motor { kv: 900.0, resistance: 0.08, no_load_current: 0.5 };
+        let battery = Battery::lipo(3,2.2,0.03);
+        ElectricPowertrain::new(propeller,motor,battery,0.95,5e-5,0)
+    }
+
+    #[test]
+    fn test_spin_up_equilibrium() {
+        let mut powertrain = powertrain();
+        for _ in 0..500 {
+            powertrain.update(&airstate(0.0,0.0),0.7,0.01);
+        }
+        // At equilibrium motor torque balances propeller torque
+        let (_, propeller_torque) = powertrain.propeller().thrust_torque(0.0,1.225,powertrain.rpm());
+        assert_relative_eq!(powertrain.motor.torque(powertrain.motor_current()),propeller_torque,max_relative=1e-3);
+        assert!(powertrain.rpm() > 1000.0);
+
+        let (force,_) = powertrain.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[0.7]);
+        assert!(force.force.x > 0.0);
+    }
+
+    #[test]
+    fn test_battery_drain_and_sag() {
+        let mut powertrain = powertrain();
+        let open_circuit = powertrain.battery().open_circuit_voltage();
+        for _ in 0..1000 {
+            powertrain.update(&airstate(0.0,0.0),1.0,0.01);
+        }
+        assert!(powertrain.battery().state_of_charge() < 1.0);
+        assert!(powertrain.voltage() < open_circuit);
+        // Charge removed matches the integrated current at steady state
+        let expected_soc = 1.0 - powertrain.battery_current() * 10.0 / (2.2 * 3600.0);
+        assert_relative_eq!(powertrain.battery().state_of_charge(),expected_soc,max_relative=1e-2);
+    }
+
+    #[test]
+    fn test_zero_throttle() {
+        let mut powertrain = powertrain();
+        powertrain.update(&airstate(0.0,0.0),0.0,0.01);
+        assert_relative_eq!(powertrain.rpm(),0.0);
+        assert_relative_eq!(powertrain.battery().state_of_charge(),1.0);
+    }
+
+    #[test]
+    fn test_propeller_input_ignored() {
+        // The propeller's channel lies outside the inputstate, and a throttle input would clamp the shaft speed
+        for input in [PropellerInput::Rpm(1),PropellerInput::Throttle { channel: 0, max_rpm: 1.0 }] {
+            let mut powertrain = powertrain_with(input);
+            for _ in 0..100 {
+                powertrain.update(&airstate(0.0,0.0),0.7,0.01);
+            }
+            let (thrust, _) = powertrain.propeller().thrust_torque(0.0,1.225,powertrain.rpm());
+            let (force,_) = powertrain.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[0.7]);
+            assert_relative_eq!(force.force.x,thrust);
+            assert!(thrust > 1.0);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,VehicleGeometry,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,UnitQuaternion,Force,Torque,Frame};
+use crate::types::{Float,DefaultFloatRepr};
+
+/// [AeroEffect] wrapper which vectors the output of a propulsion effector about a gimbal pivot
+///
+/// The wrapped effector is defined in the gimbal frame, with positions relative to the pivot, and
+/// its body-frame forces and torques are rotated by the gimbal angles read from the inputstate: a
+/// pitch angle (rad) about body Y followed by a yaw angle (rad) about body Z. For a thrust along
+/// body +X, positive pitch tilts the thrust towards body -Z and positive yaw tilts it towards body +Y.
+/// The induced moment of the rotated force about the centre of mass, `pivot × F`, is added to the
+/// rotated torque.
+///
+/// World-frame forces and torques from the wrapped effector are passed through unrotated. The
+/// wrapped effector sees the unrotated airstate. Gimbal actuator dynamics can be added by wrapping
+/// in [Actuated](super::Actuated) on each gimbal channel.
+#[derive(Copy,Clone)]
+pub struct Gimballed<E, T: Float = DefaultFloatRepr> {
+    /// Wrapped effector
+    effector: E,
+    /// Gimbal pivot relative to centre of mass, body frame (m)
+    pivot: Vector3<T>,
+    /// Inputstate index of the pitch gimbal angle
+    pitch_channel: usize,
+    /// Inputstate index of the yaw gimbal angle, if gimballed in yaw
+    yaw_channel: Option<usize>,
+    /// Maximum gimbal angle magnitude on each axis (rad)
+    limit: T,
+}
+
+impl<E, T: Float> Gimballed<E,T> {
+    /// Wrap `effector` in a single-axis pitch gimbal at `pivot`
+    ///
+    /// # Arguments
+    ///
+    /// * `effector` - Propulsion effector, defined relative to the pivot
+    /// * `pivot` - Gimbal pivot relative to centre of mass, body frame (m)
+    /// * `pitch_channel` - Inputstate index of the pitch gimbal angle (rad)
+    pub fn new(effector: E, pivot: Vector3<T>, pitch_channel: usize) -> Self {
+        Gimballed {
+            effector,
+            pivot,
+            pitch_channel,
+            yaw_channel: None,
+            limit: T::pi(),
+        }
+    }
+
+    /// Add a yaw gimbal axis driven by `yaw_channel` (rad)
+    pub fn with_yaw_channel(mut self, yaw_channel: usize) -> Self {
+        self.yaw_channel = Some(yaw_channel);
+        self
+    }
+
+    /// Limit the magnitude of each gimbal angle (rad)
+    pub fn with_limit(mut self, limit: T) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Return the wrapped effector
+    pub fn effector(&self) -> &E {
+        &self.effector
+    }
+
+    /// Return the limited `(pitch, yaw)` gimbal angles (rad) commanded by `inputstate`
+    pub fn angles<I: Index<usize,Output=T>>(&self, inputstate: &I) -> (T,T) {
+        let pitch = num_traits::clamp(inputstate[self.pitch_channel],-self.limit,self.limit);
+        let yaw = match self.yaw_channel {
+            Some(channel) => num_traits::clamp(inputstate[channel],-self.limit,self.limit),
+            None => T::zero(),
+        };
+        (pitch,yaw)
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float, E: AeroEffect<I,T>> AeroEffect<I,T> for Gimballed<E,T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (force, torque) = self.effector.get_effect(airstate,rates,inputstate);
+        let (pitch, yaw) = self.angles(inputstate);
+        let rotation = UnitQuaternion::from_euler_angles(T::zero(),pitch,yaw);
+
+        let (force, induced) = match force.frame {
+            Frame::Body => {
+                let rotated = rotation * force.force;
+                (Force::body_vec(rotated),self.pivot.cross(&rotated))
+            },
+            Frame::World => (force,Vector3::zeros()),
+        };
+        let torque = match torque.frame {
+            Frame::Body => Torque::body_vec(rotation * torque.torque + induced),
+            Frame::World => torque,
+        };
+        (force,torque)
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.effector.step(airstate,rates,inputstate,delta_t);
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.effector.set_geometry(geometry);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        self.effector.snapshot()
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        self.effector.restore(state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    struct Thrust;
+    impl AeroEffect<[f64;2]> for Thrust {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3, _inputstate: &[f64;2]) -> (Force,Torque) {
+            // Nozzle 0.5 m aft of the pivot with a roll reaction torque
+            let force = Vector3::new(100.0,0.0,0.0);
+            (Force::body_vec(force),Torque::body_vec(Vector3::new(-0.5,0.0,0.0).cross(&force) + Vector3::new(1.0,0.0,0.0)))
+        }
+    }
+
+    #[test]
+    fn test_pitch_gimbal() {
+        let gimbal = Gimballed::new(Thrust,Vector3::new(-2.0,0.0,0.0),0);
+        let angle: f64 = 0.1;
+        let (force,torque) = gimbal.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[angle,0.0]);
+        assert_relative_eq!(force.force.x,100.0 * angle.cos());
+        assert_relative_eq!(force.force.z,-100.0 * angle.sin());
+        // Upward thrust component 2 m aft of the centre of mass pitches the nose down
+        assert_relative_eq!(torque.torque.y,-2.0 * 100.0 * angle.sin());
+        assert_relative_eq!(torque.torque.x,angle.cos());
+    }
+
+    #[test]
+    fn test_yaw_gimbal_limit() {
+        let gimbal = Gimballed::new(Thrust,Vector3::new(-2.0,0.0,0.0),0)
+            .with_yaw_channel(1)
+            .with_limit(0.1);
+        let (pitch,yaw) = gimbal.angles(&[0.0,0.5]);
+        assert_relative_eq!(pitch,0.0);
+        assert_relative_eq!(yaw,0.1);
+
+        let (force,torque) = gimbal.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[0.0,0.5]);
+        assert_relative_eq!(force.force.y,100.0 * 0.1f64.sin());
+        // Side force aft of the centre of mass yaws the nose away
+        assert_relative_eq!(torque.torque.z,-2.0 * 100.0 * 0.1f64.sin());
+    }
+}
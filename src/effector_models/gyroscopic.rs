@@ -0,0 +1,94 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState};
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::{PropellerInput,Rotation};
+
+/// Built-in [AeroEffect] for the gyroscopic moment of a spinning component
+///
+/// A propeller, rotor or turbine spool with polar moment of inertia `I_r` spinning at `Ω` about
+/// `axis` carries angular momentum `H = I_r Ω axis`. Rotating the airframe at `ω` gives the
+/// gyroscopic moment
+///
+/// `M = H × ω`
+///
+/// which is added to the rigid-body dynamics. The [Rotation] is viewed looking along `axis`, so a
+/// [Rotation::Clockwise] rotor spins positively about `axis`. Torque due to changes in spin speed is
+/// not included. Torques are returned in the body frame and no force is produced.
+#[derive(Copy,Clone)]
+pub struct SpinningRotor<T: Float = DefaultFloatRepr> {
+    /// Polar moment of inertia about the spin axis (kg·m<sup>2</sup>)
+    inertia: T,
+    /// Unit spin axis, body frame
+    axis: Vector3<T>,
+    /// Direction of rotation about the axis
+    rotation: Rotation,
+    /// Input channel
+    input: PropellerInput<T>,
+}
+
+impl<T: Float> SpinningRotor<T> {
+    /// Create a new [SpinningRotor]
+    ///
+    /// # Arguments
+    ///
+    /// * `inertia` - Polar moment of inertia about the spin axis (kg·m<sup>2</sup>)
+    /// * `axis` - Spin axis, body frame, normalised on construction
+    /// * `rotation` - Direction of rotation, viewed looking along `axis`
+    /// * `input` - The channel giving the spin speed
+    pub fn new(inertia: T, axis: Vector3<T>, rotation: Rotation, input: PropellerInput<T>) -> Self {
+        SpinningRotor {
+            inertia,
+            axis: axis.normalize(),
+            rotation,
+            input,
+        }
+    }
+
+    /// Return the angular momentum (kg·m<sup>2</sup>·s<sup>-1</sup>) for `inputstate`, body frame
+    pub fn angular_momentum<I: Index<usize,Output=T>>(&self, inputstate: &I) -> Vector3<T> {
+        let spin = self.input.rpm(inputstate) * T::two_pi() / constant::<T>(60.0);
+        self.axis * (self.inertia * spin * self.rotation.sign::<T>())
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for SpinningRotor<T> {
+    fn get_effect(&self, _airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let moment = self.angular_momentum(inputstate).cross(&rates);
+        (Force::body(T::zero(),T::zero(),T::zero()),Torque::body_vec(moment))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_gyroscopic_moment() {
+        let rotor = SpinningRotor::new(0.01,Vector3::new(2.0,0.0,0.0),Rotation::Clockwise,PropellerInput::Rpm(0));
+        let airstate = airstate(0.0,0.0);
+        let rpm = 60.0 / std::f64::consts::TAU * 100.0;
+
+        // Pitching up with a clockwise propeller yaws the nose to the right
+        let (force,torque) = rotor.get_effect(airstate,Vector3::new(0.0,1.0,0.0),&[rpm]);
+        assert_relative_eq!(force.force.norm(),0.0);
+        assert_relative_eq!(torque.torque.z,1.0,max_relative=1e-9);
+        assert_relative_eq!(torque.torque.y,0.0);
+
+        // Rotation about the spin axis produces no moment
+        let (_,torque) = rotor.get_effect(airstate,Vector3::new(1.0,0.0,0.0),&[rpm]);
+        assert_relative_eq!(torque.torque.norm(),0.0);
+    }
+
+    #[test]
+    fn test_counter_rotation() {
+        let clockwise = SpinningRotor::new(0.01,Vector3::x(),Rotation::Clockwise,PropellerInput::Rpm(0));
+        let counter = SpinningRotor::new(0.01,Vector3::x(),Rotation::CounterClockwise,PropellerInput::Rpm(0));
+        let total = clockwise.angular_momentum(&[1000.0]) + counter.angular_momentum(&[1000.0]);
+        assert_relative_eq!(total.norm(),0.0);
+    }
+}
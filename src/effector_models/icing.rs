@@ -0,0 +1,241 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,VehicleGeometry,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque,Frame};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// [AeroEffect] wrapper which accretes ice on a lifting surface and degrades its aerodynamics
+///
+/// In icing conditions ice accretes at the rate
+///
+/// `ṁ = E LWC V A`
+///
+/// for collection efficiency `E`, supercooled liquid water content `LWC`, airspeed `V` and collecting
+/// area `A`, up to a maximum ice mass. The icing severity `η` is the ice mass as a fraction of the
+/// maximum. Out of icing conditions the ice is retained, or sheds with first-order dynamics if a shedding
+/// time constant is set, as for a de-icing boot or a climb into warmer air.
+///
+/// Body-frame forces from the wrapped effector are split into drag along the airspeed and lift normal
+/// to it. The lift is scaled by `1 - k_L η` and the drag by `1 + k_D η`, and the wrapped torque is scaled
+/// with the lift. World-frame forces and torques are passed through unchanged. The ice mass is available
+/// from [Iced::ice_mass] to update the vehicle mass with [Body::set_mass](crate::Body::set_mass).
+///
+/// The liquid water content is either fixed, with [Iced::with_liquid_water_content] and
+/// [Iced::set_liquid_water_content], or read from an inputstate channel (g·m<sup>-3</sup>) so that icing
+/// encounters can be scheduled.
+#[derive(Copy,Clone)]
+pub struct Iced<E, T: Float = DefaultFloatRepr> {
+    /// Wrapped effector
+    effector: E,
+    /// Collecting area (m<sup>2</sup>)
+    collection_area: T,
+    /// Collection efficiency
+    collection_efficiency: T,
+    /// Ice mass at full severity (kg)
+    max_ice_mass: T,
+    /// Fractional lift loss at full severity
+    lift_loss: T,
+    /// Fractional drag increase at full severity
+    drag_increase: T,
+    /// Shedding time constant out of icing conditions (s)
+    shedding_time: Option<T>,
+    /// Fixed liquid water content (kg·m<sup>-3</sup>)
+    liquid_water_content: T,
+    /// Inputstate index of the liquid water content (g·m<sup>-3</sup>)
+    channel: Option<usize>,
+    /// Accreted ice mass (kg)
+    ice_mass: T,
+}
+
+impl<E, T: Float> Iced<E,T> {
+    /// Wrap `effector` with no ice and out of icing conditions
+    ///
+    /// The collection efficiency defaults to 1, and full severity to a 30% lift loss and a doubling of
+    /// drag, typical of a clean wing with a severe glaze ice accretion.
+    ///
+    /// # Arguments
+    ///
+    /// * `effector` - Lifting surface
+    /// * `collection_area` - Collecting area, typically span times leading-edge height (m<sup>2</sup>)
+    /// * `max_ice_mass` - Ice mass at full severity (kg)
+    pub fn new(effector: E, collection_area: T, max_ice_mass: T) -> Self {
+        Iced {
+            effector,
+            collection_area,
+            collection_efficiency: T::one(),
+            max_ice_mass,
+            lift_loss: constant::<T>(0.3),
+            drag_increase: T::one(),
+            shedding_time: None,
+            liquid_water_content: T::zero(),
+            channel: None,
+            ice_mass: T::zero(),
+        }
+    }
+
+    /// Set the collection efficiency
+    pub fn with_collection_efficiency(mut self, collection_efficiency: T) -> Self {
+        self.collection_efficiency = collection_efficiency;
+        self
+    }
+
+    /// Set the fractional lift loss and drag increase at full severity
+    pub fn with_degradation(mut self, lift_loss: T, drag_increase: T) -> Self {
+        self.lift_loss = lift_loss;
+        self.drag_increase = drag_increase;
+        self
+    }
+
+    /// Shed ice with `shedding_time` (s) when out of icing conditions
+    pub fn with_shedding_time(mut self, shedding_time: T) -> Self {
+        self.shedding_time = Some(shedding_time);
+        self
+    }
+
+    /// Set a fixed liquid water content (g·m<sup>-3</sup>)
+    pub fn with_liquid_water_content(mut self, liquid_water_content: T) -> Self {
+        self.set_liquid_water_content(liquid_water_content);
+        self
+    }
+
+    /// Read the liquid water content (g·m<sup>-3</sup>) from the inputstate `channel`
+    pub fn with_conditions_channel(mut self, channel: usize) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Set the fixed liquid water content (g·m<sup>-3</sup>), or zero to leave icing conditions
+    pub fn set_liquid_water_content(&mut self, liquid_water_content: T) {
+        self.liquid_water_content = liquid_water_content * constant::<T>(1e-3);
+    }
+
+    /// Return the wrapped effector
+    pub fn effector(&self) -> &E {
+        &self.effector
+    }
+
+    /// Return the accreted ice mass (kg)
+    pub fn ice_mass(&self) -> T {
+        self.ice_mass
+    }
+
+    /// Return the icing severity, from 0 for clean to 1 at the maximum ice mass
+    pub fn severity(&self) -> T {
+        self.ice_mass / self.max_ice_mass
+    }
+
+    /// Advance the accretion by `delta_t` at `airspeed` (m·s<sup>-1</sup>) and liquid water content
+    /// `liquid_water_content` (kg·m<sup>-3</sup>)
+    fn accrete(&mut self, airspeed: T, liquid_water_content: T, delta_t: T) {
+        if liquid_water_content > T::zero() {
+            let rate = self.collection_efficiency * liquid_water_content * airspeed * self.collection_area;
+            self.ice_mass = <T as num_traits::Float>::min(self.ice_mass + rate * delta_t,self.max_ice_mass);
+        } else if let Some(shedding_time) = self.shedding_time {
+            self.ice_mass *= <T as num_traits::Float>::exp(-delta_t / shedding_time);
+        }
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float, E: AeroEffect<I,T>> AeroEffect<I,T> for Iced<E,T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (force, torque) = self.effector.get_effect(airstate,rates,inputstate);
+        if self.ice_mass <= T::zero() || airstate.airspeed <= T::zero() {
+            return (force,torque);
+        }
+        let severity = self.severity();
+        let lift_scale = T::one() - self.lift_loss * severity;
+        let drag_scale = T::one() + self.drag_increase * severity;
+
+        let force = match force.frame {
+            Frame::Body => {
+                let direction = super::body_airspeed(&airstate) / airstate.airspeed;
+                let along = direction * force.force.dot(&direction);
+                Force::body_vec(along * drag_scale + (force.force - along) * lift_scale)
+            },
+            Frame::World => force,
+        };
+        let torque = match torque.frame {
+            Frame::Body => Torque::body_vec(torque.torque * lift_scale),
+            Frame::World => torque,
+        };
+        (force,torque)
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        let liquid_water_content = match self.channel {
+            Some(channel) => inputstate[channel] * constant::<T>(1e-3),
+            None => self.liquid_water_content,
+        };
+        self.accrete(airstate.airspeed,liquid_water_content,delta_t);
+        self.effector.step(airstate,rates,inputstate,delta_t);
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.effector.set_geometry(geometry);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        EffectorState { parts: vec![self.effector.snapshot()], ..EffectorState::from_values(&[self.ice_mass]) }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(1,0,1)?;
+        self.effector.restore(&state.parts[0])?;
+        self.ice_mass = state.value(0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    /// Wing with a lift of 1000 N and a drag of 100 N
+    struct Wing;
+    impl AeroEffect<[f64;1]> for Wing {
+        fn get_effect(&self, airstate: AirState, _rates: Vector3, _inputstate: &[f64;1]) -> (Force,Torque) {
+            let (x, z) = super::super::stability_to_body(1000.0,100.0,airstate.alpha);
+            (Force::body(x,0.0,z),Torque::body(0.0,-50.0,0.0))
+        }
+    }
+
+    #[test]
+    fn test_accretion() {
+        let mut wing = Iced::new(Wing,0.5,10.0).with_collection_efficiency(0.8).with_conditions_channel(0);
+        wing.step(airstate(0.0,50.0),Vector3::zeros(),&[0.0],10.0);
+        assert_relative_eq!(wing.ice_mass(),0.0);
+
+        // 0.5 g/m³ at 50 m/s onto 0.5 m² with 80% efficiency accretes 10 g/s
+        wing.step(airstate(0.0,50.0),Vector3::zeros(),&[0.5],60.0);
+        assert_relative_eq!(wing.ice_mass(),0.6,max_relative=1e-9);
+        assert_relative_eq!(wing.severity(),0.06,max_relative=1e-9);
+        wing.step(airstate(0.0,50.0),Vector3::zeros(),&[0.5],3600.0);
+        assert_relative_eq!(wing.severity(),1.0);
+
+        // Ice is retained until a shedding time is set
+        wing.step(airstate(0.0,50.0),Vector3::zeros(),&[0.0],100.0);
+        assert_relative_eq!(wing.severity(),1.0);
+        let mut wing = wing.with_shedding_time(20.0);
+        wing.step(airstate(0.0,50.0),Vector3::zeros(),&[0.0],20.0);
+        assert_relative_eq!(wing.severity(),(-1.0f64).exp(),max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_degradation() {
+        let mut wing = Iced::new(Wing,0.5,0.001).with_degradation(0.4,2.0).with_liquid_water_content(1.0);
+        let (clean, _) = wing.get_effect(airstate(0.1,50.0),Vector3::zeros(),&[0.0]);
+        assert_relative_eq!(clean.force.z,Wing.get_effect(airstate(0.1,50.0),Vector3::zeros(),&[0.0]).0.force.z);
+
+        // Half severity loses 20% of lift and doubles drag
+        wing.step(airstate(0.1,50.0),Vector3::zeros(),&[0.0],0.02);
+        assert_relative_eq!(wing.severity(),0.5,max_relative=1e-9);
+        let (force, torque) = wing.get_effect(airstate(0.1,50.0),Vector3::zeros(),&[0.0]);
+        let (x, z) = super::super::stability_to_body(800.0,200.0,0.1);
+        assert_relative_eq!(force.force.x,x,max_relative=1e-9);
+        assert_relative_eq!(force.force.z,z,max_relative=1e-9);
+        assert_relative_eq!(torque.torque.y,-40.0,max_relative=1e-9);
+    }
+}
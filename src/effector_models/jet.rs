@@ -0,0 +1,241 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,Error};
+use crate::snapshot::EffectorState;
+use crate::lookup::Table;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// ISA standard sea-level density (kg·m<sup>-3</sup>)
+const SEA_LEVEL_DENSITY: f64 = 1.225;
+
+/// Variation of available thrust with flight condition for a [JetEngine]
+#[derive(Clone)]
+pub enum ThrustLapse<T: Float = DefaultFloatRepr> {
+    /// `T/T_SL = σ^exponent (1 + mach_factor M)` with `σ` the density ratio
+    Density {
+        /// Density ratio exponent, typically 0.7 for turbojets and 1.0 for high-bypass turbofans
+        exponent: T,
+        /// Linear Mach number factor, negative for turbofans
+        mach_factor: T,
+    },
+    /// `T/T_SL` from a 2D table in Mach number and density ratio
+    Table(Table<T>),
+}
+
+impl<T: Float> ThrustLapse<T> {
+    /// Return the ratio of available thrust to sea-level static thrust
+    pub fn ratio(&self, mach: T, density_ratio: T) -> T {
+        match self {
+            ThrustLapse::Density { exponent, mach_factor } =>
+                <T as num_traits::Float>::powf(density_ratio,*exponent) * <T as num_traits::Float>::max(T::one() + *mach_factor * mach,T::zero()),
+            ThrustLapse::Table(table) => table.lookup(&[mach,density_ratio]),
+        }
+    }
+}
+
+/// Built-in [AeroEffect] for a turbojet or turbofan engine
+///
+/// The throttle (0 to 1) on `channel` commands a thrust fraction between idle and maximum,
+/// `f_cmd = f_idle + (1 - f_idle) throttle`. The delivered fraction follows the command with
+/// first-order spool dynamics, `τ ḟ = f_cmd - f`, using separate time constants for spool-up
+/// and spool-down. Thrust is
+///
+/// `T = T_SL f lapse(M, σ)`
+///
+/// and the fuel flow is `ṁ = TSFC T`. The fuel flow and total fuel burned are exposed so the
+/// vehicle mass can be updated with [Body::set_mass](crate::Body::set_mass).
+///
+/// Thrust acts along body +X at the engine position. Forces and torques are returned in the body frame.
+#[derive(Clone)]
+pub struct JetEngine<T: Float = DefaultFloatRepr> {
+    /// Sea-level static thrust at maximum throttle (N)
+    max_thrust: T,
+    /// Thrust-specific fuel consumption (kg·N<sup>-1</sup>·s<sup>-1</sup>)
+    tsfc: T,
+    /// Inputstate index of the throttle
+    channel: usize,
+    /// Thrust lapse model
+    lapse: ThrustLapse<T>,
+    /// Idle thrust fraction
+    idle: T,
+    /// Spool-up time constant (s)
+    spool_up: T,
+    /// Spool-down time constant (s)
+    spool_down: T,
+    /// Engine position relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Delivered thrust fraction
+    spool: T,
+    /// Fuel flow during the last step (kg·s<sup>-1</sup>)
+    fuel_flow: T,
+    /// Total fuel burned (kg)
+    fuel_burned: T,
+}
+
+impl<T: Float> JetEngine<T> {
+    /// Create a new [JetEngine] at the centre of mass, spooled to idle
+    ///
+    /// The engine defaults to an idle fraction of 0.05 and a density-ratio lapse with exponent 0.7.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_thrust` - Sea-level static thrust at maximum throttle (N)
+    /// * `tsfc` - Thrust-specific fuel consumption (kg·N<sup>-1</sup>·s<sup>-1</sup>)
+    /// * `time_constant` - Spool time constant (s), used for both spool-up and spool-down
+    /// * `channel` - Inputstate index of the throttle (0 to 1)
+    pub fn new(max_thrust: T, tsfc: T, time_constant: T, channel: usize) -> Self {
+        let idle = constant::<T>(0.05);
+        JetEngine {
+            max_thrust,
+            tsfc,
+            channel,
+            lapse: ThrustLapse::Density { exponent: constant::<T>(0.7), mach_factor: T::zero() },
+            idle,
+            spool_up: time_constant,
+            spool_down: time_constant,
+            position: Vector3::zeros(),
+            spool: idle,
+            fuel_flow: T::zero(),
+            fuel_burned: T::zero(),
+        }
+    }
+
+    /// Set the thrust lapse model
+    pub fn with_lapse(mut self, lapse: ThrustLapse<T>) -> Self {
+        if let ThrustLapse::Table(table) = &lapse {
+            if table.dimensions() != 2 {
+                panic!("Thrust lapse table must be 2D in Mach number and density ratio")
+            }
+        }
+        self.lapse = lapse;
+        self
+    }
+
+    /// Set the idle thrust fraction, and spool the engine to idle
+    pub fn with_idle(mut self, idle: T) -> Self {
+        self.idle = idle;
+        self.spool = idle;
+        self
+    }
+
+    /// Set separate spool-up and spool-down time constants (s)
+    pub fn with_spool_time_constants(mut self, spool_up: T, spool_down: T) -> Self {
+        self.spool_up = spool_up;
+        self.spool_down = spool_down;
+        self
+    }
+
+    /// Set the engine position relative to the centre of mass, body frame (m)
+    pub fn with_position(mut self, position: Vector3<T>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Return the delivered thrust fraction
+    pub fn spool(&self) -> T {
+        self.spool
+    }
+
+    /// Set the delivered thrust fraction, for example to start at a trimmed condition
+    pub fn set_spool(&mut self, spool: T) {
+        self.spool = num_traits::clamp(spool,self.idle,T::one());
+    }
+
+    /// Return the fuel flow during the last step (kg·s<sup>-1</sup>)
+    pub fn fuel_flow(&self) -> T {
+        self.fuel_flow
+    }
+
+    /// Return the total fuel burned (kg)
+    pub fn fuel_burned(&self) -> T {
+        self.fuel_burned
+    }
+
+    /// Return the delivered thrust (N) for `airstate`
+    pub fn thrust(&self, airstate: &AirState<T>) -> T {
+        let density_ratio = airstate.density / constant::<T>(SEA_LEVEL_DENSITY);
+        self.max_thrust * self.spool * self.lapse.ratio(airstate.mach,density_ratio)
+    }
+
+    /// Advance the spool dynamics and fuel burn by `delta_t`
+    ///
+    /// # Arguments
+    ///
+    /// * `airstate` - The vehicle airstate
+    /// * `throttle` - Throttle, from 0 to 1
+    /// * `delta_t` - Timestep (s)
+    pub fn update(&mut self, airstate: &AirState<T>, throttle: T, delta_t: T) {
+        self.fuel_flow = self.tsfc * self.thrust(airstate);
+        self.fuel_burned += self.fuel_flow * delta_t;
+
+        let command = self.idle + (T::one() - self.idle) * num_traits::clamp(throttle,T::zero(),T::one());
+        let time_constant = if command > self.spool { self.spool_up } else { self.spool_down };
+        // Exact discretisation of the first-order lag over the step
+        let decay = T::one() - <T as num_traits::Float>::exp(-delta_t / time_constant);
+        self.spool += (command - self.spool) * decay;
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for JetEngine<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let force = Vector3::new(self.thrust(&airstate),T::zero(),T::zero());
+        (Force::body_vec(force),Torque::body_vec(self.position.cross(&force)))
+    }
+
+    fn step(&mut self, airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.update(&airstate,inputstate[self.channel],delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        EffectorState::from_values(&[self.spool,self.fuel_flow,self.fuel_burned])
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(3,0,0)?;
+        self.spool = state.value(0);
+        self.fuel_flow = state.value(1);
+        self.fuel_burned = state.value(2);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_spool_dynamics() {
+        let mut engine = JetEngine::new(10000.0,2e-5,1.0,0).with_idle(0.0);
+        let airstate = AirState { density: 1.225, ..airstate(0.0,340.0 * 0.0) };
+        for _ in 0..100 {
+            engine.update(&airstate,1.0,0.01);
+        }
+        // One time constant reaches 1 - 1/e of the commanded thrust
+        assert_relative_eq!(engine.spool(),1.0 - (-1.0f64).exp(),max_relative=1e-9);
+
+        let (force,_) = engine.get_effect(airstate,Vector3::zeros(),&[1.0]);
+        assert_relative_eq!(force.force.x,10000.0 * engine.spool());
+    }
+
+    #[test]
+    fn test_thrust_lapse() {
+        let mut engine = JetEngine::new(10000.0,2e-5,1.0,0)
+            .with_lapse(ThrustLapse::Density { exponent: 1.0, mach_factor: -0.5 });
+        engine.set_spool(1.0);
+        assert_relative_eq!(engine.thrust(&AirState { density: 0.6125, ..airstate(0.0,340.0 * 0.4) }),10000.0 * 0.5 * 0.8);
+    }
+
+    #[test]
+    fn test_fuel_burn() {
+        let mut engine = JetEngine::new(10000.0,2e-5,1.0,0);
+        engine.set_spool(1.0);
+        for _ in 0..10 {
+            engine.update(&AirState { density: 1.225, ..airstate(0.0,340.0 * 0.0) },1.0,0.1);
+        }
+        assert_relative_eq!(engine.fuel_flow(),0.2);
+        assert_relative_eq!(engine.fuel_burned(),0.2,max_relative=1e-9);
+    }
+}
@@ -0,0 +1,411 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,Error,VehicleGeometry};
+use crate::lookup::Table;
+use crate::inputs::ControlInputs;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr};
+use super::Compressibility;
+
+/// Dimensionless stability and control derivatives for a [LinearAero] model
+///
+/// Rate derivatives are with respect to the non-dimensional rates `p b/2V`, `q c/2V` and `r b/2V`.
+/// Angles and control deflections are in radians. Any derivative left at its default is zero.
+#[derive(Copy,Clone,Default)]
+pub struct LinearDerivatives<T: Float = DefaultFloatRepr> {
+    /// Reference area (m<sup>2</sup>)
+    pub s: T,
+    /// Reference span (m)
+    pub b: T,
+    /// Reference mean chord (m)
+    pub c: T,
+
+    /// Lift coefficient at zero alpha
+    pub c_lift_0: T,
+    /// Lift curve slope
+    pub c_lift_alpha: T,
+    /// Lift due to pitch rate
+    pub c_lift_q: T,
+    /// Lift due to elevator
+    pub c_lift_elevator: T,
+
+    /// Zero-lift drag coefficient
+    pub c_drag_0: T,
+    /// Induced drag factor, `C_D = C_D0 + k C_L^2`
+    pub k: T,
+
+    /// Side force due to sideslip
+    pub c_side_beta: T,
+    /// Side force due to roll rate
+    pub c_side_p: T,
+    /// Side force due to yaw rate
+    pub c_side_r: T,
+    /// Side force due to rudder
+    pub c_side_rudder: T,
+
+    /// Rolling moment due to sideslip (dihedral effect)
+    pub c_roll_beta: T,
+    /// Roll damping
+    pub c_roll_p: T,
+    /// Rolling moment due to yaw rate
+    pub c_roll_r: T,
+    /// Rolling moment due to aileron
+    pub c_roll_aileron: T,
+    /// Rolling moment due to rudder
+    pub c_roll_rudder: T,
+
+    /// Pitching moment coefficient at zero alpha
+    pub c_pitch_0: T,
+    /// Pitching moment due to alpha (static stability)
+    pub c_pitch_alpha: T,
+    /// Pitch damping
+    pub c_pitch_q: T,
+    /// Pitching moment due to elevator
+    pub c_pitch_elevator: T,
+
+    /// Yawing moment due to sideslip (weathercock stability)
+    pub c_yaw_beta: T,
+    /// Yawing moment due to roll rate
+    pub c_yaw_p: T,
+    /// Yaw damping
+    pub c_yaw_r: T,
+    /// Yawing moment due to aileron (adverse yaw)
+    pub c_yaw_aileron: T,
+    /// Yawing moment due to rudder
+    pub c_yaw_rudder: T,
+}
+
+/// Weighted sum of each derivative over `terms`
+macro_rules! blend_derivatives {
+    ($($field:ident),+) => {
+        impl<T: Float> LinearDerivatives<T> {
+            /// Return the sum of each derivative weighted over the non-empty `terms`
+            fn blend(terms: &[(&LinearDerivatives<T>,T)]) -> Self {
+                let mut blended = *terms[0].0;
+                $(blended.$field = T::zero();)+
+                for &(derivatives, weight) in terms {
+                    $(blended.$field += derivatives.$field * weight;)+
+                }
+                blended
+            }
+        }
+    };
+}
+
+blend_derivatives!(s,b,c,
+    c_lift_0,c_lift_alpha,c_lift_q,c_lift_elevator,
+    c_drag_0,k,
+    c_side_beta,c_side_p,c_side_r,c_side_rudder,
+    c_roll_beta,c_roll_p,c_roll_r,c_roll_aileron,c_roll_rudder,
+    c_pitch_0,c_pitch_alpha,c_pitch_q,c_pitch_elevator,
+    c_yaw_beta,c_yaw_p,c_yaw_r,c_yaw_aileron,c_yaw_rudder);
+
+/// [LinearDerivatives] scheduled over Mach number and angle of attack
+///
+/// The derivatives are interpolated bilinearly between the grid points and held at the nearest edge
+/// outside the grid, so one [LinearAero] can cover the full speed range of a faster vehicle.
+#[derive(Clone)]
+pub struct DerivativeSchedule<T: Float = DefaultFloatRepr> {
+    /// Grid of Mach and alpha breakpoints used for the interpolation weights
+    grid: Table<T>,
+    /// Derivatives at each grid point, in row-major order with alpha varying fastest
+    points: Vec<LinearDerivatives<T>>,
+}
+
+impl<T: Float> DerivativeSchedule<T> {
+    /// Create a new [DerivativeSchedule] with `points[i][j]` at `(mach[i],alpha[j])`
+    ///
+    /// # Arguments
+    ///
+    /// * `mach` - Strictly increasing Mach breakpoints
+    /// * `alpha` - Strictly increasing angle of attack breakpoints (rad)
+    /// * `points` - Derivatives at each grid point
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grid is empty, the breakpoints are not strictly increasing or the points do not match
+    /// the grid.
+    pub fn new(mach: Vec<T>, alpha: Vec<T>, points: Vec<Vec<LinearDerivatives<T>>>) -> Self {
+        if points.is_empty() || alpha.is_empty() {
+            panic!("Derivative schedule must have at least one Mach and one alpha breakpoint");
+        }
+        if points.len() != mach.len() || points.iter().any(|row| row.len() != alpha.len()) {
+            panic!("Derivative schedule must have one row per Mach breakpoint and one point per alpha breakpoint");
+        }
+        let grid = Table::new(vec![mach,alpha],vec![T::zero();points.len() * points[0].len()]);
+        DerivativeSchedule { grid, points: points.into_iter().flatten().collect() }
+    }
+
+    /// Create a new [DerivativeSchedule], returning [Error::InvalidParameter] rather than panicking if the
+    /// grid is empty, the breakpoints are not strictly increasing or the points do not match the grid
+    pub fn try_new(mach: Vec<T>, alpha: Vec<T>, points: Vec<Vec<LinearDerivatives<T>>>) -> Result<Self,Error> {
+        let increasing = |axis: &[T]| !axis.is_empty() && axis.windows(2).all(|pair| pair[1] > pair[0]);
+        if !increasing(&mach) {
+            return Err(Error::InvalidParameter("mach"));
+        }
+        if !increasing(&alpha) {
+            return Err(Error::InvalidParameter("alpha"));
+        }
+        if points.len() != mach.len() || points.iter().any(|row| row.len() != alpha.len()) {
+            return Err(Error::InvalidParameter("points"));
+        }
+        Ok(DerivativeSchedule::new(mach,alpha,points))
+    }
+
+    /// Return the derivatives at `mach` and `alpha` (rad)
+    pub fn derivatives(&self, mach: T, alpha: T) -> LinearDerivatives<T> {
+        let terms: Vec<(&LinearDerivatives<T>,T)> = self.grid.weights(&[mach,alpha]).into_iter()
+            .map(|(idx, weight)| (&self.points[idx],weight))
+            .collect();
+        LinearDerivatives::blend(&terms)
+    }
+}
+
+/// Built-in 6-DoF [AeroEffect] for a classic linear stability-derivative aircraft model
+///
+/// Control deflections are read from the inputstate as `[aileron, elevator, rudder]` by default, matching
+/// [AircraftControls](crate::inputs::AircraftControls), which can be changed with [LinearAero::with_channels]
+/// or [LinearAero::with_named_channels].
+/// Forces and torques are returned in the body frame.
+///
+/// An optional [Compressibility] correction is applied to the lift, side force and moment coefficients.
+///
+/// The derivatives may instead be scheduled over Mach number and angle of attack with
+/// [LinearAero::scheduled], in which case each coefficient is built from the derivatives interpolated at the
+/// current Mach number and angle of attack.
+///
+/// ```
+/// # use aerso::effector_models::{LinearAero,LinearDerivatives};
+/// let aero = LinearAero::new(LinearDerivatives {
+///     s: 0.5, b: 1.6, c: 0.3,
+///     c_lift_0: 0.2, c_lift_alpha: 4.5,
+///     c_drag_0: 0.03, k: 0.07,
+///     c_pitch_alpha: -0.6, c_pitch_q: -12.0, c_pitch_elevator: -1.0,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone)]
+pub struct LinearAero<T: Float = DefaultFloatRepr> {
+    /// Model derivatives
+    derivatives: LinearDerivatives<T>,
+    /// Optional schedule of the derivatives over Mach and alpha, replacing the constant derivatives
+    schedule: Option<DerivativeSchedule<T>>,
+    /// Inputstate indices for `[aileron, elevator, rudder]`
+    channels: [usize;3],
+    /// Compressibility correction
+    compressibility: Compressibility,
+}
+
+impl<T: Float> LinearAero<T> {
+    /// Create a new [LinearAero] from `derivatives`
+    pub fn new(derivatives: LinearDerivatives<T>) -> Self {
+        LinearAero {
+            derivatives,
+            schedule: None,
+            channels: [0,1,2],
+            compressibility: Compressibility::None,
+        }
+    }
+
+    /// Create a new [LinearAero] with derivatives following `schedule`
+    ///
+    /// [LinearAero::derivatives] returns the derivatives at the first grid point.
+    pub fn scheduled(schedule: DerivativeSchedule<T>) -> Self {
+        LinearAero {
+            derivatives: schedule.points[0],
+            schedule: Some(schedule),
+            channels: [0,1,2],
+            compressibility: Compressibility::None,
+        }
+    }
+
+    /// Set the compressibility correction
+    pub fn with_compressibility(mut self, compressibility: Compressibility) -> Self {
+        self.compressibility = compressibility;
+        self
+    }
+
+    /// Set the inputstate indices used for the control deflections
+    ///
+    /// # Arguments
+    ///
+    /// * `aileron` - Index of the aileron deflection (rad)
+    /// * `elevator` - Index of the elevator deflection (rad)
+    /// * `rudder` - Index of the rudder deflection (rad)
+    pub fn with_channels(mut self, aileron: usize, elevator: usize, rudder: usize) -> Self {
+        self.channels = [aileron,elevator,rudder];
+        self
+    }
+
+    /// Read the control deflections from the channels of `controls` called `aileron`, `elevator` and `rudder`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `controls` is missing any of these channels.
+    pub fn with_named_channels<C: ControlInputs<T>>(self, controls: &C) -> Self {
+        let index = |name: &str| controls.channel_index(name).unwrap_or_else(|| panic!("Control inputs have no channel called {}",name));
+        self.with_channels(index("aileron"),index("elevator"),index("rudder"))
+    }
+
+    /// Return the model derivatives
+    pub fn derivatives(&self) -> &LinearDerivatives<T> {
+        &self.derivatives
+    }
+
+    /// Return the derivatives in effect at `mach` and `alpha` (rad)
+    pub fn derivatives_at(&self, mach: T, alpha: T) -> LinearDerivatives<T> {
+        match &self.schedule {
+            Some(schedule) => schedule.derivatives(mach,alpha),
+            None => self.derivatives,
+        }
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for LinearAero<T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let d = &self.derivatives_at(airstate.mach,airstate.alpha);
+
+        let aileron = inputstate[self.channels[0]];
+        let elevator = inputstate[self.channels[1]];
+        let rudder = inputstate[self.channels[2]];
+
+        let rates_hat = super::nondimensional_rates(&rates,airstate.airspeed,d.b,d.c);
+        let (p_hat, q_hat, r_hat) = (rates_hat[0],rates_hat[1],rates_hat[2]);
+
+        let alpha = airstate.alpha;
+        let beta = airstate.beta;
+
+        let correct = |coefficient: T| self.compressibility.correct(coefficient,airstate.mach);
+
+        let c_lift = correct(d.c_lift_0 + d.c_lift_alpha * alpha + d.c_lift_q * q_hat + d.c_lift_elevator * elevator);
+        let c_drag = d.c_drag_0 + d.k * <T as num_traits::Float>::powi(c_lift,2);
+        let c_side = correct(d.c_side_beta * beta + d.c_side_p * p_hat + d.c_side_r * r_hat + d.c_side_rudder * rudder);
+
+        let c_roll = correct(d.c_roll_beta * beta + d.c_roll_p * p_hat + d.c_roll_r * r_hat + d.c_roll_aileron * aileron + d.c_roll_rudder * rudder);
+        let c_pitch = correct(d.c_pitch_0 + d.c_pitch_alpha * alpha + d.c_pitch_q * q_hat + d.c_pitch_elevator * elevator);
+        let c_yaw = correct(d.c_yaw_beta * beta + d.c_yaw_p * p_hat + d.c_yaw_r * r_hat + d.c_yaw_aileron * aileron + d.c_yaw_rudder * rudder);
+
+        let (c_x, c_z) = super::stability_to_body(c_lift,c_drag,alpha);
+
+        let q_s = airstate.q * d.s;
+        (
+            Force::body(q_s * c_x, q_s * c_side, q_s * c_z),
+            Torque::body(q_s * d.b * c_roll, q_s * d.c * c_pitch, q_s * d.b * c_yaw)
+        )
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        for d in std::iter::once(&mut self.derivatives).chain(self.schedule.iter_mut().flat_map(|schedule| schedule.points.iter_mut())) {
+            d.s = geometry.area;
+            d.b = geometry.span;
+            d.c = geometry.chord;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_level() {
+        let aero = LinearAero::new(LinearDerivatives {
+            s: 2.0, b: 4.0, c: 0.5,
+            c_lift_0: 0.5, c_drag_0: 0.1, k: 0.2,
+            c_pitch_0: 0.05,
+            ..Default::default()
+        });
+
+        let airstate = AirState { q: 100.0, ..airstate(0.0,20.0) };
+        let (force,torque) = aero.get_effect(airstate,Vector3::zeros(),&[0.0,0.0,0.0]);
+
+        assert_relative_eq!(force.force.x,-200.0 * (0.1 + 0.2 * 0.25));
+        assert_relative_eq!(force.force.y,0.0);
+        assert_relative_eq!(force.force.z,-200.0 * 0.5);
+        assert_relative_eq!(torque.torque.y,200.0 * 0.5 * 0.05);
+    }
+
+    #[test]
+    fn test_damping_and_controls() {
+        let aero = LinearAero::new(LinearDerivatives {
+            s: 2.0, b: 4.0, c: 0.5,
+            c_roll_p: -0.4, c_roll_aileron: 0.2,
+            c_yaw_rudder: -0.1,
+            ..Default::default()
+        }).with_channels(2,1,0);
+
+        let airstate = AirState { q: 100.0, ..airstate(0.0,20.0) };
+        let (_,torque) = aero.get_effect(airstate,Vector3::new(1.0,0.0,0.0),&[0.3,0.0,0.1]);
+
+        let p_hat = 1.0 * 4.0 / (2.0 * 20.0);
+        assert_relative_eq!(torque.torque.x,200.0 * 4.0 * (-0.4 * p_hat + 0.2 * 0.1));
+        assert_relative_eq!(torque.torque.z,200.0 * 4.0 * (-0.1 * 0.3));
+    }
+
+    #[test]
+    fn test_named_channels() {
+        crate::control_inputs! {
+            struct Reversed: f64 {
+                rudder: [-0.5, 0.5] = 0.0,
+                elevator: [-0.5, 0.5] = 0.0,
+                aileron: [-0.5, 0.5] = 0.0,
+            }
+        }
+        let aero = LinearAero::new(LinearDerivatives {
+            s: 2.0, b: 4.0, c: 0.5,
+            c_yaw_rudder: -0.1,
+            ..Default::default()
+        }).with_named_channels(&Reversed::neutral());
+
+        let airstate = AirState { q: 100.0, ..airstate(0.0,20.0) };
+        let (_,torque) = aero.get_effect(airstate,Vector3::zeros(),&Reversed { rudder: 0.3, ..Reversed::neutral() });
+        assert_relative_eq!(torque.torque.z,200.0 * 4.0 * (-0.1 * 0.3));
+    }
+
+    #[test]
+    fn test_mach_alpha_schedule() {
+        let point = |c_lift_alpha: f64, c_pitch_alpha: f64| LinearDerivatives {
+            s: 2.0, b: 4.0, c: 0.5,
+            c_lift_alpha, c_pitch_alpha,
+            ..Default::default()
+        };
+        // The lift slope falls above Mach 1, and the pitch stiffness grows with alpha
+        let schedule = DerivativeSchedule::new(vec![0.5,1.5],vec![0.0,0.2],vec![
+            vec![point(5.0,-0.5),point(5.0,-1.0)],
+            vec![point(3.0,-0.5),point(3.0,-1.0)],
+        ]);
+        let aero = LinearAero::scheduled(schedule);
+        assert_relative_eq!(aero.derivatives().c_lift_alpha,5.0);
+
+        let middle = aero.derivatives_at(1.0,0.1);
+        assert_relative_eq!(middle.c_lift_alpha,4.0,max_relative=1e-12);
+        assert_relative_eq!(middle.c_pitch_alpha,-0.75,max_relative=1e-12);
+        assert_relative_eq!(middle.s,2.0,max_relative=1e-12);
+        // Held outside the grid
+        assert_relative_eq!(aero.derivatives_at(3.0,-0.1).c_lift_alpha,3.0,max_relative=1e-12);
+
+        let alpha = 0.1;
+        let airstate = AirState { q: 100.0, ..airstate(alpha,340.0) };
+        let (force,torque) = aero.get_effect(airstate,Vector3::zeros(),&[0.0,0.0,0.0]);
+        let c_lift = 4.0 * alpha;
+        assert_relative_eq!(force.force.z,-200.0 * c_lift * alpha.cos(),max_relative=1e-9);
+        assert_relative_eq!(torque.torque.y,200.0 * 0.5 * -0.75 * alpha,max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_invalid_schedule() {
+        let point = LinearDerivatives::<f64>::default();
+        assert!(matches!(DerivativeSchedule::<f64>::try_new(vec![],vec![],vec![]),Err(Error::InvalidParameter("mach"))));
+        assert!(matches!(DerivativeSchedule::try_new(vec![0.5],vec![0.2,0.0],vec![vec![point,point]]),Err(Error::InvalidParameter("alpha"))));
+        assert!(matches!(DerivativeSchedule::try_new(vec![0.5,1.5],vec![0.0],vec![vec![point]]),Err(Error::InvalidParameter("points"))));
+        assert!(DerivativeSchedule::try_new(vec![0.5],vec![0.0],vec![vec![point]]).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one Mach and one alpha breakpoint")]
+    fn test_empty_schedule() {
+        DerivativeSchedule::<f64>::new(vec![],vec![],vec![]);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::types::{Vector3,Float,constant};
+
+mod compressibility;
+mod table;
+mod linear;
+mod stall;
+mod dynamic_stall;
+mod control_surface;
+mod tail;
+mod actuator;
+mod propeller;
+mod bemt;
+mod electric;
+mod jet;
+mod rocket;
+mod gimbal;
+mod rcs;
+mod multirotor;
+mod gyroscopic;
+mod parachute;
+mod momentum_drag;
+mod propwash;
+mod icing;
+mod damping;
+mod ducted_fan;
+mod transition;
+mod configuration;
+mod spin;
+
+pub use compressibility::Compressibility;
+pub use table::{TableAero,TableInput,Coefficient};
+pub use linear::{LinearAero,LinearDerivatives,DerivativeSchedule};
+pub use stall::StallAero;
+pub use dynamic_stall::DynamicStall;
+pub use control_surface::{ControlSurface,SurfaceOrientation};
+pub use tail::{HorizontalTail,DownwashLag};
+pub use actuator::{Actuator,ActuatorDynamics,Actuated};
+pub use propeller::{Propeller,PropellerInput,Rotation};
+pub use bemt::{BladeElementRotor,BladeElement,Airfoil,RotorSolution};
+pub use electric::{ElectricPowertrain,Motor,Battery};
+pub use jet::{JetEngine,ThrustLapse};
+pub use rocket::{RocketMotor,ThrustCurve};
+pub use gimbal::Gimballed;
+pub use rcs::Thruster;
+pub use multirotor::MultirotorRotor;
+pub use gyroscopic::SpinningRotor;
+pub use parachute::Parachute;
+pub use momentum_drag::MomentumDrag;
+pub use propwash::{Propwash,Slipstream};
+pub use icing::Iced;
+pub use damping::Damping;
+pub use ducted_fan::DuctedFan;
+pub use transition::Tilting;
+pub use configuration::{Configuration,ConfigurationState,ConfigurationIncrement,ConfigurationMonitor,Configured};
+pub use spin::SpinAero;
+
+/// Rotate stability-axis lift and drag coefficients into body-axis X and Z coefficients
+fn stability_to_body<T: Float>(c_lift: T, c_drag: T, alpha: T) -> (T,T) {
+    let body = crate::frames::stability_to_body(alpha) * Vector3::new(-c_drag,T::zero(),-c_lift);
+    (body[0],body[2])
+}
+
+/// Non-dimensionalise body rates as `[p b/2V, q c/2V, r b/2V]`
+/// 
+/// Returns zero rates when `airspeed` is zero
+fn nondimensional_rates<T: Float>(rates: &Vector3<T>, airspeed: T, b: T, c: T) -> Vector3<T> {
+    if airspeed == T::zero() {
+        return Vector3::zeros();
+    }
+    let scale = constant::<T>(0.5) / airspeed;
+    Vector3::new(
+        rates[0] * b * scale,
+        rates[1] * c * scale,
+        rates[2] * b * scale)
+}
+
+/// Reconstruct the body-frame air-relative velocity `[u,v,w]` from an airstate
+fn body_airspeed<T: Float>(airstate: &crate::AirState<T>) -> Vector3<T> {
+    crate::frames::wind_to_body(airstate.alpha,airstate.beta) * Vector3::x() * airstate.airspeed
+}
@@ -0,0 +1,94 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState};
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr};
+
+use super::MultirotorRotor;
+
+/// Built-in [AeroEffect] for the momentum (ram) drag of a rotor
+///
+/// Air passing through the rotor disk has its in-plane velocity relative to the vehicle removed, giving
+/// a drag force proportional to the mass flow and the in-plane relative wind,
+///
+/// `F = -ṁ V_e`, `ṁ = ρ A √((V_c + v_i)² + V_e²)`
+///
+/// with the induced velocity `v_i` solved by the associated [MultirotorRotor]. This dominates the
+/// translational damping of hovering multirotors. The force acts at the rotor hub and forces and torques
+/// are returned in the body frame. The rotor thrust itself is not included, so this effector is used
+/// alongside the rotor.
+#[derive(Copy,Clone)]
+pub struct MomentumDrag<T: Float = DefaultFloatRepr> {
+    /// Rotor providing the mass flow
+    rotor: MultirotorRotor<T>,
+}
+
+impl<T: Float> MomentumDrag<T> {
+    /// Create a new [MomentumDrag] for `rotor`
+    pub fn new(rotor: MultirotorRotor<T>) -> Self {
+        MomentumDrag { rotor }
+    }
+
+    /// Return the mass flow through the rotor (kg·s<sup>-1</sup>)
+    ///
+    /// # Arguments
+    ///
+    /// * `omega` - Shaft speed (rad·s<sup>-1</sup>)
+    /// * `axial_velocity` - Velocity through the disk, positive in climb (m·s<sup>-1</sup>)
+    /// * `edgewise_velocity` - Magnitude of the in-plane velocity (m·s<sup>-1</sup>)
+    /// * `density` - Air density (kg·m<sup>-3</sup>)
+    pub fn mass_flow(&self, omega: T, axial_velocity: T, edgewise_velocity: T, density: T) -> T {
+        let (_, v_i) = self.rotor.thrust(omega,axial_velocity,edgewise_velocity,density);
+        let through = axial_velocity + v_i;
+        let area = T::pi() * self.rotor.radius() * self.rotor.radius();
+        density * area * <T as num_traits::Float>::sqrt(through * through + edgewise_velocity * edgewise_velocity)
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for MomentumDrag<T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (axial_velocity, edgewise) = self.rotor.local_flow(&airstate,&rates);
+        let omega = self.rotor.omega(inputstate);
+        let force = if omega > T::zero() {
+            -edgewise * self.mass_flow(omega,axial_velocity,edgewise.norm(),airstate.density)
+        } else {
+            Vector3::zeros()
+        };
+        (Force::body_vec(force),Torque::body_vec(self.rotor.position().cross(&force)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::effector_models::{PropellerInput,Rotation};
+    use approx::assert_relative_eq;
+
+    fn drag() -> MomentumDrag {
+        MomentumDrag::new(MultirotorRotor::new(0.12,1.2e-5,1.5e-7,Vector3::zeros(),Rotation::Clockwise,PropellerInput::Rpm(0)))
+    }
+
+    #[test]
+    fn test_hover_mass_flow() {
+        let drag = drag();
+        let omega = 500.0;
+        let area = std::f64::consts::PI * 0.12 * 0.12;
+        // At hover the mass flow is ρ A v_h with T = 2 ρ A v_h²
+        let v_hover = (1.2e-5 * omega * omega / (2.0 * 1.225 * area)).sqrt();
+        assert_relative_eq!(drag.mass_flow(omega,0.0,0.0,1.225),1.225 * area * v_hover,max_relative=1e-6);
+    }
+
+    #[test]
+    fn test_opposes_relative_wind() {
+        let drag = drag();
+        let airstate = AirState { beta: 0.3, q: 0.0, ..airstate(0.0,2.0) };
+        let (force,_) = drag.get_effect(airstate,Vector3::zeros(),&[5000.0]);
+        assert!(force.force.x < 0.0);
+        assert!(force.force.y < 0.0);
+        assert_relative_eq!(force.force.z,0.0);
+
+        let (force,_) = drag.get_effect(airstate,Vector3::zeros(),&[0.0]);
+        assert_relative_eq!(force.force.norm(),0.0);
+    }
+}
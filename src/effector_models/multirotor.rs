@@ -0,0 +1,352 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::{PropellerInput,Rotation};
+
+/// Built-in [AeroEffect] for a multirotor rotor
+///
+/// The rotor thrusts along body -Z. At hover the thrust and shaft torque are `k_T ω²` and `k_Q ω²`.
+/// Away from hover the thrust is corrected for the inflow through the disk: the induced velocity `v_i`
+/// is solved so that the linear-inflow blade-element thrust
+///
+/// `T = k_T ω² - ρ A ω R (σa/4) (V_c + v_i - v_h)`
+///
+/// matches the momentum theory thrust `T = 2 ρ A v_i √((V_c + v_i)² + V_e²)`, with `V_c` the axial
+/// velocity through the disk (positive in climb), `V_e` the edgewise velocity, `v_h` the hover
+/// induced velocity at the same shaft speed and `σa` the product of rotor solidity and blade lift slope.
+///
+/// Blade flapping in edgewise flight tilts the thrust vector, giving an in-plane hub force
+/// `-k_H ω V_e` opposing the edgewise velocity. Local velocities include the rotational velocity of
+/// the rotor hub `ω_body × position`.
+///
+/// For helicopter and fast multirotor fidelity, [MultirotorRotor::with_flapping] adds first-order
+/// tip-path-plane flapping dynamics. The tilt of the tip-path plane relative to the shaft is an auxiliary
+/// state which relaxes with the flapping time constant `τ = 16 / (γ ω)`, for Lock number `γ`, towards
+///
+/// `β_ss = -A μ + τ (q, -p)`
+///
+/// with `μ` the in-plane advance ratio vector `V_e / (ω R)` and `A` the blowback gain. The thrust acts
+/// normal to the tip-path plane, and the hub stiffness `K_β` adds the hub moment `K_β (β_y, -β_x)`, so the
+/// rotor flaps back and pitches the vehicle nose-up with forward speed and damps the body rates. The flapping
+/// is advanced by [AeroEffect::step] when used in an [AffectedBody](crate::AffectedBody), or manually using
+/// [MultirotorRotor::update]. The lateral flapping due to coning is not modelled.
+///
+/// The [Rotation] is viewed from above, so a [Rotation::Clockwise] rotor produces a negative (nose-left)
+/// yaw reaction torque. Forces and torques are returned in the body frame.
+#[derive(Copy,Clone)]
+pub struct MultirotorRotor<T: Float = DefaultFloatRepr> {
+    /// Rotor radius (m)
+    radius: T,
+    /// Hover thrust coefficient (N·s<sup>2</sup>·rad<sup>-2</sup>)
+    k_thrust: T,
+    /// Hover torque coefficient (N·m·s<sup>2</sup>·rad<sup>-2</sup>)
+    k_torque: T,
+    /// Product of solidity and blade lift curve slope (rad<sup>-1</sup>)
+    solidity_lift_slope: T,
+    /// Flapping hub force coefficient (N·s<sup>2</sup>·m<sup>-1</sup>·rad<sup>-1</sup>)
+    k_hub: T,
+    /// Input channel
+    input: PropellerInput<T>,
+    /// Rotor hub position relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Direction of rotation, viewed from above
+    rotation: Rotation,
+    /// Tip-path-plane flapping dynamics, if modelled
+    flapping: Option<Flapping<T>>,
+}
+
+/// Tip-path-plane flapping parameters and state of a [MultirotorRotor]
+#[derive(Copy,Clone)]
+struct Flapping<T: Float> {
+    /// Blade Lock number
+    lock_number: T,
+    /// Steady flapping per unit advance ratio (rad)
+    blowback_gain: T,
+    /// Hub flapping stiffness (N·m·rad<sup>-1</sup>)
+    hub_stiffness: T,
+    /// Forward and rightward tilt of the tip-path plane relative to the shaft (rad)
+    tilt: (T,T),
+}
+
+impl<T: Float> MultirotorRotor<T> {
+    /// Number of bisection iterations used to solve the inflow
+    const INFLOW_ITERATIONS: usize = 50;
+
+    /// Create a new [MultirotorRotor] with no hub force
+    ///
+    /// The product of solidity and lift slope defaults to 0.5.
+    ///
+    /// # Arguments
+    ///
+    /// * `radius` - Rotor radius (m)
+    /// * `k_thrust` - Hover thrust coefficient (N·s<sup>2</sup>·rad<sup>-2</sup>)
+    /// * `k_torque` - Hover torque coefficient (N·m·s<sup>2</sup>·rad<sup>-2</sup>)
+    /// * `position` - Rotor hub position relative to centre of mass, body frame (m)
+    /// * `rotation` - Direction of rotation, viewed from above
+    /// * `input` - The channel driving the rotor
+    pub fn new(radius: T, k_thrust: T, k_torque: T, position: Vector3<T>, rotation: Rotation, input: PropellerInput<T>) -> Self {
+        MultirotorRotor {
+            radius,
+            k_thrust,
+            k_torque,
+            solidity_lift_slope: constant::<T>(0.5),
+            k_hub: T::zero(),
+            input,
+            position,
+            rotation,
+            flapping: None,
+        }
+    }
+
+    /// Add first-order tip-path-plane flapping dynamics, starting with the tip-path plane normal to the shaft
+    ///
+    /// # Arguments
+    ///
+    /// * `lock_number` - Blade Lock number `γ`, the ratio of aerodynamic to inertial flapping forces, typically 4–10
+    /// * `blowback_gain` - Steady flapping per unit advance ratio `A` (rad), typically 2–4 times the collective pitch
+    /// * `hub_stiffness` - Hub flapping stiffness `K_β` (N·m·rad<sup>-1</sup>), zero for a central flapping hinge
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Lock number is not positive.
+    pub fn with_flapping(mut self, lock_number: T, blowback_gain: T, hub_stiffness: T) -> Self {
+        if lock_number <= T::zero() {
+            panic!("Lock number must be positive");
+        }
+        self.flapping = Some(Flapping { lock_number, blowback_gain, hub_stiffness, tilt: (T::zero(),T::zero()) });
+        self
+    }
+
+    /// Return the forward and rightward tilt of the tip-path plane relative to the shaft (rad), if flapping is modelled
+    pub fn flapping(&self) -> Option<(T,T)> {
+        self.flapping.map(|flapping| flapping.tilt)
+    }
+
+    /// Advance the flapping by `delta_t` for the current `airstate`, body `rates` and `inputstate`
+    ///
+    /// The tip-path plane returns normal to the shaft when the rotor is stopped.
+    pub fn update<I: Index<usize,Output=T>>(&mut self, airstate: &AirState<T>, rates: &Vector3<T>, inputstate: &I, delta_t: T) {
+        let omega = self.omega(inputstate);
+        let (_, edgewise) = self.local_flow(airstate,rates);
+        let radius = self.radius;
+        if let Some(flapping) = self.flapping.as_mut() {
+            if omega <= T::zero() {
+                flapping.tilt = (T::zero(),T::zero());
+                return;
+            }
+            let tau = constant::<T>(16.0) / (flapping.lock_number * omega);
+            let advance = edgewise / (omega * radius);
+            // The tip-path plane flaps back from the relative wind and lags the rotation of the shaft
+            let target_x = -flapping.blowback_gain * advance[0] + tau * rates[1];
+            let target_y = -flapping.blowback_gain * advance[1] - tau * rates[0];
+            // Exact discretisation of the first-order lag for a constant target over the step
+            let decay = <T as num_traits::Float>::exp(-delta_t / tau);
+            flapping.tilt = (target_x + (flapping.tilt.0 - target_x) * decay,target_y + (flapping.tilt.1 - target_y) * decay);
+        }
+    }
+
+    /// Set the product of rotor solidity and blade lift curve slope (rad<sup>-1</sup>)
+    pub fn with_solidity_lift_slope(mut self, solidity_lift_slope: T) -> Self {
+        self.solidity_lift_slope = solidity_lift_slope;
+        self
+    }
+
+    /// Set the flapping hub force coefficient (N·s<sup>2</sup>·m<sup>-1</sup>·rad<sup>-1</sup>)
+    pub fn with_hub_force(mut self, k_hub: T) -> Self {
+        self.k_hub = k_hub;
+        self
+    }
+
+    /// Return the shaft speed (rev·min<sup>-1</sup>) commanded by `inputstate`
+    pub fn rpm<I: Index<usize,Output=T>>(&self, inputstate: &I) -> T {
+        self.input.rpm(inputstate)
+    }
+
+    /// Return the rotor radius (m)
+    pub fn radius(&self) -> T {
+        self.radius
+    }
+
+    /// Return the rotor hub position relative to the centre of mass, body frame (m)
+    pub fn position(&self) -> Vector3<T> {
+        self.position
+    }
+
+    /// Return the shaft speed (rad·s<sup>-1</sup>) commanded by `inputstate`
+    pub(crate) fn omega<I: Index<usize,Output=T>>(&self, inputstate: &I) -> T {
+        self.rpm(inputstate) * T::two_pi() / constant::<T>(60.0)
+    }
+
+    /// Return the axial velocity through the disk and the in-plane velocity vector of the hub relative to the air
+    pub(crate) fn local_flow(&self, airstate: &AirState<T>, rates: &Vector3<T>) -> (T,Vector3<T>) {
+        // Hub velocity relative to the air, body frame
+        let velocity = super::body_airspeed(airstate) + rates.cross(&self.position);
+        // Climbing along the thrust axis (-Z) is flow down through the disk
+        (-velocity[2],Vector3::new(velocity[0],velocity[1],T::zero()))
+    }
+
+    /// Return `(thrust, induced_velocity)` (N, m·s<sup>-1</sup>) for shaft speed `omega` (rad·s<sup>-1</sup>)
+    ///
+    /// # Arguments
+    ///
+    /// * `omega` - Shaft speed (rad·s<sup>-1</sup>)
+    /// * `axial_velocity` - Velocity through the disk, positive in climb (m·s<sup>-1</sup>)
+    /// * `edgewise_velocity` - Magnitude of the in-plane velocity (m·s<sup>-1</sup>)
+    /// * `density` - Air density (kg·m<sup>-3</sup>)
+    pub fn thrust(&self, omega: T, axial_velocity: T, edgewise_velocity: T, density: T) -> (T,T) {
+        if omega <= T::zero() || density <= T::zero() {
+            return (T::zero(),T::zero());
+        }
+        let two = constant::<T>(2.0);
+        let area = T::pi() * self.radius * self.radius;
+        let hover_thrust = self.k_thrust * omega * omega;
+        let v_hover = <T as num_traits::Float>::sqrt(hover_thrust / (two * density * area));
+        let inflow_gain = density * area * omega * self.radius * self.solidity_lift_slope / constant::<T>(4.0);
+
+        let blade_thrust = |v_i: T| hover_thrust - inflow_gain * (axial_velocity + v_i - v_hover);
+        // Residual between blade-element and momentum thrust, decreasing in v_i
+        let residual = |v_i: T| {
+            let through = axial_velocity + v_i;
+            blade_thrust(v_i) - two * density * area * v_i * <T as num_traits::Float>::sqrt(through * through + edgewise_velocity * edgewise_velocity)
+        };
+
+        let mut lower = T::zero();
+        let mut upper = <T as num_traits::Float>::max(v_hover + hover_thrust / inflow_gain - axial_velocity,T::zero());
+        if residual(lower) <= T::zero() {
+            return (<T as num_traits::Float>::max(blade_thrust(T::zero()),T::zero()),T::zero());
+        }
+        for _ in 0..Self::INFLOW_ITERATIONS {
+            let mid = (lower + upper) / two;
+            if residual(mid) > T::zero() { lower = mid } else { upper = mid }
+        }
+        let v_i = (lower + upper) / two;
+        (<T as num_traits::Float>::max(blade_thrust(v_i),T::zero()),v_i)
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for MultirotorRotor<T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let omega = self.omega(inputstate);
+        let (axial_velocity, edgewise) = self.local_flow(&airstate,&rates);
+
+        let (thrust, _) = self.thrust(omega,axial_velocity,edgewise.norm(),airstate.density);
+        let hub_force = -edgewise * self.k_hub * omega;
+        let (tilt, hub_moment) = match self.flapping {
+            Some(flapping) => (flapping.tilt,Vector3::new(flapping.tilt.1,-flapping.tilt.0,T::zero()) * flapping.hub_stiffness),
+            None => ((T::zero(),T::zero()),Vector3::zeros()),
+        };
+        let force = Vector3::new(tilt.0,tilt.1,-T::one()).normalize() * thrust + hub_force;
+
+        let shaft_torque = self.k_torque * omega * omega;
+        let reaction = Vector3::new(T::zero(),T::zero(),-self.rotation.sign::<T>() * shaft_torque);
+
+        (Force::body_vec(force),Torque::body_vec(self.position.cross(&force) + reaction + hub_moment))
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.update(&airstate,&rates,inputstate,delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        match self.flapping {
+            Some(flapping) => EffectorState::from_values(&[flapping.tilt.0,flapping.tilt.1]),
+            None => EffectorState::default(),
+        }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        match self.flapping.as_mut() {
+            Some(flapping) => {
+                state.check(2,0,0)?;
+                flapping.tilt = (state.value(0),state.value(1));
+                Ok(())
+            },
+            None => state.check(0,0,0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    fn rotor(rotation: Rotation) -> MultirotorRotor {
+        MultirotorRotor::new(0.12,1.2e-5,1.5e-7,Vector3::new(0.2,0.2,0.0),rotation,PropellerInput::Rpm(0))
+            .with_hub_force(1e-4)
+    }
+
+    #[test]
+    fn test_hover() {
+        let rotor = rotor(Rotation::Clockwise);
+        let rpm = 6000.0;
+        let omega = rpm * std::f64::consts::PI / 30.0;
+        let (force,torque) = rotor.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[rpm]);
+        assert_relative_eq!(force.force.z,-1.2e-5 * omega * omega,max_relative=1e-9);
+        // Clockwise from above yaws the airframe to the left
+        assert_relative_eq!(torque.torque.z,-1.5e-7 * omega * omega,max_relative=1e-9);
+
+        let (_,torque) = MultirotorRotor::new(0.12,1.2e-5,1.5e-7,Vector3::zeros(),Rotation::CounterClockwise,PropellerInput::Rpm(0))
+            .get_effect(airstate(0.0,0.0),Vector3::zeros(),&[rpm]);
+        assert!(torque.torque.z > 0.0);
+    }
+
+    #[test]
+    fn test_inflow_correction() {
+        let rotor = rotor(Rotation::Clockwise);
+        let omega = 600.0;
+        let (hover,v_hover) = rotor.thrust(omega,0.0,0.0,1.225);
+        let (climb,_) = rotor.thrust(omega,3.0,0.0,1.225);
+        let (descent,_) = rotor.thrust(omega,-1.0,0.0,1.225);
+        assert!(climb < hover);
+        assert!(descent > hover);
+        assert!(v_hover > 0.0);
+
+        // Edgewise flow reduces induced velocity
+        let (_,v_edgewise) = rotor.thrust(omega,0.0,10.0,1.225);
+        assert!(v_edgewise < v_hover);
+    }
+
+    #[test]
+    fn test_hub_force() {
+        let rotor = rotor(Rotation::Clockwise);
+        let (force,_) = rotor.get_effect(airstate(0.0,5.0),Vector3::zeros(),&[6000.0]);
+        assert!(force.force.x < 0.0);
+        assert_relative_eq!(force.force.y,0.0);
+    }
+
+    #[test]
+    fn test_flapping() {
+        let mut rotor = MultirotorRotor::new(0.12,1.2e-5,1.5e-7,Vector3::zeros(),Rotation::Clockwise,PropellerInput::Rpm(0))
+            .with_flapping(6.0,0.3,0.5);
+        let rpm = [6000.0];
+        let omega = 6000.0 * std::f64::consts::PI / 30.0;
+        let tau = 16.0 / (6.0 * omega);
+        assert_eq!(rotor.flapping(),Some((0.0,0.0)));
+
+        // In forward flight the tip-path plane flaps back, tilting the thrust aft and pitching nose-up
+        (0..100).for_each(|_| rotor.update(&airstate(0.0,10.0),&Vector3::zeros(),&rpm,0.01));
+        let (tilt_x, tilt_y) = rotor.flapping().unwrap();
+        assert_relative_eq!(tilt_x,-0.3 * 10.0 / (omega * 0.12),max_relative=1e-9);
+        assert_relative_eq!(tilt_y,0.0);
+        let (force,torque) = rotor.get_effect(airstate(0.0,10.0),Vector3::zeros(),&rpm);
+        assert!(force.force.x < 0.0);
+        assert_relative_eq!(torque.torque.y,-0.5 * tilt_x,max_relative=1e-9);
+
+        // From hover, a pitch rate lags the disk with the flapping time constant, damping the rate
+        let mut rotor = MultirotorRotor::new(0.12,1.2e-5,1.5e-7,Vector3::zeros(),Rotation::Clockwise,PropellerInput::Rpm(0))
+            .with_flapping(6.0,0.3,0.5);
+        let rates = Vector3::new(0.0,1.0,0.0);
+        rotor.update(&airstate(0.0,0.0),&rates,&rpm,tau);
+        assert_relative_eq!(rotor.flapping().unwrap().0,tau * (1.0 - (-1.0_f64).exp()),max_relative=1e-9);
+        let (_,torque) = rotor.get_effect(airstate(0.0,0.0),rates,&rpm);
+        assert!(torque.torque.y < 0.0);
+        rotor.update(&airstate(0.0,0.0),&rates,&[0.0],0.01);
+        assert_eq!(rotor.flapping(),Some((0.0,0.0)));
+    }
+}
@@ -0,0 +1,150 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Built-in [AeroEffect] for a parachute
+///
+/// Once deployed, the canopy inflates with first-order dynamics, `τ ḟ = 1 - f`, from a fraction `f`
+/// of zero. The drag
+///
+/// `D = f q (C_D S)`
+///
+/// acts opposite the airspeed vector at the riser attachment point, so an attachment aft of the centre
+/// of mass produces a stabilising moment. The parachute deploys when the command on the trigger channel
+/// exceeds 0.5, or on a call to [Parachute::deploy]. Forces and torques are returned in the body frame.
+#[derive(Copy,Clone)]
+pub struct Parachute<T: Float = DefaultFloatRepr> {
+    /// Fully-inflated drag area (m<sup>2</sup>)
+    drag_area: T,
+    /// Inflation time constant (s)
+    inflation_time: T,
+    /// Riser attachment point relative to centre of mass, body frame (m)
+    attachment: Vector3<T>,
+    /// Inputstate index of the deployment trigger
+    channel: Option<usize>,
+    /// Whether the parachute has been deployed
+    deployed: bool,
+    /// Inflated fraction of the drag area
+    inflation: T,
+}
+
+impl<T: Float> Parachute<T> {
+    /// Create a new stowed [Parachute] attached at the centre of mass
+    ///
+    /// # Arguments
+    ///
+    /// * `drag_area` - Fully-inflated drag area, `C_D S` (m<sup>2</sup>)
+    /// * `inflation_time` - Inflation time constant (s)
+    pub fn new(drag_area: T, inflation_time: T) -> Self {
+        Parachute {
+            drag_area,
+            inflation_time,
+            attachment: Vector3::zeros(),
+            channel: None,
+            deployed: false,
+            inflation: T::zero(),
+        }
+    }
+
+    /// Set the riser attachment point relative to the centre of mass, body frame (m)
+    pub fn with_attachment(mut self, attachment: Vector3<T>) -> Self {
+        self.attachment = attachment;
+        self
+    }
+
+    /// Deploy the parachute when the inputstate `channel` exceeds 0.5
+    pub fn with_trigger_channel(mut self, channel: usize) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Deploy the parachute
+    pub fn deploy(&mut self) {
+        self.deployed = true;
+    }
+
+    /// Return whether the parachute has been deployed
+    pub fn deployed(&self) -> bool {
+        self.deployed
+    }
+
+    /// Return the inflated fraction of the drag area
+    pub fn inflation(&self) -> T {
+        self.inflation
+    }
+
+    /// Advance the inflation by `delta_t`
+    pub fn update(&mut self, delta_t: T) {
+        if self.deployed {
+            let decay = T::one() - <T as num_traits::Float>::exp(-delta_t / self.inflation_time);
+            self.inflation += (T::one() - self.inflation) * decay;
+        }
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for Parachute<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        if airstate.airspeed <= T::zero() {
+            return (Force::body(T::zero(),T::zero(),T::zero()),Torque::body(T::zero(),T::zero(),T::zero()));
+        }
+        let direction = super::body_airspeed(&airstate) / airstate.airspeed;
+        let force = -direction * (self.inflation * airstate.q * self.drag_area);
+        (Force::body_vec(force),Torque::body_vec(self.attachment.cross(&force)))
+    }
+
+    fn step(&mut self, _airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        if let Some(channel) = self.channel {
+            if inputstate[channel] > constant::<T>(0.5) {
+                self.deploy();
+            }
+        }
+        self.update(delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        EffectorState { words: vec![self.deployed as u64], ..EffectorState::from_values(&[self.inflation]) }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(1,1,0)?;
+        self.inflation = state.value(0);
+        self.deployed = state.words[0] != 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_inflation() {
+        let mut parachute = Parachute::new(2.0,0.5).with_trigger_channel(0);
+        parachute.step(airstate(0.0,20.0),Vector3::zeros(),&[0.0],0.5);
+        assert!(!parachute.deployed());
+        assert_relative_eq!(parachute.inflation(),0.0);
+
+        for _ in 0..50 {
+            parachute.step(airstate(0.0,20.0),Vector3::zeros(),&[1.0],0.01);
+        }
+        assert_relative_eq!(parachute.inflation(),1.0 - (-1.0f64).exp(),max_relative=1e-9);
+
+        let (force,_) = parachute.get_effect(airstate(0.0,20.0),Vector3::zeros(),&[1.0]);
+        assert_relative_eq!(force.force.x,-parachute.inflation() * 245.0 * 2.0);
+    }
+
+    #[test]
+    fn test_stabilising_moment() {
+        let mut parachute = Parachute::new(2.0,0.5).with_attachment(Vector3::new(-1.0,0.0,0.0));
+        parachute.deploy();
+        parachute.update(10.0);
+        // Nose-up angle of attack gives a nose-down moment
+        let (_,torque) = parachute.get_effect(airstate(0.2,20.0),Vector3::zeros(),&[0.0]);
+        assert!(torque.torque.y < 0.0);
+    }
+}
@@ -0,0 +1,277 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState};
+use crate::lookup::Table;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Direction of rotation of a propeller or rotor, viewed from behind looking forward along the thrust axis
+#[derive(Copy,Clone)]
+pub enum Rotation {
+    /// Clockwise from behind (right-hand tractor propeller)
+    Clockwise,
+    /// Counter-clockwise from behind
+    CounterClockwise,
+}
+
+impl Rotation {
+    /// Return `1` for clockwise and `-1` for counter-clockwise rotation
+    ///
+    /// Clockwise rotation viewed from behind is a positive rotation about the thrust axis.
+    pub fn sign<T: Float>(&self) -> T {
+        match self {
+            Rotation::Clockwise => T::one(),
+            Rotation::CounterClockwise => -T::one(),
+        }
+    }
+}
+
+/// Inputstate channel which drives a [Propeller]
+#[derive(Copy,Clone)]
+pub enum PropellerInput<T: Float = DefaultFloatRepr> {
+    /// Shaft speed (rev·min<sup>-1</sup>) read directly from the channel
+    Rpm(usize),
+    /// Throttle, from 0 to 1, read from the channel and scaled linearly up to `max_rpm`
+    Throttle {
+        /// Inputstate index
+        channel: usize,
+        /// Shaft speed at full throttle (rev·min<sup>-1</sup>)
+        max_rpm: T,
+    },
+}
+
+impl<T: Float> PropellerInput<T> {
+    /// Return the shaft speed (rev·min<sup>-1</sup>) commanded by `inputstate`
+    pub fn rpm<I: Index<usize,Output=T>>(&self, inputstate: &I) -> T {
+        match *self {
+            PropellerInput::Rpm(channel) => inputstate[channel],
+            PropellerInput::Throttle { channel, max_rpm } => num_traits::clamp(inputstate[channel],T::zero(),T::one()) * max_rpm,
+        }
+    }
+}
+
+/// Built-in [AeroEffect] for a fixed-pitch propeller
+///
+/// Thrust and shaft torque are calculated from thrust and power coefficient curves as a function of the
+/// advance ratio `J = V / (n D)`:
+///
+/// `T = C_T(J) ρ n² D⁴`, `Q = C_P(J) ρ n² D⁵ / 2π`
+///
+/// with `n` the shaft speed in rev·s<sup>-1</sup>, `V` the axial airspeed and `ρ` the local air density.
+/// Thrust acts along body +X at the propeller position; the reaction torque opposes the rotation.
+/// Forces and torques are returned in the body frame.
+///
+/// Two optional asymmetric effects are available. With [Propeller::with_p_factor], inflow across the
+/// disk loads the advancing blade more heavily, offsetting the thrust line from the hub by
+/// `e = k_p R (-s x̂ × u_disk) / V` with `u_disk` the in-plane body airspeed components and `s` the
+/// rotation sign, producing yaw at angle of attack and pitch in sideslip. With [Propeller::with_swirl],
+/// the rotating slipstream acting on the tail and wing produces yaw and roll moments proportional to
+/// the shaft torque, in the same sense as the reaction torque.
+#[derive(Clone)]
+pub struct Propeller<T: Float = DefaultFloatRepr> {
+    /// Diameter (m)
+    diameter: T,
+    /// Thrust coefficient against advance ratio
+    c_thrust: Table<T>,
+    /// Power coefficient against advance ratio
+    c_power: Table<T>,
+    /// Input channel
+    input: PropellerInput<T>,
+    /// Propeller hub position relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Direction of rotation
+    rotation: Rotation,
+    /// Thrust line offset per radian of disk inflow angle, as a fraction of the radius
+    p_factor: T,
+    /// Slipstream swirl yawing moment as a fraction of shaft torque
+    swirl_yaw: T,
+    /// Slipstream swirl rolling moment as a fraction of shaft torque
+    swirl_roll: T,
+}
+
+impl<T: Float> Propeller<T> {
+    /// Create a new clockwise [Propeller] at the centre of mass
+    ///
+    /// # Arguments
+    ///
+    /// * `diameter` - Propeller diameter (m)
+    /// * `c_thrust` - 1D table of thrust coefficient against advance ratio
+    /// * `c_power` - 1D table of power coefficient against advance ratio
+    /// * `input` - The channel driving the propeller
+    pub fn new(diameter: T, c_thrust: Table<T>, c_power: Table<T>, input: PropellerInput<T>) -> Self {
+        if c_thrust.dimensions() != 1 || c_power.dimensions() != 1 {
+            panic!("Propeller coefficient tables must be 1D in advance ratio")
+        }
+        Propeller {
+            diameter,
+            c_thrust,
+            c_power,
+            input,
+            position: Vector3::zeros(),
+            rotation: Rotation::Clockwise,
+            p_factor: T::zero(),
+            swirl_yaw: T::zero(),
+            swirl_roll: T::zero(),
+        }
+    }
+
+    /// Set the propeller hub position relative to the centre of mass, body frame (m)
+    pub fn with_position(mut self, position: Vector3<T>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Set the direction of rotation
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Set the P-factor thrust line offset per radian of disk inflow angle, as a fraction of the radius
+    pub fn with_p_factor(mut self, p_factor: T) -> Self {
+        self.p_factor = p_factor;
+        self
+    }
+
+    /// Set the slipstream swirl moments as fractions of shaft torque
+    ///
+    /// # Arguments
+    ///
+    /// * `yaw` - Yawing moment from swirl over the vertical tail, as a fraction of shaft torque
+    /// * `roll` - Rolling moment from swirl over the wing, as a fraction of shaft torque
+    pub fn with_swirl(mut self, yaw: T, roll: T) -> Self {
+        self.swirl_yaw = yaw;
+        self.swirl_roll = roll;
+        self
+    }
+
+    /// Return the propeller diameter (m)
+    pub fn diameter(&self) -> T {
+        self.diameter
+    }
+
+    /// Return the shaft speed (rev·min<sup>-1</sup>) commanded by `inputstate`
+    pub fn rpm<I: Index<usize,Output=T>>(&self, inputstate: &I) -> T {
+        self.input.rpm(inputstate)
+    }
+
+    /// Return the advance ratio for axial airspeed `v` (m·s<sup>-1</sup>) and shaft speed `rpm`
+    pub fn advance_ratio(&self, v: T, rpm: T) -> T {
+        let n = rpm / constant::<T>(60.0);
+        if n <= T::zero() {
+            return T::zero();
+        }
+        v / (n * self.diameter)
+    }
+
+    /// Return the `(thrust, torque)` (N, N·m) for axial airspeed `v`, `density` and shaft speed `rpm`
+    ///
+    /// The torque is the magnitude of the shaft torque absorbed by the propeller.
+    pub fn thrust_torque(&self, v: T, density: T, rpm: T) -> (T,T) {
+        let n = rpm / constant::<T>(60.0);
+        if n <= T::zero() {
+            return (T::zero(),T::zero());
+        }
+        let j = self.advance_ratio(v,rpm);
+        let n2 = n * n;
+        let d4 = <T as num_traits::Float>::powi(self.diameter,4);
+        let thrust = self.c_thrust.lookup(&[j]) * density * n2 * d4;
+        let torque = self.c_power.lookup(&[j]) * density * n2 * d4 * self.diameter / T::two_pi();
+        (thrust,torque)
+    }
+
+    /// Return the body-frame force and torque at shaft speed `rpm`, ignoring the input channel
+    pub(crate) fn effect_at_rpm(&self, airstate: &AirState<T>, rpm: T) -> (Force<T>,Torque<T>) {
+        let airstate_velocity = super::body_airspeed(airstate);
+        let (thrust, shaft_torque) = self.thrust_torque(airstate_velocity[0],airstate.density,rpm);
+
+        let sign = self.rotation.sign::<T>();
+        let force = Vector3::new(thrust,T::zero(),T::zero());
+        let reaction = Vector3::new(-sign * shaft_torque,T::zero(),T::zero());
+
+        // P-factor offsets the thrust line towards the blade moving into the in-plane flow
+        let offset = if airstate.airspeed > T::zero() {
+            let in_plane = Vector3::new(T::zero(),airstate_velocity[1],airstate_velocity[2]) / airstate.airspeed;
+            -Vector3::x().cross(&in_plane) * (sign * self.p_factor * self.diameter / constant::<T>(2.0))
+        } else {
+            Vector3::zeros()
+        };
+        let swirl = Vector3::new(self.swirl_roll,T::zero(),self.swirl_yaw) * (-sign * shaft_torque);
+
+        (Force::body_vec(force),Torque::body_vec((self.position + offset).cross(&force) + reaction + swirl))
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for Propeller<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        self.effect_at_rpm(&airstate,self.rpm(inputstate))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    fn propeller() -> Propeller {
+        Propeller::new(
+            0.25,
+            Table::new_1d(vec![0.0,0.8],vec![0.1,0.0]),
+            Table::new_1d(vec![0.0,0.8],vec![0.05,0.02]),
+            PropellerInput::Rpm(0))
+    }
+
+    #[test]
+    fn test_static_thrust() {
+        let prop = propeller();
+        let (force,torque) = prop.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[6000.0]);
+        let expected_thrust = 0.1 * 1.225 * 100.0f64.powi(2) * 0.25f64.powi(4);
+        let expected_torque = 0.05 * 1.225 * 100.0f64.powi(2) * 0.25f64.powi(5) / (2.0 * std::f64::consts::PI);
+        assert_relative_eq!(force.force.x,expected_thrust);
+        // Clockwise propeller rolls the airframe to the left
+        assert_relative_eq!(torque.torque.x,-expected_torque);
+    }
+
+    #[test]
+    fn test_advance_ratio() {
+        let prop = propeller().with_rotation(Rotation::CounterClockwise);
+        // J = 0.4 is halfway along the tables
+        let (force,torque) = prop.get_effect(airstate(0.0,10.0),Vector3::zeros(),&[6000.0]);
+        assert_relative_eq!(force.force.x,0.05 * 1.225 * 100.0f64.powi(2) * 0.25f64.powi(4));
+        assert!(torque.torque.x > 0.0);
+
+        let (force,torque) = prop.get_effect(airstate(0.0,10.0),Vector3::zeros(),&[0.0]);
+        assert_relative_eq!(force.force.x,0.0);
+        assert_relative_eq!(torque.torque.x,0.0);
+    }
+
+    #[test]
+    fn test_throttle() {
+        let prop = Propeller::new(
+            0.25,
+            Table::new_1d(vec![0.0],vec![0.1]),
+            Table::new_1d(vec![0.0],vec![0.05]),
+            PropellerInput::Throttle { channel: 1, max_rpm: 8000.0 });
+        assert_relative_eq!(prop.rpm(&[0.0,0.5]),4000.0);
+        assert_relative_eq!(prop.rpm(&[0.0,1.5]),8000.0);
+    }
+
+    #[test]
+    fn test_p_factor_and_swirl() {
+        let prop = propeller().with_p_factor(0.5);
+        let mut state = airstate(0.0,10.0);
+        state.alpha = 0.2;
+        // Clockwise propeller at positive angle of attack yaws the nose to the left
+        let (force,torque) = prop.get_effect(state,Vector3::zeros(),&[6000.0]);
+        let offset = 0.5 * 0.125 * (0.2f64).sin();
+        assert_relative_eq!(torque.torque.z,-offset * force.force.x,max_relative=1e-9);
+        assert_relative_eq!(torque.torque.y,0.0);
+
+        let prop = propeller().with_swirl(0.2,0.1);
+        let (_,torque) = prop.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[6000.0]);
+        let (_,shaft_torque) = prop.thrust_torque(0.0,1.225,6000.0);
+        assert_relative_eq!(torque.torque.z,-0.2 * shaft_torque);
+        assert_relative_eq!(torque.torque.x,-1.1 * shaft_torque);
+    }
+}
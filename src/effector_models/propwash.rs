@@ -0,0 +1,214 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,VehicleGeometry,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::{Propeller,BladeElementRotor,ElectricPowertrain};
+
+/// Trait for propulsion effectors which produce a slipstream
+pub trait Slipstream<I, T: Float = DefaultFloatRepr> {
+    /// Return the fully-developed slipstream velocity increment (m·s<sup>-1</sup>) behind the propulsor
+    ///
+    /// The slipstream flows aft along body -X relative to the free stream.
+    fn slipstream_velocity(&self, airstate: &AirState<T>, inputstate: &I) -> T;
+}
+
+/// Return the far-wake velocity increment from actuator disk theory
+///
+/// Solves `T = 2 ρ A v_i (V + v_i)` for the induced velocity and returns `2 v_i`.
+fn momentum_slipstream<T: Float>(thrust: T, axial_velocity: T, density: T, diameter: T) -> T {
+    if thrust <= T::zero() || density <= T::zero() {
+        return T::zero();
+    }
+    let area = T::pi() * diameter * diameter / constant::<T>(4.0);
+    let axial_velocity = <T as num_traits::Float>::max(axial_velocity,T::zero());
+    let induced = (-axial_velocity + <T as num_traits::Float>::sqrt(axial_velocity * axial_velocity + constant::<T>(2.0) * thrust / (density * area))) / constant::<T>(2.0);
+    constant::<T>(2.0) * induced
+}
+
+impl<I: Index<usize,Output=T>, T: Float> Slipstream<I,T> for Propeller<T> {
+    fn slipstream_velocity(&self, airstate: &AirState<T>, inputstate: &I) -> T {
+        let axial_velocity = super::body_airspeed(airstate)[0];
+        let (thrust, _) = self.thrust_torque(axial_velocity,airstate.density,self.rpm(inputstate));
+        momentum_slipstream(thrust,axial_velocity,airstate.density,self.diameter())
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> Slipstream<I,T> for BladeElementRotor<T> {
+    fn slipstream_velocity(&self, airstate: &AirState<T>, inputstate: &I) -> T {
+        let axial_velocity = super::body_airspeed(airstate)[0];
+        let solution = self.solve(axial_velocity,airstate.density,self.rpm(inputstate));
+        constant::<T>(2.0) * solution.mean_induced_velocity
+    }
+}
+
+impl<I, T: Float> Slipstream<I,T> for ElectricPowertrain<T> {
+    fn slipstream_velocity(&self, airstate: &AirState<T>, _inputstate: &I) -> T {
+        let propeller = self.propeller();
+        let axial_velocity = super::body_airspeed(airstate)[0];
+        let (thrust, _) = propeller.thrust_torque(axial_velocity,airstate.density,self.rpm());
+        momentum_slipstream(thrust,axial_velocity,airstate.density,propeller.diameter())
+    }
+}
+
+/// Return `airstate` with the body-frame air velocity increased by `increment` along body X
+fn augmented_airstate<T: Float>(airstate: &AirState<T>, increment: T) -> AirState<T> {
+    let velocity = super::body_airspeed(airstate) + Vector3::new(increment,T::zero(),T::zero());
+    let airspeed = velocity.norm();
+    if airspeed <= T::zero() {
+        return *airstate;
+    }
+    let ratio = airspeed / <T as num_traits::Float>::max(airstate.airspeed,T::min_positive_value());
+    AirState {
+        alpha: <T as num_traits::Float>::atan2(velocity[2],velocity[0]),
+        beta: <T as num_traits::Float>::asin(velocity[1] / airspeed),
+        airspeed,
+        q: constant::<T>(0.5) * airstate.density * airspeed * airspeed,
+        mach: if airstate.airspeed > T::zero() { airstate.mach * ratio } else { airstate.mach },
+        density: airstate.density,
+    }
+}
+
+/// [AeroEffect] combinator which immerses a downstream effector in the slipstream of a propulsor
+///
+/// The downstream effector, such as a tail or flap, is evaluated both in the free stream and in the
+/// slipstream, where the local body-frame air velocity is increased along body X by
+/// `wake_factor × ΔV` with `ΔV` the [Slipstream] velocity of the propulsor. The two are blended by the
+/// immersed fraction `η`:
+///
+/// `F = (1 - η) F_free + η F_slipstream`
+///
+/// The `wake_factor` is 1 for a surface in the fully-developed slipstream and 0.5 at the propeller disk.
+/// The returned effect is the sum of the propulsor and downstream effects.
+#[derive(Copy,Clone)]
+pub struct Propwash<P, E, T: Float = DefaultFloatRepr> {
+    /// Propulsion effector producing the slipstream
+    propulsor: P,
+    /// Downstream effector
+    downstream: E,
+    /// Fraction of the downstream effector immersed in the slipstream
+    immersion: T,
+    /// Ratio of the local velocity increment to the fully-developed slipstream velocity
+    wake_factor: T,
+}
+
+impl<P, E, T: Float> Propwash<P,E,T> {
+    /// Immerse `downstream` fully in the fully-developed slipstream of `propulsor`
+    pub fn new(propulsor: P, downstream: E) -> Self {
+        Propwash {
+            propulsor,
+            downstream,
+            immersion: T::one(),
+            wake_factor: T::one(),
+        }
+    }
+
+    /// Set the fraction of the downstream effector immersed in the slipstream
+    pub fn with_immersion(mut self, immersion: T) -> Self {
+        self.immersion = immersion;
+        self
+    }
+
+    /// Set the ratio of the local velocity increment to the fully-developed slipstream velocity
+    pub fn with_wake_factor(mut self, wake_factor: T) -> Self {
+        self.wake_factor = wake_factor;
+        self
+    }
+
+    /// Return the propulsion effector
+    pub fn propulsor(&self) -> &P {
+        &self.propulsor
+    }
+
+    /// Return the downstream effector
+    pub fn downstream(&self) -> &E {
+        &self.downstream
+    }
+
+    /// Return the airstate seen by the immersed part of the downstream effector
+    pub fn slipstream_airstate<I>(&self, airstate: &AirState<T>, inputstate: &I) -> AirState<T> where P: Slipstream<I,T> {
+        augmented_airstate(airstate,self.wake_factor * self.propulsor.slipstream_velocity(airstate,inputstate))
+    }
+}
+
+impl<I, T: Float, P: AeroEffect<I,T> + Slipstream<I,T>, E: AeroEffect<I,T>> AeroEffect<I,T> for Propwash<P,E,T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (propulsor_force, propulsor_torque) = self.propulsor.get_effect(airstate,rates,inputstate);
+        let (free_force, free_torque) = self.downstream.get_effect(airstate,rates,inputstate);
+        let (wash_force, wash_torque) = self.downstream.get_effect(self.slipstream_airstate(&airstate,inputstate),rates,inputstate);
+
+        let blend = |free: Vector3<T>, wash: Vector3<T>| free * (T::one() - self.immersion) + wash * self.immersion;
+        let force = propulsor_force.force + blend(free_force.force,wash_force.force);
+        let torque = propulsor_torque.torque + blend(free_torque.torque,wash_torque.torque);
+        (Force::body_vec(force),Torque::body_vec(torque))
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        let slipstream = self.slipstream_airstate(&airstate,inputstate);
+        self.propulsor.step(airstate,rates,inputstate,delta_t);
+        self.downstream.step(slipstream,rates,inputstate,delta_t);
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.propulsor.set_geometry(geometry);
+        self.downstream.set_geometry(geometry);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        EffectorState { parts: vec![self.propulsor.snapshot(),self.downstream.snapshot()], ..EffectorState::default() }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(0,0,2)?;
+        self.propulsor.restore(&state.parts[0])?;
+        self.downstream.restore(&state.parts[1])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::lookup::Table;
+    use crate::effector_models::{PropellerInput,ControlSurface,SurfaceOrientation};
+    use approx::assert_relative_eq;
+
+    fn propeller() -> Propeller {
+        Propeller::new(
+            0.5,
+            Table::new_1d(vec![0.0,1.0],vec![0.1,0.0]),
+            Table::new_1d(vec![0.0,1.0],vec![0.05,0.02]),
+            PropellerInput::Rpm(0))
+    }
+
+    #[test]
+    fn test_slipstream_velocity() {
+        let prop = propeller();
+        let (thrust, _) = prop.thrust_torque(0.0,1.225,6000.0);
+        let area = std::f64::consts::PI * 0.25 * 0.25;
+        // Static far-wake velocity is 2 v_h with T = 2 ρ A v_h²
+        let expected = 2.0 * (thrust / (2.0 * 1.225 * area)).sqrt();
+        assert_relative_eq!(prop.slipstream_velocity(&airstate(0.0,0.0),&[6000.0]),expected,max_relative=1e-9);
+        assert_relative_eq!(prop.slipstream_velocity(&airstate(0.0,10.0),&[0.0]),0.0);
+    }
+
+    #[test]
+    fn test_tail_authority() {
+        let elevator = ControlSurface::new(SurfaceOrientation::Horizontal,0.2,0.1,Vector3::new(-1.0,0.0,0.0),1,0.5,0.0);
+        let washed = Propwash::new(propeller(),elevator).with_immersion(0.5);
+
+        let input = [6000.0,0.1];
+        let (free,_) = elevator.get_effect(airstate(0.0,10.0),Vector3::zeros(),&input);
+        let (combined,_) = washed.get_effect(airstate(0.0,10.0),Vector3::zeros(),&input);
+        let (thrust,_) = washed.propulsor().get_effect(airstate(0.0,10.0),Vector3::zeros(),&input);
+
+        // Half the elevator sees the higher slipstream dynamic pressure
+        let slipstream = washed.slipstream_airstate(&airstate(0.0,10.0),&input);
+        assert!(slipstream.airspeed > 10.0);
+        let expected_lift = 0.5 * free.force.z * (1.0 + slipstream.q / airstate(0.0,10.0).q);
+        assert_relative_eq!(combined.force.z,thrust.force.z + expected_lift,max_relative=1e-9);
+        assert!(combined.force.z < free.force.z);
+    }
+}
@@ -0,0 +1,168 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Standard gravity used to convert specific impulse to exhaust velocity (m·s<sup>-2</sup>)
+const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// Built-in [AeroEffect] for an on-off reaction control thruster
+///
+/// The thruster fires at full thrust while the command on `channel` exceeds 0.5. Once fired it stays
+/// on for at least the minimum on-time, so each firing delivers at least the minimum impulse bit.
+/// The firing state is advanced by [AeroEffect::step], so a command seen during a step takes
+/// effect from the next step.
+///
+/// Thrust acts along `direction` at `position`, generating moments `position × force`, and is
+/// independent of the airstate. Forces and torques are returned in the body frame.
+#[derive(Copy,Clone)]
+pub struct Thruster<T: Float = DefaultFloatRepr> {
+    /// Thruster position relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Unit thrust direction, body frame
+    direction: Vector3<T>,
+    /// Thrust when firing (N)
+    thrust: T,
+    /// Inputstate index of the firing command
+    channel: usize,
+    /// Minimum on-time (s)
+    minimum_on_time: T,
+    /// Specific impulse (s), if propellant use is tracked
+    isp: Option<T>,
+    /// Whether the thruster is firing
+    firing: bool,
+    /// Time since the current firing started (s)
+    on_time: T,
+    /// Total impulse delivered (N·s)
+    impulse: T,
+}
+
+impl<T: Float> Thruster<T> {
+    /// Create a new [Thruster] with no minimum impulse bit
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Thruster position relative to centre of mass, body frame (m)
+    /// * `direction` - Thrust direction, body frame, normalised on construction
+    /// * `thrust` - Thrust when firing (N)
+    /// * `channel` - Inputstate index of the firing command
+    pub fn new(position: Vector3<T>, direction: Vector3<T>, thrust: T, channel: usize) -> Self {
+        Thruster {
+            position,
+            direction: direction.normalize(),
+            thrust,
+            channel,
+            minimum_on_time: T::zero(),
+            isp: None,
+            firing: false,
+            on_time: T::zero(),
+            impulse: T::zero(),
+        }
+    }
+
+    /// Set the minimum impulse bit (N·s)
+    pub fn with_minimum_impulse(mut self, impulse: T) -> Self {
+        self.minimum_on_time = impulse / self.thrust;
+        self
+    }
+
+    /// Set the specific impulse (s) used to track propellant consumption
+    pub fn with_isp(mut self, isp: T) -> Self {
+        self.isp = Some(isp);
+        self
+    }
+
+    /// Return whether the thruster is firing
+    pub fn firing(&self) -> bool {
+        self.firing
+    }
+
+    /// Return the total impulse delivered (N·s)
+    pub fn impulse(&self) -> T {
+        self.impulse
+    }
+
+    /// Return the propellant consumed (kg), if a specific impulse is set
+    pub fn propellant_used(&self) -> Option<T> {
+        self.isp.map(|isp| self.impulse / (isp * constant::<T>(STANDARD_GRAVITY)))
+    }
+
+    /// Advance the firing state by `delta_t` using the firing `command`
+    pub fn update(&mut self, command: T, delta_t: T) {
+        if self.firing {
+            self.impulse += self.thrust * delta_t;
+            self.on_time += delta_t;
+        }
+        let commanded = command > constant::<T>(0.5);
+        if commanded && !self.firing {
+            self.firing = true;
+            self.on_time = T::zero();
+        } else if !commanded && self.firing && self.on_time >= self.minimum_on_time {
+            self.firing = false;
+        }
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for Thruster<T> {
+    fn get_effect(&self, _airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let force = if self.firing { self.direction * self.thrust } else { Vector3::zeros() };
+        (Force::body_vec(force),Torque::body_vec(self.position.cross(&force)))
+    }
+
+    fn step(&mut self, _airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.update(inputstate[self.channel],delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        EffectorState { words: vec![self.firing as u64], ..EffectorState::from_values(&[self.on_time,self.impulse]) }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(2,1,0)?;
+        self.on_time = state.value(0);
+        self.impulse = state.value(1);
+        self.firing = state.words[0] != 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_on_off() {
+        let mut thruster = Thruster::new(Vector3::new(0.0,1.0,0.0),Vector3::new(0.0,0.0,-1.0),10.0,0);
+        let (force,_) = thruster.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[1.0]);
+        assert_relative_eq!(force.force.z,0.0);
+
+        thruster.update(1.0,0.1);
+        let (force,torque) = thruster.get_effect(airstate(0.0,0.0),Vector3::zeros(),&[1.0]);
+        assert_relative_eq!(force.force.z,-10.0);
+        // Upward thrust on the right wingtip rolls left
+        assert_relative_eq!(torque.torque.x,-10.0);
+
+        thruster.update(0.0,0.1);
+        assert!(!thruster.firing());
+        assert_relative_eq!(thruster.impulse(),1.0);
+    }
+
+    #[test]
+    fn test_minimum_impulse_bit() {
+        let mut thruster = Thruster::new(Vector3::zeros(),Vector3::x(),10.0,0)
+            .with_minimum_impulse(2.0)
+            .with_isp(200.0);
+        // A single-step pulse command still fires for the minimum on-time
+        thruster.update(1.0,0.05);
+        for _ in 0..10 {
+            thruster.update(0.0,0.05);
+        }
+        assert!(!thruster.firing());
+        assert_relative_eq!(thruster.impulse(),2.0,max_relative=1e-9);
+        assert_relative_eq!(thruster.propellant_used().unwrap(),2.0 / (200.0 * STANDARD_GRAVITY),max_relative=1e-9);
+    }
+}
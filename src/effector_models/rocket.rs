@@ -0,0 +1,361 @@
+use std::ops::Index;
+use std::path::Path;
+
+use crate::{AeroEffect,AirState,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Represent a rocket motor thrust curve in the RASP `.eng` format
+///
+/// A `.eng` file consists of `;` comment lines, a header line
+///
+/// `<name> <diameter mm> <length mm> <delays> <propellant mass kg> <total mass kg> <manufacturer>`
+///
+/// followed by `<time s> <thrust N>` pairs. Thrust is linearly interpolated between points and
+/// is zero before ignition and after the last point.
+#[derive(Clone)]
+pub struct ThrustCurve<T: Float = DefaultFloatRepr> {
+    /// Motor designation
+    pub name: String,
+    /// Motor diameter (m)
+    pub diameter: T,
+    /// Motor length (m)
+    pub length: T,
+    /// Initial propellant mass (kg)
+    pub propellant_mass: T,
+    /// Initial total motor mass (kg)
+    pub total_mass: T,
+    /// Curve times (s), starting from zero
+    times: Vec<T>,
+    /// Curve thrusts (N)
+    thrusts: Vec<T>,
+    /// Cumulative impulse at each curve point (N·s)
+    impulse: Vec<T>,
+}
+
+impl<T: Float> ThrustCurve<T> {
+    /// Create a new [ThrustCurve] from `(time, thrust)` points
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Motor designation
+    /// * `propellant_mass` - Initial propellant mass (kg)
+    /// * `total_mass` - Initial total motor mass (kg)
+    /// * `points` - `(time, thrust)` pairs (s, N) in increasing time order
+    pub fn new(name: &str, propellant_mass: T, total_mass: T, points: &[(T,T)]) -> Self {
+        if points.is_empty() {
+            panic!("Thrust curve must have at least one point")
+        }
+        if points.windows(2).any(|pair| pair[1].0 <= pair[0].0) || points[0].0 < T::zero() {
+            panic!("Thrust curve times must be non-negative and strictly increasing")
+        }
+
+        let mut times = Vec::with_capacity(points.len() + 1);
+        let mut thrusts = Vec::with_capacity(points.len() + 1);
+        // Curves conventionally omit the zero-thrust ignition point
+        if points[0].0 > T::zero() {
+            times.push(T::zero());
+            thrusts.push(T::zero());
+        }
+        for &(time, thrust) in points {
+            times.push(time);
+            thrusts.push(thrust);
+        }
+
+        let mut impulse = vec![T::zero(); times.len()];
+        for idx in 1..times.len() {
+            impulse[idx] = impulse[idx-1] + (thrusts[idx] + thrusts[idx-1]) * (times[idx] - times[idx-1]) / constant::<T>(2.0);
+        }
+
+        ThrustCurve {
+            name: name.to_string(),
+            diameter: T::zero(),
+            length: T::zero(),
+            propellant_mass,
+            total_mass,
+            times,
+            thrusts,
+            impulse,
+        }
+    }
+
+    /// Parse a [ThrustCurve] from the contents of a RASP `.eng` file
+    ///
+    /// Only the first motor in the file is read.
+    pub fn from_eng(contents: &str) -> Result<Self,Error> {
+        let mut lines = contents.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with(';'));
+
+        let header = lines.next().ok_or_else(|| Error::Parse("Missing .eng header line".to_string()))?;
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        if fields.len() < 6 {
+            return Err(Error::Parse(format!("Malformed .eng header: '{}'",header)));
+        }
+        let parse = |field: &str| -> Result<T,Error> {
+            field.parse::<f64>().map(constant::<T>).map_err(|_| Error::Parse(format!("Invalid number '{}' in .eng file",field)))
+        };
+        let diameter = parse(fields[1])? / constant::<T>(1000.0);
+        let length = parse(fields[2])? / constant::<T>(1000.0);
+        let propellant_mass = parse(fields[4])?;
+        let total_mass = parse(fields[5])?;
+
+        let mut points = Vec::new();
+        for line in lines {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            // A further header line starts the next motor in the file
+            if values.len() != 2 || values[0].parse::<f64>().is_err() {
+                break;
+            }
+            points.push((parse(values[0])?,parse(values[1])?));
+        }
+        if points.is_empty() {
+            return Err(Error::Parse(format!("No thrust data for motor '{}'",fields[0])));
+        }
+        if points.windows(2).any(|pair| pair[1].0 <= pair[0].0) || points[0].0 < T::zero() {
+            return Err(Error::Parse(format!("Thrust curve times for motor '{}' are not strictly increasing",fields[0])));
+        }
+
+        let mut curve = ThrustCurve::new(fields[0],propellant_mass,total_mass,&points);
+        curve.diameter = diameter;
+        curve.length = length;
+        Ok(curve)
+    }
+
+    /// Read a [ThrustCurve] from a RASP `.eng` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self,Error> {
+        let contents = std::fs::read_to_string(path.as_ref())
+            .map_err(|err| Error::Io(format!("Unable to read '{}': {}",path.as_ref().display(),err)))?;
+        ThrustCurve::from_eng(&contents)
+    }
+
+    /// Return the burn time (s)
+    pub fn burn_time(&self) -> T {
+        self.times[self.times.len()-1]
+    }
+
+    /// Return the total impulse (N·s)
+    pub fn total_impulse(&self) -> T {
+        self.impulse[self.impulse.len()-1]
+    }
+
+    /// Return the index of the curve segment containing `time`
+    fn segment(&self, time: T) -> usize {
+        match self.times.iter().position(|&t| t > time) {
+            Some(idx) => idx.max(1) - 1,
+            None => self.times.len()-1,
+        }
+    }
+
+    /// Return the thrust (N) at `time` after ignition (s)
+    pub fn thrust(&self, time: T) -> T {
+        if time < T::zero() || time > self.burn_time() {
+            return T::zero();
+        }
+        let idx = self.segment(time);
+        if idx + 1 >= self.times.len() {
+            return self.thrusts[idx];
+        }
+        let fraction = (time - self.times[idx]) / (self.times[idx+1] - self.times[idx]);
+        self.thrusts[idx] + (self.thrusts[idx+1] - self.thrusts[idx]) * fraction
+    }
+
+    /// Return the impulse delivered (N·s) by `time` after ignition (s)
+    pub fn impulse(&self, time: T) -> T {
+        if time <= T::zero() {
+            return T::zero();
+        }
+        if time >= self.burn_time() {
+            return self.total_impulse();
+        }
+        let idx = self.segment(time);
+        self.impulse[idx] + (self.thrusts[idx] + self.thrust(time)) * (time - self.times[idx]) / constant::<T>(2.0)
+    }
+}
+
+/// Built-in [AeroEffect] for a solid rocket motor
+///
+/// Thrust follows a [ThrustCurve] from ignition, acting along a configurable body-frame axis at the
+/// motor position. Propellant is depleted in proportion to the impulse delivered, and the remaining
+/// motor mass is available from [RocketMotor::mass] to update the vehicle mass with
+/// [Body::set_mass](crate::Body::set_mass).
+///
+/// By default the motor ignites on the first step; with [RocketMotor::with_ignition_channel] it ignites
+/// when the channel first exceeds 0.5. Forces and torques are returned in the body frame.
+#[derive(Clone)]
+pub struct RocketMotor<T: Float = DefaultFloatRepr> {
+    /// Thrust curve
+    curve: ThrustCurve<T>,
+    /// Unit thrust axis, body frame
+    axis: Vector3<T>,
+    /// Nozzle position relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Inputstate index of the ignition command
+    ignition_channel: Option<usize>,
+    /// Time since ignition (s), `None` before ignition
+    burn_time: Option<T>,
+}
+
+impl<T: Float> RocketMotor<T> {
+    /// Create a new [RocketMotor] at the centre of mass, thrusting along body +X
+    pub fn new(curve: ThrustCurve<T>) -> Self {
+        RocketMotor {
+            curve,
+            axis: Vector3::x(),
+            position: Vector3::zeros(),
+            ignition_channel: None,
+            burn_time: None,
+        }
+    }
+
+    /// Set the thrust axis, body frame
+    ///
+    /// The axis is normalised.
+    pub fn with_axis(mut self, axis: Vector3<T>) -> Self {
+        self.axis = axis.normalize();
+        self
+    }
+
+    /// Set the nozzle position relative to the centre of mass, body frame (m)
+    pub fn with_position(mut self, position: Vector3<T>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Ignite the motor when the inputstate `channel` first exceeds 0.5
+    pub fn with_ignition_channel(mut self, channel: usize) -> Self {
+        self.ignition_channel = Some(channel);
+        self
+    }
+
+    /// Return the thrust curve
+    pub fn curve(&self) -> &ThrustCurve<T> {
+        &self.curve
+    }
+
+    /// Return the time since ignition (s), or `None` before ignition
+    pub fn burn_time(&self) -> Option<T> {
+        self.burn_time
+    }
+
+    /// Return the current thrust (N)
+    pub fn thrust(&self) -> T {
+        match self.burn_time {
+            Some(time) => self.curve.thrust(time),
+            None => T::zero(),
+        }
+    }
+
+    /// Return the remaining propellant mass (kg)
+    pub fn propellant_mass(&self) -> T {
+        let total_impulse = self.curve.total_impulse();
+        let delivered = match self.burn_time {
+            Some(time) if total_impulse > T::zero() => self.curve.impulse(time) / total_impulse,
+            _ => T::zero(),
+        };
+        self.curve.propellant_mass * (T::one() - delivered)
+    }
+
+    /// Return the remaining total motor mass (kg)
+    pub fn mass(&self) -> T {
+        self.curve.total_mass - self.curve.propellant_mass + self.propellant_mass()
+    }
+
+    /// Advance the burn by `delta_t`, igniting if `ignite` is set
+    pub fn update(&mut self, ignite: bool, delta_t: T) {
+        self.burn_time = match self.burn_time {
+            Some(time) => Some(time + delta_t),
+            None if ignite => Some(delta_t),
+            None => None,
+        };
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for RocketMotor<T> {
+    fn get_effect(&self, _airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let force = self.axis * self.thrust();
+        (Force::body_vec(force),Torque::body_vec(self.position.cross(&force)))
+    }
+
+    fn step(&mut self, _airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        let ignite = match self.ignition_channel {
+            Some(channel) => inputstate[channel] > constant::<T>(0.5),
+            None => true,
+        };
+        self.update(ignite,delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        // The remaining mass follows from the burn time
+        EffectorState::from_values(&self.burn_time.into_iter().collect::<Vec<_>>())
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(0,0,0).or_else(|_| state.check(1,0,0))?;
+        self.burn_time = (!state.values.is_empty()).then(|| state.value(0));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    const ENG: &str = "; Test motor
+; triangular curve
+T10 24 70 0-5-7 0.01 0.05 Test
+0.5 20.0
+1.0 0.0
+";
+
+    #[test]
+    fn test_parse_eng() {
+        let curve = ThrustCurve::<f64>::from_eng(ENG).unwrap();
+        assert_eq!(curve.name,"T10");
+        assert_relative_eq!(curve.diameter,0.024);
+        assert_relative_eq!(curve.propellant_mass,0.01);
+        assert_relative_eq!(curve.burn_time(),1.0);
+        assert_relative_eq!(curve.total_impulse(),10.0);
+        assert_relative_eq!(curve.thrust(0.25),10.0);
+        assert_relative_eq!(curve.impulse(0.5),5.0);
+
+        assert!(ThrustCurve::<f64>::from_eng("; only comments\n").is_err());
+        assert!(ThrustCurve::<f64>::from_eng("T10 24 70 0 0.01 0.05 Test\n").is_err());
+    }
+
+    #[test]
+    fn test_mass_depletion() {
+        let mut motor = RocketMotor::new(ThrustCurve::from_eng(ENG).unwrap())
+            .with_ignition_channel(0);
+        let airstate = airstate(0.0,0.0);
+
+        motor.step(airstate,Vector3::zeros(),&[0.0],0.5);
+        assert!(motor.burn_time().is_none());
+        assert_relative_eq!(motor.mass(),0.05);
+
+        motor.step(airstate,Vector3::zeros(),&[1.0],0.5);
+        assert_relative_eq!(motor.propellant_mass(),0.005);
+        let (force,_) = motor.get_effect(airstate,Vector3::zeros(),&[1.0]);
+        assert_relative_eq!(force.force.x,20.0);
+
+        motor.step(airstate,Vector3::zeros(),&[1.0],1.0);
+        assert_relative_eq!(motor.thrust(),0.0);
+        assert_relative_eq!(motor.mass(),0.04);
+    }
+
+    #[test]
+    fn test_axis() {
+        let mut motor = RocketMotor::new(ThrustCurve::new("test",0.01,0.05,&[(0.0,10.0),(1.0,10.0)]))
+            .with_axis(Vector3::new(0.0,0.0,-2.0))
+            .with_position(Vector3::new(-1.0,0.0,0.0));
+        motor.update(true,0.5);
+        let airstate = airstate(0.0,0.0);
+        let (force,torque) = motor.get_effect(airstate,Vector3::zeros(),&[0.0]);
+        assert_relative_eq!(force.force.z,-10.0);
+        // Upward thrust aft of the centre of mass pitches the nose down
+        assert_relative_eq!(torque.torque.y,-10.0);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{AeroEffect,AirState,VehicleGeometry};
+use crate::lookup::Table;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+use super::body_airspeed;
+
+/// Source of the rotary rolling and yawing moments of [SpinAero]
+#[derive(Clone)]
+enum RotaryMoments<T: Float> {
+    /// Moment coefficients `[C_lΩ, C_nΩ] Ω̂` faded in with the stall blending (rad<sup>-1</sup>)
+    Linear(T,T),
+    /// Rolling and yawing moment coefficients tabulated against `(α, Ω̂)` from rotary-balance tests
+    RotaryBalance {
+        /// Rolling moment coefficient
+        c_roll: Table<T>,
+        /// Yawing moment coefficient
+        c_yaw: Table<T>,
+    },
+}
+
+/// Built-in [AeroEffect] for the high angle of attack moments which drive and damp a spin
+///
+/// Adds rolling and yawing moments to a clean-flow aerodynamic model, from two sources:
+///
+/// * Asymmetric post-stall moments `[C_l,asym, C_n,asym] σ(α)`, from asymmetric separation over the
+///   wings and forebody. They are faded in beyond stall by the blending `σ`, which rises from 0 to 1
+///   around `±alpha_stall`, and their signs set the direction of the departure.
+/// * Rotary moments in the nondimensional spin rate `Ω̂ = Ω b / 2V`, where `Ω` is the rotation rate
+///   about the velocity vector. These are either linear in `Ω̂` and faded in with `σ`, with negative
+///   coefficients damping the spin, or tabulated against `(α, Ω̂)` from rotary-balance data. Data which
+///   changes sign with `Ω̂` captures the autorotative (pro-spin) region of a developed spin.
+///
+/// Moments are `q S b [C_l, 0, C_n]` in the body frame, with no force. Together with a
+/// [StallAero](super::StallAero) for lift and drag, and a recovery control on a
+/// [ControlSurface](super::ControlSurface) rudder, this supports investigation of incipient spins and
+/// spin recovery.
+#[derive(Clone)]
+pub struct SpinAero<T: Float = DefaultFloatRepr> {
+    /// Reference area (m<sup>2</sup>)
+    s: T,
+    /// Span (m)
+    b: T,
+    /// Stall angle of attack (rad)
+    alpha_stall: T,
+    /// Sigmoid transition rate (rad<sup>-1</sup>)
+    blend_rate: T,
+    /// Asymmetric post-stall rolling and yawing moment coefficients
+    asymmetry: (T,T),
+    /// Rotary moments
+    rotary: RotaryMoments<T>,
+}
+
+impl<T: Float> SpinAero<T> {
+    /// Create a new [SpinAero] with no moments and a blend rate of 50 rad<sup>-1</sup>
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Reference area (m<sup>2</sup>)
+    /// * `b` - Span (m)
+    /// * `alpha_stall` - Stall angle of attack (rad)
+    pub fn new(s: T, b: T, alpha_stall: T) -> Self {
+        SpinAero {
+            s,
+            b,
+            alpha_stall,
+            blend_rate: constant::<T>(50.0),
+            asymmetry: (T::zero(),T::zero()),
+            rotary: RotaryMoments::Linear(T::zero(),T::zero()),
+        }
+    }
+
+    /// Set the sigmoid transition rate (rad<sup>-1</sup>)
+    pub fn with_blend_rate(mut self, blend_rate: T) -> Self {
+        self.blend_rate = blend_rate;
+        self
+    }
+
+    /// Set the fully stalled asymmetric rolling and yawing moment coefficients
+    pub fn with_asymmetry(mut self, c_roll: T, c_yaw: T) -> Self {
+        self.asymmetry = (c_roll,c_yaw);
+        self
+    }
+
+    /// Set linear rotary rolling and yawing moment derivatives `C_lΩ` and `C_nΩ` (rad<sup>-1</sup>)
+    pub fn with_rotary_damping(mut self, c_roll_omega: T, c_yaw_omega: T) -> Self {
+        self.rotary = RotaryMoments::Linear(c_roll_omega,c_yaw_omega);
+        self
+    }
+
+    /// Set rotary-balance rolling and yawing moment coefficients
+    ///
+    /// Values are `c_roll[i][j]` and `c_yaw[i][j]` at `(alpha[i], spin[j])`, for angle of attack (rad)
+    /// and nondimensional spin rate `Ω̂`, and are held beyond the table.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the breakpoints are not strictly increasing or the tables do not match them.
+    pub fn with_rotary_balance(mut self, alpha: Vec<T>, spin: Vec<T>, c_roll: Vec<Vec<T>>, c_yaw: Vec<Vec<T>>) -> Self {
+        self.rotary = RotaryMoments::RotaryBalance {
+            c_roll: Table::new_2d(alpha.clone(),spin.clone(),c_roll),
+            c_yaw: Table::new_2d(alpha,spin,c_yaw),
+        };
+        self
+    }
+
+    /// Return the stall blending `σ` at `alpha`, from 0 in attached flow to 1 when fully stalled
+    pub fn blending(&self, alpha: T) -> T {
+        T::one() / (T::one() + <T as num_traits::Float>::exp(-self.blend_rate * (<T as num_traits::Float>::abs(alpha) - self.alpha_stall)))
+    }
+
+    /// Return the nondimensional spin rate `Ω̂ = Ω b / 2V` for `rates` in `airstate`
+    ///
+    /// Returns zero when the airspeed is zero
+    pub fn spin_rate(&self, airstate: &AirState<T>, rates: &Vector3<T>) -> T {
+        if airstate.airspeed == T::zero() {
+            return T::zero();
+        }
+        let direction = body_airspeed(airstate) / airstate.airspeed;
+        rates.dot(&direction) * self.b / (constant::<T>(2.0) * airstate.airspeed)
+    }
+
+    /// Return the rolling and yawing moment coefficients `(C_l, C_n)` at `alpha` and spin rate `spin`
+    pub fn coefficients(&self, alpha: T, spin: T) -> (T,T) {
+        let sigma = self.blending(alpha);
+        let (c_roll, c_yaw) = match &self.rotary {
+            RotaryMoments::Linear(c_roll_omega, c_yaw_omega) => (*c_roll_omega * spin * sigma,*c_yaw_omega * spin * sigma),
+            RotaryMoments::RotaryBalance { c_roll, c_yaw } => (c_roll.lookup(&[alpha,spin]),c_yaw.lookup(&[alpha,spin])),
+        };
+        (c_roll + self.asymmetry.0 * sigma,c_yaw + self.asymmetry.1 * sigma)
+    }
+}
+
+impl<I, T: Float> AeroEffect<I,T> for SpinAero<T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (c_roll, c_yaw) = self.coefficients(airstate.alpha,self.spin_rate(&airstate,&rates));
+        let q_s_b = airstate.q * self.s * self.b;
+        (Force::body(T::zero(),T::zero(),T::zero()),Torque::body(q_s_b * c_roll,T::zero(),q_s_b * c_yaw))
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.s = geometry.area;
+        self.b = geometry.span;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_asymmetry_and_damping() {
+        let spin = SpinAero::new(16.0,10.0,15.0f64.to_radians()).with_asymmetry(0.01,0.02).with_rotary_damping(-0.1,-0.2);
+        // Attached flow has no spin moments
+        let (_, torque) = spin.get_effect(airstate(0.0,30.0),Vector3::new(0.5,0.0,0.5),&());
+        assert!(torque.torque.norm() < 1e-3);
+
+        // Deep stall, rotating about the velocity vector at Ω̂ = 0.1
+        let alpha = 45.0f64.to_radians();
+        let omega = 0.6 * Vector3::new(alpha.cos(),0.0,alpha.sin());
+        assert_relative_eq!(spin.spin_rate(&airstate(alpha,30.0),&omega),0.1,max_relative=1e-9);
+        let (_, torque) = spin.get_effect(airstate(alpha,30.0),omega,&());
+        // where the rotary damping balances the asymmetry in an equilibrium spin
+        assert!(torque.torque.norm() < 1e-6);
+        let q_s_b = 551.25 * 16.0 * 10.0;
+        let (_, still) = spin.get_effect(airstate(alpha,30.0),Vector3::zeros(),&());
+        assert_relative_eq!(still.torque.z,q_s_b * 0.02,max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_rotary_balance() {
+        // Autorotative yawing moment at low spin rates, damping at high spin rates
+        let spin = SpinAero::new(16.0,10.0,15.0f64.to_radians()).with_rotary_balance(
+            vec![0.0,0.8],vec![0.0,0.2,0.4],
+            vec![vec![0.0,0.0,0.0],vec![0.0,-0.01,-0.02]],
+            vec![vec![0.0,-0.02,-0.04],vec![0.0,0.02,-0.04]]);
+        assert_relative_eq!(spin.coefficients(0.8,0.2).1,0.02,max_relative=1e-9);
+        assert_relative_eq!(spin.coefficients(0.4,0.3).1,-0.02,max_relative=1e-9);
+        // Held beyond the table
+        assert_relative_eq!(spin.coefficients(1.2,0.6).0,-0.02,max_relative=1e-9);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{AeroEffect,AirState,VehicleGeometry};
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr,constant};
+use super::Compressibility;
+
+/// Built-in [AeroEffect] for a lift and drag model which blends into flat-plate behaviour beyond stall
+///
+/// Uses the sigmoid blending formulation from Beard & McLain, *Small Unmanned Aircraft* (2012), §4.4:
+///
+/// `C_L = (1 - σ)(C_L0 + C_Lα α) + σ (2 sign(α) sin²α cos α)`
+///
+/// `C_D = (1 - σ)(C_D0 + k C_L,lin²) + σ C_D,90 sin²α`
+///
+/// where the blending function `σ` transitions from 0 to 1 around `±alpha_stall` at a rate set by the blend rate.
+/// Forces are returned in the body frame.
+///
+/// An optional [Compressibility] correction is applied to the attached-flow lift coefficient `C_L,lin`.
+#[derive(Copy,Clone)]
+pub struct StallAero<T: Float = DefaultFloatRepr> {
+    /// Reference area (m<sup>2</sup>)
+    s: T,
+    /// Lift coefficient at zero alpha
+    c_lift_0: T,
+    /// Linear lift curve slope (rad<sup>-1</sup>)
+    c_lift_alpha: T,
+    /// Zero-lift drag coefficient
+    c_drag_0: T,
+    /// Induced drag factor
+    k: T,
+    /// Stall angle of attack (rad)
+    alpha_stall: T,
+    /// Sigmoid transition rate (rad<sup>-1</sup>)
+    blend_rate: T,
+    /// Flat-plate drag coefficient at 90° alpha
+    c_drag_90: T,
+    /// Compressibility correction for the attached-flow lift
+    compressibility: Compressibility,
+}
+
+impl<T: Float> StallAero<T> {
+    /// Create a new [StallAero] with a blend rate of 50 rad<sup>-1</sup> and a flat-plate drag coefficient of 2.0
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Reference area (m<sup>2</sup>)
+    /// * `c_lift_0` - Lift coefficient at zero alpha
+    /// * `c_lift_alpha` - Linear lift curve slope (rad<sup>-1</sup>)
+    /// * `c_drag_0` - Zero-lift drag coefficient
+    /// * `k` - Induced drag factor, `C_D = C_D0 + k C_L^2` in the linear regime
+    /// * `alpha_stall` - Stall angle of attack (rad)
+    pub fn new(s: T, c_lift_0: T, c_lift_alpha: T, c_drag_0: T, k: T, alpha_stall: T) -> Self {
+        StallAero {
+            s,
+            c_lift_0,
+            c_lift_alpha,
+            c_drag_0,
+            k,
+            alpha_stall,
+            blend_rate: constant::<T>(50.0),
+            c_drag_90: constant::<T>(2.0),
+            compressibility: Compressibility::None,
+        }
+    }
+
+    /// Set the compressibility correction
+    pub fn with_compressibility(mut self, compressibility: Compressibility) -> Self {
+        self.compressibility = compressibility;
+        self
+    }
+
+    /// Set the sigmoid transition rate (rad<sup>-1</sup>)
+    ///
+    /// Larger values give a more abrupt stall
+    pub fn with_blend_rate(mut self, blend_rate: T) -> Self {
+        self.blend_rate = blend_rate;
+        self
+    }
+
+    /// Set the flat-plate drag coefficient at 90° alpha
+    pub fn with_flat_plate_drag(mut self, c_drag_90: T) -> Self {
+        self.c_drag_90 = c_drag_90;
+        self
+    }
+
+    /// Return the blending function `σ` at `alpha`
+    ///
+    /// `σ` is zero in the attached-flow regime and one in the fully stalled regime
+    pub fn blending(&self, alpha: T) -> T {
+        // Equivalent to Beard & McLain Eq. (4.10), rearranged so the exponentials cannot give inf/inf
+        let upper = T::one() / (T::one() + <T as num_traits::Float>::exp(self.blend_rate * (alpha - self.alpha_stall)));
+        let lower = T::one() / (T::one() + <T as num_traits::Float>::exp(-self.blend_rate * (alpha + self.alpha_stall)));
+        T::one() - upper * lower
+    }
+
+    /// Return the incompressible stability-axis `(C_L, C_D)` at `alpha`
+    pub fn coefficients(&self, alpha: T) -> (T,T) {
+        self.coefficients_at_mach(alpha,T::zero())
+    }
+
+    /// Return the stability-axis `(C_L, C_D)` at `alpha` and `mach`
+    pub fn coefficients_at_mach(&self, alpha: T, mach: T) -> (T,T) {
+        let sigma = self.blending(alpha);
+        let sin_alpha = <T as num_traits::Float>::sin(alpha);
+        let cos_alpha = <T as num_traits::Float>::cos(alpha);
+        let two = constant::<T>(2.0);
+
+        let c_lift_linear = self.compressibility.correct(self.c_lift_0 + self.c_lift_alpha * alpha,mach);
+        let c_lift_plate = two * <T as num_traits::Float>::signum(alpha) * sin_alpha * sin_alpha * cos_alpha;
+        let c_lift = (T::one() - sigma) * c_lift_linear + sigma * c_lift_plate;
+
+        let c_drag_linear = self.c_drag_0 + self.k * c_lift_linear * c_lift_linear;
+        let c_drag_plate = self.c_drag_90 * sin_alpha * sin_alpha;
+        let c_drag = (T::one() - sigma) * c_drag_linear + sigma * c_drag_plate;
+
+        (c_lift,c_drag)
+    }
+}
+
+impl<I, T: Float> AeroEffect<I,T> for StallAero<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let (c_lift, c_drag) = self.coefficients_at_mach(airstate.alpha,airstate.mach);
+        let (c_x, c_z) = super::stability_to_body(c_lift,c_drag,airstate.alpha);
+
+        let q_s = airstate.q * self.s;
+        (Force::body(q_s * c_x, T::zero(), q_s * c_z),Torque::body(T::zero(),T::zero(),T::zero()))
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.s = geometry.area;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn model() -> StallAero {
+        StallAero::new(0.5,0.2,5.0,0.03,0.05,15.0f64.to_radians())
+    }
+
+    #[test]
+    fn test_attached_flow() {
+        let aero = model();
+        let alpha = 2.0f64.to_radians();
+        let (c_lift, c_drag) = aero.coefficients(alpha);
+        let c_lift_linear = 0.2 + 5.0 * alpha;
+        assert_relative_eq!(c_lift,c_lift_linear,max_relative=1e-4);
+        assert_relative_eq!(c_drag,0.03 + 0.05 * c_lift_linear.powi(2),max_relative=1e-4);
+    }
+
+    #[test]
+    fn test_deep_stall() {
+        let aero = model();
+        for alpha_deg in [45.0f64,90.0,-60.0,179.0] {
+            let alpha = alpha_deg.to_radians();
+            let (c_lift, c_drag) = aero.coefficients(alpha);
+            assert_relative_eq!(c_lift,2.0 * alpha.signum() * alpha.sin().powi(2) * alpha.cos(),epsilon=1e-6);
+            assert_relative_eq!(c_drag,2.0 * alpha.sin().powi(2),epsilon=1e-6);
+        }
+    }
+
+    #[test]
+    fn test_no_overflow() {
+        let aero = StallAero::<f32>::new(0.5,0.2,5.0,0.03,0.05,0.26).with_blend_rate(500.0);
+        let (c_lift, c_drag) = aero.coefficients(3.0);
+        assert!(c_lift.is_finite());
+        assert!(c_drag.is_finite());
+    }
+}
@@ -0,0 +1,240 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,VehicleGeometry};
+use crate::lookup::Table;
+use super::Compressibility;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Independent variable used to index a coefficient table
+#[derive(Copy,Clone)]
+pub enum TableInput {
+    /// Angle of attack (rad)
+    Alpha,
+    /// Angle of sideslip (rad)
+    Beta,
+    /// Mach number
+    Mach,
+    /// Airspeed (m·s<sup>-1</sup>)
+    Airspeed,
+    /// Element of the inputstate passed to [AffectedBody::step](crate::AffectedBody::step), e.g. a control deflection
+    Input(usize),
+}
+
+/// Aerodynamic coefficient produced by a table
+#[derive(Copy,Clone)]
+pub enum Coefficient {
+    /// Lift coefficient (stability axes, positive up)
+    Lift,
+    /// Drag coefficient (stability axes, positive aft)
+    Drag,
+    /// Body-axis X force coefficient
+    ForceX,
+    /// Body-axis Y force coefficient
+    ForceY,
+    /// Body-axis Z force coefficient
+    ForceZ,
+    /// Rolling moment coefficient (referenced to span)
+    Roll,
+    /// Pitching moment coefficient (referenced to mean chord)
+    Pitch,
+    /// Yawing moment coefficient (referenced to span)
+    Yaw,
+}
+
+/// A single table and the variables used to index it
+#[derive(Clone)]
+struct CoefficientTable<T: Float> {
+    coefficient: Coefficient,
+    inputs: Vec<TableInput>,
+    table: Table<T>,
+}
+
+/// [AeroEffect] built from aerodynamic coefficient lookup tables
+///
+/// Each coefficient is the sum of all tables registered against it, so typical coefficient
+/// build-ups (e.g. `C_L = C_L(alpha,mach) + ΔC_L(alpha,elevator)`) map onto one table per term.
+/// Forces and torques are returned in the body frame.
+///
+/// An optional [Compressibility] correction may be applied to all coefficients except drag and
+/// body-axis X force, which are dominated by skin friction. This is intended for tables built from
+/// incompressible data; tables already indexed by [TableInput::Mach] should not normally be corrected.
+#[derive(Clone)]
+pub struct TableAero<T: Float = DefaultFloatRepr> {
+    /// Reference area (m<sup>2</sup>)
+    s: T,
+    /// Reference span (m)
+    b: T,
+    /// Reference mean chord (m)
+    c: T,
+    /// Registered coefficient tables
+    tables: Vec<CoefficientTable<T>>,
+    /// Compressibility correction
+    compressibility: Compressibility,
+}
+
+impl<T: Float> TableAero<T> {
+    /// Create a new [TableAero] with no tables
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Reference area (m<sup>2</sup>)
+    /// * `b` - Reference span (m)
+    /// * `c` - Reference mean chord (m)
+    pub fn new(s: T, b: T, c: T) -> Self {
+        TableAero {
+            s,
+            b,
+            c,
+            tables: Vec::new(),
+            compressibility: Compressibility::None,
+        }
+    }
+
+    /// Set the compressibility correction
+    pub fn with_compressibility(mut self, compressibility: Compressibility) -> Self {
+        self.compressibility = compressibility;
+        self
+    }
+
+    /// Add a table contributing to `coefficient`, indexed by `inputs`
+    ///
+    /// # Arguments
+    ///
+    /// * `coefficient` - The coefficient the table contributes to
+    /// * `inputs` - The independent variable for each axis of `table`, in order
+    /// * `table` - The lookup table
+    pub fn with_table(mut self, coefficient: Coefficient, inputs: Vec<TableInput>, table: Table<T>) -> Self {
+        if inputs.len() != table.dimensions() {
+            panic!("Table has {} axes but {} inputs were given",table.dimensions(),inputs.len())
+        }
+        self.tables.push(CoefficientTable { coefficient, inputs, table });
+        self
+    }
+
+    /// Add a table contributing to `coefficient` scheduled over Mach number and angle of attack, with
+    /// `values[i][j]` at `(mach[i],alpha[j])`
+    ///
+    /// The coefficient is interpolated bilinearly, covering the full speed range of the vehicle.
+    pub fn with_mach_alpha_table(self, coefficient: Coefficient, mach: Vec<T>, alpha: Vec<T>, values: Vec<Vec<T>>) -> Self {
+        self.with_table(coefficient,vec![TableInput::Mach,TableInput::Alpha],Table::new_2d(mach,alpha,values))
+    }
+
+    /// Sum the contributions to each coefficient
+    ///
+    /// Returns `[lift, drag, x, y, z, roll, pitch, yaw]`
+    fn coefficients<I: Index<usize,Output=T>>(&self, airstate: &AirState<T>, inputstate: &I) -> [T;8] {
+        let mut coefficients = [T::zero();8];
+        for entry in &self.tables {
+            let point: Vec<T> = entry.inputs.iter().map(|input| match input {
+                TableInput::Alpha => airstate.alpha,
+                TableInput::Beta => airstate.beta,
+                TableInput::Mach => airstate.mach,
+                TableInput::Airspeed => airstate.airspeed,
+                TableInput::Input(idx) => inputstate[*idx],
+            }).collect();
+            let idx = match entry.coefficient {
+                Coefficient::Lift => 0,
+                Coefficient::Drag => 1,
+                Coefficient::ForceX => 2,
+                Coefficient::ForceY => 3,
+                Coefficient::ForceZ => 4,
+                Coefficient::Roll => 5,
+                Coefficient::Pitch => 6,
+                Coefficient::Yaw => 7,
+            };
+            coefficients[idx] += entry.table.lookup(&point);
+        }
+        for idx in [0,3,4,5,6,7] {
+            coefficients[idx] = self.compressibility.correct(coefficients[idx],airstate.mach);
+        }
+        coefficients
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for TableAero<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let [c_lift, c_drag, c_x, c_y, c_z, c_roll, c_pitch, c_yaw] = self.coefficients(&airstate,inputstate);
+
+        let (c_x_stab, c_z_stab) = super::stability_to_body(c_lift,c_drag,airstate.alpha);
+
+        let q_s = airstate.q * self.s;
+
+        let force = Force::body(
+            q_s * (c_x + c_x_stab),
+            q_s * c_y,
+            q_s * (c_z + c_z_stab));
+        let torque = Torque::body(
+            q_s * self.b * c_roll,
+            q_s * self.c * c_pitch,
+            q_s * self.b * c_yaw);
+
+        (force,torque)
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.s = geometry.area;
+        self.b = geometry.span;
+        self.c = geometry.chord;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_lift_drag_rotation() {
+        let aero = TableAero::new(2.0,1.0,0.5)
+            .with_table(Coefficient::Lift, vec![TableInput::Alpha], Table::new_1d(vec![-1.0,1.0],vec![-1.0,1.0]))
+            .with_table(Coefficient::Drag, vec![TableInput::Alpha], Table::new_1d(vec![0.0],vec![0.1]));
+
+        let (force,_) = aero.get_effect(AirState { q: 100.0, ..airstate(0.0,10.0) },Vector3::zeros(),&vec![]);
+        assert_relative_eq!(force.force.x,-20.0);
+        assert_relative_eq!(force.force.z,0.0);
+
+        let alpha: f64 = 0.5;
+        let (force,_) = aero.get_effect(AirState { q: 100.0, ..airstate(alpha,10.0) },Vector3::zeros(),&vec![]);
+        assert_relative_eq!(force.force.x,200.0 * (-0.1*alpha.cos() + 0.5*alpha.sin()));
+        assert_relative_eq!(force.force.z,200.0 * (-0.1*alpha.sin() - 0.5*alpha.cos()));
+    }
+
+    #[test]
+    fn test_control_buildup() {
+        let aero = TableAero::new(2.0,1.0,0.5)
+            .with_table(Coefficient::Pitch, vec![TableInput::Alpha], Table::new_1d(vec![-1.0,1.0],vec![0.5,-0.5]))
+            .with_table(Coefficient::Pitch, vec![TableInput::Alpha,TableInput::Input(1)], Table::new_2d(
+                vec![-1.0,1.0],
+                vec![-0.5,0.5],
+                vec![vec![0.5,-0.5],vec![0.5,-0.5]]));
+
+        let (_,torque) = aero.get_effect(AirState { q: 100.0, ..airstate(0.2,10.0) },Vector3::zeros(),&[0.0,0.25]);
+        let c_pitch = -0.1 - 0.25;
+        assert_relative_eq!(torque.torque.y,100.0 * 2.0 * 0.5 * c_pitch);
+        assert_relative_eq!(torque.torque.x,0.0);
+    }
+
+    #[test]
+    fn test_mach_alpha_table() {
+        let aero = TableAero::new(2.0,1.0,0.5)
+            .with_mach_alpha_table(Coefficient::Pitch,vec![0.0,2.0],vec![-0.2,0.2],vec![vec![0.1,-0.1],vec![0.3,-0.3]]);
+        let airstate = AirState { q: 100.0, ..airstate(0.1,340.0) };
+        let (_,torque) = aero.get_effect(airstate,Vector3::zeros(),&vec![]);
+        assert_relative_eq!(torque.torque.y,100.0 * 2.0 * 0.5 * -0.1,max_relative=1e-12);
+    }
+
+    #[test]
+    fn test_compressibility() {
+        let aero = TableAero::new(2.0,1.0,0.5)
+            .with_table(Coefficient::Lift, vec![TableInput::Alpha], Table::new_1d(vec![0.0],vec![0.5]))
+            .with_table(Coefficient::Drag, vec![TableInput::Alpha], Table::new_1d(vec![0.0],vec![0.1]))
+            .with_compressibility(Compressibility::PrandtlGlauert);
+
+        let airstate = AirState { q: 100.0, mach: 0.6, ..airstate(0.0,200.0) };
+        let (force,_) = aero.get_effect(airstate,Vector3::zeros(),&vec![]);
+        assert_relative_eq!(force.force.z,-200.0 * 0.5 / 0.8);
+        assert_relative_eq!(force.force.x,-200.0 * 0.1);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,Force,Torque};
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Lag of the wing downwash at a [HorizontalTail]
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum DownwashLag {
+    /// Downwash follows the current angle of attack
+    None,
+    /// Downwash follows the angle of attack through a first-order lag with time constant `l_t/V`
+    FirstOrder,
+    /// Downwash follows the angle of attack delayed by the transport time `l_t/V`
+    TransportDelay,
+}
+
+/// Built-in [AeroEffect] for a horizontal tail in the downwash of the wing
+///
+/// The tail lift coefficient is
+///
+/// `C_Lt = a_t (α - ε + i_t + q l_t/V) + a_e δ_e`, with downwash `ε = ε0 + dε/dα α`
+///
+/// where `l_t` is the tail arm, the distance of the tail aft of the centre of mass. The wake takes `l_t/V`
+/// to travel from the wing to the tail, so with [DownwashLag::FirstOrder] or [DownwashLag::TransportDelay]
+/// the downwash follows the angle of attack of the wing with that lag. This adds the `C_mα̇` pitch
+/// damping of the tail, a first-order effect on the short-period dynamics which lumped models omit.
+///
+/// The lift acts at the tail `position` relative to the centre of mass, generating moments
+/// `position × force`. The lagged downwash is advanced by [AeroEffect::step] when used in an
+/// [AffectedBody](crate::AffectedBody), or manually using [HorizontalTail::update]; until the first update
+/// the downwash follows the current angle of attack. Forces and torques are returned in the body frame.
+#[derive(Clone)]
+pub struct HorizontalTail<T: Float = DefaultFloatRepr> {
+    /// Tail area (m<sup>2</sup>)
+    s: T,
+    /// Tail aerodynamic centre relative to centre of mass, body frame (m)
+    position: Vector3<T>,
+    /// Tail lift curve slope (rad<sup>-1</sup>)
+    lift_slope: T,
+    /// Tail incidence relative to the body X axis (rad)
+    incidence: T,
+    /// Downwash at zero angle of attack (rad)
+    epsilon_0: T,
+    /// Downwash gradient
+    d_epsilon_d_alpha: T,
+    /// Inputstate index and lift effectiveness (rad<sup>-1</sup>) of the elevator, if any
+    elevator: Option<(usize,T)>,
+    /// Downwash lag
+    lag: DownwashLag,
+    /// Lagged downwash (rad), once updated
+    epsilon: Option<T>,
+    /// Time of the downwash history, advanced by each update (s)
+    time: T,
+    /// Times (s) and angles of attack (rad) of the wing, for the transport delay
+    history: VecDeque<(T,T)>,
+}
+
+impl<T: Float> HorizontalTail<T> {
+    /// Create a new [HorizontalTail] with no incidence, downwash or elevator
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Tail area (m<sup>2</sup>)
+    /// * `position` - Tail aerodynamic centre relative to centre of mass, body frame (m)
+    /// * `lift_slope` - Tail lift curve slope (rad<sup>-1</sup>)
+    pub fn new(s: T, position: Vector3<T>, lift_slope: T) -> Self {
+        HorizontalTail {
+            s,
+            position,
+            lift_slope,
+            incidence: T::zero(),
+            epsilon_0: T::zero(),
+            d_epsilon_d_alpha: T::zero(),
+            elevator: None,
+            lag: DownwashLag::None,
+            epsilon: None,
+            time: T::zero(),
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Set the tail incidence relative to the body X axis (rad)
+    pub fn with_incidence(mut self, incidence: T) -> Self {
+        self.incidence = incidence;
+        self
+    }
+
+    /// Set the downwash at zero angle of attack `epsilon_0` (rad) and the downwash gradient `d_epsilon_d_alpha`
+    pub fn with_downwash(mut self, epsilon_0: T, d_epsilon_d_alpha: T) -> Self {
+        self.epsilon_0 = epsilon_0;
+        self.d_epsilon_d_alpha = d_epsilon_d_alpha;
+        self
+    }
+
+    /// Set the lag of the downwash
+    pub fn with_downwash_lag(mut self, lag: DownwashLag) -> Self {
+        self.lag = lag;
+        self
+    }
+
+    /// Read an elevator deflection (rad) from inputstate `channel`, with lift `effectiveness` (rad<sup>-1</sup>)
+    pub fn with_elevator(mut self, channel: usize, effectiveness: T) -> Self {
+        self.elevator = Some((channel,effectiveness));
+        self
+    }
+
+    /// Return the tail arm, the distance of the tail aft of the centre of mass (m)
+    pub fn tail_arm(&self) -> T {
+        -self.position[0]
+    }
+
+    /// Return the steady downwash (rad) at angle of attack `alpha` (rad)
+    pub fn steady_downwash(&self, alpha: T) -> T {
+        self.epsilon_0 + self.d_epsilon_d_alpha * alpha
+    }
+
+    /// Return the downwash (rad) at the tail for the current `alpha` (rad)
+    pub fn downwash(&self, alpha: T) -> T {
+        match (self.lag, self.epsilon) {
+            (DownwashLag::None, _) | (_, None) => self.steady_downwash(alpha),
+            (_, Some(epsilon)) => epsilon,
+        }
+    }
+
+    /// Advance the lagged downwash by `delta_t` given the current `airstate`
+    ///
+    /// The downwash follows the angle of attack without lag when the airspeed is zero.
+    pub fn update(&mut self, airstate: &AirState<T>, delta_t: T) {
+        let delay = if airstate.airspeed > T::zero() { self.tail_arm() / airstate.airspeed } else { T::zero() };
+        match self.lag {
+            DownwashLag::None => {},
+            DownwashLag::FirstOrder => {
+                let target = self.steady_downwash(airstate.alpha);
+                let epsilon = self.epsilon.unwrap_or(target);
+                // Exact discretisation of the first-order lag for a constant target over the step
+                let decay = if delay > T::zero() { <T as num_traits::Float>::exp(-delta_t / delay) } else { T::zero() };
+                self.epsilon = Some(target + (epsilon - target) * decay);
+            },
+            DownwashLag::TransportDelay => {
+                self.history.push_back((self.time,airstate.alpha));
+                self.time += delta_t;
+                let delayed = self.time - delay;
+                // Keep one sample at or before the delayed time for interpolation
+                while self.history.len() > 1 && self.history[1].0 <= delayed {
+                    self.history.pop_front();
+                }
+                let alpha = match (self.history.front(), self.history.get(1)) {
+                    (Some(&(t0, alpha_0)), Some(&(t1, alpha_1))) if delayed > t0 => alpha_0 + (alpha_1 - alpha_0) * (delayed - t0) / (t1 - t0),
+                    (Some(&(_, alpha_0)), _) => alpha_0,
+                    (None, _) => airstate.alpha,
+                };
+                self.epsilon = Some(self.steady_downwash(alpha));
+            },
+        }
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for HorizontalTail<T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let pitch_rate_incidence = if airstate.airspeed > T::zero() { rates[1] * self.tail_arm() / airstate.airspeed } else { T::zero() };
+        let alpha_tail = airstate.alpha - self.downwash(airstate.alpha) + self.incidence + pitch_rate_incidence;
+        let elevator = self.elevator.map_or(T::zero(),|(channel, effectiveness)| effectiveness * inputstate[channel]);
+        let c_lift = self.lift_slope * alpha_tail + elevator;
+
+        let (c_x, c_z) = super::stability_to_body(c_lift,T::zero(),airstate.alpha);
+        let q_s = airstate.q * self.s;
+        let force = Vector3::new(q_s * c_x, T::zero(), q_s * c_z);
+        (Force::body_vec(force),Torque::body_vec(self.position.cross(&force)))
+    }
+
+    fn step(&mut self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I, delta_t: T) {
+        self.update(&airstate,delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        // The lagged downwash and clock, followed by the (time, alpha) pairs of the delay history
+        let mut values = vec![self.time,self.epsilon.unwrap_or_else(T::zero)];
+        values.extend(self.history.iter().flat_map(|&(time, alpha)| vec![time,alpha]));
+        EffectorState { words: vec![self.epsilon.is_some() as u64], ..EffectorState::from_values(&values) }
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        if state.values.len() < 2 || !state.values.len().is_multiple_of(2) {
+            return Err(Error::SnapshotMismatch);
+        }
+        state.check(state.values.len(),1,0)?;
+        self.time = state.value(0);
+        self.epsilon = (state.words[0] != 0).then(|| state.value(1));
+        self.history = (2..state.values.len()).step_by(2).map(|idx| (state.value(idx),state.value(idx + 1))).collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    fn tail(lag: DownwashLag) -> HorizontalTail {
+        // Tail arm of 2 m, so the wake takes 0.1 s to reach the tail
+        HorizontalTail::new(0.2,Vector3::new(-2.0,0.0,0.0),4.0)
+            .with_downwash(0.02,0.4)
+            .with_downwash_lag(lag)
+            .with_elevator(1,1.5)
+    }
+
+    #[test]
+    fn test_steady_tail() {
+        let tail = tail(DownwashLag::None);
+        let alpha = 0.1;
+        let (force,torque) = tail.get_effect(airstate(alpha,20.0),Vector3::zeros(),&[0.0,0.05]);
+        let c_lift = 4.0 * (alpha - (0.02 + 0.4 * alpha)) + 1.5 * 0.05;
+        let lift = 245.0 * 0.2 * c_lift;
+        assert_relative_eq!(force.force.z,-lift * alpha.cos(),max_relative=1e-12);
+        // Tail lift aft of the centre of mass pitches the nose down
+        assert_relative_eq!(torque.torque.y,-2.0 * lift * alpha.cos(),max_relative=1e-12);
+
+        // Pitch rate raises the tail incidence by q l_t / V
+        let (pitching,_) = tail.get_effect(airstate(alpha,20.0),Vector3::new(0.0,0.5,0.0),&[0.0,0.05]);
+        assert_relative_eq!(pitching.force.z - force.force.z,-245.0 * 0.2 * 4.0 * 0.05 * alpha.cos(),max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_downwash_lag() {
+        let (before, after) = (0.0,0.1);
+        let mut first_order = tail(DownwashLag::FirstOrder);
+        let mut delayed = tail(DownwashLag::TransportDelay);
+        for tail in [&mut first_order, &mut delayed] {
+            (0..100).for_each(|_| tail.update(&airstate(before,20.0),0.01));
+            assert_relative_eq!(tail.downwash(after),tail.steady_downwash(before),max_relative=1e-9);
+        }
+
+        // After a step in alpha the first-order lag decays with time constant l_t/V
+        first_order.update(&airstate(after,20.0),0.1);
+        let expected = 0.02 + 0.4 * 0.1 * (1.0 - (-1.0_f64).exp());
+        assert_relative_eq!(first_order.downwash(after),expected,max_relative=1e-9);
+
+        // The transport delay holds the old downwash until the wake arrives
+        for _ in 0..9 {
+            delayed.update(&airstate(after,20.0),0.01);
+        }
+        assert_relative_eq!(delayed.downwash(after),0.02,max_relative=1e-9);
+        (0..2).for_each(|_| delayed.update(&airstate(after,20.0),0.01));
+        assert_relative_eq!(delayed.downwash(after),delayed.steady_downwash(after),max_relative=1e-9);
+        assert_relative_eq!(tail(DownwashLag::None).downwash(after),0.06,max_relative=1e-12);
+    }
+}
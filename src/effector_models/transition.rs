@@ -0,0 +1,225 @@
+use std::ops::Index;
+
+use crate::{AeroEffect,AirState,VehicleGeometry,Error};
+use crate::snapshot::EffectorState;
+use crate::types::{Vector3,UnitQuaternion,Force,Torque,Frame};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// [AeroEffect] wrapper which tilts a wing or rotor through a VTOL transition
+///
+/// A tilt channel in the inputstate moves from `0` in hover to `1` in forward flight, and is clamped to
+/// that range. The wrapped effector is defined in its own frame, with positions relative to the tilt
+/// `pivot`, and is tilted about body Y by an incidence interpolated linearly from the hover to the forward
+/// incidence. Positive incidence pitches the effector nose-up, so a rotor thrusting along -Z in hover
+/// thrusts along +X at an incidence of -90°, and a tilt-wing defined for forward flight stands vertical in
+/// hover at +90°.
+///
+/// The wrapped effector sees the airstate and rates in its tilted frame, so a tilt-wing sees its effective
+/// angle of attack change through the transition, and may stall. Its body-frame forces and torques are
+/// rotated back into the body frame, and the moment of the rotated force about the centre of mass,
+/// `pivot × F`, is added. World-frame forces and torques are passed through unrotated.
+///
+/// With [Tilting::with_blend] the contribution of the effector is also weighted smoothly through the
+/// transition, for example to fade in the aerodynamic controls of a tailsitter as its wing unstalls, or
+/// to fade out hover-only lift fans. Tilt actuator dynamics can be added by wrapping in
+/// [Actuated](super::Actuated) on the tilt channel.
+#[derive(Copy,Clone)]
+pub struct Tilting<E, T: Float = DefaultFloatRepr> {
+    /// Wrapped effector
+    effector: E,
+    /// Tilt pivot relative to centre of mass, body frame (m)
+    pivot: Vector3<T>,
+    /// Inputstate index of the tilt, from `0` in hover to `1` in forward flight
+    channel: usize,
+    /// Incidence in hover (rad)
+    hover_incidence: T,
+    /// Incidence in forward flight (rad)
+    forward_incidence: T,
+    /// Tilts `(start, end)` over which the weight moves from zero to one, if blended
+    blend: Option<(T,T)>,
+}
+
+impl<E, T: Float> Tilting<E,T> {
+    /// Wrap `effector` on a tilt pivot with full weight throughout the transition
+    ///
+    /// # Arguments
+    ///
+    /// * `effector` - Wing or rotor, defined relative to the pivot
+    /// * `pivot` - Tilt pivot relative to centre of mass, body frame (m)
+    /// * `channel` - Inputstate index of the tilt, from `0` in hover to `1` in forward flight
+    /// * `hover_incidence` - Incidence of the effector in hover (rad)
+    /// * `forward_incidence` - Incidence of the effector in forward flight (rad)
+    pub fn new(effector: E, pivot: Vector3<T>, channel: usize, hover_incidence: T, forward_incidence: T) -> Self {
+        Tilting {
+            effector,
+            pivot,
+            channel,
+            hover_incidence,
+            forward_incidence,
+            blend: None,
+        }
+    }
+
+    /// Weight the contribution of the effector smoothly from zero at tilt `start` to one at tilt `end`
+    ///
+    /// The weight is held beyond the blend, and follows a smoothstep within it so that it has no steps in
+    /// slope. An effector fades out through the transition when `start` is greater than `end`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` and `end` are equal.
+    pub fn with_blend(mut self, start: T, end: T) -> Self {
+        if start == end {
+            panic!("Blend must have a non-zero width");
+        }
+        self.blend = Some((start,end));
+        self
+    }
+
+    /// Return the wrapped effector
+    pub fn effector(&self) -> &E {
+        &self.effector
+    }
+
+    /// Return the clamped tilt commanded by `inputstate`
+    pub fn tilt<I: Index<usize,Output=T>>(&self, inputstate: &I) -> T {
+        num_traits::clamp(inputstate[self.channel],T::zero(),T::one())
+    }
+
+    /// Return the incidence (rad) of the effector at `tilt`
+    pub fn incidence(&self, tilt: T) -> T {
+        self.hover_incidence + (self.forward_incidence - self.hover_incidence) * tilt
+    }
+
+    /// Return the weight of the effector at `tilt`
+    pub fn weight(&self, tilt: T) -> T {
+        match self.blend {
+            Some((start, end)) => {
+                let s = num_traits::clamp((tilt - start) / (end - start),T::zero(),T::one());
+                s * s * (constant::<T>(3.0) - constant::<T>(2.0) * s)
+            },
+            None => T::one(),
+        }
+    }
+
+    /// Return the rotation of the effector frame into the body frame commanded by `inputstate`
+    fn rotation<I: Index<usize,Output=T>>(&self, inputstate: &I) -> UnitQuaternion<T> {
+        UnitQuaternion::from_euler_angles(T::zero(),self.incidence(self.tilt(inputstate)),T::zero())
+    }
+
+    /// Return `airstate` and `rates` in the effector frame for `rotation`
+    fn tilted(rotation: &UnitQuaternion<T>, airstate: AirState<T>, rates: Vector3<T>) -> (AirState<T>,Vector3<T>) {
+        let velocity = rotation.inverse_transform_vector(&super::body_airspeed(&airstate));
+        let alpha = <T as num_traits::Float>::atan2(velocity[2],velocity[0]);
+        let beta = if airstate.airspeed != T::zero() {
+            <T as num_traits::Float>::asin(num_traits::clamp(velocity[1] / airstate.airspeed,-T::one(),T::one()))
+        } else {
+            T::zero()
+        };
+        (AirState { alpha, beta, ..airstate },rotation.inverse_transform_vector(&rates))
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float, E: AeroEffect<I,T>> AeroEffect<I,T> for Tilting<E,T> {
+    fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>) {
+        let rotation = self.rotation(inputstate);
+        let (tilted_airstate, tilted_rates) = Self::tilted(&rotation,airstate,rates);
+        let (force, torque) = self.effector.get_effect(tilted_airstate,tilted_rates,inputstate);
+        let weight = self.weight(self.tilt(inputstate));
+
+        let (force, induced) = match force.frame {
+            Frame::Body => {
+                let rotated = rotation * force.force * weight;
+                (Force::body_vec(rotated),self.pivot.cross(&rotated))
+            },
+            Frame::World => (Force::world_vec(force.force * weight),Vector3::zeros()),
+        };
+        let torque = match torque.frame {
+            Frame::Body => Torque::body_vec(rotation * torque.torque * weight + induced),
+            Frame::World => Torque::world_vec(torque.torque * weight),
+        };
+        (force,torque)
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        let (tilted_airstate, tilted_rates) = Self::tilted(&self.rotation(inputstate),airstate,rates);
+        self.effector.step(tilted_airstate,tilted_rates,inputstate,delta_t);
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        self.effector.set_geometry(geometry);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        self.effector.snapshot()
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        self.effector.restore(state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    /// Rotor thrusting 100 N along -Z, 0.2 m above the pivot
+    struct Rotor;
+    impl AeroEffect<[f64;1]> for Rotor {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3, _inputstate: &[f64;1]) -> (Force,Torque) {
+            let force = Vector3::new(0.0,0.0,-100.0);
+            (Force::body_vec(force),Torque::body_vec(Vector3::new(0.0,0.0,-0.2).cross(&force)))
+        }
+    }
+
+    /// Wing lifting 10 N per radian of angle of attack
+    struct Wing;
+    impl AeroEffect<[f64;1]> for Wing {
+        fn get_effect(&self, airstate: AirState, _rates: Vector3, _inputstate: &[f64;1]) -> (Force,Torque) {
+            (Force::body_vec(Vector3::new(0.0,0.0,-10.0 * airstate.alpha)),Torque::body_vec(Vector3::zeros()))
+        }
+    }
+
+    #[test]
+    fn test_tilt_rotor() {
+        let rotor = Tilting::new(Rotor,Vector3::new(0.0,2.0,0.0),0,0.0,-std::f64::consts::FRAC_PI_2);
+        let (hover,_) = rotor.get_effect(airstate(0.0,20.0),Vector3::zeros(),&[0.0]);
+        assert_relative_eq!(hover.force.z,-100.0,max_relative=1e-12);
+
+        // Half way through the transition the thrust is tilted 45° forward
+        let (force,torque) = rotor.get_effect(airstate(0.0,20.0),Vector3::zeros(),&[0.5]);
+        let component = 100.0 * std::f64::consts::FRAC_1_SQRT_2;
+        assert_relative_eq!(force.force.x,component,max_relative=1e-9);
+        assert_relative_eq!(force.force.z,-component,max_relative=1e-9);
+        // Thrust on the right pivot rolls left and yaws left
+        assert_relative_eq!(torque.torque.x,-2.0 * component,max_relative=1e-9);
+        assert_relative_eq!(torque.torque.z,-2.0 * component,max_relative=1e-9);
+
+        // Forward flight, and tilts beyond the range are clamped
+        let (forward,_) = rotor.get_effect(airstate(0.0,20.0),Vector3::zeros(),&[1.5]);
+        assert_relative_eq!(forward.force.x,100.0,max_relative=1e-9);
+        assert_relative_eq!(forward.force.z,0.0,epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_tilt_wing_blend() {
+        let wing = Tilting::new(Wing,Vector3::zeros(),0,std::f64::consts::FRAC_PI_2,0.0).with_blend(0.5,1.0);
+        // The wing sees the body angle of attack plus its incidence
+        let (tilted_airstate, _) = Tilting::<Wing>::tilted(&wing.rotation(&[0.8]),airstate(0.05,20.0),Vector3::zeros());
+        assert_relative_eq!(tilted_airstate.alpha,0.05 + wing.incidence(0.8),max_relative=1e-9);
+
+        // and fades in smoothly through the second half of the transition
+        assert_eq!(wing.weight(0.2),0.0);
+        assert_relative_eq!(wing.weight(0.75),0.5,max_relative=1e-12);
+        assert_eq!(wing.weight(1.0),1.0);
+        let (cruise,_) = wing.get_effect(airstate(0.05,20.0),Vector3::zeros(),&[1.0]);
+        assert_relative_eq!(cruise.force.z,-0.5,max_relative=1e-9);
+        let (hover,_) = wing.get_effect(airstate(0.05,20.0),Vector3::zeros(),&[0.0]);
+        assert_eq!(hover.force.norm(),0.0);
+
+        let fading = Tilting::new(Wing,Vector3::zeros(),0,0.0,0.0).with_blend(0.6,0.2);
+        assert_eq!((fading.weight(0.1),fading.weight(0.7)),(1.0,0.0));
+    }
+}
@@ -1,7 +1,16 @@
-use crate::{AeroBody,AirState,WindModel,DensityModel};
-use crate::types::{Vector3,Frame,Force,Torque};
+use crate::{AeroBody,AirState,WindModel,DensityModel,Error,VehicleGeometry};
+use crate::types::{Vector3,Matrix3,Frame,Force,Torque,Isometry3};
 
 use crate::types::{Float,DefaultFloatRepr};
+use crate::inputs::{ChannelRouter,ControlInputs};
+use crate::schedule::InputSchedule;
+use crate::recorder::Recorder;
+use crate::stats::{StepStats,StepTimer};
+use crate::snapshot::{Snapshot,EffectorState,ContactState};
+use crate::contact::ContactModel;
+use crate::sensors::{Truth,SensorSuite,SensedState};
+
+use std::ops::IndexMut;
 
 /// Trait for aerodynamic effect
 pub trait AeroEffect<I = Vec<DefaultFloatRepr>, T: Float = DefaultFloatRepr> {
@@ -16,43 +25,538 @@ pub trait AeroEffect<I = Vec<DefaultFloatRepr>, T: Float = DefaultFloatRepr> {
     /// * `rates` - The body axis rates at the current timestep \[roll,pitch,yaw\] (rad/s)
     /// * `inputstate` - A reference to the inputstate passed to [AffectedBody::step]
     fn get_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Force<T>,Torque<T>);
+    
+    /// Advance any internal state of the effect by `delta_t`
+    /// 
+    /// When this effect is given to an [AffectedBody], `step` is called once per timestep, after
+    /// `get_effect` has been called for every effect, with the same arguments as were passed to `get_effect`.
+    /// The forces and torques for a timestep are therefore always calculated from the internal state at
+    /// the start of that timestep.
+    /// 
+    /// The default implementation does nothing, which is appropriate for effects which are
+    /// pure functions of the airstate, rates and inputstate.
+    /// 
+    /// # Arguments
+    /// * `airstate` - The vehicle airstate at the start of the timestep
+    /// * `rates` - The body axis rates at the start of the timestep \[roll,pitch,yaw\] (rad/s)
+    /// * `inputstate` - A reference to the inputstate passed to [AffectedBody::step]
+    /// * `delta_t` - The timestep (s)
+    fn step(&mut self, _airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I, _delta_t: T) {}
+    
+    /// Adopt the reference geometry of the vehicle
+    /// 
+    /// When this effect is given to an [AffectedBody] with a [VehicleGeometry], `set_geometry` is called
+    /// when the geometry is set and when the effect is added. Whole-vehicle coefficient models replace
+    /// their reference area, span and chord, and wrappers pass the geometry on to the effects they wrap.
+    /// Component models with their own dimensions, such as a control surface or tail, keep them.
+    /// 
+    /// The default implementation does nothing.
+    fn set_geometry(&mut self, _geometry: &VehicleGeometry<T>) {}
+    
+    /// Export the internal state of the effect advanced by [AeroEffect::step], such as an actuator position,
+    /// so it can be checkpointed alongside the body state
+    /// 
+    /// Wrappers export the state of the effects they wrap as parts. Defaults to the empty state of a
+    /// stateless effect.
+    fn snapshot(&self) -> EffectorState {
+        EffectorState::default()
+    }
+    
+    /// Restore an internal state exported by [AeroEffect::snapshot]
+    /// 
+    /// Defaults to accepting only the empty state of a stateless effect
+    /// 
+    /// # Errors
+    /// 
+    /// Returns [Error::SnapshotMismatch] if `state` was not exported by this kind of effect. A wrapper may be
+    /// partially restored when an error is returned.
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(0,0,0)
+    }
 }
 
 use crate::wind_models::ConstantWind;
 use crate::aero::StandardDensity;
 
+/// A named entry in an [Effectors] collection
+struct EffectorEntry<I, T: Float> {
+    /// Unique name of the effector
+    name: String,
+    /// The effector
+    effector: Box<dyn AeroEffect<I,T>>,
+    /// Whether the effector contributes to the dynamics
+    enabled: bool,
+    /// Force and torque from the effector during the previous step, if it was enabled
+    effect: Option<(Force<T>,Torque<T>)>,
+}
+
+/// Managed collection of named [AeroEffect]s
+/// 
+/// Effectors are kept in insertion order and can be added, removed, enabled and disabled between steps,
+/// for example to model gear retraction or an effector failure. Disabled effectors contribute no
+/// force or torque and are not stepped.
+pub struct Effectors<I = Vec<DefaultFloatRepr>, T: Float = DefaultFloatRepr> {
+    /// Effector entries in insertion order
+    entries: Vec<EffectorEntry<I,T>>,
+}
+
+impl<I, T: Float> Default for Effectors<I,T> {
+    fn default() -> Self {
+        Effectors { entries: Vec::new() }
+    }
+}
+
+impl<I, T: Float> Effectors<I,T> {
+    /// Create a new empty collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Return the position of the effector called `name`
+    fn position(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+    
+    /// Add an enabled effector called `name`
+    /// 
+    /// If an effector called `name` already exists it is replaced in place and returned.
+    pub fn add(&mut self, name: &str, effector: Box<dyn AeroEffect<I,T>>) -> Option<Box<dyn AeroEffect<I,T>>> {
+        let entry = EffectorEntry { name: name.to_string(), effector, enabled: true, effect: None };
+        match self.position(name) {
+            Some(idx) => Some(std::mem::replace(&mut self.entries[idx],entry).effector),
+            None => {
+                self.entries.push(entry);
+                None
+            }
+        }
+    }
+    
+    /// Remove and return the effector called `name`
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn AeroEffect<I,T>>> {
+        self.position(name).map(|idx| self.entries.remove(idx).effector)
+    }
+    
+    /// Enable the effector called `name`, returning `false` if it does not exist
+    pub fn enable(&mut self, name: &str) -> bool {
+        self.set_enabled(name,true)
+    }
+    
+    /// Disable the effector called `name`, returning `false` if it does not exist
+    pub fn disable(&mut self, name: &str) -> bool {
+        self.set_enabled(name,false)
+    }
+    
+    /// Set whether the effector called `name` is enabled, returning `false` if it does not exist
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.position(name) {
+            Some(idx) => {
+                self.entries[idx].enabled = enabled;
+                true
+            },
+            None => false,
+        }
+    }
+    
+    /// Return whether the effector called `name` is enabled, or `None` if it does not exist
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.position(name).map(|idx| self.entries[idx].enabled)
+    }
+    
+    /// Return the effector called `name`
+    pub fn get(&self, name: &str) -> Option<&dyn AeroEffect<I,T>> {
+        self.position(name).map(|idx| self.entries[idx].effector.as_ref())
+    }
+    
+    /// Return the effector called `name` mutably
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut (dyn AeroEffect<I,T> + 'static)> {
+        match self.position(name) {
+            Some(idx) => Some(self.entries[idx].effector.as_mut()),
+            None => None,
+        }
+    }
+    
+    /// Return the effector names in insertion order
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.entries.iter().map(|entry| entry.name.as_str())
+    }
+    
+    /// Pass `geometry` to every effector, including disabled effectors
+    pub fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        for entry in &mut self.entries {
+            entry.effector.set_geometry(geometry);
+        }
+    }
+    
+    /// Return the names and internal states of every effector, including disabled effectors
+    pub fn snapshot(&self) -> Vec<(String,EffectorState)> {
+        self.entries.iter().map(|entry| (entry.name.clone(),entry.effector.snapshot())).collect()
+    }
+
+    /// Restore the internal states of every effector from `states`, as returned by [Effectors::snapshot]
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::SnapshotMismatch] if the names differ from those of `states`, leaving every effector
+    /// unchanged, or if a state does not match its effector, in which case the preceding effectors are restored.
+    pub fn restore(&mut self, states: &[(String,EffectorState)]) -> Result<(),Error> {
+        if states.len() != self.entries.len() || self.entries.iter().zip(states).any(|(entry,(name,_))| &entry.name != name) {
+            return Err(Error::SnapshotMismatch);
+        }
+        self.entries.iter_mut().zip(states).try_for_each(|(entry,(_,state))| entry.effector.restore(state))
+    }
+
+    /// Return the number of effectors, including disabled effectors
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Return `true` if there are no effectors
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<I, T: Float> From<Vec<Box<dyn AeroEffect<I,T>>>> for Effectors<I,T> {
+    /// Create a collection from `effectors`, naming each by its index
+    fn from(effectors: Vec<Box<dyn AeroEffect<I,T>>>) -> Self {
+        let mut collection = Effectors::new();
+        for (idx, effector) in effectors.into_iter().enumerate() {
+            collection.add(&idx.to_string(),effector);
+        }
+        collection
+    }
+}
+
+/// Return `vector` in `frame` in the body frame, for the world-to-body `dcm`
+fn body_vector<T: Float>(dcm: &Matrix3<T>, vector: Vector3<T>, frame: Frame) -> Vector3<T> {
+    match frame {
+        Frame::Body => vector,
+        Frame::World => dcm * vector,
+    }
+}
+
 /// Represent a body subject to aerodynamic effects
 pub struct AffectedBody<I = Vec<DefaultFloatRepr>, T: Float = DefaultFloatRepr, W: WindModel<T> = ConstantWind<T>, D: DensityModel<T> = StandardDensity> {
     /// Underlying AeroBody
     pub body: AeroBody<T,W,D>,
-    /// Vec of aerodynamic effects
-    pub effectors: Vec<Box<dyn AeroEffect<I,T>>>,
+    /// Collection of aerodynamic effects
+    pub effectors: Effectors<I,T>,
+    /// Optional routing of named commands to inputstate slots
+    router: Option<ChannelRouter<I,T>>,
+    /// Step statistics, if enabled
+    stats: Option<StepStats>,
+    /// Contact with the surface
+    contacts: Vec<Box<dyn ContactModel<T>>>,
+    /// Reference geometry shared by the effectors, if set
+    geometry: Option<VehicleGeometry<T>>,
+    /// Total body-frame force and torque of the effectors during the previous step
+    total: Option<(Vector3<T>,Vector3<T>)>,
 }
 
 impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> AffectedBody<I,T,W,D> {
+    /// Create a new [AffectedBody] from `body` with the aerodynamic `effectors`
+    /// 
+    /// Each effector is named by its index, `"0"`, `"1"`, ...; use [AffectedBody::add_effector] to add
+    /// effectors with meaningful names.
+    pub fn new(body: AeroBody<T,W,D>, effectors: Vec<Box<dyn AeroEffect<I,T>>>) -> Self {
+        AffectedBody {
+            body,
+            effectors: Effectors::from(effectors),
+            router: None,
+            stats: None,
+            contacts: Vec::new(),
+            geometry: None,
+            total: None,
+        }
+    }
+    
+    /// Set the [ChannelRouter] used by [AffectedBody::step_commands]
+    pub fn with_router(mut self, router: ChannelRouter<I,T>) -> Self {
+        self.router = Some(router);
+        self
+    }
+    
+    /// Return the [ChannelRouter], if set
+    pub fn router(&self) -> Option<&ChannelRouter<I,T>> {
+        self.router.as_ref()
+    }
+    
+    /// Set the reference `geometry` shared by the effectors
+    pub fn with_geometry(mut self, geometry: VehicleGeometry<T>) -> Self {
+        self.set_geometry(geometry);
+        self
+    }
+    
+    /// Share the reference `geometry` with every effector, now and as they are added
+    pub fn set_geometry(&mut self, geometry: VehicleGeometry<T>) {
+        self.effectors.set_geometry(&geometry);
+        self.geometry = Some(geometry);
+    }
+    
+    /// Return the reference geometry, if set
+    pub fn geometry(&self) -> Option<&VehicleGeometry<T>> {
+        self.geometry.as_ref()
+    }
+    
+    /// Add an enabled effector called `name`, replacing and returning any existing effector of that name
+    /// 
+    /// The effector adopts the reference geometry, if set.
+    pub fn add_effector(&mut self, name: &str, mut effector: Box<dyn AeroEffect<I,T>>) -> Option<Box<dyn AeroEffect<I,T>>> {
+        if let Some(geometry) = &self.geometry {
+            effector.set_geometry(geometry);
+        }
+        self.effectors.add(name,effector)
+    }
+    
+    /// Remove and return the effector called `name`
+    pub fn remove_effector(&mut self, name: &str) -> Option<Box<dyn AeroEffect<I,T>>> {
+        self.effectors.remove(name)
+    }
+    
+    /// Enable the effector called `name`, returning `false` if it does not exist
+    pub fn enable(&mut self, name: &str) -> bool {
+        self.effectors.enable(name)
+    }
+    
+    /// Disable the effector called `name`, returning `false` if it does not exist
+    pub fn disable(&mut self, name: &str) -> bool {
+        self.effectors.disable(name)
+    }
+    
+    /// Add a [ContactModel], evaluated with the statevector at the start of each step
+    pub fn add_contact(&mut self, contact: Box<dyn ContactModel<T>>) {
+        self.contacts.push(contact);
+    }
+    
+    /// Return the number of [ContactModel]s
+    pub fn contacts(&self) -> usize {
+        self.contacts.len()
+    }
+    
     /// Propagate the system state by delta_t with `inputstate`
     /// 
     /// NB: Forces and Torques are calculated at the beginning of the timestep and are not recalculated
     /// as part of the Runge-Kutta iteration.
     /// 
+    /// Once the forces and torques have been calculated, the internal state of each enabled effector is
+    /// advanced using [AeroEffect::step]. The forces and torques of each [ContactModel] are then added,
+    /// and its internal state advanced using [ContactModel::step].
+    /// 
     /// # Arguments
     /// * `delta_t` - The timestep for this step
     /// * `inputstate` - The input state to pass to the suplied [AeroEffect]s
     pub fn step(&mut self, delta_t: T, inputstate: &I) {
+        let timer = self.stats.map(|_| StepTimer::start());
         let airstate = self.body.get_airstate();
         let rates = self.body.rates();
-        let ft_pairs = self.effectors.iter().map(|e| e.get_effect(airstate,rates,inputstate) );
         
+        let dcm = self.body.dcm();
+        let mut total = (Vector3::zeros(),Vector3::zeros());
         let mut forces = Vec::<Force<T>>::with_capacity(self.effectors.len());
         let mut torques = Vec::<Torque<T>>::with_capacity(self.effectors.len());
-        for (f,t) in ft_pairs {
+        for entry in self.effectors.entries.iter_mut() {
+            entry.effect = if entry.enabled {
+                let (f,t) = entry.effector.get_effect(airstate,rates,inputstate);
+                total.0 += body_vector(&dcm,f.force,f.frame);
+                total.1 += body_vector(&dcm,t.torque,t.frame);
+                forces.push(f);
+                torques.push(t);
+                Some((f,t))
+            } else {
+                None
+            };
+        }
+        self.total = Some(total);
+        
+        // Only the enabled effectors are evaluated and stepped
+        let evaluations = forces.len();
+        for entry in self.effectors.entries.iter_mut().filter(|entry| entry.enabled) {
+            entry.effector.step(airstate,rates,inputstate,delta_t);
+        }
+        
+        let state = self.body.statevector();
+        for contact in self.contacts.iter_mut() {
+            let (f,t) = contact.get_contact(&state);
             forces.push(f);
             torques.push(t);
+            contact.step(&state,delta_t);
         }
         
         self.body.step(&forces,&torques,delta_t);
+        
+        if let (Some(stats), Some(timer)) = (self.stats.as_mut(),timer) {
+            stats.record(timer.elapsed(),evaluations,evaluations,self.contacts.len());
+        }
     }
-   
+    
+    /// Start collecting [StepStats] for each step, keeping any already collected
+    pub fn enable_stats(&mut self) {
+        self.stats.get_or_insert_with(StepStats::new);
+    }
+    
+    /// Stop collecting [StepStats] and discard those collected
+    pub fn disable_stats(&mut self) {
+        self.stats = None;
+    }
+    
+    /// Return the [StepStats] collected since they were enabled or reset, or `None` if they are not enabled
+    pub fn stats(&self) -> Option<&StepStats> {
+        self.stats.as_ref()
+    }
+    
+    /// Reset the collected [StepStats] to zero, if they are enabled
+    pub fn reset_stats(&mut self) {
+        if let Some(stats) = self.stats.as_mut() {
+            stats.reset();
+        }
+    }
+    
+    /// Return the total body-frame force and torque of the enabled effectors at `airstate` and `rates`
+    /// 
+    /// World-frame effects are rotated into the body frame using the current attitude. This allows the
+    /// effectors to be evaluated away from the current state, and the system is not advanced.
+    pub fn total_effect(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I) -> (Vector3<T>,Vector3<T>) {
+        let dcm = self.body.dcm();
+        let mut force = Vector3::zeros();
+        let mut torque = Vector3::zeros();
+        for entry in self.effectors.entries.iter().filter(|entry| entry.enabled) {
+            let (f,t) = entry.effector.get_effect(airstate,rates,inputstate);
+            force += body_vector(&dcm,f.force,f.frame);
+            torque += body_vector(&dcm,t.torque,t.frame);
+        }
+        (force,torque)
+    }
+    
+    /// Return the total body-frame force and torque of the enabled effectors during the previous step
+    /// 
+    /// The total excludes contacts and gravity, and world-frame effects are rotated into the body frame
+    /// using the attitude at the start of the step. Returns `None` before the first step.
+    pub fn last_effect(&self) -> Option<(Vector3<T>,Vector3<T>)> {
+        self.total
+    }
+    
+    /// Calculate the statevector derivative with `inputstate`, without advancing the system
+    /// 
+    /// The derivative includes the effects of all enabled effectors, contacts and gravity. This is used for
+    /// trimming and linearisation.
+    pub fn derivative(&self, inputstate: &I) -> StateVector<T> {
+        let airstate = self.body.get_airstate();
+        let rates = self.body.rates();
+        
+        let (forces, torques): (Vec<Force<T>>,Vec<Torque<T>>) = self.effectors.entries.iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.effector.get_effect(airstate,rates,inputstate))
+            .chain(self.contacts.iter().map(|contact| contact.get_contact(&self.body.statevector())))
+            .unzip();
+        
+        self.body.derivative(&forces,&torques)
+    }
+    
+    /// Propagate the system state by delta_t with `inputstate` clamped to its [ControlInputs] saturation limits
+    pub fn step_saturated(&mut self, delta_t: T, inputstate: &I) where I: Clone + IndexMut<usize,Output=T> + ControlInputs<T> {
+        let mut saturated = inputstate.clone();
+        saturated.saturate();
+        self.step(delta_t,&saturated);
+    }
+    
+    /// Propagate the system state by delta_t with named `commands`
+    /// 
+    /// The commands are converted to an inputstate by the [ChannelRouter] set with
+    /// [AffectedBody::with_router], and then passed to [AffectedBody::step].
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if no router has been set, or if a command has no route.
+    pub fn step_commands(&mut self, delta_t: T, commands: &[(&str,T)]) where I: Clone + IndexMut<usize,Output=T> {
+        if let Err(error) = self.try_step_commands(delta_t,commands) {
+            panic!("{}",error)
+        }
+    }
+    
+    /// Propagate the system state by delta_t with named `commands`, without stepping on failure
+    /// 
+    /// Returns [Error::NoRouter] if no router has been set, or [Error::UnroutedCommand] if a command has no route.
+    pub fn try_step_commands(&mut self, delta_t: T, commands: &[(&str,T)]) -> Result<(),Error> where I: Clone + IndexMut<usize,Output=T> {
+        let inputstate = self.router.as_ref().ok_or(Error::NoRouter)?.try_inputs(commands)?;
+        self.step(delta_t,&inputstate);
+        Ok(())
+    }
+    
+    /// Run the system in closed loop for `duration` with timestep `delta_t`
+    /// 
+    /// At the start of each step the `controller` is called with the current state, airstate and time,
+    /// and returns the inputstate for that step. The time is the body clock, [AffectedBody::time], so a
+    /// further run continues from where the previous run ended. Returns a [Recorder] holding the
+    /// state at the start and at the end of every step, and the total effector force and torque of each
+    /// step.
+    /// 
+    /// # Arguments
+    /// * `duration` - Length of the run (s), rounded to a whole number of steps
+    /// * `delta_t` - The timestep for each step
+    /// * `controller` - Closure returning the inputstate from the state, airstate and time
+    pub fn run<C>(&mut self, duration: T, delta_t: T, mut controller: C) -> Recorder<T> where C: FnMut(&dyn StateView<T>, AirState<T>, T) -> I {
+        let steps = <T as num_traits::Float>::round(duration / delta_t).to_usize().unwrap_or(0);
+        let mut recorder = Recorder::new();
+        recorder.record(self.time(),self,self.get_airstate(),self.acceleration());
+        for _ in 0..steps {
+            let time = self.time();
+            let inputstate = controller(self,self.get_airstate(),time);
+            self.step(delta_t,&inputstate);
+            if let Some((force, torque)) = self.last_effect() {
+                recorder.record_effect(force,torque);
+            }
+            recorder.record(self.time(),self,self.get_airstate(),self.acceleration());
+        }
+        recorder
+    }
+    
+    /// Run the system in closed loop through `sensors` for `duration` with timestep `delta_t`
+    /// 
+    /// As [AffectedBody::run], but at the start of each step the [SensorSuite] is updated with the true
+    /// state and the `controller` is given only the [SensedState], so it cannot read the true state.
+    pub fn run_sensed<C>(&mut self, duration: T, delta_t: T, sensors: &mut SensorSuite<T>, mut controller: C) -> Recorder<T> where C: FnMut(&SensedState<T>) -> I {
+        let steps = <T as num_traits::Float>::round(duration / delta_t).to_usize().unwrap_or(0);
+        let mut recorder = Recorder::new();
+        recorder.record(self.time(),self,self.get_airstate(),self.acceleration());
+        for _ in 0..steps {
+            let time = self.time();
+            let inputstate = controller(sensors.update(&Truth::new(time,self)));
+            self.step(delta_t,&inputstate);
+            if let Some((force, torque)) = self.last_effect() {
+                recorder.record_effect(force,torque);
+            }
+            recorder.record(self.time(),self,self.get_airstate(),self.acceleration());
+        }
+        recorder
+    }
+    
+    /// Run an open-loop [InputSchedule] for `duration` with timestep `delta_t`
+    /// 
+    /// The schedule is evaluated at the body time at the start of each step. See [AffectedBody::run] for more details.
+    pub fn run_schedule(&mut self, schedule: &InputSchedule<I,T>, duration: T, delta_t: T) -> Recorder<T> where I: Clone + IndexMut<usize,Output=T> {
+        self.run(duration,delta_t,|_,_,time| schedule.inputs(time))
+    }
+    
+    /// Return the name and `(Force, Torque)` of each effector which contributed during the previous step
+    /// 
+    /// Each contribution is in the frame returned by the effector. Effectors which were disabled, or added
+    /// since the previous step, are omitted.
+    pub fn effector_effects(&self) -> impl Iterator<Item=(&str,(Force<T>,Torque<T>))> {
+        self.effectors.entries.iter().filter_map(|entry| entry.effect.map(|effect| (entry.name.as_str(),effect)))
+    }
+    
+    /// Return the `(Force, Torque)` produced by the effector called `name` during the previous step
+    pub fn effector_effect(&self, name: &str) -> Option<(Force<T>,Torque<T>)> {
+        self.effectors.position(name).and_then(|idx| self.effectors.entries[idx].effect)
+    }
+    
+    /// Get the world-to-body DCM of the current attitude
+    /// 
+    /// See [Body::dcm](crate::Body::dcm) for more details
+    pub fn dcm(&self) -> Matrix3<T> {
+        self.body.dcm()
+    }
+    
     /// Get body acceleration in previous timestep
     /// 
     /// See [Body::acceleration](crate::Body::acceleration) for more details
@@ -60,6 +564,13 @@ impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> AffectedBody<I,T,W,D> {
         self.body.acceleration()
     }
     
+    /// Get body angular acceleration in previous timestep
+    /// 
+    /// See [Body::angular_acceleration](crate::Body::angular_acceleration) for more details
+    pub fn angular_acceleration(&self) -> Vector3<T> {
+        self.body.angular_acceleration()
+    }
+    
     /// Set the statevector for the underlying [AeroBody]
     /// 
     /// This in turn sets the statevector for the underlying [Body]
@@ -69,12 +580,81 @@ impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> AffectedBody<I,T,W,D> {
         self.body.set_state(new_state);
     }
     
+    /// Set the position and attitude from `pose`, the body-to-world isometry
+    ///
+    /// The body-frame velocity and axis rates are kept. See [Body::set_pose](crate::Body::set_pose) for more details
+    pub fn set_pose(&mut self, pose: &Isometry3<T>) {
+        self.body.set_pose(pose);
+    }
+    
     /// Return the current airstate for the rigid body
     /// See documentation for [AeroBody::get_airstate]
     pub fn get_airstate(&self) -> AirState<T> {
         self.body.get_airstate()
     }
     
+    /// Return the elapsed simulation time (s)
+    /// 
+    /// See [AeroBody::time] for more details
+    pub fn time(&self) -> T {
+        self.body.time()
+    }
+    
+    /// Return the [TruthState](crate::sensors::TruthState) of the vehicle at the current simulation time
+    /// 
+    /// This is the true state from which sensors measure. See [Truth::new] for more details
+    pub fn truth(&self) -> Truth<T> {
+        Truth::new(self.time(),self)
+    }
+    
+    /// Set the elapsed simulation time (s)
+    pub fn set_time(&mut self, time: T) {
+        self.body.set_time(time);
+    }
+    
+    /// Return a [Snapshot] of the statevector, simulation time, wind model state, effector states and
+    /// contact states
+    /// 
+    /// Every effector is included, enabled or not, in the order of [AffectedBody::effectors], followed by
+    /// the contact models in the order they were added.
+    pub fn snapshot(&self) -> Snapshot<T> {
+        Snapshot {
+            effectors: self.effectors.snapshot(),
+            contacts: self.contacts.iter().map(|contact| contact.snapshot()).collect(),
+            ..self.body.snapshot()
+        }
+    }
+    
+    /// Restore the statevector, simulation time, wind model state, effector states and contact states
+    /// from `snapshot`
+    /// 
+    /// See [AeroBody::restore] for more details
+    /// 
+    /// # Errors
+    /// 
+    /// Returns [Error::SnapshotMismatch] if the effector names or number of contacts differ from those of the
+    /// snapshot, or a state does not match its effector, contact or the wind model, in which case the vehicle
+    /// is unchanged.
+    pub fn restore(&mut self, snapshot: &Snapshot<T>) -> Result<(),Error> {
+        if snapshot.contacts.len() != self.contacts.len() {
+            return Err(Error::SnapshotMismatch);
+        }
+        let current = self.snapshot();
+        self.effectors.restore(&snapshot.effectors)
+            .and_then(|_| self.restore_contacts(&snapshot.contacts))
+            .and_then(|_| self.body.restore(snapshot))
+            .inspect_err(|_| {
+                // Roll back any effectors and contacts already restored
+                let _ = self.effectors.restore(&current.effectors);
+                let _ = self.restore_contacts(&current.contacts);
+            })
+    }
+    
+    /// Restore the internal state of each contact model from `states`, of the same length
+    fn restore_contacts(&mut self, states: &[ContactState]) -> Result<(),Error> {
+        self.contacts.iter_mut().zip(states).try_for_each(|(contact, state)| contact.restore(state))
+    }
+    
 }
 
 use crate::types::{UnitQuaternion,StateVector,StateView};
@@ -0,0 +1,240 @@
+//! Flight envelope analysis
+//!
+//! An [Envelope] sweeps airspeed and angle of attack against the effectors and mass of an [AffectedBody]
+//! and returns a [VnDiagram]: at each airspeed, the largest positive and negative load factors the
+//! aerodynamics can generate before stall, and the manoeuvring boundaries where these are capped by the
+//! structural limit loads. The diagram gives the 1 g stall speeds and the corner speed for plotting V-n
+//! diagrams.
+//!
+//! The effectors are evaluated at the density and temperature at the vehicle position, with zero sideslip
+//! and body rates, using [AffectedBody::total_effect]. Stall is the maximum of the lift over the swept
+//! angles of attack, so models without a lift break stall at the edge of the sweep, which should be set to
+//! the usable angle of attack range.
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel};
+use crate::aero::{GAMMA_AIR,R_AIR};
+use crate::types::{Vector3,Float,DefaultFloatRepr,constant};
+
+/// Load factor boundaries at a single airspeed
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct VnPoint<T: Float = DefaultFloatRepr> {
+    /// True airspeed (m·s<sup>-1</sup>)
+    pub airspeed: T,
+    /// Largest positive load factor before stall
+    pub stall_positive: T,
+    /// Largest negative load factor before stall
+    pub stall_negative: T,
+    /// Angle of attack of the positive stall (rad)
+    pub alpha_positive: T,
+    /// Angle of attack of the negative stall (rad)
+    pub alpha_negative: T,
+    /// Positive boundary, the lesser of the stall and the positive limit load factor
+    pub positive: T,
+    /// Negative boundary, the greater of the stall and the negative limit load factor
+    pub negative: T,
+}
+
+/// V-n diagram of a vehicle
+#[derive(Clone,Debug)]
+pub struct VnDiagram<T: Float = DefaultFloatRepr> {
+    /// Boundaries at each airspeed, in increasing airspeed order
+    pub points: Vec<VnPoint<T>>,
+    /// Positive structural limit load factor
+    pub positive_limit: T,
+    /// Negative structural limit load factor
+    pub negative_limit: T,
+}
+
+impl<T: Float> VnDiagram<T> {
+    /// Return the airspeed (m·s<sup>-1</sup>) at which the positive stall reaches `load_factor`, interpolated
+    /// between the swept airspeeds, or `None` if it is not reached within the sweep
+    pub fn stall_speed_at(&self, load_factor: T) -> Option<T> {
+        crossing(&self.points,load_factor,|point| point.stall_positive)
+    }
+
+    /// Return the airspeed (m·s<sup>-1</sup>) at which the negative stall reaches `load_factor`, or `None` if
+    /// it is not reached within the sweep
+    pub fn negative_stall_speed_at(&self, load_factor: T) -> Option<T> {
+        crossing(&self.points,-load_factor,|point| -point.stall_negative)
+    }
+
+    /// Return the 1 g stall speed (m·s<sup>-1</sup>), or `None` if it is outside the sweep
+    pub fn stall_speed(&self) -> Option<T> {
+        self.stall_speed_at(T::one())
+    }
+
+    /// Return the corner speed (m·s<sup>-1</sup>), where the positive stall reaches the positive limit load
+    /// factor, or `None` if it is outside the sweep
+    pub fn corner_speed(&self) -> Option<T> {
+        self.stall_speed_at(self.positive_limit)
+    }
+}
+
+/// Return the airspeed at which `value` of the points first rises through `level`
+fn crossing<T: Float, F: Fn(&VnPoint<T>) -> T>(points: &[VnPoint<T>], level: T, value: F) -> Option<T> {
+    if let Some(first) = points.first() {
+        if value(first) >= level {
+            return Some(first.airspeed);
+        }
+    }
+    points.windows(2).find(|pair| value(&pair[0]) < level && value(&pair[1]) >= level).map(|pair| {
+        let fraction = (level - value(&pair[0])) / (value(&pair[1]) - value(&pair[0]));
+        pair[0].airspeed + fraction * (pair[1].airspeed - pair[0].airspeed)
+    })
+}
+
+/// Flight envelope sweep
+#[derive(Copy,Clone,Debug)]
+pub struct Envelope<T: Float = DefaultFloatRepr> {
+    /// Positive structural limit load factor
+    positive_limit: T,
+    /// Negative structural limit load factor
+    negative_limit: T,
+    /// Smallest angle of attack of the sweep (rad)
+    alpha_min: T,
+    /// Largest angle of attack of the sweep (rad)
+    alpha_max: T,
+    /// Number of angles of attack in the sweep
+    alpha_count: usize,
+}
+
+impl<T: Float> Envelope<T> {
+    /// Create a new [Envelope] with structural limit load factors `positive_limit` and `negative_limit`
+    ///
+    /// The angle of attack is swept over ±20° in 0.5° steps by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `positive_limit` is not positive or `negative_limit` is positive.
+    pub fn new(positive_limit: T, negative_limit: T) -> Self {
+        if positive_limit <= T::zero() || negative_limit > T::zero() {
+            panic!("Limit load factors must be positive and non-positive");
+        }
+        let alpha_max = constant::<T>(20.0_f64.to_radians());
+        Envelope { positive_limit, negative_limit, alpha_min: -alpha_max, alpha_max, alpha_count: 81 }
+    }
+
+    /// Sweep the angle of attack over `count` evenly-spaced values from `min` to `max` inclusive (rad)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is not negative, `max` is not positive or `count` is less than two.
+    pub fn with_alpha_range(mut self, min: T, max: T, count: usize) -> Self {
+        if min >= T::zero() || max <= T::zero() || count < 2 {
+            panic!("Alpha sweep must span zero with at least two values");
+        }
+        self.alpha_min = min;
+        self.alpha_max = max;
+        self.alpha_count = count;
+        self
+    }
+
+    /// Return the lift load factor of `vehicle` at `airspeed` (m·s<sup>-1</sup>) and angle of attack `alpha` (rad)
+    ///
+    /// The lift is the component of the total effector force normal to the air-relative velocity in the body
+    /// plane of symmetry, divided by the weight.
+    pub fn load_factor<I, W: WindModel<T>, D: DensityModel<T>>(vehicle: &AffectedBody<I,T,W,D>, inputs: &I, airspeed: T, alpha: T) -> T {
+        let density = vehicle.get_airstate().density;
+        let speed_of_sound = <T as num_traits::Float>::sqrt(constant::<T>(GAMMA_AIR * R_AIR) * vehicle.body.get_temperature());
+        let airstate = AirState {
+            alpha,
+            beta: T::zero(),
+            airspeed,
+            q: constant::<T>(0.5) * density * airspeed * airspeed,
+            mach: airspeed / speed_of_sound,
+            density,
+        };
+        let (force, _) = vehicle.total_effect(airstate,Vector3::zeros(),inputs);
+        let (sin_alpha, cos_alpha) = <T as num_traits::Float>::sin_cos(alpha);
+        let lift = force[0] * sin_alpha - force[2] * cos_alpha;
+        lift / (vehicle.body.body.mass() * constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY))
+    }
+
+    /// Return the [VnPoint] of `vehicle` with `inputs` at `airspeed` (m·s<sup>-1</sup>)
+    pub fn point<I, W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &AffectedBody<I,T,W,D>, inputs: &I, airspeed: T) -> VnPoint<T> {
+        let step = (self.alpha_max - self.alpha_min) / constant::<T>((self.alpha_count - 1) as f64);
+        let mut point = VnPoint {
+            airspeed,
+            stall_positive: T::neg_infinity(),
+            stall_negative: T::infinity(),
+            alpha_positive: T::zero(),
+            alpha_negative: T::zero(),
+            positive: T::zero(),
+            negative: T::zero(),
+        };
+        for idx in 0..self.alpha_count {
+            let alpha = self.alpha_min + step * constant::<T>(idx as f64);
+            let load_factor = Self::load_factor(vehicle,inputs,airspeed,alpha);
+            if load_factor > point.stall_positive {
+                point.stall_positive = load_factor;
+                point.alpha_positive = alpha;
+            }
+            if load_factor < point.stall_negative {
+                point.stall_negative = load_factor;
+                point.alpha_negative = alpha;
+            }
+        }
+        point.positive = <T as num_traits::Float>::min(point.stall_positive,self.positive_limit);
+        point.negative = <T as num_traits::Float>::max(point.stall_negative,self.negative_limit);
+        point
+    }
+
+    /// Return the [VnDiagram] of `vehicle` with `inputs` at each of `airspeeds` (m·s<sup>-1</sup>)
+    ///
+    /// The airspeeds should be increasing and the vehicle is not changed.
+    pub fn sweep<I, W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &AffectedBody<I,T,W,D>, inputs: &I, airspeeds: &[T]) -> VnDiagram<T> {
+        VnDiagram {
+            points: airspeeds.iter().map(|&airspeed| self.point(vehicle,inputs,airspeed)).collect(),
+            positive_limit: self.positive_limit,
+            negative_limit: self.negative_limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use physical_constants::STANDARD_ACCELERATION_OF_GRAVITY;
+    use approx::assert_relative_eq;
+
+    /// Return the speed at which the test aircraft generates `load_factor` at lift coefficient `c_lift`
+    fn speed(load_factor: f64, c_lift: f64) -> f64 {
+        (2.0 * load_factor * 3.0 * STANDARD_ACCELERATION_OF_GRAVITY / (1.225 * 0.5 * c_lift)).sqrt()
+    }
+
+    #[test]
+    fn test_stall_boundaries() {
+        let vehicle = test_aircraft::vehicle(0.0);
+        let inputs = vec![0.0;4];
+        let envelope = Envelope::new(3.8,-1.5).with_alpha_range(-0.2,0.25,46);
+        let airspeeds: Vec<f64> = (1..=40).map(|speed| speed as f64).collect();
+        let diagram = envelope.sweep(&vehicle,&inputs,&airspeeds);
+
+        // The linear lift curve stalls at the edges of the alpha range, C_L = 0.2 + 5 alpha
+        let point = diagram.points[14];
+        assert_relative_eq!(point.alpha_positive,0.25,max_relative=1e-9);
+        assert_relative_eq!(point.alpha_negative,-0.2,max_relative=1e-9);
+        let c_lift = point.stall_positive * 3.0 * STANDARD_ACCELERATION_OF_GRAVITY / (0.5 * 1.225 * 15.0 * 15.0 * 0.5);
+        assert_relative_eq!(c_lift,1.45,max_relative=0.01);
+
+        assert_relative_eq!(diagram.stall_speed().unwrap(),speed(1.0,1.45),max_relative=0.02);
+        assert_relative_eq!(diagram.corner_speed().unwrap(),speed(3.8,1.45),max_relative=0.02);
+        assert_relative_eq!(diagram.negative_stall_speed_at(-1.0).unwrap(),speed(1.0,0.8),max_relative=0.02);
+        assert!(diagram.stall_speed_at(100.0).is_none());
+    }
+
+    #[test]
+    fn test_structural_limits() {
+        let vehicle = test_aircraft::vehicle(0.0);
+        let diagram = Envelope::new(2.5,-1.0).sweep(&vehicle,&vec![0.0;4],&[5.0,30.0]);
+        // Stall limits at low speed, structure at high speed
+        let slow = diagram.points[0];
+        assert_eq!(slow.positive,slow.stall_positive);
+        assert!(slow.positive < 1.0 && slow.negative > -1.0);
+        let fast = diagram.points[1];
+        assert_eq!((fast.positive,fast.negative),(2.5,-1.0));
+        assert!(fast.stall_positive > 2.5);
+        assert_relative_eq!(fast.alpha_positive,20.0_f64.to_radians(),max_relative=1e-9);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::BodyError;
+
+/// Error from a fallible operation of the crate
+///
+/// Returned by the fallible `try_` variants of APIs which otherwise panic on bad configuration, so that
+/// problems can be handled when a simulation is set up rather than discovered as a panic part way through a
+/// run, and by the parsers, solvers and checks which can fail. [BodyError]s convert into it, so `?` can be
+/// used across both.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Error {
+    /// Invalid rigid body configuration
+    Body(BodyError),
+    /// Invalid value of the named parameter
+    InvalidParameter(&'static str),
+    /// A command has no route in the channel router
+    UnroutedCommand(String),
+    /// No channel router has been set
+    NoRouter,
+    /// A snapshot does not match the model it is restored into
+    SnapshotMismatch,
+    /// Malformed text input, with a description of the problem
+    Parse(String),
+    /// A file could not be read, with the path and cause
+    Io(String),
+    /// The trim solver did not converge
+    TrimNotConverged {
+        /// Residual acceleration when the solver stopped
+        residual: f64,
+        /// Number of iterations taken
+        iterations: usize,
+    },
+    /// Repeated runs of a simulation diverge at the given sample
+    NotDeterministic(usize),
+    /// The fingerprint of a run does not match the recorded fingerprint
+    FingerprintMismatch {
+        /// Recorded fingerprint
+        expected: u64,
+        /// Fingerprint of the run
+        actual: u64,
+    },
+    /// No variable has the given value reference
+    UnknownVariable(u32),
+    /// The variable with the given value reference cannot be set
+    ReadOnlyVariable(u32),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Body(error) => write!(f,"invalid body: {}",error),
+            Error::InvalidParameter(name) => write!(f,"invalid value of '{}'",name),
+            Error::UnroutedCommand(name) => write!(f,"no route for command '{}'",name),
+            Error::NoRouter => write!(f,"no channel router has been set"),
+            Error::SnapshotMismatch => write!(f,"snapshot does not match the model"),
+            Error::Parse(message) => write!(f,"{}",message),
+            Error::Io(message) => write!(f,"{}",message),
+            Error::TrimNotConverged { residual, iterations } => write!(f,"trim did not converge: residual acceleration {} after {} iterations",residual,iterations),
+            Error::NotDeterministic(sample) => write!(f,"simulation is not deterministic: runs diverge at sample {}",sample),
+            Error::FingerprintMismatch { expected, actual } => write!(f,"history fingerprint {:016x} does not match recorded {:016x}",actual,expected),
+            Error::UnknownVariable(reference) => write!(f,"no variable {}",reference),
+            Error::ReadOnlyVariable(reference) => write!(f,"variable {} cannot be set",reference),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Body(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<BodyError> for Error {
+    fn from(error: BodyError) -> Self {
+        Error::Body(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Body;
+    use crate::types::{Vector3,Matrix3,UnitQuaternion,StateView};
+
+    #[test]
+    fn test_body_error() {
+        let build = |mass: f64| -> Result<Body<f64>,Error> {
+            Ok(Body::try_new(mass,Matrix3::identity(),Vector3::zeros(),Vector3::zeros(),UnitQuaternion::identity(),Vector3::zeros())?)
+        };
+        assert!(build(1.0).is_ok());
+        let error = build(-1.0).err().unwrap();
+        assert_eq!(error,Error::Body(BodyError::NonPositiveMass));
+        assert_eq!(error.to_string(),"invalid body: mass must be positive");
+        assert!(std::error::Error::source(&error).is_some());
+
+        let mut body = build(1.0).unwrap();
+        assert_eq!(body.try_set_mass(0.0),Err(Error::InvalidParameter("mass")));
+        assert_eq!(body.try_set_mass(f64::NAN),Err(Error::InvalidParameter("mass")));
+        assert_eq!(body.mass(),1.0);
+        assert!(body.try_set_mass(2.0).is_ok());
+        assert_eq!(body.mass(),2.0);
+    }
+
+    #[test]
+    fn test_command_errors() {
+        use crate::{AeroBody,AffectedBody};
+        use crate::inputs::ChannelRouter;
+
+        let router = ChannelRouter::new(vec![0.0,0.0]).route("elevator",1);
+        assert_eq!(router.try_inputs(&[("elevator",0.5)]),Ok(vec![0.0,0.5]));
+        assert_eq!(router.try_inputs(&[("elevatr",0.5)]),Err(Error::UnroutedCommand("elevatr".to_string())));
+
+        let mut vehicle: AffectedBody<Vec<f64>> = AffectedBody::new(AeroBody::new(Body::new_at_origin(1.0,Matrix3::identity())),vec![]);
+        assert_eq!(vehicle.try_step_commands(0.01,&[("elevator",0.5)]),Err(Error::NoRouter));
+        // Nothing is stepped on failure
+        assert_eq!(vehicle.position(),Vector3::zeros());
+        let mut vehicle = vehicle.with_router(router);
+        assert!(vehicle.try_step_commands(0.01,&[("rudder",0.5)]).is_err());
+        assert!(vehicle.try_step_commands(0.01,&[("elevator",0.5)]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use crate::schedule::InputSchedule;
+        use crate::replay::ReplayToken;
+
+        let error = InputSchedule::<Vec<f64>>::from_csv(vec![0.0],"").err().unwrap();
+        assert_eq!(error,Error::Parse("Missing CSV header".to_string()));
+        assert_eq!(error.to_string(),"Missing CSV header");
+        assert!(matches!(ReplayToken::<f64>::parse("seed=1"),Err(Error::Parse(_))));
+
+        let error = Error::TrimNotConverged { residual: 0.5, iterations: 20 };
+        assert_eq!(error.to_string(),"trim did not converge: residual acceleration 0.5 after 20 iterations");
+    }
+}
@@ -0,0 +1,827 @@
+//! State estimation from sensor measurements
+//!
+//! An [Estimator] fuses the built-in [sensors](crate::sensors) into an estimate of the rigid-body state,
+//! so that guidance and control can be tested against the estimated rather than the true state. Both
+//! estimators are strapdown navigation filters: the [ImuMeasurement]s drive the prediction, integrating
+//! the specific force and rotation rates, and [GpsMeasurement]s, [BaroMeasurement]s and
+//! [MagnetometerMeasurement]s correct it.
+//!
+//! The filters track the North-East-Down position and velocity and the attitude. The attitude is carried
+//! as a quaternion, and its uncertainty as a small world-frame rotation, so the [ErrorCovariance] is 9×9
+//! over `[δposition, δvelocity, δattitude]`. The [Ekf] linearises the dynamics and measurements about the
+//! estimate, and the [Ukf] propagates sigma points through them instead. Sensor biases are not estimated,
+//! and the rates of the estimated [StateVector] are those of the latest IMU measurement.
+//!
+//! For a realistic attitude-estimate error without a full navigation filter, the lightweight
+//! [ComplementaryFilter] estimates the attitude and gyro bias alone from the IMU and magnetometer.
+//!
+//! ```
+//! use aerso::estimation::{Estimator,Ekf,FilterConfig,ErrorCovariance};
+//! use aerso::geodetic::{Geodetic,LocalTangentPlane};
+//! use aerso::sensors::ImuMeasurement;
+//! use aerso::types::{StateVector,Vector3};
+//!
+//! let plane = LocalTangentPlane::new(Geodetic::from_degrees(51.5,-1.25,80.0));
+//! let mut initial = StateVector::zeros();
+//! initial[9] = 1.0;
+//! let mut filter = Ekf::new(FilterConfig::new(plane),0.0,&initial,ErrorCovariance::identity());
+//! // At rest and level, the accelerometer reads -g
+//! filter.predict(&ImuMeasurement { time: 0.01, rates: Vector3::zeros(), specific_force: Vector3::new(0.0,0.0,-9.80665) });
+//! assert!(filter.estimate().state.fixed_rows::<3>(3).norm() < 1e-9);
+//! ```
+
+use nalgebra::{SMatrix,SVector,DMatrix,DVector};
+
+use crate::geodetic::LocalTangentPlane;
+use crate::sensors::{ImuMeasurement,GpsMeasurement,BaroMeasurement,MagnetometerMeasurement};
+use crate::types::{Vector3,Matrix3,UnitQuaternion,StateVector,Float,DefaultFloatRepr,constant};
+
+/// Covariance of the `[δposition, δvelocity, δattitude]` error state of an [Estimator]
+pub type ErrorCovariance<T = DefaultFloatRepr> = SMatrix<T,9,9>;
+
+/// Error state `[δposition, δvelocity, δattitude]`
+type ErrorState<T> = SVector<T,9>;
+
+/// Estimated state of an [Estimator]
+#[derive(Copy,Clone,Debug)]
+pub struct Estimate<T: Float = DefaultFloatRepr> {
+    /// Time of the estimate (s)
+    pub time: T,
+    /// Estimated statevector, in the order \[position,velocity(body),attitude_quaternion(i,j,k,w),axis_rates(body)\]
+    pub state: StateVector<T>,
+    /// Covariance of the `[δposition, δvelocity, δattitude]` errors, with the velocity in the world frame
+    pub covariance: ErrorCovariance<T>,
+}
+
+impl<T: Float> Estimate<T> {
+    /// Return the standard deviation of the position error along each world axis (m)
+    pub fn position_sigma(&self) -> Vector3<T> {
+        Vector3::from_fn(|idx, _| <T as num_traits::Float>::sqrt(self.covariance[(idx,idx)]))
+    }
+
+    /// Return the standard deviation of the attitude error about each world axis (rad)
+    pub fn attitude_sigma(&self) -> Vector3<T> {
+        Vector3::from_fn(|idx, _| <T as num_traits::Float>::sqrt(self.covariance[(idx + 6,idx + 6)]))
+    }
+}
+
+/// Sensor noise and reference models shared by the [Ekf] and [Ukf]
+#[derive(Copy,Clone,Debug)]
+pub struct FilterConfig<T: Float = DefaultFloatRepr> {
+    /// Tangent plane of the GPS positions
+    plane: LocalTangentPlane<T>,
+    /// Accelerometer noise density (m·s<sup>-2</sup>·s<sup>½</sup>)
+    accelerometer_noise: T,
+    /// Gyro noise density (rad·s<sup>-1</sup>·s<sup>½</sup>)
+    gyro_noise: T,
+    /// Standard deviation of the GPS position (m)
+    gps_position_noise: T,
+    /// Standard deviation of the GPS velocity (m·s<sup>-1</sup>)
+    gps_velocity_noise: T,
+    /// Standard deviation of the barometric altitude (m)
+    baro_noise: T,
+    /// Barometric altitude of the world origin (m)
+    baro_reference: T,
+    /// World-frame magnetic field (nT)
+    magnetic_field: Vector3<T>,
+    /// Standard deviation of each magnetometer axis (nT)
+    magnetometer_noise: T,
+}
+
+impl<T: Float> FilterConfig<T> {
+    /// Create a new [FilterConfig] with GPS positions on `plane`
+    ///
+    /// The defaults suit a small consumer-grade navigation system: accelerometer and gyro noise densities
+    /// of 0.05 m·s<sup>-2</sup>·s<sup>½</sup> and 0.005 rad·s<sup>-1</sup>·s<sup>½</sup>, GPS errors of
+    /// 2 m and 0.1 m·s<sup>-1</sup>, a barometric error of 1 m with the world origin at zero altitude, and
+    /// a 50000 nT field pointing north and 60° down with an error of 100 nT.
+    pub fn new(plane: LocalTangentPlane<T>) -> Self {
+        let (sin_dip, cos_dip) = <T as num_traits::Float>::sin_cos(constant::<T>(60.0_f64.to_radians()));
+        FilterConfig {
+            plane,
+            accelerometer_noise: constant::<T>(0.05),
+            gyro_noise: constant::<T>(0.005),
+            gps_position_noise: constant::<T>(2.0),
+            gps_velocity_noise: constant::<T>(0.1),
+            baro_noise: T::one(),
+            baro_reference: T::zero(),
+            magnetic_field: Vector3::new(cos_dip,T::zero(),sin_dip) * constant::<T>(50000.0),
+            magnetometer_noise: constant::<T>(100.0),
+        }
+    }
+
+    /// Set the accelerometer (m·s<sup>-2</sup>·s<sup>½</sup>) and gyro (rad·s<sup>-1</sup>·s<sup>½</sup>) noise densities
+    pub fn with_imu_noise(mut self, accelerometer: T, gyro: T) -> Self {
+        self.accelerometer_noise = accelerometer;
+        self.gyro_noise = gyro;
+        self
+    }
+
+    /// Set the standard deviations of the GPS position (m) and velocity (m·s<sup>-1</sup>)
+    pub fn with_gps_noise(mut self, position: T, velocity: T) -> Self {
+        self.gps_position_noise = position;
+        self.gps_velocity_noise = velocity;
+        self
+    }
+
+    /// Set the standard deviation of the barometric altitude (m), and the barometric altitude of the world origin (m)
+    pub fn with_baro(mut self, noise: T, reference: T) -> Self {
+        self.baro_noise = noise;
+        self.baro_reference = reference;
+        self
+    }
+
+    /// Set the world-frame magnetic `field` (nT) and the standard deviation of each magnetometer axis (nT)
+    pub fn with_magnetic_field(mut self, field: Vector3<T>, noise: T) -> Self {
+        self.magnetic_field = field;
+        self.magnetometer_noise = noise;
+        self
+    }
+
+    /// Return the process noise covariance over `delta_t`
+    fn process_noise(&self, delta_t: T) -> ErrorCovariance<T> {
+        let mut noise = ErrorCovariance::zeros();
+        let velocity = self.accelerometer_noise * self.accelerometer_noise * delta_t;
+        let attitude = self.gyro_noise * self.gyro_noise * delta_t;
+        for idx in 0..3 {
+            noise[(idx + 3,idx + 3)] = velocity;
+            noise[(idx + 6,idx + 6)] = attitude;
+        }
+        noise
+    }
+
+    /// Return the [Observation] of `measurement`
+    fn gps(&self, measurement: &GpsMeasurement<T>) -> Observation<T> {
+        let position = self.plane.to_local(&measurement.position);
+        let mut noise = DMatrix::zeros(6,6);
+        for idx in 0..3 {
+            noise[(idx,idx)] = self.gps_position_noise * self.gps_position_noise;
+            noise[(idx + 3,idx + 3)] = self.gps_velocity_noise * self.gps_velocity_noise;
+        }
+        Observation {
+            model: ObservationModel::Gps,
+            measured: DVector::from_iterator(6,position.iter().chain(measurement.velocity.iter()).copied()),
+            noise,
+        }
+    }
+
+    /// Return the [Observation] of `measurement`
+    fn barometer(&self, measurement: &BaroMeasurement<T>) -> Observation<T> {
+        Observation {
+            model: ObservationModel::Altitude(self.baro_reference),
+            measured: DVector::from_element(1,measurement.altitude),
+            noise: DMatrix::from_element(1,1,self.baro_noise * self.baro_noise),
+        }
+    }
+
+    /// Return the [Observation] of `measurement`
+    fn magnetometer(&self, measurement: &MagnetometerMeasurement<T>) -> Observation<T> {
+        Observation {
+            model: ObservationModel::Magnetic(self.magnetic_field),
+            measured: DVector::from_iterator(3,measurement.field.iter().copied()),
+            noise: DMatrix::identity(3,3) * (self.magnetometer_noise * self.magnetometer_noise),
+        }
+    }
+}
+
+/// Navigation state of a strapdown filter
+#[derive(Copy,Clone,Debug)]
+struct NavState<T: Float> {
+    /// North-East-Down position (m)
+    position: Vector3<T>,
+    /// North-East-Down velocity (m·s<sup>-1</sup>)
+    velocity: Vector3<T>,
+    /// Rotation from body to world axes
+    attitude: UnitQuaternion<T>,
+}
+
+impl<T: Float> NavState<T> {
+    /// Return the navigation state of `state`
+    fn from_statevector(state: &StateVector<T>) -> Self {
+        let attitude = UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(state[9],state[6],state[7],state[8]));
+        NavState {
+            position: state.fixed_rows::<3>(0).into(),
+            velocity: attitude * Vector3::new(state[3],state[4],state[5]),
+            attitude,
+        }
+    }
+
+    /// Return the statevector with body `rates`
+    fn statevector(&self, rates: &Vector3<T>) -> StateVector<T> {
+        let velocity = self.attitude.inverse_transform_vector(&self.velocity);
+        let quaternion = self.attitude.quaternion();
+        let mut state = StateVector::zeros();
+        state.fixed_rows_mut::<3>(0).copy_from(&self.position);
+        state.fixed_rows_mut::<3>(3).copy_from(&velocity);
+        state.fixed_rows_mut::<4>(6).copy_from(&quaternion.coords);
+        state.fixed_rows_mut::<3>(10).copy_from(rates);
+        state
+    }
+
+    /// Return the state displaced by `error`
+    fn perturbed(&self, error: &ErrorState<T>) -> Self {
+        NavState {
+            position: self.position + error.fixed_rows::<3>(0),
+            velocity: self.velocity + error.fixed_rows::<3>(3),
+            attitude: UnitQuaternion::from_scaled_axis(Vector3::new(error[6],error[7],error[8])) * self.attitude,
+        }
+    }
+
+    /// Return the error of the state from `reference`
+    fn error(&self, reference: &Self) -> ErrorState<T> {
+        let mut error = ErrorState::zeros();
+        error.fixed_rows_mut::<3>(0).copy_from(&(self.position - reference.position));
+        error.fixed_rows_mut::<3>(3).copy_from(&(self.velocity - reference.velocity));
+        error.fixed_rows_mut::<3>(6).copy_from(&(self.attitude * reference.attitude.inverse()).scaled_axis());
+        error
+    }
+
+    /// Return the world-frame acceleration for the body `specific_force`
+    fn acceleration(&self, specific_force: &Vector3<T>) -> Vector3<T> {
+        self.attitude * specific_force + Vector3::new(T::zero(),T::zero(),constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY))
+    }
+
+    /// Return the state after `delta_t` with the IMU `measurement`
+    fn propagate(&self, measurement: &ImuMeasurement<T>, delta_t: T) -> Self {
+        let acceleration = self.acceleration(&measurement.specific_force);
+        NavState {
+            position: self.position + self.velocity * delta_t + acceleration * (constant::<T>(0.5) * delta_t * delta_t),
+            velocity: self.velocity + acceleration * delta_t,
+            attitude: self.attitude * UnitQuaternion::from_scaled_axis(measurement.rates * delta_t),
+        }
+    }
+}
+
+/// Measurement model of an [Observation]
+#[derive(Copy,Clone,Debug)]
+enum ObservationModel<T: Float> {
+    /// North-East-Down position and velocity
+    Gps,
+    /// Altitude above the level of the given world-origin altitude
+    Altitude(T),
+    /// Body-frame magnetic field of the given world-frame field
+    Magnetic(Vector3<T>),
+}
+
+/// Measurement with its model and noise covariance
+struct Observation<T: Float> {
+    /// Measurement model
+    model: ObservationModel<T>,
+    /// Measured values
+    measured: DVector<T>,
+    /// Measurement noise covariance
+    noise: DMatrix<T>,
+}
+
+impl<T: Float> Observation<T> {
+    /// Return the measurement predicted at `state`
+    fn predict(&self, state: &NavState<T>) -> DVector<T> {
+        match self.model {
+            ObservationModel::Gps => DVector::from_iterator(6,state.position.iter().chain(state.velocity.iter()).copied()),
+            ObservationModel::Altitude(reference) => DVector::from_element(1,reference - state.position[2]),
+            ObservationModel::Magnetic(field) => {
+                let body = state.attitude.inverse_transform_vector(&field);
+                DVector::from_iterator(3,body.iter().copied())
+            },
+        }
+    }
+
+    /// Return the Jacobian of the measurement with respect to the error state at `state`
+    fn jacobian(&self, state: &NavState<T>) -> DMatrix<T> {
+        match self.model {
+            ObservationModel::Gps => DMatrix::identity(6,9),
+            ObservationModel::Altitude(_) => {
+                let mut jacobian = DMatrix::zeros(1,9);
+                jacobian[(0,2)] = -T::one();
+                jacobian
+            },
+            ObservationModel::Magnetic(field) => {
+                // The body field is Rᵀ(I - [δθ]×)B, so it changes by Rᵀ[B]× δθ
+                let block = state.attitude.to_rotation_matrix().matrix().transpose() * field.cross_matrix();
+                let mut jacobian = DMatrix::zeros(3,9);
+                jacobian.slice_mut((0,6),(3,3)).copy_from(&block);
+                jacobian
+            },
+        }
+    }
+}
+
+/// Navigation filter estimating the rigid-body state from sensor measurements
+///
+/// Measurements must be given in time order. Each IMU measurement advances the estimate to its time,
+/// and the corrections apply at the time of the estimate.
+pub trait Estimator<T: Float = DefaultFloatRepr> {
+    /// Advance the estimate to the time of the IMU `measurement`, integrating its specific force and rates
+    fn predict(&mut self, measurement: &ImuMeasurement<T>);
+
+    /// Correct the estimate with a GPS `measurement`
+    fn correct_gps(&mut self, measurement: &GpsMeasurement<T>);
+
+    /// Correct the estimate with a barometer `measurement`
+    fn correct_barometer(&mut self, measurement: &BaroMeasurement<T>);
+
+    /// Correct the estimate with a magnetometer `measurement`
+    fn correct_magnetometer(&mut self, measurement: &MagnetometerMeasurement<T>);
+
+    /// Return the current [Estimate]
+    fn estimate(&self) -> Estimate<T>;
+}
+
+/// Common state of the [Ekf] and [Ukf]
+#[derive(Copy,Clone,Debug)]
+struct FilterState<T: Float> {
+    /// Sensor noise and reference models
+    config: FilterConfig<T>,
+    /// Time of the estimate (s)
+    time: T,
+    /// Estimated navigation state
+    state: NavState<T>,
+    /// Error covariance
+    covariance: ErrorCovariance<T>,
+    /// Body rates of the latest IMU measurement (rad·s<sup>-1</sup>)
+    rates: Vector3<T>,
+}
+
+impl<T: Float> FilterState<T> {
+    fn new(config: FilterConfig<T>, time: T, state: &StateVector<T>, covariance: ErrorCovariance<T>) -> Self {
+        FilterState {
+            config,
+            time,
+            state: NavState::from_statevector(state),
+            covariance,
+            rates: state.fixed_rows::<3>(10).into(),
+        }
+    }
+
+    /// Record the time and rates of `measurement`, returning the time step to it
+    fn advance(&mut self, measurement: &ImuMeasurement<T>) -> T {
+        let delta_t = measurement.time - self.time;
+        self.time = measurement.time;
+        self.rates = measurement.rates;
+        delta_t
+    }
+
+    fn estimate(&self) -> Estimate<T> {
+        Estimate { time: self.time, state: self.state.statevector(&self.rates), covariance: self.covariance }
+    }
+
+    /// Displace the estimate by the error-state `correction`
+    fn apply(&mut self, correction: &DVector<T>) {
+        let correction = ErrorState::from_iterator(correction.iter().copied());
+        self.state = self.state.perturbed(&correction);
+    }
+}
+
+/// Extended Kalman filter over the navigation state
+///
+/// The error dynamics are linearised about the estimate over each IMU step, and each measurement about
+/// the predicted estimate. The covariance is corrected in Joseph form, which keeps it symmetric and
+/// positive definite.
+#[derive(Copy,Clone,Debug)]
+pub struct Ekf<T: Float = DefaultFloatRepr> {
+    /// Filter state
+    filter: FilterState<T>,
+}
+
+impl<T: Float> Ekf<T> {
+    /// Create a new [Ekf] at `time` (s) with the initial `state` and error `covariance`
+    pub fn new(config: FilterConfig<T>, time: T, state: &StateVector<T>, covariance: ErrorCovariance<T>) -> Self {
+        Ekf { filter: FilterState::new(config,time,state,covariance) }
+    }
+
+    /// Correct the estimate with `observation`
+    fn correct(&mut self, observation: &Observation<T>) {
+        let filter = &mut self.filter;
+        let covariance = DMatrix::from_iterator(9,9,filter.covariance.iter().copied());
+        let jacobian = observation.jacobian(&filter.state);
+        let innovation = &observation.measured - observation.predict(&filter.state);
+        let innovation_covariance = &jacobian * &covariance * jacobian.transpose() + &observation.noise;
+        let inverse = match innovation_covariance.try_inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let gain = &covariance * jacobian.transpose() * inverse;
+        filter.apply(&(&gain * innovation));
+        let joseph = DMatrix::identity(9,9) - &gain * &jacobian;
+        let covariance = &joseph * covariance * joseph.transpose() + &gain * &observation.noise * gain.transpose();
+        filter.covariance = ErrorCovariance::from_iterator(covariance.iter().copied());
+    }
+}
+
+impl<T: Float> Estimator<T> for Ekf<T> {
+    fn predict(&mut self, measurement: &ImuMeasurement<T>) {
+        let filter = &mut self.filter;
+        let delta_t = filter.advance(measurement);
+        // δv̇ = -[R f]× δθ, and the world-frame attitude error is constant
+        let mut transition = ErrorCovariance::identity();
+        transition.fixed_slice_mut::<3,3>(0,3).copy_from(&(Matrix3::identity() * delta_t));
+        let force = filter.state.attitude * measurement.specific_force;
+        transition.fixed_slice_mut::<3,3>(3,6).copy_from(&(-force.cross_matrix() * delta_t));
+        filter.state = filter.state.propagate(measurement,delta_t);
+        filter.covariance = transition * filter.covariance * transition.transpose() + filter.config.process_noise(delta_t);
+    }
+
+    fn correct_gps(&mut self, measurement: &GpsMeasurement<T>) {
+        self.correct(&self.filter.config.gps(measurement));
+    }
+
+    fn correct_barometer(&mut self, measurement: &BaroMeasurement<T>) {
+        self.correct(&self.filter.config.barometer(measurement));
+    }
+
+    fn correct_magnetometer(&mut self, measurement: &MagnetometerMeasurement<T>) {
+        self.correct(&self.filter.config.magnetometer(measurement));
+    }
+
+    fn estimate(&self) -> Estimate<T> {
+        self.filter.estimate()
+    }
+}
+
+/// Unscented Kalman filter over the navigation state
+///
+/// Sigma points are spread about the estimate in the error state, propagated through the strapdown
+/// dynamics and the measurement models, and recombined. The spread follows the scaled unscented
+/// transform with parameters `(α, β, κ)`, which default to `(1, 0, 0)` so that every weight is positive.
+#[derive(Copy,Clone,Debug)]
+pub struct Ukf<T: Float = DefaultFloatRepr> {
+    /// Filter state
+    filter: FilterState<T>,
+    /// Sigma point spread `α`
+    alpha: T,
+    /// Prior distribution parameter `β`
+    beta: T,
+    /// Secondary scaling `κ`
+    kappa: T,
+}
+
+impl<T: Float> Ukf<T> {
+    /// Create a new [Ukf] at `time` (s) with the initial `state` and error `covariance`
+    pub fn new(config: FilterConfig<T>, time: T, state: &StateVector<T>, covariance: ErrorCovariance<T>) -> Self {
+        Ukf { filter: FilterState::new(config,time,state,covariance), alpha: T::one(), beta: T::zero(), kappa: T::zero() }
+    }
+
+    /// Set the unscented transform parameters `α`, `β` and `κ`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sigma points would not be spread, when `α²(9 + κ)` is not positive.
+    pub fn with_scaling(mut self, alpha: T, beta: T, kappa: T) -> Self {
+        if alpha * alpha * (constant::<T>(9.0) + kappa) <= T::zero() {
+            panic!("Unscented transform spread must be positive");
+        }
+        self.alpha = alpha;
+        self.beta = beta;
+        self.kappa = kappa;
+        self
+    }
+
+    /// Return the scaling `n + λ` and the mean and covariance weights of the centre and other sigma points
+    fn weights(&self) -> (T,(T,T),T) {
+        let n = constant::<T>(9.0);
+        let scale = self.alpha * self.alpha * (n + self.kappa);
+        let lambda = scale - n;
+        let centre_mean = lambda / scale;
+        let centre_covariance = centre_mean + T::one() - self.alpha * self.alpha + self.beta;
+        (scale,(centre_mean,centre_covariance),T::one() / (constant::<T>(2.0) * scale))
+    }
+
+    /// Return the error-state offsets of the sigma points, with the centre first
+    ///
+    /// Returns `None` if the covariance is not positive definite.
+    fn sigma_offsets(&self, scale: T) -> Option<Vec<ErrorState<T>>> {
+        let root = (self.filter.covariance * scale).cholesky()?.l();
+        let mut offsets = vec![ErrorState::zeros()];
+        for column in root.column_iter() {
+            offsets.push(column.into_owned());
+            offsets.push(-column.into_owned());
+        }
+        Some(offsets)
+    }
+
+    /// Correct the estimate with `observation`
+    fn correct(&mut self, observation: &Observation<T>) {
+        let (scale, (centre_mean, centre_covariance), weight) = self.weights();
+        let offsets = match self.sigma_offsets(scale) {
+            Some(offsets) => offsets,
+            None => return,
+        };
+        let mean_weight = |idx: usize| if idx == 0 { centre_mean } else { weight };
+        let covariance_weight = |idx: usize| if idx == 0 { centre_covariance } else { weight };
+
+        let state = self.filter.state;
+        let predictions: Vec<DVector<T>> = offsets.iter().map(|offset| observation.predict(&state.perturbed(offset))).collect();
+        let mean = predictions.iter().enumerate().fold(DVector::zeros(observation.measured.len()),|mean, (idx, prediction)| mean + prediction * mean_weight(idx));
+
+        let mut innovation_covariance = observation.noise.clone();
+        let mut cross_covariance = DMatrix::zeros(9,observation.measured.len());
+        for (idx, (offset, prediction)) in offsets.iter().zip(predictions.iter()).enumerate() {
+            let deviation = prediction - &mean;
+            innovation_covariance += &deviation * deviation.transpose() * covariance_weight(idx);
+            cross_covariance += DVector::from_iterator(9,offset.iter().copied()) * deviation.transpose() * covariance_weight(idx);
+        }
+        let inverse = match innovation_covariance.clone().try_inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let gain = cross_covariance * inverse;
+        self.filter.apply(&(&gain * (&observation.measured - mean)));
+        let reduction = &gain * innovation_covariance * gain.transpose();
+        self.filter.covariance -= ErrorCovariance::from_iterator(reduction.iter().copied());
+    }
+}
+
+impl<T: Float> Estimator<T> for Ukf<T> {
+    fn predict(&mut self, measurement: &ImuMeasurement<T>) {
+        let (scale, (centre_mean, centre_covariance), weight) = self.weights();
+        let offsets = self.sigma_offsets(scale);
+        let delta_t = self.filter.advance(measurement);
+        let state = self.filter.state;
+        let centre = state.propagate(measurement,delta_t);
+        let offsets = match offsets {
+            Some(offsets) => offsets,
+            None => {
+                self.filter.state = centre;
+                return;
+            },
+        };
+
+        let errors: Vec<ErrorState<T>> = offsets.iter().map(|offset| state.perturbed(offset).propagate(measurement,delta_t).error(&centre)).collect();
+        let mean = errors.iter().enumerate().fold(ErrorState::zeros(),|mean, (idx, error)| mean + error * if idx == 0 { centre_mean } else { weight });
+        let mut covariance = self.filter.config.process_noise(delta_t);
+        for (idx, error) in errors.iter().enumerate() {
+            let deviation = error - mean;
+            covariance += deviation * deviation.transpose() * if idx == 0 { centre_covariance } else { weight };
+        }
+        self.filter.state = centre.perturbed(&mean);
+        self.filter.covariance = covariance;
+    }
+
+    fn correct_gps(&mut self, measurement: &GpsMeasurement<T>) {
+        self.correct(&self.filter.config.gps(measurement));
+    }
+
+    fn correct_barometer(&mut self, measurement: &BaroMeasurement<T>) {
+        self.correct(&self.filter.config.barometer(measurement));
+    }
+
+    fn correct_magnetometer(&mut self, measurement: &MagnetometerMeasurement<T>) {
+        self.correct(&self.filter.config.magnetometer(measurement));
+    }
+
+    fn estimate(&self) -> Estimate<T> {
+        self.filter.estimate()
+    }
+}
+
+/// Mahony-style complementary filter estimating the attitude and gyro bias
+///
+/// The gyro rates are integrated, and corrected towards the gravity direction measured by the
+/// accelerometer with proportional gain `k_P` and integral gain `k_I`, the integral term estimating the
+/// gyro bias. The accelerometer is only trusted when the specific force is within a tolerance of `g`, so
+/// manoeuvres cause realistic attitude drift. Each magnetometer measurement corrects the heading alone,
+/// through the horizontal component of the field, by a fraction `1 - exp(-k_P Δt)` of the heading error
+/// for the time `Δt` since the previous measurement.
+#[derive(Copy,Clone,Debug)]
+pub struct ComplementaryFilter<T: Float = DefaultFloatRepr> {
+    /// Time of the estimate (s)
+    time: T,
+    /// Time of the previous magnetometer measurement (s)
+    magnetometer_time: T,
+    /// Estimated rotation from body to world axes
+    attitude: UnitQuaternion<T>,
+    /// Estimated gyro bias (rad·s<sup>-1</sup>)
+    bias: Vector3<T>,
+    /// Proportional gain (rad·s<sup>-1</sup>)
+    proportional: T,
+    /// Integral gain (s<sup>-2</sup>)
+    integral: T,
+    /// Largest difference of the specific force from `g` at which the accelerometer is used (m·s<sup>-2</sup>)
+    gate: T,
+    /// World-frame magnetic field direction
+    magnetic_field: Vector3<T>,
+}
+
+impl<T: Float> ComplementaryFilter<T> {
+    /// Create a new [ComplementaryFilter] at `time` (s) with the initial `attitude` and no gyro bias
+    ///
+    /// The gains default to `k_P = 1` rad·s<sup>-1</sup> and `k_I = 0.05` s<sup>-2</sup>, the accelerometer is
+    /// used within 1 m·s<sup>-2</sup> of `g`, and the field points north and 60° down.
+    pub fn new(time: T, attitude: UnitQuaternion<T>) -> Self {
+        let (sin_dip, cos_dip) = <T as num_traits::Float>::sin_cos(constant::<T>(60.0_f64.to_radians()));
+        ComplementaryFilter {
+            time,
+            magnetometer_time: time,
+            attitude,
+            bias: Vector3::zeros(),
+            proportional: T::one(),
+            integral: constant::<T>(0.05),
+            gate: T::one(),
+            magnetic_field: Vector3::new(cos_dip,T::zero(),sin_dip),
+        }
+    }
+
+    /// Set the proportional (rad·s<sup>-1</sup>) and integral (s<sup>-2</sup>) gains
+    pub fn with_gains(mut self, proportional: T, integral: T) -> Self {
+        self.proportional = proportional;
+        self.integral = integral;
+        self
+    }
+
+    /// Use the accelerometer only when the specific force is within `gate` of `g` (m·s<sup>-2</sup>)
+    pub fn with_acceleration_gate(mut self, gate: T) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    /// Set the world-frame magnetic `field`, of which only the direction is used
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field has no horizontal component.
+    pub fn with_magnetic_field(mut self, field: Vector3<T>) -> Self {
+        if field[0] == T::zero() && field[1] == T::zero() {
+            panic!("Magnetic field must have a horizontal component");
+        }
+        self.magnetic_field = field.normalize();
+        self
+    }
+
+    /// Return the time of the estimate (s)
+    pub fn time(&self) -> T {
+        self.time
+    }
+
+    /// Return the estimated rotation from body to world axes
+    pub fn attitude(&self) -> UnitQuaternion<T> {
+        self.attitude
+    }
+
+    /// Return the estimated gyro bias (rad·s<sup>-1</sup>)
+    pub fn bias(&self) -> Vector3<T> {
+        self.bias
+    }
+
+    /// Advance the estimate to the time of the IMU `measurement`
+    pub fn update_imu(&mut self, measurement: &ImuMeasurement<T>) {
+        let delta_t = measurement.time - self.time;
+        self.time = measurement.time;
+        let gravity = constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY);
+        let magnitude = measurement.specific_force.norm();
+        let error = if magnitude > T::zero() && <T as num_traits::Float>::abs(magnitude - gravity) <= self.gate {
+            // Measured and predicted body-frame down directions
+            let measured = -measurement.specific_force / magnitude;
+            let predicted = self.attitude.inverse_transform_vector(&Vector3::z());
+            measured.cross(&predicted)
+        } else {
+            Vector3::zeros()
+        };
+        self.bias -= error * (self.integral * delta_t);
+        let rates = measurement.rates - self.bias + error * self.proportional;
+        self.attitude *= UnitQuaternion::from_scaled_axis(rates * delta_t);
+    }
+
+    /// Correct the heading with the magnetometer `measurement`
+    pub fn update_magnetometer(&mut self, measurement: &MagnetometerMeasurement<T>) {
+        let delta_t = measurement.time - self.magnetometer_time;
+        self.magnetometer_time = measurement.time;
+        let measured = self.attitude * measurement.field;
+        let measured = Vector3::new(measured[0],measured[1],T::zero());
+        let reference = Vector3::new(self.magnetic_field[0],self.magnetic_field[1],T::zero());
+        if measured.norm() == T::zero() {
+            return;
+        }
+        let (measured, reference) = (measured.normalize(),reference.normalize());
+        // Heading of the estimate east of the true heading
+        let heading_error = <T as num_traits::Float>::atan2(reference.cross(&measured)[2],reference.dot(&measured));
+        let fraction = T::one() - <T as num_traits::Float>::exp(-self.proportional * delta_t);
+        let correction = Vector3::new(T::zero(),T::zero(),-heading_error * fraction);
+        self.bias -= self.attitude.inverse_transform_vector(&correction) * self.integral / self.proportional;
+        self.attitude = UnitQuaternion::from_scaled_axis(correction) * self.attitude;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::geodetic::Geodetic;
+    use crate::sensors::{Truth,Sensor,Imu,Gps,Barometer,Magnetometer,ConstantField};
+
+    fn plane() -> LocalTangentPlane {
+        LocalTangentPlane::new(Geodetic::from_degrees(51.5,-1.25,80.0))
+    }
+
+    fn statevector(position: Vector3, attitude: UnitQuaternion) -> StateVector {
+        NavState { position, velocity: Vector3::zeros(), attitude }.statevector(&Vector3::zeros())
+    }
+
+    fn at_rest(time: f64, state: StateVector) -> Truth {
+        Truth {
+            time,
+            state,
+            airstate: airstate(0.0,0.0),
+            acceleration: Vector3::zeros(),
+            angular_acceleration: Vector3::zeros(),
+            wind: Vector3::zeros(),
+            pressure: 101325.0,
+            temperature: 288.15,
+        }
+    }
+
+    /// Run `filter` for 20 s on perfect sensors of a vehicle at rest 20 m north and yawed 30°, returning
+    /// the final estimate and the true state
+    fn converge<E: Estimator>(mut filter: E) -> (Estimate,StateVector) {
+        let config = FilterConfig::new(plane());
+        let truth_state = statevector(Vector3::new(20.0,-5.0,0.0),UnitQuaternion::from_euler_angles(0.0,0.0,0.5));
+        let mut imu = Imu::new(100.0,1);
+        let mut gps = Gps::new(5.0,plane(),2);
+        let mut barometer = Barometer::new(10.0,3);
+        let mut magnetometer = Magnetometer::new(ConstantField::new(config.magnetic_field),plane(),10.0,4);
+        for step in 1..=2000 {
+            let truth = at_rest(step as f64 * 0.01,truth_state);
+            if let Some(measurement) = imu.update(&truth) {
+                filter.predict(&measurement);
+            }
+            if let Some(measurement) = gps.update(&truth) {
+                filter.correct_gps(&measurement);
+            }
+            if let Some(measurement) = barometer.update(&truth) {
+                filter.correct_barometer(&measurement);
+            }
+            if let Some(measurement) = magnetometer.update(&truth) {
+                filter.correct_magnetometer(&measurement);
+            }
+        }
+        (filter.estimate(),truth_state)
+    }
+
+    fn initial() -> (StateVector,ErrorCovariance) {
+        let state = statevector(Vector3::new(30.0,0.0,3.0),UnitQuaternion::from_euler_angles(0.0,0.0,0.3));
+        let covariance = ErrorCovariance::from_diagonal(&SVector::<f64,9>::from_column_slice(&[100.0,100.0,100.0,1.0,1.0,1.0,0.1,0.1,0.1]));
+        (state,covariance)
+    }
+
+    fn check(estimate: &Estimate, truth: &StateVector) {
+        let estimated = NavState::from_statevector(&estimate.state);
+        let error = estimated.error(&NavState::from_statevector(truth));
+        assert!(error.fixed_rows::<3>(0).norm() < 0.5,"position error {}",error.fixed_rows::<3>(0).norm());
+        assert!(error.fixed_rows::<3>(3).norm() < 0.05,"velocity error {}",error.fixed_rows::<3>(3).norm());
+        assert!(error.fixed_rows::<3>(6).norm() < 0.01,"attitude error {}",error.fixed_rows::<3>(6).norm());
+        assert!(estimate.position_sigma().norm() < 2.0);
+        assert!(estimate.attitude_sigma().norm() < 0.05);
+        assert_eq!(estimate.time,20.0);
+    }
+
+    #[test]
+    fn test_ekf() {
+        let (state, covariance) = initial();
+        let (estimate, truth) = converge(Ekf::new(FilterConfig::new(plane()),0.0,&state,covariance));
+        check(&estimate,&truth);
+    }
+
+    #[test]
+    fn test_ukf() {
+        let (state, covariance) = initial();
+        let (estimate, truth) = converge(Ukf::new(FilterConfig::new(plane()),0.0,&state,covariance));
+        check(&estimate,&truth);
+    }
+
+    #[test]
+    fn test_complementary_filter() {
+        let attitude = UnitQuaternion::from_euler_angles(0.2,-0.1,0.5);
+        let truth_state = statevector(Vector3::zeros(),attitude);
+        let gyro_bias = Vector3::new(0.01,-0.005,0.008);
+        let mut imu = Imu::new(100.0,1);
+        let mut magnetometer = Magnetometer::new(ConstantField::new(Vector3::new(17000.0,-500.0,45000.0)),plane(),10.0,2);
+        let mut filter = ComplementaryFilter::new(0.0,UnitQuaternion::identity()).with_magnetic_field(Vector3::new(17000.0,-500.0,45000.0));
+        for step in 1..=12000 {
+            let truth = at_rest(step as f64 * 0.01,truth_state);
+            if let Some(mut measurement) = imu.update(&truth) {
+                measurement.rates += gyro_bias;
+                filter.update_imu(&measurement);
+            }
+            if let Some(measurement) = magnetometer.update(&truth) {
+                filter.update_magnetometer(&measurement);
+            }
+        }
+        assert_eq!(filter.time(),120.0);
+        assert!(filter.attitude().angle_to(&attitude) < 1e-3,"attitude error {}",filter.attitude().angle_to(&attitude));
+        assert!((filter.bias() - gyro_bias).norm() < 1e-4,"bias error {}",(filter.bias() - gyro_bias).norm());
+    }
+
+    #[test]
+    fn test_acceleration_gate() {
+        // Accelerating at 6 m/s² the accelerometer is ignored, so a roll error is held
+        let mut filter = ComplementaryFilter::new(0.0,UnitQuaternion::from_euler_angles(0.1,0.0,0.0));
+        for step in 1..=500 {
+            filter.update_imu(&ImuMeasurement { time: step as f64 * 0.01, rates: Vector3::zeros(), specific_force: Vector3::new(6.0,0.0,-9.80665) });
+        }
+        assert!((filter.attitude().euler_angles().0 - 0.1).abs() < 1e-12);
+        // and corrected once unaccelerated
+        for step in 501..=2000 {
+            filter.update_imu(&ImuMeasurement { time: step as f64 * 0.01, rates: Vector3::zeros(), specific_force: Vector3::new(0.0,0.0,-9.80665) });
+        }
+        assert!(filter.attitude().angle() < 0.01);
+    }
+}
@@ -0,0 +1,290 @@
+//! Fault injection
+//!
+//! A [FaultInjector] holds a list of [Fault]s, each activated by a [Trigger] at a scheduled time or on
+//! an event such as reaching an altitude. Active faults freeze, zero or offset actuator channels of the
+//! inputstate, disable effectors such as an engine, or corrupt and drop sensor measurements, so failure
+//! detection and degraded-mode logic can be exercised in simulation.
+//!
+//! Call [FaultInjector::update] at the start of each step to evaluate the triggers, then apply the
+//! active faults with [FaultInjector::apply_inputs], [FaultInjector::apply_effectors] and
+//! [FaultInjector::apply_measurement], or use [FaultInjector::step] to do all but the last.
+
+use std::any::Any;
+use std::ops::IndexMut;
+
+use crate::{AffectedBody,WindModel,DensityModel};
+use crate::sensors::Measurement;
+use crate::types::{StateView,Float,DefaultFloatRepr};
+
+/// Event condition of a [Trigger], called with the time and vehicle state
+pub type Condition<T> = Box<dyn FnMut(T,&dyn StateView<T>) -> bool>;
+
+/// Condition activating a [Fault]
+pub enum Trigger<T: Float = DefaultFloatRepr> {
+    /// Active from the given time (s) onwards
+    At(T),
+    /// Active from the first time (s) until the second
+    Between(T,T),
+    /// Active once the closure first returns `true` for the time and vehicle state, and thereafter
+    Event(Condition<T>),
+}
+
+impl<T: Float> Trigger<T> {
+    /// Create an [Event](Trigger::Event) trigger from `condition`
+    pub fn event<F: FnMut(T,&dyn StateView<T>) -> bool + 'static>(condition: F) -> Self {
+        Trigger::Event(Box::new(condition))
+    }
+}
+
+/// Failure of an actuator channel
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum ActuatorFault<T: Float = DefaultFloatRepr> {
+    /// Hold the command from when the fault became active
+    Freeze,
+    /// Hold the given value, such as a hard-over
+    Stuck(T),
+    /// Command zero
+    Zero,
+    /// Add the given offset to the command
+    Offset(T),
+}
+
+/// Failure of a sensor
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum SensorFault<T: Float = DefaultFloatRepr> {
+    /// Repeat the last measurement from before the fault became active
+    Freeze,
+    /// Report zero for every value
+    Zero,
+    /// Add the given offset to every value
+    Offset(T),
+    /// Lose every measurement
+    Dropout,
+}
+
+/// Fault injected by a [FaultInjector]
+#[derive(Clone,Debug,PartialEq)]
+pub enum Fault<T: Float = DefaultFloatRepr> {
+    /// Failure of an inputstate slot
+    Actuator {
+        /// Inputstate slot
+        slot: usize,
+        /// Failure mode
+        fault: ActuatorFault<T>,
+    },
+    /// Disable the named effector, such as an engine
+    KillEffector(String),
+    /// Failure of the named sensor
+    Sensor {
+        /// Sensor name, as passed to [FaultInjector::apply_measurement]
+        sensor: String,
+        /// Failure mode
+        fault: SensorFault<T>,
+    },
+}
+
+/// A [Fault] with its [Trigger] and state
+struct ScheduledFault<T: Float> {
+    /// Activating condition
+    trigger: Trigger<T>,
+    /// Injected fault
+    fault: Fault<T>,
+    /// Whether the fault is active
+    active: bool,
+    /// Whether an event trigger has fired
+    latched: bool,
+    /// Command held by a frozen actuator
+    frozen: Option<T>,
+    /// Whether the injector disabled an effector for this fault
+    disabled: bool,
+}
+
+/// Scheduler and injector of [Fault]s
+pub struct FaultInjector<T: Float = DefaultFloatRepr> {
+    /// Faults in the order they were added
+    faults: Vec<ScheduledFault<T>>,
+    /// Last measurement from each sensor before any fault, for frozen sensors
+    last_good: Vec<(String,Box<dyn Any>)>,
+}
+
+impl<T: Float> Default for FaultInjector<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> FaultInjector<T> {
+    /// Create a new [FaultInjector] with no faults
+    pub fn new() -> Self {
+        FaultInjector {
+            faults: Vec::new(),
+            last_good: Vec::new(),
+        }
+    }
+
+    /// Add `fault`, activated by `trigger`
+    pub fn with_fault(mut self, trigger: Trigger<T>, fault: Fault<T>) -> Self {
+        self.faults.push(ScheduledFault { trigger, fault, active: false, latched: false, frozen: None, disabled: false });
+        self
+    }
+
+    /// Evaluate the triggers at `time` (s) for the vehicle `state`
+    pub fn update(&mut self, time: T, state: &dyn StateView<T>) {
+        for scheduled in self.faults.iter_mut() {
+            scheduled.active = match &mut scheduled.trigger {
+                Trigger::At(start) => time >= *start,
+                Trigger::Between(start, end) => time >= *start && time < *end,
+                Trigger::Event(condition) => {
+                    scheduled.latched = scheduled.latched || condition(time,state);
+                    scheduled.latched
+                },
+            };
+            if !scheduled.active {
+                scheduled.frozen = None;
+            }
+        }
+    }
+
+    /// Return the active faults
+    pub fn active(&self) -> impl Iterator<Item=&Fault<T>> {
+        self.faults.iter().filter(|scheduled| scheduled.active).map(|scheduled| &scheduled.fault)
+    }
+
+    /// Apply the active actuator faults to `inputs`
+    pub fn apply_inputs<I: IndexMut<usize,Output=T>>(&mut self, inputs: &mut I) {
+        for scheduled in self.faults.iter_mut().filter(|scheduled| scheduled.active) {
+            if let Fault::Actuator { slot, fault } = scheduled.fault {
+                inputs[slot] = match fault {
+                    ActuatorFault::Freeze => *scheduled.frozen.get_or_insert(inputs[slot]),
+                    ActuatorFault::Stuck(value) => value,
+                    ActuatorFault::Zero => T::zero(),
+                    ActuatorFault::Offset(offset) => inputs[slot] + offset,
+                };
+            }
+        }
+    }
+
+    /// Disable the effectors of active effector faults on `vehicle`
+    ///
+    /// Effectors are enabled again when their fault is no longer active.
+    pub fn apply_effectors<I, W: WindModel<T>, D: DensityModel<T>>(&mut self, vehicle: &mut AffectedBody<I,T,W,D>) {
+        for scheduled in self.faults.iter_mut() {
+            if let Fault::KillEffector(name) = &scheduled.fault {
+                if scheduled.active && !scheduled.disabled {
+                    scheduled.disabled = vehicle.disable(name);
+                } else if !scheduled.active && scheduled.disabled {
+                    vehicle.enable(name);
+                    scheduled.disabled = false;
+                }
+            }
+        }
+    }
+
+    /// Apply the active faults of the sensor called `sensor` to its `measurement`
+    ///
+    /// Measurements are passed through unchanged while the sensor has no active fault. Call this with
+    /// every output of the sensor, including `None`, so that frozen sensors repeat at the sensor rate.
+    pub fn apply_measurement<M: Measurement<T> + 'static>(&mut self, sensor: &str, measurement: Option<M>) -> Option<M> {
+        let measurement = measurement?;
+        let mut faults = self.faults.iter()
+            .filter(|scheduled| scheduled.active)
+            .filter_map(|scheduled| match &scheduled.fault {
+                Fault::Sensor { sensor: name, fault } if name == sensor => Some(*fault),
+                _ => None,
+            })
+            .peekable();
+
+        if faults.peek().is_none() {
+            match self.last_good.iter_mut().find(|(name, _)| name == sensor) {
+                Some((_, last)) => *last = Box::new(measurement),
+                None => self.last_good.push((sensor.to_string(),Box::new(measurement))),
+            }
+            return Some(measurement);
+        }
+
+        let last_good = self.last_good.iter().find(|(name, _)| name == sensor).and_then(|(_, last)| last.downcast_ref::<M>()).copied();
+        faults.try_fold(measurement,|measurement, fault| match fault {
+            SensorFault::Freeze => Some(last_good.unwrap_or(measurement).retimed(measurement.time())),
+            SensorFault::Zero => Some(measurement.map_values(|_| T::zero())),
+            SensorFault::Offset(offset) => Some(measurement.map_values(|value| value + offset)),
+            SensorFault::Dropout => None,
+        })
+    }
+
+    /// Evaluate the triggers at `time` and step `vehicle` by `delta_t` with the faulted `inputs`
+    pub fn step<I: Clone + IndexMut<usize,Output=T>, W: WindModel<T>, D: DensityModel<T>>(&mut self, vehicle: &mut AffectedBody<I,T,W,D>, time: T, delta_t: T, inputs: &I) {
+        self.update(time,vehicle);
+        self.apply_effectors(vehicle);
+        let mut inputs = inputs.clone();
+        self.apply_inputs(&mut inputs);
+        vehicle.step(delta_t,&inputs);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sensors::{Sensor,Truth,Gps,GpsMeasurement};
+    use crate::geodetic::{Geodetic,LocalTangentPlane};
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_actuator_faults() {
+        let vehicle = test_aircraft::vehicle(100.0);
+        let mut injector = FaultInjector::new()
+            .with_fault(Trigger::At(1.0),Fault::Actuator { slot: 1, fault: ActuatorFault::Freeze })
+            .with_fault(Trigger::Between(0.5,1.5),Fault::Actuator { slot: 3, fault: ActuatorFault::Offset(0.25) });
+        let mut apply = |time: f64, elevator: f64| {
+            injector.update(time,&vehicle);
+            let mut inputs = vec![0.0,elevator,0.0,0.5];
+            injector.apply_inputs(&mut inputs);
+            inputs
+        };
+        assert_eq!(apply(0.0,0.1),vec![0.0,0.1,0.0,0.5]);
+        assert_eq!(apply(0.75,0.2),vec![0.0,0.2,0.0,0.75]);
+        assert_eq!(apply(1.0,0.3),vec![0.0,0.3,0.0,0.75]);
+        assert_eq!(apply(2.0,-0.4),vec![0.0,0.3,0.0,0.5]);
+    }
+
+    #[test]
+    fn test_engine_failure() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let mut injector = FaultInjector::new().with_fault(Trigger::Between(0.5,1.0),Fault::KillEffector("1".to_string()));
+        let inputs = vec![0.0,0.0,0.0,1.0];
+        for step in 0..150 {
+            let time = step as f64 * 0.01;
+            injector.step(&mut vehicle,time,0.01,&inputs);
+            let thrust = vehicle.effector_effect("1");
+            assert_eq!(thrust.is_none(),(0.5..1.0).contains(&time),"at {}",time);
+        }
+        assert_eq!(injector.active().count(),0);
+    }
+
+    #[test]
+    fn test_sensor_faults() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let mut gps = Gps::new(0.0,LocalTangentPlane::new(Geodetic::new(0.0,0.0,0.0)),1);
+        // The receiver freezes once the aircraft descends below 99 m, then drops out after 3 s
+        let mut injector = FaultInjector::new()
+            .with_fault(Trigger::event(|_, state: &dyn StateView| state.position()[2] > -99.0),Fault::Sensor { sensor: "gps".to_string(), fault: SensorFault::Freeze })
+            .with_fault(Trigger::At(3.0),Fault::Sensor { sensor: "gps".to_string(), fault: SensorFault::Dropout });
+
+        let inputs = vec![0.0,0.05,0.0,0.0];
+        let mut outputs: Vec<(f64,Option<GpsMeasurement>)> = Vec::new();
+        for step in 0..400 {
+            let time = step as f64 * 0.01;
+            injector.update(time,&vehicle);
+            let measurement = gps.update(&Truth::new(time,&vehicle));
+            outputs.push((vehicle.position()[2],injector.apply_measurement("gps",measurement)));
+            vehicle.step(0.01,&inputs);
+        }
+
+        let frozen: Vec<&GpsMeasurement> = outputs.iter().filter(|(z, _)| *z > -99.0).filter_map(|(_, measurement)| measurement.as_ref()).collect();
+        assert!(frozen.len() > 2);
+        assert!(frozen.iter().all(|measurement| measurement.position == frozen[0].position));
+        assert_relative_eq!(frozen[0].position.altitude,99.0,epsilon=0.1);
+        assert!(frozen[1].time > frozen[0].time);
+        assert!(outputs[300..].iter().all(|(_, measurement)| measurement.is_none()));
+    }
+}
@@ -0,0 +1,323 @@
+//! C foreign function interface
+//!
+//! Enabled by the `ffi` feature, this module exposes a stable C ABI for embedding the dynamics in C
+//! and C++ hosts. An opaque [AersoBody] is created with [aerso_body_new] and stepped with [aerso_step],
+//! and states are exchanged as flat `double` arrays with the usual layouts:
+//!
+//! * statevector, 13 elements: `x,y,z,u,v,w,qx,qy,qz,qw,p,q,r`
+//! * airstate, 6 elements: `alpha,beta,airspeed,q,mach,density`
+//!
+//! The body flies through the ISA standard atmosphere with a constant wind, zero by default. Effectors
+//! are C callbacks added with [aerso_body_add_effector]. The header `include/aerso.h` is generated with
+//! `cbindgen`, configured by `cbindgen.toml`, and the library is built with, for example,
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! Functions which take a body return an [AersoStatus], and never unwind into the caller.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char,c_int,c_void};
+use std::panic::{catch_unwind,AssertUnwindSafe};
+
+use crate::{Body,AeroBody,AffectedBody,AeroEffect,AirState};
+use crate::aero::StandardAtmosphere;
+use crate::types::{Vector3,Matrix3,StateVector,StateView,Force,Torque};
+use crate::wind_models::ConstantWind;
+
+/// Status returned by the C interface
+#[repr(C)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum AersoStatus {
+    /// Success
+    AersoOk = 0,
+    /// A required pointer was null
+    AersoNullPointer = 1,
+    /// An argument was invalid, such as a duplicate effector name
+    AersoInvalidArgument = 2,
+    /// The simulation panicked, and the body should be freed
+    AersoPanic = 3,
+}
+
+/// Effector callback
+///
+/// Called with the user data, the airstate (6 elements), the body rates (3 elements) and the inputs,
+/// and writes the body-frame force (N) and torque (N·m) into the 3-element `force` and `torque` arrays.
+pub type AersoEffectFn = Option<unsafe extern "C" fn(user_data: *mut c_void, airstate: *const f64, rates: *const f64, inputs: *const f64, input_count: usize, force: *mut f64, torque: *mut f64)>;
+
+/// [AeroEffect] calling a C callback
+struct CallbackEffector {
+    /// Callback
+    callback: unsafe extern "C" fn(*mut c_void, *const f64, *const f64, *const f64, usize, *mut f64, *mut f64),
+    /// User data passed to the callback
+    user_data: *mut c_void,
+}
+
+impl AeroEffect<Vec<f64>,f64> for CallbackEffector {
+    fn get_effect(&self, airstate: AirState<f64>, rates: Vector3<f64>, inputstate: &Vec<f64>) -> (Force<f64>,Torque<f64>) {
+        let airstate = airstate_array(&airstate);
+        let mut force = [0.0;3];
+        let mut torque = [0.0;3];
+        // The callback contract is documented on aerso_body_add_effector
+        unsafe {
+            (self.callback)(self.user_data,airstate.as_ptr(),rates.as_ptr(),inputstate.as_ptr(),inputstate.len(),force.as_mut_ptr(),torque.as_mut_ptr());
+        }
+        (Force::body_vec(Vector3::from(force)),Torque::body_vec(Vector3::from(torque)))
+    }
+}
+
+/// Return the elements of `airstate` in C interface order
+fn airstate_array(airstate: &AirState<f64>) -> [f64;6] {
+    [airstate.alpha,airstate.beta,airstate.airspeed,airstate.q,airstate.mach,airstate.density]
+}
+
+/// Opaque vehicle handle
+pub struct AersoBody {
+    /// Wrapped vehicle
+    vehicle: AffectedBody<Vec<f64>,f64,ConstantWind<f64>,StandardAtmosphere<f64>>,
+}
+
+/// Return the library version as a static C string
+#[no_mangle]
+pub extern "C" fn aerso_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"),"\0").as_ptr() as *const c_char
+}
+
+/// Create a new body with no effectors, returning null if the arguments are invalid
+///
+/// # Safety
+///
+/// `inertia` must point to 9 elements of the row-major inertia matrix (kg·m²), and `state` to 13
+/// statevector elements.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_new(mass: f64, inertia: *const f64, state: *const f64) -> *mut AersoBody {
+    if inertia.is_null() || state.is_null() {
+        return std::ptr::null_mut();
+    }
+    let inertia = Matrix3::from_row_slice(std::slice::from_raw_parts(inertia,9));
+    let state = StateVector::from_column_slice(std::slice::from_raw_parts(state,13));
+    let body = catch_unwind(|| Body::new_from_statevector(mass,inertia,state));
+    match body {
+        Ok(body) => {
+            let vehicle = AffectedBody::new(AeroBody::with_density_model(body,ConstantWind::new(Vector3::zeros()),StandardAtmosphere::new()),Vec::new());
+            Box::into_raw(Box::new(AersoBody { vehicle }))
+        },
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free a body created by [aerso_body_new]
+///
+/// # Safety
+///
+/// `body` must be null or a body which has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_free(body: *mut AersoBody) {
+    if !body.is_null() {
+        drop(Box::from_raw(body));
+    }
+}
+
+/// Run `f` on the body behind `body`, catching panics
+unsafe fn with_body<F: FnOnce(&mut AersoBody) -> AersoStatus>(body: *mut AersoBody, f: F) -> AersoStatus {
+    match body.as_mut() {
+        Some(body) => catch_unwind(AssertUnwindSafe(|| f(body))).unwrap_or(AersoStatus::AersoPanic),
+        None => AersoStatus::AersoNullPointer,
+    }
+}
+
+/// Set a constant North-East-Down wind (m·s<sup>-1</sup>)
+///
+/// # Safety
+///
+/// `body` must be a live body, and `wind` must point to 3 elements.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_set_wind(body: *mut AersoBody, wind: *const f64) -> AersoStatus {
+    if wind.is_null() {
+        return AersoStatus::AersoNullPointer;
+    }
+    let wind = Vector3::from_column_slice(std::slice::from_raw_parts(wind,3));
+    with_body(body,|body| {
+        let kinematics = body.vehicle.body.body;
+        body.vehicle.body = AeroBody::with_density_model(kinematics,ConstantWind::new(wind),StandardAtmosphere::new());
+        AersoStatus::AersoOk
+    })
+}
+
+/// Add an effector called `name`, which calls `callback` with `user_data` each step
+///
+/// # Safety
+///
+/// `body` must be a live body and `name` a valid C string. The callback must write 3 elements to each of
+/// `force` and `torque`, must not unwind, and `user_data` must remain valid while the body is live.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_add_effector(body: *mut AersoBody, name: *const c_char, callback: AersoEffectFn, user_data: *mut c_void) -> AersoStatus {
+    let callback = match callback {
+        Some(callback) if !name.is_null() => callback,
+        _ => return AersoStatus::AersoNullPointer,
+    };
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(name) => name.to_string(),
+        Err(_) => return AersoStatus::AersoInvalidArgument,
+    };
+    with_body(body,|body| {
+        if body.vehicle.effectors.is_enabled(&name).is_some() {
+            return AersoStatus::AersoInvalidArgument;
+        }
+        body.vehicle.add_effector(&name,Box::new(CallbackEffector { callback, user_data }));
+        AersoStatus::AersoOk
+    })
+}
+
+/// Enable or disable the effector called `name`
+///
+/// # Safety
+///
+/// `body` must be a live body and `name` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_set_effector_enabled(body: *mut AersoBody, name: *const c_char, enabled: c_int) -> AersoStatus {
+    if name.is_null() {
+        return AersoStatus::AersoNullPointer;
+    }
+    let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+    with_body(body,|body| {
+        let found = if enabled != 0 { body.vehicle.enable(&name) } else { body.vehicle.disable(&name) };
+        if found { AersoStatus::AersoOk } else { AersoStatus::AersoInvalidArgument }
+    })
+}
+
+/// Propagate the body by `delta_t` (s) with `input_count` inputs
+///
+/// # Safety
+///
+/// `body` must be a live body, and `inputs` must point to `input_count` elements, or may be null if
+/// `input_count` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_step(body: *mut AersoBody, delta_t: f64, inputs: *const f64, input_count: usize) -> AersoStatus {
+    let inputs = match (inputs.is_null(), input_count) {
+        (_, 0) => Vec::new(),
+        (true, _) => return AersoStatus::AersoNullPointer,
+        (false, count) => std::slice::from_raw_parts(inputs,count).to_vec(),
+    };
+    with_body(body,|body| {
+        body.vehicle.step(delta_t,&inputs);
+        AersoStatus::AersoOk
+    })
+}
+
+/// Write the 13-element statevector into `state`
+///
+/// # Safety
+///
+/// `body` must be a live body, and `state` must point to 13 writable elements.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_get_state(body: *mut AersoBody, state: *mut f64) -> AersoStatus {
+    if state.is_null() {
+        return AersoStatus::AersoNullPointer;
+    }
+    with_body(body,|body| {
+        std::slice::from_raw_parts_mut(state,13).copy_from_slice(body.vehicle.statevector().as_slice());
+        AersoStatus::AersoOk
+    })
+}
+
+/// Set the statevector from the 13 elements of `state`
+///
+/// # Safety
+///
+/// `body` must be a live body, and `state` must point to 13 elements.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_set_state(body: *mut AersoBody, state: *const f64) -> AersoStatus {
+    if state.is_null() {
+        return AersoStatus::AersoNullPointer;
+    }
+    let state = StateVector::from_column_slice(std::slice::from_raw_parts(state,13));
+    with_body(body,|body| {
+        body.vehicle.set_state(state);
+        AersoStatus::AersoOk
+    })
+}
+
+/// Write the 6-element airstate into `airstate`
+///
+/// # Safety
+///
+/// `body` must be a live body, and `airstate` must point to 6 writable elements.
+#[no_mangle]
+pub unsafe extern "C" fn aerso_body_get_airstate(body: *mut AersoBody, airstate: *mut f64) -> AersoStatus {
+    if airstate.is_null() {
+        return AersoStatus::AersoNullPointer;
+    }
+    with_body(body,|body| {
+        std::slice::from_raw_parts_mut(airstate,6).copy_from_slice(&airstate_array(&body.vehicle.get_airstate()));
+        AersoStatus::AersoOk
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// Effector producing a forward force of `inputs[0]` times the value behind `user_data`
+    unsafe extern "C" fn thrust(user_data: *mut c_void, _airstate: *const f64, _rates: *const f64, inputs: *const f64, input_count: usize, force: *mut f64, torque: *mut f64) {
+        let gain = *(user_data as *const f64);
+        let inputs = std::slice::from_raw_parts(inputs,input_count);
+        std::slice::from_raw_parts_mut(force,3).copy_from_slice(&[gain * inputs[0],0.0,0.0]);
+        std::slice::from_raw_parts_mut(torque,3).copy_from_slice(&[0.0,0.0,0.0]);
+    }
+
+    fn initial_state() -> [f64;13] {
+        let mut state = [0.0;13];
+        state[2] = -1000.0;
+        state[9] = 1.0;
+        state
+    }
+
+    #[test]
+    fn test_step() {
+        let inertia = [1.0,0.0,0.0, 0.0,1.0,0.0, 0.0,0.0,1.0];
+        let mut gain = 10.0;
+        unsafe {
+            assert!(aerso_body_new(-1.0,inertia.as_ptr(),initial_state().as_ptr()).is_null());
+            let body = aerso_body_new(2.0,inertia.as_ptr(),initial_state().as_ptr());
+            assert!(!body.is_null());
+            let name = b"motor\0".as_ptr() as *const c_char;
+            assert_eq!(aerso_body_add_effector(body,name,Some(thrust),&mut gain as *mut f64 as *mut c_void),AersoStatus::AersoOk);
+            assert_eq!(aerso_body_add_effector(body,name,Some(thrust),std::ptr::null_mut()),AersoStatus::AersoInvalidArgument);
+
+            // 10 N on 2 kg for 1 s
+            for _ in 0..100 {
+                assert_eq!(aerso_step(body,0.01,[1.0].as_ptr(),1),AersoStatus::AersoOk);
+            }
+            let mut state = [0.0;13];
+            assert_eq!(aerso_body_get_state(body,state.as_mut_ptr()),AersoStatus::AersoOk);
+            assert_relative_eq!(state[3],5.0,max_relative=1e-9);
+            assert_relative_eq!(state[2],-1000.0 + 0.5 * physical_constants::STANDARD_ACCELERATION_OF_GRAVITY,max_relative=1e-9);
+
+            assert_eq!(aerso_body_set_effector_enabled(body,name,0),AersoStatus::AersoOk);
+            assert_eq!(aerso_body_set_effector_enabled(body,b"other\0".as_ptr() as *const c_char,0),AersoStatus::AersoInvalidArgument);
+            aerso_body_free(body);
+        }
+    }
+
+    #[test]
+    fn test_state_and_wind() {
+        let inertia = [1.0,0.0,0.0, 0.0,1.0,0.0, 0.0,0.0,1.0];
+        unsafe {
+            let body = aerso_body_new(1.0,inertia.as_ptr(),initial_state().as_ptr());
+            let mut state = initial_state();
+            state[3] = 50.0;
+            assert_eq!(aerso_body_set_state(body,state.as_ptr()),AersoStatus::AersoOk);
+            assert_eq!(aerso_body_set_wind(body,[-10.0,0.0,0.0].as_ptr()),AersoStatus::AersoOk);
+            let mut airstate = [0.0;6];
+            assert_eq!(aerso_body_get_airstate(body,airstate.as_mut_ptr()),AersoStatus::AersoOk);
+            // A headwind adds to the airspeed, in the thinner air at 1000 m
+            assert_relative_eq!(airstate[2],60.0,max_relative=1e-9);
+            assert!(airstate[5] < 1.2);
+
+            assert_eq!(aerso_step(std::ptr::null_mut(),0.01,std::ptr::null(),0),AersoStatus::AersoNullPointer);
+            assert_eq!(aerso_body_get_state(body,std::ptr::null_mut()),AersoStatus::AersoNullPointer);
+            aerso_body_free(body);
+        }
+        assert!(!aerso_version().is_null());
+    }
+}
@@ -0,0 +1,354 @@
+//! Structural flexibility modes
+//!
+//! [FlexibleModes] adds a small number of user-defined generalised structural modes to a vehicle without a
+//! full finite element model. Each [FlexibleMode] is a damped oscillator in its modal coordinate `η`,
+//!
+//! `η̈ + 2ζω η̇ + ω² η = q (Q_α α) + Σ g δ`
+//!
+//! excited by the angle of attack through the dynamic pressure `q`, and by control inputs `δ`, such as
+//! control surface deflections. Flexing in turn changes the aerodynamic force and torque on the vehicle by
+//! `q F_η η` and `q M_η η`, closing the loop between the structure and the rigid-body dynamics.
+//!
+//! [FlexibleModes] is an [AeroEffect] whose modal states are advanced by [AeroEffect::step]. Once it has been
+//! added to an [AffectedBody](crate::AffectedBody), a [ModalMonitor] from [FlexibleModes::monitor] shares the
+//! modal states, so sensors can see the structure flex: [ModalMonitor::truth_at] moves a [Truth] to a sensor
+//! station using the [ModeShape] of each mode there.
+//!
+//! ```
+//! use aerso::flexibility::{FlexibleModes,FlexibleMode,ModeShape};
+//! use aerso::types::Vector3;
+//!
+//! // First wing bending at 3 Hz, seen as pitching at a sensor station in the nose
+//! let bending = FlexibleMode::new(3.0 * std::f64::consts::TAU,0.02)
+//!     .with_excitation(0.5)
+//!     .with_aero_influence(Vector3::new(0.0,0.0,-0.4),Vector3::zeros())
+//!     .with_station(ModeShape::new(Vector3::new(0.0,0.0,0.1),Vector3::new(0.0,0.2,0.0)));
+//! let modes = FlexibleModes::new(vec![bending]);
+//! let monitor = modes.monitor();
+//! assert_eq!(monitor.states()[0].displacement,0.0);
+//! ```
+
+use std::cell::RefCell;
+use std::ops::Index;
+use std::rc::Rc;
+
+use nalgebra::{Matrix2,Quaternion,UnitQuaternion,Vector2};
+
+use crate::{AeroEffect,AirState,Body,Error};
+use crate::snapshot::EffectorState;
+use crate::sensors::Truth;
+use crate::types::{Vector3,Force,Torque,Float,DefaultFloatRepr,constant};
+
+/// Displacement and rotation of a point on the structure per unit modal coordinate
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct ModeShape<T: Float = DefaultFloatRepr> {
+    /// Body-frame displacement per unit modal coordinate (m)
+    pub displacement: Vector3<T>,
+    /// Body-frame rotation per unit modal coordinate (rad)
+    pub rotation: Vector3<T>,
+}
+
+impl<T: Float> ModeShape<T> {
+    /// Create a new [ModeShape] with `displacement` (m) and `rotation` (rad) per unit modal coordinate
+    pub fn new(displacement: Vector3<T>, rotation: Vector3<T>) -> Self {
+        ModeShape { displacement, rotation }
+    }
+}
+
+/// A generalised structural mode
+#[derive(Clone,Debug)]
+pub struct FlexibleMode<T: Float = DefaultFloatRepr> {
+    /// Natural frequency (rad·s<sup>-1</sup>)
+    frequency: T,
+    /// Damping ratio
+    damping: T,
+    /// Modal acceleration per unit dynamic pressure per unit angle of attack (Pa<sup>-1</sup>·s<sup>-2</sup>·rad<sup>-1</sup>)
+    excitation: T,
+    /// Inputstate channels and their modal accelerations per unit input (s<sup>-2</sup>)
+    inputs: Vec<(usize,T)>,
+    /// Body-frame force per unit dynamic pressure per unit modal coordinate (m<sup>2</sup>)
+    force: Vector3<T>,
+    /// Body-frame torque per unit dynamic pressure per unit modal coordinate (m<sup>3</sup>)
+    torque: Vector3<T>,
+    /// Mode shapes at the sensor stations
+    stations: Vec<ModeShape<T>>,
+}
+
+impl<T: Float> FlexibleMode<T> {
+    /// Create a new [FlexibleMode] with natural `frequency` (rad·s<sup>-1</sup>) and `damping` ratio, with no
+    /// excitation, aerodynamic influence or sensor stations
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frequency` is not positive or `damping` is negative.
+    pub fn new(frequency: T, damping: T) -> Self {
+        if frequency <= T::zero() || damping < T::zero() {
+            panic!("Mode frequency must be positive and damping non-negative");
+        }
+        FlexibleMode {
+            frequency,
+            damping,
+            excitation: T::zero(),
+            inputs: Vec::new(),
+            force: Vector3::zeros(),
+            torque: Vector3::zeros(),
+            stations: Vec::new(),
+        }
+    }
+
+    /// Set the modal acceleration per unit dynamic pressure per unit angle of attack (Pa<sup>-1</sup>·s<sup>-2</sup>·rad<sup>-1</sup>)
+    pub fn with_excitation(mut self, excitation: T) -> Self {
+        self.excitation = excitation;
+        self
+    }
+
+    /// Excite the mode from inputstate `channel` with modal acceleration `gain` per unit input (s<sup>-2</sup>)
+    pub fn with_input(mut self, channel: usize, gain: T) -> Self {
+        self.inputs.push((channel,gain));
+        self
+    }
+
+    /// Set the body-frame `force` (m<sup>2</sup>) and `torque` (m<sup>3</sup>) per unit dynamic pressure per
+    /// unit modal coordinate
+    pub fn with_aero_influence(mut self, force: Vector3<T>, torque: Vector3<T>) -> Self {
+        self.force = force;
+        self.torque = torque;
+        self
+    }
+
+    /// Add the mode shape at the next sensor station
+    ///
+    /// Stations are numbered in the order they are added. A mode without a shape at a station does not
+    /// move it.
+    pub fn with_station(mut self, shape: ModeShape<T>) -> Self {
+        self.stations.push(shape);
+        self
+    }
+
+    /// Return the natural frequency (rad·s<sup>-1</sup>)
+    pub fn frequency(&self) -> T {
+        self.frequency
+    }
+
+    /// Return the damping ratio
+    pub fn damping(&self) -> T {
+        self.damping
+    }
+
+    /// Return the modal forcing (s<sup>-2</sup>) at `airstate` with `inputstate`
+    fn forcing<I: Index<usize,Output=T>>(&self, airstate: &AirState<T>, inputstate: &I) -> T {
+        self.inputs.iter().fold(airstate.q * self.excitation * airstate.alpha,|forcing, &(channel, gain)| forcing + gain * inputstate[channel])
+    }
+
+    /// Return the modal acceleration (s<sup>-2</sup>) in `state` with `forcing`
+    fn acceleration(&self, state: &ModalState<T>, forcing: T) -> T {
+        let two = constant::<T>(2.0);
+        forcing - two * self.damping * self.frequency * state.velocity - self.frequency * self.frequency * state.displacement
+    }
+}
+
+/// State of a [FlexibleMode]
+#[derive(Copy,Clone,Debug,Default,PartialEq)]
+pub struct ModalState<T: Float = DefaultFloatRepr> {
+    /// Modal coordinate
+    pub displacement: T,
+    /// Rate of the modal coordinate (s<sup>-1</sup>)
+    pub velocity: T,
+    /// Acceleration of the modal coordinate at the start of the last step (s<sup>-2</sup>)
+    pub acceleration: T,
+}
+
+/// Built-in [AeroEffect] integrating a set of [FlexibleMode]s as auxiliary states
+pub struct FlexibleModes<T: Float = DefaultFloatRepr> {
+    /// Modes
+    modes: Rc<Vec<FlexibleMode<T>>>,
+    /// Modal states, shared with each [ModalMonitor]
+    states: Rc<RefCell<Vec<ModalState<T>>>>,
+}
+
+impl<T: Float> FlexibleModes<T> {
+    /// Create a new [FlexibleModes] with `modes`, initially undeformed
+    pub fn new(modes: Vec<FlexibleMode<T>>) -> Self {
+        let states = vec![ModalState { displacement: T::zero(), velocity: T::zero(), acceleration: T::zero() }; modes.len()];
+        FlexibleModes { modes: Rc::new(modes), states: Rc::new(RefCell::new(states)) }
+    }
+
+    /// Return a [ModalMonitor] sharing the modal states
+    pub fn monitor(&self) -> ModalMonitor<T> {
+        ModalMonitor { modes: self.modes.clone(), states: self.states.clone() }
+    }
+
+    /// Advance the modal states by `delta_t` given the current `airstate` and `inputstate`
+    ///
+    /// The forcing is held constant over the step.
+    pub fn update<I: Index<usize,Output=T>>(&mut self, airstate: &AirState<T>, inputstate: &I, delta_t: T) {
+        let mut states = self.states.borrow_mut();
+        for (mode, state) in self.modes.iter().zip(states.iter_mut()) {
+            let forcing = mode.forcing(airstate,inputstate);
+            let omega = mode.frequency;
+            // Exact discretisation of the oscillator about its equilibrium for a constant forcing over the step
+            let equilibrium = forcing / (omega * omega);
+            let two = constant::<T>(2.0);
+            let transition = (Matrix2::new(T::zero(),T::one(),-omega * omega,-two * mode.damping * omega) * delta_t).exp();
+            let next = transition * Vector2::new(state.displacement - equilibrium,state.velocity);
+            state.acceleration = mode.acceleration(state,forcing);
+            state.displacement = next[0] + equilibrium;
+            state.velocity = next[1];
+        }
+    }
+}
+
+impl<I: Index<usize,Output=T>, T: Float> AeroEffect<I,T> for FlexibleModes<T> {
+    fn get_effect(&self, airstate: AirState<T>, _rates: Vector3<T>, _inputstate: &I) -> (Force<T>,Torque<T>) {
+        let states = self.states.borrow();
+        let (force, torque) = self.modes.iter().zip(states.iter()).fold((Vector3::zeros(),Vector3::zeros()),|(force, torque), (mode, state)| {
+            (force + mode.force * state.displacement,torque + mode.torque * state.displacement)
+        });
+        (Force::body_vec(force * airstate.q),Torque::body_vec(torque * airstate.q))
+    }
+
+    fn step(&mut self, airstate: AirState<T>, _rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        self.update(&airstate,inputstate,delta_t);
+    }
+
+    fn snapshot(&self) -> EffectorState {
+        let values: Vec<_> = self.states.borrow().iter().flat_map(|state| vec![state.displacement,state.velocity,state.acceleration]).collect();
+        EffectorState::from_values(&values)
+    }
+
+    fn restore(&mut self, state: &EffectorState) -> Result<(),Error> {
+        state.check(3 * self.modes.len(),0,0)?;
+        for (idx, modal) in self.states.borrow_mut().iter_mut().enumerate() {
+            *modal = ModalState { displacement: state.value(3 * idx), velocity: state.value(3 * idx + 1), acceleration: state.value(3 * idx + 2) };
+        }
+        Ok(())
+    }
+}
+
+/// Handle sharing the modal states of a [FlexibleModes]
+#[derive(Clone)]
+pub struct ModalMonitor<T: Float = DefaultFloatRepr> {
+    /// Modes
+    modes: Rc<Vec<FlexibleMode<T>>>,
+    /// Modal states
+    states: Rc<RefCell<Vec<ModalState<T>>>>,
+}
+
+impl<T: Float> ModalMonitor<T> {
+    /// Return the current modal states, in the order of the modes
+    pub fn states(&self) -> Vec<ModalState<T>> {
+        self.states.borrow().clone()
+    }
+
+    /// Return the body-frame displacement (m) and rotation (rad) of sensor `station`
+    pub fn deflection(&self, station: usize) -> ModeShape<T> {
+        self.sum(station,|state| state.displacement)
+    }
+
+    /// Return `truth` as seen at sensor `station` on the flexing structure
+    ///
+    /// The attitude is rotated, the rates and angular acceleration include the rotation rate and acceleration
+    /// of the station, the position is displaced, and the acceleration includes the displacement acceleration
+    /// of the station. The acceleration terms use the modal accelerations at the start of the last step.
+    pub fn truth_at(&self, station: usize, truth: &Truth<T>) -> Truth<T> {
+        let deflection = self.deflection(station);
+        let rate = self.sum(station,|state| state.velocity);
+        let acceleration = self.sum(station,|state| state.acceleration);
+
+        let mut state = truth.state;
+        let position = Body::get_dcm_body(&state) * deflection.displacement;
+        state[0] += position[0];
+        state[1] += position[1];
+        state[2] += position[2];
+        let attitude = UnitQuaternion::from_quaternion(Quaternion::new(state[9],state[6],state[7],state[8]));
+        let flexed = (attitude * UnitQuaternion::from_scaled_axis(deflection.rotation)).into_inner();
+        state[6] = flexed.i;
+        state[7] = flexed.j;
+        state[8] = flexed.k;
+        state[9] = flexed.w;
+        state[10] += rate.rotation[0];
+        state[11] += rate.rotation[1];
+        state[12] += rate.rotation[2];
+
+        Truth {
+            state,
+            acceleration: truth.acceleration + acceleration.displacement,
+            angular_acceleration: truth.angular_acceleration + acceleration.rotation,
+            ..*truth
+        }
+    }
+
+    /// Return the mode shapes at `station` summed over the modes, each scaled by `value` of its state
+    fn sum<F: Fn(&ModalState<T>) -> T>(&self, station: usize, value: F) -> ModeShape<T> {
+        let states = self.states.borrow();
+        self.modes.iter().zip(states.iter())
+            .filter_map(|(mode, state)| mode.stations.get(station).map(|shape| (shape,value(state))))
+            .fold(ModeShape::new(Vector3::zeros(),Vector3::zeros()),|sum, (shape, value)| {
+                ModeShape::new(sum.displacement + shape.displacement * value,sum.rotation + shape.rotation * value)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::{AeroBody,AffectedBody};
+    use crate::types::{Matrix3,StateView};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_modal_response() {
+        // An undamped mode released from rest oscillates about its equilibrium, η = η*(1 - cos ωt)
+        let omega = 10.0;
+        let mut modes = FlexibleModes::new(vec![
+            FlexibleMode::new(omega,0.0).with_excitation(0.2).with_aero_influence(Vector3::new(0.0,0.0,-0.5),Vector3::new(0.0,0.1,0.0)),
+            FlexibleMode::new(30.0,0.7).with_input(0,50.0),
+        ]);
+        let monitor = modes.monitor();
+        let alpha = 0.1;
+        let equilibrium = 245.0 * 0.2 * alpha / (omega * omega);
+        for _ in 0..37 {
+            modes.update(&airstate(alpha,20.0),&[0.0],0.01);
+        }
+        let states = monitor.states();
+        assert_relative_eq!(states[0].displacement,equilibrium * (1.0 - (omega * 0.37_f64).cos()),max_relative=1e-9);
+        assert_relative_eq!(states[0].velocity,equilibrium * omega * (omega * 0.37_f64).sin(),max_relative=1e-9);
+        assert_eq!(states[1],ModalState::default());
+
+        let (force,torque) = modes.get_effect(airstate(alpha,20.0),Vector3::zeros(),&[0.0]);
+        assert_relative_eq!(force.force.z,-0.5 * 245.0 * states[0].displacement,max_relative=1e-12);
+        assert_relative_eq!(torque.torque.y,0.1 * 245.0 * states[0].displacement,max_relative=1e-12);
+
+        // A damped mode settles at its equilibrium
+        for _ in 0..200 {
+            modes.update(&airstate(0.0,20.0),&[0.02],0.01);
+        }
+        assert_relative_eq!(monitor.states()[1].displacement,50.0 * 0.02 / 900.0,max_relative=1e-6);
+    }
+
+    #[test]
+    fn test_sensor_station() {
+        let shape = ModeShape::new(Vector3::new(0.0,0.0,0.1),Vector3::new(0.0,0.2,0.0));
+        let modes = FlexibleModes::new(vec![FlexibleMode::new(20.0,0.05).with_input(0,400.0).with_station(shape)]);
+        let monitor = modes.monitor();
+        let body = AeroBody::new(crate::Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(20.0,0.0,0.0),UnitQuaternion::identity(),Vector3::zeros()));
+        let mut vehicle = AffectedBody::new(body,vec![Box::new(modes)]);
+        for _ in 0..5 {
+            vehicle.step(0.01,&vec![1.0]);
+        }
+
+        let state = monitor.states()[0];
+        assert!(state.displacement > 0.0 && state.velocity > 0.0);
+        let truth = Truth::new(vehicle.time(),&vehicle);
+        let sensed = monitor.truth_at(0,&truth);
+        assert_relative_eq!(sensed.rates()[1] - truth.rates()[1],0.2 * state.velocity,max_relative=1e-12);
+        assert_relative_eq!(sensed.acceleration[2] - truth.acceleration[2],0.1 * state.acceleration,max_relative=1e-12);
+        assert_relative_eq!(sensed.state[2] - truth.state[2],0.1 * state.displacement,max_relative=1e-9);
+        let pitch = UnitQuaternion::from_quaternion(Quaternion::new(sensed.state[9],sensed.state[6],sensed.state[7],sensed.state[8])).euler_angles().1;
+        assert_relative_eq!(pitch - vehicle.attitude().euler_angles().1,0.2 * state.displacement,max_relative=1e-6);
+
+        // Stations without a mode shape are not moved
+        assert_eq!(monitor.truth_at(1,&truth).state,truth.state);
+    }
+}
@@ -0,0 +1,635 @@
+//! FMI 2.0 co-simulation export
+//!
+//! Enabled by the `fmi` feature. An [FmuInstance] wraps an [AffectedBody] as an FMI co-simulation slave,
+//! with the statevector and airstate as real outputs and the inputstate as real inputs, and
+//! [model_description] writes the matching `modelDescription.xml`. The [export_fmu](crate::export_fmu)
+//! macro generates the FMI 2.0 C entry points for a `cdylib` crate from a function constructing the
+//! instance, so the vehicle model can be packaged as an FMU for Simulink, OpenModelica or any other FMI
+//! host.
+//!
+//! # Value references
+//!
+//! | Value reference | Variable |
+//! |---|---|
+//! | 0 - 12 | Statevector, `x,y,z,u,v,w,qx,qy,qz,qw,p,q,r` |
+//! | 13 - 18 | Airstate, `alpha,beta,airspeed,q,mach,density` |
+//! | 1000 + n | Inputstate element `n` |
+//!
+//! Statevector outputs can also be set during initialization mode, to set the initial state.
+
+use std::ops::IndexMut;
+
+use crate::{AffectedBody,WindModel,DensityModel,Error};
+use crate::types::{StateView,Float,DefaultFloatRepr,constant};
+
+/// Names of the statevector outputs, in value reference order
+const STATE_NAMES: [&str;13] = ["x","y","z","u","v","w","qx","qy","qz","qw","p","q","r"];
+/// Names of the airstate outputs, in value reference order from [AIRSTATE_REFERENCE]
+const AIRSTATE_NAMES: [&str;6] = ["alpha","beta","airspeed","dynamic_pressure","mach","density"];
+/// Value reference of the first airstate output
+pub const AIRSTATE_REFERENCE: u32 = 13;
+/// Value reference of the first input
+pub const INPUT_REFERENCE: u32 = 1000;
+
+/// Interface of an FMI co-simulation slave, used by the [export_fmu](crate::export_fmu) entry points
+pub trait FmuSlave {
+    /// Set the real variable `reference` to `value`
+    fn set_real(&mut self, reference: u32, value: f64) -> Result<(),Error>;
+
+    /// Return the value of the real variable `reference`
+    fn get_real(&self, reference: u32) -> Result<f64,Error>;
+
+    /// Enter or leave initialization mode
+    fn set_initializing(&mut self, initializing: bool);
+
+    /// Advance the slave from `time` by `step` (s)
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::InvalidParameter] if `step` is negative, not finite or too long to be stepped.
+    fn do_step(&mut self, time: f64, step: f64) -> Result<(),Error>;
+
+    /// Return the simulation time (s)
+    fn time(&self) -> f64;
+}
+
+/// [AffectedBody] wrapped as an FMI co-simulation slave
+///
+/// Each communication step is taken as sub-steps no longer than the maximum timestep, with the inputs
+/// held over the communication step.
+pub struct FmuInstance<I, W: WindModel<T>, D: DensityModel<T>, T: Float = DefaultFloatRepr> {
+    /// Wrapped vehicle
+    vehicle: AffectedBody<I,T,W,D>,
+    /// Inputstate held over each communication step
+    inputs: I,
+    /// Number of inputstate elements exposed as inputs
+    input_count: usize,
+    /// Longest sub-step (s)
+    max_step: T,
+    /// Simulation time (s)
+    time: T,
+    /// Whether the instance is in initialization mode
+    initializing: bool,
+}
+
+impl<I: IndexMut<usize,Output=T>, W: WindModel<T>, D: DensityModel<T>, T: Float> FmuInstance<I,W,D,T> {
+    /// Wrap `vehicle` with `input_count` inputs starting from `inputs`
+    ///
+    /// The maximum timestep defaults to 0.01 s.
+    pub fn new(vehicle: AffectedBody<I,T,W,D>, inputs: I, input_count: usize) -> Self {
+        FmuInstance {
+            vehicle,
+            inputs,
+            input_count,
+            max_step: constant::<T>(0.01),
+            time: T::zero(),
+            initializing: false,
+        }
+    }
+
+    /// Set the longest sub-step taken within a communication step (s)
+    pub fn with_max_step(mut self, max_step: T) -> Self {
+        self.max_step = max_step;
+        self
+    }
+
+    /// Return the wrapped vehicle
+    pub fn vehicle(&self) -> &AffectedBody<I,T,W,D> {
+        &self.vehicle
+    }
+
+    /// Return the input index of `reference`, if it is an input
+    fn input_index(&self, reference: u32) -> Option<usize> {
+        reference.checked_sub(INPUT_REFERENCE).map(|index| index as usize).filter(|index| *index < self.input_count)
+    }
+}
+
+impl<I: IndexMut<usize,Output=T>, W: WindModel<T>, D: DensityModel<T>, T: Float> FmuSlave for FmuInstance<I,W,D,T> {
+    fn set_real(&mut self, reference: u32, value: f64) -> Result<(),Error> {
+        let value = constant::<T>(value);
+        if let Some(index) = self.input_index(reference) {
+            self.inputs[index] = value;
+            Ok(())
+        } else if reference < AIRSTATE_REFERENCE && self.initializing {
+            let mut state = self.vehicle.statevector();
+            state[reference as usize] = value;
+            self.vehicle.set_state(state);
+            Ok(())
+        } else {
+            Err(Error::ReadOnlyVariable(reference))
+        }
+    }
+
+    fn get_real(&self, reference: u32) -> Result<f64,Error> {
+        let value = if reference < AIRSTATE_REFERENCE {
+            self.vehicle.statevector()[reference as usize]
+        } else if reference < AIRSTATE_REFERENCE + 6 {
+            let airstate = self.vehicle.get_airstate();
+            [airstate.alpha,airstate.beta,airstate.airspeed,airstate.q,airstate.mach,airstate.density][(reference - AIRSTATE_REFERENCE) as usize]
+        } else if let Some(index) = self.input_index(reference) {
+            self.inputs[index]
+        } else {
+            return Err(Error::UnknownVariable(reference));
+        };
+        Ok(value.to_f64().unwrap())
+    }
+
+    fn set_initializing(&mut self, initializing: bool) {
+        self.initializing = initializing;
+    }
+
+    fn do_step(&mut self, time: f64, step: f64) -> Result<(),Error> {
+        if !step.is_finite() || step < 0.0 {
+            return Err(Error::InvalidParameter("step"));
+        }
+        let time = constant::<T>(time);
+        let step = constant::<T>(step);
+        let substeps = <T as num_traits::Float>::max(<T as num_traits::Float>::ceil(step / self.max_step),T::one());
+        let delta_t = step / substeps;
+        // A step too long to be divided into substeps is rejected rather than run indefinitely
+        let count = substeps.to_usize().ok_or(Error::InvalidParameter("step"))?;
+        for _ in 0..count {
+            self.vehicle.step(delta_t,&self.inputs);
+        }
+        self.time = time + step;
+        Ok(())
+    }
+
+    fn time(&self) -> f64 {
+        self.time.to_f64().unwrap()
+    }
+}
+
+/// Return the FMI 2.0 `modelDescription.xml` of an [FmuInstance] with `input_count` inputs
+///
+/// # Arguments
+///
+/// * `model_identifier` - Model name, which is also the name of the shared library in the FMU
+/// * `guid` - Unique identifier of the model, passed to `fmi2Instantiate` by the host
+/// * `input_count` - Number of inputs
+pub fn model_description(model_identifier: &str, guid: &str, input_count: usize) -> String {
+    let mut variables = Vec::new();
+    for (index, name) in STATE_NAMES.iter().enumerate() {
+        variables.push(format!("    <ScalarVariable name=\"state.{}\" valueReference=\"{}\" causality=\"output\" variability=\"continuous\" initial=\"exact\"><Real start=\"0\"/></ScalarVariable>",name,index));
+    }
+    for (index, name) in AIRSTATE_NAMES.iter().enumerate() {
+        variables.push(format!("    <ScalarVariable name=\"airstate.{}\" valueReference=\"{}\" causality=\"output\" variability=\"continuous\" initial=\"calculated\"><Real/></ScalarVariable>",name,AIRSTATE_REFERENCE as usize + index));
+    }
+    for index in 0..input_count {
+        variables.push(format!("    <ScalarVariable name=\"input[{}]\" valueReference=\"{}\" causality=\"input\" variability=\"continuous\"><Real start=\"0\"/></ScalarVariable>",index,INPUT_REFERENCE as usize + index));
+    }
+    // Outputs are listed by their one-based index in ModelVariables
+    let outputs: Vec<String> = (1..=STATE_NAMES.len() + AIRSTATE_NAMES.len()).map(|index| format!("      <Unknown index=\"{}\"/>",index)).collect();
+    format!(
+"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<fmiModelDescription fmiVersion=\"2.0\" modelName=\"{name}\" guid=\"{guid}\" generationTool=\"aerso\" variableNamingConvention=\"structured\">
+  <CoSimulation modelIdentifier=\"{name}\" canHandleVariableCommunicationStepSize=\"true\"/>
+  <ModelVariables>
+{variables}
+  </ModelVariables>
+  <ModelStructure>
+    <Outputs>
+{outputs}
+    </Outputs>
+  </ModelStructure>
+</fmiModelDescription>
+",name=model_identifier,guid=guid,variables=variables.join("\n"),outputs=outputs.join("\n"))
+}
+
+#[doc(hidden)]
+pub mod ffi {
+    //! Implementation of the FMI 2.0 entry points generated by [export_fmu](crate::export_fmu)
+
+    use std::os::raw::{c_char,c_void};
+    use super::FmuSlave;
+
+    /// `fmi2OK`
+    pub const OK: i32 = 0;
+    /// `fmi2Discard`
+    pub const DISCARD: i32 = 2;
+    /// `fmi2Error`
+    pub const ERROR: i32 = 3;
+    /// `fmi2CoSimulation`
+    const CO_SIMULATION: i32 = 1;
+    /// `fmi2LastSuccessfulTime`
+    const LAST_SUCCESSFUL_TIME: i32 = 2;
+
+    /// Instance behind an `fmi2Component`
+    pub struct Component {
+        /// Constructor of the slave, used on reset
+        constructor: fn() -> Box<dyn FmuSlave>,
+        /// The slave
+        slave: Box<dyn FmuSlave>,
+    }
+
+    /// Return the component behind `component`, if it is not null
+    unsafe fn component<'a>(component: *mut c_void) -> Option<&'a mut Component> {
+        (component as *mut Component).as_mut()
+    }
+
+    /// `fmi2Instantiate`
+    ///
+    /// # Safety
+    ///
+    /// `guid` must be null or a valid C string.
+    pub unsafe fn instantiate(constructor: fn() -> Box<dyn FmuSlave>, fmu_type: i32, guid: *const c_char, expected_guid: &str) -> *mut c_void {
+        if fmu_type != CO_SIMULATION {
+            return std::ptr::null_mut();
+        }
+        if !guid.is_null() && std::ffi::CStr::from_ptr(guid).to_str() != Ok(expected_guid) {
+            return std::ptr::null_mut();
+        }
+        Box::into_raw(Box::new(Component { constructor, slave: constructor() })) as *mut c_void
+    }
+
+    /// `fmi2FreeInstance`
+    ///
+    /// # Safety
+    ///
+    /// `component` must be null or returned by [instantiate] and not yet freed.
+    pub unsafe fn free_instance(component: *mut c_void) {
+        if !component.is_null() {
+            drop(Box::from_raw(component as *mut Component));
+        }
+    }
+
+    /// `fmi2Reset`
+    ///
+    /// # Safety
+    ///
+    /// `component` must be null or a live instance.
+    pub unsafe fn reset(handle: *mut c_void) -> i32 {
+        match component(handle) {
+            Some(component) => {
+                component.slave = (component.constructor)();
+                OK
+            },
+            None => ERROR,
+        }
+    }
+
+    /// `fmi2EnterInitializationMode` and `fmi2ExitInitializationMode`
+    ///
+    /// # Safety
+    ///
+    /// `component` must be null or a live instance.
+    pub unsafe fn set_initializing(handle: *mut c_void, initializing: bool) -> i32 {
+        match component(handle) {
+            Some(component) => {
+                component.slave.set_initializing(initializing);
+                OK
+            },
+            None => ERROR,
+        }
+    }
+
+    /// `fmi2GetReal`
+    ///
+    /// # Safety
+    ///
+    /// `component` must be null or a live instance, and `references` and `values` must have `count` elements.
+    pub unsafe fn get_real(handle: *mut c_void, references: *const u32, count: usize, values: *mut f64) -> i32 {
+        let component = match component(handle) {
+            Some(component) => component,
+            None => return ERROR,
+        };
+        if count == 0 {
+            return OK;
+        }
+        let references = std::slice::from_raw_parts(references,count);
+        let values = std::slice::from_raw_parts_mut(values,count);
+        for (reference, value) in references.iter().zip(values.iter_mut()) {
+            match component.slave.get_real(*reference) {
+                Ok(real) => *value = real,
+                Err(_) => return ERROR,
+            }
+        }
+        OK
+    }
+
+    /// `fmi2SetReal`
+    ///
+    /// # Safety
+    ///
+    /// `component` must be null or a live instance, and `references` and `values` must have `count` elements.
+    pub unsafe fn set_real(handle: *mut c_void, references: *const u32, count: usize, values: *const f64) -> i32 {
+        let component = match component(handle) {
+            Some(component) => component,
+            None => return ERROR,
+        };
+        if count == 0 {
+            return OK;
+        }
+        let references = std::slice::from_raw_parts(references,count);
+        let values = std::slice::from_raw_parts(values,count);
+        for (reference, value) in references.iter().zip(values.iter()) {
+            if component.slave.set_real(*reference,*value).is_err() {
+                return ERROR;
+            }
+        }
+        OK
+    }
+
+    /// `fmi2DoStep`
+    ///
+    /// # Safety
+    ///
+    /// `component` must be null or a live instance.
+    pub unsafe fn do_step(handle: *mut c_void, time: f64, step: f64) -> i32 {
+        match component(handle) {
+            Some(component) => match component.slave.do_step(time,step) {
+                Ok(()) => OK,
+                Err(_) => ERROR,
+            },
+            None => ERROR,
+        }
+    }
+
+    /// `fmi2GetRealStatus`
+    ///
+    /// # Safety
+    ///
+    /// `component` must be null or a live instance, and `value` must be valid for writes.
+    pub unsafe fn get_real_status(handle: *mut c_void, kind: i32, value: *mut f64) -> i32 {
+        match component(handle) {
+            Some(component) if kind == LAST_SUCCESSFUL_TIME => {
+                *value = component.slave.time();
+                OK
+            },
+            Some(_) => DISCARD,
+            None => ERROR,
+        }
+    }
+
+    /// Return `OK` for an empty access to an unsupported variable type, and `ERROR` otherwise
+    pub fn empty_access(count: usize) -> i32 {
+        if count == 0 { OK } else { ERROR }
+    }
+}
+
+/// Generate the FMI 2.0 co-simulation entry points for an [FmuSlave](crate::fmi::FmuSlave)
+///
+/// Called once in a `cdylib` crate with the GUID of its [model_description](crate::fmi::model_description)
+/// and a function returning a new boxed slave, such as an [FmuInstance](crate::fmi::FmuInstance).
+/// Only real variables are supported, and FMU state saving, input derivatives and asynchronous steps
+/// return `fmi2Error`.
+///
+/// ```ignore
+/// fn glider() -> Box<dyn aerso::fmi::FmuSlave> {
+///     Box::new(aerso::fmi::FmuInstance::new(make_vehicle(),vec![0.0;4],4))
+/// }
+/// aerso::export_fmu!(glider,"{8c4e810f-3df3-4a00-8276-176fa3c9f000}");
+/// ```
+#[macro_export]
+macro_rules! export_fmu {
+    ($constructor:path, $guid:expr) => {
+        mod fmi2_entry_points {
+            #![allow(non_snake_case,clippy::missing_safety_doc)]
+            use std::os::raw::{c_char,c_void};
+            use super::*;
+            use $crate::fmi::ffi;
+
+            fn construct() -> Box<dyn $crate::fmi::FmuSlave> {
+                $constructor()
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetTypesPlatform() -> *const c_char {
+                b"default\0".as_ptr() as *const c_char
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetVersion() -> *const c_char {
+                b"2.0\0".as_ptr() as *const c_char
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SetDebugLogging(_c: *mut c_void, _logging_on: i32, _n: usize, _categories: *const *const c_char) -> i32 {
+                ffi::OK
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2Instantiate(_name: *const c_char, fmu_type: i32, guid: *const c_char, _resources: *const c_char, _functions: *const c_void, _visible: i32, _logging_on: i32) -> *mut c_void {
+                ffi::instantiate(construct,fmu_type,guid,$guid)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2FreeInstance(c: *mut c_void) {
+                ffi::free_instance(c)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SetupExperiment(_c: *mut c_void, _tolerance_defined: i32, _tolerance: f64, _start: f64, _stop_defined: i32, _stop: f64) -> i32 {
+                ffi::OK
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2EnterInitializationMode(c: *mut c_void) -> i32 {
+                ffi::set_initializing(c,true)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2ExitInitializationMode(c: *mut c_void) -> i32 {
+                ffi::set_initializing(c,false)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2Terminate(_c: *mut c_void) -> i32 {
+                ffi::OK
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2Reset(c: *mut c_void) -> i32 {
+                ffi::reset(c)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2GetReal(c: *mut c_void, vr: *const u32, n: usize, value: *mut f64) -> i32 {
+                ffi::get_real(c,vr,n,value)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2SetReal(c: *mut c_void, vr: *const u32, n: usize, value: *const f64) -> i32 {
+                ffi::set_real(c,vr,n,value)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetInteger(_c: *mut c_void, _vr: *const u32, n: usize, _value: *mut i32) -> i32 {
+                ffi::empty_access(n)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SetInteger(_c: *mut c_void, _vr: *const u32, n: usize, _value: *const i32) -> i32 {
+                ffi::empty_access(n)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetBoolean(_c: *mut c_void, _vr: *const u32, n: usize, _value: *mut i32) -> i32 {
+                ffi::empty_access(n)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SetBoolean(_c: *mut c_void, _vr: *const u32, n: usize, _value: *const i32) -> i32 {
+                ffi::empty_access(n)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetString(_c: *mut c_void, _vr: *const u32, n: usize, _value: *mut *const c_char) -> i32 {
+                ffi::empty_access(n)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SetString(_c: *mut c_void, _vr: *const u32, n: usize, _value: *const *const c_char) -> i32 {
+                ffi::empty_access(n)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetFMUstate(_c: *mut c_void, _state: *mut *mut c_void) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SetFMUstate(_c: *mut c_void, _state: *mut c_void) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2FreeFMUstate(_c: *mut c_void, _state: *mut *mut c_void) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SerializedFMUstateSize(_c: *mut c_void, _state: *mut c_void, _size: *mut usize) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SerializeFMUstate(_c: *mut c_void, _state: *mut c_void, _bytes: *mut u8, _size: usize) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2DeSerializeFMUstate(_c: *mut c_void, _bytes: *const u8, _size: usize, _state: *mut *mut c_void) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetDirectionalDerivative(_c: *mut c_void, _unknown: *const u32, _n_unknown: usize, _known: *const u32, _n_known: usize, _dv_known: *const f64, _dv_unknown: *mut f64) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2SetRealInputDerivatives(_c: *mut c_void, _vr: *const u32, _n: usize, _order: *const i32, _value: *const f64) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetRealOutputDerivatives(_c: *mut c_void, _vr: *const u32, _n: usize, _order: *const i32, _value: *mut f64) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2DoStep(c: *mut c_void, time: f64, step: f64, _no_prior_state: i32) -> i32 {
+                ffi::do_step(c,time,step)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2CancelStep(_c: *mut c_void) -> i32 {
+                ffi::ERROR
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetStatus(_c: *mut c_void, _kind: i32, _value: *mut i32) -> i32 {
+                ffi::DISCARD
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2GetRealStatus(c: *mut c_void, kind: i32, value: *mut f64) -> i32 {
+                ffi::get_real_status(c,kind,value)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetIntegerStatus(_c: *mut c_void, _kind: i32, _value: *mut i32) -> i32 {
+                ffi::DISCARD
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetBooleanStatus(_c: *mut c_void, _kind: i32, _value: *mut i32) -> i32 {
+                ffi::DISCARD
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetStringStatus(_c: *mut c_void, _kind: i32, _value: *mut *const c_char) -> i32 {
+                ffi::DISCARD
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    fn aircraft() -> Box<dyn FmuSlave> {
+        Box::new(FmuInstance::new(test_aircraft::vehicle(1000.0),vec![0.0;4],4).with_max_step(0.005))
+    }
+
+    crate::export_fmu!(aircraft,"{test}");
+
+    #[test]
+    fn test_instance() {
+        let mut instance = aircraft();
+        assert!(instance.set_real(0,100.0).is_err());
+        instance.set_initializing(true);
+        instance.set_real(3,60.0).unwrap();
+        instance.set_initializing(false);
+        assert_relative_eq!(instance.get_real(3).unwrap(),60.0);
+        assert_relative_eq!(instance.get_real(AIRSTATE_REFERENCE + 2).unwrap(),60.0,max_relative=1e-9);
+
+        instance.set_real(INPUT_REFERENCE + 3,0.5).unwrap();
+        assert!(instance.set_real(INPUT_REFERENCE + 4,0.5).is_err());
+        instance.do_step(0.0,0.1).unwrap();
+        assert_relative_eq!(instance.time(),0.1);
+        for step in [-0.1,f64::INFINITY,f64::NAN,1e300].iter() {
+            assert_eq!(instance.do_step(0.1,*step),Err(Error::InvalidParameter("step")));
+        }
+        assert_relative_eq!(instance.time(),0.1);
+        assert!(instance.get_real(0).unwrap() > 5.0);
+        assert!(instance.get_real(2000).is_err());
+
+        let description = model_description("glider","{test}",4);
+        assert_eq!(description.matches("<ScalarVariable").count(),23);
+        assert!(description.contains("valueReference=\"1003\" causality=\"input\""));
+    }
+
+    #[test]
+    fn test_entry_points() {
+        use std::os::raw::c_char;
+        use fmi2_entry_points::*;
+        unsafe {
+            let guid = b"{test}\0".as_ptr() as *const c_char;
+            assert!(fmi2Instantiate(std::ptr::null(),0,guid,std::ptr::null(),std::ptr::null(),0,0).is_null());
+            assert!(fmi2Instantiate(std::ptr::null(),1,b"{other}\0".as_ptr() as *const c_char,std::ptr::null(),std::ptr::null(),0,0).is_null());
+            let component = fmi2Instantiate(std::ptr::null(),1,guid,std::ptr::null(),std::ptr::null(),0,0);
+            assert!(!component.is_null());
+
+            let references = [2,INPUT_REFERENCE + 3];
+            assert_eq!(fmi2SetReal(component,references[1..].as_ptr(),1,[1.0].as_ptr()),ffi::OK);
+            assert_eq!(fmi2DoStep(component,0.0,0.5,1),ffi::OK);
+            let mut values = [0.0;2];
+            assert_eq!(fmi2GetReal(component,references.as_ptr(),2,values.as_mut_ptr()),ffi::OK);
+            assert_eq!(values[1],1.0);
+            let mut time = 0.0;
+            assert_eq!(fmi2GetRealStatus(component,2,&mut time),ffi::OK);
+            assert_relative_eq!(time,0.5);
+            assert_eq!(fmi2GetFMUstate(component,std::ptr::null_mut()),ffi::ERROR);
+
+            assert_eq!(fmi2Reset(component),ffi::OK);
+            assert_eq!(fmi2GetReal(component,references.as_ptr(),2,values.as_mut_ptr()),ffi::OK);
+            assert_eq!(values,[-1000.0,0.0]);
+            fmi2FreeInstance(component);
+        }
+    }
+}
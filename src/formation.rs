@@ -0,0 +1,224 @@
+//! Formation-flight aerodynamic interference
+//!
+//! A [FormationModel] estimates the benefit to a follower flying in the wake of a leader. The upwash of
+//! the leader's [VortexWake] is sampled in strips across the follower's span: its mean, weighted by the
+//! follower's elliptic lift distribution, tilts the lift forward to reduce drag, and its spanwise asymmetry
+//! rolls the follower, away from the leader once the follower's tip is clear of the trailing vortex.
+//!
+//! The follower's own circulation is treated as fixed, so the model is a simplified steady estimate for
+//! evaluating formation-keeping and energy-saving control algorithms rather than a lifting-surface solution.
+//!
+//! ```
+//! use aerso::formation::FormationModel;
+//! use aerso::world::VortexWake;
+//! use aerso::types::Vector3;
+//!
+//! let model = FormationModel::new(VortexWake::new(10.0,5000.0),10.0,5000.0);
+//! let lateral: Vec<f64> = (0..=20).map(|i| i as f64).collect();
+//! let savings: Vec<f64> = lateral.iter()
+//!     .map(|&lateral| model.at_offset(Vector3::new(30.0,lateral,0.0),40.0,1.225).induced_drag_saving)
+//!     .collect();
+//! ```
+
+use crate::{AirState,Body};
+use crate::types::{Vector3,StateVector,Float,DefaultFloatRepr,constant};
+use crate::world::{VortexWake,WakeModel};
+
+/// Interference on a follower from the wake of a leader
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct FormationInterference<T: Float = DefaultFloatRepr> {
+    /// Offset of the follower from the leader, as distances behind, to the right and below along the leader's
+    /// horizontal heading (m)
+    pub offset: Vector3<T>,
+    /// Mean upwash over the follower's span weighted by an elliptic lift distribution, positive upward (m·s<sup>-1</sup>)
+    pub upwash: T,
+    /// Change in follower drag, negative for a benefit (N)
+    pub drag_change: T,
+    /// Fraction of the follower's isolated induced drag saved, negative for a penalty
+    pub induced_drag_saving: T,
+    /// Rolling moment on the follower about its body x axis (N·m)
+    pub rolling_moment: T,
+}
+
+/// Simplified model of the interference of a leader's wake on a follower
+#[derive(Copy,Clone,Debug)]
+pub struct FormationModel<T: Float = DefaultFloatRepr> {
+    /// Wake of the leader
+    wake: VortexWake<T>,
+    /// Follower wing span (m)
+    span: T,
+    /// Follower lift (N)
+    lift: T,
+    /// Follower lift-curve slope (rad<sup>-1</sup>)
+    lift_slope: T,
+    /// Follower wing area (m²)
+    area: T,
+    /// Number of spanwise strips
+    stations: usize,
+}
+
+impl<T: Float> FormationModel<T> {
+    /// Create a new [FormationModel] for a follower of `span` (m) supporting `lift` (N) in the `wake` of the leader
+    ///
+    /// The rolling moment is that of a rectangular wing with the aspect ratio of the span and a lift-curve
+    /// slope of 2π, unless set with [FormationModel::with_roll].
+    pub fn new(wake: VortexWake<T>, span: T, lift: T) -> Self {
+        let two_pi = constant::<T>(std::f64::consts::TAU);
+        FormationModel { wake, span, lift, lift_slope: two_pi, area: span * span / constant::<T>(8.0), stations: 20 }
+    }
+
+    /// Set the follower lift-curve slope (rad<sup>-1</sup>) and wing area (m²) used for the rolling moment
+    pub fn with_roll(mut self, lift_slope: T, area: T) -> Self {
+        self.lift_slope = lift_slope;
+        self.area = area;
+        self
+    }
+
+    /// Set the number of spanwise strips the wake is sampled at
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stations` is zero.
+    pub fn with_stations(mut self, stations: usize) -> Self {
+        if stations == 0 {
+            panic!("Formation model needs at least one spanwise station");
+        }
+        self.stations = stations;
+        self
+    }
+
+    /// Return the wake of the leader for modification, such as to update its lift
+    pub fn wake_mut(&mut self) -> &mut VortexWake<T> {
+        &mut self.wake
+    }
+
+    /// Set the follower lift (N)
+    pub fn set_lift(&mut self, lift: T) {
+        self.lift = lift;
+    }
+
+    /// Return the interference on a follower in `follower_state` with `follower_airstate`, from a leader in
+    /// `leader_state` with `leader_airstate`
+    pub fn evaluate(&self, leader_state: &StateVector<T>, leader_airstate: AirState<T>, follower_state: &StateVector<T>, follower_airstate: AirState<T>) -> FormationInterference<T> {
+        let leader_position: Vector3<T> = leader_state.fixed_rows::<3>(0).into();
+        let follower_position: Vector3<T> = follower_state.fixed_rows::<3>(0).into();
+        let nose: Vector3<T> = Body::get_dcm_body(leader_state) * Vector3::x();
+        let heading = <T as num_traits::Float>::atan2(nose[1],nose[0]);
+        let (sin_heading, cos_heading) = <T as num_traits::Float>::sin_cos(heading);
+        let relative = follower_position - leader_position;
+        let offset = Vector3::new(
+            -(relative[0] * cos_heading + relative[1] * sin_heading),
+            -relative[0] * sin_heading + relative[1] * cos_heading,
+            relative[2],
+        );
+
+        let to_world = Body::get_dcm_body(follower_state);
+        let spanwise: Vector3<T> = to_world * Vector3::y();
+        let up: Vector3<T> = -(to_world * Vector3::z());
+        let count = constant::<T>(self.stations as f64);
+        let strip = self.span / count;
+        let half = constant::<T>(0.5);
+        // Drag is weighted by the elliptic lift distribution of the follower, normalised over the strips
+        let (weighted, weights, moment) = (0..self.stations).fold((T::zero(),T::zero(),T::zero()),|(weighted, weights, moment), station| {
+            let y = (constant::<T>(station as f64) + half) * strip - half * self.span;
+            let upwash = self.wake.induced_velocity(leader_state,leader_airstate,&(follower_position + spanwise * y)).dot(&up);
+            let fraction = (y + y) / self.span;
+            let weight = <T as num_traits::Float>::sqrt(T::one() - fraction * fraction);
+            (weighted + upwash * weight,weights + weight,moment + upwash * y)
+        });
+        let upwash = weighted / weights;
+
+        let airspeed = follower_airstate.airspeed;
+        let (drag_change, induced_drag_saving, rolling_moment) = if airspeed > T::zero() {
+            let q = half * follower_airstate.density * airspeed * airspeed;
+            let pi = constant::<T>(std::f64::consts::PI);
+            let induced_drag = self.lift * self.lift / (q * pi * self.span * self.span);
+            let drag_change = -self.lift * upwash / airspeed;
+            // Lift of each strip grows with its upwash, and lift on the right wing rolls the follower left
+            let rolling_moment = -q * self.lift_slope * self.area / self.span * moment * strip / airspeed;
+            (drag_change,-drag_change / induced_drag,rolling_moment)
+        } else {
+            (T::zero(),T::zero(),T::zero())
+        };
+        FormationInterference { offset, upwash, drag_change, induced_drag_saving, rolling_moment }
+    }
+
+    /// Return the interference in straight and level flight at `airspeed` (m·s<sup>-1</sup>) in air of `density`
+    /// (kg·m<sup>-3</sup>), with the follower `offset` behind, to the right of and below the leader (m)
+    pub fn at_offset(&self, offset: Vector3<T>, airspeed: T, density: T) -> FormationInterference<T> {
+        let state = |position: Vector3<T>| {
+            let mut state = StateVector::zeros();
+            state.fixed_rows_mut::<3>(0).copy_from(&position);
+            state[3] = airspeed;
+            state[9] = T::one();
+            state
+        };
+        let airstate = AirState {
+            alpha: T::zero(),
+            beta: T::zero(),
+            airspeed,
+            q: constant::<T>(0.5) * density * airspeed * airspeed,
+            mach: T::zero(),
+            density,
+        };
+        self.evaluate(&state(Vector3::zeros()),airstate,&state(Vector3::new(-offset[0],offset[1],offset[2])),airstate)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::types::UnitQuaternion;
+    use approx::assert_relative_eq;
+
+    fn model() -> FormationModel<f64> {
+        FormationModel::new(VortexWake::new(10.0,5000.0),10.0,5000.0)
+    }
+
+    #[test]
+    fn test_offsets() {
+        let model = model();
+        // Directly behind the leader the follower sits in downwash, with no roll by symmetry
+        let behind = model.at_offset(Vector3::new(50.0,0.0,0.0),40.0,1.225);
+        assert!(behind.upwash < 0.0 && behind.drag_change > 0.0 && behind.induced_drag_saving < 0.0);
+        assert_relative_eq!(behind.rolling_moment,0.0,epsilon=1e-6);
+
+        // To the right in echelon it gains, and is rolled away from the leader
+        let echelon = model.at_offset(Vector3::new(50.0,10.0,0.0),40.0,1.225);
+        assert_eq!(echelon.offset,Vector3::new(50.0,10.0,0.0));
+        assert!(echelon.upwash > 0.0 && echelon.induced_drag_saving > 0.0);
+        assert!(echelon.rolling_moment > 0.0);
+        assert_relative_eq!(echelon.drag_change,-5000.0 * echelon.upwash / 40.0,max_relative=1e-9);
+        let left = model.at_offset(Vector3::new(50.0,-10.0,0.0),40.0,1.225);
+        assert_relative_eq!(left.induced_drag_saving,echelon.induced_drag_saving,max_relative=1e-9);
+        assert_relative_eq!(left.rolling_moment,-echelon.rolling_moment,max_relative=1e-9);
+
+        // Stepping out of the wake vertically reduces the benefit
+        let stacked = model.at_offset(Vector3::new(50.0,10.0,5.0),40.0,1.225);
+        assert!(stacked.induced_drag_saving < echelon.induced_drag_saving);
+    }
+
+    #[test]
+    fn test_optimum() {
+        // The benefit of equal wings peaks with the tips slightly overlapped, between π/4 and one span apart
+        let model = model().with_stations(40);
+        let lateral: Vec<f64> = (0..=150).map(|i| i as f64 * 0.1).collect();
+        let savings: Vec<f64> = lateral.iter().map(|&lateral| model.at_offset(Vector3::new(100.0,lateral,0.0),40.0,1.225).induced_drag_saving).collect();
+        let best = savings.iter().enumerate().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()).unwrap().0;
+        assert!(lateral[best] > 7.85 && lateral[best] < 10.0);
+        assert!(savings[best] > 0.1 && savings[best] < 1.0);
+
+        // Formation geometry follows the leader's heading
+        let mut leader = StateVector::zeros();
+        leader[3] = 40.0;
+        leader.fixed_rows_mut::<4>(6).copy_from(&UnitQuaternion::from_euler_angles(0.0,0.0,std::f64::consts::FRAC_PI_2).coords);
+        let mut follower = leader;
+        follower.fixed_rows_mut::<3>(0).copy_from(&Vector3::new(-8.0,-50.0,0.0));
+        let airstate = airstate(0.0,40.0);
+        let turned = model.evaluate(&leader,airstate,&follower,airstate);
+        assert_relative_eq!(turned.offset[0],50.0,max_relative=1e-9);
+        assert_relative_eq!(turned.offset[1],8.0,max_relative=1e-9);
+        assert_relative_eq!(turned.induced_drag_saving,model.at_offset(Vector3::new(50.0,8.0,0.0),40.0,1.225).induced_drag_saving,max_relative=1e-6);
+    }
+}
@@ -0,0 +1,174 @@
+//! Transforms between axis systems
+//!
+//! Vectors can be transformed between the world (North-East-Down) axes and the body, stability and wind
+//! axes of a vehicle with [FrameTransform::transform], which uses the current attitude and the angles of
+//! attack and sideslip of the vehicle.
+//!
+//! The stability axes are the body axes rotated about the body y axis by the angle of attack, so the x axis
+//! lies along the projection of the air-relative velocity in the body plane of symmetry. The wind axes are
+//! the stability axes rotated about their z axis by the sideslip angle, so the x axis lies along the
+//! air-relative velocity.
+//!
+//! ```
+//! use aerso::{Body,AeroBody};
+//! use aerso::frames::{Axes,FrameTransform};
+//! use aerso::types::{Vector3,Matrix3,UnitQuaternion};
+//!
+//! let body = Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(50.0,0.0,5.0),UnitQuaternion::identity(),Vector3::zeros());
+//! let vehicle = AeroBody::new(body);
+//! // Drag acts along the negative wind x axis
+//! let drag = vehicle.transform(Vector3::new(-10.0,0.0,0.0),Axes::Wind,Axes::Body);
+//! assert!(drag[0] < 0.0 && drag[2] < 0.0);
+//! ```
+
+use crate::{Body,AeroBody,AffectedBody,StaticAffectedBody,WindModel,DensityModel};
+use crate::types::{Vector3,Matrix3,StateVector,StateView,Frame,Float,DefaultFloatRepr};
+
+/// Axis system of a vector
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Axes {
+    /// World (North-East-Down) axes
+    World,
+    /// Body (forward-right-down) axes
+    Body,
+    /// Stability axes, the body axes rotated by the angle of attack
+    Stability,
+    /// Wind axes, with the x axis along the air-relative velocity
+    Wind,
+}
+
+impl From<Frame> for Axes {
+    fn from(frame: Frame) -> Self {
+        match frame {
+            Frame::World => Axes::World,
+            Frame::Body => Axes::Body,
+        }
+    }
+}
+
+/// Return the matrix transforming stability-axis vectors into the body axes at angle of attack `alpha` (rad)
+pub fn stability_to_body<T: Float>(alpha: T) -> Matrix3<T> {
+    let (sin_alpha, cos_alpha) = <T as num_traits::Float>::sin_cos(alpha);
+    Matrix3::new(
+        cos_alpha, T::zero(), -sin_alpha,
+        T::zero(), T::one(), T::zero(),
+        sin_alpha, T::zero(), cos_alpha)
+}
+
+/// Return the matrix transforming wind-axis vectors into the body axes at angle of attack `alpha` and
+/// sideslip `beta` (rad)
+pub fn wind_to_body<T: Float>(alpha: T, beta: T) -> Matrix3<T> {
+    let (sin_beta, cos_beta) = <T as num_traits::Float>::sin_cos(beta);
+    let wind_to_stability = Matrix3::new(
+        cos_beta, -sin_beta, T::zero(),
+        sin_beta, cos_beta, T::zero(),
+        T::zero(), T::zero(), T::one());
+    stability_to_body(alpha) * wind_to_stability
+}
+
+/// Return the matrix transforming vectors in `axes` into the body axes
+fn to_body<T: Float>(state: &StateVector<T>, alpha: T, beta: T, axes: Axes) -> Matrix3<T> {
+    match axes {
+        Axes::World => Body::get_dcm(state),
+        Axes::Body => Matrix3::identity(),
+        Axes::Stability => stability_to_body(alpha),
+        Axes::Wind => wind_to_body(alpha,beta),
+    }
+}
+
+/// Transform `vector` from the `from` axes to the `to` axes of a vehicle in `state` at angle of attack
+/// `alpha` and sideslip `beta` (rad)
+pub fn transform<T: Float>(state: &StateVector<T>, alpha: T, beta: T, vector: Vector3<T>, from: Axes, to: Axes) -> Vector3<T> {
+    if from == to {
+        return vector;
+    }
+    to_body(state,alpha,beta,to).transpose() * (to_body(state,alpha,beta,from) * vector)
+}
+
+/// Trait for vehicles whose vectors can be transformed between axis systems
+pub trait FrameTransform<T: Float = DefaultFloatRepr>: StateView<T> {
+    /// Return the angles of attack and sideslip (rad) which define the stability and wind axes
+    fn aerodynamic_angles(&self) -> (T,T);
+
+    /// Transform `vector` from the `from` axes to the `to` axes using the current state
+    fn transform(&self, vector: Vector3<T>, from: Axes, to: Axes) -> Vector3<T> {
+        let (alpha, beta) = self.aerodynamic_angles();
+        transform(&self.statevector(),alpha,beta,vector,from,to)
+    }
+}
+
+impl<T: Float, W: WindModel<T>, D: DensityModel<T>> FrameTransform<T> for AeroBody<T,W,D> {
+    fn aerodynamic_angles(&self) -> (T,T) {
+        let airstate = self.get_airstate();
+        (airstate.alpha,airstate.beta)
+    }
+}
+
+impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> FrameTransform<T> for AffectedBody<I,T,W,D> {
+    fn aerodynamic_angles(&self) -> (T,T) {
+        let airstate = self.get_airstate();
+        (airstate.alpha,airstate.beta)
+    }
+}
+
+impl<E, T: Float, W: WindModel<T>, D: DensityModel<T>> FrameTransform<T> for StaticAffectedBody<E,T,W,D> {
+    fn aerodynamic_angles(&self) -> (T,T) {
+        let airstate = self.get_airstate();
+        (airstate.alpha,airstate.beta)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::UnitQuaternion;
+    use crate::wind_models::ConstantWind;
+    use crate::density_models::StandardDensity;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_aerodynamic_axes() {
+        let (alpha, beta) = (0.2,-0.1);
+        // The wind x axis lies along the air-relative velocity
+        let velocity = wind_to_body(alpha,beta) * Vector3::x();
+        assert_relative_eq!(<f64 as num_traits::Float>::atan2(velocity[2],velocity[0]),alpha,max_relative=1e-12);
+        assert_relative_eq!(velocity[1].asin(),beta,max_relative=1e-12);
+        let stability = stability_to_body(alpha) * Vector3::x();
+        assert_relative_eq!(stability[1],0.0);
+        assert_relative_eq!(stability[2],alpha.sin(),max_relative=1e-12);
+
+        // Transforms round trip and are identities between equal axes
+        let state = StateVector::zeros();
+        let vector = Vector3::new(1.0,-2.0,0.5);
+        let there = transform(&state,alpha,beta,vector,Axes::Wind,Axes::Stability);
+        let back = transform(&state,alpha,beta,there,Axes::Stability,Axes::Wind);
+        assert!((back - vector).norm() < 1e-12);
+        assert_eq!(transform(&state,alpha,beta,vector,Axes::Body,Axes::Body),vector);
+    }
+
+    #[test]
+    fn test_vehicle_transform() {
+        // Heading East at 30 m/s with a 10 m/s headwind and a 2 m/s updraft
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.0,std::f64::consts::FRAC_PI_2);
+        let body = Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(30.0,0.0,0.0),attitude,Vector3::zeros());
+        let vehicle = AeroBody::with_density_model(body,ConstantWind::new(Vector3::new(0.0,-10.0,-2.0)),StandardDensity{});
+        let airstate = vehicle.get_airstate();
+
+        // The air-relative velocity in the world is the airspeed along the wind x axis
+        let air_velocity = vehicle.transform(Vector3::new(airstate.airspeed,0.0,0.0),Axes::Wind,Axes::World);
+        assert!((air_velocity - Vector3::new(0.0,40.0,2.0)).norm() < 1e-9);
+        let east = vehicle.transform(Vector3::new(0.0,1.0,0.0),Frame::World.into(),Axes::Body);
+        assert!((east - Vector3::x()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_statevector_world_frame() {
+        // Heading East, rolling at 0.1 rad/s
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.0,std::f64::consts::FRAC_PI_2);
+        let body = Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(30.0,0.0,0.0),attitude,Vector3::new(0.1,0.0,0.0));
+        let state = body.statevector();
+        assert!((state.velocity_in_frame(Frame::World) - Vector3::new(0.0,30.0,0.0)).norm() < 1e-12);
+        assert!((state.rates_in_frame(Frame::World) - Vector3::new(0.0,0.1,0.0)).norm() < 1e-12);
+        assert!((body.velocity_in_frame(Frame::World) - state.velocity_in_frame(Frame::World)).norm() < 1e-12);
+    }
+}
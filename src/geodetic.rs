@@ -0,0 +1,152 @@
+//! Geodetic positions
+//!
+//! Vehicle positions are North-East-Down coordinates in a local tangent plane. A [LocalTangentPlane]
+//! fixes that plane to a geodetic origin on the WGS84 ellipsoid, so positions can be converted to and
+//! from latitude, longitude and altitude, as reported by satellite navigation.
+
+use crate::types::{Vector3,Matrix3,Float,DefaultFloatRepr,constant};
+
+/// WGS84 semi-major axis (m)
+const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// Geodetic position on the WGS84 ellipsoid
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Geodetic<T: Float = DefaultFloatRepr> {
+    /// Latitude (rad)
+    pub latitude: T,
+    /// Longitude (rad)
+    pub longitude: T,
+    /// Altitude above the ellipsoid (m)
+    pub altitude: T,
+}
+
+impl<T: Float> Geodetic<T> {
+    /// Create a new [Geodetic] position
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - Latitude (rad)
+    /// * `longitude` - Longitude (rad)
+    /// * `altitude` - Altitude above the ellipsoid (m)
+    pub fn new(latitude: T, longitude: T, altitude: T) -> Self {
+        Geodetic { latitude, longitude, altitude }
+    }
+
+    /// Create a new [Geodetic] position from latitude and longitude in degrees
+    pub fn from_degrees(latitude: T, longitude: T, altitude: T) -> Self {
+        Geodetic::new(latitude.to_radians(),longitude.to_radians(),altitude)
+    }
+
+    /// Return the Earth-centred, Earth-fixed position (m)
+    pub fn to_ecef(&self) -> Vector3<T> {
+        let a = constant::<T>(WGS84_A);
+        let e2 = eccentricity_squared::<T>();
+        let (sin_lat, cos_lat) = <T as num_traits::Float>::sin_cos(self.latitude);
+        let (sin_lon, cos_lon) = <T as num_traits::Float>::sin_cos(self.longitude);
+        let normal = a / <T as num_traits::Float>::sqrt(T::one() - e2 * sin_lat * sin_lat);
+        Vector3::new(
+            (normal + self.altitude) * cos_lat * cos_lon,
+            (normal + self.altitude) * cos_lat * sin_lon,
+            (normal * (T::one() - e2) + self.altitude) * sin_lat,
+        )
+    }
+
+    /// Return the geodetic position of the Earth-centred, Earth-fixed position `ecef` (m)
+    pub fn from_ecef(ecef: &Vector3<T>) -> Self {
+        let a = constant::<T>(WGS84_A);
+        let e2 = eccentricity_squared::<T>();
+        let p = <T as num_traits::Float>::hypot(ecef[0],ecef[1]);
+        let longitude = <T as num_traits::Float>::atan2(ecef[1],ecef[0]);
+        // Fixed-point iteration on latitude converges to millimetres within a few steps near the surface
+        let mut latitude = <T as num_traits::Float>::atan2(ecef[2],p * (T::one() - e2));
+        let mut altitude = T::zero();
+        for _ in 0..5 {
+            let sin_lat = <T as num_traits::Float>::sin(latitude);
+            let normal = a / <T as num_traits::Float>::sqrt(T::one() - e2 * sin_lat * sin_lat);
+            altitude = p / <T as num_traits::Float>::cos(latitude) - normal;
+            latitude = <T as num_traits::Float>::atan2(ecef[2],p * (T::one() - e2 * normal / (normal + altitude)));
+        }
+        Geodetic { latitude, longitude, altitude }
+    }
+}
+
+/// Return the square of the WGS84 first eccentricity
+fn eccentricity_squared<T: Float>() -> T {
+    constant::<T>(WGS84_F * (2.0 - WGS84_F))
+}
+
+/// Local North-East-Down tangent plane fixed to a geodetic origin
+#[derive(Copy,Clone,Debug)]
+pub struct LocalTangentPlane<T: Float = DefaultFloatRepr> {
+    /// Geodetic position of the plane origin
+    origin: Geodetic<T>,
+    /// Earth-centred, Earth-fixed position of the origin (m)
+    origin_ecef: Vector3<T>,
+    /// Rotation from Earth-centred, Earth-fixed to North-East-Down axes
+    ecef_to_ned: Matrix3<T>,
+}
+
+impl<T: Float> LocalTangentPlane<T> {
+    /// Create a new [LocalTangentPlane] with its origin at `origin`
+    pub fn new(origin: Geodetic<T>) -> Self {
+        let (sin_lat, cos_lat) = <T as num_traits::Float>::sin_cos(origin.latitude);
+        let (sin_lon, cos_lon) = <T as num_traits::Float>::sin_cos(origin.longitude);
+        LocalTangentPlane {
+            origin,
+            origin_ecef: origin.to_ecef(),
+            ecef_to_ned: Matrix3::new(
+                -sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat,
+                -sin_lon,           cos_lon,            T::zero(),
+                -cos_lat * cos_lon, -cos_lat * sin_lon, -sin_lat,
+            ),
+        }
+    }
+
+    /// Return the geodetic position of the plane origin
+    pub fn origin(&self) -> Geodetic<T> {
+        self.origin
+    }
+
+    /// Return the geodetic position of the North-East-Down `position` (m)
+    pub fn to_geodetic(&self, position: &Vector3<T>) -> Geodetic<T> {
+        Geodetic::from_ecef(&(self.origin_ecef + self.ecef_to_ned.transpose() * position))
+    }
+
+    /// Return the North-East-Down position of `geodetic` (m)
+    pub fn to_local(&self, geodetic: &Geodetic<T>) -> Vector3<T> {
+        self.ecef_to_ned * (geodetic.to_ecef() - self.origin_ecef)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_round_trip() {
+        let plane = LocalTangentPlane::new(Geodetic::from_degrees(51.5,-1.25,80.0));
+        let position = Vector3::new(1500.0,-4200.0,-350.0);
+        assert_relative_eq!(plane.to_geodetic(&Vector3::new(0.0,0.0,-350.0)).altitude,430.0,epsilon=1e-6);
+        let geodetic = plane.to_geodetic(&position);
+        // The tangent plane rises above the curved ellipsoid away from the origin
+        assert!(geodetic.altitude > 431.0);
+        let local = plane.to_local(&geodetic);
+        for idx in 0..3 {
+            assert_relative_eq!(local[idx],position[idx],epsilon=1e-6);
+        }
+        assert_relative_eq!(plane.to_geodetic(&Vector3::zeros()).latitude,plane.origin().latitude,epsilon=1e-12);
+    }
+
+    #[test]
+    fn test_equator_arc_lengths() {
+        let plane = LocalTangentPlane::new(Geodetic::new(0.0,0.0,0.0));
+        let north = plane.to_geodetic(&Vector3::new(1000.0,0.0,0.0));
+        let east = plane.to_geodetic(&Vector3::new(0.0,1000.0,0.0));
+        // Meridian radius at the equator is a(1 - e²), the prime vertical radius is a
+        assert_relative_eq!(north.latitude,1000.0 / (WGS84_A * (1.0 - eccentricity_squared::<f64>())),max_relative=1e-6);
+        assert_relative_eq!(east.longitude,1000.0 / WGS84_A,max_relative=1e-6);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Reference geometry of a vehicle, shared by its aerodynamic models
+///
+/// Coefficient-based models non-dimensionalise forces on the reference area, rolling and yawing moments
+/// and rates on the span, and pitching moments and rates on the mean chord. Giving a [VehicleGeometry] to
+/// an [AffectedBody](crate::AffectedBody) with [AffectedBody::with_geometry](crate::AffectedBody::with_geometry)
+/// passes it to every effector through [AeroEffect::set_geometry](crate::AeroEffect::set_geometry), so that
+/// the whole-vehicle models cannot silently disagree.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct VehicleGeometry<T: Float = DefaultFloatRepr> {
+    /// Reference area (m<sup>2</sup>)
+    pub area: T,
+    /// Reference span (m)
+    pub span: T,
+    /// Reference mean chord (m)
+    pub chord: T,
+}
+
+impl<T: Float> VehicleGeometry<T> {
+    /// Create a new [VehicleGeometry]
+    ///
+    /// # Panics
+    ///
+    /// Panics if any dimension is not positive.
+    pub fn new(area: T, span: T, chord: T) -> Self {
+        if area <= T::zero() || span <= T::zero() || chord <= T::zero() {
+            panic!("Reference geometry must be positive");
+        }
+        VehicleGeometry { area, span, chord }
+    }
+
+    /// Return the aspect ratio `b² / S`
+    pub fn aspect_ratio(&self) -> T {
+        self.span * self.span / self.area
+    }
+}
@@ -0,0 +1,159 @@
+//! glam math-type interop
+//!
+//! With the `glam` feature, [ToGlam] and [FromGlam] convert the crate's nalgebra [Vector3] and
+//! [UnitQuaternion] to and from their [glam] counterparts: `Vec3` and `Quat` in single precision, and
+//! `DVec3` and `DQuat` in double precision. [GlamPose] extracts a `Transform`-style translation and
+//! rotation from any [StateView], for engines which standardise on glam.
+//!
+//! Conversions keep the crate's axes, North-East-Down in the world and forward-right-down in the body;
+//! see the `bevy` feature for conversion to a Y-up convention.
+//!
+//! ```
+//! use aerso::glam_interop::{ToGlam,FromGlam,GlamPose};
+//! use aerso::types::{Vector3,UnitQuaternion,StateVector};
+//!
+//! let position = Vector3::new(10.0,0.0,-100.0).to_glam();
+//! assert_eq!(position,glam::DVec3::new(10.0,0.0,-100.0));
+//! assert_eq!(Vector3::from_glam(position),Vector3::new(10.0,0.0,-100.0));
+//!
+//! let mut state = StateVector::zeros();
+//! state[9] = 1.0;
+//! let pose = GlamPose::from_state(&state);
+//! assert_eq!(pose.rotation,glam::Quat::IDENTITY);
+//! ```
+
+use glam::{Vec3,DVec3,Quat,DQuat,Mat4};
+use nalgebra::Quaternion;
+
+use crate::types::{Vector3,UnitQuaternion,StateView,Float};
+
+/// Conversion of a nalgebra type to its glam counterpart
+pub trait ToGlam {
+    /// glam counterpart of the type
+    type Glam;
+
+    /// Return the glam counterpart of `self`
+    fn to_glam(&self) -> Self::Glam;
+}
+
+/// Conversion of a glam type to its nalgebra counterpart
+pub trait FromGlam<G> {
+    /// Return the nalgebra counterpart of `value`
+    fn from_glam(value: G) -> Self;
+}
+
+impl ToGlam for Vector3<f32> {
+    type Glam = Vec3;
+    fn to_glam(&self) -> Vec3 {
+        Vec3::new(self[0],self[1],self[2])
+    }
+}
+
+impl ToGlam for Vector3<f64> {
+    type Glam = DVec3;
+    fn to_glam(&self) -> DVec3 {
+        DVec3::new(self[0],self[1],self[2])
+    }
+}
+
+impl ToGlam for UnitQuaternion<f32> {
+    type Glam = Quat;
+    fn to_glam(&self) -> Quat {
+        Quat::from_xyzw(self.i,self.j,self.k,self.w)
+    }
+}
+
+impl ToGlam for UnitQuaternion<f64> {
+    type Glam = DQuat;
+    fn to_glam(&self) -> DQuat {
+        DQuat::from_xyzw(self.i,self.j,self.k,self.w)
+    }
+}
+
+impl FromGlam<Vec3> for Vector3<f32> {
+    fn from_glam(value: Vec3) -> Self {
+        Vector3::new(value.x,value.y,value.z)
+    }
+}
+
+impl FromGlam<DVec3> for Vector3<f64> {
+    fn from_glam(value: DVec3) -> Self {
+        Vector3::new(value.x,value.y,value.z)
+    }
+}
+
+/// The quaternion is renormalised, as glam does not enforce unit length
+impl FromGlam<Quat> for UnitQuaternion<f32> {
+    fn from_glam(value: Quat) -> Self {
+        UnitQuaternion::from_quaternion(Quaternion::new(value.w,value.x,value.y,value.z))
+    }
+}
+
+/// The quaternion is renormalised, as glam does not enforce unit length
+impl FromGlam<DQuat> for UnitQuaternion<f64> {
+    fn from_glam(value: DQuat) -> Self {
+        UnitQuaternion::from_quaternion(Quaternion::new(value.w,value.x,value.y,value.z))
+    }
+}
+
+/// `Transform`-style pose of a body in single precision, in the crate's axes
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct GlamPose {
+    /// World-frame position (m)
+    pub translation: Vec3,
+    /// Body-to-world rotation
+    pub rotation: Quat,
+}
+
+impl GlamPose {
+    /// Return the pose of the body in `state`
+    pub fn from_state<T: Float, S: StateView<T> + ?Sized>(state: &S) -> Self {
+        let position = state.position();
+        let attitude = state.attitude();
+        let single = |value: T| num_traits::cast::<T,f32>(value).unwrap();
+        GlamPose {
+            translation: Vec3::new(single(position[0]),single(position[1]),single(position[2])),
+            rotation: Quat::from_xyzw(single(attitude.i),single(attitude.j),single(attitude.k),single(attitude.w)),
+        }
+    }
+
+    /// Return the body-to-world transformation matrix of the pose
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation,self.translation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::StateVector;
+
+    #[test]
+    fn test_round_trip() {
+        let vector = Vector3::new(1.0_f32,-2.0,3.0);
+        assert_eq!(Vector3::from_glam(vector.to_glam()),vector);
+        let attitude = UnitQuaternion::from_euler_angles(0.1_f64,-0.2,0.3);
+        let converted = attitude.to_glam();
+        assert_eq!((converted.x,converted.y,converted.z,converted.w),(attitude.i,attitude.j,attitude.k,attitude.w));
+        assert!(UnitQuaternion::from_glam(converted).angle_to(&attitude) < 1e-12);
+
+        // Rotations agree between the two libraries
+        let rotated = converted * vector.cast::<f64>().to_glam();
+        let expected = attitude * vector.cast::<f64>();
+        assert!((Vector3::from_glam(rotated) - expected).norm() < 1e-12);
+        assert!((UnitQuaternion::from_glam(Quat::from_xyzw(0.0,0.0,0.0,2.0)).w - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pose() {
+        let mut state = StateVector::<f64>::zeros();
+        state.fixed_rows_mut::<3>(0).copy_from(&Vector3::new(100.0,50.0,-20.0));
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.0,std::f64::consts::FRAC_PI_2);
+        state.fixed_rows_mut::<4>(6).copy_from(&attitude.coords);
+        let pose = GlamPose::from_state(&state);
+        assert_eq!(pose.translation,Vec3::new(100.0,50.0,-20.0));
+        // Heading east, the body forward axis points along +y and sits at the position
+        let nose = pose.to_mat4().transform_point3(Vec3::X);
+        assert!((nose - Vec3::new(100.0,51.0,-20.0)).length() < 1e-4);
+    }
+}
@@ -0,0 +1,337 @@
+//! Hardware-in-the-loop interface
+//!
+//! A [HilServer] closes the loop with an external autopilot over UDP. Each tick it sends a
+//! [StatePacket] holding the time, statevector, airstate and any sensor measurements, then waits for a
+//! command packet holding the inputstate for the next step.
+//!
+//! Packets are either JSON or a compact little-endian binary schema, chosen with [PacketFormat].
+//!
+//! # JSON
+//!
+//! State packets are objects of the form
+//!
+//! `{"time":1.0,"state":[x,y,z,u,v,w,qx,qy,qz,qw,p,q,r],"airstate":{"alpha":..,"beta":..,"airspeed":..,"q":..,"mach":..,"density":..},"sensors":{"imu":[..]}}`
+//!
+//! with non-finite values sent as `null`. Command packets are either an array of numbers, or an object
+//! with an `"inputs"` array.
+//!
+//! # Binary
+//!
+//! State packets are the magic bytes `AERS`, then the time, the 13 statevector elements and the 6 airstate
+//! elements in the JSON order as `f64`, then a `u16` count of sensors, each as a `u8` name length, the
+//! UTF-8 name, a `u16` count of values and the values as `f64`. Command packets are the magic bytes `AERC`,
+//! a `u16` count of inputs and the inputs as `f64`.
+
+use std::convert::TryFrom;
+use std::io;
+use std::net::{UdpSocket,ToSocketAddrs,SocketAddr};
+use std::time::Duration;
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel};
+use crate::sensors::Measurement;
+use crate::types::{StateVector,StateView,Float,DefaultFloatRepr,constant};
+
+/// Magic bytes starting a binary state packet
+const STATE_MAGIC: &[u8;4] = b"AERS";
+/// Magic bytes starting a binary command packet
+const COMMAND_MAGIC: &[u8;4] = b"AERC";
+/// Largest packet received
+const MAX_PACKET: usize = 65_507;
+
+/// Encoding of [HilServer] packets
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum PacketFormat {
+    /// JSON text
+    Json,
+    /// Compact little-endian binary
+    Binary,
+}
+
+/// State of the vehicle sent each tick by a [HilServer]
+#[derive(Clone)]
+pub struct StatePacket<T: Float = DefaultFloatRepr> {
+    /// Simulation time (s)
+    pub time: T,
+    /// Vehicle statevector
+    pub state: StateVector<T>,
+    /// Vehicle airstate
+    pub airstate: AirState<T>,
+    /// Named sensor values
+    pub sensors: Vec<(String,Vec<T>)>,
+}
+
+impl<T: Float> StatePacket<T> {
+    /// Create a new [StatePacket] from the state of `vehicle` at `time`, with no sensors
+    pub fn new<I, W: WindModel<T>, D: DensityModel<T>>(time: T, vehicle: &AffectedBody<I,T,W,D>) -> Self {
+        StatePacket {
+            time,
+            state: vehicle.statevector(),
+            airstate: vehicle.get_airstate(),
+            sensors: Vec::new(),
+        }
+    }
+
+    /// Add the values of `measurement` under `name`, in the order of [Measurement::map_values]
+    pub fn with_measurement<M: Measurement<T>>(mut self, name: &str, measurement: &M) -> Self {
+        let mut values = Vec::new();
+        measurement.map_values(|value| { values.push(value); value });
+        self.sensors.push((name.to_string(),values));
+        self
+    }
+
+    /// Return the airstate elements in packet order
+    fn airstate_values(&self) -> [T;6] {
+        let airstate = &self.airstate;
+        [airstate.alpha,airstate.beta,airstate.airspeed,airstate.q,airstate.mach,airstate.density]
+    }
+
+    /// Encode the packet as JSON
+    pub fn to_json(&self) -> String {
+        let names = ["alpha","beta","airspeed","q","mach","density"];
+        let airstate: Vec<String> = names.iter().zip(self.airstate_values().iter()).map(|(name, value)| format!("\"{}\":{}",name,json_number(*value))).collect();
+        let sensors: Vec<String> = self.sensors.iter().map(|(name, values)| format!("{}:{}",json_string(name),json_array(values))).collect();
+        format!("{{\"time\":{},\"state\":{},\"airstate\":{{{}}},\"sensors\":{{{}}}}}",
+            json_number(self.time),json_array(self.state.as_slice()),airstate.join(","),sensors.join(","))
+    }
+
+    /// Encode the packet as binary
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 65535 sensors, a sensor name is longer than 255 bytes, or a sensor
+    /// has more than 65535 values.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut bytes = STATE_MAGIC.to_vec();
+        let push = |bytes: &mut Vec<u8>, value: T| bytes.extend_from_slice(&value.to_f64().unwrap().to_le_bytes());
+        push(&mut bytes,self.time);
+        self.state.iter().for_each(|value| push(&mut bytes,*value));
+        self.airstate_values().iter().for_each(|value| push(&mut bytes,*value));
+        bytes.extend_from_slice(&u16::try_from(self.sensors.len()).expect("Too many sensors for a binary packet").to_le_bytes());
+        for (name, values) in &self.sensors {
+            bytes.push(u8::try_from(name.len()).expect("Sensor name too long for a binary packet"));
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&u16::try_from(values.len()).expect("Too many sensor values for a binary packet").to_le_bytes());
+            values.iter().for_each(|value| push(&mut bytes,*value));
+        }
+        bytes
+    }
+
+    /// Encode the packet in `format`
+    pub fn encode(&self, format: PacketFormat) -> Vec<u8> {
+        match format {
+            PacketFormat::Json => self.to_json().into_bytes(),
+            PacketFormat::Binary => self.to_binary(),
+        }
+    }
+}
+
+/// Format `value` as a JSON number, or `null` if it is not finite
+fn json_number<T: Float>(value: T) -> String {
+    let value = value.to_f64().unwrap();
+    if value.is_finite() { format!("{}",value) } else { "null".to_string() }
+}
+
+/// Format `values` as a JSON array of numbers
+fn json_array<T: Float>(values: &[T]) -> String {
+    format!("[{}]",values.iter().map(|value| json_number(*value)).collect::<Vec<String>>().join(","))
+}
+
+/// Format `text` as a JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}",c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Parse a command packet in `format` into an inputstate
+pub fn parse_commands<T: Float>(packet: &[u8], format: PacketFormat) -> Result<Vec<T>,String> {
+    match format {
+        PacketFormat::Json => {
+            let text = std::str::from_utf8(packet).map_err(|e| format!("Command packet is not UTF-8: {}",e))?.trim();
+            let array = if text.starts_with('{') {
+                let key = text.find("\"inputs\"").ok_or_else(|| "Command object has no \"inputs\"".to_string())?;
+                &text[key + "\"inputs\"".len()..]
+            } else {
+                text
+            };
+            let start = array.find('[').ok_or_else(|| "Commands are not an array".to_string())?;
+            let end = array[start..].find(']').ok_or_else(|| "Unterminated command array".to_string())? + start;
+            let body = array[start + 1..end].trim();
+            if body.is_empty() {
+                return Ok(Vec::new());
+            }
+            body.split(',')
+                .map(|value| value.trim().parse::<f64>().map(|value| constant::<T>(value)).map_err(|e| format!("Invalid command {:?}: {}",value.trim(),e)))
+                .collect()
+        },
+        PacketFormat::Binary => {
+            if packet.len() < 6 || &packet[..4] != COMMAND_MAGIC {
+                return Err("Command packet does not start with AERC".to_string());
+            }
+            let count = u16::from_le_bytes([packet[4],packet[5]]) as usize;
+            if packet.len() != 6 + 8 * count {
+                return Err(format!("Command packet of {} bytes does not hold {} inputs",packet.len(),count));
+            }
+            Ok(packet[6..].chunks_exact(8).map(|chunk| {
+                let mut bytes = [0u8;8];
+                bytes.copy_from_slice(chunk);
+                constant::<T>(f64::from_le_bytes(bytes))
+            }).collect())
+        },
+    }
+}
+
+/// Encode `inputs` as a binary command packet, as sent by an external autopilot
+pub fn encode_binary_commands<T: Float>(inputs: &[T]) -> Vec<u8> {
+    let mut bytes = COMMAND_MAGIC.to_vec();
+    bytes.extend_from_slice(&(inputs.len() as u16).to_le_bytes());
+    inputs.iter().for_each(|value| bytes.extend_from_slice(&value.to_f64().unwrap().to_le_bytes()));
+    bytes
+}
+
+/// UDP server exchanging state and commands with an external autopilot
+///
+/// Commands are only accepted from the remote address. If no command arrives within the timeout, the
+/// previous commands are held, so a slow or disconnected autopilot leaves the actuators where they were.
+#[derive(Debug)]
+pub struct HilServer<T: Float = DefaultFloatRepr> {
+    /// Bound socket
+    socket: UdpSocket,
+    /// Address of the autopilot
+    remote: SocketAddr,
+    /// Packet encoding
+    format: PacketFormat,
+    /// Latest commands received
+    commands: Vec<T>,
+    /// Number of ticks on which no command arrived
+    missed: usize,
+}
+
+impl<T: Float> HilServer<T> {
+    /// Create a new [HilServer] bound to `local`, sending JSON to `remote`
+    ///
+    /// The server waits up to 100 ms for commands. Until the first command arrives, the inputstate is
+    /// `initial_commands`.
+    pub fn bind<A: ToSocketAddrs, B: ToSocketAddrs>(local: A, remote: B, initial_commands: Vec<T>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local)?;
+        socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+        let remote = remote.to_socket_addrs()?.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput,"No remote address"))?;
+        Ok(HilServer { socket, remote, format: PacketFormat::Json, commands: initial_commands, missed: 0 })
+    }
+
+    /// Set the packet encoding
+    pub fn with_format(mut self, format: PacketFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the time to wait for commands each tick, or `None` to wait indefinitely
+    pub fn with_timeout(self, timeout: Option<Duration>) -> io::Result<Self> {
+        self.socket.set_read_timeout(timeout)?;
+        Ok(self)
+    }
+
+    /// Return the local address of the server
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Return the latest commands
+    pub fn commands(&self) -> &[T] {
+        &self.commands
+    }
+
+    /// Return the number of ticks on which no command arrived
+    pub fn missed(&self) -> usize {
+        self.missed
+    }
+
+    /// Send `packet`, then wait for commands and return them
+    ///
+    /// Malformed command packets are an [io::ErrorKind::InvalidData] error.
+    pub fn exchange(&mut self, packet: &StatePacket<T>) -> io::Result<&[T]> {
+        self.socket.send_to(&packet.encode(self.format),self.remote)?;
+        let mut buffer = vec![0u8;MAX_PACKET];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((length, from)) if from == self.remote => {
+                    self.commands = parse_commands(&buffer[..length],self.format).map_err(|e| io::Error::new(io::ErrorKind::InvalidData,e))?;
+                    return Ok(&self.commands);
+                },
+                Ok(_) => continue,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                    self.missed += 1;
+                    return Ok(&self.commands);
+                },
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Exchange the state of `vehicle` at `time` for commands, and step it by `delta_t` with them
+    ///
+    /// Sensor measurements can be added with [HilServer::exchange] and a [StatePacket] instead.
+    pub fn step<I: From<Vec<T>>, W: WindModel<T>, D: DensityModel<T>>(&mut self, vehicle: &mut AffectedBody<I,T,W,D>, time: T, delta_t: T) -> io::Result<()> {
+        let commands = self.exchange(&StatePacket::new(time,vehicle))?.to_vec();
+        vehicle.step(delta_t,&I::from(commands));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sensors::{Sensor,Truth,Variometer};
+    use crate::trim::test_aircraft;
+
+    #[test]
+    fn test_encoding() {
+        let vehicle = test_aircraft::vehicle(100.0);
+        let measurement = Variometer::new(0.0).update(&Truth::new(0.0,&vehicle)).unwrap();
+        let packet = StatePacket::new(0.5,&vehicle).with_measurement("vario",&measurement);
+        let json = packet.to_json();
+        assert!(json.starts_with("{\"time\":0.5,\"state\":[0,0,-100,"));
+        assert!(json.contains("\"airstate\":{\"alpha\":"));
+        assert!(json.ends_with("\"sensors\":{\"vario\":[0,0]}}"));
+        assert_eq!(packet.to_binary().len(),4 + 8 * 20 + 2 + 1 + 5 + 2 + 8 * 2);
+
+        assert_eq!(parse_commands::<f64>(b"[0.1, -0.2,0.5]",PacketFormat::Json),Ok(vec![0.1,-0.2,0.5]));
+        assert_eq!(parse_commands::<f64>(b"{\"time\":1.0,\"inputs\":[1e-3]}",PacketFormat::Json),Ok(vec![0.001]));
+        assert!(parse_commands::<f64>(b"[0.1,x]",PacketFormat::Json).is_err());
+        assert_eq!(parse_commands::<f64>(&encode_binary_commands(&[0.25,1.0]),PacketFormat::Binary),Ok(vec![0.25,1.0]));
+        assert!(parse_commands::<f64>(b"AERC\x02\x00",PacketFormat::Binary).is_err());
+    }
+
+    #[test]
+    fn test_loopback() {
+        let autopilot = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut server = HilServer::bind("127.0.0.1:0",autopilot.local_addr().unwrap(),vec![0.0,0.0,0.0,0.0]).unwrap().with_format(PacketFormat::Binary);
+        let server_addr = server.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            let mut buffer = vec![0u8;MAX_PACKET];
+            for tick in 0..3 {
+                let (length, _) = autopilot.recv_from(&mut buffer).unwrap();
+                assert_eq!(&buffer[..4],STATE_MAGIC);
+                assert_eq!(length,4 + 8 * 20 + 2);
+                autopilot.send_to(&encode_binary_commands(&[0.0,0.0,0.0,0.2 * tick as f64]),server_addr).unwrap();
+            }
+        });
+
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        for tick in 0..3 {
+            server.step(&mut vehicle,tick as f64 * 0.01,0.01).unwrap();
+        }
+        client.join().unwrap();
+        assert_eq!(server.commands(),&[0.0,0.0,0.0,0.4]);
+        assert_eq!(server.missed(),0);
+    }
+}
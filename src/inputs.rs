@@ -0,0 +1,386 @@
+//! Control input types
+//!
+//! Effectors read their inputs from the inputstate by index. [ControlInputs] adds channel names, saturation
+//! limits and a neutral value on top of indexing, so inputs can be addressed as `"rudder"` rather than by
+//! remembering which index each effector reads. Named input structs are declared with [control_inputs!](crate::control_inputs).
+//!
+//! A [ChannelRouter] can route commands to channels by name, [LinearAero::with_named_channels](crate::effector_models::LinearAero::with_named_channels)
+//! finds its control channels by name, and [AffectedBody::step_saturated](crate::AffectedBody::step_saturated)
+//! clamps the inputstate to the channel limits before stepping.
+
+use std::ops::{Index,IndexMut};
+
+use crate::Error;
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Trait for an inputstate made of indexed control channels
+pub trait ControlInputs<T: Float = DefaultFloatRepr>: Index<usize,Output=T> {
+    /// Return the number of channels
+    fn channel_count(&self) -> usize;
+
+    /// Return the name of the channel at `index`, if channels are named
+    fn channel_name(&self, _index: usize) -> Option<&'static str> {
+        None
+    }
+
+    /// Return the saturation limits `(min, max)` of the channel at `index`, if limited
+    fn limits(&self, _index: usize) -> Option<(T,T)> {
+        None
+    }
+
+    /// Return the inputstate with every channel at its neutral value
+    fn neutral() -> Self where Self: Sized;
+
+    /// Return the index of the channel called `name`
+    fn channel_index(&self, name: &str) -> Option<usize> {
+        (0..self.channel_count()).find(|&index| self.channel_name(index) == Some(name))
+    }
+
+    /// Return the value of the channel called `name`
+    fn channel(&self, name: &str) -> Option<T> {
+        self.channel_index(name).map(|index| self[index])
+    }
+
+    /// Set the channel called `name` to `value`, returning `false` if there is no such channel
+    fn set_channel(&mut self, name: &str, value: T) -> bool where Self: IndexMut<usize,Output=T> {
+        match self.channel_index(name) {
+            Some(index) => {
+                self[index] = value;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Clamp every channel to its saturation limits
+    fn saturate(&mut self) where Self: IndexMut<usize,Output=T> {
+        for index in 0..self.channel_count() {
+            if let Some((min, max)) = self.limits(index) {
+                self[index] = num_traits::clamp(self[index],min,max);
+            }
+        }
+    }
+}
+
+impl<T: Float, const N: usize> ControlInputs<T> for [T; N] {
+    fn channel_count(&self) -> usize {
+        N
+    }
+
+    fn neutral() -> Self {
+        [T::zero(); N]
+    }
+}
+
+impl<T: Float> ControlInputs<T> for Vec<T> {
+    fn channel_count(&self) -> usize {
+        self.len()
+    }
+
+    /// Return an empty inputstate, as a `Vec` has no fixed channel count
+    fn neutral() -> Self {
+        Vec::new()
+    }
+}
+
+/// Declare a struct of named control channels implementing [ControlInputs]
+///
+/// Each channel is declared with its saturation limits and neutral value, and channels are indexed in
+/// declaration order, so the struct can be passed to any effector which reads channels by index.
+///
+/// ```
+/// use aerso::control_inputs;
+/// use aerso::inputs::ControlInputs;
+///
+/// control_inputs! {
+///     /// Glider controls
+///     pub struct GliderControls: f64 {
+///         elevator: [-0.4, 0.4] = 0.0,
+///         rudder: [-0.5, 0.5] = 0.0,
+///     }
+/// }
+///
+/// let mut controls = GliderControls::neutral();
+/// controls.rudder = 1.0;
+/// controls.saturate();
+/// assert_eq!(controls[1],0.5);
+/// assert_eq!(controls.channel_index("rudder"),Some(1));
+/// ```
+#[macro_export]
+macro_rules! control_inputs {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident: $t:ty {
+            $( $(#[$field_meta:meta])* $field:ident: [$min:expr, $max:expr] = $neutral:expr ),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy,Clone)]
+        $vis struct $name {
+            $( $(#[$field_meta])* pub $field: $t, )+
+        }
+
+        impl $name {
+            /// Channel names in index order
+            pub const CHANNELS: &'static [&'static str] = &[$( stringify!($field) ),+];
+        }
+
+        impl ::std::default::Default for $name {
+            fn default() -> Self {
+                $name { $( $field: $neutral, )+ }
+            }
+        }
+
+        impl ::std::ops::Index<usize> for $name {
+            type Output = $t;
+            fn index(&self, index: usize) -> &$t {
+                let channels = [$( &self.$field ),+];
+                match channels.get(index) {
+                    Some(channel) => channel,
+                    None => panic!("Channel index {} out of range for {}",index,stringify!($name)),
+                }
+            }
+        }
+
+        impl ::std::ops::IndexMut<usize> for $name {
+            fn index_mut(&mut self, index: usize) -> &mut $t {
+                let channels = [$( &mut self.$field ),+];
+                match ::std::iter::IntoIterator::into_iter(channels).nth(index) {
+                    Some(channel) => channel,
+                    None => panic!("Channel index {} out of range for {}",index,stringify!($name)),
+                }
+            }
+        }
+
+        impl $crate::inputs::ControlInputs<$t> for $name {
+            fn channel_count(&self) -> usize {
+                Self::CHANNELS.len()
+            }
+
+            fn channel_name(&self, index: usize) -> Option<&'static str> {
+                Self::CHANNELS.get(index).copied()
+            }
+
+            fn limits(&self, index: usize) -> Option<($t,$t)> {
+                let limits: &[($t,$t)] = &[$( ($min,$max) ),+];
+                limits.get(index).copied()
+            }
+
+            fn neutral() -> Self {
+                Self::default()
+            }
+        }
+    };
+}
+
+control_inputs! {
+    /// Conventional aircraft controls
+    ///
+    /// The channel order matches the default channels of [LinearAero](crate::effector_models::LinearAero):
+    /// aileron, elevator and rudder deflections (rad) followed by throttle (0 to 1).
+    pub struct AircraftControls: DefaultFloatRepr {
+        /// Aileron deflection (rad)
+        aileron: [-0.5, 0.5] = 0.0,
+        /// Elevator deflection (rad)
+        elevator: [-0.5, 0.5] = 0.0,
+        /// Rudder deflection (rad)
+        rudder: [-0.5, 0.5] = 0.0,
+        /// Throttle, from 0 to 1
+        throttle: [0.0, 1.0] = 0.0,
+    }
+}
+
+/// A single route from a named command to an inputstate slot
+#[derive(Clone)]
+struct Route<T: Float> {
+    /// Command name
+    command: String,
+    /// Inputstate index
+    slot: usize,
+    /// Gain applied to the command
+    gain: T,
+}
+
+/// Routes named commands to the inputstate slots consumed by effectors
+///
+/// The router starts from a template inputstate, which sets the size and the value of any slot not
+/// driven by a command. Each command can drive several slots with a gain, for example a single
+/// `"aileron"` command driving left and right ailerons in opposite senses, and slots driven by
+/// several commands receive the sum, for example elevons mixing `"elevator"` and `"aileron"`.
+#[derive(Clone)]
+pub struct ChannelRouter<I, T: Float = DefaultFloatRepr> {
+    /// Inputstate used for slots without commands
+    template: I,
+    /// Routes from commands to slots
+    routes: Vec<Route<T>>,
+}
+
+impl<I: Clone + IndexMut<usize,Output=T>, T: Float> ChannelRouter<I,T> {
+    /// Create a new [ChannelRouter] with no routes, producing copies of `template`
+    pub fn new(template: I) -> Self {
+        ChannelRouter {
+            template,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Route `command` to `slot` with unit gain
+    pub fn route(self, command: &str, slot: usize) -> Self {
+        self.route_with_gain(command,slot,T::one())
+    }
+
+    /// Route `command` to `slot`, scaled by `gain`
+    pub fn route_with_gain(mut self, command: &str, slot: usize, gain: T) -> Self {
+        self.routes.push(Route { command: command.to_string(), slot, gain });
+        self
+    }
+
+    /// Return the distinct command names in the order they were first routed
+    pub fn commands(&self) -> Vec<&str> {
+        let mut commands: Vec<&str> = Vec::new();
+        for route in &self.routes {
+            if !commands.contains(&route.command.as_str()) {
+                commands.push(&route.command);
+            }
+        }
+        commands
+    }
+
+    /// Build the inputstate for the named `commands`
+    ///
+    /// Routed slots with no matching command take the value from the template.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a command has no route, as this is almost always a misspelled channel name.
+    pub fn inputs(&self, commands: &[(&str,T)]) -> I {
+        self.try_inputs(commands).unwrap_or_else(|error| panic!("{}",error))
+    }
+
+    /// Build the inputstate for the named `commands`, returning [Error::UnroutedCommand] if a command has no route
+    pub fn try_inputs(&self, commands: &[(&str,T)]) -> Result<I,Error> {
+        if let Some((name, _)) = commands.iter().find(|(name, _)| !self.routes.iter().any(|route| route.command == *name)) {
+            return Err(Error::UnroutedCommand(name.to_string()));
+        }
+        let mut inputs = self.template.clone();
+        let mut driven: Vec<usize> = Vec::new();
+        for &(name, value) in commands {
+            for route in self.routes.iter().filter(|route| route.command == name) {
+                if !driven.contains(&route.slot) {
+                    inputs[route.slot] = T::zero();
+                    driven.push(route.slot);
+                }
+                inputs[route.slot] += route.gain * value;
+            }
+        }
+        Ok(inputs)
+    }
+}
+
+impl<I: Clone + IndexMut<usize,Output=T> + ControlInputs<T>, T: Float> ChannelRouter<I,T> {
+    /// Route `command` to the template channel called `channel` with unit gain
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template has no channel called `channel`.
+    pub fn route_channel(self, command: &str, channel: &str) -> Self {
+        self.route_channel_with_gain(command,channel,T::one())
+    }
+
+    /// Route `command` to the template channel called `channel`, scaled by `gain`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template has no channel called `channel`.
+    pub fn route_channel_with_gain(self, command: &str, channel: &str, gain: T) -> Self {
+        let slot = self.template.channel_index(channel).unwrap_or_else(|| panic!("Router template has no channel called {}",channel));
+        self.route_with_gain(command,slot,gain)
+    }
+
+    /// Build the inputstate for the named `commands`, clamped to the channel saturation limits
+    ///
+    /// # Panics
+    ///
+    /// Panics if a command has no route.
+    pub fn saturated_inputs(&self, commands: &[(&str,T)]) -> I {
+        let mut inputs = self.inputs(commands);
+        inputs.saturate();
+        inputs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_named_channels() {
+        let mut controls = AircraftControls::neutral();
+        controls.elevator = -0.1;
+        assert_relative_eq!(controls[1],-0.1);
+        assert_relative_eq!(controls.channel("elevator").unwrap(),-0.1);
+        assert!(controls.channel("flap").is_none());
+
+        assert!(controls.set_channel("throttle",1.5));
+        controls.saturate();
+        assert_relative_eq!(controls.throttle,1.0);
+        assert_eq!(controls.channel_name(2),Some("rudder"));
+        assert_eq!(controls.channel_count(),4);
+    }
+
+    #[test]
+    fn test_router_mixing() {
+        // Elevons on slots 0 and 1, throttles on slots 2 and 3
+        let router = ChannelRouter::new([0.0,0.0,0.0,0.0])
+            .route("elevator",0)
+            .route("elevator",1)
+            .route_with_gain("aileron",0,1.0)
+            .route_with_gain("aileron",1,-1.0)
+            .route("throttle_left",2)
+            .route("throttle_right",3);
+        assert_eq!(router.commands(),vec!["elevator","aileron","throttle_left","throttle_right"]);
+
+        let inputs = router.inputs(&[("elevator",0.1),("aileron",0.05),("throttle_right",0.8)]);
+        assert_relative_eq!(inputs[0],0.15);
+        assert_relative_eq!(inputs[1],0.05);
+        assert_relative_eq!(inputs[2],0.0);
+        assert_relative_eq!(inputs[3],0.8);
+    }
+
+    #[test]
+    fn test_router_channel_names() {
+        // Routing by name follows the template's channel order
+        let router = ChannelRouter::new(AircraftControls::neutral())
+            .route_channel("pitch","elevator")
+            .route_channel_with_gain("yaw","rudder",-1.0)
+            .route_channel("power","throttle");
+        let inputs = router.inputs(&[("pitch",0.1),("yaw",0.2),("power",1.5)]);
+        assert_relative_eq!(inputs.elevator,0.1);
+        assert_relative_eq!(inputs.rudder,-0.2);
+        assert_relative_eq!(inputs.throttle,1.5);
+        assert_relative_eq!(router.saturated_inputs(&[("power",1.5)]).throttle,1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_router_unknown_channel() {
+        ChannelRouter::new(AircraftControls::neutral()).route_channel("pitch","elevatr");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_router_unknown_command() {
+        ChannelRouter::new(vec![0.0]).route("elevator",0).inputs(&[("elevatr",0.1)]);
+    }
+
+    #[test]
+    fn test_array_inputs() {
+        let mut inputs = <[f64;3]>::neutral();
+        inputs[2] = 5.0;
+        inputs.saturate();
+        assert_relative_eq!(inputs[2],5.0);
+        assert_eq!(inputs.channel_count(),3);
+        assert!(inputs.channel_index("rudder").is_none());
+    }
+}
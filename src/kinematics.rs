@@ -1,6 +1,8 @@
 extern crate nalgebra as na;
 
-use crate::types::{Vector3,Matrix3,UnitQuaternion,Frame,StateVector,StateView,Force,Torque};
+use crate::types::{Vector3,Matrix3,UnitQuaternion,Frame,StateVector,StateView,Isometry3,Force,Torque};
+use crate::types::{with_pose,constant};
+use crate::Error;
 use crate::types::{Float,DefaultFloatRepr};
 
 // Integrating Rotations using Non-Unit Quaternions
@@ -19,10 +21,59 @@ pub struct Body<T: Float = DefaultFloatRepr> {
     /// 
     /// Statevector is formed of \[position,velocity(body),attitude_quaternion(i,j,k,w),axis_rates(body)\]
     statevector: StateVector<T>,
+    /// World-to-body DCM of the statevector attitude, updated whenever the statevector changes
+    dcm: Matrix3<T>,
     /// Body frame acceleration of vehicle during last step
     acceleration: Vector3<T>,
+    /// Body frame angular acceleration of vehicle during last step
+    angular_acceleration: Vector3<T>,
+    /// Apparent mass of the surrounding fluid, if any
+    added_mass: Option<AddedMass<T>>,
 }
 
+/// Apparent mass and inertia of the fluid displaced by a [Body]
+#[derive(Copy,Clone)]
+struct AddedMass<T: Float> {
+    /// Added mass matrix, body frame (kg)
+    mass: Matrix3<T>,
+    /// Added inertia matrix, body frame (kg·m<sup>2</sup>)
+    inertia: Matrix3<T>,
+    /// Inverse of the total mass matrix, body and added
+    mass_inverse: Matrix3<T>,
+    /// Inverse of the total inertia matrix, body and added
+    inertia_inverse: Matrix3<T>,
+}
+
+
+/// Reason a [Body] could not be constructed by [Body::try_new] or [Body::try_new_from_statevector]
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum BodyError {
+    /// Mass is zero, negative or NaN
+    NonPositiveMass,
+    /// Inertia matrix is not symmetric
+    NonSymmetricInertia,
+    /// Inertia matrix is not positive definite
+    NonPositiveDefiniteInertia,
+    /// Attitude quaternion is not of unit length
+    NonUnitQuaternion,
+    /// Statevector element at the index is NaN or infinite
+    NonFiniteState(usize),
+}
+
+impl std::fmt::Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyError::NonPositiveMass => write!(f,"mass must be positive"),
+            BodyError::NonSymmetricInertia => write!(f,"inertia matrix must be symmetric"),
+            BodyError::NonPositiveDefiniteInertia => write!(f,"inertia matrix must be positive definite"),
+            BodyError::NonUnitQuaternion => write!(f,"attitude quaternion must be of unit length"),
+            BodyError::NonFiniteState(index) => write!(f,"statevector element {} is not finite",index),
+        }
+    }
+}
+
+impl std::error::Error for BodyError {}
+
 
 impl<T: Float> Body<T> {
     /// Create a new instance of Body with `mass` and `inertia` at the origin
@@ -58,14 +109,102 @@ impl<T: Float> Body<T> {
             inertia,
             inertia_inverse,
             statevector,
+            dcm: Body::get_dcm(&statevector),
             acceleration: Vector3::<T>::new(T::zero(),T::zero(),T::zero()),
+            angular_acceleration: Vector3::zeros(),
+            added_mass: None,
+        }
+    }
+
+    /// Add the apparent mass of the surrounding fluid, body frame
+    ///
+    /// A body accelerating through a fluid must also accelerate some of the fluid around it. This is
+    /// negligible for aircraft but comparable to the body mass for airships and balloons, where it is
+    /// modelled by the added `mass` (kg) and `inertia` (kg·m<sup>2</sup>) matrices, diagonal in the body axes
+    /// for a body with three planes of symmetry. The equations of motion become
+    ///
+    /// `(m I + M_A) v̇ = F - ω × ((m I + M_A) v)`
+    ///
+    /// `(I + I_A) ω̇ = M - ((I + I_A) ω) × ω - v × (M_A v)`
+    ///
+    /// where the last term is the Munk moment, which turns an elongated body broadside to the flow.
+    /// Gravity acts on the body mass alone, so buoyancy should be added as an effector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total mass or inertia matrix is not invertible.
+    pub fn with_added_mass(mut self, mass: Matrix3<T>, inertia: Matrix3<T>) -> Self {
+        let zero = Matrix3::zeros();
+        self.added_mass = Some(AddedMass { mass, inertia, mass_inverse: zero, inertia_inverse: zero });
+        self.update_added_mass();
+        self
+    }
+
+    /// Return the added `(mass, inertia)` matrices, body frame (kg, kg·m<sup>2</sup>), if any
+    pub fn added_mass(&self) -> Option<(Matrix3<T>,Matrix3<T>)> {
+        self.added_mass.map(|added| (added.mass,added.inertia))
+    }
+
+    /// Recalculate the inverse total mass and inertia matrices after a change of mass
+    fn update_added_mass(&mut self) {
+        if let Some(added) = self.added_mass.as_mut() {
+            added.mass_inverse = match (Matrix3::identity() * self.mass + added.mass).try_inverse() {
+                Some(inverted) => inverted,
+                None => { panic!("Unable to invert total mass matrix") }
+            };
+            added.inertia_inverse = match (self.inertia + added.inertia).try_inverse() {
+                Some(inverted) => inverted,
+                None => { panic!("Unable to invert total inertia matrix") }
+            };
+        }
+    }
+    
+    /// Create a new instance of Body with `mass` and `inertia` in specified state, validating the configuration
+    /// 
+    /// Unlike [Body::new], the mass, inertia and state are checked, returning a [BodyError] rather than
+    /// constructing a body which would panic or silently produce NaN when stepped.
+    pub fn try_new(mass: T, inertia: Matrix3<T>, position: Vector3<T>, velocity: Vector3<T>, attitude: UnitQuaternion<T>, rates: Vector3<T>) -> Result<Self,BodyError> {
+        let mut statevector = StateVector::zeros();
+        statevector.fixed_rows_mut::<3>(0).copy_from(&position);
+        statevector.fixed_rows_mut::<3>(3).copy_from(&velocity);
+        statevector.fixed_rows_mut::<4>(6).copy_from(&attitude.coords);
+        statevector.fixed_rows_mut::<3>(10).copy_from(&rates);
+        Body::try_new_from_statevector(mass,inertia,statevector)
+    }
+    
+    /// Create a new instance of Body with `mass` and `inertia` in specified state, validating the configuration
+    /// 
+    /// statevector is made of \[position,velocity(body),attitude_quaternion(i,j,k,w),axis_rates(body)\]
+    /// 
+    /// The mass must be positive, the inertia symmetric and positive definite, the attitude quaternion of
+    /// unit length and every element of the statevector finite.
+    pub fn try_new_from_statevector(mass: T, inertia: Matrix3<T>, statevector: StateVector<T>) -> Result<Self,BodyError> {
+        if mass <= T::zero() || !<T as num_traits::Float>::is_finite(mass) {
+            return Err(BodyError::NonPositiveMass);
+        }
+        if let Some(index) = statevector.iter().position(|value| !<T as num_traits::Float>::is_finite(*value)) {
+            return Err(BodyError::NonFiniteState(index));
+        }
+        let tolerance = <T as num_traits::Float>::sqrt(<T as num_traits::Float>::epsilon());
+        if (inertia - inertia.transpose()).norm() > tolerance * inertia.norm() {
+            return Err(BodyError::NonSymmetricInertia);
         }
+        if !inertia.iter().all(|value| <T as num_traits::Float>::is_finite(*value)) || inertia.cholesky().is_none() {
+            return Err(BodyError::NonPositiveDefiniteInertia);
+        }
+        if <T as num_traits::Float>::abs(statevector.fixed_rows::<4>(6).norm() - T::one()) > tolerance {
+            return Err(BodyError::NonUnitQuaternion);
+        }
+        Ok(Body::new_from_statevector(mass,inertia,statevector))
     }
     
     /// Construct the Direction Cosine Matrix (DCM) from the state attitude
     /// 
     /// Transforms quantites from the world frame to the body frame
     /// 
+    /// This is a stable part of the public API. See [attitude](crate::attitude) for the DCM of a quaternion or
+    /// Euler angles without a statevector.
+    /// 
     /// Note that this is not a struct method. Usage:
     /// ```
     /// # use aerso::Body;
@@ -85,7 +224,7 @@ impl<T: Float> Body<T> {
         let q2 = q[1]; let q22 = <T as num_traits::Float>::powi(q2,2); // j
         let q3 = q[2]; let q32 = <T as num_traits::Float>::powi(q3,2); // k
         
-        let two: T = T::from_f64(2.0).unwrap();
+        let two: T = constant::<T>(2.0);
         
         // NB: This appears as the transpose of Eq. (13) from the referenced paper
         // This matches the convention of the Stengel notes
@@ -124,10 +263,15 @@ impl<T: Float> Body<T> {
     /// * `forces` - Vector of applied forces, both world and body frame
     /// * `torques` - Vector of applied torques, both world and body frame
     fn get_derivative(&self, state: &StateVector<T>, forces: &[Force<T>], torques: &[Torque<T>]) -> StateVector<T> {
+        self.get_derivative_with_dcm(state,&Body::get_dcm(state),forces,torques)
+    }
+    
+    /// Calculate the derivative of the state with the world-to-body `dcm` of its attitude
+    fn get_derivative_with_dcm(&self, state: &StateVector<T>, dcm: &Matrix3<T>, forces: &[Force<T>], torques: &[Torque<T>]) -> StateVector<T> {
         let gravity_accel: Vector3<T> = Vector3::new(
             T::zero(),
             T::zero(),
-            T::from(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY).unwrap()
+            constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY)
             );
         let mut world_forces = gravity_accel * self.mass;
         let mut body_forces = Vector3::zeros();
@@ -147,11 +291,17 @@ impl<T: Float> Body<T> {
             }
         }
         
-        let dcm = Body::get_dcm(state);
+        let dcm = *dcm;
         let dcm_body = dcm.transpose();
 
         let position_dot = dcm_body * state.velocity();
-        let velocity_dot = state.velocity().cross(&state.rates()) + ( (dcm * world_forces) + body_forces ) * <T as num_traits::Float>::recip(self.mass);
+        let velocity_dot = match &self.added_mass {
+            Some(added) => {
+                let momentum = state.velocity() * self.mass + added.mass * state.velocity();
+                added.mass_inverse * ( (dcm * world_forces) + body_forces - state.rates().cross(&momentum) )
+            },
+            None => state.velocity().cross(&state.rates()) + ( (dcm * world_forces) + body_forces ) * <T as num_traits::Float>::recip(self.mass),
+        };
         
         let o_x = state.rates()[0];
         let o_y = state.rates()[1];
@@ -169,7 +319,7 @@ impl<T: Float> Body<T> {
         // NB: Quaternion does not remain normalised throughout integration
         let q = state.fixed_rows::<4>(6); // Don't use attitude here to avoid uneccesary square root
         #[cfg(not(feature="constrain-qnorm-drift"))]
-        let attitude_dot = qdot_matrix * q * T::from(0.5).unwrap();
+        let attitude_dot = qdot_matrix * q * constant::<T>(0.5);
         #[cfg(feature="constrain-qnorm-drift")]
         let attitude_dot = {
             // Use Eq. (23) from the paper to set the free parameter to constrain the drift of the quaternion norm
@@ -177,12 +327,18 @@ impl<T: Float> Body<T> {
                         <T as num_traits::Float>::powi(q[1],2) +
                         <T as num_traits::Float>::powi(q[2],2) +
                         <T as num_traits::Float>::powi(q[3],2);
-            let k = T::from_f64(0.001).unwrap();
+            let k = constant::<T>(0.001);
             let c = k * (T::one() - qnorm);
-            qdot_matrix * q * T::from(0.5).unwrap() + q * c
+            qdot_matrix * q * constant::<T>(0.5) + q * c
             };
         
-            let rates_dot = self.inertia_inverse * (dcm * world_torques + body_torques - (self.inertia * state.rates()).cross(&state.rates()) );
+        let rates_dot = match &self.added_mass {
+            Some(added) => {
+                let munk = state.velocity().cross(&(added.mass * state.velocity()));
+                added.inertia_inverse * (dcm * world_torques + body_torques - ((self.inertia + added.inertia) * state.rates()).cross(&state.rates()) - munk)
+            },
+            None => self.inertia_inverse * (dcm * world_torques + body_torques - (self.inertia * state.rates()).cross(&state.rates()) ),
+        };
         
         StateVector::from_vec(vec![
             position_dot[0], position_dot[1], position_dot[2],
@@ -193,6 +349,17 @@ impl<T: Float> Body<T> {
         
     }
     
+    /// Calculate the derivative of the current statevector under the supplied forces and torques
+    /// 
+    /// NB: Gravity is included by default
+    /// 
+    /// # Arguments
+    /// * `forces` - Vector of applied forces, both world and body frame
+    /// * `torques` - Vector of applied torques, both world and body frame
+    pub fn derivative(&self, forces: &[Force<T>], torques: &[Torque<T>]) -> StateVector<T> {
+        self.get_derivative_with_dcm(&self.statevector,&self.dcm,forces,torques)
+    }
+    
     /// Propagate the state vector by delta_t under the supplied forces and torques
     ///
     /// Uses 4th-order Runge-Kutta integration
@@ -205,14 +372,24 @@ impl<T: Float> Body<T> {
     /// * `torques` - Vector of applied torques, both world and body frame
     /// * `delta_t` - Timestep (s)
     pub fn step(&mut self, forces: &[Force<T>], torques: &[Torque<T>], delta_t: T) {
-        let k1 = self.get_derivative( &self.statevector,                                           forces, torques);
-        let k2 = self.get_derivative(&(self.statevector + k1 * delta_t/T::from_f64(2.0).unwrap()), forces, torques);
-        let k3 = self.get_derivative(&(self.statevector + k2 * delta_t/T::from_f64(2.0).unwrap()), forces, torques);
+        let k1 = self.get_derivative_with_dcm(&self.statevector, &self.dcm,                         forces, torques);
+        let k2 = self.get_derivative(&(self.statevector + k1 * delta_t/constant::<T>(2.0)), forces, torques);
+        let k3 = self.get_derivative(&(self.statevector + k2 * delta_t/constant::<T>(2.0)), forces, torques);
         let k4 = self.get_derivative(&(self.statevector + k3 * delta_t),                           forces, torques);
         
         // NB: k1 is a derivative so velocity -> velocity_dot -> acceleration
         self.acceleration = k1.velocity();
-        self.statevector += (k1 + k2*T::from_f64(2.0).unwrap() + k3*T::from_f64(2.0).unwrap() + k4) * delta_t/T::from_f64(6.0).unwrap();
+        self.angular_acceleration = k1.rates();
+        self.statevector += (k1 + k2*constant::<T>(2.0) + k3*constant::<T>(2.0) + k4) * delta_t/constant::<T>(6.0);
+        self.dcm = Body::get_dcm(&self.statevector);
+    }
+    
+    /// Get the world-to-body DCM of the current attitude
+    /// 
+    /// This is [Body::get_dcm] of the current statevector, cached when the statevector changes so that
+    /// repeated queries within a step do not recompute it.
+    pub fn dcm(&self) -> Matrix3<T> {
+        self.dcm
     }
     
     /// Get body-frame acceleration at the start of the previous timestep
@@ -231,16 +408,56 @@ impl<T: Float> Body<T> {
         self.acceleration
     }
     
+    /// Get body-frame angular acceleration at the start of the previous timestep
+    pub fn angular_acceleration(&self) -> Vector3<T> {
+        self.angular_acceleration
+    }
+    
     /// Set the body statevector
     /// 
-    /// Will also reset the body acceleration to zero
+    /// Will also reset the body accelerations to zero
     /// 
     /// The statevector is in the order: \[position,velocity(body),attitude_quaternion(i,j,k,w),axis_rates(body)\]
     pub fn set_state(&mut self, new_state: StateVector<T>) {
         self.statevector = new_state;
+        self.dcm = Body::get_dcm(&new_state);
         self.acceleration = Vector3::zeros();
+        self.angular_acceleration = Vector3::zeros();
+    }
+    
+    /// Set the position and attitude from `pose`, the body-to-world isometry
+    /// 
+    /// The body-frame velocity and axis rates are kept. Will also reset the body accelerations to zero
+    pub fn set_pose(&mut self, pose: &Isometry3<T>) {
+        self.set_state(with_pose(self.statevector,pose));
+    }
+
+    /// Get the body mass (kg)
+    pub fn mass(&self) -> T {
+        self.mass
+    }
+
+    /// Set the body mass (kg), for example to account for fuel burn
+    ///
+    /// The inertia matrix is unchanged.
+    pub fn set_mass(&mut self, mass: T) {
+        if mass <= T::zero() {
+            panic!("Mass must be >= 0.0")
+        }
+        self.mass = mass;
+        self.update_added_mass();
     }
     
+    /// Set the body mass (kg), returning [Error::InvalidParameter] without changing the mass if it is not positive and finite
+    pub fn try_set_mass(&mut self, mass: T) -> Result<(),Error> {
+        if mass <= T::zero() || !<T as num_traits::Float>::is_finite(mass) {
+            return Err(Error::InvalidParameter("mass"));
+        }
+        self.mass = mass;
+        self.update_added_mass();
+        Ok(())
+    }
+
 }
 
 /// Add state vector helpers to the Body struct
@@ -285,6 +502,32 @@ mod test {
         Body::new_at_origin(1.0,inertia);
     }
 
+    #[test]
+    fn test_try_new() {
+        let inertia = Matrix3::from_diagonal(&Vector3::new(1.0,2.0,3.0));
+        let attitude = UnitQuaternion::from_euler_angles(0.1,0.2,0.3);
+        let body = Body::try_new(2.0,inertia,Vector3::zeros(),Vector3::new(10.0,0.0,0.0),attitude,Vector3::zeros()).unwrap();
+        assert_eq!(body.statevector()[3],10.0);
+        assert_eq!(Body::try_new(0.0,inertia,Vector3::zeros(),Vector3::zeros(),attitude,Vector3::zeros()).err(),Some(BodyError::NonPositiveMass));
+        assert_eq!(Body::try_new(f64::NAN,inertia,Vector3::zeros(),Vector3::zeros(),attitude,Vector3::zeros()).err(),Some(BodyError::NonPositiveMass));
+        assert_eq!(Body::try_new(1.0,inertia,Vector3::new(0.0,f64::NAN,0.0),Vector3::zeros(),attitude,Vector3::zeros()).err(),Some(BodyError::NonFiniteState(1)));
+    }
+    
+    #[test]
+    fn test_try_new_inertia() {
+        let mut state = StateVector::zeros();
+        state[9] = 1.0;
+        let mut skewed = Matrix3::identity();
+        skewed[(0,1)] = 0.5;
+        assert_eq!(Body::try_new_from_statevector(1.0,skewed,state).err(),Some(BodyError::NonSymmetricInertia));
+        let indefinite = Matrix3::from_diagonal(&Vector3::new(1.0,-1.0,1.0));
+        assert_eq!(Body::try_new_from_statevector(1.0,indefinite,state).err(),Some(BodyError::NonPositiveDefiniteInertia));
+        state[9] = 2.0;
+        let error = Body::try_new_from_statevector(1.0,Matrix3::identity(),state).err().unwrap();
+        assert_eq!(error,BodyError::NonUnitQuaternion);
+        assert_eq!(error.to_string(),"attitude quaternion must be of unit length");
+    }
+    
     #[test]
     #[should_panic]
     fn test_zero_mass() {
@@ -299,6 +542,36 @@ mod test {
         Body::new_at_origin(-1.0,inertia);
     }
 
+    #[test]
+    fn test_pose() {
+        let attitude = UnitQuaternion::from_euler_angles(0.1,0.2,0.3);
+        let mut body = Body::new(1.0,Matrix3::identity(),Vector3::new(1.0,2.0,3.0),Vector3::new(10.0,0.0,1.0),attitude,Vector3::new(0.0,0.1,0.0));
+        let pose = body.pose();
+        assert_eq!(pose.translation.vector,Vector3::new(1.0,2.0,3.0));
+        assert!(pose.rotation.angle_to(&attitude) < 1e-12);
+        // The pose maps body-frame points to the world
+        assert!(((pose * nalgebra::Point3::new(1.0,0.0,0.0)).coords - (Vector3::new(1.0,2.0,3.0) + attitude * Vector3::x())).norm() < 1e-12);
+        
+        let new_pose = Isometry3::from_parts(Vector3::new(-5.0,0.0,-100.0).into(),UnitQuaternion::from_euler_angles(0.0,0.0,1.0));
+        body.set_pose(&new_pose);
+        assert_eq!(body.position(),Vector3::new(-5.0,0.0,-100.0));
+        assert!(body.attitude().angle_to(&new_pose.rotation) < 1e-12);
+        assert_eq!(body.velocity(),Vector3::new(10.0,0.0,1.0));
+        assert_eq!(body.rates(),Vector3::new(0.0,0.1,0.0));
+    }
+    
+    #[test]
+    fn test_dcm_cache() {
+        let attitude = UnitQuaternion::from_euler_angles(0.1,0.2,0.3);
+        let mut body = Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(10.0,0.0,0.0),attitude,Vector3::new(0.3,-0.2,0.1));
+        assert_eq!(body.dcm(),Body::get_dcm(&body.statevector()));
+        // The cache follows the attitude as it is integrated and set
+        body.step(&[],&[],0.1);
+        assert_eq!(body.dcm(),Body::get_dcm(&body.statevector()));
+        body.set_pose(&Isometry3::identity());
+        assert_eq!(body.dcm(),Matrix3::identity());
+    }
+    
     #[test]
     fn test_get_dcm() {
         let state = StateVector::from_vec(vec![
@@ -393,4 +666,40 @@ mod test {
 
     }
 
+    #[test]
+    fn test_added_mass() {
+        // Prolate airship, with more added mass broadside than along the hull
+        let added_mass = Matrix3::from_diagonal(&Vector3::new(50.0,400.0,400.0));
+        let added_inertia = Matrix3::from_diagonal(&Vector3::new(0.0,3000.0,3000.0));
+        let inertia = Matrix3::from_diagonal(&Vector3::new(500.0,2000.0,2000.0));
+        let airship = Body::new_at_origin(500.0,inertia).with_added_mass(added_mass,added_inertia);
+        assert_eq!(airship.added_mass(),Some((added_mass,added_inertia)));
+
+        // A body force accelerates the body and the fluid it displaces
+        let thrust = [Force::body_vec(Vector3::new(110.0,0.0,0.0))];
+        let derivative = airship.derivative(&thrust,&[]);
+        assert_relative_eq!(derivative[3],0.2,max_relative=1e-12);
+        // Gravity acts on the body mass alone
+        assert_relative_eq!(derivative[5],500.0 * physical_constants::STANDARD_ACCELERATION_OF_GRAVITY / 900.0,max_relative=1e-12);
+
+        let mut light = airship;
+        light.set_mass(400.0);
+        assert_relative_eq!(light.derivative(&thrust,&[])[3],110.0 / 450.0,max_relative=1e-12);
+        assert!(Body::new_at_origin(500.0,inertia).added_mass().is_none());
+    }
+
+    #[test]
+    fn test_munk_moment() {
+        // Flying nose-up into the flow, the Munk moment pitches the hull further nose-up
+        let added_mass = Matrix3::from_diagonal(&Vector3::new(50.0,400.0,400.0));
+        let inertia = Matrix3::from_diagonal(&Vector3::new(500.0,2000.0,2000.0));
+        let (u, w) = (10.0,1.0);
+        let airship = Body::new(500.0,inertia,Vector3::zeros(),Vector3::new(u,0.0,w),UnitQuaternion::identity(),Vector3::zeros())
+            .with_added_mass(added_mass,Matrix3::zeros());
+        let derivative = airship.derivative(&[],&[]);
+        assert_relative_eq!(derivative[11],u * w * (400.0 - 50.0) / 2000.0,max_relative=1e-12);
+        assert_relative_eq!(derivative[10],0.0);
+        assert_relative_eq!(derivative[12],0.0);
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,470 @@
+//! Launch rails and catapults
+//!
+//! A [LaunchRail] constrains a vehicle to slide along a straight rail, at a fixed attitude and without
+//! rotation, until it reaches the end of the rail and is released into free flight. Along the rail the
+//! vehicle is driven by the component of its effector forces and weight along the rail, such as the
+//! thrust of a [RocketMotor](crate::effector_models::RocketMotor), plus an optional catapult
+//! [RailProfile].
+//!
+//! A [WinchLaunch] instead pulls the vehicle along a [Tether] to a winch or bungee anchor, with a
+//! [TensionProfile] against time or tether length, until a [Release] condition drops the tether. It is
+//! a [ContactModel] added with [AffectedBody::add_contact], so the vehicle flies freely while towed, as
+//! a glider on a winch launch. The release is reported through a [WinchMonitor].
+//!
+//! ```
+//! use aerso::{Body,AeroBody,AffectedBody};
+//! use aerso::launch::{LaunchRail,RailProfile};
+//! use aerso::types::{Vector3,Matrix3,UnitQuaternion,StateView};
+//!
+//! // Catapult launch at 15° elevation, 12 g over a 4 m rail
+//! let attitude = UnitQuaternion::from_euler_angles(0.0,15.0_f64.to_radians(),0.0);
+//! let body = Body::new(10.0,Matrix3::identity(),Vector3::zeros(),Vector3::zeros(),attitude,Vector3::zeros());
+//! let mut vehicle = AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::new(body),vec![]);
+//! let mut rail = LaunchRail::new(Vector3::zeros(),attitude,4.0).with_profile(RailProfile::Acceleration(vec![(0.0,120.0)]));
+//! while !rail.is_exited() {
+//!     rail.step(&mut vehicle,0.001,&vec![]);
+//! }
+//! assert!((rail.exit_speed().unwrap() - (2.0 * 120.0 * 4.0_f64).sqrt()).abs() < 1e-9);
+//! ```
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::{AffectedBody,WindModel,DensityModel,Error};
+use crate::contact::ContactModel;
+use crate::snapshot::ContactState;
+use crate::lookup::Table;
+use crate::tether::{Tether,TetherGeometry};
+use crate::types::{Vector3,UnitQuaternion,StateVector,Force,Torque,Float,DefaultFloatRepr,constant};
+
+/// Catapult drive along a [LaunchRail], as a function of time since launch
+///
+/// Profiles are `(time, value)` points interpolated linearly and held beyond the first and last points,
+/// so a catapult stroke should end with a zero point.
+#[derive(Clone,Debug,PartialEq)]
+pub enum RailProfile<T: Float = DefaultFloatRepr> {
+    /// No catapult, the vehicle is driven only by its effectors and weight
+    None,
+    /// Catapult force along the rail (N), added to the effectors and weight
+    Force(Vec<(T,T)>),
+    /// Prescribed acceleration along the rail (m·s<sup>-2</sup>), replacing the effectors and weight
+    Acceleration(Vec<(T,T)>),
+}
+
+/// Straight launch rail holding a vehicle until rail exit
+pub struct LaunchRail<T: Float = DefaultFloatRepr> {
+    /// World position of the centre of mass at the start of the rail (m)
+    origin: Vector3<T>,
+    /// Attitude of the vehicle on the rail, with the rail along body X
+    attitude: UnitQuaternion<T>,
+    /// Rail length (m)
+    length: T,
+    /// Catapult drive
+    profile: RailProfile<T>,
+    /// Catapult drive as a table of time since launch, if any
+    table: Option<Table<T>>,
+    /// Distance travelled along the rail (m)
+    travel: T,
+    /// Speed along the rail (m·s<sup>-1</sup>)
+    speed: T,
+    /// Time since launch (s)
+    time: T,
+    /// Speed at rail exit (m·s<sup>-1</sup>), once exited
+    exit_speed: Option<T>,
+}
+
+impl<T: Float> LaunchRail<T> {
+    /// Create a new [LaunchRail] of `length` (m) starting at the world `origin` (m), without a catapult
+    ///
+    /// The rail lies along body X of the vehicle held at `attitude`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` is not positive.
+    pub fn new(origin: Vector3<T>, attitude: UnitQuaternion<T>, length: T) -> Self {
+        if length <= T::zero() {
+            panic!("Rail length must be positive");
+        }
+        LaunchRail {
+            origin,
+            attitude,
+            length,
+            profile: RailProfile::None,
+            table: None,
+            travel: T::zero(),
+            speed: T::zero(),
+            time: T::zero(),
+            exit_speed: None,
+        }
+    }
+
+    /// Drive the vehicle along the rail with a catapult `profile`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the profile times are not strictly increasing.
+    pub fn with_profile(mut self, profile: RailProfile<T>) -> Self {
+        self.table = match &profile {
+            RailProfile::None => None,
+            RailProfile::Force(points) | RailProfile::Acceleration(points) => {
+                let (times, values) = points.iter().cloned().unzip();
+                Some(Table::new(vec![times],values))
+            },
+        };
+        self.profile = profile;
+        self
+    }
+
+    /// Return the world-frame direction of the rail
+    pub fn direction(&self) -> Vector3<T> {
+        self.attitude * Vector3::x()
+    }
+
+    /// Return the distance travelled along the rail (m)
+    pub fn travel(&self) -> T {
+        self.travel
+    }
+
+    /// Return the speed along the rail (m·s<sup>-1</sup>)
+    pub fn speed(&self) -> T {
+        self.speed
+    }
+
+    /// Return `true` once the vehicle has left the rail
+    pub fn is_exited(&self) -> bool {
+        self.exit_speed.is_some()
+    }
+
+    /// Return the speed at rail exit (m·s<sup>-1</sup>), once exited
+    pub fn exit_speed(&self) -> Option<T> {
+        self.exit_speed
+    }
+
+    /// Return the acceleration along the rail (m·s<sup>-2</sup>) of `vehicle` with `inputstate`
+    ///
+    /// The vehicle is held at rest at the start of the rail until the drive overcomes its weight.
+    pub fn acceleration<I, W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &AffectedBody<I,T,W,D>, inputstate: &I) -> T {
+        let drive = self.table.as_ref().map_or(T::zero(),|table| table.lookup(&[self.time]));
+        let acceleration = match self.profile {
+            RailProfile::Acceleration(_) => drive,
+            _ => {
+                let mass = vehicle.body.body.mass();
+                let (force, _) = vehicle.total_effect(vehicle.get_airstate(),Vector3::zeros(),inputstate);
+                let weight = Vector3::new(T::zero(),T::zero(),mass * constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY));
+                ((self.attitude * force + weight).dot(&self.direction()) + drive) / mass
+            },
+        };
+        if self.travel <= T::zero() && self.speed <= T::zero() {
+            <T as num_traits::Float>::max(acceleration,T::zero())
+        } else {
+            acceleration
+        }
+    }
+
+    /// Propagate `vehicle` by `delta_t` with `inputstate`, holding it on the rail until rail exit
+    ///
+    /// The vehicle is stepped with [AffectedBody::step], so its effectors, wind and clock advance as in
+    /// free flight, and is then returned to the rail with the travel and speed integrated at the
+    /// acceleration from the start of the step. At rail exit the vehicle is placed at the end of the
+    /// rail with the exit speed, and once exited it is stepped freely.
+    pub fn step<I, W: WindModel<T>, D: DensityModel<T>>(&mut self, vehicle: &mut AffectedBody<I,T,W,D>, delta_t: T, inputstate: &I) {
+        if self.is_exited() {
+            vehicle.step(delta_t,inputstate);
+            return;
+        }
+        let acceleration = self.acceleration(vehicle,inputstate);
+        vehicle.step(delta_t,inputstate);
+
+        let half = constant::<T>(0.5);
+        let travel = self.travel + self.speed * delta_t + half * acceleration * delta_t * delta_t;
+        if travel >= self.length {
+            let speed_sqd = self.speed * self.speed + constant::<T>(2.0) * acceleration * (self.length - self.travel);
+            self.travel = self.length;
+            self.speed = <T as num_traits::Float>::sqrt(<T as num_traits::Float>::max(speed_sqd,T::zero()));
+            self.exit_speed = Some(self.speed);
+        } else {
+            self.travel = <T as num_traits::Float>::max(travel,T::zero());
+            self.speed = if self.travel > T::zero() { self.speed + acceleration * delta_t } else { T::zero() };
+        }
+        self.time += delta_t;
+
+        let mut state = StateVector::zeros();
+        state.fixed_rows_mut::<3>(0).copy_from(&(self.origin + self.direction() * self.travel));
+        state.fixed_rows_mut::<3>(3).copy_from(&(Vector3::x() * self.speed));
+        state.fixed_rows_mut::<4>(6).copy_from(&self.attitude.coords);
+        vehicle.set_state(state);
+    }
+}
+
+/// Tension schedule of a [WinchLaunch]
+///
+/// Profiles are `(argument, tension)` points interpolated linearly and held beyond the first and last
+/// points.
+#[derive(Clone,Debug,PartialEq)]
+pub enum TensionProfile<T: Float = DefaultFloatRepr> {
+    /// Tension (N) against time since launch (s), as for a winch
+    Time(Vec<(T,T)>),
+    /// Tension (N) against tether length (m), as for a bungee stretched beyond its rest length
+    Payout(Vec<(T,T)>),
+}
+
+/// Condition dropping the tether of a [WinchLaunch]
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Release<T: Float = DefaultFloatRepr> {
+    /// Release once the tether depression angle at the hook exceeds the angle (rad), as for a back-release hook
+    Depression(T),
+    /// Release at the time since launch (s)
+    Time(T),
+    /// Release when the tether goes slack after first taking up tension
+    Slack,
+}
+
+/// State of a [WinchLaunch]
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct WinchStatus<T: Float = DefaultFloatRepr> {
+    /// Time since launch (s)
+    pub time: T,
+    /// Tether tension at the start of the last step (N)
+    pub tension: T,
+    /// Tether length at the start of the last step (m)
+    pub length: T,
+    /// Time since launch of the release (s), once released
+    pub released: Option<T>,
+}
+
+/// Built-in [ContactModel] towing a vehicle on a [Tether] for a winch or bungee launch
+pub struct WinchLaunch<T: Float = DefaultFloatRepr> {
+    /// Tether to the winch or bungee anchor
+    tether: Tether<T>,
+    /// Tension schedule
+    table: Table<T>,
+    /// Whether the tension is scheduled against tether length rather than time
+    by_payout: bool,
+    /// Release conditions
+    releases: Vec<Release<T>>,
+    /// Whether the tether has taken up tension
+    tensioned: bool,
+    /// Status shared with each [WinchMonitor]
+    status: Rc<Cell<WinchStatus<T>>>,
+}
+
+impl<T: Float> WinchLaunch<T> {
+    /// Create a new [WinchLaunch] pulling on `tether` with the tension `profile`, released only manually
+    ///
+    /// # Panics
+    ///
+    /// Panics if the profile arguments are not strictly increasing.
+    pub fn new(tether: Tether<T>, profile: TensionProfile<T>) -> Self {
+        let (by_payout, points) = match profile {
+            TensionProfile::Time(points) => (false,points),
+            TensionProfile::Payout(points) => (true,points),
+        };
+        let (arguments, tensions) = points.into_iter().unzip();
+        WinchLaunch {
+            tether,
+            table: Table::new(vec![arguments],tensions),
+            by_payout,
+            releases: Vec::new(),
+            tensioned: false,
+            status: Rc::new(Cell::new(WinchStatus { time: T::zero(), tension: T::zero(), length: T::zero(), released: None })),
+        }
+    }
+
+    /// Release the tether when `release` is met, in addition to any other conditions
+    pub fn with_release(mut self, release: Release<T>) -> Self {
+        self.releases.push(release);
+        self
+    }
+
+    /// Return a [WinchMonitor] sharing the status of the launch
+    pub fn monitor(&self) -> WinchMonitor<T> {
+        WinchMonitor { status: self.status.clone() }
+    }
+
+    /// Return the tether tension (N) for `geometry`, or zero once released
+    pub fn tension(&self, geometry: &TetherGeometry<T>) -> T {
+        let status = self.status.get();
+        if status.released.is_some() {
+            return T::zero();
+        }
+        let argument = if self.by_payout { geometry.length } else { status.time };
+        <T as num_traits::Float>::max(self.table.lookup(&[argument]),T::zero())
+    }
+}
+
+impl<T: Float> ContactModel<T> for WinchLaunch<T> {
+    fn get_contact(&self, state: &StateVector<T>) -> (Force<T>,Torque<T>) {
+        self.tether.effect(state,self.tension(&self.tether.geometry(state)))
+    }
+
+    fn step(&mut self, state: &StateVector<T>, delta_t: T) {
+        let mut status = self.status.get();
+        if status.released.is_some() {
+            return;
+        }
+        let geometry = self.tether.geometry(state);
+        let tension = self.tension(&geometry);
+        let slack = self.tensioned && tension <= T::zero();
+        self.tensioned |= tension > T::zero();
+        let released = self.releases.iter().any(|release| match *release {
+            Release::Depression(angle) => geometry.depression > angle,
+            Release::Time(time) => status.time >= time,
+            Release::Slack => slack,
+        });
+        status.tension = tension;
+        status.length = geometry.length;
+        status.time += delta_t;
+        if released {
+            status.released = Some(status.time);
+        }
+        self.status.set(status);
+    }
+
+    fn snapshot(&self) -> ContactState {
+        let status = self.status.get();
+        ContactState {
+            words: vec![self.tensioned as u64,status.released.is_some() as u64],
+            ..ContactState::from_values(&[status.time,status.tension,status.length,status.released.unwrap_or_else(T::zero)])
+        }
+    }
+
+    fn restore(&mut self, state: &ContactState) -> Result<(),Error> {
+        state.check(4,2,0)?;
+        self.tensioned = state.words[0] != 0;
+        let released = (state.words[1] != 0).then(|| state.value(3));
+        self.status.set(WinchStatus { time: state.value(0), tension: state.value(1), length: state.value(2), released });
+        Ok(())
+    }
+}
+
+/// Handle sharing the [WinchStatus] of a [WinchLaunch]
+#[derive(Clone)]
+pub struct WinchMonitor<T: Float = DefaultFloatRepr> {
+    /// Shared status
+    status: Rc<Cell<WinchStatus<T>>>,
+}
+
+impl<T: Float> WinchMonitor<T> {
+    /// Return the current [WinchStatus]
+    pub fn status(&self) -> WinchStatus<T> {
+        self.status.get()
+    }
+
+    /// Return `true` once the tether has been released
+    pub fn is_released(&self) -> bool {
+        self.status.get().released.is_some()
+    }
+
+    /// Release the tether now, as by the pilot pulling the release
+    pub fn release(&self) {
+        let mut status = self.status.get();
+        status.released.get_or_insert(status.time);
+        self.status.set(status);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Body,AeroBody,AeroEffect,AirState};
+    use crate::types::{Matrix3,Force,Torque,Frame,StateView};
+    use approx::assert_relative_eq;
+
+    /// Rocket motor thrusting along body X
+    struct Motor;
+    impl AeroEffect for Motor {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3, inputstate: &Vec<f64>) -> (Force,Torque) {
+            (Force::body_vec(Vector3::new(inputstate[0],0.0,0.0)),Torque::body_vec(Vector3::zeros()))
+        }
+    }
+
+    fn vehicle(attitude: UnitQuaternion<f64>) -> AffectedBody {
+        let body = Body::new(2.0,Matrix3::identity(),Vector3::zeros(),Vector3::zeros(),attitude,Vector3::zeros());
+        AffectedBody::new(AeroBody::new(body),vec![Box::new(Motor)])
+    }
+
+    #[test]
+    fn test_catapult() {
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.3,0.5);
+        let mut vehicle = vehicle(attitude);
+        // 200 N catapult stroke, with the motor off
+        let profile = RailProfile::Force(vec![(0.0,200.0),(0.5,200.0),(0.51,0.0)]);
+        let mut rail = LaunchRail::new(Vector3::zeros(),attitude,3.0).with_profile(profile);
+        let acceleration = (200.0 - 2.0 * physical_constants::STANDARD_ACCELERATION_OF_GRAVITY * 0.3_f64.sin()) / 2.0;
+        assert_relative_eq!(rail.acceleration(&vehicle,&vec![0.0]),acceleration,max_relative=1e-9);
+
+        while !rail.is_exited() {
+            rail.step(&mut vehicle,0.001,&vec![0.0]);
+            // Held on the rail at the launch attitude
+            assert_relative_eq!(vehicle.position().cross(&rail.direction()).norm(),0.0,epsilon=1e-9);
+            assert!(vehicle.attitude().angle_to(&attitude) < 1e-9);
+        }
+        assert_relative_eq!(rail.exit_speed().unwrap(),(2.0 * acceleration * 3.0).sqrt(),max_relative=1e-9);
+        assert_relative_eq!(vehicle.velocity_in_frame(Frame::World).dot(&rail.direction()),rail.exit_speed().unwrap(),max_relative=1e-9);
+
+        // Free flight after rail exit
+        rail.step(&mut vehicle,0.01,&vec![0.0]);
+        assert!(vehicle.position().dot(&rail.direction()) > 3.0);
+        assert!(vehicle.rates().norm() == 0.0 && vehicle.attitude().angle_to(&attitude) < 1e-9);
+    }
+
+    #[test]
+    fn test_rocket_on_rail() {
+        let attitude = UnitQuaternion::from_euler_angles(0.0,1.4,0.0);
+        let mut vehicle = vehicle(attitude);
+        let mut rail = LaunchRail::new(Vector3::zeros(),attitude,1.0);
+        // Thrust below the weight along the rail leaves the rocket resting at the start
+        (0..10).for_each(|_| rail.step(&mut vehicle,0.01,&vec![10.0]));
+        assert_eq!((rail.travel(),rail.speed()),(0.0,0.0));
+        assert_eq!(vehicle.position(),Vector3::zeros());
+
+        // Once ignited it leaves the rail
+        (0..100).for_each(|_| rail.step(&mut vehicle,0.01,&vec![60.0]));
+        assert!(rail.is_exited());
+        assert!(vehicle.position().dot(&rail.direction()) > 1.0);
+    }
+
+    #[test]
+    fn test_winch_release() {
+        // Glider 400 m above and 300 m beyond the winch, with the back-release hook set to 50°
+        let tether = Tether::new(Vector3::zeros(),Vector3::zeros());
+        let mut winch = WinchLaunch::new(tether,TensionProfile::Time(vec![(0.0,0.0),(2.0,4000.0)])).with_release(Release::Depression(50.0_f64.to_radians()));
+        let monitor = winch.monitor();
+        let low = Body::new(300.0,Matrix3::identity(),Vector3::new(800.0,0.0,-300.0),Vector3::zeros(),UnitQuaternion::identity(),Vector3::zeros()).statevector();
+        winch.step(&low,1.0);
+        let (force,_) = winch.get_contact(&low);
+        assert_relative_eq!(force.force.norm(),2000.0,max_relative=1e-9);
+        assert!(force.force.x < 0.0 && force.force.z > 0.0);
+        assert!(!monitor.is_released());
+
+        let high = Body::new(300.0,Matrix3::identity(),Vector3::new(300.0,0.0,-400.0),Vector3::zeros(),UnitQuaternion::identity(),Vector3::zeros()).statevector();
+        winch.step(&high,1.0);
+        assert_eq!(monitor.status().released,Some(2.0));
+        assert_eq!(winch.get_contact(&high).0.force.norm(),0.0);
+
+        let manual = WinchLaunch::new(tether,TensionProfile::Time(vec![(0.0,1000.0)]));
+        manual.monitor().release();
+        assert_eq!(manual.get_contact(&low).0.force.norm(),0.0);
+    }
+
+    #[test]
+    fn test_bungee_launch() {
+        // Bungee anchored 50 m ahead, slack below 20 m, dropping off the hook once slack
+        let mut vehicle = vehicle(UnitQuaternion::identity());
+        let bungee = WinchLaunch::new(Tether::new(Vector3::new(50.0,0.0,0.0),Vector3::zeros()),TensionProfile::Payout(vec![(20.0,0.0),(50.0,300.0)]))
+            .with_release(Release::Slack);
+        let monitor = bungee.monitor();
+        vehicle.add_contact(Box::new(bungee));
+        vehicle.step(0.01,&vec![0.0]);
+        assert_relative_eq!(monitor.status().tension,300.0,max_relative=1e-9);
+        assert_relative_eq!(vehicle.acceleration()[0],150.0,max_relative=1e-9);
+
+        while !monitor.is_released() && vehicle.time() < 5.0 {
+            vehicle.step(0.01,&vec![0.0]);
+        }
+        assert!(monitor.is_released());
+        assert!(monitor.status().length < 20.0);
+        // Energy of the stretched bungee, ½ 300 N × 30 m, is given to the 2 kg vehicle
+        assert_relative_eq!(vehicle.velocity()[0],(2.0 * 0.5 * 300.0 * 30.0 / 2.0_f64).sqrt(),max_relative=0.02);
+    }
+}
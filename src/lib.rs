@@ -6,12 +6,75 @@ mod kinematics;
 mod aero;
 
 mod effectors;
+mod static_body;
+mod geometry;
 
-pub use kinematics::Body;
+pub use kinematics::{Body,BodyError};
 pub use aero::{AeroBody,WindModel,DensityModel,AirState};
-pub use effectors::{AeroEffect,AffectedBody};
+pub use effectors::{AeroEffect,AffectedBody,Effectors};
+pub use static_body::{StaticAffectedBody,EffectorSet};
+pub use geometry::VehicleGeometry;
+
+mod error;
+pub use error::Error;
+
+pub mod lookup;
+pub mod inputs;
+pub mod schedule;
+pub mod recorder;
+pub mod stats;
+pub mod snapshot;
+pub mod trim;
+pub mod linearise;
+pub mod modes;
+pub mod derivatives;
+pub mod random;
+pub mod monte_carlo;
+pub mod sweep;
+pub mod scenario;
+pub mod envelope;
+pub mod sensitivity;
+pub mod replay;
+pub mod simulation;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod realtime;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hil;
+#[cfg(feature = "fmi")]
+pub mod fmi;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
+#[cfg(feature = "ros2")]
+pub mod ros2;
+pub mod attitude;
+pub mod frames;
+pub mod geodetic;
+pub mod wind_triangle;
+pub mod weather;
+pub mod certification;
+pub mod loads;
+pub mod flexibility;
+pub mod contact;
+pub mod tether;
+pub mod launch;
+pub mod world;
+pub mod formation;
+#[cfg(feature = "uom")]
+pub mod units;
+#[cfg(feature = "glam")]
+pub mod glam_interop;
+pub mod sensors;
+pub mod estimation;
+pub mod faults;
 
 pub mod wind_models;
 pub mod density_models {
-    pub use crate::aero::StandardDensity;
+    pub use crate::aero::{StandardDensity,StandardAtmosphere,sutherland_viscosity};
 }
+pub mod effector_models;
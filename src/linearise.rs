@@ -0,0 +1,323 @@
+//! Numerical linearisation
+//!
+//! [StateSpace::linearise] perturbs the state and inputs of an [AffectedBody] about an operating
+//! point, typically found with [Trim](crate::trim::Trim), and returns the continuous-time model
+//!
+//! `ẋ = A x + B u`, `y = C x + D u`
+//!
+//! The Jacobians are found by central differences. The internal states of effectors, such as actuator
+//! positions, are held at their current values and are not part of the model.
+//!
+//! [LinearTimeVarying::linearise] repeats the linearisation along a recorded trajectory, giving the
+//! sequence of `A(t)` and `B(t)` matrices for linear time-varying control synthesis and covariance
+//! propagation.
+
+use std::ops::IndexMut;
+
+use nalgebra::{DMatrix,DVector};
+
+use crate::{AffectedBody,WindModel,DensityModel};
+use crate::recorder::{Recorder,Sample};
+use crate::types::{StateVector,StateView,UnitQuaternion};
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Names of the linear model states, in order
+///
+/// Position and Euler angles replace the quaternion attitude so that the states are independent.
+pub const STATES: [&str; 12] = ["x","y","z","u","v","w","phi","theta","psi","p","q","r"];
+
+/// Names of the linear model outputs, in order
+///
+/// The outputs are the states followed by the airspeed, angle of attack and angle of sideslip.
+pub const OUTPUTS: [&str; 15] = ["x","y","z","u","v","w","phi","theta","psi","p","q","r","airspeed","alpha","beta"];
+
+/// Continuous-time linear state-space model
+#[derive(Clone,Debug)]
+pub struct StateSpace<T: Float = DefaultFloatRepr> {
+    /// State matrix, ordered as [STATES]
+    pub a: DMatrix<T>,
+    /// Input matrix, with one column per linearised input slot
+    pub b: DMatrix<T>,
+    /// Output matrix, ordered as [OUTPUTS]
+    pub c: DMatrix<T>,
+    /// Feedthrough matrix
+    pub d: DMatrix<T>,
+    /// Inputstate slot of each column of [StateSpace::b]
+    pub input_slots: Vec<usize>,
+}
+
+impl<T: Float> StateSpace<T> {
+    /// Linearise `vehicle` about its current state with `inputs`
+    ///
+    /// The state of the vehicle is restored afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `vehicle` - The vehicle to linearise
+    /// * `inputs` - Inputstate at the operating point
+    /// * `input_slots` - Inputstate slots to include as inputs of the linear model
+    pub fn linearise<I: Clone + IndexMut<usize,Output=T>, W: WindModel<T>, D: DensityModel<T>>(vehicle: &mut AffectedBody<I,T,W,D>, inputs: &I, input_slots: &[usize]) -> Self {
+        let initial_state = vehicle.statevector();
+        let x0 = euler_state(&initial_state);
+        let u0 = DVector::from_iterator(input_slots.len(),input_slots.iter().map(|&slot| inputs[slot]));
+
+        let mut evaluate = |x: &DVector<T>, u: &DVector<T>| {
+            let mut perturbed = inputs.clone();
+            for (idx, &slot) in input_slots.iter().enumerate() {
+                perturbed[slot] = u[idx];
+            }
+            vehicle.set_state(quaternion_state(x));
+            (state_derivative(x,&vehicle.derivative(&perturbed)),outputs(x,vehicle))
+        };
+
+        let n = STATES.len();
+        let m = input_slots.len();
+        let p = OUTPUTS.len();
+        let mut a = DMatrix::zeros(n,n);
+        let mut c = DMatrix::zeros(p,n);
+        for idx in 0..n {
+            let h = step_size(x0[idx]);
+            let mut upper = x0.clone();
+            upper[idx] += h;
+            let mut lower = x0.clone();
+            lower[idx] -= h;
+            let (f_upper, y_upper) = evaluate(&upper,&u0);
+            let (f_lower, y_lower) = evaluate(&lower,&u0);
+            a.set_column(idx,&((f_upper - f_lower) / (h + h)));
+            c.set_column(idx,&((y_upper - y_lower) / (h + h)));
+        }
+
+        let mut b = DMatrix::zeros(n,m);
+        let mut d = DMatrix::zeros(p,m);
+        for idx in 0..m {
+            let h = step_size(u0[idx]);
+            let mut upper = u0.clone();
+            upper[idx] += h;
+            let mut lower = u0.clone();
+            lower[idx] -= h;
+            let (f_upper, y_upper) = evaluate(&x0,&upper);
+            let (f_lower, y_lower) = evaluate(&x0,&lower);
+            b.set_column(idx,&((f_upper - f_lower) / (h + h)));
+            d.set_column(idx,&((y_upper - y_lower) / (h + h)));
+        }
+
+        vehicle.set_state(initial_state);
+        StateSpace { a, b, c, d, input_slots: input_slots.to_vec() }
+    }
+
+    /// Return the index of the state called `name` in [STATES]
+    pub fn state_index(name: &str) -> Option<usize> {
+        STATES.iter().position(|state| *state == name)
+    }
+
+    /// Return the index of the output called `name` in [OUTPUTS]
+    pub fn output_index(name: &str) -> Option<usize> {
+        OUTPUTS.iter().position(|output| *output == name)
+    }
+}
+
+/// Sequence of linear models along a trajectory
+#[derive(Clone,Debug)]
+pub struct LinearTimeVarying<T: Float = DefaultFloatRepr> {
+    /// Time of each model (s), in increasing order
+    pub times: Vec<T>,
+    /// Linear model at each time
+    pub models: Vec<StateSpace<T>>,
+}
+
+impl<T: Float> LinearTimeVarying<T> {
+    /// Linearise `vehicle` at every `every`th sample of `trajectory`, starting from the first
+    ///
+    /// The vehicle is set to the state of each selected sample and linearised with the inputstate returned by
+    /// `inputs` for that sample, such as the evaluation of the [InputSchedule](crate::schedule::InputSchedule)
+    /// which flew the trajectory. Wind and density models are evaluated at their current time. The state of
+    /// the vehicle is restored afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `vehicle` - The vehicle to linearise
+    /// * `trajectory` - Recorded trajectory, such as one returned by [AffectedBody::run]
+    /// * `every` - Spacing of the linearisation points, in samples
+    /// * `input_slots` - Inputstate slots to include as inputs of the linear models
+    /// * `inputs` - Closure returning the inputstate at a sample
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    pub fn linearise<I, W, D, F>(vehicle: &mut AffectedBody<I,T,W,D>, trajectory: &Recorder<T>, every: usize, input_slots: &[usize], mut inputs: F) -> Self
+    where I: Clone + IndexMut<usize,Output=T>, W: WindModel<T>, D: DensityModel<T>, F: FnMut(&Sample<T>) -> I {
+        if every == 0 {
+            panic!("Linearisation spacing must be at least one sample");
+        }
+        let initial_state = vehicle.statevector();
+        let (times, models) = trajectory.samples().iter().step_by(every).map(|sample| {
+            vehicle.set_state(sample.state);
+            (sample.time,StateSpace::linearise(vehicle,&inputs(sample),input_slots))
+        }).unzip();
+        vehicle.set_state(initial_state);
+        LinearTimeVarying { times, models }
+    }
+
+    /// Return the number of models
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    /// Return `true` if there are no models
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Return the model in effect at `time` (s), held from the latest linearisation point at or before it
+    ///
+    /// Times before the first point return the first model, or `None` if there are no models.
+    pub fn at(&self, time: T) -> Option<&StateSpace<T>> {
+        let idx = self.times.iter().rposition(|&point| point <= time).unwrap_or(0);
+        self.models.get(idx)
+    }
+}
+
+/// Return the central-difference step for a variable with value `x`
+fn step_size<T: Float>(x: T) -> T {
+    <T as num_traits::Float>::cbrt(T::epsilon()) * <T as num_traits::Float>::max(T::one(),<T as num_traits::Float>::abs(x))
+}
+
+/// Convert a statevector to the linear model states
+fn euler_state<T: Float>(state: &StateVector<T>) -> DVector<T> {
+    let (roll, pitch, yaw) = state.attitude().euler_angles();
+    let mut x = DVector::zeros(12);
+    for idx in 0..6 {
+        x[idx] = state[idx];
+    }
+    x[6] = roll;
+    x[7] = pitch;
+    x[8] = yaw;
+    for idx in 0..3 {
+        x[9 + idx] = state[10 + idx];
+    }
+    x
+}
+
+/// Convert the linear model states to a statevector
+fn quaternion_state<T: Float>(x: &DVector<T>) -> StateVector<T> {
+    let attitude = UnitQuaternion::from_euler_angles(x[6],x[7],x[8]);
+    StateVector::from_vec(vec![
+        x[0],        x[1],        x[2],
+        x[3],        x[4],        x[5],
+        attitude[0], attitude[1], attitude[2], attitude[3],
+        x[9],        x[10],       x[11]
+        ])
+}
+
+/// Return the linear model state derivative from the statevector derivative
+fn state_derivative<T: Float>(x: &DVector<T>, derivative: &StateVector<T>) -> DVector<T> {
+    let (sin_roll, cos_roll) = <T as num_traits::Float>::sin_cos(x[6]);
+    let (sin_pitch, cos_pitch) = <T as num_traits::Float>::sin_cos(x[7]);
+    let (p, q, r) = (x[9],x[10],x[11]);
+
+    let mut x_dot = DVector::zeros(12);
+    for idx in 0..6 {
+        x_dot[idx] = derivative[idx];
+    }
+    x_dot[6] = p + (q * sin_roll + r * cos_roll) * sin_pitch / cos_pitch;
+    x_dot[7] = q * cos_roll - r * sin_roll;
+    x_dot[8] = (q * sin_roll + r * cos_roll) / cos_pitch;
+    for idx in 0..3 {
+        x_dot[9 + idx] = derivative[10 + idx];
+    }
+    x_dot
+}
+
+/// Return the linear model outputs for the vehicle in state `x`
+fn outputs<I, T: Float, W: WindModel<T>, D: DensityModel<T>>(x: &DVector<T>, vehicle: &AffectedBody<I,T,W,D>) -> DVector<T> {
+    let airstate = vehicle.get_airstate();
+    DVector::from_iterator(OUTPUTS.len(),x.iter().copied().chain([airstate.airspeed,airstate.alpha,airstate.beta]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::{Trim,TrimCondition,test_aircraft};
+    use crate::types::Vector3;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_trimmed_linearisation() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let trim = Trim::new(vec![0.0,0.0,0.0,0.5],&[0,1,2,3]).solve(&mut vehicle,&TrimCondition::level(20.0)).unwrap();
+        let model = StateSpace::linearise(&mut vehicle,&trim.inputs,&[0,1,2,3]);
+        assert_eq!(vehicle.statevector(),trim.state);
+
+        let idx = |name| StateSpace::<f64>::state_index(name).unwrap();
+        // Kinematics
+        assert_relative_eq!(model.a[(idx("x"),idx("u"))],trim.pitch.cos(),max_relative=1e-6);
+        assert_relative_eq!(model.a[(idx("theta"),idx("q"))],1.0,max_relative=1e-6);
+        // Speed stability, pitch damping and elevator effectiveness
+        assert!(model.a[(idx("u"),idx("u"))] < 0.0);
+        assert!(model.a[(idx("q"),idx("q"))] < 0.0);
+        assert!(model.b[(idx("q"),1)] < 0.0);
+        assert!(model.b[(idx("u"),3)] > 0.0);
+
+        // Angle of attack output responds to heave velocity
+        let alpha = StateSpace::<f64>::output_index("alpha").unwrap();
+        assert_relative_eq!(model.c[(alpha,idx("w"))],trim.alpha.cos() / 20.0,max_relative=1e-5);
+        assert_relative_eq!(model.d.amax(),0.0,epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_small_perturbation() {
+        // The linear model predicts the nonlinear response to a small pitch rate perturbation
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let trim = Trim::new(vec![0.0,0.0,0.0,0.5],&[0,1,2,3]).solve(&mut vehicle,&TrimCondition::level(20.0)).unwrap();
+        let model = StateSpace::linearise(&mut vehicle,&trim.inputs,&[0,1,2,3]);
+
+        let mut perturbed = trim.state;
+        perturbed[11] = 1e-3;
+        vehicle.set_state(perturbed);
+        let derivative = vehicle.derivative(&trim.inputs);
+        let q_dot = model.a[(10,10)] * 1e-3;
+        assert_relative_eq!(derivative[11],q_dot,max_relative=1e-3);
+        let rates: Vector3 = derivative.fixed_rows::<3>(10).into();
+        assert_relative_eq!(rates.x,0.0,epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_trajectory_linearisation() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let trim = Trim::new(vec![0.0,0.0,0.0,0.5],&[0,1,2,3]).solve(&mut vehicle,&TrimCondition::level(20.0)).unwrap();
+        let reference = StateSpace::linearise(&mut vehicle,&trim.inputs,&[0,1,2,3]);
+
+        // A pitch-up manoeuvre from trim
+        let trajectory = vehicle.run(1.0,0.01,|_,_,time| {
+            let mut inputs = trim.inputs.clone();
+            inputs[1] -= if time < 0.5 { 0.05 } else { 0.0 };
+            inputs
+        });
+        let end_state = vehicle.statevector();
+        let ltv = LinearTimeVarying::linearise(&mut vehicle,&trajectory,25,&[0,1,2,3],|_| trim.inputs.clone());
+        assert_eq!(vehicle.statevector(),end_state);
+        assert_eq!(ltv.len(),5);
+        assert_relative_eq!(ltv.times[4],1.0,max_relative=1e-12);
+
+        // The first point is the trimmed model, and the dynamics change along the manoeuvre
+        let idx = |name| StateSpace::<f64>::state_index(name).unwrap();
+        assert!((&ltv.models[0].a - &reference.a).amax() < 1e-6);
+        assert!((&ltv.models[4].a - &reference.a).amax() > 1e-3);
+        assert!(ltv.models.iter().all(|model| model.a[(idx("q"),idx("q"))] < 0.0));
+    }
+
+    #[test]
+    fn test_hold_lookup() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let inputs = vec![0.0,0.0,0.0,0.5];
+        let trajectory = vehicle.run(0.1,0.01,|_,_,_| inputs.clone());
+        let ltv = LinearTimeVarying::linearise(&mut vehicle,&trajectory,4,&[1],|_| inputs.clone());
+        assert_eq!(ltv.times.len(),3);
+        let held = |time: f64| ltv.models.iter().position(|model| std::ptr::eq(model,ltv.at(time).unwrap())).unwrap();
+        assert_eq!(held(-1.0),0);
+        assert_eq!(held(0.05),1);
+        assert_eq!(held(0.08),2);
+        assert_eq!(held(10.0),2);
+        assert!(LinearTimeVarying::<f64> { times: vec![], models: vec![] }.at(0.0).is_none());
+    }
+}
@@ -0,0 +1,232 @@
+//! Structural loads extraction
+//!
+//! A [Loads] recorder screens a simulation for structural loads. Each step it records the body-axis
+//! load factor and, at each [LoadStation], a bending-moment proxy: the moment about the station root of
+//! the forces of chosen effectors, applied at their points of action. Exceedance counts of the recorded
+//! histories then give the load spectra used to screen gust and manoeuvre cases.
+//!
+//! Effectors return their total force and torque, so the application point of each effector is given to
+//! the station; only effector forces are included, not the inertia relief of the structure outboard.
+
+use crate::{AffectedBody,WindModel,DensityModel,Body};
+use crate::sensors::Truth;
+use crate::types::{Vector3,StateVector,StateView,Frame,Float,DefaultFloatRepr,constant};
+
+/// Structural station at which a bending-moment proxy is computed
+#[derive(Clone,Debug)]
+pub struct LoadStation<T: Float = DefaultFloatRepr> {
+    /// Name of the station
+    name: String,
+    /// Body-frame position of the station root (m)
+    root: Vector3<T>,
+    /// Names and body-frame application points (m) of the effectors outboard of the station
+    effectors: Vec<(String,Vector3<T>)>,
+}
+
+impl<T: Float> LoadStation<T> {
+    /// Create a new [LoadStation] called `name` with its root at the body-frame position `root` (m)
+    pub fn new(name: &str, root: Vector3<T>) -> Self {
+        LoadStation { name: name.to_string(), root, effectors: Vec::new() }
+    }
+
+    /// Include the force of the effector called `name`, acting at the body-frame position `point` (m)
+    pub fn with_effector(mut self, name: &str, point: Vector3<T>) -> Self {
+        self.effectors.push((name.to_string(),point));
+        self
+    }
+
+    /// Return the name of the station
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the body-frame moment (N·m) about the root of the effector forces during the previous step of `vehicle`
+    ///
+    /// Effectors which did not contribute in the previous step are omitted.
+    pub fn moment<I, W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &AffectedBody<I,T,W,D>) -> Vector3<T> {
+        let state = vehicle.statevector();
+        self.effectors.iter()
+            .filter_map(|(name, point)| vehicle.effector_effect(name).map(|(force, _)| (point,body_force(&state,force.force,force.frame))))
+            .fold(Vector3::zeros(),|moment, (point, force)| moment + (point - self.root).cross(&force))
+    }
+}
+
+/// Return `force` in `frame` as a body-frame force for a vehicle in `state`
+fn body_force<T: Float>(state: &StateVector<T>, force: Vector3<T>, frame: Frame) -> Vector3<T> {
+    match frame {
+        Frame::Body => force,
+        Frame::World => Body::get_dcm(state) * force,
+    }
+}
+
+/// Loads at a single step
+#[derive(Clone,Debug)]
+pub struct LoadSample<T: Float = DefaultFloatRepr> {
+    /// Simulation time (s)
+    pub time: T,
+    /// Body-axis load factor, the specific force over `-g`, so level flight gives `(0, 0, 1)`
+    pub load_factor: Vector3<T>,
+    /// Body-frame bending-moment proxy at each station, in the order the stations were added (N·m)
+    pub moments: Vec<Vector3<T>>,
+}
+
+/// Return the number of times `values` cross each of `levels` away from `reference`
+///
+/// Levels above the reference count upward crossings, and levels below count downward crossings, as in
+/// the exceedance spectra of gust and manoeuvre loads.
+pub fn exceedances<T: Float>(values: &[T], reference: T, levels: &[T]) -> Vec<usize> {
+    levels.iter().map(|&level| {
+        values.windows(2).filter(|pair| if level >= reference {
+            pair[0] < level && pair[1] >= level
+        } else {
+            pair[0] > level && pair[1] <= level
+        }).count()
+    }).collect()
+}
+
+/// Recorder of load factors and station bending moments
+#[derive(Clone,Debug)]
+pub struct Loads<T: Float = DefaultFloatRepr> {
+    /// Stations at which moments are recorded
+    stations: Vec<LoadStation<T>>,
+    /// Recorded samples, in time order
+    samples: Vec<LoadSample<T>>,
+}
+
+impl<T: Float> Default for Loads<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Loads<T> {
+    /// Create a new [Loads] recorder without stations
+    pub fn new() -> Self {
+        Loads { stations: Vec::new(), samples: Vec::new() }
+    }
+
+    /// Add a station at which to record the bending-moment proxy
+    pub fn with_station(mut self, station: LoadStation<T>) -> Self {
+        self.stations.push(station);
+        self
+    }
+
+    /// Return the stations, in the order they were added
+    pub fn stations(&self) -> &[LoadStation<T>] {
+        &self.stations
+    }
+
+    /// Record the loads of `vehicle` at `time`, after it has been stepped
+    pub fn record<I, W: WindModel<T>, D: DensityModel<T>>(&mut self, time: T, vehicle: &AffectedBody<I,T,W,D>) {
+        let gravity = constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY);
+        self.samples.push(LoadSample {
+            time,
+            load_factor: -Truth::new(time,vehicle).specific_force() / gravity,
+            moments: self.stations.iter().map(|station| station.moment(vehicle)).collect(),
+        });
+    }
+
+    /// Return the recorded samples
+    pub fn samples(&self) -> &[LoadSample<T>] {
+        &self.samples
+    }
+
+    /// Return the history of the load factor along the body `axis` (0, 1 or 2)
+    pub fn load_factor(&self, axis: usize) -> Vec<T> {
+        self.samples.iter().map(|sample| sample.load_factor[axis]).collect()
+    }
+
+    /// Return the history of the moment about the body `axis` at the station called `name`, if it exists
+    pub fn moment(&self, name: &str, axis: usize) -> Option<Vec<T>> {
+        let index = self.stations.iter().position(|station| station.name == name)?;
+        Some(self.samples.iter().map(|sample| sample.moments[index][axis]).collect())
+    }
+
+    /// Return the minimum and maximum normal load factor, or `None` if nothing has been recorded
+    pub fn load_factor_range(&self) -> Option<(T,T)> {
+        self.samples.iter().map(|sample| sample.load_factor[2]).fold(None,|range, value| match range {
+            None => Some((value,value)),
+            Some((min, max)) => Some((<T as num_traits::Float>::min(min,value),<T as num_traits::Float>::max(max,value))),
+        })
+    }
+
+    /// Return the number of times the normal load factor crosses each of `levels` away from 1 g
+    pub fn load_factor_exceedances(&self, levels: &[T]) -> Vec<usize> {
+        exceedances(&self.load_factor(2),T::one(),levels)
+    }
+
+    /// Return the number of times the moment about `axis` at the station called `name` crosses each of
+    /// `levels` away from its first recorded value, if the station exists
+    pub fn moment_exceedances(&self, name: &str, axis: usize, levels: &[T]) -> Option<Vec<usize>> {
+        let history = self.moment(name,axis)?;
+        let reference = history.first().copied().unwrap_or(T::zero());
+        Some(exceedances(&history,reference,levels))
+    }
+
+    /// Remove all recorded samples, keeping the stations
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AeroBody,AeroEffect,AirState};
+    use crate::types::{Force,Torque,Matrix3};
+    use crate::wind_models::ConstantWind;
+    use crate::density_models::StandardDensity;
+    use approx::assert_relative_eq;
+
+    /// Upward body-frame force of the first input times a fixed force (N)
+    struct Lift(f64);
+
+    impl AeroEffect for Lift {
+        fn get_effect(&self, _airstate: AirState<f64>, _rates: Vector3<f64>, inputstate: &Vec<f64>) -> (Force<f64>,Torque<f64>) {
+            (Force::body(0.0,0.0,-self.0 * inputstate[0]),Torque::body(0.0,0.0,0.0))
+        }
+    }
+
+    fn vehicle() -> AffectedBody<Vec<f64>,f64,ConstantWind<f64>,StandardDensity> {
+        let body = Body::new_at_origin(10.0,Matrix3::identity());
+        let mut vehicle = AffectedBody::new(AeroBody::with_density_model(body,ConstantWind::new(Vector3::zeros()),StandardDensity{}),vec![]);
+        let weight = 10.0 * physical_constants::STANDARD_ACCELERATION_OF_GRAVITY;
+        vehicle.add_effector("left",Box::new(Lift(weight / 2.0)));
+        vehicle.add_effector("right",Box::new(Lift(weight / 2.0)));
+        vehicle
+    }
+
+    #[test]
+    fn test_record() {
+        let mut vehicle = vehicle();
+        let mut loads = Loads::new()
+            .with_station(LoadStation::new("right root",Vector3::new(0.0,0.5,0.0)).with_effector("right",Vector3::new(0.0,3.0,0.0)));
+        vehicle.step(0.01,&vec![2.0]);
+        loads.record(0.01,&vehicle);
+
+        let sample = &loads.samples()[0];
+        assert_relative_eq!(sample.load_factor[2],2.0,max_relative=1e-9);
+        // Half of twice the weight acting 2.5 m outboard bends the wing tip-up, a negative moment about +x
+        let weight = 10.0 * physical_constants::STANDARD_ACCELERATION_OF_GRAVITY;
+        assert_relative_eq!(sample.moments[0][0],-weight * 2.5,max_relative=1e-9);
+        assert_eq!(loads.moment("right root",0).unwrap().len(),1);
+        assert!(loads.moment("left root",0).is_none());
+    }
+
+    #[test]
+    fn test_exceedances() {
+        let history = [1.0,1.6,0.8,1.3,2.1,1.0,0.2,1.0];
+        assert_eq!(exceedances(&history,1.0,&[1.5,2.0,0.5,0.0]),vec![2,1,1,0]);
+
+        let mut vehicle = vehicle();
+        let mut loads: Loads<f64> = Loads::new();
+        for (step, &input) in history.iter().enumerate() {
+            vehicle.step(0.01,&vec![input]);
+            loads.record(step as f64 * 0.01,&vehicle);
+        }
+        assert_eq!(loads.load_factor_exceedances(&[1.5,2.0,0.5]),vec![2,1,1]);
+        let (min, max) = loads.load_factor_range().unwrap();
+        assert_relative_eq!(min,0.2,max_relative=1e-9);
+        assert_relative_eq!(max,2.1,max_relative=1e-9);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Interpolation policy used between table breakpoints
+#[derive(Copy,Clone)]
+pub enum Interpolation {
+    /// Multi-linear interpolation between neighbouring breakpoints
+    Linear,
+    /// Use the value at the nearest breakpoint
+    Nearest,
+}
+
+/// Extrapolation policy used outside the table breakpoints
+#[derive(Copy,Clone)]
+pub enum Extrapolation {
+    /// Hold the value at the nearest edge of the table
+    Clamp,
+    /// Extend the gradient of the outermost segment
+    Linear,
+}
+
+/// Represent an N-dimensional lookup table on a rectilinear grid
+///
+/// Values are stored flattened in row-major order, so the last axis varies fastest.
+#[derive(Clone)]
+pub struct Table<T: Float = DefaultFloatRepr> {
+    /// Strictly increasing breakpoints for each axis
+    breakpoints: Vec<Vec<T>>,
+    /// Flattened table values
+    values: Vec<T>,
+    /// Interpolation policy
+    interpolation: Interpolation,
+    /// Extrapolation policy
+    extrapolation: Extrapolation,
+}
+
+impl<T: Float> Table<T> {
+    /// Create a new N-dimensional table with linear interpolation and clamped extrapolation
+    ///
+    /// # Arguments
+    ///
+    /// * `breakpoints` - Strictly increasing breakpoints for each axis
+    /// * `values` - Flattened table values in row-major order (last axis varies fastest)
+    pub fn new(breakpoints: Vec<Vec<T>>, values: Vec<T>) -> Self {
+        if breakpoints.is_empty() {
+            panic!("Table must have at least one axis")
+        }
+        for axis in &breakpoints {
+            if axis.is_empty() {
+                panic!("Table axes must have at least one breakpoint")
+            }
+            if axis.windows(2).any(|pair| pair[1] <= pair[0]) {
+                panic!("Table breakpoints must be strictly increasing")
+            }
+        }
+        let expected_len: usize = breakpoints.iter().map(|axis| axis.len()).product();
+        if values.len() != expected_len {
+            panic!("Table has {} values but breakpoints require {}",values.len(),expected_len)
+        }
+        Table {
+            breakpoints,
+            values,
+            interpolation: Interpolation::Linear,
+            extrapolation: Extrapolation::Clamp,
+        }
+    }
+
+    /// Create a new 1D table with `values[i]` at `x[i]`
+    pub fn new_1d(x: Vec<T>, values: Vec<T>) -> Self {
+        Table::new(vec![x],values)
+    }
+
+    /// Create a new 2D table with `values[i][j]` at `(x[i],y[j])`
+    pub fn new_2d(x: Vec<T>, y: Vec<T>, values: Vec<Vec<T>>) -> Self {
+        if values.iter().any(|row| row.len() != y.len()) {
+            panic!("Table rows must match the number of y breakpoints")
+        }
+        Table::new(vec![x,y],values.into_iter().flatten().collect())
+    }
+
+    /// Create a new 3D table with `values[i][j][k]` at `(x[i],y[j],z[k])`
+    pub fn new_3d(x: Vec<T>, y: Vec<T>, z: Vec<T>, values: Vec<Vec<Vec<T>>>) -> Self {
+        if values.iter().any(|plane| plane.len() != y.len() || plane.iter().any(|row| row.len() != z.len())) {
+            panic!("Table planes must match the number of y and z breakpoints")
+        }
+        Table::new(vec![x,y,z],values.into_iter().flatten().flatten().collect())
+    }
+
+    /// Set the interpolation policy
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Set the extrapolation policy
+    pub fn with_extrapolation(mut self, extrapolation: Extrapolation) -> Self {
+        self.extrapolation = extrapolation;
+        self
+    }
+
+    /// Return the number of axes of the table
+    pub fn dimensions(&self) -> usize {
+        self.breakpoints.len()
+    }
+
+    /// Find the lower breakpoint index and interpolation fraction along `axis` for `x`
+    fn locate(&self, axis: &[T], x: T) -> (usize,T) {
+        if axis.len() == 1 {
+            return (0,T::zero());
+        }
+        let idx = match axis.iter().rposition(|&bp| bp <= x) {
+            Some(idx) => idx.min(axis.len() - 2),
+            None => 0,
+        };
+        let mut fraction = (x - axis[idx]) / (axis[idx+1] - axis[idx]);
+        if let Extrapolation::Clamp = self.extrapolation {
+            fraction = num_traits::clamp(fraction,T::zero(),T::one());
+        }
+        if let Interpolation::Nearest = self.interpolation {
+            let half = constant::<T>(0.5);
+            fraction = if fraction < half { T::zero() } else { T::one() };
+        }
+        (idx,fraction)
+    }
+
+    /// Look up the table value at `point`
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - Coordinate for each axis of the table
+    pub fn lookup(&self, point: &[T]) -> T {
+        self.weights(point).into_iter().map(|(idx, weight)| weight * self.values[idx]).fold(T::zero(),|sum, value| sum + value)
+    }
+
+    /// Return the flattened index and interpolation weight of each grid point contributing at `point`
+    pub(crate) fn weights(&self, point: &[T]) -> Vec<(usize,T)> {
+        if point.len() != self.dimensions() {
+            panic!("Table lookup needs {} coordinates, got {}",self.dimensions(),point.len())
+        }
+
+        let located: Vec<(usize,T)> = self.breakpoints.iter().zip(point).map(|(axis,&x)| self.locate(axis,x)).collect();
+
+        // Collect the weight of each corner of the enclosing hypercube
+        let mut weights = Vec::new();
+        for corner in 0..(1usize << self.dimensions()) {
+            let mut weight = T::one();
+            let mut flat_idx = 0;
+            for (dim,(axis,&(idx,fraction))) in self.breakpoints.iter().zip(&located).enumerate() {
+                let upper = (corner >> dim) & 1 == 1 && axis.len() > 1;
+                if upper {
+                    weight *= fraction;
+                } else if axis.len() > 1 {
+                    weight *= T::one() - fraction;
+                } else if (corner >> dim) & 1 == 1 {
+                    weight = T::zero();
+                }
+                flat_idx = flat_idx * axis.len() + idx + upper as usize;
+            }
+            if weight != T::zero() {
+                weights.push((flat_idx,weight));
+            }
+        }
+        weights
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_1d() {
+        let table = Table::new_1d(vec![0.0,1.0,3.0],vec![0.0,2.0,6.0]);
+        assert_relative_eq!(table.lookup(&[0.5]),1.0);
+        assert_relative_eq!(table.lookup(&[2.0]),4.0);
+        assert_relative_eq!(table.lookup(&[3.0]),6.0);
+
+        // Clamped by default
+        assert_relative_eq!(table.lookup(&[-1.0]),0.0);
+        assert_relative_eq!(table.lookup(&[5.0]),6.0);
+
+        let table = table.with_extrapolation(Extrapolation::Linear);
+        assert_relative_eq!(table.lookup(&[-1.0]),-2.0);
+        assert_relative_eq!(table.lookup(&[5.0]),10.0);
+
+        let table = table.with_interpolation(Interpolation::Nearest);
+        assert_relative_eq!(table.lookup(&[0.4]),0.0);
+        assert_relative_eq!(table.lookup(&[0.6]),2.0);
+    }
+
+    #[test]
+    fn test_2d() {
+        // f(x,y) = x + 10y is reproduced exactly by bilinear interpolation
+        let table = Table::new_2d(
+            vec![0.0,1.0,2.0],
+            vec![0.0,1.0],
+            vec![vec![0.0,10.0],vec![1.0,11.0],vec![2.0,12.0]]);
+        assert_relative_eq!(table.lookup(&[0.5,0.5]),5.5);
+        assert_relative_eq!(table.lookup(&[1.25,0.75]),8.75);
+        assert_relative_eq!(table.lookup(&[2.0,1.0]),12.0);
+    }
+
+    #[test]
+    fn test_3d_singleton_axis() {
+        let table = Table::new_3d(
+            vec![0.0,1.0],
+            vec![5.0],
+            vec![0.0,1.0],
+            vec![vec![vec![0.0,1.0]],vec![vec![2.0,3.0]]]);
+        assert_relative_eq!(table.lookup(&[0.5,-3.0,0.5]),1.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_non_increasing_breakpoints() {
+        Table::new_1d(vec![0.0,0.0],vec![1.0,2.0]);
+    }
+}
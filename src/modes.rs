@@ -0,0 +1,313 @@
+//! Dynamic mode analysis
+//!
+//! [ModeReport] finds the classic rigid-body modes of a linear [StateSpace] model. The longitudinal
+//! states `[u, w, q, theta]` and lateral states `[v, p, r, phi]` are analysed separately, which
+//! is exact when the two are decoupled, as in symmetric straight flight.
+//!
+//! - The longitudinal roots are the **phugoid**, the pair of lowest magnitude, and the **short period**.
+//! - The lateral roots are the oscillatory **Dutch roll** pair, and the real **spiral** and **roll
+//!   subsidence** roots, of lowest and highest magnitude respectively. When the roll and spiral roots
+//!   couple into an oscillatory pair this is reported as the **roll-spiral** mode.
+//!
+//! A subsystem whose state matrix is not finite, for example from a NaN derivative, has NaN roots which
+//! are reported as **unclassified**.
+
+use nalgebra::{Complex,DMatrix,DVector};
+
+use crate::linearise::StateSpace;
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Longitudinal states, as indices in [STATES](crate::linearise::STATES)
+const LONGITUDINAL: [usize; 4] = [3,5,10,7];
+
+/// Lateral states, as indices in [STATES](crate::linearise::STATES)
+const LATERAL: [usize; 4] = [4,9,11,6];
+
+/// Classic rigid-body modes
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum ModeKind {
+    /// Fast, well-damped pitching oscillation at near-constant speed
+    ShortPeriod,
+    /// Slow exchange of airspeed and height
+    Phugoid,
+    /// Coupled yawing and rolling oscillation
+    DutchRoll,
+    /// Fast, real roll rate convergence
+    RollSubsidence,
+    /// Slow, real divergence or convergence in bank and heading
+    Spiral,
+    /// Oscillatory coupling of the roll subsidence and spiral roots
+    RollSpiral,
+    /// Root of a subsystem which could not be analysed, such as a NaN root from a NaN derivative
+    Unclassified,
+}
+
+/// Single dynamic mode
+#[derive(Clone,Debug)]
+pub struct Mode<T: Float = DefaultFloatRepr> {
+    /// Type of mode
+    pub kind: ModeKind,
+    /// Eigenvalue, with non-negative imaginary part (s<sup>-1</sup>)
+    pub eigenvalue: Complex<T>,
+    /// Eigenvector over [STATES](crate::linearise::STATES), scaled so the largest component is 1
+    pub eigenvector: DVector<Complex<T>>,
+    /// Undamped natural frequency (rad·s<sup>-1</sup>)
+    pub natural_frequency: T,
+    /// Damping ratio, negative when unstable
+    pub damping_ratio: T,
+}
+
+impl<T: Float> Mode<T> {
+    /// Return `true` if the mode is an oscillation
+    pub fn is_oscillatory(&self) -> bool {
+        self.eigenvalue.im > T::zero()
+    }
+
+    /// Return `true` if the mode decays
+    pub fn is_stable(&self) -> bool {
+        self.eigenvalue.re < T::zero()
+    }
+
+    /// Return the damped period of an oscillatory mode (s)
+    pub fn period(&self) -> Option<T> {
+        if self.is_oscillatory() { Some(T::two_pi() / self.eigenvalue.im) } else { None }
+    }
+
+    /// Return the time to half amplitude of a stable mode, or to double amplitude of an unstable mode (s)
+    pub fn time_to_half_or_double(&self) -> Option<T> {
+        if self.eigenvalue.re == T::zero() {
+            None
+        } else {
+            Some(T::ln_2() / <T as num_traits::Float>::abs(self.eigenvalue.re))
+        }
+    }
+}
+
+/// Modes of a linear model
+#[derive(Clone,Debug)]
+pub struct ModeReport<T: Float = DefaultFloatRepr> {
+    /// Classified modes, longitudinal then lateral, with one entry per oscillatory pair or real root
+    pub modes: Vec<Mode<T>>,
+    /// Every eigenvalue of the full state matrix, NaN if the matrix is not finite
+    pub eigenvalues: DVector<Complex<T>>,
+}
+
+impl<T: Float> ModeReport<T> {
+    /// Find and classify the modes of `model`
+    pub fn new(model: &StateSpace<T>) -> Self {
+        let a = &model.a;
+        let eigenvalues = eigenvalues(a);
+        let mut modes = Vec::new();
+
+        let mut longitudinal = subsystem_eigenvalues(a,&LONGITUDINAL);
+        if !longitudinal.iter().all(is_finite) {
+            push_unclassified(&mut modes,a,&longitudinal);
+        } else {
+            sort_by_modulus(&mut longitudinal);
+            for (idx, eigenvalue) in longitudinal.into_iter().enumerate() {
+                let kind = if idx < 2 { ModeKind::Phugoid } else { ModeKind::ShortPeriod };
+                push_mode(&mut modes,a,kind,eigenvalue);
+            }
+        }
+
+        let mut lateral = subsystem_eigenvalues(a,&LATERAL);
+        if !lateral.iter().all(is_finite) {
+            push_unclassified(&mut modes,a,&lateral);
+            return ModeReport { modes, eigenvalues };
+        }
+        sort_by_modulus(&mut lateral);
+        let real_roots: Vec<Complex<T>> = lateral.iter().copied().filter(|eigenvalue| eigenvalue.im == T::zero()).collect();
+        for (idx, eigenvalue) in lateral.iter().enumerate() {
+            let kind = if eigenvalue.im != T::zero() {
+                // With two oscillatory pairs the roll and spiral roots have coupled into the slower pair
+                if real_roots.is_empty() && idx < 2 { ModeKind::RollSpiral } else { ModeKind::DutchRoll }
+            } else {
+                // With four real roots the middle two are an overdamped Dutch roll
+                match real_roots.iter().position(|root| root == eigenvalue) {
+                    Some(0) => ModeKind::Spiral,
+                    Some(position) if position == real_roots.len() - 1 => ModeKind::RollSubsidence,
+                    _ => ModeKind::DutchRoll,
+                }
+            };
+            push_mode(&mut modes,a,kind,*eigenvalue);
+        }
+
+        ModeReport { modes, eigenvalues }
+    }
+
+    /// Return the first mode of `kind`
+    pub fn mode(&self, kind: ModeKind) -> Option<&Mode<T>> {
+        self.modes.iter().find(|mode| mode.kind == kind)
+    }
+
+    /// Return `true` if every classified mode decays
+    pub fn is_stable(&self) -> bool {
+        self.modes.iter().all(|mode| mode.is_stable())
+    }
+}
+
+/// Return the modulus of a complex number
+fn modulus<T: Float>(value: &Complex<T>) -> T {
+    <T as num_traits::Float>::sqrt(value.norm_sqr())
+}
+
+/// Return `true` if both parts of a complex number are finite
+fn is_finite<T: Float>(value: &Complex<T>) -> bool {
+    <T as num_traits::Float>::is_finite(value.re) && <T as num_traits::Float>::is_finite(value.im)
+}
+
+/// Sort finite `eigenvalues` by increasing modulus
+fn sort_by_modulus<T: Float>(eigenvalues: &mut [Complex<T>]) {
+    eigenvalues.sort_by(|x, y| modulus(x).partial_cmp(&modulus(y)).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// Return the eigenvalues of `a`, or NaN eigenvalues if it is not finite
+///
+/// The eigenvalue iteration does not converge on a matrix which is not finite, so is not attempted.
+fn eigenvalues<T: Float>(a: &DMatrix<T>) -> DVector<Complex<T>> {
+    if a.iter().all(|value| <T as num_traits::Float>::is_finite(*value)) {
+        a.complex_eigenvalues()
+    } else {
+        DVector::from_element(a.nrows(),Complex::new(T::nan(),T::nan()))
+    }
+}
+
+/// Return the eigenvalues of the submatrix of `a` over the states `states`
+fn subsystem_eigenvalues<T: Float>(a: &DMatrix<T>, states: &[usize]) -> Vec<Complex<T>> {
+    let sub = DMatrix::from_fn(states.len(),states.len(),|row, col| a[(states[row],states[col])]);
+    eigenvalues(&sub).iter().copied().collect()
+}
+
+/// Add every one of `eigenvalues` to `modes` as an unclassified root
+fn push_unclassified<T: Float>(modes: &mut Vec<Mode<T>>, a: &DMatrix<T>, eigenvalues: &[Complex<T>]) {
+    for eigenvalue in eigenvalues {
+        modes.push(Mode {
+            kind: ModeKind::Unclassified,
+            eigenvalue: *eigenvalue,
+            eigenvector: DVector::from_element(a.nrows(),Complex::new(T::nan(),T::nan())),
+            natural_frequency: modulus(eigenvalue),
+            damping_ratio: T::nan(),
+        });
+    }
+}
+
+/// Add the mode with `eigenvalue` to `modes`, skipping the conjugate of an oscillatory pair
+fn push_mode<T: Float>(modes: &mut Vec<Mode<T>>, a: &DMatrix<T>, kind: ModeKind, eigenvalue: Complex<T>) {
+    if eigenvalue.im < T::zero() {
+        return;
+    }
+    let natural_frequency = modulus(&eigenvalue);
+    let damping_ratio = if natural_frequency > T::zero() { -eigenvalue.re / natural_frequency } else { T::zero() };
+    modes.push(Mode {
+        kind,
+        eigenvalue,
+        eigenvector: eigenvector(a,eigenvalue),
+        natural_frequency,
+        damping_ratio,
+    });
+}
+
+/// Return the eigenvector of `a` for `eigenvalue` by shifted inverse iteration
+fn eigenvector<T: Float>(a: &DMatrix<T>, eigenvalue: Complex<T>) -> DVector<Complex<T>> {
+    let n = a.nrows();
+    let offset = <T as num_traits::Float>::sqrt(T::epsilon()) * <T as num_traits::Float>::max(T::one(),modulus(&eigenvalue));
+    let shift = eigenvalue + Complex::new(offset,offset);
+    let shifted = DMatrix::from_fn(n,n,|row, col| {
+        let value = Complex::new(a[(row,col)],T::zero());
+        if row == col { value - shift } else { value }
+    });
+    let lu = shifted.lu();
+    let mut vector = DVector::from_element(n,Complex::new(T::one(),T::zero()));
+    for _ in 0..3 {
+        vector = match lu.solve(&vector) {
+            Some(solution) => solution,
+            None => break,
+        };
+        let largest = vector.iter().copied().fold(Complex::new(T::zero(),T::zero()),|largest, component| if modulus(&component) > modulus(&largest) { component } else { largest });
+        if modulus(&largest) > T::zero() {
+            vector /= largest;
+        }
+    }
+    vector
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::{Trim,TrimCondition,test_aircraft};
+    use crate::linearise::STATES;
+    use approx::assert_relative_eq;
+
+    /// Return a model with oscillatory `(frequency, damping)` blocks on the state pairs `blocks`
+    fn oscillators(blocks: &[(usize,usize,f64,f64)], real_roots: &[(usize,f64)]) -> StateSpace {
+        let mut a = DMatrix::zeros(STATES.len(),STATES.len());
+        for &(x, x_dot, frequency, damping) in blocks {
+            a[(x,x_dot)] = 1.0;
+            a[(x_dot,x)] = -frequency * frequency;
+            a[(x_dot,x_dot)] = -2.0 * damping * frequency;
+        }
+        for &(x, root) in real_roots {
+            a[(x,x)] = root;
+        }
+        StateSpace { a, b: DMatrix::zeros(12,0), c: DMatrix::zeros(15,12), d: DMatrix::zeros(15,0), input_slots: Vec::new() }
+    }
+
+    #[test]
+    fn test_classification() {
+        // theta/q short period, u/w phugoid, v/r Dutch roll, real p and phi roots
+        let model = oscillators(&[(7,10,4.0,0.6),(3,5,0.3,0.05),(4,11,2.0,0.2)],&[(9,-8.0),(6,0.02)]);
+        let report = ModeReport::new(&model);
+        assert_eq!(report.modes.len(),5);
+        assert_eq!(report.eigenvalues.len(),12);
+
+        let short_period = report.mode(ModeKind::ShortPeriod).unwrap();
+        assert_relative_eq!(short_period.natural_frequency,4.0,max_relative=1e-9);
+        assert_relative_eq!(short_period.damping_ratio,0.6,max_relative=1e-9);
+        let phugoid = report.mode(ModeKind::Phugoid).unwrap();
+        assert_relative_eq!(phugoid.natural_frequency,0.3,max_relative=1e-9);
+        assert_relative_eq!(phugoid.period().unwrap(),2.0 * std::f64::consts::PI / (0.3 * (1.0 - 0.05f64.powi(2)).sqrt()),max_relative=1e-9);
+        assert_relative_eq!(report.mode(ModeKind::DutchRoll).unwrap().damping_ratio,0.2,max_relative=1e-9);
+        assert_relative_eq!(report.mode(ModeKind::RollSubsidence).unwrap().eigenvalue.re,-8.0,max_relative=1e-9);
+
+        // An unstable spiral has a doubling time
+        let spiral = report.mode(ModeKind::Spiral).unwrap();
+        assert!(!spiral.is_stable());
+        assert_relative_eq!(spiral.time_to_half_or_double().unwrap(),2f64.ln() / 0.02,max_relative=1e-9);
+        assert!(!report.is_stable());
+        // The spiral mode is entirely in bank angle
+        assert_relative_eq!(spiral.eigenvector[6].norm_sqr().sqrt(),1.0,max_relative=1e-6);
+        assert!(spiral.eigenvector[9].norm_sqr().sqrt() < 1e-6);
+    }
+
+    #[test]
+    fn test_aircraft_modes() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let trim = Trim::new(vec![0.0,0.0,0.0,0.5],&[0,1,2,3]).solve(&mut vehicle,&TrimCondition::level(20.0)).unwrap();
+        let report = ModeReport::new(&StateSpace::linearise(&mut vehicle,&trim.inputs,&[0,1,2,3]));
+
+        let short_period = report.mode(ModeKind::ShortPeriod).unwrap();
+        let phugoid = report.mode(ModeKind::Phugoid).unwrap();
+        assert!(short_period.is_oscillatory() && phugoid.is_oscillatory());
+        assert!(short_period.natural_frequency > 5.0 * phugoid.natural_frequency);
+        assert!(short_period.damping_ratio > phugoid.damping_ratio);
+        assert!(report.mode(ModeKind::DutchRoll).unwrap().is_oscillatory());
+        assert!(report.mode(ModeKind::RollSubsidence).unwrap().is_stable());
+
+        // The short period eigenvector is dominated by pitch rate and heave rather than speed
+        let vector = &short_period.eigenvector;
+        assert!(vector[3].norm_sqr() < vector[5].norm_sqr());
+    }
+
+    #[test]
+    fn test_nan_derivative() {
+        // A NaN heave derivative leaves the longitudinal roots unclassified, and the lateral modes intact
+        let mut model = oscillators(&[(7,10,4.0,0.6),(3,5,0.3,0.05),(4,11,2.0,0.2)],&[(9,-8.0),(6,0.02)]);
+        model.a[(5,3)] = f64::NAN;
+        let report = ModeReport::new(&model);
+        assert!(report.eigenvalues.iter().all(|eigenvalue| eigenvalue.re.is_nan()));
+        assert_eq!(report.modes.iter().filter(|mode| mode.kind == ModeKind::Unclassified).count(),4);
+        assert!(report.mode(ModeKind::ShortPeriod).is_none());
+        assert_relative_eq!(report.mode(ModeKind::DutchRoll).unwrap().damping_ratio,0.2,max_relative=1e-9);
+        assert!(!report.is_stable());
+    }
+}
@@ -0,0 +1,281 @@
+//! Monte Carlo campaigns
+//!
+//! A [MonteCarlo] campaign samples named parameters, such as mass, centre of gravity, wind seed or
+//! sensor biases, from [Distribution]s and runs a user-supplied simulation for each sample across
+//! several threads. The simulation builds its own vehicle from the sampled [Parameters] and returns a
+//! [Termination] reason with any output, which are collected in a [Campaign] for summary statistics.
+//!
+//! Every run draws from its own stream seeded by the campaign seed and the run index, so results do
+//! not depend on the number of threads.
+
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::sync::Mutex;
+
+use crate::random::Rng;
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Probability distribution of a parameter
+#[derive(Copy,Clone,Debug)]
+pub enum Distribution<T: Float = DefaultFloatRepr> {
+    /// Fixed value
+    Constant(T),
+    /// Uniform distribution between `min` and `max`
+    Uniform {
+        /// Lower bound
+        min: T,
+        /// Upper bound
+        max: T,
+    },
+    /// Normal distribution
+    Normal {
+        /// Mean
+        mean: T,
+        /// Standard deviation
+        std_dev: T,
+    },
+}
+
+impl<T: Float> Distribution<T> {
+    /// Draw a sample from the distribution
+    pub fn sample(&self, rng: &mut Rng) -> T {
+        match *self {
+            Distribution::Constant(value) => value,
+            Distribution::Uniform { min, max } => min + (max - min) * constant::<T>(rng.uniform()),
+            Distribution::Normal { mean, std_dev } => rng.gaussian(mean,std_dev),
+        }
+    }
+}
+
+/// Sampled parameters of a single run
+#[derive(Clone,Debug)]
+pub struct Parameters<T: Float = DefaultFloatRepr> {
+    /// Index of the run in the campaign
+    pub run: usize,
+    /// Seed for any further random numbers in the run, such as turbulence
+    pub seed: u64,
+    /// Parameter names and values
+    values: Vec<(String,T)>,
+}
+
+impl<T: Float> Parameters<T> {
+    /// Return the value of the parameter called `name`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such parameter, as this is almost always a misspelled name.
+    pub fn get(&self, name: &str) -> T {
+        match self.values.iter().find(|(parameter, _)| parameter == name) {
+            Some((_, value)) => *value,
+            None => panic!("No parameter called '{}'",name),
+        }
+    }
+
+    /// Return the parameter names and values
+    pub fn values(&self) -> impl Iterator<Item=(&str,T)> {
+        self.values.iter().map(|(name, value)| (name.as_str(),*value))
+    }
+}
+
+/// Reason a run ended
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Termination {
+    /// The run reached the end of its duration
+    Completed,
+    /// The run was stopped by a condition, such as ground contact
+    Stopped(String),
+    /// The run failed, for example because the state became non-finite
+    Failed(String),
+}
+
+/// Result of a single run
+#[derive(Clone,Debug)]
+pub struct RunResult<R, T: Float = DefaultFloatRepr> {
+    /// Sampled parameters
+    pub parameters: Parameters<T>,
+    /// Reason the run ended
+    pub termination: Termination,
+    /// Output returned by the simulation
+    pub output: R,
+}
+
+/// Summary statistics of a set of values
+#[derive(Copy,Clone,Debug)]
+pub struct Statistics<T: Float = DefaultFloatRepr> {
+    /// Number of values
+    pub count: usize,
+    /// Mean
+    pub mean: T,
+    /// Sample standard deviation
+    pub std_dev: T,
+    /// Smallest value
+    pub min: T,
+    /// Largest value
+    pub max: T,
+}
+
+impl<T: Float> Statistics<T> {
+    /// Return the statistics of `values`, or `None` if there are none
+    pub fn new<V: IntoIterator<Item=T>>(values: V) -> Option<Self> {
+        let values: Vec<T> = values.into_iter().collect();
+        if values.is_empty() {
+            return None;
+        }
+        let count = constant::<T>(values.len() as f64);
+        let mean = values.iter().fold(T::zero(),|sum, &value| sum + value) / count;
+        let squares = values.iter().fold(T::zero(),|sum, &value| sum + (value - mean) * (value - mean));
+        let std_dev = if values.len() > 1 { <T as num_traits::Float>::sqrt(squares / (count - T::one())) } else { T::zero() };
+        Some(Statistics {
+            count: values.len(),
+            mean,
+            std_dev,
+            min: values.iter().copied().fold(T::infinity(),<T as num_traits::Float>::min),
+            max: values.iter().copied().fold(T::neg_infinity(),<T as num_traits::Float>::max),
+        })
+    }
+}
+
+/// Results of a Monte Carlo campaign
+#[derive(Clone,Debug)]
+pub struct Campaign<R, T: Float = DefaultFloatRepr> {
+    /// Results of every run, in run order
+    pub runs: Vec<RunResult<R,T>>,
+}
+
+impl<R, T: Float> Campaign<R,T> {
+    /// Return the number of runs which ended with `termination`
+    pub fn count(&self, termination: &Termination) -> usize {
+        self.runs.iter().filter(|run| run.termination == *termination).count()
+    }
+
+    /// Return the statistics of `metric` over the runs which completed
+    pub fn statistics<F: Fn(&RunResult<R,T>) -> T>(&self, metric: F) -> Option<Statistics<T>> {
+        Statistics::new(self.runs.iter().filter(|run| run.termination == Termination::Completed).map(metric))
+    }
+}
+
+/// Monte Carlo campaign runner
+#[derive(Clone,Debug)]
+pub struct MonteCarlo<T: Float = DefaultFloatRepr> {
+    /// Number of runs
+    runs: usize,
+    /// Campaign seed
+    seed: u64,
+    /// Number of worker threads
+    threads: usize,
+    /// Parameter names and distributions
+    parameters: Vec<(String,Distribution<T>)>,
+}
+
+impl<T: Float> MonteCarlo<T> {
+    /// Create a new [MonteCarlo] campaign of `runs` with `seed`, using every available thread
+    pub fn new(runs: usize, seed: u64) -> Self {
+        MonteCarlo {
+            runs,
+            seed,
+            threads: std::thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Sample the parameter called `name` from `distribution`
+    pub fn with_parameter(mut self, name: &str, distribution: Distribution<T>) -> Self {
+        self.parameters.push((name.to_string(),distribution));
+        self
+    }
+
+    /// Set the number of worker threads
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Return the sampled parameters of `run`
+    pub fn parameters(&self, run: usize) -> Parameters<T> {
+        let seed = Rng::derive_seed(self.seed,run as u64);
+        let mut rng = Rng::new(seed);
+        let values = self.parameters.iter().map(|(name, distribution)| (name.clone(),distribution.sample(&mut rng))).collect();
+        Parameters {
+            run,
+            seed: rng.next_u64(),
+            values,
+        }
+    }
+
+    /// Run `simulate` for every sample and collect the results
+    ///
+    /// The simulation is called with the sampled parameters of each run and returns the reason the run
+    /// ended with its output. Runs are shared between the worker threads, or run on the calling thread
+    /// if there is only one, as on targets without threads such as `wasm32-unknown-unknown`.
+    pub fn run<R, F>(&self, simulate: F) -> Campaign<R,T> where R: Send, F: Fn(&Parameters<T>) -> (Termination,R) + Sync {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(self.runs));
+        let worker = || loop {
+            let run = next.fetch_add(1,Ordering::Relaxed);
+            if run >= self.runs {
+                break;
+            }
+            let parameters = self.parameters(run);
+            let (termination, output) = simulate(&parameters);
+            results.lock().unwrap().push(RunResult { parameters, termination, output });
+        };
+        match self.threads.min(self.runs.max(1)) {
+            1 => worker(),
+            threads => std::thread::scope(|scope| {
+                for _ in 0..threads {
+                    scope.spawn(worker);
+                }
+            }),
+        }
+        let mut runs = results.into_inner().unwrap();
+        runs.sort_by_key(|result| result.parameters.run);
+        Campaign { runs }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use crate::types::StateView;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_distributions() {
+        let campaign = MonteCarlo::new(2000,1)
+            .with_parameter("mass",Distribution::Normal { mean: 3.0, std_dev: 0.1 })
+            .with_parameter("cg",Distribution::Uniform { min: -0.02, max: 0.02 })
+            .with_parameter("span",Distribution::Constant(2.0));
+        let results = campaign.run(|parameters| (Termination::Completed,parameters.get("cg")));
+
+        let mass = results.statistics(|run| run.parameters.get("mass")).unwrap();
+        assert_relative_eq!(mass.mean,3.0,epsilon=0.01);
+        assert_relative_eq!(mass.std_dev,0.1,epsilon=0.01);
+        let cg = results.statistics(|run| run.output).unwrap();
+        assert!(cg.min >= -0.02 && cg.max < 0.02);
+        assert_eq!(results.statistics(|run| run.parameters.get("span")).unwrap().std_dev,0.0);
+    }
+
+    #[test]
+    fn test_thread_independence() {
+        let campaign = MonteCarlo::new(8,99).with_parameter("throttle",Distribution::Uniform { min: 0.0, max: 1.0 });
+        let simulate = |parameters: &Parameters| {
+            let mut vehicle = test_aircraft::vehicle(10.0);
+            let inputs = vec![0.0,0.0,0.0,parameters.get("throttle")];
+            for _ in 0..500 {
+                vehicle.step(0.01,&inputs);
+                if vehicle.position().z > 0.0 {
+                    return (Termination::Stopped("ground contact".to_string()),vehicle.statevector());
+                }
+            }
+            (Termination::Completed,vehicle.statevector())
+        };
+        let single = campaign.clone().with_threads(1).run(simulate);
+        let parallel = campaign.with_threads(4).run(simulate);
+        assert_eq!(single.runs.len(),8);
+        for (a, b) in single.runs.iter().zip(parallel.runs.iter()) {
+            assert_eq!(a.output,b.output);
+            assert_eq!(a.termination,b.termination);
+        }
+        assert_eq!(single.count(&Termination::Completed) + single.count(&Termination::Stopped("ground contact".to_string())),8);
+    }
+}
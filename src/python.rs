@@ -0,0 +1,597 @@
+//! Python bindings
+//!
+//! Enabled by the `python` feature, this module defines an `aerso` Python extension module exposing
+//! [Body], [AeroBody], [AffectedBody], the built-in wind and density models and the [Recorder], with
+//! statevectors, forces and histories exchanged as NumPy arrays. Statevectors have the usual layout,
+//! `[x,y,z,u,v,w,qx,qy,qz,qw,p,q,r]`.
+//!
+//! Effectors are Python objects with a `get_effect(airstate, rates, inputs)` method returning the
+//! body-frame force and torque as two 3-element sequences, and optionally a
+//! `step(airstate, rates, inputs, delta_t)` method to advance any internal state.
+//!
+//! `FlightEnv` steps a vehicle with the Gymnasium `reset`/`step` API, and `python/aerso_gym.py` wraps
+//! it as a `gymnasium.Env` with Box observation and action spaces for reinforcement learning.
+//!
+//! The extension is built as a `cdylib`, for example with
+//! `cargo rustc --release --features python,pyo3/extension-module --crate-type cdylib`, and the library
+//! renamed to `aerso.so` (`aerso.pyd` on Windows), or with `maturin` configured with the same features.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use numpy::{PyArray1,PyArray2,PyReadonlyArray1,PyReadonlyArray2};
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyDict;
+
+use crate::{Body,AeroBody,AffectedBody,AeroEffect,WindModel,DensityModel,AirState};
+use crate::aero::{StandardDensity,StandardAtmosphere};
+use crate::recorder::Recorder;
+use crate::types::{Vector3,Matrix3,UnitQuaternion,StateVector,StateView,Force,Torque};
+use crate::wind_models::{ConstantWind,LogWind,PowerWind};
+
+/// Return the 3-vector held in `array`
+fn vector3(array: PyReadonlyArray1<f64>) -> PyResult<Vector3<f64>> {
+    let array = array.as_array();
+    if array.len() != 3 {
+        return Err(PyValueError::new_err(format!("Expected 3 elements, found {}",array.len())));
+    }
+    Ok(Vector3::new(array[0],array[1],array[2]))
+}
+
+/// Return the statevector held in `array`
+fn statevector(array: PyReadonlyArray1<f64>) -> PyResult<StateVector<f64>> {
+    let array = array.as_array();
+    if array.len() != 13 {
+        return Err(PyValueError::new_err(format!("Expected 13 statevector elements, found {}",array.len())));
+    }
+    Ok(StateVector::from_iterator(array.iter().copied()))
+}
+
+/// Return `state` as a NumPy array
+fn state_array<'py>(py: Python<'py>, state: &StateVector<f64>) -> Bound<'py,PyArray1<f64>> {
+    PyArray1::from_slice(py,state.as_slice())
+}
+
+/// Wind model selected from Python
+#[derive(Copy,Clone)]
+enum Wind {
+    /// [ConstantWind]
+    Constant(ConstantWind<f64>),
+    /// [LogWind]
+    Log(LogWind<f64>),
+    /// [PowerWind]
+    Power(PowerWind<f64>),
+}
+
+impl WindModel<f64> for Wind {
+    fn get_wind(&self, position: &Vector3<f64>) -> Vector3<f64> {
+        match self {
+            Wind::Constant(model) => model.get_wind(position),
+            Wind::Log(model) => model.get_wind(position),
+            Wind::Power(model) => model.get_wind(position),
+        }
+    }
+
+    fn step(&mut self, delta_t: f64) {
+        match self {
+            Wind::Constant(model) => model.step(delta_t),
+            Wind::Log(model) => model.step(delta_t),
+            Wind::Power(model) => model.step(delta_t),
+        }
+    }
+}
+
+/// Density model selected from Python
+#[derive(Copy,Clone)]
+enum Density {
+    /// [StandardDensity]
+    Standard,
+    /// [StandardAtmosphere]
+    Atmosphere(StandardAtmosphere<f64>),
+}
+
+impl DensityModel<f64> for Density {
+    fn get_density(&self, position: &Vector3<f64>) -> f64 {
+        match self {
+            Density::Standard => StandardDensity.get_density(position),
+            Density::Atmosphere(model) => model.get_density(position),
+        }
+    }
+
+    fn get_temperature(&self, position: &Vector3<f64>) -> f64 {
+        match self {
+            Density::Standard => DensityModel::<f64>::get_temperature(&StandardDensity,position),
+            Density::Atmosphere(model) => model.get_temperature(position),
+        }
+    }
+
+    fn get_pressure(&self, position: &Vector3<f64>) -> f64 {
+        match self {
+            Density::Standard => DensityModel::<f64>::get_pressure(&StandardDensity,position),
+            Density::Atmosphere(model) => model.get_pressure(position),
+        }
+    }
+}
+
+/// Python `WindModel`
+#[pyclass(name = "WindModel", module = "aerso")]
+#[derive(Copy,Clone)]
+struct PyWindModel(Wind);
+
+#[pymethods]
+impl PyWindModel {
+    /// Return a constant North-East-Down `wind` (m/s)
+    #[staticmethod]
+    fn constant(wind: PyReadonlyArray1<f64>) -> PyResult<Self> {
+        Ok(PyWindModel(Wind::Constant(ConstantWind::new(vector3(wind)?))))
+    }
+
+    /// Return a log wind profile
+    #[staticmethod]
+    fn log(d: f64, z0: f64, u_star: f64, bearing: f64) -> Self {
+        PyWindModel(Wind::Log(LogWind::new(d,z0,u_star,bearing)))
+    }
+
+    /// Return a power-law wind profile, with the typical exponent if `alpha` is not given
+    #[staticmethod]
+    #[pyo3(signature = (u_r, z_r, bearing, alpha=None))]
+    fn power(u_r: f64, z_r: f64, bearing: f64, alpha: Option<f64>) -> Self {
+        PyWindModel(Wind::Power(match alpha {
+            Some(alpha) => PowerWind::new_with_alpha(u_r,z_r,bearing,alpha),
+            None => PowerWind::new(u_r,z_r,bearing),
+        }))
+    }
+
+    /// Return the North-East-Down wind at `position` (m/s)
+    fn get_wind<'py>(&self, py: Python<'py>, position: PyReadonlyArray1<f64>) -> PyResult<Bound<'py,PyArray1<f64>>> {
+        Ok(PyArray1::from_slice(py,self.0.get_wind(&vector3(position)?).as_slice()))
+    }
+}
+
+/// Python `DensityModel`
+#[pyclass(name = "DensityModel", module = "aerso")]
+#[derive(Copy,Clone)]
+struct PyDensityModel(Density);
+
+#[pymethods]
+impl PyDensityModel {
+    /// Return the constant ISA sea-level density
+    #[staticmethod]
+    fn standard() -> Self {
+        PyDensityModel(Density::Standard)
+    }
+
+    /// Return the ISA standard atmosphere
+    #[staticmethod]
+    #[pyo3(signature = (sea_level_pressure=101_325.0, temperature_offset=0.0))]
+    fn atmosphere(sea_level_pressure: f64, temperature_offset: f64) -> Self {
+        PyDensityModel(Density::Atmosphere(StandardAtmosphere::new().with_sea_level_pressure(sea_level_pressure).with_temperature_offset(temperature_offset)))
+    }
+
+    /// Return the density at `position` (kg/m³)
+    fn get_density(&self, position: PyReadonlyArray1<f64>) -> PyResult<f64> {
+        Ok(self.0.get_density(&vector3(position)?))
+    }
+}
+
+/// Python `AirState`
+#[pyclass(name = "AirState", module = "aerso", get_all)]
+#[derive(Copy,Clone)]
+struct PyAirState {
+    /// Angle of attack (rad)
+    alpha: f64,
+    /// Angle of sideslip (rad)
+    beta: f64,
+    /// Airspeed (m/s)
+    airspeed: f64,
+    /// Dynamic pressure (Pa)
+    q: f64,
+    /// Mach number
+    mach: f64,
+    /// Air density (kg/m³)
+    density: f64,
+}
+
+impl From<AirState<f64>> for PyAirState {
+    fn from(airstate: AirState<f64>) -> Self {
+        PyAirState {
+            alpha: airstate.alpha,
+            beta: airstate.beta,
+            airspeed: airstate.airspeed,
+            q: airstate.q,
+            mach: airstate.mach,
+            density: airstate.density,
+        }
+    }
+}
+
+/// Python `Body`
+#[pyclass(name = "Body", module = "aerso")]
+#[derive(Copy,Clone)]
+struct PyBody(Body<f64>);
+
+#[pymethods]
+impl PyBody {
+    /// Create a body with `mass` (kg), `inertia` (3×3), `position` (m), body-frame `velocity` (m/s),
+    /// `attitude` quaternion `[qx,qy,qz,qw]` and body `rates` (rad/s)
+    #[new]
+    fn new(mass: f64, inertia: PyReadonlyArray2<f64>, position: PyReadonlyArray1<f64>, velocity: PyReadonlyArray1<f64>, attitude: PyReadonlyArray1<f64>, rates: PyReadonlyArray1<f64>) -> PyResult<Self> {
+        let inertia = inertia.as_array();
+        if inertia.shape() != [3,3] {
+            return Err(PyValueError::new_err("Inertia must be 3×3"));
+        }
+        let attitude = attitude.as_array();
+        if attitude.len() != 4 {
+            return Err(PyValueError::new_err("Attitude must be a quaternion [qx,qy,qz,qw]"));
+        }
+        let inertia = Matrix3::from_fn(|row, col| inertia[[row,col]]);
+        let attitude = UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(attitude[3],attitude[0],attitude[1],attitude[2]));
+        Ok(PyBody(Body::new(mass,inertia,vector3(position)?,vector3(velocity)?,attitude,vector3(rates)?)))
+    }
+
+    /// Return the statevector
+    fn statevector<'py>(&self, py: Python<'py>) -> Bound<'py,PyArray1<f64>> {
+        state_array(py,&self.0.statevector())
+    }
+
+    /// Set the statevector
+    fn set_state(&mut self, state: PyReadonlyArray1<f64>) -> PyResult<()> {
+        self.0.set_state(statevector(state)?);
+        Ok(())
+    }
+
+    /// Mass (kg)
+    #[getter]
+    fn mass(&self) -> f64 {
+        self.0.mass()
+    }
+
+    /// Propagate by `delta_t` (s) with a body-frame `force` (N) and `torque` (N·m)
+    fn step(&mut self, force: PyReadonlyArray1<f64>, torque: PyReadonlyArray1<f64>, delta_t: f64) -> PyResult<()> {
+        self.0.step(&[Force::body_vec(vector3(force)?)],&[Torque::body_vec(vector3(torque)?)],delta_t);
+        Ok(())
+    }
+}
+
+/// Python `AeroBody`
+#[pyclass(name = "AeroBody", module = "aerso")]
+#[derive(Copy,Clone)]
+struct PyAeroBody(AeroBody<f64,Wind,Density>);
+
+#[pymethods]
+impl PyAeroBody {
+    /// Create an aero body from `body`, with no wind and sea-level density by default
+    #[new]
+    #[pyo3(signature = (body, wind=None, density=None))]
+    fn new(body: PyBody, wind: Option<PyWindModel>, density: Option<PyDensityModel>) -> Self {
+        let wind = wind.map_or(Wind::Constant(ConstantWind::new(Vector3::zeros())),|wind| wind.0);
+        let density = density.map_or(Density::Standard,|density| density.0);
+        PyAeroBody(AeroBody::with_density_model(body.0,wind,density))
+    }
+
+    /// Return the statevector
+    fn statevector<'py>(&self, py: Python<'py>) -> Bound<'py,PyArray1<f64>> {
+        state_array(py,&self.0.statevector())
+    }
+
+    /// Return the airstate
+    fn airstate(&self) -> PyAirState {
+        self.0.get_airstate().into()
+    }
+
+    /// Propagate by `delta_t` (s) with a body-frame `force` (N) and `torque` (N·m)
+    fn step(&mut self, force: PyReadonlyArray1<f64>, torque: PyReadonlyArray1<f64>, delta_t: f64) -> PyResult<()> {
+        self.0.step(&[Force::body_vec(vector3(force)?)],&[Torque::body_vec(vector3(torque)?)],delta_t);
+        Ok(())
+    }
+}
+
+/// First error raised by a Python effector since it was last checked
+type EffectorError = Rc<RefCell<Option<PyErr>>>;
+
+/// [AeroEffect] calling a Python object
+///
+/// Errors raised by the object are stored, and the effect is zero, until the error is raised by the
+/// owning [PyAffectedBody].
+struct PyEffector {
+    /// Python effector
+    object: PyObject,
+    /// Shared error slot of the owning body
+    error: EffectorError,
+}
+
+impl PyEffector {
+    /// Store `error` if no error is already stored
+    fn store(&self, error: PyErr) {
+        self.error.borrow_mut().get_or_insert(error);
+    }
+}
+
+impl AeroEffect<Vec<f64>,f64> for PyEffector {
+    fn get_effect(&self, airstate: AirState<f64>, rates: Vector3<f64>, inputstate: &Vec<f64>) -> (Force<f64>,Torque<f64>) {
+        Python::with_gil(|py| {
+            let result = self.object.call_method1(py,"get_effect",(PyAirState::from(airstate),PyArray1::from_slice(py,rates.as_slice()),PyArray1::from_slice(py,inputstate)))
+                .and_then(|effect| effect.extract::<([f64;3],[f64;3])>(py));
+            match result {
+                Ok((force, torque)) => (Force::body_vec(Vector3::from(force)),Torque::body_vec(Vector3::from(torque))),
+                Err(error) => {
+                    self.store(error);
+                    (Force::body_vec(Vector3::zeros()),Torque::body_vec(Vector3::zeros()))
+                },
+            }
+        })
+    }
+
+    fn step(&mut self, airstate: AirState<f64>, rates: Vector3<f64>, inputstate: &Vec<f64>, delta_t: f64) {
+        Python::with_gil(|py| {
+            let object = self.object.bind(py);
+            if object.hasattr("step").unwrap_or(false) {
+                if let Err(error) = object.call_method1("step",(PyAirState::from(airstate),PyArray1::from_slice(py,rates.as_slice()),PyArray1::from_slice(py,inputstate),delta_t)) {
+                    self.store(error);
+                }
+            }
+        })
+    }
+}
+
+/// Python `AffectedBody`
+#[pyclass(name = "AffectedBody", module = "aerso", unsendable)]
+struct PyAffectedBody {
+    /// Wrapped vehicle
+    vehicle: AffectedBody<Vec<f64>,f64,Wind,Density>,
+    /// Error slot shared with the effectors
+    error: EffectorError,
+}
+
+impl PyAffectedBody {
+    /// Raise any error stored by an effector
+    fn check(&self) -> PyResult<()> {
+        match self.error.borrow_mut().take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[pymethods]
+impl PyAffectedBody {
+    /// Create an affected body from `body` and a list of Python `effectors`
+    #[new]
+    #[pyo3(signature = (body, effectors=Vec::new()))]
+    fn new(body: PyAeroBody, effectors: Vec<PyObject>) -> Self {
+        let error = EffectorError::default();
+        let effectors = effectors.into_iter()
+            .map(|object| Box::new(PyEffector { object, error: error.clone() }) as Box<dyn AeroEffect<Vec<f64>,f64>>)
+            .collect();
+        PyAffectedBody { vehicle: AffectedBody::new(body.0,effectors), error }
+    }
+
+    /// Return the statevector
+    fn statevector<'py>(&self, py: Python<'py>) -> Bound<'py,PyArray1<f64>> {
+        state_array(py,&self.vehicle.statevector())
+    }
+
+    /// Set the statevector
+    fn set_state(&mut self, state: PyReadonlyArray1<f64>) -> PyResult<()> {
+        self.vehicle.set_state(statevector(state)?);
+        Ok(())
+    }
+
+    /// Return the airstate
+    fn airstate(&self) -> PyAirState {
+        self.vehicle.get_airstate().into()
+    }
+
+    /// Return the body-frame acceleration over the previous step (m/s²)
+    fn acceleration<'py>(&self, py: Python<'py>) -> Bound<'py,PyArray1<f64>> {
+        PyArray1::from_slice(py,self.vehicle.acceleration().as_slice())
+    }
+
+    /// Enable the effector called `name`, returning whether it exists
+    fn enable(&mut self, name: &str) -> bool {
+        self.vehicle.enable(name)
+    }
+
+    /// Disable the effector called `name`, returning whether it exists
+    fn disable(&mut self, name: &str) -> bool {
+        self.vehicle.disable(name)
+    }
+
+    /// Propagate by `delta_t` (s) with `inputs`
+    fn step(&mut self, delta_t: f64, inputs: Vec<f64>) -> PyResult<()> {
+        self.vehicle.step(delta_t,&inputs);
+        self.check()
+    }
+
+    /// Run for `duration` (s) with timestep `delta_t` (s), calling `controller(state, airstate, time)`
+    /// for the inputs of each step
+    fn run(&mut self, py: Python<'_>, duration: f64, delta_t: f64, controller: PyObject) -> PyResult<PyRecorder> {
+        let mut controller_error = None;
+        let recorder = self.vehicle.run(duration,delta_t,|state, airstate, time| {
+            if controller_error.is_some() {
+                return Vec::new();
+            }
+            let inputs = controller.call1(py,(state_array(py,&state.statevector()),PyAirState::from(airstate),time))
+                .and_then(|inputs| inputs.extract::<Vec<f64>>(py));
+            inputs.unwrap_or_else(|error| {
+                controller_error = Some(error);
+                Vec::new()
+            })
+        });
+        match controller_error {
+            Some(error) => Err(error),
+            None => self.check().map(|_| PyRecorder(recorder)),
+        }
+    }
+}
+
+/// Python `Recorder`
+#[pyclass(name = "Recorder", module = "aerso")]
+#[derive(Clone)]
+struct PyRecorder(Recorder<f64>);
+
+#[pymethods]
+impl PyRecorder {
+    /// Return the recorded times (s)
+    fn times<'py>(&self, py: Python<'py>) -> Bound<'py,PyArray1<f64>> {
+        PyArray1::from_vec(py,self.0.times().collect())
+    }
+
+    /// Return the recorded statevectors, one row per sample
+    fn states<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py,PyArray2<f64>>> {
+        let rows: Vec<Vec<f64>> = self.0.samples().iter().map(|sample| sample.state.as_slice().to_vec()).collect();
+        PyArray2::from_vec2(py,&rows).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Return the recorded airstates as rows of `[alpha,beta,airspeed,q,mach,density]`
+    fn airstates<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py,PyArray2<f64>>> {
+        let rows: Vec<Vec<f64>> = self.0.samples().iter().map(|sample| {
+            let airstate = &sample.airstate;
+            vec![airstate.alpha,airstate.beta,airstate.airspeed,airstate.q,airstate.mach,airstate.density]
+        }).collect();
+        PyArray2::from_vec2(py,&rows).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Return the history as CSV
+    fn to_csv(&self) -> String {
+        self.0.to_csv()
+    }
+
+    /// Write the history as CSV to `path`
+    fn write_csv(&self, path: &str) -> PyResult<()> {
+        self.0.write_csv(path).map_err(|e| e.into())
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Python `FlightEnv`, a reinforcement-learning environment following the Gymnasium step API
+///
+/// Each episode starts from a vehicle returned by `make_vehicle()`. The observation is
+/// `observe(vehicle)` if given, or the statevector followed by `[alpha,beta,airspeed,q,mach,density]`.
+/// The reward is `reward(observation, action)` if given, or zero, and an episode terminates when
+/// `terminate(observation)` returns true, or by default when the vehicle is below zero altitude. Episodes
+/// are truncated after `max_steps` steps.
+#[pyclass(name = "FlightEnv", module = "aerso", unsendable)]
+struct PyFlightEnv {
+    /// Factory of the vehicle at the start of each episode
+    make_vehicle: PyObject,
+    /// Number of inputstate elements in each action
+    action_size: usize,
+    /// Timestep of each step (s)
+    delta_t: f64,
+    /// Steps after which an episode is truncated
+    max_steps: usize,
+    /// Observation hook
+    observe: Option<PyObject>,
+    /// Reward hook
+    reward: Option<PyObject>,
+    /// Termination hook
+    terminate: Option<PyObject>,
+    /// Vehicle of the current episode
+    vehicle: Option<Py<PyAffectedBody>>,
+    /// Steps taken in the current episode
+    steps: usize,
+}
+
+impl PyFlightEnv {
+    /// Return the observation of the current vehicle
+    fn observation<'py>(&self, py: Python<'py>, vehicle: &Py<PyAffectedBody>) -> PyResult<Bound<'py,PyArray1<f64>>> {
+        let observation = match &self.observe {
+            Some(observe) => observe.call1(py,(vehicle.clone_ref(py),))?.extract::<Vec<f64>>(py)?,
+            None => {
+                let vehicle = vehicle.borrow(py);
+                let airstate = vehicle.vehicle.get_airstate();
+                vehicle.vehicle.statevector().iter().copied()
+                    .chain([airstate.alpha,airstate.beta,airstate.airspeed,airstate.q,airstate.mach,airstate.density])
+                    .collect()
+            },
+        };
+        Ok(PyArray1::from_vec(py,observation))
+    }
+
+    /// Return the simulation time of the current episode (s)
+    fn time(&self) -> f64 {
+        self.steps as f64 * self.delta_t
+    }
+}
+
+#[pymethods]
+impl PyFlightEnv {
+    #[new]
+    #[pyo3(signature = (make_vehicle, action_size, delta_t=0.01, max_steps=1000, observe=None, reward=None, terminate=None))]
+    fn new(make_vehicle: PyObject, action_size: usize, delta_t: f64, max_steps: usize, observe: Option<PyObject>, reward: Option<PyObject>, terminate: Option<PyObject>) -> PyResult<Self> {
+        if delta_t <= 0.0 {
+            return Err(PyValueError::new_err("Timestep must be positive"));
+        }
+        Ok(PyFlightEnv { make_vehicle, action_size, delta_t, max_steps, observe, reward, terminate, vehicle: None, steps: 0 })
+    }
+
+    /// Start a new episode, returning the observation and an info dict
+    ///
+    /// The `seed` and `options` are accepted for Gymnasium compatibility, and any randomisation of the
+    /// initial state is left to `make_vehicle`.
+    #[pyo3(signature = (seed=None, options=None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>, options: Option<PyObject>) -> PyResult<(Bound<'py,PyArray1<f64>>,Bound<'py,PyDict>)> {
+        let _ = (seed,options);
+        let vehicle = self.make_vehicle.call0(py)?.extract::<Py<PyAffectedBody>>(py)?;
+        self.steps = 0;
+        let observation = self.observation(py,&vehicle)?;
+        self.vehicle = Some(vehicle);
+        let info = PyDict::new(py);
+        info.set_item("time",self.time())?;
+        Ok((observation,info))
+    }
+
+    /// Step the current episode with `action`, returning the observation, reward, whether the episode
+    /// terminated or was truncated, and an info dict
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(&mut self, py: Python<'py>, action: Vec<f64>) -> PyResult<(Bound<'py,PyArray1<f64>>,f64,bool,bool,Bound<'py,PyDict>)> {
+        if action.len() != self.action_size {
+            return Err(PyValueError::new_err(format!("Expected {} actions, found {}",self.action_size,action.len())));
+        }
+        let vehicle = match &self.vehicle {
+            Some(vehicle) => vehicle.clone_ref(py),
+            None => return Err(PyValueError::new_err("Environment must be reset before stepping")),
+        };
+        vehicle.borrow_mut(py).step(self.delta_t,action.clone())?;
+        self.steps += 1;
+
+        let observation = self.observation(py,&vehicle)?;
+        let reward = match &self.reward {
+            Some(reward) => reward.call1(py,(observation.clone(),PyArray1::from_vec(py,action)))?.extract::<f64>(py)?,
+            None => 0.0,
+        };
+        let terminated = match &self.terminate {
+            Some(terminate) => terminate.call1(py,(observation.clone(),))?.extract::<bool>(py)?,
+            None => vehicle.borrow(py).vehicle.position()[2] > 0.0,
+        };
+        let truncated = self.steps >= self.max_steps;
+        let info = PyDict::new(py);
+        info.set_item("time",self.time())?;
+        Ok((observation,reward,terminated,truncated,info))
+    }
+
+    /// Vehicle of the current episode
+    #[getter]
+    fn vehicle(&self, py: Python<'_>) -> Option<Py<PyAffectedBody>> {
+        self.vehicle.as_ref().map(|vehicle| vehicle.clone_ref(py))
+    }
+}
+
+/// Python extension module
+#[pymodule]
+fn aerso(module: &Bound<'_,PyModule>) -> PyResult<()> {
+    module.add_class::<PyWindModel>()?;
+    module.add_class::<PyDensityModel>()?;
+    module.add_class::<PyAirState>()?;
+    module.add_class::<PyBody>()?;
+    module.add_class::<PyAeroBody>()?;
+    module.add_class::<PyAffectedBody>()?;
+    module.add_class::<PyRecorder>()?;
+    module.add_class::<PyFlightEnv>()?;
+    Ok(())
+}
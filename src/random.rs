@@ -0,0 +1,136 @@
+//! Seedable random numbers
+//!
+//! [Rng] is a small, fast and fully deterministic generator (xoshiro256\*\*, seeded with SplitMix64),
+//! so stochastic models such as turbulence and sensor noise give identical results for identical seeds
+//! on every platform.
+
+use crate::types::{Float,constant};
+
+/// Advance a SplitMix64 `state` and return the next output
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Deterministic pseudo-random number generator
+#[derive(Clone,Debug)]
+pub struct Rng {
+    /// Generator state
+    state: [u64; 4],
+    /// Second normal deviate from the previous Box-Muller transform
+    spare_normal: Option<f64>,
+}
+
+impl Rng {
+    /// Number of words returned by [Rng::state_words]
+    pub const STATE_WORDS: usize = 6;
+
+    /// Create a new [Rng] from `seed`
+    pub fn new(seed: u64) -> Self {
+        let mut mix = seed;
+        Rng {
+            state: [split_mix(&mut mix),split_mix(&mut mix),split_mix(&mut mix),split_mix(&mut mix)],
+            spare_normal: None,
+        }
+    }
+
+    /// Return a seed for an independent stream, such as the `index`th run of a campaign
+    ///
+    /// The derived seed depends only on `seed` and `index`, so streams can be created in any order.
+    pub fn derive_seed(seed: u64, index: u64) -> u64 {
+        let mut mix = seed ^ split_mix(&mut index.wrapping_add(0x5851_F42D_4C95_7F2D));
+        split_mix(&mut mix)
+    }
+
+    /// Return the next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Return a uniformly-distributed value in `[0, 1)`
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Return a normally-distributed value with zero mean and unit standard deviation
+    pub fn normal(&mut self) -> f64 {
+        if let Some(spare) = self.spare_normal.take() {
+            return spare;
+        }
+        // Box-Muller transform, avoiding ln(0)
+        let radius = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+        let angle = 2.0 * std::f64::consts::PI * self.uniform();
+        self.spare_normal = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+
+    /// Return the generator state as words, so it can be checkpointed
+    pub fn state_words(&self) -> Vec<u64> {
+        let mut words = self.state.to_vec();
+        match self.spare_normal {
+            Some(spare) => words.extend([1,spare.to_bits()]),
+            None => words.extend([0,0]),
+        }
+        words
+    }
+
+    /// Create an [Rng] continuing from the words returned by [Rng::state_words], or `None` if `words` is not
+    /// a generator state
+    pub fn from_state_words(words: &[u64]) -> Option<Self> {
+        match words {
+            &[a,b,c,d,flag,spare] if flag <= 1 => Some(Rng {
+                state: [a,b,c,d],
+                spare_normal: if flag == 1 { Some(f64::from_bits(spare)) } else { None },
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return a normally-distributed value with `mean` and `std_dev`
+    pub fn gaussian<T: Float>(&mut self, mean: T, std_dev: T) -> T {
+        mean + std_dev * constant::<T>(self.normal())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(first,(0..5).map(|_| b.next_u64()).collect::<Vec<u64>>());
+        assert_ne!(first[0],Rng::new(43).next_u64());
+        assert_ne!(Rng::derive_seed(42,0),Rng::derive_seed(42,1));
+    }
+
+    #[test]
+    fn test_moments() {
+        let mut rng = Rng::new(7);
+        let n = 100_000;
+        let uniform: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+        assert!(uniform.iter().all(|&x| (0.0..1.0).contains(&x)));
+        let mean = uniform.iter().sum::<f64>() / n as f64;
+        assert!((mean - 0.5).abs() < 0.01);
+
+        let normal: Vec<f64> = (0..n).map(|_| rng.normal()).collect();
+        let mean = normal.iter().sum::<f64>() / n as f64;
+        let variance = normal.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!(mean.abs() < 0.02);
+        assert!((variance - 1.0).abs() < 0.02);
+    }
+}
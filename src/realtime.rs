@@ -0,0 +1,163 @@
+//! Real-time pacing
+//!
+//! A [RealTimeRunner] runs an [AffectedBody] in closed loop like [AffectedBody::run], but holds each
+//! step until the wall clock reaches its simulation time, scaled by a speed-up factor, for
+//! pilot-in-the-loop and hardware-in-the-loop use. Pacing is soft real-time: steps which finish late
+//! are reported as [Overrun]s, and the following steps run without waiting until the simulation has
+//! caught up with the wall clock.
+
+use std::time::{Duration,Instant};
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel};
+use crate::recorder::Recorder;
+use crate::types::{StateView,Float,DefaultFloatRepr,constant};
+
+/// Step which finished after its wall-clock deadline
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Overrun<T: Float = DefaultFloatRepr> {
+    /// Index of the step
+    pub step: usize,
+    /// Simulation time at the end of the step (s)
+    pub time: T,
+    /// Time by which the step missed its deadline
+    pub lateness: Duration,
+}
+
+/// Timing summary of a [RealTimeRunner] run
+#[derive(Clone,Debug)]
+pub struct TimingReport<T: Float = DefaultFloatRepr> {
+    /// Number of steps run
+    pub steps: usize,
+    /// Wall-clock duration of the run
+    pub elapsed: Duration,
+    /// Steps which missed their deadline by more than the overrun tolerance
+    pub overruns: Vec<Overrun<T>>,
+    /// Largest lateness of any step
+    pub max_lateness: Duration,
+}
+
+/// Runner which paces an [AffectedBody] to the wall clock
+#[derive(Copy,Clone,Debug)]
+pub struct RealTimeRunner<T: Float = DefaultFloatRepr> {
+    /// Simulation timestep (s)
+    delta_t: T,
+    /// Simulation seconds per wall-clock second
+    speed_up: T,
+    /// Lateness below which a step is not reported as an overrun
+    tolerance: Duration,
+}
+
+impl<T: Float> RealTimeRunner<T> {
+    /// Create a new [RealTimeRunner] stepping by `delta_t` (s) at wall-clock rate
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delta_t` is not positive.
+    pub fn new(delta_t: T) -> Self {
+        if delta_t <= T::zero() {
+            panic!("Real-time timestep must be positive");
+        }
+        RealTimeRunner {
+            delta_t,
+            speed_up: T::one(),
+            tolerance: Duration::from_secs(0),
+        }
+    }
+
+    /// Run `speed_up` simulation seconds per wall-clock second
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed_up` is not positive.
+    pub fn with_speed_up(mut self, speed_up: T) -> Self {
+        if speed_up <= T::zero() {
+            panic!("Real-time speed-up must be positive");
+        }
+        self.speed_up = speed_up;
+        self
+    }
+
+    /// Report only steps which are later than `tolerance` as overruns
+    pub fn with_overrun_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Return the wall-clock time from the start of the run to the end of `step`
+    fn deadline(&self, step: usize) -> Duration {
+        Duration::from_secs_f64((constant::<T>((step + 1) as f64) * self.delta_t / self.speed_up).to_f64().unwrap())
+    }
+
+    /// Run `vehicle` in closed loop for `duration` (s), paced to the wall clock
+    ///
+    /// The `controller` is called at the start of each step as for [AffectedBody::run], so it can read
+    /// pilot or hardware inputs at the paced time. Returns the recorded time history with a
+    /// [TimingReport].
+    pub fn run<I, W: WindModel<T>, D: DensityModel<T>, C>(&self, vehicle: &mut AffectedBody<I,T,W,D>, duration: T, mut controller: C) -> (Recorder<T>,TimingReport<T>)
+        where C: FnMut(&dyn StateView<T>, AirState<T>, T) -> I {
+        let steps = <T as num_traits::Float>::round(duration / self.delta_t).to_usize().unwrap_or(0);
+        let mut recorder = Recorder::new();
+        let mut overruns = Vec::new();
+        let mut max_lateness = Duration::from_secs(0);
+        recorder.record(vehicle.time(),vehicle,vehicle.get_airstate(),vehicle.acceleration());
+
+        let start = Instant::now();
+        for step in 0..steps {
+            let time = vehicle.time();
+            let inputstate = controller(vehicle,vehicle.get_airstate(),time);
+            vehicle.step(self.delta_t,&inputstate);
+            if let Some((force, torque)) = vehicle.last_effect() {
+                recorder.record_effect(force,torque);
+            }
+            recorder.record(vehicle.time(),vehicle,vehicle.get_airstate(),vehicle.acceleration());
+
+            let deadline = self.deadline(step);
+            let elapsed = start.elapsed();
+            match elapsed.checked_sub(deadline) {
+                Some(lateness) => {
+                    max_lateness = max_lateness.max(lateness);
+                    if lateness > self.tolerance {
+                        overruns.push(Overrun { step, time: vehicle.time(), lateness });
+                    }
+                },
+                None => std::thread::sleep(deadline - elapsed),
+            }
+        }
+
+        let report = TimingReport { steps, elapsed: start.elapsed(), overruns, max_lateness };
+        (recorder,report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+
+    #[test]
+    fn test_pacing() {
+        let mut vehicle = test_aircraft::vehicle(1000.0);
+        let runner = RealTimeRunner::new(0.01).with_speed_up(2.0).with_overrun_tolerance(Duration::from_millis(50));
+        let (recorder, report) = runner.run(&mut vehicle,0.2,|_,_,_| vec![0.0,0.0,0.0,0.5]);
+        assert_eq!(report.steps,20);
+        assert_eq!(recorder.len(),21);
+        // 0.2 s of simulation at double speed takes at least 0.1 s
+        assert!(report.elapsed >= Duration::from_millis(100));
+        assert!(report.overruns.is_empty());
+    }
+
+    #[test]
+    fn test_overruns() {
+        let mut vehicle = test_aircraft::vehicle(1000.0);
+        let runner = RealTimeRunner::new(0.001).with_overrun_tolerance(Duration::from_millis(1));
+        // A controller slower than the timestep falls further behind on every step
+        let (_, report) = runner.run(&mut vehicle,0.005,|_,_,_| {
+            std::thread::sleep(Duration::from_millis(5));
+            vec![0.0,0.0,0.0,0.5]
+        });
+        assert_eq!(report.overruns.len(),5);
+        assert_eq!(report.overruns[4].step,4);
+        assert!(report.max_lateness >= Duration::from_millis(20));
+        assert!(report.overruns.windows(2).all(|pair| pair[1].lateness > pair[0].lateness));
+    }
+}
@@ -0,0 +1,281 @@
+//! Time-history recording
+//!
+//! A [Recorder] stores the state of a body at each step of a simulation, such as one made with
+//! [AffectedBody::run](crate::AffectedBody::run), and can export the history as CSV for plotting.
+//!
+//! When the total effector force and torque of each step are recorded, as by
+//! [AffectedBody::run](crate::AffectedBody::run), [Recorder::coefficients] converts them back into
+//! non-dimensional [Coefficients] on the reference geometry, for direct comparison with wind-tunnel data.
+
+use std::path::Path;
+
+use crate::{AirState,VehicleGeometry};
+use crate::types::{Vector3,StateVector,StateView};
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Column names of the CSV export, in order
+const CSV_HEADER: &str = "time,x,y,z,u,v,w,qx,qy,qz,qw,p,q,r,alpha,beta,airspeed,mach,ax,ay,az";
+
+/// Column names of the coefficient CSV export, in order
+const COEFFICIENT_CSV_HEADER: &str = "time,alpha,beta,mach,CL,CD,CY,Cl,Cm,Cn";
+
+/// Recorded state at a single time
+#[derive(Copy,Clone)]
+pub struct Sample<T: Float = DefaultFloatRepr> {
+    /// Simulation time (s)
+    pub time: T,
+    /// Statevector, in the order \[position,velocity(body),attitude_quaternion(i,j,k,w),axis_rates(body)\]
+    pub state: StateVector<T>,
+    /// Airstate
+    pub airstate: AirState<T>,
+    /// Body-frame acceleration over the previous step (m·s<sup>-2</sup>)
+    pub acceleration: Vector3<T>,
+    /// Total body-frame effector force (N) and torque (N·m) over the following step, if recorded
+    pub effect: Option<(Vector3<T>,Vector3<T>)>,
+}
+
+/// Non-dimensional aerodynamic coefficients
+///
+/// Lift and drag are in the stability axes, and the side force and moments in the body axes, following
+/// the conventions of [LinearAero](crate::effector_models::LinearAero).
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Coefficients<T: Float = DefaultFloatRepr> {
+    /// Lift coefficient
+    pub c_lift: T,
+    /// Drag coefficient
+    pub c_drag: T,
+    /// Side force coefficient
+    pub c_side: T,
+    /// Rolling moment coefficient
+    pub c_roll: T,
+    /// Pitching moment coefficient
+    pub c_pitch: T,
+    /// Yawing moment coefficient
+    pub c_yaw: T,
+}
+
+impl<T: Float> Coefficients<T> {
+    /// Return the coefficients of the body-frame `force` (N) and `torque` (N·m) at `airstate` on `geometry`
+    ///
+    /// Returns `None` when the dynamic pressure is zero.
+    pub fn from_effect(force: &Vector3<T>, torque: &Vector3<T>, airstate: &AirState<T>, geometry: &VehicleGeometry<T>) -> Option<Self> {
+        if airstate.q == T::zero() {
+            return None;
+        }
+        let q_s = airstate.q * geometry.area;
+        let stability = crate::frames::stability_to_body(airstate.alpha).transpose() * force;
+        Some(Coefficients {
+            c_lift: -stability[2] / q_s,
+            c_drag: -stability[0] / q_s,
+            c_side: force[1] / q_s,
+            c_roll: torque[0] / (q_s * geometry.span),
+            c_pitch: torque[1] / (q_s * geometry.chord),
+            c_yaw: torque[2] / (q_s * geometry.span),
+        })
+    }
+}
+
+/// Record of the time history of a body
+#[derive(Clone)]
+pub struct Recorder<T: Float = DefaultFloatRepr> {
+    /// Recorded samples, in time order
+    samples: Vec<Sample<T>>,
+}
+
+impl<T: Float> Default for Recorder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Recorder<T> {
+    /// Create a new empty [Recorder]
+    pub fn new() -> Self {
+        Recorder {
+            samples: Vec::new(),
+        }
+    }
+
+    /// Record the state of `body` at `time`
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Simulation time (s)
+    /// * `body` - Body to record
+    /// * `airstate` - Airstate of the body
+    /// * `acceleration` - Body-frame acceleration over the previous step (m·s<sup>-2</sup>)
+    pub fn record(&mut self, time: T, body: &dyn StateView<T>, airstate: AirState<T>, acceleration: Vector3<T>) {
+        self.samples.push(Sample {
+            time,
+            state: body.statevector(),
+            airstate,
+            acceleration,
+            effect: None,
+        });
+    }
+
+    /// Record the total body-frame effector `force` (N) and `torque` (N·m) over the step from the most
+    /// recent sample
+    ///
+    /// Does nothing if nothing has been recorded.
+    pub fn record_effect(&mut self, force: Vector3<T>, torque: Vector3<T>) {
+        if let Some(sample) = self.samples.last_mut() {
+            sample.effect = Some((force,torque));
+        }
+    }
+
+    /// Return each sample with a recorded effect and its [Coefficients] on `geometry`
+    fn coefficient_samples<'a>(&'a self, geometry: &'a VehicleGeometry<T>) -> impl Iterator<Item=(&'a Sample<T>,Coefficients<T>)> + 'a {
+        self.samples.iter().filter_map(move |sample| sample.effect
+            .and_then(|(force, torque)| Coefficients::from_effect(&force,&torque,&sample.airstate,geometry))
+            .map(|coefficients| (sample,coefficients)))
+    }
+
+    /// Return the time (s) and [Coefficients] of each sample with a recorded effect on `geometry`
+    ///
+    /// Samples without a recorded effect, or at zero dynamic pressure, are omitted.
+    pub fn coefficients(&self, geometry: &VehicleGeometry<T>) -> Vec<(T,Coefficients<T>)> {
+        self.coefficient_samples(geometry).map(|(sample, coefficients)| (sample.time,coefficients)).collect()
+    }
+
+    /// Return the coefficient history on `geometry` as CSV, with a header row and one row per sample
+    ///
+    /// See [Recorder::coefficients] for the samples included.
+    pub fn coefficients_to_csv(&self, geometry: &VehicleGeometry<T>) -> String {
+        let mut csv = String::from(COEFFICIENT_CSV_HEADER);
+        csv.push('\n');
+        for (sample, c) in self.coefficient_samples(geometry) {
+            let airstate = &sample.airstate;
+            let row: Vec<String> = [sample.time,airstate.alpha,airstate.beta,airstate.mach,c.c_lift,c.c_drag,c.c_side,c.c_roll,c.c_pitch,c.c_yaw].iter()
+                .map(|value| value.to_string())
+                .collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Return the recorded samples
+    pub fn samples(&self) -> &[Sample<T>] {
+        &self.samples
+    }
+
+    /// Return the most recent sample
+    pub fn last(&self) -> Option<&Sample<T>> {
+        self.samples.last()
+    }
+
+    /// Return the number of recorded samples
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Return `true` if nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Remove all recorded samples
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Return an iterator over the recorded times (s)
+    pub fn times(&self) -> impl Iterator<Item=T> + '_ {
+        self.samples.iter().map(|sample| sample.time)
+    }
+
+    /// Return the history as CSV, with a header row and one row per sample
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for sample in &self.samples {
+            let airstate = &sample.airstate;
+            let row: Vec<String> = std::iter::once(sample.time)
+                .chain(sample.state.iter().copied())
+                .chain([airstate.alpha,airstate.beta,airstate.airspeed,airstate.mach])
+                .chain(sample.acceleration.iter().copied())
+                .map(|value| value.to_string())
+                .collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Write the history as CSV to the file at `path`
+    ///
+    /// See [Recorder::to_csv] for the format.
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path,self.to_csv())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_record() {
+        let mut recorder = Recorder::new();
+        assert!(recorder.is_empty());
+        let mut state = StateVector::zeros();
+        state[3] = 20.0;
+        state[9] = 1.0;
+        recorder.record(0.0,&state,AirState { mach: 0.06, ..airstate(0.1,20.0) },Vector3::zeros());
+        state[0] = 2.0;
+        recorder.record(0.1,&state,AirState { mach: 0.06, ..airstate(0.1,20.0) },Vector3::new(1.0,0.0,0.0));
+
+        assert_eq!(recorder.len(),2);
+        assert_eq!(recorder.times().collect::<Vec<f64>>(),vec![0.0,0.1]);
+        let last = recorder.last().unwrap();
+        assert_relative_eq!(last.state[0],2.0);
+        assert_relative_eq!(last.acceleration.x,1.0);
+    }
+
+    #[test]
+    fn test_coefficients() {
+        // Lift and drag built as a coefficient model would, at 0.1 rad angle of attack
+        let geometry = VehicleGeometry::new(2.0,8.0,0.25);
+        let q_s = 245.0 * 2.0;
+        let body = crate::frames::stability_to_body(0.1) * Vector3::new(-0.05 * q_s,0.0,-0.8 * q_s);
+        let force = body + Vector3::new(0.0,0.02 * q_s,0.0);
+        let torque = Vector3::new(0.01 * q_s * 8.0,-0.1 * q_s * 0.25,0.0);
+
+        let mut recorder = Recorder::new();
+        recorder.record_effect(force,torque);
+        recorder.record(0.0,&StateVector::zeros(),AirState { mach: 0.06, ..airstate(0.1,20.0) },Vector3::zeros());
+        recorder.record_effect(force,torque);
+        recorder.record(0.1,&StateVector::zeros(),AirState { mach: 0.06, ..airstate(0.1,20.0) },Vector3::zeros());
+        let coefficients = recorder.coefficients(&geometry);
+        assert_eq!(coefficients.len(),1);
+        let (time, c) = coefficients[0];
+        assert_eq!(time,0.0);
+        assert_relative_eq!(c.c_lift,0.8,max_relative=1e-12);
+        assert_relative_eq!(c.c_drag,0.05,max_relative=1e-12);
+        assert_relative_eq!(c.c_side,0.02,max_relative=1e-12);
+        assert_relative_eq!(c.c_roll,0.01,max_relative=1e-12);
+        assert_relative_eq!(c.c_pitch,-0.1,max_relative=1e-12);
+        assert_eq!(c.c_yaw,0.0);
+
+        let csv = recorder.coefficients_to_csv(&geometry);
+        assert_eq!(csv.lines().count(),2);
+        assert!(csv.lines().nth(1).unwrap().starts_with("0,0.1,0,0.06,"));
+        let still = AirState { q: 0.0, airspeed: 0.0, mach: 0.06, ..airstate(0.1,20.0) };
+        assert!(Coefficients::from_effect(&force,&torque,&still,&geometry).is_none());
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut recorder = Recorder::new();
+        recorder.record(0.5,&StateVector::zeros(),AirState { mach: 0.06, ..airstate(0.1,20.0) },Vector3::zeros());
+        let csv = recorder.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(),2);
+        assert_eq!(lines[0].split(',').count(),lines[1].split(',').count());
+        assert!(lines[1].starts_with("0.5,"));
+        assert!(lines[1].contains(",0.1,0,20,0.06,"));
+    }
+}
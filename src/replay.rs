@@ -0,0 +1,221 @@
+//! Deterministic replay
+//!
+//! Stepping an [AffectedBody](crate::AffectedBody) is deterministic: for identical initial states,
+//! inputs, timesteps and seeds, two runs produce bit-identical state histories. The built-in models hold
+//! no hidden global state, and stochastic models draw from a seeded [Rng](crate::random::Rng), so
+//! determinism only depends on user-supplied effectors and controllers doing the same.
+//!
+//! A [ReplayToken] captures everything needed to repeat a run: the seed, timestep, duration and any
+//! named configuration. Recording a run stores a fingerprint of its history in the token, and
+//! [ReplayToken::verify] repeats the run and checks it against the fingerprint.
+//! Tokens can be saved and restored as text.
+
+use std::fmt;
+
+use crate::Error;
+use crate::recorder::Recorder;
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Return a 64-bit FNV-1a fingerprint of the exact bits of every recorded time and state
+pub fn fingerprint<T: Float>(recorder: &Recorder<T>) -> u64 {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    for sample in recorder.samples() {
+        for value in std::iter::once(sample.time).chain(sample.state.iter().copied()) {
+            for byte in value.to_f64().unwrap().to_bits().to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+            }
+        }
+    }
+    hash
+}
+
+/// Return the index of the first sample at which `a` and `b` differ in any bit, if any
+///
+/// Histories of different lengths diverge at the end of the shorter one.
+pub fn first_divergence<T: Float>(a: &Recorder<T>, b: &Recorder<T>) -> Option<usize> {
+    let same = |x: T, y: T| x.to_f64().unwrap().to_bits() == y.to_f64().unwrap().to_bits();
+    a.samples().iter().zip(b.samples())
+        .position(|(a, b)| !same(a.time,b.time) || a.state.iter().zip(b.state.iter()).any(|(&x, &y)| !same(x,y)))
+        .or(if a.len() != b.len() { Some(a.len().min(b.len())) } else { None })
+}
+
+/// Record of the seed and configuration of a run, for exact replay
+#[derive(Clone,Debug,PartialEq)]
+pub struct ReplayToken<T: Float = DefaultFloatRepr> {
+    /// Seed for every random number in the run
+    pub seed: u64,
+    /// Timestep (s)
+    pub delta_t: T,
+    /// Duration of the run (s)
+    pub duration: T,
+    /// Named configuration, such as model versions or scenario files
+    config: Vec<(String,String)>,
+    /// Fingerprint of the recorded history
+    fingerprint: Option<u64>,
+}
+
+impl<T: Float> ReplayToken<T> {
+    /// Create a new [ReplayToken]
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed for every random number in the run
+    /// * `delta_t` - Timestep (s)
+    /// * `duration` - Duration of the run (s)
+    pub fn new(seed: u64, delta_t: T, duration: T) -> Self {
+        ReplayToken {
+            seed,
+            delta_t,
+            duration,
+            config: Vec::new(),
+            fingerprint: None,
+        }
+    }
+
+    /// Add the configuration entry `key` with `value`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key or value contains a line break, or the key contains `=`.
+    pub fn with_config<V: ToString>(mut self, key: &str, value: V) -> Self {
+        let value = value.to_string();
+        if key.contains(['\n','\r','=']) || value.contains(['\n','\r']) {
+            panic!("Invalid replay configuration entry '{}'",key);
+        }
+        self.config.push((key.to_string(),value));
+        self
+    }
+
+    /// Return the configuration value for `key`
+    pub fn config(&self, key: &str) -> Option<&str> {
+        self.config.iter().find(|(entry, _)| entry == key).map(|(_, value)| value.as_str())
+    }
+
+    /// Return the fingerprint of the recorded history, if the run has been recorded
+    pub fn fingerprint(&self) -> Option<u64> {
+        self.fingerprint
+    }
+
+    /// Run `simulate` with this token and store the fingerprint of its history
+    pub fn record<F: FnMut(&Self) -> Recorder<T>>(mut self, mut simulate: F) -> Self {
+        self.fingerprint = Some(fingerprint(&simulate(&self)));
+        self
+    }
+
+    /// Verify that `simulate` reproduces the recorded run
+    ///
+    /// If the token has no fingerprint, the run is repeated twice and the histories are compared. If the
+    /// simulation is not deterministic, the error gives the first sample at which repeated runs diverge.
+    pub fn verify<F: FnMut(&Self) -> Recorder<T>>(&self, mut simulate: F) -> Result<(),Error> {
+        let first = simulate(self);
+        match self.fingerprint {
+            Some(expected) => {
+                let actual = fingerprint(&first);
+                if actual == expected {
+                    Ok(())
+                } else {
+                    // Repeat the run to find out whether the simulation is itself non-deterministic
+                    match first_divergence(&first,&simulate(self)) {
+                        Some(idx) => Err(Error::NotDeterministic(idx)),
+                        None => Err(Error::FingerprintMismatch { expected, actual }),
+                    }
+                }
+            },
+            None => match first_divergence(&first,&simulate(self)) {
+                Some(idx) => Err(Error::NotDeterministic(idx)),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Parse a token written with `to_string`
+    pub fn parse(text: &str) -> Result<Self,Error> {
+        let mut seed = None;
+        let mut delta_t = None;
+        let mut duration = None;
+        let mut fingerprint = None;
+        let mut config = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once('=').ok_or_else(|| Error::Parse(format!("Invalid replay token line '{}'",line)))?;
+            let number = || value.parse::<f64>().map(constant::<T>).map_err(|_| Error::Parse(format!("Invalid value '{}' for '{}'",value,key)));
+            match key {
+                "seed" => seed = Some(value.parse::<u64>().map_err(|_| Error::Parse(format!("Invalid seed '{}'",value)))?),
+                "delta_t" => delta_t = Some(number()?),
+                "duration" => duration = Some(number()?),
+                "fingerprint" => fingerprint = Some(u64::from_str_radix(value,16).map_err(|_| Error::Parse(format!("Invalid fingerprint '{}'",value)))?),
+                _ => match key.strip_prefix("config.") {
+                    Some(entry) => config.push((entry.to_string(),value.to_string())),
+                    None => return Err(Error::Parse(format!("Unknown replay token key '{}'",key))),
+                },
+            }
+        }
+        Ok(ReplayToken {
+            seed: seed.ok_or_else(|| Error::Parse("Replay token has no seed".to_string()))?,
+            delta_t: delta_t.ok_or_else(|| Error::Parse("Replay token has no delta_t".to_string()))?,
+            duration: duration.ok_or_else(|| Error::Parse("Replay token has no duration".to_string()))?,
+            config,
+            fingerprint,
+        })
+    }
+}
+
+impl<T: Float> fmt::Display for ReplayToken<T> {
+    /// Write the token as `key=value` lines, with exact floating-point values
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f,"seed={}",self.seed)?;
+        writeln!(f,"delta_t={}",self.delta_t.to_f64().unwrap())?;
+        writeln!(f,"duration={}",self.duration.to_f64().unwrap())?;
+        if let Some(fingerprint) = self.fingerprint {
+            writeln!(f,"fingerprint={:016x}",fingerprint)?;
+        }
+        for (key, value) in &self.config {
+            writeln!(f,"config.{}={}",key,value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::random::Rng;
+    use crate::trim::test_aircraft;
+
+    /// Fly the test aircraft with random throttle noise seeded by the token
+    fn simulate(token: &ReplayToken) -> Recorder {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let mut rng = Rng::new(token.seed);
+        vehicle.run(token.duration,token.delta_t,|_,_,_| vec![0.0,-0.02,0.0,0.5 + 0.1 * rng.normal()])
+    }
+
+    #[test]
+    fn test_verify() {
+        let token = ReplayToken::new(1234,0.01,2.0).with_config("model","test_aircraft").record(simulate);
+        assert!(token.fingerprint().is_some());
+        assert!(token.verify(simulate).is_ok());
+
+        // A different seed changes the history
+        let mismatched = ReplayToken { seed: 4321, ..token.clone() };
+        assert!(matches!(mismatched.verify(simulate),Err(Error::FingerprintMismatch { .. })));
+        assert_eq!(first_divergence(&simulate(&token),&simulate(&mismatched)),Some(1));
+
+        // Unseeded randomness is detected
+        let mut calls = 0;
+        let result = ReplayToken::new(0,0.01,1.0).verify(|token| {
+            calls += 1;
+            simulate(&ReplayToken { seed: token.seed + calls, ..token.clone() })
+        });
+        assert_eq!(result,Err(Error::NotDeterministic(1)));
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let token = ReplayToken::new(7,0.1 / 3.0,12.5).with_config("wind","gusty").record(simulate);
+        let parsed = ReplayToken::parse(&token.to_string()).unwrap();
+        assert_eq!(parsed,token);
+        assert_eq!(parsed.config("wind"),Some("gusty"));
+        assert!(ReplayToken::<f64>::parse("seed=1\ndelta_t=0.1\n").is_err());
+        assert!(ReplayToken::<f64>::parse("seed=1\ndelta_t=0.1\nduration=1\ncolour=red\n").is_err());
+    }
+}
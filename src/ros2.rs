@@ -0,0 +1,306 @@
+//! ROS 2 bridge
+//!
+//! Enabled by the `ros2` feature, a [RosBridge] steps an [AffectedBody] against a ROS 2 graph,
+//! publishing odometry, IMU and air-data messages and applying the latest actuator commands received.
+//!
+//! Messages are built in the ROS conventions of REP-103 and REP-105: the `odom` frame is
+//! East-North-Up and the `base_link` frame forward-left-up, where the simulation uses North-East-Down
+//! and forward-right-down axes. [Odometry] and [Imu] carry the fields of `nav_msgs/Odometry` and
+//! `sensor_msgs/Imu` without covariances, and [AirData] the airspeed, flow angles and altitude for a
+//! custom message or `mavros_msgs/VfrHud`.
+//!
+//! ROS 2 message crates are generated by `colcon` in the user's workspace rather than published, so the
+//! middleware is reached through the [RosTransport] trait. With `rclrs`, a node implements it by
+//! copying each message into its generated type and publishing it, and by storing commands received by
+//! an actuator subscription for [RosTransport::take_commands]:
+//!
+//! ```ignore
+//! impl RosTransport for RclrsTransport {
+//!     fn publish_odometry(&mut self, odometry: &Odometry) {
+//!         let mut message = nav_msgs::msg::Odometry::default();
+//!         message.pose.pose.position.x = odometry.position[0];
+//!         // ...
+//!         self.odometry.publish(&message).unwrap();
+//!     }
+//!     fn take_commands(&mut self) -> Option<Vec<f64>> {
+//!         self.commands.lock().unwrap().take()
+//!     }
+//!     // ...
+//! }
+//! ```
+
+use crate::{AffectedBody,WindModel,DensityModel};
+use crate::sensors::Truth;
+use crate::types::{Vector3,Matrix3,UnitQuaternion,StateView,Float,DefaultFloatRepr};
+
+/// Stamp and frame of a message, as `std_msgs/Header`
+#[derive(Clone,Debug,PartialEq)]
+pub struct Header<T: Float = DefaultFloatRepr> {
+    /// Simulation time (s)
+    pub stamp: T,
+    /// Frame of the message
+    pub frame_id: String,
+}
+
+/// Pose and twist of the vehicle, as `nav_msgs/Odometry`
+#[derive(Clone,Debug,PartialEq)]
+pub struct Odometry<T: Float = DefaultFloatRepr> {
+    /// Header, in the odometry frame
+    pub header: Header<T>,
+    /// Frame of the twist
+    pub child_frame_id: String,
+    /// East-North-Up position (m)
+    pub position: Vector3<T>,
+    /// Attitude of the forward-left-up body axes in the East-North-Up frame
+    pub orientation: UnitQuaternion<T>,
+    /// Forward-left-up velocity (m·s<sup>-1</sup>)
+    pub linear: Vector3<T>,
+    /// Forward-left-up rates (rad·s<sup>-1</sup>)
+    pub angular: Vector3<T>,
+}
+
+/// Inertial measurements, as `sensor_msgs/Imu`
+#[derive(Clone,Debug,PartialEq)]
+pub struct Imu<T: Float = DefaultFloatRepr> {
+    /// Header, in the body frame
+    pub header: Header<T>,
+    /// Attitude of the forward-left-up body axes in the East-North-Up frame
+    pub orientation: UnitQuaternion<T>,
+    /// Forward-left-up rates (rad·s<sup>-1</sup>)
+    pub angular_velocity: Vector3<T>,
+    /// Forward-left-up specific force (m·s<sup>-2</sup>), reading +g upwards at rest
+    pub linear_acceleration: Vector3<T>,
+}
+
+/// Air data of the vehicle
+#[derive(Clone,Debug,PartialEq)]
+pub struct AirData<T: Float = DefaultFloatRepr> {
+    /// Header, in the body frame
+    pub header: Header<T>,
+    /// True airspeed (m·s<sup>-1</sup>)
+    pub airspeed: T,
+    /// Angle of attack (rad)
+    pub alpha: T,
+    /// Sideslip angle (rad)
+    pub beta: T,
+    /// Altitude above the origin (m)
+    pub altitude: T,
+    /// Climb rate (m·s<sup>-1</sup>)
+    pub climb_rate: T,
+    /// Air density (kg·m<sup>-3</sup>)
+    pub density: T,
+}
+
+/// Connection of a [RosBridge] to the ROS 2 graph
+pub trait RosTransport<T: Float = DefaultFloatRepr> {
+    /// Publish `odometry`
+    fn publish_odometry(&mut self, odometry: &Odometry<T>);
+
+    /// Publish `imu`
+    fn publish_imu(&mut self, imu: &Imu<T>);
+
+    /// Publish `air_data`
+    fn publish_air_data(&mut self, air_data: &AirData<T>);
+
+    /// Return the actuator commands received since the last call, if any
+    fn take_commands(&mut self) -> Option<Vec<T>>;
+}
+
+/// Return the rotation from North-East-Down to East-North-Up axes, equal to its inverse
+fn ned_to_enu<T: Float>() -> Matrix3<T> {
+    Matrix3::new(
+        T::zero(),T::one(),T::zero(),
+        T::one(),T::zero(),T::zero(),
+        T::zero(),T::zero(),-T::one())
+}
+
+/// Convert a forward-right-down vector to forward-left-up
+fn frd_to_flu<T: Float>(vector: &Vector3<T>) -> Vector3<T> {
+    Vector3::new(vector[0],-vector[1],-vector[2])
+}
+
+/// Convert a North-East-Down position to East-North-Up
+pub fn position_to_enu<T: Float>(position: &Vector3<T>) -> Vector3<T> {
+    ned_to_enu() * position
+}
+
+/// Convert a North-East-Down attitude of forward-right-down axes to an East-North-Up attitude of
+/// forward-left-up axes
+pub fn attitude_to_enu<T: Float>(attitude: &UnitQuaternion<T>) -> UnitQuaternion<T> {
+    let frd_to_flu = Matrix3::from_diagonal(&Vector3::new(T::one(),-T::one(),-T::one()));
+    let matrix = ned_to_enu() * attitude.to_rotation_matrix().matrix() * frd_to_flu;
+    UnitQuaternion::from_rotation_matrix(&nalgebra::Rotation3::from_matrix_unchecked(matrix))
+}
+
+/// Bridge stepping a vehicle against a [RosTransport]
+pub struct RosBridge<T: Float = DefaultFloatRepr> {
+    /// Odometry frame
+    odom_frame: String,
+    /// Body frame
+    base_frame: String,
+    /// Time between publications (s)
+    publish_period: T,
+    /// Time of the next publication (s)
+    next_publish: Option<T>,
+    /// Latest actuator commands
+    commands: Vec<T>,
+}
+
+impl<T: Float> RosBridge<T> {
+    /// Create a new [RosBridge] applying `initial_commands` until commands are received, publishing
+    /// every step in the `odom` and `base_link` frames
+    pub fn new(initial_commands: Vec<T>) -> Self {
+        RosBridge {
+            odom_frame: "odom".to_string(),
+            base_frame: "base_link".to_string(),
+            publish_period: T::zero(),
+            next_publish: None,
+            commands: initial_commands,
+        }
+    }
+
+    /// Publish in the `odom_frame` and `base_frame` frames
+    pub fn with_frames(mut self, odom_frame: &str, base_frame: &str) -> Self {
+        self.odom_frame = odom_frame.to_string();
+        self.base_frame = base_frame.to_string();
+        self
+    }
+
+    /// Publish at `rate` (Hz), or every step if `rate` is zero
+    pub fn with_publish_rate(mut self, rate: T) -> Self {
+        self.publish_period = if rate > T::zero() { T::one() / rate } else { T::zero() };
+        self
+    }
+
+    /// Return the latest actuator commands
+    pub fn commands(&self) -> &[T] {
+        &self.commands
+    }
+
+    /// Return a header stamped `time` in `frame`
+    fn header(&self, time: T, frame: &str) -> Header<T> {
+        Header { stamp: time, frame_id: frame.to_string() }
+    }
+
+    /// Return the messages describing `vehicle` at `time`
+    pub fn messages<I, W: WindModel<T>, D: DensityModel<T>>(&self, time: T, vehicle: &AffectedBody<I,T,W,D>) -> (Odometry<T>,Imu<T>,AirData<T>) {
+        let truth = Truth::new(time,vehicle);
+        let state = &truth.state;
+        let orientation = attitude_to_enu(&state.attitude());
+        let rates = frd_to_flu(&truth.rates());
+        let odometry = Odometry {
+            header: self.header(time,&self.odom_frame),
+            child_frame_id: self.base_frame.clone(),
+            position: position_to_enu(&state.position()),
+            orientation,
+            linear: frd_to_flu(&state.velocity()),
+            angular: rates,
+        };
+        let imu = Imu {
+            header: self.header(time,&self.base_frame),
+            orientation,
+            angular_velocity: rates,
+            linear_acceleration: frd_to_flu(&truth.specific_force()),
+        };
+        let air_data = AirData {
+            header: self.header(time,&self.base_frame),
+            airspeed: truth.airstate.airspeed,
+            alpha: truth.airstate.alpha,
+            beta: truth.airstate.beta,
+            altitude: -state[2],
+            climb_rate: (orientation * odometry.linear)[2],
+            density: truth.airstate.density,
+        };
+        (odometry,imu,air_data)
+    }
+
+    /// Publish the messages describing `vehicle` at `time` if due, then step it by `delta_t` with the
+    /// latest actuator commands
+    pub fn step<I: From<Vec<T>>, W: WindModel<T>, D: DensityModel<T>, R: RosTransport<T>>(&mut self, transport: &mut R, vehicle: &mut AffectedBody<I,T,W,D>, time: T, delta_t: T) {
+        if self.next_publish.is_none_or(|next| time >= next) {
+            let (odometry, imu, air_data) = self.messages(time,vehicle);
+            transport.publish_odometry(&odometry);
+            transport.publish_imu(&imu);
+            transport.publish_air_data(&air_data);
+            self.next_publish = Some(self.next_publish.unwrap_or(time) + self.publish_period);
+        }
+        if let Some(commands) = transport.take_commands() {
+            self.commands = commands;
+        }
+        vehicle.step(delta_t,&I::from(self.commands.clone()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    /// Transport recording messages and replaying queued commands
+    #[derive(Default)]
+    struct Loopback {
+        odometry: Vec<Odometry>,
+        imu: Vec<Imu>,
+        air_data: Vec<AirData>,
+        commands: Vec<Vec<f64>>,
+    }
+
+    impl RosTransport for Loopback {
+        fn publish_odometry(&mut self, odometry: &Odometry) {
+            self.odometry.push(odometry.clone());
+        }
+
+        fn publish_imu(&mut self, imu: &Imu) {
+            self.imu.push(imu.clone());
+        }
+
+        fn publish_air_data(&mut self, air_data: &AirData) {
+            self.air_data.push(air_data.clone());
+        }
+
+        fn take_commands(&mut self) -> Option<Vec<f64>> {
+            self.commands.pop()
+        }
+    }
+
+    #[test]
+    fn test_conventions() {
+        let position = position_to_enu(&Vector3::new(10.0,20.0,-30.0));
+        assert_eq!(position,Vector3::new(20.0,10.0,30.0));
+
+        // Heading East, the ENU attitude is unrotated; heading North it is yawed 90° left
+        let east = attitude_to_enu(&UnitQuaternion::from_euler_angles(0.0,0.0,std::f64::consts::FRAC_PI_2));
+        assert_relative_eq!(east.angle(),0.0,epsilon=1e-9);
+        let north = attitude_to_enu(&UnitQuaternion::<f64>::identity());
+        let (roll, pitch, yaw) = north.euler_angles();
+        assert_relative_eq!(roll,0.0,epsilon=1e-9);
+        assert_relative_eq!(pitch,0.0,epsilon=1e-9);
+        assert_relative_eq!(yaw,std::f64::consts::FRAC_PI_2,epsilon=1e-9);
+        // Pitched nose up in NED is pitched nose up in ENU
+        let (_, pitch, _) = attitude_to_enu(&UnitQuaternion::from_euler_angles(0.0,0.2,std::f64::consts::FRAC_PI_2)).euler_angles();
+        assert_relative_eq!(pitch,-0.2,epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_bridge() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let mut transport = Loopback { commands: vec![vec![0.0,0.0,0.0,1.0]], ..Default::default() };
+        let mut bridge = RosBridge::new(vec![0.0,0.0,0.0,0.5]).with_publish_rate(10.0);
+        for step in 0..100 {
+            bridge.step(&mut transport,&mut vehicle,step as f64 * 0.01,0.01);
+        }
+        assert_eq!(bridge.commands(),&[0.0,0.0,0.0,1.0]);
+        assert_eq!(transport.odometry.len(),10);
+        assert_relative_eq!(transport.odometry[9].header.stamp,0.9,epsilon=1e-9);
+
+        let (odometry, imu, air_data) = bridge.messages(1.0,&vehicle);
+        assert_eq!(odometry.header.frame_id,"odom");
+        assert_eq!(imu.header.frame_id,"base_link");
+        assert_relative_eq!(odometry.position[2],-vehicle.position()[2],epsilon=1e-9);
+        assert_relative_eq!(air_data.altitude,odometry.position[2],epsilon=1e-9);
+        assert_relative_eq!(odometry.linear[0],vehicle.velocity()[0],epsilon=1e-9);
+        // Flying roughly level, the accelerometers read roughly +g upwards
+        assert!(imu.linear_acceleration[2] > 5.0);
+    }
+}
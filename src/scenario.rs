@@ -0,0 +1,320 @@
+//! Scenarios bundling the environment, vehicles, inputs and events of a run
+//!
+//! A [Scenario] holds the wind and density models of the environment, and builds each of its vehicles
+//! in that environment. Each vehicle is added to a [World] with the [Inputs] which drive it, either an
+//! [InputSchedule] or a controller. Named events apply an action to the world once their [Trigger] is
+//! met for a vehicle, and stop conditions end the run early.
+//!
+//! [Scenario::run] steps the world for the duration of the scenario, and returns a [ScenarioResult]
+//! with the [Termination] reason, the events which fired, a [Recorder] per vehicle and the final world.
+//!
+//! ```
+//! use aerso::Body;
+//! use aerso::density_models::StandardDensity;
+//! use aerso::effector_models::Damping;
+//! use aerso::faults::Trigger;
+//! use aerso::monte_carlo::Termination;
+//! use aerso::scenario::{Scenario,Inputs};
+//! use aerso::schedule::InputSchedule;
+//! use aerso::types::{Vector3,Matrix3,UnitQuaternion,StateView};
+//! use aerso::wind_models::ConstantWind;
+//!
+//! let body = Body::new(1.0,Matrix3::identity(),Vector3::new(0.0,0.0,-100.0),Vector3::zeros(),UnitQuaternion::identity(),Vector3::zeros());
+//! let scenario = Scenario::new(ConstantWind::new(Vector3::zeros()),StandardDensity{},60.0,0.01)
+//!     .with_vehicle("drop",body,vec![],None,Inputs::Schedule(InputSchedule::new(vec![])))
+//!     .with_event("chute",Trigger::At(1.0),|world| {
+//!         world.get_mut("drop").unwrap().add_effector("chute",Box::new(Damping::new(Vector3::zeros(),Vector3::new(5.0,5.0,5.0))));
+//!     })
+//!     .with_stop("ground contact","drop",|_,state| state.position()[2] > 0.0);
+//! let result = scenario.run();
+//! assert_eq!(result.termination,Termination::Stopped("ground contact".to_string()));
+//! assert_eq!(result.events[0].1,"chute");
+//! ```
+
+use std::ops::IndexMut;
+
+use crate::{AeroEffect,AeroBody,AffectedBody,AirState,Body,WindModel,DensityModel};
+use crate::faults::{Trigger,Condition};
+use crate::monte_carlo::Termination;
+use crate::recorder::Recorder;
+use crate::schedule::InputSchedule;
+use crate::types::{StateView,Float,DefaultFloatRepr,constant};
+use crate::world::{World,WorldVehicle,WakeModel,InteractionWind};
+
+/// Controller returning the inputstate from the state, airstate and time, as for [AffectedBody::run]
+pub type Controller<I,T> = Box<dyn FnMut(&dyn StateView<T>,AirState<T>,T) -> I>;
+
+/// Action of a scenario event, applied to the world when the event fires
+pub type Action<I,T,W,D> = Box<dyn FnMut(&mut World<I,T,W,D>)>;
+
+/// Source of the inputstate of a vehicle in a [Scenario]
+pub enum Inputs<I, T: Float = DefaultFloatRepr> {
+    /// Open-loop schedule, evaluated from zero time
+    Schedule(InputSchedule<I,T>),
+    /// Closed-loop controller
+    Controller(Controller<I,T>),
+}
+
+impl<I: Clone + IndexMut<usize,Output=T>, T: Float> Inputs<I,T> {
+    /// Create a [Controller](Inputs::Controller) from `controller`
+    pub fn controller<F: FnMut(&dyn StateView<T>,AirState<T>,T) -> I + 'static>(controller: F) -> Self {
+        Inputs::Controller(Box::new(controller))
+    }
+
+    /// Return the inputstate for `vehicle` at `time` (s)
+    fn inputs<W: WindModel<T>, D: DensityModel<T>>(&mut self, vehicle: &WorldVehicle<I,T,W,D>, time: T) -> I {
+        match self {
+            Inputs::Schedule(schedule) => schedule.inputs(time),
+            Inputs::Controller(controller) => controller(vehicle,vehicle.get_airstate(),time),
+        }
+    }
+}
+
+/// Named event of a [Scenario]
+struct ScenarioEvent<I, T: Float, W: WindModel<T>, D: DensityModel<T>> {
+    /// Name of the event
+    name: String,
+    /// Vehicle whose state is given to the trigger, or `None` for a time-only trigger
+    vehicle: Option<String>,
+    /// Condition firing the event
+    trigger: Trigger<T>,
+    /// Action applied to the world when the event fires
+    action: Action<I,T,W,D>,
+    /// Whether the event has fired
+    fired: bool,
+}
+
+/// Condition ending a [Scenario] early
+struct StopCondition<T: Float> {
+    /// Reason given in the [Termination]
+    reason: String,
+    /// Vehicle whose state is given to the condition
+    vehicle: String,
+    /// Condition on the time and vehicle state
+    condition: Condition<T>,
+}
+
+/// Result of a [Scenario] run
+pub struct ScenarioResult<I, T: Float, W: WindModel<T>, D: DensityModel<T>> {
+    /// Reason the run ended
+    pub termination: Termination,
+    /// Simulation time at the end of the run (s)
+    pub time: T,
+    /// Time (s) and name of each event which fired, in order
+    pub events: Vec<(T,String)>,
+    /// Name and time history of each vehicle
+    pub recorders: Vec<(String,Recorder<T>)>,
+    /// World at the end of the run
+    pub world: World<I,T,W,D>,
+}
+
+impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> ScenarioResult<I,T,W,D> {
+    /// Return the time history of the vehicle called `name`
+    pub fn recorder(&self, name: &str) -> Option<&Recorder<T>> {
+        self.recorders.iter().find(|(vehicle, _)| vehicle == name).map(|(_, recorder)| recorder)
+    }
+}
+
+/// Environment, vehicles, inputs, events and stop conditions of a run
+pub struct Scenario<I = Vec<DefaultFloatRepr>, T: Float = DefaultFloatRepr, W: WindModel<T> = crate::wind_models::ConstantWind<T>, D: DensityModel<T> = crate::density_models::StandardDensity> {
+    /// Wind model given to each vehicle
+    wind: W,
+    /// Density model given to each vehicle
+    density: D,
+    /// Vehicles of the scenario
+    world: World<I,T,W,D>,
+    /// Name and inputs of each vehicle
+    inputs: Vec<(String,Inputs<I,T>)>,
+    /// Events in the order they were added
+    events: Vec<ScenarioEvent<I,T,W,D>>,
+    /// Stop conditions in the order they were added
+    stops: Vec<StopCondition<T>>,
+    /// Length of the run (s)
+    duration: T,
+    /// Timestep (s)
+    delta_t: T,
+}
+
+impl<I: Clone + IndexMut<usize,Output=T>, T: Float, W: WindModel<T> + Clone, D: DensityModel<T> + Clone> Scenario<I,T,W,D> {
+    /// Create a new [Scenario] in the environment of `wind` and `density`, run for `duration` (s) with timestep `delta_t` (s)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delta_t` is not positive.
+    pub fn new(wind: W, density: D, duration: T, delta_t: T) -> Self {
+        if delta_t <= T::zero() {
+            panic!("Scenario timestep must be positive");
+        }
+        Scenario { wind, density, world: World::new(), inputs: Vec::new(), events: Vec::new(), stops: Vec::new(), duration, delta_t }
+    }
+
+    /// Add a vehicle called `name` from `body` and `effectors` in the scenario environment, with an optional `wake`, driven by `inputs`
+    ///
+    /// A vehicle of the same name is replaced. Use [Scenario::vehicle_mut] for further configuration,
+    /// such as contact models.
+    pub fn with_vehicle(mut self, name: &str, body: Body<T>, effectors: Vec<Box<dyn AeroEffect<I,T>>>, wake: Option<Box<dyn WakeModel<T>>>, inputs: Inputs<I,T>) -> Self {
+        let aero = AeroBody::with_density_model(body,InteractionWind::new(self.wind.clone()),self.density.clone());
+        self.world.add(name,AffectedBody::new(aero,effectors),wake);
+        self.inputs.retain(|(vehicle, _)| vehicle != name);
+        self.inputs.push((name.to_string(),inputs));
+        self
+    }
+
+    /// Add an event called `name` which applies `action` to the world once `trigger` is first met
+    ///
+    /// [Event](Trigger::Event) conditions are given the state of the first vehicle; use
+    /// [Scenario::with_vehicle_event] to choose the vehicle.
+    pub fn with_event<F: FnMut(&mut World<I,T,W,D>) + 'static>(mut self, name: &str, trigger: Trigger<T>, action: F) -> Self {
+        self.events.push(ScenarioEvent { name: name.to_string(), vehicle: None, trigger, action: Box::new(action), fired: false });
+        self
+    }
+
+    /// Add an event called `name` which applies `action` to the world once `trigger` is first met for the vehicle called `vehicle`
+    pub fn with_vehicle_event<F: FnMut(&mut World<I,T,W,D>) + 'static>(mut self, name: &str, vehicle: &str, trigger: Trigger<T>, action: F) -> Self {
+        self.events.push(ScenarioEvent { name: name.to_string(), vehicle: Some(vehicle.to_string()), trigger, action: Box::new(action), fired: false });
+        self
+    }
+
+    /// Stop the run with `reason` once `condition` is met for the time and state of the vehicle called `vehicle`
+    pub fn with_stop<F: FnMut(T,&dyn StateView<T>) -> bool + 'static>(mut self, reason: &str, vehicle: &str, condition: F) -> Self {
+        self.stops.push(StopCondition { reason: reason.to_string(), vehicle: vehicle.to_string(), condition: Box::new(condition) });
+        self
+    }
+
+    /// Return the world of vehicles
+    pub fn world(&self) -> &World<I,T,W,D> {
+        &self.world
+    }
+
+    /// Return the vehicle called `name` for modification
+    pub fn vehicle_mut(&mut self, name: &str) -> Option<&mut WorldVehicle<I,T,W,D>> {
+        self.world.get_mut(name)
+    }
+
+    /// Run the scenario from zero time, returning its [ScenarioResult]
+    ///
+    /// At the start of each step the stop conditions are checked, and then the events, before the inputs
+    /// are evaluated and the world is stepped. The run fails if a vehicle state becomes non-finite, a
+    /// vehicle named by an event or stop condition is missing, or a vehicle added by an event has no inputs.
+    pub fn run(mut self) -> ScenarioResult<I,T,W,D> {
+        self.world.set_time(T::zero());
+        let mut recorders: Vec<(String,Recorder<T>)> = self.world.vehicles().map(|(name, vehicle)| {
+            let mut recorder = Recorder::new();
+            recorder.record(T::zero(),vehicle,vehicle.get_airstate(),vehicle.acceleration());
+            (name.to_string(),recorder)
+        }).collect();
+        let mut events = Vec::new();
+        let termination = self.run_steps(&mut recorders,&mut events);
+        ScenarioResult { termination, time: self.world.time(), events, recorders, world: self.world }
+    }
+
+    /// Step the world to the end of the run, recording into `recorders` and `events`, and return the [Termination]
+    fn run_steps(&mut self, recorders: &mut Vec<(String,Recorder<T>)>, events: &mut Vec<(T,String)>) -> Termination {
+        let steps = <T as num_traits::Float>::round(self.duration / self.delta_t).to_usize().unwrap_or(0);
+        for step in 0..steps {
+            let time = constant::<T>(step as f64) * self.delta_t;
+            for stop in self.stops.iter_mut() {
+                match self.world.get(&stop.vehicle) {
+                    Some(vehicle) if (stop.condition)(time,vehicle) => return Termination::Stopped(stop.reason.clone()),
+                    Some(_) => {},
+                    None => return Termination::Failed(format!("no vehicle '{}' for stop condition",stop.vehicle)),
+                }
+            }
+            for event in self.events.iter_mut().filter(|event| !event.fired) {
+                let vehicle = match &event.vehicle {
+                    Some(name) => self.world.get(name),
+                    None => self.world.vehicles().next().map(|(_, vehicle)| vehicle),
+                };
+                let state: &dyn StateView<T> = match vehicle {
+                    Some(vehicle) => vehicle,
+                    None => return Termination::Failed(format!("no vehicle for event '{}'",event.name)),
+                };
+                event.fired = match &mut event.trigger {
+                    Trigger::At(start) => time >= *start,
+                    Trigger::Between(start, end) => time >= *start && time < *end,
+                    Trigger::Event(condition) => condition(time,state),
+                };
+                if event.fired {
+                    (event.action)(&mut self.world);
+                    events.push((time,event.name.clone()));
+                }
+            }
+
+            let mut inputstates = Vec::with_capacity(self.world.len());
+            for (name, vehicle) in self.world.vehicles() {
+                match self.inputs.iter_mut().find(|(vehicle, _)| vehicle == name) {
+                    Some((_, inputs)) => inputstates.push(inputs.inputs(vehicle,time)),
+                    None => return Termination::Failed(format!("no inputs for vehicle '{}'",name)),
+                }
+            }
+            self.world.step(self.delta_t,&inputstates);
+
+            for (name, vehicle) in self.world.vehicles() {
+                if vehicle.statevector().iter().any(|value| !<T as num_traits::Float>::is_finite(*value)) {
+                    return Termination::Failed(format!("non-finite state of vehicle '{}'",name));
+                }
+                let index = match recorders.iter().position(|(recorded, _)| recorded == name) {
+                    Some(index) => index,
+                    None => {
+                        recorders.push((name.to_string(),Recorder::new()));
+                        recorders.len() - 1
+                    },
+                };
+                let recorder = &mut recorders[index].1;
+                if let Some((force, torque)) = vehicle.last_effect() {
+                    recorder.record_effect(force,torque);
+                }
+                recorder.record(time + self.delta_t,vehicle,vehicle.get_airstate(),vehicle.acceleration());
+            }
+        }
+        Termination::Completed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::density_models::StandardDensity;
+    use crate::effector_models::Damping;
+    use crate::wind_models::ConstantWind;
+    use crate::types::{Vector3,Matrix3,UnitQuaternion};
+    use approx::assert_relative_eq;
+
+    fn body(position: Vector3<f64>, velocity: Vector3<f64>) -> Body<f64> {
+        Body::new(2.0,Matrix3::identity(),position,velocity,UnitQuaternion::identity(),Vector3::zeros())
+    }
+
+    fn scenario(duration: f64) -> Scenario {
+        Scenario::new(ConstantWind::new(Vector3::zeros()),StandardDensity{},duration,0.01)
+            .with_vehicle("glider",body(Vector3::new(0.0,0.0,-100.0),Vector3::new(20.0,0.0,0.0)),vec![],None,Inputs::Schedule(InputSchedule::new(vec![])))
+    }
+
+    #[test]
+    fn test_completed() {
+        let result = scenario(1.0)
+            .with_vehicle("target",body(Vector3::new(100.0,0.0,-100.0),Vector3::zeros()),vec![],None,Inputs::controller(|_,_,_| vec![]))
+            .run();
+        assert_eq!(result.termination,Termination::Completed);
+        assert_relative_eq!(result.time,1.0,max_relative=1e-9);
+        assert_eq!(result.recorder("glider").unwrap().len(),101);
+        assert_eq!(result.recorders.len(),2);
+        // Falling freely for 1 s
+        assert_relative_eq!(result.world.get("glider").unwrap().position()[2],-100.0 + 9.80665 / 2.0,max_relative=1e-3);
+    }
+
+    #[test]
+    fn test_events_and_stops() {
+        let result = scenario(60.0)
+            .with_vehicle_event("release","glider",Trigger::event(|_,state| state.position()[2] > -90.0),|world| {
+                world.get_mut("glider").unwrap().add_effector("drag",Box::new(Damping::new(Vector3::zeros(),Vector3::new(2.0,2.0,2.0))));
+            })
+            .with_stop("ground contact","glider",|_,state| state.position()[2] > 0.0)
+            .run();
+        assert_eq!(result.termination,Termination::Stopped("ground contact".to_string()));
+        assert_eq!(result.events.len(),1);
+        assert_eq!(result.events[0].1,"release");
+        // 10 m falls in about 1.43 s
+        assert_relative_eq!(result.events[0].0,1.43,epsilon=0.02);
+        assert!(result.time < 60.0);
+    }
+}
@@ -0,0 +1,211 @@
+//! Scripted input schedules
+//!
+//! An [InputSchedule] adds time-varying [Signal]s to the slots of a template inputstate, for
+//! system-identification manoeuvres and open-loop response studies. Schedules can be built from
+//! standard manoeuvres or played back from CSV, and are run with [AffectedBody::run_schedule](crate::AffectedBody::run_schedule).
+
+use std::ops::IndexMut;
+
+use crate::Error;
+use crate::lookup::Table;
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// A scalar signal of time
+#[derive(Clone)]
+pub enum Signal<T: Float = DefaultFloatRepr> {
+    /// Constant value
+    Constant(T),
+    /// Step of `amplitude` at `start` (s)
+    Step {
+        /// Start time (s)
+        start: T,
+        /// Step amplitude
+        amplitude: T,
+    },
+    /// Doublet of `+amplitude` then `-amplitude`, each held for `width` (s)
+    Doublet {
+        /// Start time (s)
+        start: T,
+        /// Duration of each half (s)
+        width: T,
+        /// Pulse amplitude
+        amplitude: T,
+    },
+    /// 3-2-1-1 multistep of alternating pulses lasting 3, 2, 1 and 1 units of `unit` (s)
+    Multistep3211 {
+        /// Start time (s)
+        start: T,
+        /// Duration of one unit (s)
+        unit: T,
+        /// Pulse amplitude
+        amplitude: T,
+    },
+    /// Linear frequency sweep from `f_start` to `f_end` (Hz) over `duration` (s)
+    Chirp {
+        /// Start time (s)
+        start: T,
+        /// Sweep duration (s)
+        duration: T,
+        /// Start frequency (Hz)
+        f_start: T,
+        /// End frequency (Hz)
+        f_end: T,
+        /// Sine amplitude
+        amplitude: T,
+    },
+    /// Linear interpolation of a 1D table in time, holding the end values
+    Samples(Table<T>),
+}
+
+impl<T: Float> Signal<T> {
+    /// Return the signal value at `time` (s)
+    pub fn value(&self, time: T) -> T {
+        match self {
+            Signal::Constant(value) => *value,
+            Signal::Step { start, amplitude } => if time >= *start { *amplitude } else { T::zero() },
+            Signal::Doublet { start, width, amplitude } => Self::pulses(time - *start,*width,*amplitude,&[1,1]),
+            Signal::Multistep3211 { start, unit, amplitude } => Self::pulses(time - *start,*unit,*amplitude,&[3,2,1,1]),
+            Signal::Chirp { start, duration, f_start, f_end, amplitude } => {
+                let t = time - *start;
+                if t < T::zero() || t > *duration {
+                    return T::zero();
+                }
+                let phase = T::two_pi() * (*f_start * t + (*f_end - *f_start) * t * t / (constant::<T>(2.0) * *duration));
+                *amplitude * <T as num_traits::Float>::sin(phase)
+            },
+            Signal::Samples(table) => table.lookup(&[time]),
+        }
+    }
+
+    /// Return the value of alternating pulses lasting `units × unit` at `time` after the start
+    fn pulses(time: T, unit: T, amplitude: T, units: &[usize]) -> T {
+        if time < T::zero() {
+            return T::zero();
+        }
+        let mut end = T::zero();
+        let mut sign = T::one();
+        for &count in units {
+            end += unit * constant::<T>(count as f64);
+            if time < end {
+                return sign * amplitude;
+            }
+            sign = -sign;
+        }
+        T::zero()
+    }
+}
+
+/// Schedule of [Signal]s added to the slots of a template inputstate
+#[derive(Clone)]
+pub struct InputSchedule<I, T: Float = DefaultFloatRepr> {
+    /// Inputstate to which the signals are added
+    template: I,
+    /// Signals and the inputstate slots they drive
+    signals: Vec<(usize,Signal<T>)>,
+}
+
+impl<I: Clone + IndexMut<usize,Output=T>, T: Float> InputSchedule<I,T> {
+    /// Create a new [InputSchedule] with no signals, producing copies of `template`
+    ///
+    /// The template is typically the trimmed inputstate, so manoeuvres are perturbations about trim.
+    pub fn new(template: I) -> Self {
+        InputSchedule {
+            template,
+            signals: Vec::new(),
+        }
+    }
+
+    /// Add `signal` to the inputstate `slot`
+    pub fn with_signal(mut self, slot: usize, signal: Signal<T>) -> Self {
+        self.signals.push((slot,signal));
+        self
+    }
+
+    /// Create a schedule which plays back CSV data on top of `template`
+    ///
+    /// The first row is a header, and the first column is time (s). Each subsequent column is named
+    /// by the inputstate slot it drives and is linearly interpolated in time, holding the first and
+    /// last values outside the data.
+    pub fn from_csv(template: I, contents: &str) -> Result<Self,Error> {
+        let mut lines = contents.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+        let header = lines.next().ok_or_else(|| Error::Parse("Missing CSV header".to_string()))?;
+        let slots = header.split(',').skip(1)
+            .map(|field| field.trim().parse::<usize>().map_err(|_| Error::Parse(format!("CSV column '{}' is not an inputstate slot",field.trim()))))
+            .collect::<Result<Vec<usize>,Error>>()?;
+
+        let mut times = Vec::new();
+        let mut columns: Vec<Vec<T>> = vec![Vec::new(); slots.len()];
+        for (row, line) in lines.enumerate() {
+            let values = line.split(',')
+                .map(|field| field.trim().parse::<f64>().map(constant::<T>).map_err(|_| Error::Parse(format!("Invalid number '{}' in CSV row {}",field.trim(),row + 1))))
+                .collect::<Result<Vec<T>,Error>>()?;
+            if values.len() != slots.len() + 1 {
+                return Err(Error::Parse(format!("CSV row {} has {} columns, expected {}",row + 1,values.len(),slots.len() + 1)));
+            }
+            times.push(values[0]);
+            for (column, &value) in columns.iter_mut().zip(values[1..].iter()) {
+                column.push(value);
+            }
+        }
+        if times.is_empty() {
+            return Err(Error::Parse("CSV contains no data".to_string()));
+        }
+        if times.windows(2).any(|pair| pair[1] <= pair[0]) {
+            return Err(Error::Parse("CSV times are not strictly increasing".to_string()));
+        }
+
+        let mut schedule = InputSchedule::new(template);
+        for (slot, column) in slots.into_iter().zip(columns) {
+            schedule = schedule.with_signal(slot,Signal::Samples(Table::new_1d(times.clone(),column)));
+        }
+        Ok(schedule)
+    }
+
+    /// Return the inputstate at `time` (s)
+    pub fn inputs(&self, time: T) -> I {
+        let mut inputs = self.template.clone();
+        for (slot, signal) in &self.signals {
+            inputs[*slot] += signal.value(time);
+        }
+        inputs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_manoeuvres() {
+        let doublet = Signal::Doublet { start: 1.0, width: 0.5, amplitude: 2.0 };
+        assert_relative_eq!(doublet.value(0.9),0.0);
+        assert_relative_eq!(doublet.value(1.2),2.0);
+        assert_relative_eq!(doublet.value(1.7),-2.0);
+        assert_relative_eq!(doublet.value(2.1),0.0);
+
+        let multistep = Signal::Multistep3211 { start: 0.0, unit: 1.0, amplitude: 1.0 };
+        let values: Vec<f64> = [0.5,2.5,3.5,5.5,6.5,7.5].iter().map(|&t| multistep.value(t)).collect();
+        assert_eq!(values,vec![1.0,1.0,-1.0,1.0,-1.0,0.0]);
+
+        let chirp = Signal::Chirp { start: 0.0, duration: 10.0, f_start: 0.1, f_end: 2.0, amplitude: 1.0 };
+        assert_relative_eq!(chirp.value(0.0),0.0);
+        assert_relative_eq!(chirp.value(11.0),0.0);
+        assert!(chirp.value(1.0_f64).abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_schedule_and_csv() {
+        let schedule = InputSchedule::new(vec![0.1,0.5])
+            .with_signal(0,Signal::Step { start: 1.0, amplitude: 0.2 });
+        assert_relative_eq!(schedule.inputs(0.0)[0],0.1);
+        assert_relative_eq!(schedule.inputs(2.0)[0],0.3);
+        assert_relative_eq!(schedule.inputs(2.0)[1],0.5);
+
+        let csv = "time,1\n0.0,0.0\n1.0,1.0\n";
+        let playback = InputSchedule::from_csv(vec![0.0,0.5],csv).unwrap();
+        assert_relative_eq!(playback.inputs(0.5)[1],1.0);
+        assert!(InputSchedule::<Vec<f64>>::from_csv(vec![0.0],"time,elevator\n0,0\n").is_err());
+        assert!(InputSchedule::<Vec<f64>>::from_csv(vec![0.0],"time,0\n0,0,1\n").is_err());
+    }
+}
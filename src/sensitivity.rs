@@ -0,0 +1,162 @@
+//! Trajectory sensitivity
+//!
+//! [SensitivityAnalysis] finds the forward sensitivities `dx/dp` of a simulated trajectory to named
+//! parameters, at every recorded time, for design optimisation and uncertainty quantification. The
+//! simulation is supplied as a closure which builds and runs a vehicle from the parameter values,
+//! typically with [AffectedBody::run](crate::AffectedBody::run), and it is repeated with each parameter
+//! perturbed either side of nominal to form central differences.
+
+use crate::recorder::Recorder;
+use crate::types::StateVector;
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Trajectory sensitivities to a set of parameters
+#[derive(Clone,Debug)]
+pub struct TrajectorySensitivity<T: Float = DefaultFloatRepr> {
+    /// Recorded times (s)
+    pub times: Vec<T>,
+    /// Nominal trajectory
+    pub nominal: Vec<StateVector<T>>,
+    /// Parameter names
+    pub parameters: Vec<String>,
+    /// Statevector sensitivity at each time, for each parameter
+    pub sensitivities: Vec<Vec<StateVector<T>>>,
+}
+
+impl<T: Float> TrajectorySensitivity<T> {
+    /// Return the statevector sensitivity history for the parameter called `name`
+    pub fn sensitivity(&self, name: &str) -> Option<&[StateVector<T>]> {
+        self.parameters.iter().position(|parameter| parameter == name).map(|idx| self.sensitivities[idx].as_slice())
+    }
+
+    /// Return the statevector sensitivity at the final time for the parameter called `name`
+    pub fn final_sensitivity(&self, name: &str) -> Option<StateVector<T>> {
+        self.sensitivity(name).and_then(|history| history.last().copied())
+    }
+}
+
+/// Finite-difference trajectory sensitivity analysis
+#[derive(Clone,Debug)]
+pub struct SensitivityAnalysis<T: Float = DefaultFloatRepr> {
+    /// Parameter names and nominal values
+    parameters: Vec<(String,T)>,
+    /// Perturbation relative to the magnitude of each parameter
+    relative_step: T,
+}
+
+impl<T: Float> Default for SensitivityAnalysis<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> SensitivityAnalysis<T> {
+    /// Create a new [SensitivityAnalysis] with no parameters
+    pub fn new() -> Self {
+        SensitivityAnalysis {
+            parameters: Vec::new(),
+            relative_step: <T as num_traits::Float>::cbrt(T::epsilon()),
+        }
+    }
+
+    /// Add the parameter called `name` with `nominal` value
+    pub fn with_parameter(mut self, name: &str, nominal: T) -> Self {
+        self.parameters.push((name.to_string(),nominal));
+        self
+    }
+
+    /// Set the perturbation relative to the magnitude of each parameter
+    ///
+    /// Parameters with magnitudes below 1 are perturbed by the relative step itself.
+    pub fn with_relative_step(mut self, relative_step: T) -> Self {
+        self.relative_step = relative_step;
+        self
+    }
+
+    /// Return the nominal parameter values, in order
+    pub fn nominal(&self) -> Vec<T> {
+        self.parameters.iter().map(|(_, value)| *value).collect()
+    }
+
+    /// Find the sensitivities of the trajectory recorded by `simulate`
+    ///
+    /// The simulation is called with the parameter values in the order they were added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the perturbed simulations record a different number of samples from the nominal simulation.
+    pub fn run<F: FnMut(&[T]) -> Recorder<T>>(&self, mut simulate: F) -> TrajectorySensitivity<T> {
+        let nominal_values = self.nominal();
+        let nominal = simulate(&nominal_values);
+
+        let sensitivities = (0..self.parameters.len()).map(|idx| {
+            let h = self.relative_step * <T as num_traits::Float>::max(T::one(),<T as num_traits::Float>::abs(nominal_values[idx]));
+            let mut values = nominal_values.clone();
+            values[idx] = nominal_values[idx] + h;
+            let upper = simulate(&values);
+            values[idx] = nominal_values[idx] - h;
+            let lower = simulate(&values);
+            if upper.len() != nominal.len() || lower.len() != nominal.len() {
+                panic!("Perturbed runs recorded {} and {} samples, expected {}",upper.len(),lower.len(),nominal.len());
+            }
+            upper.samples().iter().zip(lower.samples()).map(|(upper, lower)| (upper.state - lower.state) / (h + h)).collect()
+        }).collect();
+
+        TrajectorySensitivity {
+            times: nominal.times().collect(),
+            nominal: nominal.samples().iter().map(|sample| sample.state).collect(),
+            parameters: self.parameters.iter().map(|(name, _)| name.clone()).collect(),
+            sensitivities,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AeroEffect,AirState,Body,AeroBody,AffectedBody};
+    use crate::types::{Vector3,Matrix3,Force,Torque};
+    use approx::assert_relative_eq;
+
+    struct Thrust(f64);
+
+    impl AeroEffect<Vec<f64>> for Thrust {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3, _inputstate: &Vec<f64>) -> (Force,Torque) {
+            (Force::body(self.0,0.0,0.0),Torque::body(0.0,0.0,0.0))
+        }
+    }
+
+    fn simulate(values: &[f64]) -> Recorder {
+        let body = Body::new_at_origin(values[0],Matrix3::identity());
+        let mut vehicle = AffectedBody::new(AeroBody::new(body),vec![Box::new(Thrust(values[1]))]);
+        vehicle.run(2.0,0.01,|_,_,_| vec![])
+    }
+
+    #[test]
+    fn test_analytic_sensitivity() {
+        // x = F t² / 2m
+        let analysis = SensitivityAnalysis::new().with_parameter("mass",2.0).with_parameter("thrust",4.0);
+        let result = analysis.run(simulate);
+        assert_eq!(result.times.len(),201);
+        assert_relative_eq!(result.nominal[200][0],4.0,max_relative=1e-9);
+
+        let dx_dm = result.final_sensitivity("mass").unwrap();
+        assert_relative_eq!(dx_dm[0],-0.5 * 4.0 / 4.0 * 4.0,max_relative=1e-6);
+        assert_relative_eq!(dx_dm[3],-4.0 / 4.0 * 2.0,max_relative=1e-6);
+        let dx_df = result.final_sensitivity("thrust").unwrap();
+        assert_relative_eq!(dx_df[0],0.5 * 4.0 / 2.0,max_relative=1e-6);
+        // Falling under gravity does not depend on mass
+        assert_relative_eq!(dx_dm[2],0.0,epsilon=1e-6);
+        assert!(result.sensitivity("drag").is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mismatched_runs() {
+        SensitivityAnalysis::new().with_parameter("duration",1.0).with_relative_step(0.1).run(|values: &[f64]| {
+            let body = Body::new_at_origin(1.0,Matrix3::identity());
+            let mut vehicle = AffectedBody::<Vec<f64>>::new(AeroBody::new(body),vec![]);
+            vehicle.run(values[0],1e-3,|_,_,_| vec![])
+        });
+    }
+}
@@ -0,0 +1,342 @@
+use crate::aero::{GAMMA_AIR,R_AIR,ISA_STANDARD_TEMPERATURE,ISA_STANDARD_PRESSURE};
+use crate::types::{Vector3,Float,DefaultFloatRepr,constant};
+use super::{Sensor,Measurement,Truth,SampleClock,pressure_altitude};
+
+/// Return the pitot total pressure (Pa) for static pressure `static_pressure` (Pa) and Mach number `mach`
+///
+/// Beyond Mach 1, this is the total pressure behind the normal shock ahead of the probe.
+fn total_pressure<T: Float>(static_pressure: T, mach: T) -> T {
+    let gamma = constant::<T>(GAMMA_AIR);
+    let one = T::one();
+    let two = constant::<T>(2.0);
+    let m2 = mach * mach;
+    let exponent = gamma / (gamma - one);
+    if mach <= one {
+        static_pressure * <T as num_traits::Float>::powf(one + (gamma - one) / two * m2,exponent)
+    } else {
+        // Rayleigh pitot formula
+        let shock = (gamma + one) * (gamma + one) * m2 / (constant::<T>(4.0) * gamma * m2 - two * (gamma - one));
+        static_pressure * <T as num_traits::Float>::powf(shock,exponent) * (one - gamma + two * gamma * m2) / (gamma + one)
+    }
+}
+
+/// Return the calibrated airspeed (m·s<sup>-1</sup>) for `impact_pressure` (Pa)
+fn calibrated_airspeed<T: Float>(impact_pressure: T) -> T {
+    let gamma = constant::<T>(GAMMA_AIR);
+    let p0 = constant::<T>(ISA_STANDARD_PRESSURE);
+    let a0 = <T as num_traits::Float>::sqrt(constant::<T>(GAMMA_AIR * R_AIR * ISA_STANDARD_TEMPERATURE));
+    let ratio = <T as num_traits::Float>::powf(impact_pressure / p0 + T::one(),(gamma - T::one()) / gamma) - T::one();
+    a0 * <T as num_traits::Float>::sqrt(<T as num_traits::Float>::max(T::zero(),constant::<T>(2.0) / (gamma - T::one()) * ratio))
+}
+
+/// Measurement from a [PitotStatic] system
+#[derive(Copy,Clone,Debug)]
+pub struct AirDataMeasurement<T: Float = DefaultFloatRepr> {
+    /// Time of the measurement (s)
+    pub time: T,
+    /// Measured static pressure (Pa)
+    pub static_pressure: T,
+    /// Measured impact pressure, the difference between total and static pressure (Pa)
+    pub impact_pressure: T,
+    /// Indicated airspeed (m·s<sup>-1</sup>)
+    pub indicated_airspeed: T,
+    /// Pressure altitude above the ISA standard sea-level pressure (m)
+    pub pressure_altitude: T,
+}
+
+impl<T: Float> Measurement<T> for AirDataMeasurement<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+
+    fn retimed(&self, time: T) -> Self {
+        AirDataMeasurement { time, ..*self }
+    }
+
+    fn map_values<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        AirDataMeasurement {
+            time: self.time,
+            static_pressure: f(self.static_pressure),
+            impact_pressure: f(self.impact_pressure),
+            indicated_airspeed: f(self.indicated_airspeed),
+            pressure_altitude: f(self.pressure_altitude),
+        }
+    }
+}
+
+/// Pitot-static system measuring indicated airspeed and pressure altitude
+///
+/// Indicated airspeed is found from the impact pressure with the compressible calibration used by
+/// airspeed indicators, so it is the calibrated airspeed for a perfect installation. The static port
+/// position error is a fraction of the dynamic pressure added to the static pressure, and the
+/// pneumatic lag of the lines is modelled as a first-order lag on each pressure.
+#[derive(Clone,Debug)]
+pub struct PitotStatic<T: Float = DefaultFloatRepr> {
+    /// Sample clock
+    clock: SampleClock<T>,
+    /// Static pressure error as a fraction of the dynamic pressure
+    position_error: T,
+    /// Time constant of the pneumatic lag (s)
+    lag: T,
+    /// Time of the previous update with the lagged static and total pressures
+    pressures: Option<(T,T,T)>,
+}
+
+impl<T: Float> PitotStatic<T> {
+    /// Create a new perfect [PitotStatic] system sampling at `rate` (Hz), or at every update if zero
+    pub fn new(rate: T) -> Self {
+        PitotStatic {
+            clock: SampleClock::new(rate),
+            position_error: T::zero(),
+            lag: T::zero(),
+            pressures: None,
+        }
+    }
+
+    /// Set the static pressure error as a fraction of the dynamic pressure
+    ///
+    /// A negative position error lowers the static pressure, so the system over-reads both airspeed and altitude.
+    pub fn with_position_error(mut self, position_error: T) -> Self {
+        self.position_error = position_error;
+        self
+    }
+
+    /// Set the time constant of the pneumatic lag (s)
+    pub fn with_lag(mut self, lag: T) -> Self {
+        self.lag = lag;
+        self
+    }
+}
+
+impl<T: Float> Sensor<T> for PitotStatic<T> {
+    type Measurement = AirDataMeasurement<T>;
+
+    fn update(&mut self, truth: &Truth<T>) -> Option<AirDataMeasurement<T>> {
+        let static_pressure = truth.pressure + self.position_error * truth.airstate.q;
+        let total_pressure = total_pressure(truth.pressure,truth.airstate.mach);
+
+        let (static_pressure, total_pressure) = match self.pressures {
+            Some((last, lagged_static, lagged_total)) if self.lag > T::zero() => {
+                let gain = T::one() - <T as num_traits::Float>::exp(-(truth.time - last) / self.lag);
+                (lagged_static + (static_pressure - lagged_static) * gain,lagged_total + (total_pressure - lagged_total) * gain)
+            },
+            _ => (static_pressure,total_pressure),
+        };
+        self.pressures = Some((truth.time,static_pressure,total_pressure));
+
+        if !self.clock.due(truth.time) {
+            return None;
+        }
+        let impact_pressure = total_pressure - static_pressure;
+        Some(AirDataMeasurement {
+            time: truth.time,
+            static_pressure,
+            impact_pressure,
+            indicated_airspeed: calibrated_airspeed(impact_pressure),
+            pressure_altitude: pressure_altitude(static_pressure,constant::<T>(ISA_STANDARD_PRESSURE)),
+        })
+    }
+}
+
+/// Flow angle measured by a [Vane]
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum VaneAxis {
+    /// Angle of attack
+    Alpha,
+    /// Angle of sideslip
+    Beta,
+}
+
+/// Measurement from a [Vane]
+#[derive(Copy,Clone,Debug)]
+pub struct VaneMeasurement<T: Float = DefaultFloatRepr> {
+    /// Time of the measurement (s)
+    pub time: T,
+    /// Vane angle (rad)
+    pub angle: T,
+}
+
+impl<T: Float> Measurement<T> for VaneMeasurement<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+
+    fn retimed(&self, time: T) -> Self {
+        VaneMeasurement { time, ..*self }
+    }
+
+    fn map_values<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        VaneMeasurement { time: self.time, angle: f(self.angle) }
+    }
+}
+
+/// Flow-angle vane measuring angle of attack or sideslip
+///
+/// The vane senses the local flow at its mounting point, which includes the flow induced by the body
+/// rotation, so a vane on a nose boom reads the pitch rate as well as the angle of attack. The vane
+/// itself is a second-order system with a natural frequency and damping ratio.
+#[derive(Clone,Debug)]
+pub struct Vane<T: Float = DefaultFloatRepr> {
+    /// Flow angle measured
+    axis: VaneAxis,
+    /// Sample clock
+    clock: SampleClock<T>,
+    /// Body-frame position relative to the centre of gravity (m)
+    position: Vector3<T>,
+    /// Natural frequency and damping ratio of the vane, or `None` for a vane with no dynamics
+    dynamics: Option<(T,T)>,
+    /// Time of the previous update with the vane angle and angular rate
+    vane: Option<(T,T,T)>,
+}
+
+impl<T: Float> Vane<T> {
+    /// Create a new [Vane] with no dynamics at the centre of gravity
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - Flow angle measured
+    /// * `rate` - Sample rate (Hz), or zero to sample at every update
+    pub fn new(axis: VaneAxis, rate: T) -> Self {
+        Vane {
+            axis,
+            clock: SampleClock::new(rate),
+            position: Vector3::zeros(),
+            dynamics: None,
+            vane: None,
+        }
+    }
+
+    /// Mount the vane at body-frame `position` relative to the centre of gravity (m)
+    pub fn with_position(mut self, position: Vector3<T>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Set the vane `natural_frequency` (rad·s<sup>-1</sup>) and `damping_ratio`
+    pub fn with_dynamics(mut self, natural_frequency: T, damping_ratio: T) -> Self {
+        self.dynamics = Some((natural_frequency,damping_ratio));
+        self
+    }
+
+    /// Return the local flow angle at the vane (rad)
+    fn local_angle(&self, truth: &Truth<T>) -> T {
+        let airstate = &truth.airstate;
+        let (sin_alpha, cos_alpha) = <T as num_traits::Float>::sin_cos(airstate.alpha);
+        let (sin_beta, cos_beta) = <T as num_traits::Float>::sin_cos(airstate.beta);
+        let air = Vector3::new(cos_alpha * cos_beta,sin_beta,sin_alpha * cos_beta) * airstate.airspeed + truth.rates().cross(&self.position);
+        match self.axis {
+            VaneAxis::Alpha => <T as num_traits::Float>::atan2(air[2],air[0]),
+            VaneAxis::Beta => {
+                let airspeed = air.norm();
+                if airspeed != T::zero() { <T as num_traits::Float>::asin(air[1] / airspeed) } else { T::zero() }
+            },
+        }
+    }
+}
+
+impl<T: Float> Sensor<T> for Vane<T> {
+    type Measurement = VaneMeasurement<T>;
+
+    fn update(&mut self, truth: &Truth<T>) -> Option<VaneMeasurement<T>> {
+        let target = self.local_angle(truth);
+        let (angle, angle_rate) = match (self.vane, self.dynamics) {
+            (Some((last, angle, angle_rate)), Some((natural_frequency, damping_ratio))) => {
+                // Integrate the vane in substeps short relative to its natural period
+                let delta_t = truth.time - last;
+                let substeps = <T as num_traits::Float>::ceil(delta_t * natural_frequency / constant::<T>(0.05));
+                let substeps = <T as num_traits::Float>::max(substeps,T::one());
+                let dt = delta_t / substeps;
+                match substeps.to_usize().filter(|_| delta_t.is_finite()) {
+                    Some(substeps) => {
+                        let (mut angle, mut angle_rate) = (angle,angle_rate);
+                        for _ in 0..substeps {
+                            let angle_accel = natural_frequency * natural_frequency * (target - angle) - constant::<T>(2.0) * damping_ratio * natural_frequency * angle_rate;
+                            angle_rate += angle_accel * dt;
+                            angle += angle_rate * dt;
+                        }
+                        (angle,angle_rate)
+                    },
+                    // The vane has settled over a gap too long to integrate
+                    None => (target,T::zero()),
+                }
+            },
+            _ => (target,T::zero()),
+        };
+        self.vane = Some((truth.time,angle,angle_rate));
+
+        if self.clock.due(truth.time) {
+            Some(VaneMeasurement { time: truth.time, angle })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Body,AeroBody,AffectedBody};
+    use crate::density_models::StandardAtmosphere;
+    use crate::wind_models::ConstantWind;
+    use crate::types::Matrix3;
+    use approx::assert_relative_eq;
+
+    fn truth(altitude: f64, airspeed: f64) -> Truth {
+        let body = Body::new(1.0,Matrix3::identity(),Vector3::new(0.0,0.0,-altitude),Vector3::new(airspeed,0.0,0.0),Default::default(),Vector3::zeros());
+        let vehicle = AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::with_density_model(body,ConstantWind::new(Vector3::zeros()),StandardAtmosphere::new()),vec![]);
+        Truth::new(0.0,&vehicle)
+    }
+
+    #[test]
+    fn test_indicated_airspeed() {
+        let mut pitot = PitotStatic::new(0.0);
+        let sea_level = pitot.update(&truth(0.0,50.0)).unwrap();
+        assert_relative_eq!(sea_level.indicated_airspeed,50.0,max_relative=1e-6);
+        assert_relative_eq!(sea_level.pressure_altitude,0.0,epsilon=0.01);
+
+        // At altitude the indicated airspeed is close to the equivalent airspeed at low Mach number
+        let truth = truth(3000.0,80.0);
+        let high = PitotStatic::new(0.0).update(&truth).unwrap();
+        assert_relative_eq!(high.pressure_altitude,3000.0,max_relative=1e-3);
+        assert_relative_eq!(high.indicated_airspeed,80.0 * (truth.airstate.density / 1.225).sqrt(),max_relative=0.01);
+
+        // Position error
+        let error = PitotStatic::new(0.0).with_position_error(-0.05).update(&truth).unwrap();
+        assert!(error.indicated_airspeed > high.indicated_airspeed && error.pressure_altitude > high.pressure_altitude);
+        assert_relative_eq!(error.static_pressure,high.static_pressure - 0.05 * truth.airstate.q);
+    }
+
+    #[test]
+    fn test_pneumatic_lag() {
+        let mut pitot = PitotStatic::new(0.0).with_lag(0.5);
+        pitot.update(&truth(0.0,30.0));
+        let lagged = pitot.update(&Truth { time: 0.5, ..truth(0.0,60.0) }).unwrap();
+        // One time constant covers 63% of a step in impact pressure
+        let impact = |airspeed| total_pressure(truth(0.0,airspeed).pressure,truth(0.0,airspeed).airstate.mach) - truth(0.0,airspeed).pressure;
+        assert_relative_eq!(lagged.impact_pressure,impact(30.0) + (impact(60.0) - impact(30.0)) * (1.0 - (-1.0_f64).exp()),max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_vane() {
+        let mut step = truth(0.0,50.0);
+        step.airstate.alpha = 0.1;
+        let mut vane = Vane::new(VaneAxis::Alpha,0.0).with_dynamics(30.0,0.3);
+        vane.update(&Truth { airstate: truth(0.0,50.0).airstate, ..step });
+        // Lightly damped, the vane overshoots then settles on the new angle
+        let angles: Vec<f64> = (1..=200).map(|idx| vane.update(&Truth { time: idx as f64 * 0.01, ..step }).unwrap().angle).collect();
+        assert!(angles.iter().cloned().fold(0.0,f64::max) > 0.12);
+        assert_relative_eq!(angles[199],0.1,epsilon=1e-4);
+
+        // A gap too long to integrate settles the vane rather than panicking
+        let mut lagged = Vane::new(VaneAxis::Alpha,0.0).with_dynamics(30.0,0.3);
+        lagged.update(&Truth { airstate: truth(0.0,50.0).airstate, ..step });
+        assert_relative_eq!(lagged.update(&Truth { time: f64::INFINITY, ..step }).unwrap().angle,0.1);
+
+        // A nose-boom vane reads the pitch rate
+        step.state[11] = 0.5;
+        let boom = Vane::new(VaneAxis::Alpha,0.0).with_position(Vector3::new(2.0,0.0,0.0)).update(&step).unwrap();
+        let local = Vector3::new(0.1_f64.cos(),0.0,0.1_f64.sin()) * 50.0 + Vector3::new(0.0,0.5,0.0).cross(&Vector3::new(2.0,0.0,0.0));
+        assert_relative_eq!(boom.angle,local[2].atan2(local[0]));
+        assert!(boom.angle < 0.1);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::aero::ISA_STANDARD_PRESSURE;
+use crate::random::Rng;
+use crate::types::{Float,DefaultFloatRepr,constant};
+use super::{Sensor,Measurement,Truth,SampleClock,pressure_altitude};
+use super::errors::{ErrorModel,ErrorChain,WhiteNoise};
+
+/// Measurement from a [Barometer]
+#[derive(Copy,Clone,Debug)]
+pub struct BaroMeasurement<T: Float = DefaultFloatRepr> {
+    /// Time of the measurement (s)
+    pub time: T,
+    /// Measured static pressure (Pa)
+    pub pressure: T,
+    /// Altitude above the level of the QNH pressure setting (m)
+    pub altitude: T,
+}
+
+impl<T: Float> Measurement<T> for BaroMeasurement<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+
+    fn retimed(&self, time: T) -> Self {
+        BaroMeasurement { time, ..*self }
+    }
+
+    fn map_values<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        BaroMeasurement { time: self.time, pressure: f(self.pressure), altitude: f(self.altitude) }
+    }
+}
+
+/// Barometric altimeter
+///
+/// The barometer measures the static pressure of the vehicle [DensityModel](crate::DensityModel), the
+/// same pressure field as is used for the dynamics, and converts it to altitude with the ISA troposphere
+/// relative to the QNH pressure setting. With the standard setting it reads pressure altitude, and with
+/// the sea-level pressure of the atmosphere it reads altitude above sea level.
+#[derive(Clone,Debug)]
+pub struct Barometer<T: Float = DefaultFloatRepr> {
+    /// Sample clock
+    clock: SampleClock<T>,
+    /// Pressure setting (Pa)
+    qnh: T,
+    /// Constant pressure bias (Pa)
+    bias: T,
+    /// Stochastic pressure errors
+    errors: ErrorChain<T>,
+    /// Noise source
+    rng: Rng,
+}
+
+impl<T: Float> Barometer<T> {
+    /// Create a new perfect [Barometer] with the standard pressure setting
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - Sample rate (Hz), or zero to sample at every update
+    /// * `seed` - Seed for the measurement noise
+    pub fn new(rate: T, seed: u64) -> Self {
+        Barometer {
+            clock: SampleClock::new(rate),
+            qnh: constant::<T>(ISA_STANDARD_PRESSURE),
+            bias: T::zero(),
+            errors: ErrorChain::new(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Set the QNH pressure setting (Pa)
+    pub fn with_qnh(mut self, qnh: T) -> Self {
+        self.qnh = qnh;
+        self
+    }
+
+    /// Set the constant pressure `bias` and the standard deviation of white `noise` on each sample (Pa)
+    pub fn with_errors(mut self, bias: T, noise: T) -> Self {
+        self.bias = bias;
+        self.errors = ErrorChain::new().with(WhiteNoise::new(noise));
+        self
+    }
+
+    /// Replace the stochastic pressure errors with `model`
+    ///
+    /// The constant bias is kept.
+    pub fn with_error_model(mut self, model: ErrorChain<T>) -> Self {
+        self.errors = model;
+        self
+    }
+
+    /// Change the QNH pressure setting (Pa), as a pilot would in flight
+    pub fn set_qnh(&mut self, qnh: T) {
+        self.qnh = qnh;
+    }
+}
+
+impl<T: Float> Sensor<T> for Barometer<T> {
+    type Measurement = BaroMeasurement<T>;
+
+    fn update(&mut self, truth: &Truth<T>) -> Option<BaroMeasurement<T>> {
+        if !self.clock.due(truth.time) {
+            return None;
+        }
+        let pressure = self.errors.corrupt(truth.time,truth.pressure + self.bias,&mut self.rng);
+        Some(BaroMeasurement {
+            time: truth.time,
+            pressure,
+            altitude: pressure_altitude(pressure,self.qnh),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Body,AeroBody,AffectedBody};
+    use crate::density_models::StandardAtmosphere;
+    use crate::wind_models::ConstantWind;
+    use crate::sensors::Quantization;
+    use crate::types::{Vector3,Matrix3};
+    use approx::assert_relative_eq;
+
+    fn truth(altitude: f64, sea_level_pressure: f64) -> Truth {
+        let body = Body::new(1.0,Matrix3::identity(),Vector3::new(0.0,0.0,-altitude),Vector3::zeros(),Default::default(),Vector3::zeros());
+        let atmosphere = StandardAtmosphere::new().with_sea_level_pressure(sea_level_pressure);
+        let vehicle = AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::with_density_model(body,ConstantWind::new(Vector3::zeros()),atmosphere),vec![]);
+        Truth::new(0.0,&vehicle)
+    }
+
+    #[test]
+    fn test_qnh() {
+        let truth = truth(1500.0,99_500.0);
+        let mut baro = Barometer::new(0.0,1).with_qnh(99_500.0);
+        assert_relative_eq!(baro.update(&truth).unwrap().altitude,1500.0,max_relative=1e-9);
+
+        // On a low-pressure day the standard setting over-reads altitude
+        baro.set_qnh(101_325.0);
+        let pressure_altitude = baro.update(&truth).unwrap().altitude;
+        assert!(pressure_altitude > 1640.0 && pressure_altitude < 1660.0);
+    }
+
+    #[test]
+    fn test_noise() {
+        let truth = truth(300.0,101_325.0);
+        let mut baro = Barometer::new(0.0,9).with_errors(20.0,5.0);
+        let pressures: Vec<f64> = (0..10_000).map(|_| baro.update(&truth).unwrap().pressure).collect();
+        let mean = pressures.iter().sum::<f64>() / pressures.len() as f64;
+        let std_dev = (pressures.iter().map(|pressure| (pressure - mean).powi(2)).sum::<f64>() / pressures.len() as f64).sqrt();
+        assert_relative_eq!(mean,truth.pressure + 20.0,epsilon=0.2);
+        assert_relative_eq!(std_dev,5.0,max_relative=0.05);
+
+        // A 24-bit sensor resolves to about 1 Pa
+        let mut quantized = Barometer::new(0.0,9).with_error_model(ErrorChain::new().with(Quantization::new(1.0)));
+        assert_eq!(quantized.update(&truth).unwrap().pressure,truth.pressure.round());
+    }
+}
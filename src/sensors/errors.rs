@@ -0,0 +1,243 @@
+use crate::random::Rng;
+use crate::types::{Float,DefaultFloatRepr};
+
+/// Trait for stochastic sensor error models
+///
+/// Error models hold their own parameters and state, and draw random numbers from an [Rng] supplied by
+/// the sensor, so a sensor with one seed gives identical errors on every run.
+pub trait ErrorModel<T: Float = DefaultFloatRepr> {
+    /// Return `value` at `time` (s) corrupted by the error
+    fn corrupt(&mut self, time: T, value: T, rng: &mut Rng) -> T;
+}
+
+/// Zero-mean Gaussian white noise
+#[derive(Copy,Clone,Debug)]
+pub struct WhiteNoise<T: Float = DefaultFloatRepr> {
+    /// Standard deviation of each sample
+    std_dev: T,
+}
+
+impl<T: Float> WhiteNoise<T> {
+    /// Create a new [WhiteNoise] with standard deviation `std_dev`
+    pub fn new(std_dev: T) -> Self {
+        WhiteNoise { std_dev }
+    }
+}
+
+impl<T: Float> ErrorModel<T> for WhiteNoise<T> {
+    fn corrupt(&mut self, _time: T, value: T, rng: &mut Rng) -> T {
+        if self.std_dev == T::zero() {
+            return value;
+        }
+        value + rng.gaussian(T::zero(),self.std_dev)
+    }
+}
+
+/// Bias which wanders as a random walk
+///
+/// The bias changes between samples by a Gaussian step whose standard deviation grows with the square
+/// root of the time between them, as for the rate random walk of an inertial sensor.
+#[derive(Copy,Clone,Debug)]
+pub struct BiasRandomWalk<T: Float = DefaultFloatRepr> {
+    /// Random walk intensity (units·s<sup>-1/2</sup>)
+    intensity: T,
+    /// Current bias
+    bias: T,
+    /// Time of the previous sample (s)
+    last: Option<T>,
+}
+
+impl<T: Float> BiasRandomWalk<T> {
+    /// Create a new [BiasRandomWalk] starting from `initial` with `intensity` (units·s<sup>-1/2</sup>)
+    pub fn new(initial: T, intensity: T) -> Self {
+        BiasRandomWalk { intensity, bias: initial, last: None }
+    }
+
+    /// Return the current bias
+    pub fn bias(&self) -> T {
+        self.bias
+    }
+}
+
+impl<T: Float> ErrorModel<T> for BiasRandomWalk<T> {
+    fn corrupt(&mut self, time: T, value: T, rng: &mut Rng) -> T {
+        if let Some(last) = self.last {
+            let delta_t = <T as num_traits::Float>::max(time - last,T::zero());
+            self.bias += rng.gaussian(T::zero(),self.intensity * <T as num_traits::Float>::sqrt(delta_t));
+        }
+        self.last = Some(time);
+        value + self.bias
+    }
+}
+
+/// First-order Gauss-Markov error
+///
+/// The error is exponentially correlated in time, with a stationary standard deviation and a
+/// correlation time. The first sample is drawn from the stationary distribution.
+#[derive(Copy,Clone,Debug)]
+pub struct GaussMarkov<T: Float = DefaultFloatRepr> {
+    /// Stationary standard deviation
+    std_dev: T,
+    /// Correlation time (s)
+    correlation_time: T,
+    /// Time of the previous sample and the error at that time
+    state: Option<(T,T)>,
+}
+
+impl<T: Float> GaussMarkov<T> {
+    /// Create a new [GaussMarkov] error with stationary `std_dev` and `correlation_time` (s)
+    pub fn new(std_dev: T, correlation_time: T) -> Self {
+        GaussMarkov { std_dev, correlation_time, state: None }
+    }
+
+    /// Return the error at the previous sample, if there has been one
+    pub fn error(&self) -> Option<T> {
+        self.state.map(|(_, error)| error)
+    }
+}
+
+impl<T: Float> ErrorModel<T> for GaussMarkov<T> {
+    fn corrupt(&mut self, time: T, value: T, rng: &mut Rng) -> T {
+        let error = match self.state {
+            None => rng.gaussian(T::zero(),self.std_dev),
+            Some((last, error)) => {
+                let decay = <T as num_traits::Float>::exp(-(time - last) / self.correlation_time);
+                let drive = <T as num_traits::Float>::sqrt(T::one() - decay * decay);
+                decay * error + rng.gaussian(T::zero(),drive * self.std_dev)
+            },
+        };
+        self.state = Some((time,error));
+        value + error
+    }
+}
+
+/// Quantization to a fixed resolution, as by an analogue-to-digital converter
+#[derive(Copy,Clone,Debug)]
+pub struct Quantization<T: Float = DefaultFloatRepr> {
+    /// Value of the least significant bit
+    resolution: T,
+}
+
+impl<T: Float> Quantization<T> {
+    /// Create a new [Quantization] to the nearest multiple of `resolution`
+    pub fn new(resolution: T) -> Self {
+        Quantization { resolution }
+    }
+}
+
+impl<T: Float> ErrorModel<T> for Quantization<T> {
+    fn corrupt(&mut self, _time: T, value: T, _rng: &mut Rng) -> T {
+        if self.resolution <= T::zero() {
+            return value;
+        }
+        <T as num_traits::Float>::round(value / self.resolution) * self.resolution
+    }
+}
+
+/// [ErrorModel] which can be cloned behind a box
+trait BoxedErrorModel<T: Float>: ErrorModel<T> {
+    /// Return a boxed copy of the model
+    fn boxed_clone(&self) -> Box<dyn BoxedErrorModel<T>>;
+}
+
+impl<T: Float, E: ErrorModel<T> + Clone + 'static> BoxedErrorModel<T> for E {
+    fn boxed_clone(&self) -> Box<dyn BoxedErrorModel<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Sequence of [ErrorModel]s applied in order
+///
+/// Quantization should normally be last, so that it applies to the sum of the other errors. Cloning a
+/// chain copies the state of each model, so a chain can be built once and cloned for each sensor axis.
+pub struct ErrorChain<T: Float = DefaultFloatRepr> {
+    /// Error models, in the order they are applied
+    models: Vec<Box<dyn BoxedErrorModel<T>>>,
+}
+
+impl<T: Float> Default for ErrorChain<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Clone for ErrorChain<T> {
+    fn clone(&self) -> Self {
+        ErrorChain { models: self.models.iter().map(|model| model.boxed_clone()).collect() }
+    }
+}
+
+impl<T: Float> ErrorChain<T> {
+    /// Create a new [ErrorChain] with no errors
+    pub fn new() -> Self {
+        ErrorChain { models: Vec::new() }
+    }
+
+    /// Append `model` to the chain
+    pub fn with<E: ErrorModel<T> + Clone + 'static>(mut self, model: E) -> Self {
+        self.models.push(Box::new(model));
+        self
+    }
+
+    /// Return `true` if the chain has no errors
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+}
+
+impl<T: Float> ErrorModel<T> for ErrorChain<T> {
+    fn corrupt(&mut self, time: T, value: T, rng: &mut Rng) -> T {
+        self.models.iter_mut().fold(value,|value, model| model.corrupt(time,value,rng))
+    }
+}
+
+impl<T: Float> std::fmt::Debug for ErrorChain<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ErrorChain").field("models",&self.models.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// Return the mean and standard deviation of `values`
+    fn moments(values: &[f64]) -> (f64,f64) {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        (mean,(values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt())
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut rng = Rng::new(11);
+        let mut noise = WhiteNoise::new(0.5);
+        let (mean, std_dev) = moments(&(0..20_000).map(|idx| noise.corrupt(idx as f64,1.0,&mut rng)).collect::<Vec<f64>>());
+        assert_relative_eq!(mean,1.0,epsilon=0.02);
+        assert_relative_eq!(std_dev,0.5,max_relative=0.03);
+
+        // Random walk variance grows linearly with time
+        let finals: Vec<f64> = (0..2000).map(|_| {
+            let mut walk = BiasRandomWalk::new(0.0,0.1);
+            (0..=100).map(|idx| walk.corrupt(idx as f64,0.0,&mut rng)).last().unwrap()
+        }).collect();
+        assert_relative_eq!(moments(&finals).1,0.1 * 100.0_f64.sqrt(),max_relative=0.05);
+
+        // Gauss-Markov keeps its stationary deviation, with correlation decaying over the correlation time
+        let mut markov = GaussMarkov::new(2.0,5.0);
+        let errors: Vec<f64> = (0..50_000).map(|idx| markov.corrupt(idx as f64,0.0,&mut rng)).collect();
+        assert_relative_eq!(moments(&errors).1,2.0,max_relative=0.05);
+        let lag = errors.iter().zip(errors.iter().skip(5)).map(|(a, b)| a * b).sum::<f64>() / (errors.len() - 5) as f64 / 4.0;
+        assert_relative_eq!(lag,(-1.0_f64).exp(),epsilon=0.05);
+    }
+
+    #[test]
+    fn test_chain() {
+        let mut rng = Rng::new(3);
+        let mut chain = ErrorChain::new().with(BiasRandomWalk::new(0.3,0.0)).with(Quantization::new(0.25));
+        assert_eq!(chain.corrupt(0.0,1.0,&mut rng),1.25);
+        assert_eq!(chain.corrupt(1.0,-1.0,&mut rng),-0.75);
+        assert!(ErrorChain::<f64>::new().is_empty());
+        assert_eq!(ErrorChain::new().corrupt(0.0,2.0,&mut rng),2.0);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::VecDeque;
+
+use crate::Body;
+use crate::geodetic::{Geodetic,LocalTangentPlane};
+use crate::random::Rng;
+use crate::types::{Vector3,Float,DefaultFloatRepr,constant};
+use super::{Sensor,Measurement,Truth,SampleClock};
+use super::errors::{ErrorModel,GaussMarkov,WhiteNoise};
+
+/// Measurement from a [Gps] receiver
+#[derive(Copy,Clone,Debug)]
+pub struct GpsMeasurement<T: Float = DefaultFloatRepr> {
+    /// Time at which the measurement is valid (s)
+    ///
+    /// This is earlier than the time the measurement is output by the receiver latency.
+    pub time: T,
+    /// Geodetic position
+    pub position: Geodetic<T>,
+    /// North-East-Down velocity (m·s<sup>-1</sup>)
+    pub velocity: Vector3<T>,
+}
+
+impl<T: Float> Measurement<T> for GpsMeasurement<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+
+    fn retimed(&self, time: T) -> Self {
+        GpsMeasurement { time, ..*self }
+    }
+
+    fn map_values<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        let position = Geodetic::new(f(self.position.latitude),f(self.position.longitude),f(self.position.altitude));
+        GpsMeasurement { time: self.time, position, velocity: self.velocity.map(&mut f) }
+    }
+}
+
+/// Satellite navigation receiver
+///
+/// Positions are reported as geodetic coordinates, found from the vehicle position in the local tangent
+/// plane. Position errors follow a first-order Gauss-Markov process, which models the slowly-wandering
+/// error of an unaided receiver, and velocity errors are white. Measurements are output after a fixed
+/// latency, and are lost during outages and at random with a dropout probability.
+#[derive(Clone,Debug)]
+pub struct Gps<T: Float = DefaultFloatRepr> {
+    /// Sample clock
+    clock: SampleClock<T>,
+    /// Tangent plane of the vehicle position
+    plane: LocalTangentPlane<T>,
+    /// Delay between the time of validity and output of each measurement (s)
+    latency: T,
+    /// North-East-Down position errors
+    position_error: [GaussMarkov<T>; 3],
+    /// Velocity error
+    velocity_noise: WhiteNoise<T>,
+    /// Probability that each measurement is lost
+    dropout_probability: T,
+    /// Start and end times of outages (s)
+    outages: Vec<(T,T)>,
+    /// Measurements waiting to be output
+    pending: VecDeque<GpsMeasurement<T>>,
+    /// Noise source
+    rng: Rng,
+}
+
+impl<T: Float> Gps<T> {
+    /// Create a new perfect [Gps] with no latency
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - Sample rate (Hz), typically 1 to 10 Hz
+    /// * `plane` - Tangent plane of the vehicle position
+    /// * `seed` - Seed for the measurement errors and dropouts
+    pub fn new(rate: T, plane: LocalTangentPlane<T>, seed: u64) -> Self {
+        Gps {
+            clock: SampleClock::new(rate),
+            plane,
+            latency: T::zero(),
+            position_error: [GaussMarkov::new(T::zero(),T::one()); 3],
+            velocity_noise: WhiteNoise::new(T::zero()),
+            dropout_probability: T::zero(),
+            outages: Vec::new(),
+            pending: VecDeque::new(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Output each measurement `latency` seconds after its time of validity
+    pub fn with_latency(mut self, latency: T) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Set the Gauss-Markov position error
+    ///
+    /// # Arguments
+    ///
+    /// * `horizontal` - Standard deviation of the North and East position errors (m)
+    /// * `vertical` - Standard deviation of the Down position error (m)
+    /// * `correlation_time` - Correlation time of the errors (s)
+    pub fn with_position_error(mut self, horizontal: T, vertical: T, correlation_time: T) -> Self {
+        let horizontal = GaussMarkov::new(horizontal,correlation_time);
+        self.position_error = [horizontal,horizontal,GaussMarkov::new(vertical,correlation_time)];
+        self
+    }
+
+    /// Set the standard deviation of white noise on each velocity component (m·s<sup>-1</sup>)
+    pub fn with_velocity_noise(mut self, velocity_noise: T) -> Self {
+        self.velocity_noise = WhiteNoise::new(velocity_noise);
+        self
+    }
+
+    /// Lose each measurement with `probability`
+    pub fn with_dropout_probability(mut self, probability: T) -> Self {
+        self.dropout_probability = probability;
+        self
+    }
+
+    /// Add an outage from `start` to `end` (s), during which no measurements are made
+    pub fn with_outage(mut self, start: T, end: T) -> Self {
+        self.outages.push((start,end));
+        self
+    }
+
+    /// Return `true` if `time` is in an outage
+    pub fn in_outage(&self, time: T) -> bool {
+        self.outages.iter().any(|&(start, end)| time >= start && time < end)
+    }
+}
+
+impl<T: Float> Sensor<T> for Gps<T> {
+    type Measurement = GpsMeasurement<T>;
+
+    fn update(&mut self, truth: &Truth<T>) -> Option<GpsMeasurement<T>> {
+        if self.clock.due(truth.time) && !self.in_outage(truth.time) {
+            let time = truth.time;
+            let Gps { position_error, velocity_noise, rng, .. } = self;
+            let position = Vector3::from_fn(|idx, _| position_error[idx].corrupt(time,truth.state[idx],rng));
+            let velocity = Body::get_dcm_body(&truth.state) * Vector3::new(truth.state[3],truth.state[4],truth.state[5]);
+            let velocity = velocity.map(|component| velocity_noise.corrupt(time,component,rng));
+            // Always draw, so the error sequence does not depend on the dropout probability
+            let dropped = constant::<T>(self.rng.uniform()) < self.dropout_probability;
+            if !dropped {
+                self.pending.push_back(GpsMeasurement {
+                    time: truth.time,
+                    position: self.plane.to_geodetic(&position),
+                    velocity,
+                });
+            }
+        }
+
+        // Output the latest measurement whose latency has elapsed
+        let tolerance = constant::<T>(1e-9);
+        let mut latest = None;
+        while self.pending.front().is_some_and(|measurement| measurement.time + self.latency <= truth.time + tolerance) {
+            latest = self.pending.pop_front();
+        }
+        latest
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    fn plane() -> LocalTangentPlane {
+        LocalTangentPlane::new(Geodetic::from_degrees(52.0,0.5,50.0))
+    }
+
+    #[test]
+    fn test_latency_and_outage() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let inputs = vec![0.0,-0.02,0.0,0.5];
+        let mut gps = Gps::new(5.0,plane(),1).with_latency(0.1).with_outage(1.0,1.5);
+        let mut outputs = Vec::new();
+        for step in 0..=300 {
+            let time = step as f64 * 0.01;
+            if let Some(measurement) = gps.update(&Truth::new(time,&vehicle)) {
+                outputs.push((time,measurement));
+            }
+            vehicle.step(0.01,&inputs);
+        }
+        // 15 samples output by 3 s, less the 3 in the outage
+        assert_eq!(outputs.len(),12);
+        for (time, measurement) in &outputs {
+            assert_relative_eq!(time - measurement.time,0.1,epsilon=1e-9);
+        }
+        assert!(outputs.iter().all(|(_, measurement)| !(1.0..1.5).contains(&measurement.time)));
+
+        // The first sample is at the starting position and velocity
+        let first = &outputs[0].1;
+        assert_relative_eq!(first.position.altitude,150.0,epsilon=1e-6);
+        assert_relative_eq!(first.velocity[0],20.0,epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_position_error() {
+        let vehicle = test_aircraft::vehicle(100.0);
+        let truth = Truth::new(0.0,&vehicle);
+        let mut gps = Gps::new(10.0,plane(),5).with_position_error(2.0,4.0,1.0).with_dropout_probability(0.2);
+        let errors: Vec<Vector3> = (0..20_000)
+            .filter_map(|step| gps.update(&Truth { time: step as f64 * 0.1, ..truth }))
+            .map(|measurement| plane().to_local(&measurement.position) - truth.state.fixed_rows::<3>(0))
+            .collect();
+        assert_relative_eq!(errors.len() as f64,16_000.0,max_relative=0.02);
+
+        let rms = |idx: usize| (errors.iter().map(|error| error[idx] * error[idx]).sum::<f64>() / errors.len() as f64).sqrt();
+        assert_relative_eq!(rms(0),2.0,max_relative=0.2);
+        assert_relative_eq!(rms(2),4.0,max_relative=0.2);
+        // Errors are strongly correlated from sample to sample
+        let correlation = errors.windows(2).map(|pair| pair[0][0] * pair[1][0]).sum::<f64>() / (errors.len() - 1) as f64 / rms(0).powi(2);
+        assert!(correlation > 0.8);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::random::Rng;
+use crate::types::{Vector3,Float,DefaultFloatRepr};
+use super::{Sensor,Measurement,Truth,SampleClock};
+use super::errors::{ErrorModel,ErrorChain,WhiteNoise};
+
+/// Bias, scale-factor and stochastic errors of a triad of inertial sensors
+#[derive(Clone,Debug)]
+struct TriadErrors<T: Float> {
+    /// Constant bias on each axis
+    bias: Vector3<T>,
+    /// Scale-factor error on each axis, as a fraction of the true value
+    scale_factor: Vector3<T>,
+    /// Stochastic errors on each axis
+    models: [ErrorChain<T>; 3],
+}
+
+impl<T: Float> TriadErrors<T> {
+    /// Create a perfect [TriadErrors]
+    fn none() -> Self {
+        TriadErrors {
+            bias: Vector3::zeros(),
+            scale_factor: Vector3::zeros(),
+            models: [ErrorChain::new(),ErrorChain::new(),ErrorChain::new()],
+        }
+    }
+
+    /// Create a [TriadErrors] with constant `bias`, white `noise` and `scale_factor` errors
+    fn new(bias: Vector3<T>, noise: T, scale_factor: Vector3<T>) -> Self {
+        let models = ErrorChain::new().with(WhiteNoise::new(noise));
+        TriadErrors { bias, scale_factor, models: [models.clone(),models.clone(),models] }
+    }
+
+    /// Return the measurement of `value` at `time` corrupted by the errors
+    fn apply(&mut self, time: T, value: Vector3<T>, rng: &mut Rng) -> Vector3<T> {
+        let value = value + value.component_mul(&self.scale_factor) + self.bias;
+        Vector3::from_fn(|idx, _| self.models[idx].corrupt(time,value[idx],rng))
+    }
+}
+
+/// Measurement from an [Imu]
+#[derive(Copy,Clone,Debug)]
+pub struct ImuMeasurement<T: Float = DefaultFloatRepr> {
+    /// Time of the measurement (s)
+    pub time: T,
+    /// Body-frame rotation rates (rad·s<sup>-1</sup>)
+    pub rates: Vector3<T>,
+    /// Body-frame specific force (m·s<sup>-2</sup>)
+    pub specific_force: Vector3<T>,
+}
+
+impl<T: Float> Measurement<T> for ImuMeasurement<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+
+    fn retimed(&self, time: T) -> Self {
+        ImuMeasurement { time, ..*self }
+    }
+
+    fn map_values<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        ImuMeasurement { time: self.time, rates: self.rates.map(&mut f), specific_force: self.specific_force.map(&mut f) }
+    }
+}
+
+/// Inertial measurement unit with rate gyros and accelerometers aligned with the body axes
+///
+/// The accelerometers measure specific force, so they read `(0, 0, -g)` when level and at rest. When
+/// the unit is mounted away from the centre of gravity, they also sense the tangential and centripetal
+/// acceleration of the mounting point.
+#[derive(Clone,Debug)]
+pub struct Imu<T: Float = DefaultFloatRepr> {
+    /// Sample clock
+    clock: SampleClock<T>,
+    /// Body-frame position relative to the centre of gravity (m)
+    position: Vector3<T>,
+    /// Gyro errors
+    gyro: TriadErrors<T>,
+    /// Accelerometer errors
+    accelerometer: TriadErrors<T>,
+    /// Noise source
+    rng: Rng,
+}
+
+impl<T: Float> Imu<T> {
+    /// Create a new perfect [Imu] at the centre of gravity
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - Sample rate (Hz), or zero to sample at every update
+    /// * `seed` - Seed for the measurement noise
+    pub fn new(rate: T, seed: u64) -> Self {
+        Imu {
+            clock: SampleClock::new(rate),
+            position: Vector3::zeros(),
+            gyro: TriadErrors::none(),
+            accelerometer: TriadErrors::none(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Mount the unit at body-frame `position` relative to the centre of gravity (m)
+    pub fn with_position(mut self, position: Vector3<T>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Set the gyro errors
+    ///
+    /// # Arguments
+    ///
+    /// * `bias` - Constant bias on each axis (rad·s<sup>-1</sup>)
+    /// * `noise` - Standard deviation of white noise on each sample (rad·s<sup>-1</sup>)
+    /// * `scale_factor` - Scale-factor error on each axis, as a fraction of the true rate
+    pub fn with_gyro_errors(mut self, bias: Vector3<T>, noise: T, scale_factor: Vector3<T>) -> Self {
+        self.gyro = TriadErrors::new(bias,noise,scale_factor);
+        self
+    }
+
+    /// Replace the stochastic gyro errors with a copy of `model` on each axis
+    ///
+    /// The constant bias and scale-factor errors are kept.
+    pub fn with_gyro_error_model(mut self, model: ErrorChain<T>) -> Self {
+        self.gyro.models = [model.clone(),model.clone(),model];
+        self
+    }
+
+    /// Set the accelerometer errors
+    ///
+    /// # Arguments
+    ///
+    /// * `bias` - Constant bias on each axis (m·s<sup>-2</sup>)
+    /// * `noise` - Standard deviation of white noise on each sample (m·s<sup>-2</sup>)
+    /// * `scale_factor` - Scale-factor error on each axis, as a fraction of the true specific force
+    pub fn with_accelerometer_errors(mut self, bias: Vector3<T>, noise: T, scale_factor: Vector3<T>) -> Self {
+        self.accelerometer = TriadErrors::new(bias,noise,scale_factor);
+        self
+    }
+
+    /// Replace the stochastic accelerometer errors with a copy of `model` on each axis
+    ///
+    /// The constant bias and scale-factor errors are kept.
+    pub fn with_accelerometer_error_model(mut self, model: ErrorChain<T>) -> Self {
+        self.accelerometer.models = [model.clone(),model.clone(),model];
+        self
+    }
+}
+
+impl<T: Float> Sensor<T> for Imu<T> {
+    type Measurement = ImuMeasurement<T>;
+
+    fn update(&mut self, truth: &Truth<T>) -> Option<ImuMeasurement<T>> {
+        if !self.clock.due(truth.time) {
+            return None;
+        }
+        let rates = self.gyro.apply(truth.time,truth.rates(),&mut self.rng);
+        let specific_force = self.accelerometer.apply(truth.time,truth.specific_force_at(&self.position),&mut self.rng);
+        Some(ImuMeasurement { time: truth.time, rates, specific_force })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::{Trim,TrimCondition,test_aircraft};
+    use crate::types::StateView;
+    use crate::sensors::Quantization;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_trimmed_flight() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let trim = Trim::new(vec![0.0,0.0,0.0,0.5],&[1,3]).solve(&mut vehicle,&TrimCondition::level(20.0)).unwrap();
+        vehicle.step(0.01,&trim.inputs);
+
+        let mut imu = Imu::new(0.0,1);
+        let measurement = imu.update(&Truth::new(0.01,&vehicle)).unwrap();
+        // Lift balances weight, so the accelerometers sense g along the tilted body z axis
+        let g = physical_constants::STANDARD_ACCELERATION_OF_GRAVITY;
+        assert_relative_eq!(measurement.specific_force.norm(),g,max_relative=1e-3);
+        assert_relative_eq!(measurement.specific_force[2],-g * trim.pitch.cos(),max_relative=1e-3);
+        assert_eq!(measurement.rates,vehicle.rates());
+    }
+
+    #[test]
+    fn test_errors_and_rate() {
+        let vehicle = test_aircraft::vehicle(100.0);
+        let mut state = vehicle.statevector();
+        state[10] = 0.1;
+        let mut truth = Truth::new(0.0,&vehicle);
+        truth.state = state;
+
+        let mut imu = Imu::new(50.0,7)
+            .with_gyro_errors(Vector3::new(0.01,0.0,0.0),0.0,Vector3::new(0.1,0.0,0.0))
+            .with_accelerometer_errors(Vector3::new(0.0,0.2,0.0),0.0,Vector3::zeros());
+        let measurement = imu.update(&truth).unwrap();
+        assert_relative_eq!(measurement.rates[0],0.1 * 1.1 + 0.01);
+        assert_relative_eq!(measurement.specific_force[1],truth.specific_force()[1] + 0.2);
+
+        // Samples every 20 ms at a 100 Hz update
+        let times = (1..=10).map(|step| step as f64 * 0.01).filter(|&time| imu.update(&Truth { time, ..truth }).is_some()).count();
+        assert_eq!(times,5);
+
+        // Noise is reproducible for a seed
+        let noisy = |seed| Imu::new(0.0,seed).with_gyro_errors(Vector3::zeros(),0.01,Vector3::zeros()).update(&truth).unwrap().rates;
+        assert_eq!(noisy(3),noisy(3));
+        assert_ne!(noisy(3),noisy(4));
+
+        // Stochastic error models replace the white noise
+        let mut quantized = Imu::new(0.0,1).with_gyro_error_model(ErrorChain::new().with(Quantization::new(0.03)));
+        assert_relative_eq!(quantized.update(&truth).unwrap().rates[0],0.09);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::Body;
+use crate::geodetic::{Geodetic,LocalTangentPlane};
+use crate::random::Rng;
+use crate::types::{Vector3,Matrix3,Float,DefaultFloatRepr,constant};
+use super::{Sensor,Measurement,Truth,SampleClock};
+use super::errors::{ErrorModel,WhiteNoise};
+
+/// Trait for models of the geomagnetic field
+pub trait MagneticField<T: Float = DefaultFloatRepr> {
+    /// Return the North-East-Down magnetic field at `position` (nT)
+    fn get_field(&self, position: &Geodetic<T>) -> Vector3<T>;
+}
+
+/// Geomagnetic field with the same North-East-Down value everywhere
+#[derive(Copy,Clone,Debug)]
+pub struct ConstantField<T: Float = DefaultFloatRepr> {
+    /// North-East-Down field (nT)
+    field: Vector3<T>,
+}
+
+impl<T: Float> ConstantField<T> {
+    /// Create a new [ConstantField] from its North-East-Down `field` (nT)
+    pub fn new(field: Vector3<T>) -> Self {
+        ConstantField { field }
+    }
+}
+
+impl<T: Float> MagneticField<T> for ConstantField<T> {
+    fn get_field(&self, _position: &Geodetic<T>) -> Vector3<T> {
+        self.field
+    }
+}
+
+/// Tilted dipole approximation of the geomagnetic field
+///
+/// The field is the first-degree term of the spherical harmonic expansion used by the World Magnetic
+/// Model and IGRF, which captures the main field to within about 10% and its direction to within a few
+/// degrees at most locations. Geodetic latitude is used as geocentric latitude, which is accurate to
+/// better than 0.2°.
+#[derive(Copy,Clone,Debug)]
+pub struct DipoleField<T: Float = DefaultFloatRepr> {
+    /// Gauss coefficients g₁⁰, g₁¹ and h₁¹ (nT)
+    coefficients: (T,T,T),
+}
+
+impl<T: Float> Default for DipoleField<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> DipoleField<T> {
+    /// Reference radius of the expansion (m)
+    const REFERENCE_RADIUS: f64 = 6_371_200.0;
+
+    /// Create a new [DipoleField] with the IGRF-13 coefficients for epoch 2020.0
+    pub fn new() -> Self {
+        DipoleField::with_coefficients(constant::<T>(-29_404.8),constant::<T>(-1_450.9),constant::<T>(4_652.5))
+    }
+
+    /// Create a new [DipoleField] with Gauss coefficients `g10`, `g11` and `h11` (nT)
+    pub fn with_coefficients(g10: T, g11: T, h11: T) -> Self {
+        DipoleField { coefficients: (g10,g11,h11) }
+    }
+}
+
+impl<T: Float> MagneticField<T> for DipoleField<T> {
+    fn get_field(&self, position: &Geodetic<T>) -> Vector3<T> {
+        let (g10, g11, h11) = self.coefficients;
+        let a = constant::<T>(Self::REFERENCE_RADIUS);
+        let scale = <T as num_traits::Float>::powi(a / (a + position.altitude),3);
+        // Colatitude θ, so sin θ = cos(latitude)
+        let (cos_theta, sin_theta) = <T as num_traits::Float>::sin_cos(position.latitude);
+        let (sin_lon, cos_lon) = <T as num_traits::Float>::sin_cos(position.longitude);
+        let equatorial = g11 * cos_lon + h11 * sin_lon;
+
+        let radial = constant::<T>(2.0) * scale * (g10 * cos_theta + equatorial * sin_theta);
+        let colatitude = -scale * (equatorial * cos_theta - g10 * sin_theta);
+        let longitude = scale * (g11 * sin_lon - h11 * cos_lon);
+        Vector3::new(-colatitude,longitude,-radial)
+    }
+}
+
+/// Measurement from a [Magnetometer]
+#[derive(Copy,Clone,Debug)]
+pub struct MagnetometerMeasurement<T: Float = DefaultFloatRepr> {
+    /// Time of the measurement (s)
+    pub time: T,
+    /// Body-frame magnetic field (nT)
+    pub field: Vector3<T>,
+}
+
+impl<T: Float> Measurement<T> for MagnetometerMeasurement<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+
+    fn retimed(&self, time: T) -> Self {
+        MagnetometerMeasurement { time, ..*self }
+    }
+
+    fn map_values<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        MagnetometerMeasurement { time: self.time, field: self.field.map(&mut f) }
+    }
+}
+
+/// Three-axis magnetometer aligned with the body axes
+///
+/// The magnetometer measures the geomagnetic field at the geodetic position of the vehicle, rotated into
+/// body axes. Soft-iron errors are a matrix applied to the true field, and hard-iron errors are a
+/// constant body-frame field added to it, as from magnetised parts of the airframe.
+#[derive(Clone,Debug)]
+pub struct Magnetometer<F: MagneticField<T>, T: Float = DefaultFloatRepr> {
+    /// Geomagnetic field model
+    field: F,
+    /// Tangent plane of the vehicle position
+    plane: LocalTangentPlane<T>,
+    /// Sample clock
+    clock: SampleClock<T>,
+    /// Hard-iron field (nT)
+    hard_iron: Vector3<T>,
+    /// Soft-iron matrix
+    soft_iron: Matrix3<T>,
+    /// White noise on each axis
+    noise: WhiteNoise<T>,
+    /// Noise source
+    rng: Rng,
+}
+
+impl<F: MagneticField<T>, T: Float> Magnetometer<F,T> {
+    /// Create a new perfect [Magnetometer]
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Geomagnetic field model
+    /// * `plane` - Tangent plane of the vehicle position
+    /// * `rate` - Sample rate (Hz), or zero to sample at every update
+    /// * `seed` - Seed for the measurement noise
+    pub fn new(field: F, plane: LocalTangentPlane<T>, rate: T, seed: u64) -> Self {
+        Magnetometer {
+            field,
+            plane,
+            clock: SampleClock::new(rate),
+            hard_iron: Vector3::zeros(),
+            soft_iron: Matrix3::identity(),
+            noise: WhiteNoise::new(T::zero()),
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Set the body-frame hard-iron field (nT)
+    pub fn with_hard_iron(mut self, hard_iron: Vector3<T>) -> Self {
+        self.hard_iron = hard_iron;
+        self
+    }
+
+    /// Set the soft-iron matrix, which is the identity for no soft-iron error
+    pub fn with_soft_iron(mut self, soft_iron: Matrix3<T>) -> Self {
+        self.soft_iron = soft_iron;
+        self
+    }
+
+    /// Set the standard deviation of white noise on each axis (nT)
+    pub fn with_noise(mut self, noise: T) -> Self {
+        self.noise = WhiteNoise::new(noise);
+        self
+    }
+}
+
+impl<F: MagneticField<T>, T: Float> Sensor<T> for Magnetometer<F,T> {
+    type Measurement = MagnetometerMeasurement<T>;
+
+    fn update(&mut self, truth: &Truth<T>) -> Option<MagnetometerMeasurement<T>> {
+        if !self.clock.due(truth.time) {
+            return None;
+        }
+        let position = self.plane.to_geodetic(&truth.state.fixed_rows::<3>(0).into());
+        let body_field = Body::get_dcm(&truth.state) * self.field.get_field(&position);
+        let field = (self.soft_iron * body_field + self.hard_iron).map(|component| self.noise.corrupt(truth.time,component,&mut self.rng));
+        Some(MagnetometerMeasurement { time: truth.time, field })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use crate::types::{UnitQuaternion,StateView};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_dipole_field() {
+        let field = DipoleField::with_coefficients(-30_000.0,0.0,0.0);
+        // An axial dipole points north at the equator and down at the north pole, with twice the strength
+        let equator = field.get_field(&Geodetic::new(0.0,1.0,0.0));
+        assert_relative_eq!(equator[0],30_000.0,max_relative=1e-9);
+        assert_relative_eq!(equator[2],0.0,epsilon=1e-9);
+        let pole = field.get_field(&Geodetic::new(std::f64::consts::FRAC_PI_2,0.0,0.0));
+        assert_relative_eq!(pole[2],60_000.0,max_relative=1e-9);
+
+        // The IGRF dipole gives a steeply-inclined field in the UK
+        let uk = DipoleField::new().get_field(&Geodetic::from_degrees(52.0,-1.0,0.0));
+        assert!(uk.norm() > 40_000.0 && uk.norm() < 55_000.0);
+        assert!(uk[2] > 2.0 * uk[0]);
+    }
+
+    #[test]
+    fn test_heading_and_iron() {
+        let plane = LocalTangentPlane::new(Geodetic::from_degrees(52.0,-1.0,0.0));
+        let field = ConstantField::new(Vector3::new(20_000.0,0.0,40_000.0));
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let mut state = vehicle.statevector();
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.0,std::f64::consts::FRAC_PI_2);
+        state.fixed_rows_mut::<4>(6).copy_from(&attitude.coords);
+        vehicle.set_state(state);
+
+        // Heading east, the northerly field is on the left wing
+        let truth = Truth::new(0.0,&vehicle);
+        let measurement = Magnetometer::new(field,plane,0.0,1).update(&truth).unwrap();
+        assert_relative_eq!(measurement.field[1],-20_000.0,max_relative=1e-9);
+        assert_relative_eq!(measurement.field[0],0.0,epsilon=1e-6);
+
+        let soft_iron = Matrix3::new(1.1,0.0,0.0, 0.0,0.9,0.0, 0.0,0.0,1.0);
+        let distorted = Magnetometer::new(field,plane,0.0,1).with_hard_iron(Vector3::new(500.0,0.0,0.0)).with_soft_iron(soft_iron).update(&truth).unwrap();
+        assert_relative_eq!(distorted.field[0],500.0,epsilon=1e-6);
+        assert_relative_eq!(distorted.field[1],-18_000.0,max_relative=1e-9);
+    }
+}
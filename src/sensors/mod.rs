@@ -0,0 +1,210 @@
+//! Sensor models
+//!
+//! Sensors produce measurements from the true state of a vehicle, captured each timestep in a [Truth].
+//! Each sensor runs at its own sample rate and returns a measurement from [Sensor::update] only when a
+//! sample is due, so sensors can be updated every timestep of a simulation loop.
+//!
+//! A [SensorSuite] gathers the latest measurement of each fitted sensor into a [SensedState], the view of
+//! the vehicle available to a controller, kept apart from the [TruthState] given by [AffectedBody::truth].
+//!
+//! Noise and drift are built from composable [ErrorModel]s, which can also be used to build custom sensors.
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel};
+use crate::types::{Vector3,StateVector,StateView,Float,DefaultFloatRepr,constant};
+use crate::Body;
+use crate::aero::{ISA_STANDARD_TEMPERATURE,R_AIR};
+
+mod errors;
+mod imu;
+mod gps;
+mod air_data;
+mod baro;
+mod magnetometer;
+mod variometer;
+mod suite;
+
+pub use errors::{ErrorModel,ErrorChain,WhiteNoise,BiasRandomWalk,GaussMarkov,Quantization};
+pub use imu::{Imu,ImuMeasurement};
+pub use gps::{Gps,GpsMeasurement};
+pub use air_data::{PitotStatic,AirDataMeasurement,Vane,VaneAxis,VaneMeasurement};
+pub use baro::{Barometer,BaroMeasurement};
+pub use magnetometer::{Magnetometer,MagnetometerMeasurement,MagneticField,DipoleField,ConstantField};
+pub use variometer::{Variometer,VariometerMeasurement};
+pub use suite::{SensorSuite,SensedState};
+
+/// True state of a vehicle at an instant, from which sensor measurements are made
+#[derive(Copy,Clone)]
+pub struct Truth<T: Float = DefaultFloatRepr> {
+    /// Simulation time (s)
+    pub time: T,
+    /// Vehicle statevector
+    pub state: StateVector<T>,
+    /// Vehicle airstate
+    pub airstate: AirState<T>,
+    /// Body-frame acceleration, as given by [AffectedBody::acceleration]
+    pub acceleration: Vector3<T>,
+    /// Body-frame angular acceleration, as given by [AffectedBody::angular_acceleration]
+    pub angular_acceleration: Vector3<T>,
+    /// World-frame wind velocity at the vehicle position
+    pub wind: Vector3<T>,
+    /// Static pressure at the vehicle position (Pa)
+    pub pressure: T,
+    /// Static temperature at the vehicle position (K)
+    pub temperature: T,
+}
+
+/// True state of a vehicle, as distinct from the [SensedState] seen through its sensors
+pub type TruthState<T = DefaultFloatRepr> = Truth<T>;
+
+impl<T: Float> Truth<T> {
+    /// Capture the true state of `vehicle` at `time`
+    ///
+    /// The accelerations are those at the start of the previous step.
+    pub fn new<I, W: WindModel<T>, D: DensityModel<T>>(time: T, vehicle: &AffectedBody<I,T,W,D>) -> Self {
+        Truth {
+            time,
+            state: vehicle.statevector(),
+            airstate: vehicle.get_airstate(),
+            acceleration: vehicle.acceleration(),
+            angular_acceleration: vehicle.angular_acceleration(),
+            wind: vehicle.body.get_wind(),
+            pressure: vehicle.body.get_pressure(),
+            temperature: vehicle.body.get_temperature(),
+        }
+    }
+
+    /// Return the body-frame rotation rates (rad·s<sup>-1</sup>)
+    pub fn rates(&self) -> Vector3<T> {
+        self.state.fixed_rows::<3>(10).into()
+    }
+
+    /// Return the body-frame specific force at the centre of gravity (m·s<sup>-2</sup>)
+    ///
+    /// This is the non-gravitational acceleration sensed by an accelerometer, so a vehicle at rest reads
+    /// `(0, 0, -g)` when level.
+    pub fn specific_force(&self) -> Vector3<T> {
+        let velocity: Vector3<T> = self.state.fixed_rows::<3>(3).into();
+        let gravity = Vector3::new(T::zero(),T::zero(),constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY));
+        // Body axes rotate, so the inertial acceleration includes the transport term ω×v
+        self.acceleration + self.rates().cross(&velocity) - Body::get_dcm(&self.state) * gravity
+    }
+
+    /// Return the body-frame specific force at `position` relative to the centre of gravity (m·s<sup>-2</sup>)
+    pub fn specific_force_at(&self, position: &Vector3<T>) -> Vector3<T> {
+        let rates = self.rates();
+        self.specific_force() + self.angular_acceleration.cross(position) + rates.cross(&rates.cross(position))
+    }
+}
+
+/// ISA troposphere temperature lapse rate (K·m<sup>-1</sup>)
+const ISA_LAPSE_RATE: f64 = 0.0065;
+
+/// Return the ISA troposphere altitude (m) at which the pressure is `pressure`, above the level where it is `reference` (Pa)
+pub(crate) fn pressure_altitude<T: Float>(pressure: T, reference: T) -> T {
+    let exponent = constant::<T>(R_AIR * ISA_LAPSE_RATE / physical_constants::STANDARD_ACCELERATION_OF_GRAVITY);
+    constant::<T>(ISA_STANDARD_TEMPERATURE / ISA_LAPSE_RATE) * (T::one() - <T as num_traits::Float>::powf(pressure / reference,exponent))
+}
+
+/// A sensor producing measurements from the true vehicle state
+pub trait Sensor<T: Float = DefaultFloatRepr> {
+    /// Measurement produced by the sensor
+    type Measurement;
+
+    /// Update the sensor with the true state of the vehicle
+    ///
+    /// Returns a measurement if a sample is due at `truth.time`, or `None` otherwise.
+    fn update(&mut self, truth: &Truth<T>) -> Option<Self::Measurement>;
+}
+
+/// Measurement produced by a [Sensor]
+pub trait Measurement<T: Float = DefaultFloatRepr>: Copy {
+    /// Return the time of the measurement (s)
+    fn time(&self) -> T;
+
+    /// Return a copy of the measurement at `time` (s)
+    fn retimed(&self, time: T) -> Self;
+
+    /// Return a copy of the measurement with every value except the time transformed by `f`
+    fn map_values<F: FnMut(T) -> T>(&self, f: F) -> Self;
+}
+
+/// Clock deciding when a sensor with a fixed sample rate is due
+#[derive(Copy,Clone,Debug)]
+pub(crate) struct SampleClock<T: Float> {
+    /// Sample period (s), or zero to sample at every update
+    period: T,
+    /// Time of the next sample (s)
+    next: Option<T>,
+}
+
+impl<T: Float> SampleClock<T> {
+    /// Create a new [SampleClock] at `rate` (Hz)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate is negative.
+    pub(crate) fn new(rate: T) -> Self {
+        if rate < T::zero() {
+            panic!("Sample rate must not be negative");
+        }
+        SampleClock {
+            period: if rate > T::zero() { T::one() / rate } else { T::zero() },
+            next: None,
+        }
+    }
+
+    /// Return `true` if a sample is due at `time`
+    ///
+    /// The first sample is taken at the first update. Later samples are scheduled from the first, so
+    /// the mean rate is exact even when it is not a divisor of the simulation timestep.
+    pub(crate) fn due(&mut self, time: T) -> bool {
+        let tolerance = constant::<T>(1e-9) * self.period;
+        match self.next {
+            Some(next) if time + tolerance < next => false,
+            Some(next) => {
+                let mut next = next + self.period;
+                while self.period > T::zero() && next <= time + tolerance {
+                    next += self.period;
+                }
+                self.next = Some(next);
+                true
+            },
+            None => {
+                self.next = Some(time + self.period);
+                true
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_sample_clock() {
+        let mut clock = SampleClock::new(30.0);
+        let samples = (0..=100).filter(|&step| clock.due(step as f64 * 0.01)).count();
+        assert_eq!(samples,31);
+        let mut every = SampleClock::new(0.0);
+        assert!((0..10).all(|step| every.due(step as f64 * 0.01)));
+    }
+
+    #[test]
+    fn test_specific_force_at_rest() {
+        let vehicle = test_aircraft::vehicle(100.0);
+        let mut truth = Truth::new(0.0,&vehicle);
+        truth.acceleration = Vector3::zeros();
+        truth.state.fixed_rows_mut::<3>(3).copy_from(&Vector3::zeros());
+        let force = truth.specific_force();
+        assert_relative_eq!(force[0],0.0);
+        assert_relative_eq!(force[2],-physical_constants::STANDARD_ACCELERATION_OF_GRAVITY);
+
+        // Centripetal acceleration of an offset point in a steady yaw
+        truth.state[12] = 2.0;
+        let force = truth.specific_force_at(&Vector3::new(0.5,0.0,0.0));
+        assert_relative_eq!(force[0],-2.0 * 2.0 * 0.5);
+    }
+}
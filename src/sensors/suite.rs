@@ -0,0 +1,168 @@
+use crate::types::{Float,DefaultFloatRepr};
+
+use super::{Sensor,Truth};
+use super::{ImuMeasurement,GpsMeasurement,BaroMeasurement,MagnetometerMeasurement,AirDataMeasurement,VariometerMeasurement};
+
+/// Boxed [Sensor] producing measurements of type `M`
+type BoxedSensor<T,M> = Box<dyn Sensor<T,Measurement=M>>;
+
+/// Sensed view of a vehicle, holding the latest measurement of each sensor in a [SensorSuite]
+///
+/// Measurements are held until the next sample, so a measurement is fresh when its time is the time of
+/// the [SensedState]. Sensors not fitted to the suite, or not yet sampled, are `None`.
+#[derive(Copy,Clone,Debug)]
+pub struct SensedState<T: Float = DefaultFloatRepr> {
+    /// Time of the latest update (s)
+    pub time: T,
+    /// Latest [Imu](super::Imu) measurement
+    pub imu: Option<ImuMeasurement<T>>,
+    /// Latest [Gps](super::Gps) measurement
+    pub gps: Option<GpsMeasurement<T>>,
+    /// Latest [Barometer](super::Barometer) measurement
+    pub barometer: Option<BaroMeasurement<T>>,
+    /// Latest [Magnetometer](super::Magnetometer) measurement
+    pub magnetometer: Option<MagnetometerMeasurement<T>>,
+    /// Latest [PitotStatic](super::PitotStatic) measurement
+    pub air_data: Option<AirDataMeasurement<T>>,
+    /// Latest [Variometer](super::Variometer) measurement
+    pub variometer: Option<VariometerMeasurement<T>>,
+}
+
+impl<T: Float> SensedState<T> {
+    /// Create an empty [SensedState] at `time` (s)
+    pub fn new(time: T) -> Self {
+        SensedState { time, imu: None, gps: None, barometer: None, magnetometer: None, air_data: None, variometer: None }
+    }
+}
+
+/// Set of sensors producing the [SensedState] of a vehicle from its [Truth]
+///
+/// A controller given only the [SensedState], as by [AffectedBody::run_sensed](crate::AffectedBody::run_sensed),
+/// cannot read the true state by accident, so control studies see the effect of sensor errors and rates.
+pub struct SensorSuite<T: Float = DefaultFloatRepr> {
+    /// Inertial measurement unit
+    imu: Option<BoxedSensor<T,ImuMeasurement<T>>>,
+    /// GPS receiver
+    gps: Option<BoxedSensor<T,GpsMeasurement<T>>>,
+    /// Barometer
+    barometer: Option<BoxedSensor<T,BaroMeasurement<T>>>,
+    /// Magnetometer
+    magnetometer: Option<BoxedSensor<T,MagnetometerMeasurement<T>>>,
+    /// Pitot-static system
+    air_data: Option<BoxedSensor<T,AirDataMeasurement<T>>>,
+    /// Variometer
+    variometer: Option<BoxedSensor<T,VariometerMeasurement<T>>>,
+    /// Latest sensed state
+    sensed: SensedState<T>,
+}
+
+impl<T: Float> SensorSuite<T> {
+    /// Create a new [SensorSuite] with no sensors
+    pub fn new() -> Self {
+        SensorSuite { imu: None, gps: None, barometer: None, magnetometer: None, air_data: None, variometer: None, sensed: SensedState::new(T::zero()) }
+    }
+
+    /// Fit an inertial measurement unit
+    pub fn with_imu<S: Sensor<T,Measurement=ImuMeasurement<T>> + 'static>(mut self, sensor: S) -> Self {
+        self.imu = Some(Box::new(sensor));
+        self
+    }
+
+    /// Fit a GPS receiver
+    pub fn with_gps<S: Sensor<T,Measurement=GpsMeasurement<T>> + 'static>(mut self, sensor: S) -> Self {
+        self.gps = Some(Box::new(sensor));
+        self
+    }
+
+    /// Fit a barometer
+    pub fn with_barometer<S: Sensor<T,Measurement=BaroMeasurement<T>> + 'static>(mut self, sensor: S) -> Self {
+        self.barometer = Some(Box::new(sensor));
+        self
+    }
+
+    /// Fit a magnetometer
+    pub fn with_magnetometer<S: Sensor<T,Measurement=MagnetometerMeasurement<T>> + 'static>(mut self, sensor: S) -> Self {
+        self.magnetometer = Some(Box::new(sensor));
+        self
+    }
+
+    /// Fit a pitot-static system
+    pub fn with_air_data<S: Sensor<T,Measurement=AirDataMeasurement<T>> + 'static>(mut self, sensor: S) -> Self {
+        self.air_data = Some(Box::new(sensor));
+        self
+    }
+
+    /// Fit a variometer
+    pub fn with_variometer<S: Sensor<T,Measurement=VariometerMeasurement<T>> + 'static>(mut self, sensor: S) -> Self {
+        self.variometer = Some(Box::new(sensor));
+        self
+    }
+
+    /// Update every sensor with `truth`, returning the [SensedState]
+    pub fn update(&mut self, truth: &Truth<T>) -> &SensedState<T> {
+        /// Replace the held measurement of `sensor` if a sample is due
+        fn sample<T: Float, M>(sensor: &mut Option<BoxedSensor<T,M>>, held: &mut Option<M>, truth: &Truth<T>) {
+            if let Some(measurement) = sensor.as_mut().and_then(|sensor| sensor.update(truth)) {
+                *held = Some(measurement);
+            }
+        }
+        self.sensed.time = truth.time;
+        sample(&mut self.imu,&mut self.sensed.imu,truth);
+        sample(&mut self.gps,&mut self.sensed.gps,truth);
+        sample(&mut self.barometer,&mut self.sensed.barometer,truth);
+        sample(&mut self.magnetometer,&mut self.sensed.magnetometer,truth);
+        sample(&mut self.air_data,&mut self.sensed.air_data,truth);
+        sample(&mut self.variometer,&mut self.sensed.variometer,truth);
+        &self.sensed
+    }
+
+    /// Return the latest [SensedState]
+    pub fn sensed(&self) -> &SensedState<T> {
+        &self.sensed
+    }
+}
+
+impl<T: Float> Default for SensorSuite<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sensors::{Imu,Barometer};
+    use crate::trim::test_aircraft;
+
+    #[test]
+    fn test_held_measurements() {
+        let vehicle = test_aircraft::vehicle(100.0);
+        let mut suite = SensorSuite::new().with_imu(Imu::new(100.0,1)).with_barometer(Barometer::new(10.0,2));
+        let mut truth = vehicle.truth();
+        for step in 0..5 {
+            truth.time = step as f64 * 0.01;
+            suite.update(&truth);
+        }
+        let sensed = suite.sensed();
+        assert_eq!(sensed.time,0.04);
+        // The IMU is fresh, the barometer held from the first sample
+        assert_eq!(sensed.imu.unwrap().time,0.04);
+        assert_eq!(sensed.barometer.unwrap().time,0.0);
+        assert!(sensed.gps.is_none() && sensed.magnetometer.is_none());
+    }
+
+    #[test]
+    fn test_run_sensed() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let mut suite = SensorSuite::new().with_imu(Imu::new(50.0,1));
+        let mut samples = 0;
+        let recorder = vehicle.run_sensed(1.0,0.01,&mut suite,|sensed| {
+            if sensed.imu.unwrap().time == sensed.time {
+                samples += 1;
+            }
+            vec![0.0,0.0,0.0,0.5]
+        });
+        assert_eq!(samples,50);
+        assert_eq!(recorder.len(),101);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::types::{Float,DefaultFloatRepr,constant};
+use super::{Sensor,Measurement,Truth,SampleClock};
+
+/// Measurement from a [Variometer]
+#[derive(Copy,Clone,Debug)]
+pub struct VariometerMeasurement<T: Float = DefaultFloatRepr> {
+    /// Time of the measurement (s)
+    pub time: T,
+    /// Uncompensated rate of climb (m·s<sup>-1</sup>)
+    pub climb_rate: T,
+    /// Total-energy-compensated rate of climb (m·s<sup>-1</sup>)
+    pub total_energy_rate: T,
+}
+
+impl<T: Float> Measurement<T> for VariometerMeasurement<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+
+    fn retimed(&self, time: T) -> Self {
+        VariometerMeasurement { time, ..*self }
+    }
+
+    fn map_values<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        VariometerMeasurement { time: self.time, climb_rate: f(self.climb_rate), total_energy_rate: f(self.total_energy_rate) }
+    }
+}
+
+/// Total-energy-compensated variometer
+///
+/// The variometer differentiates the altitude `h` of the vehicle and the energy height
+///
+/// `h_E = h + k V² / 2g`
+///
+/// for airspeed `V` and compensation `k` between successive updates, and passes both rates through a
+/// first-order lag, as of the capacity flask of a mechanical instrument. With full compensation,
+/// `k = 1`, exchanging airspeed for height at constant energy reads zero, so the total energy rate is the
+/// rate of climb of the air mass less the sink rate of the glider. The airspeed is relative to the
+/// wind model, so updrafts and wind gradients appear in the reading as they would in flight.
+///
+/// The rates are found from the updates rather than the sample times, so the variometer should be
+/// updated every timestep. The first update reads zero.
+#[derive(Clone,Debug)]
+pub struct Variometer<T: Float = DefaultFloatRepr> {
+    /// Sample clock
+    clock: SampleClock<T>,
+    /// Total energy compensation
+    compensation: T,
+    /// Time constant of the lag (s)
+    lag: T,
+    /// Time, altitude and energy height of the previous update
+    previous: Option<(T,T,T)>,
+    /// Lagged climb and total energy rates
+    rates: (T,T),
+}
+
+impl<T: Float> Variometer<T> {
+    /// Create a new fully-compensated [Variometer] with no lag, sampling at `rate` (Hz), or at every
+    /// update if zero
+    pub fn new(rate: T) -> Self {
+        Variometer {
+            clock: SampleClock::new(rate),
+            compensation: T::one(),
+            lag: T::zero(),
+            previous: None,
+            rates: (T::zero(),T::zero()),
+        }
+    }
+
+    /// Set the total energy compensation, from 0 for none to 1 for full compensation
+    pub fn with_compensation(mut self, compensation: T) -> Self {
+        self.compensation = compensation;
+        self
+    }
+
+    /// Set the time constant of the lag (s)
+    pub fn with_lag(mut self, lag: T) -> Self {
+        self.lag = lag;
+        self
+    }
+}
+
+impl<T: Float> Sensor<T> for Variometer<T> {
+    type Measurement = VariometerMeasurement<T>;
+
+    fn update(&mut self, truth: &Truth<T>) -> Option<VariometerMeasurement<T>> {
+        let g = constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY);
+        let altitude = -truth.state[2];
+        let airspeed = truth.airstate.airspeed;
+        let energy_height = altitude + self.compensation * airspeed * airspeed / (constant::<T>(2.0) * g);
+
+        if let Some((last, last_altitude, last_energy_height)) = self.previous {
+            let delta_t = truth.time - last;
+            if delta_t > T::zero() {
+                let climb_rate = (altitude - last_altitude) / delta_t;
+                let total_energy_rate = (energy_height - last_energy_height) / delta_t;
+                let gain = if self.lag > T::zero() { T::one() - <T as num_traits::Float>::exp(-delta_t / self.lag) } else { T::one() };
+                let (lagged_climb, lagged_total) = self.rates;
+                self.rates = (lagged_climb + (climb_rate - lagged_climb) * gain,lagged_total + (total_energy_rate - lagged_total) * gain);
+            }
+        }
+        self.previous = Some((truth.time,altitude,energy_height));
+
+        if !self.clock.due(truth.time) {
+            return None;
+        }
+        let (climb_rate, total_energy_rate) = self.rates;
+        Some(VariometerMeasurement { time: truth.time, climb_rate, total_energy_rate })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Body,AeroBody,AffectedBody};
+    use crate::types::{Vector3,Matrix3};
+    use approx::assert_relative_eq;
+
+    fn truth(time: f64, altitude: f64, airspeed: f64) -> Truth {
+        let body = Body::new(1.0,Matrix3::identity(),Vector3::new(0.0,0.0,-altitude),Vector3::new(airspeed,0.0,0.0),Default::default(),Vector3::zeros());
+        Truth::new(time,&AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::new(body),vec![]))
+    }
+
+    #[test]
+    fn test_total_energy() {
+        let g = physical_constants::STANDARD_ACCELERATION_OF_GRAVITY;
+        let mut vario = Variometer::new(0.0);
+        let mut uncompensated = Variometer::new(0.0).with_compensation(0.0);
+        assert_eq!(vario.update(&truth(0.0,500.0,30.0)).unwrap().total_energy_rate,0.0);
+        uncompensated.update(&truth(0.0,500.0,30.0));
+
+        // Pulling up from 30 m/s to 29 m/s at constant energy
+        let altitude = 500.0 + (30.0f64.powi(2) - 29.0f64.powi(2)) / (2.0 * g);
+        let measurement = vario.update(&truth(1.0,altitude,29.0)).unwrap();
+        assert_relative_eq!(measurement.total_energy_rate,0.0,epsilon=1e-9);
+        assert_relative_eq!(measurement.climb_rate,altitude - 500.0,max_relative=1e-9);
+        assert_relative_eq!(uncompensated.update(&truth(1.0,altitude,29.0)).unwrap().total_energy_rate,altitude - 500.0,max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_lag() {
+        let mut vario = Variometer::new(0.0).with_lag(2.0);
+        // A steady 1 m/s sink at constant airspeed
+        let readings: Vec<VariometerMeasurement> = (0..=200).map(|step| {
+            let time = step as f64 * 0.01;
+            vario.update(&truth(time,500.0 - time,25.0)).unwrap()
+        }).collect();
+        assert_relative_eq!(readings[200].total_energy_rate,-(1.0 - (-1.0f64).exp()),max_relative=1e-6);
+        assert_relative_eq!(readings[200].climb_rate,readings[200].total_energy_rate,max_relative=1e-9);
+    }
+}
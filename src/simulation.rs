@@ -0,0 +1,204 @@
+//! Fixed-timestep simulation driver
+//!
+//! Interactive applications advance the simulation by the wall-clock time of each frame, which varies
+//! with the frame rate. A [Simulation] decouples the physics from the frame rate with the standard
+//! accumulator pattern: frame time is accumulated, and whole physics steps of a fixed timestep are taken
+//! while a step's worth of time is available. The remainder is carried to the next frame, and the state
+//! can be interpolated across it between the last two physics steps for smooth output.
+//!
+//! ```
+//! use aerso::simulation::Simulation;
+//! # use aerso::{Body,AeroBody,AffectedBody};
+//! # use aerso::types::{Vector3,Matrix3,UnitQuaternion};
+//! # let body = Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(10.0,0.0,0.0),UnitQuaternion::identity(),Vector3::zeros());
+//! # let vehicle: AffectedBody<Vec<f64>> = AffectedBody::new(AeroBody::new(body),vec![]);
+//!
+//! let mut simulation = Simulation::new(vehicle,0.01);
+//! // A 16.7 ms frame takes one physics step and carries 6.7 ms
+//! assert_eq!(simulation.advance(1.0 / 60.0,&vec![]),1);
+//! let display = simulation.interpolated_state();
+//! ```
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel};
+use crate::types::{StateVector,StateView,Float,DefaultFloatRepr,constant};
+use crate::wind_models::ConstantWind;
+use crate::density_models::StandardDensity;
+
+/// Driver stepping an [AffectedBody] by a fixed timestep from variable frame times
+pub struct Simulation<I, T: Float = DefaultFloatRepr, W: WindModel<T> = ConstantWind<T>, D: DensityModel<T> = StandardDensity> {
+    /// Vehicle being simulated
+    vehicle: AffectedBody<I,T,W,D>,
+    /// Physics timestep (s)
+    delta_t: T,
+    /// Frame time not yet simulated (s)
+    accumulator: T,
+    /// Largest number of physics steps taken in one advance
+    max_steps: usize,
+    /// Statevector before the latest physics step
+    previous: StateVector<T>,
+}
+
+impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> Simulation<I,T,W,D> {
+    /// Create a new [Simulation] of `vehicle` with physics timestep `delta_t` (s)
+    ///
+    /// At most 10 physics steps are taken in one advance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delta_t` is not positive.
+    pub fn new(vehicle: AffectedBody<I,T,W,D>, delta_t: T) -> Self {
+        if delta_t <= T::zero() {
+            panic!("Physics timestep must be positive");
+        }
+        let previous = vehicle.statevector();
+        Simulation { vehicle, delta_t, accumulator: T::zero(), max_steps: 10, previous }
+    }
+
+    /// Take at most `max_steps` physics steps in one advance
+    ///
+    /// When a frame takes longer than `max_steps` physics steps, the excess time is dropped so a slow
+    /// frame cannot leave the simulation ever further behind. The simulation then runs slower than the
+    /// wall clock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_steps` is zero.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        if max_steps == 0 {
+            panic!("Simulation must take at least one step per advance");
+        }
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Return the physics timestep (s)
+    pub fn delta_t(&self) -> T {
+        self.delta_t
+    }
+
+    /// Return a reference to the vehicle
+    pub fn vehicle(&self) -> &AffectedBody<I,T,W,D> {
+        &self.vehicle
+    }
+
+    /// Return a mutable reference to the vehicle
+    pub fn vehicle_mut(&mut self) -> &mut AffectedBody<I,T,W,D> {
+        &mut self.vehicle
+    }
+
+    /// Return the vehicle, consuming the [Simulation]
+    pub fn into_vehicle(self) -> AffectedBody<I,T,W,D> {
+        self.vehicle
+    }
+
+    /// Return the frame time not yet simulated (s)
+    pub fn accumulated(&self) -> T {
+        self.accumulator
+    }
+
+    /// Advance by the frame time `elapsed` (s) with a constant `inputstate`
+    ///
+    /// Returns the number of physics steps taken.
+    pub fn advance(&mut self, elapsed: T, inputstate: &I) -> usize {
+        let steps = self.steps_due(elapsed);
+        for _ in 0..steps {
+            self.step(inputstate);
+        }
+        steps
+    }
+
+    /// Advance by the frame time `elapsed` (s), calling `controller` at the start of each physics step
+    ///
+    /// The `controller` is given the current state, airstate and simulation time, as for
+    /// [AffectedBody::run]. Returns the number of physics steps taken.
+    pub fn advance_with<C>(&mut self, elapsed: T, mut controller: C) -> usize where C: FnMut(&dyn StateView<T>, AirState<T>, T) -> I {
+        let steps = self.steps_due(elapsed);
+        for _ in 0..steps {
+            let inputstate = controller(&self.vehicle,self.vehicle.get_airstate(),self.vehicle.time());
+            self.step(&inputstate);
+        }
+        steps
+    }
+
+    /// Return the fraction of a physics step accumulated since the latest step, from 0 to 1
+    pub fn interpolation(&self) -> T {
+        self.accumulator / self.delta_t
+    }
+
+    /// Return the statevector interpolated between the last two physics steps by [Simulation::interpolation]
+    ///
+    /// The position, velocity and rates are interpolated linearly and the attitude spherically. This lags
+    /// the simulated state by up to one step, but moves smoothly when the frame rate and physics rate differ.
+    pub fn interpolated_state(&self) -> StateVector<T> {
+        let fraction = self.interpolation();
+        let current = self.vehicle.statevector();
+        let mut state = self.previous.lerp(&current,fraction);
+        let attitude = self.previous.attitude().slerp(&current.attitude(),fraction);
+        state.fixed_rows_mut::<4>(6).copy_from(&attitude.coords);
+        state
+    }
+
+    /// Accumulate `elapsed` (s) and return the number of physics steps now due
+    fn steps_due(&mut self, elapsed: T) -> usize {
+        self.accumulator += elapsed;
+        let tolerance = constant::<T>(1e-9) * self.delta_t;
+        let due = <T as num_traits::Float>::floor((self.accumulator + tolerance) / self.delta_t).to_usize().unwrap_or(0);
+        let steps = due.min(self.max_steps);
+        self.accumulator = if due > steps {
+            T::zero()
+        } else {
+            <T as num_traits::Float>::max(self.accumulator - constant::<T>(steps as f64) * self.delta_t,T::zero())
+        };
+        steps
+    }
+
+    /// Take one physics step with `inputstate`
+    fn step(&mut self, inputstate: &I) {
+        self.previous = self.vehicle.statevector();
+        self.vehicle.step(self.delta_t,inputstate);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::test_aircraft;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_accumulator() {
+        let mut simulation = Simulation::new(test_aircraft::vehicle(1000.0),0.01);
+        let mut reference = test_aircraft::vehicle(1000.0);
+        let inputs = vec![0.0,0.0,0.0,0.5];
+        // 60 frames of 1/60 s take exactly 100 physics steps
+        let steps: usize = (0..60).map(|_| simulation.advance(1.0 / 60.0,&inputs)).sum();
+        assert_eq!(steps,100);
+        assert!(simulation.accumulated() < 1e-9);
+        for _ in 0..100 {
+            reference.step(0.01,&inputs);
+        }
+        assert_eq!(simulation.vehicle().statevector(),reference.statevector());
+
+        // A long frame is capped, dropping the excess time
+        assert_eq!(simulation.advance(1.0,&inputs),10);
+        assert_eq!(simulation.accumulated(),0.0);
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut simulation = Simulation::new(test_aircraft::vehicle(1000.0),0.01);
+        let mut times = Vec::new();
+        simulation.advance_with(0.025,|_,_,time| {
+            times.push(time);
+            vec![0.0,0.0,0.0,0.5]
+        });
+        assert_eq!(times.len(),2);
+        assert_relative_eq!(times[1],0.01,max_relative=1e-12);
+        assert_relative_eq!(simulation.interpolation(),0.5,max_relative=1e-9);
+        let previous = simulation.previous;
+        let current = simulation.vehicle().statevector();
+        let interpolated = simulation.interpolated_state();
+        assert!((interpolated.position() - (previous.position() + current.position()) / 2.0).norm() < 1e-9);
+        assert_relative_eq!(interpolated.attitude().angle_to(&current.attitude()),previous.attitude().angle_to(&current.attitude()) / 2.0,max_relative=1e-6);
+    }
+}
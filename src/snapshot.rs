@@ -0,0 +1,208 @@
+//! Checkpointing of simulation state
+//!
+//! [AeroBody::snapshot](crate::AeroBody::snapshot), [AffectedBody::snapshot](crate::AffectedBody::snapshot) and
+//! [StaticAffectedBody::snapshot](crate::StaticAffectedBody::snapshot) capture the statevector, the simulation
+//! clock and the internal states of the wind model, effectors and contact models in a [Snapshot].
+//! Restoring it with `restore` and stepping again reproduces the original run exactly, including the random
+//! draws of stochastic wind models such as [DrydenTurbulence](crate::wind_models::DrydenTurbulence), so a
+//! run can be branched from a checkpoint.
+//!
+//! Wind models export their state as a [WindState] through [WindModel::snapshot](crate::WindModel::snapshot),
+//! and effectors theirs as an [EffectorState] through [AeroEffect::snapshot](crate::AeroEffect::snapshot), such
+//! as actuator positions, dynamic stall separation and rocket burn time. Stateless models keep the default
+//! empty state.
+//!
+//! ```
+//! use aerso::{Body,AeroBody,AffectedBody,WindModel};
+//! use aerso::types::{Vector3,Matrix3,StateView};
+//! use aerso::wind_models::{ConstantWind,DrydenConfig};
+//!
+//! let wind = ConstantWind::new(Vector3::new(5.0,0.0,0.0)).with_turbulence(DrydenConfig::high_altitude(20.0,2.0),1);
+//! let body = AeroBody::with_wind_model(Body::new_at_origin(1.0,Matrix3::identity()),wind);
+//! let mut vehicle = AffectedBody::<Vec<f64>,_,_,_>::new(body,vec![]);
+//!
+//! let checkpoint = vehicle.snapshot();
+//! let gusts: Vec<_> = (0..10).map(|_| { vehicle.step(0.01,&vec![]); vehicle.get_airstate().airspeed }).collect();
+//! vehicle.restore(&checkpoint).unwrap();
+//! let replayed: Vec<_> = (0..10).map(|_| { vehicle.step(0.01,&vec![]); vehicle.get_airstate().airspeed }).collect();
+//! assert_eq!(gusts,replayed);
+//! ```
+
+use crate::Error;
+use crate::types::{StateVector,Float,DefaultFloatRepr,constant};
+
+/// Exported internal state of a [WindModel](crate::WindModel) or, as an [EffectorState], an [AeroEffect](crate::AeroEffect)
+///
+/// The layout is private to each model, which only needs to restore states it exported itself.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct WindState {
+    /// Real-valued state, such as gust velocities and model clocks
+    pub values: Vec<f64>,
+    /// Integer state, such as the state of a random number generator
+    pub words: Vec<u64>,
+    /// States of the models composed within the model, in a fixed order
+    pub parts: Vec<WindState>,
+}
+
+/// Exported internal state of an [AeroEffect](crate::AeroEffect)
+pub type EffectorState = WindState;
+
+/// Exported internal state of a [ContactModel](crate::contact::ContactModel)
+pub type ContactState = WindState;
+
+impl WindState {
+    /// Return `true` if the state holds nothing, as exported by a stateless model
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.words.is_empty() && self.parts.is_empty()
+    }
+
+    /// Return an [Error::SnapshotMismatch] unless the state has `values` values, `words` words and `parts` parts
+    pub(crate) fn check(&self, values: usize, words: usize, parts: usize) -> Result<(),Error> {
+        if self.values.len() == values && self.words.len() == words && self.parts.len() == parts {
+            Ok(())
+        } else {
+            Err(Error::SnapshotMismatch)
+        }
+    }
+
+    /// Create a state holding only the real `values`
+    pub(crate) fn from_values<T: Float>(values: &[T]) -> Self {
+        WindState { values: values.iter().map(|&value| export(value)).collect(), ..WindState::default() }
+    }
+
+    /// Return value `index` converted to `T`
+    pub(crate) fn value<T: Float>(&self, index: usize) -> T {
+        constant::<T>(self.values[index])
+    }
+}
+
+/// Convert `value` to `f64` for a [WindState]
+pub(crate) fn export<T: Float>(value: T) -> f64 {
+    num_traits::ToPrimitive::to_f64(&value).unwrap_or(f64::NAN)
+}
+
+/// Checkpoint of the state of a body
+#[derive(Clone,Debug,PartialEq)]
+pub struct Snapshot<T: Float = DefaultFloatRepr> {
+    /// Statevector of the body
+    pub statevector: StateVector<T>,
+    /// Elapsed simulation time (s)
+    pub time: T,
+    /// Internal state of the wind model
+    pub wind: WindState,
+    /// Names and internal states of the effectors, in order, empty for an [AeroBody](crate::AeroBody)
+    pub effectors: Vec<(String,EffectorState)>,
+    /// Internal states of the contact models, in order, empty for a body without contacts
+    pub contacts: Vec<ContactState>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Body,AeroBody,AffectedBody,StaticAffectedBody,EffectorSet,WindModel,AeroEffect,AirState};
+    use crate::types::{Vector3,Matrix3,UnitQuaternion,StateView,Force,Torque};
+    use crate::wind_models::{ConstantWind,DrydenConfig,DrydenTurbulence};
+    use crate::effector_models::{Actuated,Actuator,ActuatorDynamics,DynamicStall};
+
+    #[test]
+    fn test_wind_state() {
+        let mut turbulence = DrydenTurbulence::new(DrydenConfig::high_altitude(30.0,2.0),5);
+        (0..3).for_each(|_| turbulence.step(0.1));
+        let state = turbulence.snapshot();
+        let gusts: Vec<_> = (0..20).map(|_| { turbulence.step(0.1); turbulence.get_wind(&Vector3::zeros()) }).collect();
+
+        // A fresh model with another seed follows the original once restored
+        let mut other = DrydenTurbulence::new(DrydenConfig::high_altitude(30.0,2.0),6);
+        other.restore(&state).unwrap();
+        let replayed: Vec<_> = (0..20).map(|_| { other.step(0.1); other.get_wind(&Vector3::zeros()) }).collect();
+        assert_eq!(gusts,replayed);
+
+        // Stateless models accept only empty states
+        let mut constant = ConstantWind::new(Vector3::<f64>::zeros());
+        assert!(constant.snapshot().is_empty());
+        assert_eq!(constant.restore(&WindState::default()),Ok(()));
+        assert_eq!(constant.restore(&state),Err(Error::SnapshotMismatch));
+        assert_eq!(other.restore(&WindState::default()),Err(Error::SnapshotMismatch));
+    }
+
+    #[test]
+    fn test_vehicle_restore() {
+        let wind = ConstantWind::new(Vector3::new(0.0,5.0,0.0)).with_turbulence(DrydenConfig::high_altitude(20.0,3.0),2);
+        let body = Body::new_at_origin(1.0,Matrix3::identity());
+        let mut vehicle = AffectedBody::<Vec<f64>,_,_,_>::new(AeroBody::with_wind_model(body,wind),vec![]);
+        (0..50).for_each(|_| vehicle.step(0.02,&vec![]));
+
+        let checkpoint = vehicle.snapshot();
+        assert_eq!(checkpoint.time,vehicle.time());
+        let run = |vehicle: &mut AffectedBody<Vec<f64>,f64,_,_>| {
+            (0..100).for_each(|_| vehicle.step(0.02,&vec![]));
+            (vehicle.statevector(),vehicle.time(),vehicle.body.get_wind())
+        };
+        let first = run(&mut vehicle);
+        vehicle.restore(&checkpoint).unwrap();
+        assert_eq!(vehicle.statevector(),checkpoint.statevector);
+        assert_eq!(run(&mut vehicle),first);
+    }
+
+    /// Body-x thrust equal to the command on channel 0
+    struct Thrust;
+
+    impl AeroEffect for Thrust {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3<f64>, inputstate: &Vec<f64>) -> (Force,Torque) {
+            (Force::body(inputstate[0],0.0,0.0),Torque::body(0.0,0.0,0.0))
+        }
+    }
+
+    #[test]
+    fn test_effector_restore() {
+        let body = AeroBody::new(Body::new_at_origin(1.0,Matrix3::identity()));
+        let actuator = Actuator::new(ActuatorDynamics::FirstOrder { time_constant: 0.5 });
+        let mut vehicle = AffectedBody::new(body,vec![]);
+        vehicle.add_effector("thrust",Box::new(Actuated::new(Thrust,0,actuator)));
+        let command = vec![1.0];
+        (0..20).for_each(|_| vehicle.step(0.01,&command));
+
+        let checkpoint = vehicle.snapshot();
+        assert_eq!(checkpoint.effectors.len(),1);
+        assert_eq!(checkpoint.effectors[0].0,"thrust");
+        let run = |vehicle: &mut AffectedBody| {
+            (0..50).for_each(|_| vehicle.step(0.01,&command));
+            (vehicle.statevector(),vehicle.effectors.snapshot())
+        };
+        let first = run(&mut vehicle);
+        // Replaying from the checkpoint restarts the actuator part-way through its lag
+        vehicle.restore(&checkpoint).unwrap();
+        assert_eq!(vehicle.effectors.snapshot(),checkpoint.effectors);
+        assert_eq!(run(&mut vehicle),first);
+
+        // A vehicle with other effectors is left unchanged
+        let mut other: AffectedBody = AffectedBody::new(AeroBody::new(Body::new_at_origin(1.0,Matrix3::identity())),vec![]);
+        assert_eq!(other.restore(&checkpoint),Err(Error::SnapshotMismatch));
+        assert_eq!(other.time(),0.0);
+    }
+
+    #[test]
+    fn test_static_restore() {
+        let body = Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(20.0,0.0,4.0),UnitQuaternion::identity(),Vector3::zeros());
+        let actuator = Actuator::new(ActuatorDynamics::FirstOrder { time_constant: 0.5 });
+        let stall = DynamicStall::new(1.0,0.3,5.5,0.02,12.0f64.to_radians(),30.0,3.0,2.0);
+        let mut vehicle = StaticAffectedBody::new(AeroBody::new(body),(Actuated::new(Thrust,0,actuator),stall));
+        let command = vec![1.0];
+        (0..20).for_each(|_| vehicle.step(0.01,&command));
+
+        let checkpoint = vehicle.snapshot::<Vec<f64>>();
+        assert_eq!(checkpoint.effectors.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),["0","1"]);
+        let run = |vehicle: &mut StaticAffectedBody<_>| {
+            (0..50).for_each(|_| vehicle.step(0.01,&command));
+            (vehicle.statevector(),EffectorSet::<Vec<f64>>::snapshot(&vehicle.effectors))
+        };
+        let first = run(&mut vehicle);
+        vehicle.restore::<Vec<f64>>(&checkpoint).unwrap();
+        assert_eq!(run(&mut vehicle),first);
+
+        // A set with another number of effectors is left unchanged
+        let mut other = StaticAffectedBody::new(AeroBody::new(body),vec![stall]);
+        assert_eq!(other.restore::<Vec<f64>>(&checkpoint),Err(Error::SnapshotMismatch));
+        assert_eq!(other.time(),0.0);
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{AeroBody,AeroEffect,AirState,WindModel,DensityModel,VehicleGeometry,Error};
+use crate::snapshot::{Snapshot,EffectorState};
+use crate::types::{Vector3,Matrix3,Frame,Force,Torque,StateVector,StateView,UnitQuaternion,Isometry3};
+use crate::types::{Float,DefaultFloatRepr};
+
+use crate::wind_models::ConstantWind;
+use crate::aero::StandardDensity;
+
+/// Trait for a statically-dispatched set of [AeroEffect]s
+///
+/// Implemented for tuples of up to 12 effectors, arrays and `Vec`s of a single effector type, so that
+/// a [StaticAffectedBody] can hold its effectors without boxing them.
+pub trait EffectorSet<I, T: Float = DefaultFloatRepr> {
+    /// Call `f` with the effect of each effector, in order
+    fn for_each_effect<V: FnMut(Force<T>,Torque<T>)>(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, f: V);
+
+    /// Advance the internal state of each effector by `delta_t`
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T);
+
+    /// Pass the reference `geometry` to each effector
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>);
+
+    /// Return the internal state of each effector, in order, named by its index `"0"`, `"1"`, ...
+    fn snapshot(&self) -> Vec<(String,EffectorState)>;
+
+    /// Restore the internal state of each effector from `states`, as returned by [EffectorSet::snapshot]
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::SnapshotMismatch] if the names differ from those of `states`, leaving every effector
+    /// unchanged, or if a state does not match its effector, in which case the preceding effectors are restored.
+    fn restore(&mut self, states: &[(String,EffectorState)]) -> Result<(),Error>;
+}
+
+/// Return an [Error::SnapshotMismatch] unless `states` are named by the indices of `len` effectors
+fn check_names(states: &[(String,EffectorState)], len: usize) -> Result<(),Error> {
+    if states.len() == len && states.iter().enumerate().all(|(idx, (name, _))| *name == idx.to_string()) {
+        Ok(())
+    } else {
+        Err(Error::SnapshotMismatch)
+    }
+}
+
+macro_rules! impl_effector_set_for_tuple {
+    ($($name:ident $idx:tt),+) => {
+        impl<I, T: Float, $($name: AeroEffect<I,T>),+> EffectorSet<I,T> for ($($name,)+) {
+            fn for_each_effect<V: FnMut(Force<T>,Torque<T>)>(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, mut f: V) {
+                $(
+                    let (force, torque) = self.$idx.get_effect(airstate,rates,inputstate);
+                    f(force,torque);
+                )+
+            }
+
+            fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+                $( self.$idx.step(airstate,rates,inputstate,delta_t); )+
+            }
+
+            fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+                $( AeroEffect::<I,T>::set_geometry(&mut self.$idx,geometry); )+
+            }
+
+            fn snapshot(&self) -> Vec<(String,EffectorState)> {
+                vec![$( (stringify!($idx).to_string(),AeroEffect::<I,T>::snapshot(&self.$idx)), )+]
+            }
+
+            fn restore(&mut self, states: &[(String,EffectorState)]) -> Result<(),Error> {
+                check_names(states,[$( stringify!($idx), )+].len())?;
+                $( AeroEffect::<I,T>::restore(&mut self.$idx,&states[$idx].1)?; )+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_effector_set_for_tuple!(A 0);
+impl_effector_set_for_tuple!(A 0, B 1);
+impl_effector_set_for_tuple!(A 0, B 1, C 2);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, J 8);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, J 8, K 9);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, J 8, K 9, L 10);
+impl_effector_set_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, J 8, K 9, L 10, M 11);
+
+impl<I, T: Float, E: AeroEffect<I,T>, const N: usize> EffectorSet<I,T> for [E; N] {
+    fn for_each_effect<V: FnMut(Force<T>,Torque<T>)>(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, mut f: V) {
+        for effector in self.iter() {
+            let (force, torque) = effector.get_effect(airstate,rates,inputstate);
+            f(force,torque);
+        }
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        for effector in self.iter_mut() {
+            effector.step(airstate,rates,inputstate,delta_t);
+        }
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        for effector in self.iter_mut() {
+            effector.set_geometry(geometry);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<(String,EffectorState)> {
+        self.iter().enumerate().map(|(idx, effector)| (idx.to_string(),effector.snapshot())).collect()
+    }
+
+    fn restore(&mut self, states: &[(String,EffectorState)]) -> Result<(),Error> {
+        check_names(states,self.len())?;
+        self.iter_mut().zip(states).try_for_each(|(effector, (_, state))| effector.restore(state))
+    }
+}
+
+impl<I, T: Float, E: AeroEffect<I,T>> EffectorSet<I,T> for Vec<E> {
+    fn for_each_effect<V: FnMut(Force<T>,Torque<T>)>(&self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, mut f: V) {
+        for effector in self.iter() {
+            let (force, torque) = effector.get_effect(airstate,rates,inputstate);
+            f(force,torque);
+        }
+    }
+
+    fn step(&mut self, airstate: AirState<T>, rates: Vector3<T>, inputstate: &I, delta_t: T) {
+        for effector in self.iter_mut() {
+            effector.step(airstate,rates,inputstate,delta_t);
+        }
+    }
+
+    fn set_geometry(&mut self, geometry: &VehicleGeometry<T>) {
+        for effector in self.iter_mut() {
+            effector.set_geometry(geometry);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<(String,EffectorState)> {
+        self.iter().enumerate().map(|(idx, effector)| (idx.to_string(),effector.snapshot())).collect()
+    }
+
+    fn restore(&mut self, states: &[(String,EffectorState)]) -> Result<(),Error> {
+        check_names(states,self.len())?;
+        self.iter_mut().zip(states).try_for_each(|(effector, (_, state))| effector.restore(state))
+    }
+}
+
+/// Represent a body subject to a statically-dispatched set of aerodynamic effects
+///
+/// This behaves as an [AffectedBody](crate::AffectedBody), but the effectors are held in an
+/// [EffectorSet] such as a tuple, so no boxing or virtual calls are needed and stepping does not allocate.
+/// Forces and torques from all effectors are summed in their own frames before being passed to the body.
+///
+/// ```
+/// # use aerso::{Body,AeroBody,AeroEffect,AirState,StaticAffectedBody};
+/// # use aerso::types::{Vector3,Matrix3,Force,Torque};
+/// struct Thrust;
+/// impl AeroEffect<[f64;1]> for Thrust {
+///     fn get_effect(&self, _airstate: AirState, _rates: Vector3, inputstate: &[f64;1]) -> (Force,Torque) {
+///         (Force::body(inputstate[0],0.0,0.0),Torque::body(0.0,0.0,0.0))
+///     }
+/// }
+/// let body = AeroBody::new(Body::new_at_origin(1.0,Matrix3::identity()));
+/// let mut vehicle = StaticAffectedBody::new(body,(Thrust,Thrust));
+/// vehicle.step(0.01,&[1.0]);
+/// ```
+pub struct StaticAffectedBody<E, T: Float = DefaultFloatRepr, W: WindModel<T> = ConstantWind<T>, D: DensityModel<T> = StandardDensity> {
+    /// Underlying AeroBody
+    pub body: AeroBody<T,W,D>,
+    /// Set of aerodynamic effects
+    pub effectors: E,
+    /// Reference geometry passed to the effectors, if set
+    geometry: Option<VehicleGeometry<T>>,
+}
+
+impl<E, T: Float, W: WindModel<T>, D: DensityModel<T>> StaticAffectedBody<E,T,W,D> {
+    /// Create a new [StaticAffectedBody] from `body` with the aerodynamic `effectors`
+    pub fn new(body: AeroBody<T,W,D>, effectors: E) -> Self {
+        StaticAffectedBody {
+            body,
+            effectors,
+            geometry: None,
+        }
+    }
+
+    /// Set the reference `geometry` of the effectors
+    ///
+    /// The inputstate type `I` cannot be inferred from the geometry, so it must be given explicitly.
+    pub fn with_geometry<I>(mut self, geometry: VehicleGeometry<T>) -> Self where E: EffectorSet<I,T> {
+        self.set_geometry(geometry);
+        self
+    }
+
+    /// Pass the reference `geometry` to every effector
+    ///
+    /// See [AffectedBody::set_geometry](crate::AffectedBody::set_geometry) for more details
+    pub fn set_geometry<I>(&mut self, geometry: VehicleGeometry<T>) where E: EffectorSet<I,T> {
+        self.effectors.set_geometry(&geometry);
+        self.geometry = Some(geometry);
+    }
+
+    /// Return the reference geometry, if set
+    pub fn geometry(&self) -> Option<&VehicleGeometry<T>> {
+        self.geometry.as_ref()
+    }
+
+    /// Return a [Snapshot] of the statevector, simulation time, wind model state and effector states
+    ///
+    /// The effectors are named by their index in the [EffectorSet], and there are no contact states. As for
+    /// [StaticAffectedBody::with_geometry], the inputstate type `I` must be given explicitly.
+    pub fn snapshot<I>(&self) -> Snapshot<T> where E: EffectorSet<I,T> {
+        Snapshot { effectors: self.effectors.snapshot(), ..self.body.snapshot() }
+    }
+
+    /// Restore the statevector, simulation time, wind model state and effector states from `snapshot`
+    ///
+    /// See [AffectedBody::restore](crate::AffectedBody::restore) for more details
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::SnapshotMismatch] if the effectors differ from those of the snapshot, the snapshot has
+    /// contact states or a state does not match its effector or the wind model, in which case the vehicle is
+    /// unchanged.
+    pub fn restore<I>(&mut self, snapshot: &Snapshot<T>) -> Result<(),Error> where E: EffectorSet<I,T> {
+        if !snapshot.contacts.is_empty() {
+            return Err(Error::SnapshotMismatch);
+        }
+        let current = self.effectors.snapshot();
+        self.effectors.restore(&snapshot.effectors)
+            .and_then(|_| self.body.restore(snapshot))
+            .inspect_err(|_| {
+                // Roll back any effectors already restored
+                let _ = self.effectors.restore(&current);
+            })
+    }
+
+    /// Propagate the system state by delta_t with `inputstate`
+    ///
+    /// See [AffectedBody::step](crate::AffectedBody::step) for more details
+    pub fn step<I>(&mut self, delta_t: T, inputstate: &I) where E: EffectorSet<I,T> {
+        let airstate = self.body.get_airstate();
+        let rates = self.body.rates();
+
+        let mut forces = [Force::body(T::zero(),T::zero(),T::zero()),Force::world(T::zero(),T::zero(),T::zero())];
+        let mut torques = [Torque::body(T::zero(),T::zero(),T::zero()),Torque::world(T::zero(),T::zero(),T::zero())];
+        self.effectors.for_each_effect(airstate,rates,inputstate,|force,torque| {
+            match force.frame {
+                Frame::Body => forces[0].force += force.force,
+                Frame::World => forces[1].force += force.force,
+            }
+            match torque.frame {
+                Frame::Body => torques[0].torque += torque.torque,
+                Frame::World => torques[1].torque += torque.torque,
+            }
+        });
+
+        self.effectors.step(airstate,rates,inputstate,delta_t);
+
+        self.body.step(&forces,&torques,delta_t);
+    }
+
+    /// Get the world-to-body DCM of the current attitude
+    ///
+    /// See [Body::dcm](crate::Body::dcm) for more details
+    pub fn dcm(&self) -> Matrix3<T> {
+        self.body.dcm()
+    }
+
+    /// Get body acceleration in previous timestep
+    ///
+    /// See [Body::acceleration](crate::Body::acceleration) for more details
+    pub fn acceleration(&self) -> Vector3<T> {
+        self.body.acceleration()
+    }
+
+    /// Set the statevector for the underlying [AeroBody]
+    ///
+    /// The statevector is in the order: \[position,velocity(body),attitude_quaternion(i,j,k,w),axis_rates(body)\]
+    pub fn set_state(&mut self, new_state: StateVector<T>) {
+        self.body.set_state(new_state);
+    }
+
+    /// Set the position and attitude from `pose`, the body-to-world isometry
+    ///
+    /// The body-frame velocity and axis rates are kept. See [Body::set_pose](crate::Body::set_pose) for more details
+    pub fn set_pose(&mut self, pose: &Isometry3<T>) {
+        self.body.set_pose(pose);
+    }
+
+    /// Return the current airstate for the rigid body
+    /// See documentation for [AeroBody::get_airstate]
+    pub fn get_airstate(&self) -> AirState<T> {
+        self.body.get_airstate()
+    }
+
+    /// Return the elapsed simulation time (s)
+    ///
+    /// See [AeroBody::time] for more details
+    pub fn time(&self) -> T {
+        self.body.time()
+    }
+
+    /// Set the elapsed simulation time (s)
+    pub fn set_time(&mut self, time: T) {
+        self.body.set_time(time);
+    }
+}
+
+impl<E, T: Float, W: WindModel<T>, D: DensityModel<T>> StateView<T> for StaticAffectedBody<E,T,W,D> {
+    fn position(&self) -> Vector3<T> {
+        self.body.position()
+    }
+
+    fn velocity_in_frame(&self, frame: Frame) -> Vector3<T> {
+        self.body.velocity_in_frame(frame)
+    }
+
+    fn attitude(&self) -> UnitQuaternion<T> {
+        self.body.attitude()
+    }
+
+    fn rates_in_frame(&self, frame: Frame) -> Vector3<T> {
+        self.body.rates_in_frame(frame)
+    }
+
+    fn statevector(&self) -> StateVector<T> {
+        self.body.statevector()
+    }
+}
@@ -0,0 +1,193 @@
+//! Step performance statistics
+//!
+//! Collection is opt-in with [AffectedBody::enable_stats](crate::AffectedBody::enable_stats). Once enabled,
+//! each [AffectedBody::step](crate::AffectedBody::step) records its wall-clock time, the integrator stages
+//! and the effector and contact evaluations in a [StepStats], to tune the timestep and integrator against a real-time
+//! budget. Bodies without statistics enabled pay no timing cost. On `wasm32`, which has no wall clock,
+//! the step times are recorded as zero and only the counts are collected.
+//!
+//! ```
+//! use aerso::{Body,AeroBody,AffectedBody};
+//! use aerso::types::Matrix3;
+//!
+//! let mut vehicle: AffectedBody = AffectedBody::new(AeroBody::new(Body::new_at_origin(1.0,Matrix3::identity())),vec![]);
+//! vehicle.enable_stats();
+//! for _ in 0..100 {
+//!     vehicle.step(0.01,&vec![]);
+//! }
+//! let stats = vehicle.stats().unwrap();
+//! assert_eq!(stats.steps,100);
+//! println!("Mean step time {:?}",stats.mean_step_time());
+//! ```
+
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Wall-clock timer of a single step
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct StepTimer(Instant);
+
+/// Wall-clock timer of a single step, always reading zero as `wasm32` has no wall clock
+#[cfg(target_arch = "wasm32")]
+pub(crate) struct StepTimer;
+
+impl StepTimer {
+    /// Start timing a step
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn start() -> Self {
+        StepTimer(Instant::now())
+    }
+
+    /// Start timing a step
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn start() -> Self {
+        StepTimer
+    }
+
+    /// Return the time since the step started
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+
+    /// Return the time since the step started
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Statistics of the steps of a body
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq)]
+pub struct StepStats {
+    /// Number of steps
+    pub steps: usize,
+    /// Number of integrator stages, the derivative evaluations of the equations of motion
+    pub integrator_stages: usize,
+    /// Number of effector force and torque evaluations
+    pub effector_evaluations: usize,
+    /// Number of effector internal state updates
+    pub effector_steps: usize,
+    /// Number of contact model evaluations, including towline contacts
+    pub contact_evaluations: usize,
+    /// Wall-clock time of the latest step
+    pub last_step_time: Duration,
+    /// Longest wall-clock time of any step
+    pub max_step_time: Duration,
+    /// Total wall-clock time of all steps
+    pub total_step_time: Duration,
+}
+
+impl StepStats {
+    /// Number of integrator stages of each step of the fourth-order Runge-Kutta integrator
+    pub(crate) const RK4_STAGES: usize = 4;
+
+    /// Create a new empty [StepStats]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a step which took `time`, with `evaluations` effector evaluations, `updates` effector updates and
+    /// `contacts` contact evaluations
+    pub(crate) fn record(&mut self, time: Duration, evaluations: usize, updates: usize, contacts: usize) {
+        self.steps += 1;
+        self.integrator_stages += Self::RK4_STAGES;
+        self.effector_evaluations += evaluations;
+        self.effector_steps += updates;
+        self.contact_evaluations += contacts;
+        self.last_step_time = time;
+        self.max_step_time = self.max_step_time.max(time);
+        self.total_step_time += time;
+    }
+
+    /// Return the mean wall-clock time of a step, or zero if there have been no steps
+    pub fn mean_step_time(&self) -> Duration {
+        match self.steps {
+            0 => Duration::ZERO,
+            steps => Duration::from_secs_f64(self.total_step_time.as_secs_f64() / steps as f64),
+        }
+    }
+
+    /// Return the fraction of a real-time budget of `delta_t` (s) per step used by the mean step time
+    pub fn budget_fraction(&self, delta_t: f64) -> f64 {
+        self.mean_step_time().as_secs_f64() / delta_t
+    }
+
+    /// Reset all statistics to zero
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Body,AeroBody,AffectedBody};
+    use crate::effector_models::Damping;
+    use crate::contact::{WaterContact,FloatHull};
+    use crate::types::{Vector3,Matrix3};
+
+    #[test]
+    fn test_record() {
+        let mut stats = StepStats::new();
+        assert_eq!(stats.mean_step_time(),Duration::ZERO);
+        stats.record(Duration::from_micros(10),2,2,1);
+        stats.record(Duration::from_micros(30),2,1,1);
+        assert_eq!(stats.steps,2);
+        assert_eq!(stats.integrator_stages,8);
+        assert_eq!((stats.effector_evaluations,stats.effector_steps,stats.contact_evaluations),(4,3,2));
+        assert_eq!(stats.last_step_time,Duration::from_micros(30));
+        assert_eq!(stats.max_step_time,Duration::from_micros(30));
+        assert_eq!(stats.mean_step_time(),Duration::from_micros(20));
+        assert!((stats.budget_fraction(0.0001) - 0.2).abs() < 1e-9);
+        stats.reset();
+        assert_eq!(stats,StepStats::default());
+
+        // More steps than fit in a u32
+        let long_run = StepStats { steps: 1 << 33, total_step_time: Duration::from_secs(1 << 34), ..StepStats::default() };
+        assert_eq!(long_run.mean_step_time(),Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_vehicle_stats() {
+        let mut vehicle: AffectedBody = AffectedBody::new(AeroBody::new(Body::new_at_origin(1.0,Matrix3::identity())),vec![]);
+        vehicle.add_effector("damping",Box::new(Damping::new(Vector3::new(1.0,1.0,1.0),Vector3::zeros())));
+        vehicle.add_effector("spare",Box::new(Damping::new(Vector3::zeros(),Vector3::zeros())));
+        vehicle.step(0.01,&vec![]);
+        assert!(vehicle.stats().is_none());
+
+        vehicle.enable_stats();
+        vehicle.disable("spare");
+        for _ in 0..10 {
+            vehicle.step(0.01,&vec![]);
+        }
+        let stats = vehicle.stats().unwrap();
+        assert_eq!(stats.steps,10);
+        assert_eq!(stats.integrator_stages,40);
+        assert_eq!(stats.effector_evaluations,10);
+        assert_eq!(stats.effector_steps,10);
+        assert!(stats.max_step_time >= stats.mean_step_time());
+
+        vehicle.reset_stats();
+        assert_eq!(vehicle.stats().unwrap().steps,0);
+        vehicle.disable_stats();
+        assert!(vehicle.stats().is_none());
+    }
+
+    #[test]
+    fn test_contact_stats() {
+        let mut vehicle: AffectedBody = AffectedBody::new(AeroBody::new(Body::new_at_origin(1.0,Matrix3::identity())),vec![]);
+        vehicle.add_effector("spare",Box::new(Damping::new(Vector3::zeros(),Vector3::zeros())));
+        vehicle.add_contact(Box::new(WaterContact::new(vec![FloatHull::new(Vector3::zeros(),1.0,0.5,0.4)])));
+        vehicle.enable_stats();
+        vehicle.disable("spare");
+        for _ in 0..10 {
+            vehicle.step(0.01,&vec![]);
+        }
+        // The contact is counted apart from the disabled effector
+        let stats = vehicle.stats().unwrap();
+        assert_eq!((stats.effector_evaluations,stats.effector_steps),(0,0));
+        assert_eq!(stats.contact_evaluations,10);
+    }
+}
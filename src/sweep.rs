@@ -0,0 +1,183 @@
+//! Parameter sweeps
+//!
+//! A [Sweep] evaluates a user-supplied closure at every point of a grid of named axes, such as centre
+//! of gravity position × airspeed × altitude, and collects the results in a [SweepTable]. The closure
+//! typically trims, linearises or simulates a vehicle at each point, for envelope and sensitivity maps.
+//! Points are visited in row-major order, with the last axis varying fastest.
+
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Single point of a [Sweep]
+#[derive(Clone,Debug)]
+pub struct SweepPoint<T: Float = DefaultFloatRepr> {
+    /// Index of the point in the sweep
+    pub index: usize,
+    /// Axis names and values
+    values: Vec<(String,T)>,
+}
+
+impl<T: Float> SweepPoint<T> {
+    /// Return the value of the axis called `name`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such axis, as this is almost always a misspelled name.
+    pub fn get(&self, name: &str) -> T {
+        match self.values.iter().find(|(axis, _)| axis == name) {
+            Some((_, value)) => *value,
+            None => panic!("No sweep axis called '{}'",name),
+        }
+    }
+
+    /// Return the axis values in axis order
+    pub fn values(&self) -> impl Iterator<Item=T> + '_ {
+        self.values.iter().map(|(_, value)| *value)
+    }
+}
+
+/// Grid of named axes to sweep over
+#[derive(Clone,Debug,Default)]
+pub struct Sweep<T: Float = DefaultFloatRepr> {
+    /// Axis names and values
+    axes: Vec<(String,Vec<T>)>,
+}
+
+impl<T: Float> Sweep<T> {
+    /// Create a new [Sweep] with no axes
+    pub fn new() -> Self {
+        Sweep {
+            axes: Vec::new(),
+        }
+    }
+
+    /// Add an axis called `name` taking each of `values`
+    pub fn with_axis(mut self, name: &str, values: &[T]) -> Self {
+        self.axes.push((name.to_string(),values.to_vec()));
+        self
+    }
+
+    /// Add an axis called `name` with `count` evenly-spaced values from `start` to `end` inclusive
+    pub fn with_linspace(self, name: &str, start: T, end: T, count: usize) -> Self {
+        let values: Vec<T> = match count {
+            0 => Vec::new(),
+            1 => vec![start],
+            _ => (0..count).map(|idx| start + (end - start) * constant::<T>(idx as f64) / constant::<T>((count - 1) as f64)).collect(),
+        };
+        self.with_axis(name,&values)
+    }
+
+    /// Return the axis names
+    pub fn axis_names(&self) -> impl Iterator<Item=&str> {
+        self.axes.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Return the number of points in the sweep
+    pub fn len(&self) -> usize {
+        if self.axes.is_empty() { 0 } else { self.axes.iter().map(|(_, values)| values.len()).product() }
+    }
+
+    /// Return `true` if the sweep has no points
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the point at `index`
+    pub fn point(&self, index: usize) -> SweepPoint<T> {
+        let mut remainder = index;
+        let mut values = vec![(String::new(),T::zero()); self.axes.len()];
+        for (slot, (name, axis)) in values.iter_mut().zip(self.axes.iter()).rev() {
+            *slot = (name.clone(),axis[remainder % axis.len()]);
+            remainder /= axis.len();
+        }
+        SweepPoint { index, values }
+    }
+
+    /// Return an iterator over every point of the sweep
+    pub fn points(&self) -> impl Iterator<Item=SweepPoint<T>> + '_ {
+        (0..self.len()).map(move |index| self.point(index))
+    }
+
+    /// Evaluate `evaluate` at every point of the sweep
+    pub fn run<R, F: FnMut(&SweepPoint<T>) -> R>(&self, mut evaluate: F) -> SweepTable<R,T> {
+        SweepTable {
+            axes: self.axis_names().map(str::to_string).collect(),
+            rows: self.points().map(|point| {
+                let result = evaluate(&point);
+                (point,result)
+            }).collect(),
+        }
+    }
+}
+
+/// Results of a [Sweep], with one row per point
+#[derive(Clone,Debug)]
+pub struct SweepTable<R, T: Float = DefaultFloatRepr> {
+    /// Axis names
+    pub axes: Vec<String>,
+    /// Sweep points and their results
+    pub rows: Vec<(SweepPoint<T>,R)>,
+}
+
+impl<R, T: Float> SweepTable<R,T> {
+    /// Return one value per row, extracted from each result by `value`
+    pub fn column<F: Fn(&R) -> T>(&self, value: F) -> Vec<T> {
+        self.rows.iter().map(|(_, result)| value(result)).collect()
+    }
+
+    /// Return the table as CSV, with the axis values followed by the named `columns` of each result
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Names of the result columns
+    /// * `values` - Closure returning the value of each result column, in order
+    pub fn to_csv<F: Fn(&R) -> Vec<T>>(&self, columns: &[&str], values: F) -> String {
+        let header: Vec<&str> = self.axes.iter().map(String::as_str).chain(columns.iter().copied()).collect();
+        let mut csv = header.join(",");
+        csv.push('\n');
+        for (point, result) in &self.rows {
+            let row: Vec<String> = point.values().chain(values(result)).map(|value| value.to_string()).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trim::{Trim,TrimCondition,test_aircraft};
+
+    #[test]
+    fn test_grid_order() {
+        let sweep = Sweep::new().with_axis("cg",&[-0.01,0.01]).with_linspace("airspeed",15.0,25.0,3);
+        assert_eq!(sweep.len(),6);
+        let point = sweep.point(4);
+        assert_eq!(point.get("cg"),0.01);
+        assert_eq!(point.get("airspeed"),20.0);
+        assert_eq!(sweep.points().map(|point| point.get("airspeed")).collect::<Vec<f64>>(),vec![15.0,20.0,25.0,15.0,20.0,25.0]);
+        assert!(Sweep::<f64>::new().is_empty());
+    }
+
+    #[test]
+    fn test_trim_envelope() {
+        let sweep = Sweep::new().with_linspace("airspeed",16.0,28.0,4).with_axis("altitude",&[100.0,1000.0]);
+        let trim = Trim::new(vec![0.0,0.0,0.0,0.5],&[0,1,2,3]);
+        let table = sweep.run(|point| {
+            let mut vehicle = test_aircraft::vehicle(point.get("altitude"));
+            trim.solve(&mut vehicle,&TrimCondition::level(point.get("airspeed")))
+        });
+
+        // Trim angle of attack falls with airspeed
+        let alpha = table.column(|result| result.as_ref().map(|trim| trim.alpha).unwrap_or(f64::NAN));
+        assert!(alpha.iter().all(|alpha| alpha.is_finite()));
+        assert!(alpha[0] > alpha[2] && alpha[2] > alpha[4] && alpha[4] > alpha[6]);
+
+        let csv = table.to_csv(&["alpha","elevator"],|result| {
+            let trim = result.as_ref().unwrap();
+            vec![trim.alpha,trim.inputs[1]]
+        });
+        assert_eq!(csv.lines().next().unwrap(),"airspeed,altitude,alpha,elevator");
+        assert_eq!(csv.lines().count(),9);
+    }
+}
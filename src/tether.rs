@@ -0,0 +1,430 @@
+//! Tethers between a vehicle and a world anchor
+//!
+//! A [Tether] runs in a straight line from a hook on the vehicle to an anchor in the world frame. It
+//! gives the geometry of the cable and the force and torque of a tension in it, and can only pull. Launch
+//! and towing models such as [WinchLaunch](crate::launch::WinchLaunch) decide the tension.
+//!
+//! A [Towline] is a flexible hose or towline between two flying vehicles, modelled as a chain of lumped
+//! masses joined by elastic segments, with cross-flow drag on the line and a [Drogue] end-body. The tow
+//! end is held at a hook on the towing vehicle, and the tail end is either free, as for a refuelling
+//! drogue, or attached to a hook on a towed vehicle. It can be stepped directly, or between two
+//! vehicles of a [World](crate::world::World) with [World::add_towline](crate::world::World::add_towline).
+
+use crate::Body;
+use crate::types::{Vector3,StateVector,StateView,Force,Torque,Float,DefaultFloatRepr,constant};
+
+/// Return the world position (m) and velocity (m·s<sup>-1</sup>) of `hook`, relative to the centre of mass in the body frame, of a vehicle in `state`
+fn hook_motion<T: Float>(state: &StateVector<T>, hook: &Vector3<T>) -> (Vector3<T>,Vector3<T>) {
+    let dcm_body = Body::get_dcm_body(state);
+    (state.position() + dcm_body * hook,dcm_body * (state.velocity() + state.rates().cross(hook)))
+}
+
+/// Geometry of a [Tether]
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct TetherGeometry<T: Float = DefaultFloatRepr> {
+    /// Straight-line length from the hook to the anchor (m)
+    pub length: T,
+    /// World-frame unit vector from the hook towards the anchor
+    pub direction: Vector3<T>,
+    /// Rate of change of the length (m·s<sup>-1</sup>), positive as the hook moves away from the anchor
+    pub rate: T,
+    /// Angle of the tether below the horizontal at the hook (rad)
+    pub depression: T,
+}
+
+/// Straight tether from a hook on a vehicle to a world anchor
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Tether<T: Float = DefaultFloatRepr> {
+    /// World position of the anchor (m)
+    anchor: Vector3<T>,
+    /// Velocity of the anchor, world frame (m·s<sup>-1</sup>)
+    anchor_velocity: Vector3<T>,
+    /// Hook position relative to centre of mass, body frame (m)
+    hook: Vector3<T>,
+}
+
+impl<T: Float> Tether<T> {
+    /// Create a new [Tether] from `hook`, relative to the centre of mass in the body frame (m), to the
+    /// stationary world `anchor` (m)
+    pub fn new(anchor: Vector3<T>, hook: Vector3<T>) -> Self {
+        Tether { anchor, anchor_velocity: Vector3::zeros(), hook }
+    }
+
+    /// Move the anchor to the world `anchor` (m) with world-frame `velocity` (m·s<sup>-1</sup>)
+    pub fn set_anchor(&mut self, anchor: Vector3<T>, velocity: Vector3<T>) {
+        self.anchor = anchor;
+        self.anchor_velocity = velocity;
+    }
+
+    /// Return the world position of the anchor (m)
+    pub fn anchor(&self) -> Vector3<T> {
+        self.anchor
+    }
+
+    /// Return the hook position relative to the centre of mass, body frame (m)
+    pub fn hook(&self) -> Vector3<T> {
+        self.hook
+    }
+
+    /// Return the [TetherGeometry] of a vehicle in `state`
+    ///
+    /// The direction is along world +Z when the hook is at the anchor.
+    pub fn geometry(&self, state: &StateVector<T>) -> TetherGeometry<T> {
+        let (hook_position, hook_velocity) = hook_motion(state,&self.hook);
+        let offset = self.anchor - hook_position;
+        let length = offset.norm();
+        let direction = if length > T::zero() { offset / length } else { Vector3::z() };
+        let horizontal = <T as num_traits::Float>::hypot(direction[0],direction[1]);
+        TetherGeometry {
+            length,
+            direction,
+            rate: (self.anchor_velocity - hook_velocity).dot(&direction),
+            depression: <T as num_traits::Float>::atan2(direction[2],horizontal),
+        }
+    }
+
+    /// Return the body-frame force and torque of `tension` (N) in the tether on a vehicle in `state`
+    ///
+    /// A tether cannot push, so a negative tension gives no force.
+    pub fn effect(&self, state: &StateVector<T>, tension: T) -> (Force<T>,Torque<T>) {
+        if tension <= T::zero() {
+            return (Force::body_vec(Vector3::zeros()),Torque::body_vec(Vector3::zeros()));
+        }
+        let force = Body::get_dcm(state) * self.geometry(state).direction * tension;
+        (Force::body_vec(force),Torque::body_vec(self.hook.cross(&force)))
+    }
+}
+
+/// End-body at the tail of a [Towline], such as a refuelling drogue
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Drogue<T: Float = DefaultFloatRepr> {
+    /// Mass (kg)
+    pub mass: T,
+    /// Drag area `C_D S` (m<sup>2</sup>)
+    pub drag_area: T,
+}
+
+/// Flexible hose or towline between two vehicles, with a [Drogue] at the tail
+///
+/// The line is `segments` elastic segments joining lumped masses. Node zero is held at the tow hook, and
+/// the last node carries the drogue, or is held at the tail hook once attached. Each segment carries a
+/// tension `EA ε` with damping, and none when slack, and has cross-flow drag `½ ρ C_D d l |v_n| v_n` on
+/// the velocity normal to it, shared between its nodes. The line is integrated in substeps short enough
+/// for its stiffness.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Towline<T: Float = DefaultFloatRepr> {
+    /// Tow hook relative to the centre of mass of the towing vehicle, body frame (m)
+    tow_hook: Vector3<T>,
+    /// Tail hook relative to the centre of mass of the towed vehicle, body frame (m), once attached
+    tail_hook: Option<Vector3<T>>,
+    /// Probe of the towed vehicle, body frame (m), and radius (m) within which it captures the drogue
+    capture: Option<(Vector3<T>,T)>,
+    /// Unstretched length of each segment (m)
+    segment_length: T,
+    /// Axial stiffness `EA` (N)
+    stiffness: T,
+    /// Damping ratio of each segment
+    damping: T,
+    /// Mass per unit length (kg·m<sup>-1</sup>)
+    mass_per_length: T,
+    /// Diameter (m)
+    diameter: T,
+    /// Cross-flow drag coefficient
+    drag_coefficient: T,
+    /// Drogue at the tail
+    drogue: Drogue<T>,
+    /// World position of each node (m)
+    positions: Vec<Vector3<T>>,
+    /// World velocity of each node (m·s<sup>-1</sup>)
+    velocities: Vec<Vector3<T>>,
+}
+
+impl<T: Float> Towline<T> {
+    /// Create a new [Towline] of `length` (m) in `segments` from `tow_hook`, relative to the centre of mass of the towing vehicle in the body frame (m), with axial stiffness `stiffness` (N)
+    ///
+    /// The line defaults to 0.5 kg·m<sup>-1</sup>, 50 mm diameter with a cross-flow drag coefficient of
+    /// 1.2 and a damping ratio of 0.5, with a 1 kg drogue with no drag. It has no nodes until
+    /// [deployed](Towline::deploy).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` or `stiffness` is not positive, or `segments` is zero.
+    pub fn new(tow_hook: Vector3<T>, length: T, segments: usize, stiffness: T) -> Self {
+        if length <= T::zero() || stiffness <= T::zero() || segments == 0 {
+            panic!("Towline length, stiffness and segments must be positive");
+        }
+        Towline {
+            tow_hook,
+            tail_hook: None,
+            capture: None,
+            segment_length: length / constant::<T>(segments as f64),
+            stiffness,
+            damping: constant::<T>(0.5),
+            mass_per_length: constant::<T>(0.5),
+            diameter: constant::<T>(0.05),
+            drag_coefficient: constant::<T>(1.2),
+            drogue: Drogue { mass: T::one(), drag_area: T::zero() },
+            positions: vec![Vector3::zeros(); segments + 1],
+            velocities: vec![Vector3::zeros(); segments + 1],
+        }
+    }
+
+    /// Set the line mass per unit length (kg·m<sup>-1</sup>), diameter (m) and cross-flow drag coefficient
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mass_per_length` is not positive.
+    pub fn with_line(mut self, mass_per_length: T, diameter: T, drag_coefficient: T) -> Self {
+        if mass_per_length <= T::zero() {
+            panic!("Towline mass per length must be positive");
+        }
+        self.mass_per_length = mass_per_length;
+        self.diameter = diameter;
+        self.drag_coefficient = drag_coefficient;
+        self
+    }
+
+    /// Set the damping ratio of each segment
+    pub fn with_damping(mut self, damping: T) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    /// Set the [Drogue] at the tail
+    pub fn with_drogue(mut self, drogue: Drogue<T>) -> Self {
+        self.drogue = drogue;
+        self
+    }
+
+    /// Capture the drogue with `probe`, relative to the centre of mass of the towed vehicle in the body frame (m), once within `radius` (m)
+    ///
+    /// On capture the tail is attached at the probe, as for probe-and-drogue refuelling contact.
+    pub fn with_capture(mut self, probe: Vector3<T>, radius: T) -> Self {
+        self.capture = Some((probe,radius));
+        self
+    }
+
+    /// Lay the line out straight and taut behind the tow hook of a vehicle in `tow`, moving with the hook
+    pub fn deploy(&mut self, tow: &StateVector<T>) {
+        let (hook, velocity) = hook_motion(tow,&self.tow_hook);
+        let aft = -(Body::get_dcm_body(tow) * Vector3::x());
+        for (idx, (position, node_velocity)) in self.positions.iter_mut().zip(self.velocities.iter_mut()).enumerate() {
+            *position = hook + aft * (self.segment_length * constant::<T>(idx as f64));
+            *node_velocity = velocity;
+        }
+    }
+
+    /// Attach the tail to `hook`, relative to the centre of mass of the towed vehicle in the body frame (m)
+    pub fn attach_tail(&mut self, hook: Vector3<T>) {
+        self.tail_hook = Some(hook);
+    }
+
+    /// Release the tail, leaving the drogue free, and stop any capture
+    pub fn release_tail(&mut self) {
+        self.tail_hook = None;
+        self.capture = None;
+    }
+
+    /// Return `true` if the tail is attached to a towed vehicle
+    pub fn is_attached(&self) -> bool {
+        self.tail_hook.is_some()
+    }
+
+    /// Return the world position of each node from the tow hook to the tail (m)
+    pub fn nodes(&self) -> &[Vector3<T>] {
+        &self.positions
+    }
+
+    /// Return the world position of the drogue (m)
+    pub fn drogue_position(&self) -> Vector3<T> {
+        self.positions[self.positions.len() - 1]
+    }
+
+    /// Return the tension (N) of each segment from the tow hook to the tail
+    pub fn tensions(&self) -> Vec<T> {
+        (0..self.positions.len() - 1).map(|idx| self.segment(idx).1).collect()
+    }
+
+    /// Return the mass of each node (kg)
+    fn node_mass(&self, idx: usize) -> T {
+        let segment_mass = self.mass_per_length * self.segment_length;
+        let last = self.positions.len() - 1;
+        if idx == last {
+            segment_mass / constant::<T>(2.0) + self.drogue.mass
+        } else if idx == 0 {
+            segment_mass / constant::<T>(2.0)
+        } else {
+            segment_mass
+        }
+    }
+
+    /// Return the damping coefficient of each segment (N·s·m<sup>-1</sup>)
+    fn segment_damping(&self) -> T {
+        let k = self.stiffness / self.segment_length;
+        constant::<T>(2.0) * self.damping * <T as num_traits::Float>::sqrt(k * self.mass_per_length * self.segment_length)
+    }
+
+    /// Return the unit vector along segment `idx` towards the tail, and its tension (N)
+    fn segment(&self, idx: usize) -> (Vector3<T>,T) {
+        let offset = self.positions[idx + 1] - self.positions[idx];
+        let length = offset.norm();
+        if length == T::zero() {
+            return (Vector3::zeros(),T::zero());
+        }
+        let direction = offset / length;
+        let rate = (self.velocities[idx + 1] - self.velocities[idx]).dot(&direction);
+        let strain = (length - self.segment_length) / self.segment_length;
+        let tension = self.stiffness * strain + self.segment_damping() * rate;
+        (direction,if length > self.segment_length { <T as num_traits::Float>::max(tension,T::zero()) } else { T::zero() })
+    }
+
+    /// Return the tension (N) of a segment from `hook`, its world position (m) and velocity (m·s<sup>-1</sup>), to the node `node`
+    fn end_tension(&self, hook: (Vector3<T>,Vector3<T>), node: usize) -> T {
+        let offset = self.positions[node] - hook.0;
+        let length = offset.norm();
+        if length <= self.segment_length {
+            return T::zero();
+        }
+        let rate = (self.velocities[node] - hook.1).dot(&(offset / length));
+        let tension = self.stiffness * (length - self.segment_length) / self.segment_length + self.segment_damping() * rate;
+        <T as num_traits::Float>::max(tension,T::zero())
+    }
+
+    /// Return the body-frame force and torque of the line on the towing vehicle in `tow`
+    pub fn tow_effect(&self, tow: &StateVector<T>) -> (Force<T>,Torque<T>) {
+        let tension = self.end_tension(hook_motion(tow,&self.tow_hook),1);
+        Tether::new(self.positions[1],self.tow_hook).effect(tow,tension)
+    }
+
+    /// Return the body-frame force and torque of the line on the towed vehicle in `tail`, or `None` if the tail is not attached
+    pub fn tail_effect(&self, tail: &StateVector<T>) -> Option<(Force<T>,Torque<T>)> {
+        let hook = self.tail_hook?;
+        let node = self.positions.len() - 2;
+        let tension = self.end_tension(hook_motion(tail,&hook),node);
+        Some(Tether::new(self.positions[node],hook).effect(tail,tension))
+    }
+
+    /// Advance the line by `delta_t` (s) between the towing vehicle in `tow` and any towed vehicle in `tail`, in `wind` (world frame, m·s<sup>-1</sup>) and air of `density` (kg·m<sup>-3</sup>)
+    ///
+    /// The hooks move at their velocities at the start of the step. A towed vehicle with a capture probe
+    /// within range of the drogue attaches first.
+    pub fn step(&mut self, delta_t: T, tow: &StateVector<T>, tail: Option<&StateVector<T>>, wind: Vector3<T>, density: T) {
+        if let (None, Some((probe, radius)), Some(tail)) = (self.tail_hook, self.capture, tail) {
+            if (hook_motion(tail,&probe).0 - self.drogue_position()).norm() <= radius {
+                self.tail_hook = Some(probe);
+            }
+        }
+        let last = self.positions.len() - 1;
+        let tow_hook = hook_motion(tow,&self.tow_hook);
+        let tail_hook = match (self.tail_hook, tail) {
+            (Some(hook), Some(tail)) => Some(hook_motion(tail,&hook)),
+            _ => None,
+        };
+
+        // Substeps well inside the stability limit of the stiffest segment on the lightest node
+        let lightest = self.mass_per_length * self.segment_length / constant::<T>(2.0);
+        let limit = constant::<T>(0.2) * <T as num_traits::Float>::sqrt(lightest * self.segment_length / self.stiffness);
+        let substeps = <T as num_traits::Float>::ceil(delta_t / limit).to_usize().unwrap_or(1).max(1);
+        let tau = delta_t / constant::<T>(substeps as f64);
+        let gravity = Vector3::new(T::zero(),T::zero(),constant::<T>(physical_constants::STANDARD_ACCELERATION_OF_GRAVITY));
+        let half = constant::<T>(0.5);
+        let masses: Vec<T> = (0..=last).map(|idx| self.node_mass(idx)).collect();
+
+        for substep in 1..=substeps {
+            let elapsed = tau * constant::<T>(substep as f64);
+            let mut forces: Vec<Vector3<T>> = masses.iter().map(|mass| gravity * *mass).collect();
+            for idx in 0..last {
+                let (direction, tension) = self.segment(idx);
+                forces[idx] += direction * tension;
+                forces[idx + 1] -= direction * tension;
+                let relative = (self.velocities[idx] + self.velocities[idx + 1]) * half - wind;
+                let normal = relative - direction * relative.dot(&direction);
+                let length = (self.positions[idx + 1] - self.positions[idx]).norm();
+                let drag = normal * (-half * density * self.drag_coefficient * self.diameter * length * normal.norm());
+                forces[idx] += drag * half;
+                forces[idx + 1] += drag * half;
+            }
+            let relative = self.velocities[last] - wind;
+            forces[last] -= relative * (half * density * self.drogue.drag_area * relative.norm());
+
+            for (((position, velocity), force), mass) in self.positions.iter_mut().zip(self.velocities.iter_mut()).zip(forces).zip(masses.iter()).skip(1) {
+                *velocity += force * (tau / *mass);
+                *position += *velocity * tau;
+            }
+            self.positions[0] = tow_hook.0 + tow_hook.1 * elapsed;
+            self.velocities[0] = tow_hook.1;
+            if let Some((position, velocity)) = tail_hook {
+                self.positions[last] = position + velocity * elapsed;
+                self.velocities[last] = velocity;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{Matrix3,UnitQuaternion};
+    use approx::assert_relative_eq;
+
+    fn state(position: Vector3<f64>, velocity: Vector3<f64>) -> StateVector<f64> {
+        Body::new(1.0,Matrix3::identity(),position,velocity,UnitQuaternion::identity(),Vector3::zeros()).statevector()
+    }
+
+    #[test]
+    fn test_geometry() {
+        // Hook on the nose, 300 m ahead of and 400 m above the winch
+        let tether = Tether::new(Vector3::zeros(),Vector3::new(1.0,0.0,0.0));
+        let geometry = tether.geometry(&state(Vector3::new(299.0,0.0,-400.0),Vector3::new(10.0,0.0,0.0)));
+        assert_relative_eq!(geometry.length,500.0,max_relative=1e-12);
+        assert!((geometry.direction - Vector3::new(-0.6,0.0,0.8)).norm() < 1e-12);
+        assert_relative_eq!(geometry.rate,6.0,max_relative=1e-12);
+        assert_relative_eq!(geometry.depression,(0.8_f64).atan2(0.6),max_relative=1e-12);
+
+        // A moving anchor following the vehicle holds the length
+        let mut towed = tether;
+        towed.set_anchor(Vector3::zeros(),Vector3::new(10.0,0.0,0.0));
+        assert_relative_eq!(towed.geometry(&state(Vector3::new(299.0,0.0,-400.0),Vector3::new(10.0,0.0,0.0))).rate,0.0,epsilon=1e-12);
+    }
+
+    #[test]
+    fn test_tension() {
+        let tether = Tether::new(Vector3::new(-100.0,0.0,0.5),Vector3::new(0.0,0.0,0.5));
+        let vehicle = state(Vector3::zeros(),Vector3::zeros());
+        let (force,torque) = tether.effect(&vehicle,200.0);
+        assert!((force.force - Vector3::new(-200.0,0.0,0.0)).norm() < 1e-9);
+        // Pulled aft below the centre of mass, the nose pitches down
+        assert_relative_eq!(torque.torque.y,-100.0,max_relative=1e-9);
+        assert_eq!(tether.effect(&vehicle,-5.0).0.force.norm(),0.0);
+    }
+
+    #[test]
+    fn test_towline_drogue() {
+        // Hose trailed from a tanker in level flight at 100 m/s
+        let drogue = Drogue { mass: 20.0, drag_area: 0.5 };
+        let mut towline = Towline::new(Vector3::new(-5.0,0.0,1.0),30.0,10,1e5).with_line(0.5,0.07,1.2).with_drogue(drogue).with_capture(Vector3::new(4.0,0.0,0.0),0.5);
+        let velocity = Vector3::new(100.0,0.0,0.0);
+        let tanker = |time: f64| state(Vector3::new(100.0 * time,0.0,-3000.0),velocity);
+        towline.deploy(&tanker(0.0));
+        assert!((towline.drogue_position() - Vector3::new(-35.0,0.0,-2999.0)).norm() < 1e-9);
+        for step in 0..2000 {
+            towline.step(0.01,&tanker(step as f64 * 0.01),None,Vector3::zeros(),1.0);
+        }
+        let tanker = tanker(20.0);
+        let trail = towline.drogue_position() - (tanker.position() + Vector3::new(-5.0,0.0,1.0));
+        // Streamed aft and a little below the hook, stretched by the drogue drag
+        let drag = 0.5 * 1.0 * 0.5 * 100.0 * 100.0;
+        assert!(trail[0] < -30.0 && trail[0] > -32.0,"trail {}",trail);
+        assert!(trail[2] > 0.0 && trail[2] < 5.0,"trail {}",trail);
+        let (force, _) = towline.tow_effect(&tanker);
+        assert!(-force.force[0] > drag && -force.force[0] < 1.2 * drag,"tow force {}",force.force);
+        assert!(towline.tensions().windows(2).all(|pair| pair[0] >= pair[1]));
+
+        // A receiver probe reaching the drogue captures it and is pulled forward
+        assert!(towline.tail_effect(&tanker).is_none());
+        let receiver = state(towline.drogue_position() - Vector3::new(4.0,0.0,0.0),velocity);
+        towline.step(0.01,&tanker,Some(&receiver),Vector3::zeros(),1.0);
+        assert!(towline.is_attached());
+        assert!(towline.tail_effect(&receiver).unwrap().0.force[0] > 0.0);
+    }
+}
@@ -0,0 +1,355 @@
+//! Trim solver
+//!
+//! [Trim] finds the angle of attack, bank angle and free control inputs which put an [AffectedBody]
+//! in steady flight at a [TrimCondition]: straight and level, climbing or descending, or a steady
+//! coordinated turn. Steady flight is found by driving the body-frame linear and angular accelerations
+//! to zero with a Levenberg-Marquardt iteration, using finite-difference Jacobians, so any combination
+//! of effectors can be trimmed.
+
+use std::ops::IndexMut;
+
+use nalgebra::{DMatrix,DVector};
+
+use crate::{AffectedBody,WindModel,DensityModel,Error};
+use crate::types::{Vector3,StateVector,StateView,UnitQuaternion};
+use crate::types::{Float,DefaultFloatRepr,constant};
+
+/// Steady flight condition to trim for
+#[derive(Copy,Clone)]
+pub struct TrimCondition<T: Float = DefaultFloatRepr> {
+    /// True airspeed (m·s<sup>-1</sup>)
+    pub airspeed: T,
+    /// Flight path angle relative to the air mass, positive climbing (rad)
+    pub flight_path_angle: T,
+    /// Turn rate, positive turning right (rad·s<sup>-1</sup>)
+    pub turn_rate: T,
+    /// Angle of sideslip, zero for coordinated flight (rad)
+    pub sideslip: T,
+}
+
+impl<T: Float> TrimCondition<T> {
+    /// Create a straight and level [TrimCondition] at `airspeed`
+    pub fn level(airspeed: T) -> Self {
+        TrimCondition {
+            airspeed,
+            flight_path_angle: T::zero(),
+            turn_rate: T::zero(),
+            sideslip: T::zero(),
+        }
+    }
+
+    /// Set the flight path angle, positive climbing (rad)
+    pub fn with_flight_path_angle(mut self, flight_path_angle: T) -> Self {
+        self.flight_path_angle = flight_path_angle;
+        self
+    }
+
+    /// Set the turn rate, positive turning right (rad·s<sup>-1</sup>)
+    pub fn with_turn_rate(mut self, turn_rate: T) -> Self {
+        self.turn_rate = turn_rate;
+        self
+    }
+
+    /// Set the angle of sideslip (rad)
+    pub fn with_sideslip(mut self, sideslip: T) -> Self {
+        self.sideslip = sideslip;
+        self
+    }
+}
+
+/// Trimmed state and inputs
+#[derive(Clone)]
+pub struct TrimResult<I, T: Float = DefaultFloatRepr> {
+    /// Trimmed statevector
+    pub state: StateVector<T>,
+    /// Trimmed inputstate
+    pub inputs: I,
+    /// Angle of attack (rad)
+    pub alpha: T,
+    /// Pitch angle (rad)
+    pub pitch: T,
+    /// Bank angle (rad)
+    pub bank: T,
+    /// Largest remaining body-frame acceleration, linear (m·s<sup>-2</sup>) or angular (rad·s<sup>-2</sup>)
+    pub residual: T,
+    /// Number of iterations taken
+    pub iterations: usize,
+}
+
+/// Trim solver for an [AffectedBody]
+///
+/// The solver varies the angle of attack, the bank angle and the inputstate slots listed as free,
+/// starting from a template inputstate which also sets the value of every other slot. Trimming in a
+/// turn normally requires the lateral controls to be free as well as the pitch and thrust controls.
+///
+/// The position and heading of the vehicle are held, and the velocity is relative to the local wind.
+#[derive(Clone)]
+pub struct Trim<I, T: Float = DefaultFloatRepr> {
+    /// Initial inputstate, and the value of any slot not trimmed
+    template: I,
+    /// Inputstate slots varied by the solver
+    free_inputs: Vec<usize>,
+    /// Convergence tolerance on the largest acceleration
+    tolerance: T,
+    /// Maximum number of iterations
+    max_iterations: usize,
+}
+
+impl<I: Clone + IndexMut<usize,Output=T>, T: Float> Trim<I,T> {
+    /// Create a new [Trim] solver
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Initial inputstate, which also sets the value of every slot not trimmed
+    /// * `free_inputs` - Inputstate slots the solver may vary
+    pub fn new(template: I, free_inputs: &[usize]) -> Self {
+        Trim {
+            template,
+            free_inputs: free_inputs.to_vec(),
+            tolerance: <T as num_traits::Float>::sqrt(T::epsilon()),
+            max_iterations: 100,
+        }
+    }
+
+    /// Set the convergence tolerance on the largest remaining acceleration
+    pub fn with_tolerance(mut self, tolerance: T) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Set the maximum number of iterations
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Trim `vehicle` at `condition`
+    ///
+    /// On success the vehicle is left in the trimmed state and the trimmed inputs are returned. If the
+    /// solver does not converge the original state of the vehicle is restored and an error is returned.
+    pub fn solve<W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &mut AffectedBody<I,T,W,D>, condition: &TrimCondition<T>) -> Result<TrimResult<I,T>,Error> {
+        let initial_state = vehicle.statevector();
+        let (_, _, heading) = vehicle.attitude().euler_angles();
+
+        let mut x = DVector::zeros(2 + self.free_inputs.len());
+        for (idx, &slot) in self.free_inputs.iter().enumerate() {
+            x[2 + idx] = self.template[slot];
+        }
+
+        let mut residuals = self.residuals(vehicle,condition,heading,&x);
+        let mut lambda = constant::<T>(1e-3);
+        let mut iterations = 0;
+        while residuals.amax() > self.tolerance && iterations < self.max_iterations {
+            iterations += 1;
+            let jacobian = self.jacobian(vehicle,condition,heading,&x);
+            let jtj = jacobian.transpose() * &jacobian;
+            let gradient = jacobian.transpose() * &residuals;
+            // Increase the damping until the step reduces the residuals
+            loop {
+                let mut damped = jtj.clone();
+                for idx in 0..x.len() {
+                    damped[(idx,idx)] += lambda * (jtj[(idx,idx)] + T::one());
+                }
+                let step = match damped.lu().solve(&(-&gradient)) {
+                    Some(step) => step,
+                    None => break,
+                };
+                let candidate = &x + step;
+                let candidate_residuals = self.residuals(vehicle,condition,heading,&candidate);
+                if candidate_residuals.norm() < residuals.norm() {
+                    x = candidate;
+                    residuals = candidate_residuals;
+                    lambda = <T as num_traits::Float>::max(lambda / constant::<T>(10.0),constant::<T>(1e-12));
+                    break;
+                }
+                lambda *= constant::<T>(10.0);
+                if lambda > constant::<T>(1e12) {
+                    break;
+                }
+            }
+            if lambda > constant::<T>(1e12) {
+                break;
+            }
+        }
+
+        let residual = residuals.amax();
+        if residual > self.tolerance {
+            vehicle.set_state(initial_state);
+            return Err(Error::TrimNotConverged { residual: residual.to_f64().unwrap_or(f64::NAN), iterations });
+        }
+
+        let state = self.state(vehicle,condition,heading,&x);
+        vehicle.set_state(state);
+        let (bank, pitch, _) = vehicle.attitude().euler_angles();
+        Ok(TrimResult {
+            state,
+            inputs: self.inputs(&x),
+            alpha: x[0],
+            pitch,
+            bank,
+            residual,
+            iterations,
+        })
+    }
+
+    /// Return the inputstate for the trim variables `x`
+    fn inputs(&self, x: &DVector<T>) -> I {
+        let mut inputs = self.template.clone();
+        for (idx, &slot) in self.free_inputs.iter().enumerate() {
+            inputs[slot] = x[2 + idx];
+        }
+        inputs
+    }
+
+    /// Return the statevector for the trim variables `x = [alpha, bank, inputs...]`
+    ///
+    /// The pitch angle which gives the flight path angle and the body rates of a steady turn follow
+    /// Stevens and Lewis, *Aircraft Control and Simulation*, Section 3.6.
+    fn state<W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &AffectedBody<I,T,W,D>, condition: &TrimCondition<T>, heading: T, x: &DVector<T>) -> StateVector<T> {
+        let (alpha, bank, beta) = (x[0],x[1],condition.sideslip);
+        let (sin_alpha, cos_alpha) = <T as num_traits::Float>::sin_cos(alpha);
+        let (sin_beta, cos_beta) = <T as num_traits::Float>::sin_cos(beta);
+        let (sin_bank, cos_bank) = <T as num_traits::Float>::sin_cos(bank);
+        let sin_gamma = <T as num_traits::Float>::sin(condition.flight_path_angle);
+
+        let a = cos_alpha * cos_beta;
+        let b = sin_bank * sin_beta + cos_bank * sin_alpha * cos_beta;
+        let root = <T as num_traits::Float>::sqrt(<T as num_traits::Float>::max(a * a - sin_gamma * sin_gamma + b * b,T::zero()));
+        let pitch = <T as num_traits::Float>::atan2(a * b + sin_gamma * root,a * a - sin_gamma * sin_gamma);
+        let (sin_pitch, cos_pitch) = <T as num_traits::Float>::sin_cos(pitch);
+        let attitude = UnitQuaternion::from_euler_angles(bank,pitch,heading);
+
+        let air_velocity = Vector3::new(cos_alpha * cos_beta,sin_beta,sin_alpha * cos_beta) * condition.airspeed;
+        let wind = attitude.inverse_transform_vector(&vehicle.body.get_wind());
+        let velocity = air_velocity + wind;
+
+        let turn_rate = condition.turn_rate;
+        let rates = Vector3::new(-turn_rate * sin_pitch,turn_rate * sin_bank * cos_pitch,turn_rate * cos_bank * cos_pitch);
+
+        let position = vehicle.position();
+        StateVector::from_vec(vec![
+            position[0], position[1], position[2],
+            velocity[0], velocity[1], velocity[2],
+            attitude[0], attitude[1], attitude[2], attitude[3],
+            rates[0],    rates[1],    rates[2]
+            ])
+    }
+
+    /// Return the body-frame linear and angular accelerations for the trim variables `x`
+    fn residuals<W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &mut AffectedBody<I,T,W,D>, condition: &TrimCondition<T>, heading: T, x: &DVector<T>) -> DVector<T> {
+        let state = self.state(vehicle,condition,heading,x);
+        vehicle.set_state(state);
+        let derivative = vehicle.derivative(&self.inputs(x));
+        DVector::from_iterator(6,derivative.iter().skip(3).take(3).chain(derivative.iter().skip(10)).copied())
+    }
+
+    /// Return the central-difference Jacobian of the residuals with respect to the trim variables
+    fn jacobian<W: WindModel<T>, D: DensityModel<T>>(&self, vehicle: &mut AffectedBody<I,T,W,D>, condition: &TrimCondition<T>, heading: T, x: &DVector<T>) -> DMatrix<T> {
+        let relative_step = <T as num_traits::Float>::cbrt(T::epsilon());
+        let mut jacobian = DMatrix::zeros(6,x.len());
+        for idx in 0..x.len() {
+            let h = relative_step * <T as num_traits::Float>::max(T::one(),<T as num_traits::Float>::abs(x[idx]));
+            let mut upper = x.clone();
+            upper[idx] += h;
+            let mut lower = x.clone();
+            lower[idx] -= h;
+            let difference = (self.residuals(vehicle,condition,heading,&upper) - self.residuals(vehicle,condition,heading,&lower)) / (h + h);
+            jacobian.set_column(idx,&difference);
+        }
+        jacobian
+    }
+}
+
+/// Test aircraft shared by the analysis modules
+///
+/// A small UAV with a [LinearAero](crate::effector_models::LinearAero) model reading
+/// `[aileron, elevator, rudder]` from slots 0 to 2, and a throttle on slot 3 giving up to 20 N of thrust.
+#[cfg(test)]
+pub(crate) mod test_aircraft {
+    use crate::{AeroEffect,AirState,Body,AeroBody,AffectedBody};
+    use crate::types::{Vector3,Matrix3,Force,Torque};
+    use crate::effector_models::{LinearAero,LinearDerivatives};
+
+    /// Throttle-controlled thrust along body X
+    pub(crate) struct Thrust;
+
+    impl AeroEffect<Vec<f64>> for Thrust {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3, inputstate: &Vec<f64>) -> (Force,Torque) {
+            (Force::body(20.0 * inputstate[3],0.0,0.0),Torque::body(0.0,0.0,0.0))
+        }
+    }
+
+    /// Return the stability-derivative model of the test aircraft
+    pub(crate) fn aero() -> LinearAero {
+        LinearAero::new(LinearDerivatives {
+            s: 0.5, b: 2.0, c: 0.25,
+            c_lift_0: 0.2, c_lift_alpha: 5.0, c_lift_elevator: 0.4,
+            c_drag_0: 0.03, k: 0.05,
+            c_side_beta: -0.3,
+            c_roll_beta: -0.05, c_roll_p: -0.45, c_roll_r: 0.1, c_roll_aileron: 0.15,
+            c_pitch_0: 0.02, c_pitch_alpha: -0.5, c_pitch_q: -10.0, c_pitch_elevator: -1.2,
+            c_yaw_beta: 0.08, c_yaw_p: -0.03, c_yaw_r: -0.1, c_yaw_rudder: -0.06,
+            ..Default::default()
+        })
+    }
+
+    /// Return the test aircraft at `altitude` (m)
+    pub(crate) fn vehicle(altitude: f64) -> AffectedBody {
+        let inertia = Matrix3::new(0.5,0.0,0.0, 0.0,0.8,0.0, 0.0,0.0,1.2);
+        let body = Body::new(3.0,inertia,Vector3::new(0.0,0.0,-altitude),Vector3::new(20.0,0.0,0.0),crate::types::UnitQuaternion::identity(),Vector3::zeros());
+        AffectedBody::new(AeroBody::new(body),vec![Box::new(aero()),Box::new(Thrust)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::Frame;
+    use approx::assert_relative_eq;
+
+    fn trim() -> Trim<Vec<f64>> {
+        Trim::new(vec![0.0,0.0,0.0,0.5],&[0,1,2,3])
+    }
+
+    #[test]
+    fn test_level_trim() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let result = trim().solve(&mut vehicle,&TrimCondition::level(20.0)).unwrap();
+        assert!(result.residual < 1e-6);
+        assert_relative_eq!(result.pitch,result.alpha,epsilon=1e-9);
+        assert_relative_eq!(result.bank,0.0,epsilon=1e-6);
+        assert!(result.inputs[3] > 0.0 && result.inputs[3] < 1.0);
+
+        // Trimmed flight holds altitude and airspeed
+        let inputs = result.inputs.clone();
+        vehicle.run(5.0,0.01,|_,_,_| inputs.clone());
+        assert_relative_eq!(vehicle.position().z,-100.0,epsilon=1e-3);
+        assert_relative_eq!(vehicle.get_airstate().airspeed,20.0,max_relative=1e-5);
+    }
+
+    #[test]
+    fn test_climbing_turn() {
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let condition = TrimCondition::level(20.0).with_flight_path_angle(0.05).with_turn_rate(0.2);
+        let result = trim().solve(&mut vehicle,&condition).unwrap();
+        assert!(result.bank > 0.0);
+        assert_relative_eq!(vehicle.get_airstate().beta,0.0,epsilon=1e-9);
+
+        // Climb rate is V sin γ and the heading rate matches the turn rate
+        let world_velocity = vehicle.velocity_in_frame(Frame::World);
+        assert_relative_eq!(-world_velocity.z,20.0 * 0.05f64.sin(),max_relative=1e-6);
+        let world_rates = crate::Body::get_dcm_body(&vehicle.statevector()) * vehicle.rates();
+        assert_relative_eq!(world_rates.z,0.2,max_relative=1e-6);
+    }
+
+    #[test]
+    fn test_trim_failure() {
+        // Without thrust the drag cannot be balanced in level flight
+        let mut vehicle = test_aircraft::vehicle(100.0);
+        let initial = vehicle.statevector();
+        let result = Trim::new(vec![0.0,0.0,0.0,0.0],&[1]).with_max_iterations(20)
+            .solve(&mut vehicle,&TrimCondition::level(20.0));
+        assert!(matches!(result,Err(Error::TrimNotConverged { residual, iterations }) if residual > 1e-6 && iterations <= 20));
+        assert_eq!(vehicle.statevector(),initial);
+    }
+}
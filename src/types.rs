@@ -21,6 +21,9 @@ pub type Matrix3<T = DefaultFloatRepr> = na::Matrix3<T>;
 /// Alias for unit quaternion representation
 pub type UnitQuaternion<T = DefaultFloatRepr> = na::UnitQuaternion<T>;
 
+/// Alias for isometry (rotation and translation) representation
+pub type Isometry3<T = DefaultFloatRepr> = na::Isometry3<T>;
+
 /// Alias for 13-dimensional state vector representation
 pub type StateVector<T = DefaultFloatRepr> = na::SVector<T,13>;
 
@@ -29,6 +32,14 @@ pub trait Float: num_traits::Float + num_traits::FromPrimitive + na::RealField {
 impl Float for f32 {}
 impl Float for f64 {}
 
+/// Return the constant `value` in the scalar type `T`
+///
+/// [Float] includes nalgebra's `RealField`, a superset of `f64`, so the conversion cannot fail; this
+/// replaces `T::from(value).unwrap()` in library code.
+pub(crate) fn constant<T: Float>(value: f64) -> T {
+    na::convert(value)
+}
+
 /// Represent reference frame of quantities
 #[derive(Copy,Clone)]
 pub enum Frame {
@@ -145,6 +156,20 @@ pub trait StateView<T: Float = DefaultFloatRepr> {
     /// Return the entire statevector
     fn statevector(&self) -> StateVector<T>;
     
+    /// Return the pose, the body-to-world isometry of the world-frame position and the attitude
+    fn pose(&self) -> Isometry3<T> {
+        Isometry3::from_parts(self.position().into(),self.attitude())
+    }
+    
+}
+
+/// Return `state` with the position and attitude of `pose`
+///
+/// The body-frame velocity and axis rates are kept.
+pub(crate) fn with_pose<T: Float>(mut state: StateVector<T>, pose: &Isometry3<T>) -> StateVector<T> {
+    state.fixed_rows_mut::<3>(0).copy_from(&pose.translation.vector);
+    state.fixed_rows_mut::<4>(6).copy_from(&pose.rotation.coords);
+    state
 }
 
 impl<T: Float> StateView<T> for StateVector<T> {
@@ -155,7 +180,7 @@ impl<T: Float> StateView<T> for StateVector<T> {
     fn velocity_in_frame(&self, frame: Frame) -> Vector3<T> {
         match frame {
             Frame::Body => self.fixed_rows::<3>(3).into(),
-            _ => unimplemented!(),
+            Frame::World => crate::frames::transform(self,T::zero(),T::zero(),self.fixed_rows::<3>(3).into(),crate::frames::Axes::Body,crate::frames::Axes::World),
         }
     }
     
@@ -167,7 +192,7 @@ impl<T: Float> StateView<T> for StateVector<T> {
     fn rates_in_frame(&self, frame: Frame) -> Vector3<T> {
         match frame {
             Frame::Body => self.fixed_rows::<3>(10).into(),
-            _ => unimplemented!(),
+            Frame::World => crate::frames::transform(self,T::zero(),T::zero(),self.fixed_rows::<3>(10).into(),crate::frames::Axes::Body,crate::frames::Axes::World),
         }
     }
     
@@ -0,0 +1,230 @@
+//! Unit-of-measure safety layer
+//!
+//! With the `uom` feature, constructors and getters typed with [uom] quantities wrap the raw-float API,
+//! so unit mix-ups are caught at compile time. For example [PowerWind] and [LogWind] take their bearing
+//! in degrees, while [PowerWind::new_with_units] takes an [Angle] and converts it.
+//!
+//! The layer is provided for double precision.
+//!
+//! ```
+//! use aerso::Body;
+//! use aerso::types::UnitQuaternion;
+//! use aerso::units::UnitStateView;
+//! use aerso::wind_models::PowerWind;
+//! use uom::si::f64::{Mass,MomentOfInertia,Length,Velocity,Angle,AngularVelocity};
+//! use uom::si::{mass::kilogram,moment_of_inertia::kilogram_square_meter,length::meter,velocity::{meter_per_second,knot},angle::degree,angular_velocity::radian_per_second};
+//!
+//! let zero = MomentOfInertia::new::<kilogram_square_meter>(0.0);
+//! let inertia = |i| MomentOfInertia::new::<kilogram_square_meter>(i);
+//! let body = Body::new_with_units(
+//!     Mass::new::<kilogram>(2.0),
+//!     [[inertia(0.1),zero,zero],[zero,inertia(0.2),zero],[zero,zero,inertia(0.3)]],
+//!     [Length::new::<meter>(0.0),Length::new::<meter>(0.0),Length::new::<meter>(-100.0)],
+//!     [Velocity::new::<knot>(40.0),Velocity::new::<meter_per_second>(0.0),Velocity::new::<meter_per_second>(0.0)],
+//!     UnitQuaternion::identity(),
+//!     [AngularVelocity::new::<radian_per_second>(0.0); 3],
+//! );
+//! let wind = PowerWind::new_with_units(Velocity::new::<knot>(10.0),Length::new::<meter>(10.0),Angle::new::<degree>(270.0));
+//! let speed = body.quantities().velocity[0];
+//! ```
+
+use uom::si::f64::{Angle,AngularVelocity,Length,Mass,MassDensity,MomentOfInertia,Pressure,Ratio,Velocity};
+use uom::si::{angle,angular_velocity,length,mass,mass_density,moment_of_inertia,pressure,ratio,velocity};
+
+use crate::{Body,BodyError,AirState};
+use crate::types::{Vector3,Matrix3,UnitQuaternion,StateView};
+use crate::wind_models::{ConstantWind,LogWind,PowerWind};
+
+/// Return the raw vector of the `value`s of `quantities`
+fn vector<Q: Copy>(quantities: &[Q; 3], value: impl Fn(Q) -> f64) -> Vector3<f64> {
+    Vector3::new(value(quantities[0]),value(quantities[1]),value(quantities[2]))
+}
+
+/// Return the raw inertia matrix (kg·m<sup>2</sup>) of the `rows` of a unit-typed inertia
+fn inertia_matrix(rows: &[[MomentOfInertia; 3]; 3]) -> Matrix3<f64> {
+    Matrix3::from_fn(|row, column| rows[row][column].get::<moment_of_inertia::kilogram_square_meter>())
+}
+
+impl Body<f64> {
+    /// Create a new instance of Body with unit-typed `mass` and `inertia` (rows) in the specified state
+    ///
+    /// See [Body::new]; the velocity is in the body frame and the rates are body axis rates.
+    pub fn new_with_units(mass: Mass, inertia: [[MomentOfInertia; 3]; 3], position: [Length; 3], velocity: [Velocity; 3], attitude: UnitQuaternion<f64>, rates: [AngularVelocity; 3]) -> Self {
+        Body::new(
+            mass.get::<mass::kilogram>(),
+            inertia_matrix(&inertia),
+            vector(&position,|value: Length| value.get::<length::meter>()),
+            vector(&velocity,|value: Velocity| value.get::<velocity::meter_per_second>()),
+            attitude,
+            vector(&rates,|value: AngularVelocity| value.get::<angular_velocity::radian_per_second>()),
+        )
+    }
+
+    /// Create a new instance of Body with unit-typed arguments, validating the configuration
+    ///
+    /// See [Body::try_new].
+    pub fn try_new_with_units(mass: Mass, inertia: [[MomentOfInertia; 3]; 3], position: [Length; 3], velocity: [Velocity; 3], attitude: UnitQuaternion<f64>, rates: [AngularVelocity; 3]) -> Result<Self,BodyError> {
+        Body::try_new(
+            mass.get::<mass::kilogram>(),
+            inertia_matrix(&inertia),
+            vector(&position,|value: Length| value.get::<length::meter>()),
+            vector(&velocity,|value: Velocity| value.get::<velocity::meter_per_second>()),
+            attitude,
+            vector(&rates,|value: AngularVelocity| value.get::<angular_velocity::radian_per_second>()),
+        )
+    }
+
+    /// Return the mass of the body
+    pub fn mass_quantity(&self) -> Mass {
+        Mass::new::<mass::kilogram>(self.mass())
+    }
+}
+
+/// Unit-typed state of a body
+#[derive(Copy,Clone,Debug)]
+pub struct StateQuantities {
+    /// World-frame position
+    pub position: [Length; 3],
+    /// Body-frame velocity
+    pub velocity: [Velocity; 3],
+    /// Attitude
+    pub attitude: UnitQuaternion<f64>,
+    /// Body axis rates
+    pub rates: [AngularVelocity; 3],
+}
+
+/// Unit-typed getters for any [StateView]
+pub trait UnitStateView {
+    /// Return the unit-typed state
+    fn quantities(&self) -> StateQuantities;
+}
+
+impl<S: StateView<f64> + ?Sized> UnitStateView for S {
+    fn quantities(&self) -> StateQuantities {
+        let position = self.position();
+        let velocity = self.velocity();
+        let rates = self.rates();
+        StateQuantities {
+            position: [0,1,2].map(|axis| Length::new::<length::meter>(position[axis])),
+            velocity: [0,1,2].map(|axis| Velocity::new::<velocity::meter_per_second>(velocity[axis])),
+            attitude: self.attitude(),
+            rates: [0,1,2].map(|axis| AngularVelocity::new::<angular_velocity::radian_per_second>(rates[axis])),
+        }
+    }
+}
+
+/// Unit-typed [AirState]
+#[derive(Copy,Clone,Debug)]
+pub struct AirStateQuantities {
+    /// Angle of attack
+    pub alpha: Angle,
+    /// Angle of sideslip
+    pub beta: Angle,
+    /// Airspeed
+    pub airspeed: Velocity,
+    /// Dynamic pressure
+    pub q: Pressure,
+    /// Mach number
+    pub mach: Ratio,
+    /// Air density
+    pub density: MassDensity,
+}
+
+impl AirState<f64> {
+    /// Return the unit-typed airstate
+    pub fn quantities(&self) -> AirStateQuantities {
+        AirStateQuantities {
+            alpha: Angle::new::<angle::radian>(self.alpha),
+            beta: Angle::new::<angle::radian>(self.beta),
+            airspeed: Velocity::new::<velocity::meter_per_second>(self.airspeed),
+            q: Pressure::new::<pressure::pascal>(self.q),
+            mach: Ratio::new::<ratio::ratio>(self.mach),
+            density: MassDensity::new::<mass_density::kilogram_per_cubic_meter>(self.density),
+        }
+    }
+}
+
+impl ConstantWind<f64> {
+    /// Create a new [ConstantWind] with the unit-typed world-frame `wind`
+    pub fn new_with_units(wind: [Velocity; 3]) -> Self {
+        ConstantWind::new(vector(&wind,|value: Velocity| value.get::<velocity::meter_per_second>()))
+    }
+}
+
+impl LogWind<f64> {
+    /// Create a new [LogWind] with unit-typed parameters
+    ///
+    /// # Arguments
+    ///
+    /// * `d` - Zero plane displacement
+    /// * `z0` - Surface roughness
+    /// * `u_star` - Friction velocity
+    /// * `bearing` - The bearing for the calculated wind vector
+    pub fn new_with_units(d: Length, z0: Length, u_star: Velocity, bearing: Angle) -> Self {
+        LogWind::new(d.get::<length::meter>(),z0.get::<length::meter>(),u_star.get::<velocity::meter_per_second>(),bearing.get::<angle::degree>())
+    }
+}
+
+impl PowerWind<f64> {
+    /// Create a new [PowerWind] with unit-typed parameters and typical alpha
+    ///
+    /// # Arguments
+    ///
+    /// * `u_r` - Reference wind speed
+    /// * `z_r` - Reference wind height
+    /// * `bearing` - The bearing for the calculated wind vector
+    pub fn new_with_units(u_r: Velocity, z_r: Length, bearing: Angle) -> Self {
+        PowerWind::new(u_r.get::<velocity::meter_per_second>(),z_r.get::<length::meter>(),bearing.get::<angle::degree>())
+    }
+
+    /// Create a new [PowerWind] with unit-typed parameters and power law exponent `alpha`
+    pub fn new_with_alpha_units(u_r: Velocity, z_r: Length, bearing: Angle, alpha: Ratio) -> Self {
+        PowerWind::new_with_alpha(u_r.get::<velocity::meter_per_second>(),z_r.get::<length::meter>(),bearing.get::<angle::degree>(),alpha.get::<ratio::ratio>())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aero::test_airstate::airstate;
+    use crate::WindModel;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_body() {
+        let inertia = |value| MomentOfInertia::new::<moment_of_inertia::kilogram_square_meter>(value);
+        let zero = inertia(0.0);
+        let rows = [[inertia(1.0),zero,zero],[zero,inertia(2.0),zero],[zero,zero,inertia(3.0)]];
+        let position = [Length::new::<length::kilometer>(1.0),Length::new::<length::foot>(10.0),Length::new::<length::meter>(0.0)];
+        let velocity = [Velocity::new::<velocity::knot>(10.0),Velocity::new::<velocity::meter_per_second>(0.0),Velocity::new::<velocity::meter_per_second>(0.0)];
+        let rates = [AngularVelocity::new::<angular_velocity::degree_per_second>(90.0),AngularVelocity::new::<angular_velocity::radian_per_second>(0.0),AngularVelocity::new::<angular_velocity::radian_per_second>(0.0)];
+        let body = Body::new_with_units(Mass::new::<mass::pound>(10.0),rows,position,velocity,UnitQuaternion::identity(),rates);
+        assert_relative_eq!(body.mass(),4.5359237,max_relative=1e-6);
+        assert_relative_eq!(body.position()[0],1000.0,max_relative=1e-12);
+        assert_relative_eq!(body.position()[1],3.048,max_relative=1e-12);
+        assert_relative_eq!(body.velocity()[0],10.0 * 1852.0 / 3600.0,max_relative=1e-12);
+        assert_relative_eq!(body.rates()[0],std::f64::consts::FRAC_PI_2,max_relative=1e-12);
+
+        let quantities = body.quantities();
+        assert_relative_eq!(quantities.velocity[0].get::<velocity::knot>(),10.0,max_relative=1e-12);
+        assert_relative_eq!(body.mass_quantity().get::<mass::pound>(),10.0,max_relative=1e-12);
+        assert!(Body::try_new_with_units(Mass::new::<mass::kilogram>(0.0),rows,position,velocity,UnitQuaternion::identity(),rates).is_err());
+    }
+
+    #[test]
+    fn test_wind_and_airstate() {
+        // A bearing in radians is converted to the degrees the raw constructor expects
+        let typed = PowerWind::new_with_units(Velocity::new::<velocity::meter_per_second>(10.0),Length::new::<length::meter>(10.0),Angle::new::<angle::radian>(std::f64::consts::FRAC_PI_2));
+        let raw = PowerWind::new(10.0,10.0,90.0);
+        let position = Vector3::new(0.0,0.0,20.0);
+        assert_eq!(typed.get_wind(&position),raw.get_wind(&position));
+        let log = LogWind::new_with_units(Length::new::<length::meter>(0.0),Length::new::<length::meter>(0.1),Velocity::new::<velocity::meter_per_second>(1.0),Angle::new::<angle::degree>(45.0));
+        assert_eq!(log.get_wind(&position),LogWind::new(0.0,0.1,1.0,45.0).get_wind(&position));
+
+        let airstate = airstate(0.1,50.0);
+        let quantities = airstate.quantities();
+        assert_relative_eq!(quantities.alpha.get::<angle::degree>(),0.1_f64.to_degrees(),max_relative=1e-12);
+        assert_relative_eq!(quantities.q.get::<pressure::kilopascal>(),1.53125,max_relative=1e-12);
+        assert_eq!(quantities.density.get::<mass_density::kilogram_per_cubic_meter>(),1.225);
+    }
+}
@@ -0,0 +1,184 @@
+//! WebAssembly bindings
+//!
+//! Enabled by the `wasm` feature, this module exports a `Vehicle` class to JavaScript with
+//! `wasm-bindgen`, for interactive flight-dynamics demos and teaching tools in the browser. The vehicle
+//! flies through the ISA standard atmosphere with a constant wind, and statevectors and airstates are
+//! exchanged as `Float64Array`s with the usual layouts:
+//!
+//! * statevector, 13 elements: `x,y,z,u,v,w,qx,qy,qz,qw,p,q,r`
+//! * airstate, 6 elements: `alpha,beta,airspeed,q,mach,density`
+//!
+//! Effectors are JavaScript functions `getEffect(airstate, rates, inputs)` returning the body-frame
+//! force and torque as 6 numbers, `[X,Y,Z,L,M,N]`.
+//!
+//! The module is built for `wasm32-unknown-unknown` and bound with `wasm-bindgen`, for example with
+//! `cargo rustc --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib` then
+//! `wasm-bindgen --target web --out-dir web/pkg target/wasm32-unknown-unknown/release/aerso.wasm`,
+//! and `web/index.html` is a small example page.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Float64Array,Function};
+use wasm_bindgen::prelude::*;
+
+use crate::{Body,AeroBody,AffectedBody,AeroEffect,AirState};
+use crate::aero::StandardAtmosphere;
+use crate::types::{Vector3,Matrix3,StateVector,StateView,Force,Torque};
+use crate::wind_models::ConstantWind;
+
+/// Error raised by an effector during a step, shared with the owning vehicle
+type EffectorError = Rc<RefCell<Option<JsValue>>>;
+
+/// Return the elements of `airstate` in binding order
+fn airstate_array(airstate: &AirState<f64>) -> [f64;6] {
+    [airstate.alpha,airstate.beta,airstate.airspeed,airstate.q,airstate.mach,airstate.density]
+}
+
+/// Return `values` as a 3-vector
+fn vector3(values: &[f64]) -> Result<Vector3<f64>,JsError> {
+    match values.len() {
+        3 => Ok(Vector3::from_column_slice(values)),
+        len => Err(JsError::new(&format!("Expected 3 elements, found {}",len))),
+    }
+}
+
+/// Return `values` as a statevector
+fn statevector(values: &[f64]) -> Result<StateVector<f64>,JsError> {
+    match values.len() {
+        13 => Ok(StateVector::from_column_slice(values)),
+        len => Err(JsError::new(&format!("Expected 13 statevector elements, found {}",len))),
+    }
+}
+
+/// [AeroEffect] calling a JavaScript function
+struct JsEffector {
+    /// `getEffect(airstate, rates, inputs)` function
+    get_effect: Function,
+    /// Shared error slot of the owning vehicle
+    error: EffectorError,
+}
+
+impl JsEffector {
+    /// Call the effect function, returning the 6 force and torque components
+    fn call(&self, airstate: AirState<f64>, rates: Vector3<f64>, inputstate: &[f64]) -> Result<Vec<f64>,JsValue> {
+        let airstate = Float64Array::from(&airstate_array(&airstate)[..]);
+        let rates = Float64Array::from(rates.as_slice());
+        let inputs = Float64Array::from(inputstate);
+        let effect = Float64Array::new(&self.get_effect.call3(&JsValue::NULL,&airstate,&rates,&inputs)?).to_vec();
+        match effect.len() {
+            6 => Ok(effect),
+            len => Err(JsError::new(&format!("Effectors must return 6 force and torque components, found {}",len)).into()),
+        }
+    }
+}
+
+impl AeroEffect<Vec<f64>,f64> for JsEffector {
+    fn get_effect(&self, airstate: AirState<f64>, rates: Vector3<f64>, inputstate: &Vec<f64>) -> (Force<f64>,Torque<f64>) {
+        match self.call(airstate,rates,inputstate) {
+            Ok(effect) => (Force::body(effect[0],effect[1],effect[2]),Torque::body(effect[3],effect[4],effect[5])),
+            Err(error) => {
+                self.error.borrow_mut().get_or_insert(error);
+                (Force::body_vec(Vector3::zeros()),Torque::body_vec(Vector3::zeros()))
+            },
+        }
+    }
+}
+
+/// JavaScript `Vehicle`
+#[wasm_bindgen]
+pub struct Vehicle {
+    /// Wrapped vehicle
+    vehicle: AffectedBody<Vec<f64>,f64,ConstantWind<f64>,StandardAtmosphere<f64>>,
+    /// First error raised by an effector since the last check
+    error: EffectorError,
+}
+
+impl Vehicle {
+    /// Raise any error stored by an effector
+    fn check(&self) -> Result<(),JsValue> {
+        match self.error.borrow_mut().take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Vehicle {
+    /// Create a vehicle of `mass` (kg) with a row-major `inertia` (kg·m²) in `state`, with no effectors
+    #[wasm_bindgen(constructor)]
+    pub fn new(mass: f64, inertia: &[f64], state: &[f64]) -> Result<Vehicle,JsError> {
+        if inertia.len() != 9 {
+            return Err(JsError::new(&format!("Expected 9 inertia elements, found {}",inertia.len())));
+        }
+        let inertia = Matrix3::from_row_slice(inertia);
+        if mass <= 0.0 || inertia.try_inverse().is_none() {
+            return Err(JsError::new("Mass must be positive and inertia invertible"));
+        }
+        let body = Body::new_from_statevector(mass,inertia,statevector(state)?);
+        Ok(Vehicle {
+            vehicle: AffectedBody::new(AeroBody::with_density_model(body,ConstantWind::new(Vector3::zeros()),StandardAtmosphere::new()),Vec::new()),
+            error: Rc::new(RefCell::new(None)),
+        })
+    }
+
+    /// Set a constant North-East-Down wind (m·s<sup>-1</sup>)
+    #[wasm_bindgen(js_name = setWind)]
+    pub fn set_wind(&mut self, wind: &[f64]) -> Result<(),JsError> {
+        let kinematics = self.vehicle.body.body;
+        self.vehicle.body = AeroBody::with_density_model(kinematics,ConstantWind::new(vector3(wind)?),StandardAtmosphere::new());
+        Ok(())
+    }
+
+    /// Add an effector called `name`, replacing any existing effector of that name
+    #[wasm_bindgen(js_name = addEffector)]
+    pub fn add_effector(&mut self, name: &str, get_effect: Function) {
+        self.vehicle.add_effector(name,Box::new(JsEffector { get_effect, error: self.error.clone() }));
+    }
+
+    /// Enable the effector called `name`, returning `false` if it does not exist
+    pub fn enable(&mut self, name: &str) -> bool {
+        self.vehicle.enable(name)
+    }
+
+    /// Disable the effector called `name`, returning `false` if it does not exist
+    pub fn disable(&mut self, name: &str) -> bool {
+        self.vehicle.disable(name)
+    }
+
+    /// Propagate the vehicle by `delta_t` (s) with `inputs`, raising any error from an effector
+    pub fn step(&mut self, delta_t: f64, inputs: &[f64]) -> Result<(),JsValue> {
+        self.vehicle.step(delta_t,&inputs.to_vec());
+        self.check()
+    }
+
+    /// Return the statevector
+    pub fn statevector(&self) -> Vec<f64> {
+        self.vehicle.statevector().as_slice().to_vec()
+    }
+
+    /// Set the statevector
+    #[wasm_bindgen(js_name = setState)]
+    pub fn set_state(&mut self, state: &[f64]) -> Result<(),JsError> {
+        self.vehicle.set_state(statevector(state)?);
+        Ok(())
+    }
+
+    /// Return the airstate
+    pub fn airstate(&self) -> Vec<f64> {
+        airstate_array(&self.vehicle.get_airstate()).to_vec()
+    }
+
+    /// Return the roll, pitch and yaw angles (rad)
+    #[wasm_bindgen(js_name = eulerAngles)]
+    pub fn euler_angles(&self) -> Vec<f64> {
+        let (roll, pitch, yaw) = self.vehicle.attitude().euler_angles();
+        vec![roll,pitch,yaw]
+    }
+
+    /// Return the body-frame acceleration (m·s<sup>-2</sup>) from the last step
+    pub fn acceleration(&self) -> Vec<f64> {
+        self.vehicle.acceleration().as_slice().to_vec()
+    }
+}
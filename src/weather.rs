@@ -0,0 +1,300 @@
+//! Scripted weather timelines
+//!
+//! A [WeatherScript] is a timeline of [WeatherPhase] keyframes giving the mean wind, turbulence intensity
+//! and ISA temperature offset, linearly interpolated between keyframes and held beyond the first and last.
+//! A single run can then include, for example, a calm departure, en-route turbulence and a gusty arrival.
+//!
+//! [WeatherScript::models] returns a [ScriptedWind] and [ScriptedAtmosphere] for an [AeroBody](crate::AeroBody).
+//! The two share a clock which is advanced by [WindModel::step], so the atmosphere follows the script as
+//! the vehicle is stepped.
+//!
+//! ```
+//! use aerso::weather::{WeatherScript,WeatherPhase};
+//! use aerso::wind_models::DrydenConfig;
+//! use aerso::types::Vector3;
+//!
+//! let script = WeatherScript::new(DrydenConfig::high_altitude(50.0,1.0))
+//!     .with_phase(WeatherPhase::new(0.0,Vector3::zeros(),0.0,0.0))
+//!     .with_phase(WeatherPhase::new(600.0,Vector3::new(-10.0,0.0,0.0),2.0,5.0));
+//! let (wind, atmosphere) = script.models(1);
+//! ```
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::{WindModel,DensityModel,Error};
+use crate::snapshot::{WindState,export};
+use crate::aero::StandardAtmosphere;
+use crate::types::{Vector3,Float,constant};
+use crate::wind_models::{DrydenConfig,DrydenTurbulence};
+
+/// Weather conditions at a point in a [WeatherScript]
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct WeatherPhase<T: Float> {
+    /// Time of the keyframe (s)
+    pub time: T,
+    /// Mean world-frame wind (m·s<sup>-1</sup>)
+    pub wind: Vector3<T>,
+    /// Multiplier on the turbulence standard deviations of the script
+    pub turbulence: T,
+    /// Temperature offset from ISA at every altitude (K)
+    pub temperature_offset: T,
+}
+
+impl<T: Float> WeatherPhase<T> {
+    /// Create a new [WeatherPhase] keyframe
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Time of the keyframe (s)
+    /// * `wind` - Mean world-frame wind (m·s<sup>-1</sup>)
+    /// * `turbulence` - Multiplier on the turbulence standard deviations
+    /// * `temperature_offset` - Temperature offset from ISA (K)
+    pub fn new(time: T, wind: Vector3<T>, turbulence: T, temperature_offset: T) -> Self {
+        WeatherPhase { time, wind, turbulence, temperature_offset }
+    }
+}
+
+/// Timeline of weather conditions
+#[derive(Clone,Debug)]
+pub struct WeatherScript<T: Float> {
+    /// Turbulence at unit intensity
+    turbulence: DrydenConfig<T>,
+    /// Keyframes in time order
+    phases: Vec<WeatherPhase<T>>,
+    /// ISA atmosphere the temperature offset is applied to
+    atmosphere: StandardAtmosphere<T>,
+}
+
+impl<T: Float> WeatherScript<T> {
+    /// Create an empty [WeatherScript] with `turbulence` at unit intensity
+    ///
+    /// Without phases the script is calm with standard temperature.
+    pub fn new(turbulence: DrydenConfig<T>) -> Self {
+        WeatherScript { turbulence, phases: Vec::new(), atmosphere: StandardAtmosphere::new() }
+    }
+
+    /// Add a keyframe to the end of the timeline
+    ///
+    /// # Panics
+    ///
+    /// Panics if `phase` is not later than the last keyframe.
+    pub fn with_phase(mut self, phase: WeatherPhase<T>) -> Self {
+        if self.phases.last().is_some_and(|last| phase.time <= last.time) {
+            panic!("Weather phases must be in strictly increasing time order");
+        }
+        self.phases.push(phase);
+        self
+    }
+
+    /// Set the ISA atmosphere the scripted temperature offset is added to
+    pub fn with_atmosphere(mut self, atmosphere: StandardAtmosphere<T>) -> Self {
+        self.atmosphere = atmosphere;
+        self
+    }
+
+    /// Create a [WeatherScript] from CSV `contents` with `turbulence` at unit intensity
+    ///
+    /// The header names the columns `time`, `wind_n`, `wind_e`, `wind_d`, `turbulence` and
+    /// `temperature_offset`, in any order, and each following row is a keyframe.
+    pub fn from_csv(turbulence: DrydenConfig<T>, contents: &str) -> Result<Self,Error> {
+        const COLUMNS: [&str; 6] = ["time","wind_n","wind_e","wind_d","turbulence","temperature_offset"];
+        let mut lines = contents.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+        let header: Vec<&str> = lines.next().ok_or_else(|| Error::Parse("Missing CSV header".to_string()))?.split(',').map(|field| field.trim()).collect();
+        let order = COLUMNS.iter()
+            .map(|column| header.iter().position(|field| field == column).ok_or_else(|| Error::Parse(format!("Missing CSV column '{}'",column))))
+            .collect::<Result<Vec<usize>,Error>>()?;
+        if let Some(field) = header.iter().find(|field| !COLUMNS.contains(field)) {
+            return Err(Error::Parse(format!("Unknown CSV column '{}'",field)));
+        }
+
+        let mut script = WeatherScript::new(turbulence);
+        for (row, line) in lines.enumerate() {
+            let values = line.split(',')
+                .map(|field| field.trim().parse::<f64>().map(constant::<T>).map_err(|_| Error::Parse(format!("Invalid number '{}' in CSV row {}",field.trim(),row + 1))))
+                .collect::<Result<Vec<T>,Error>>()?;
+            if values.len() != header.len() {
+                return Err(Error::Parse(format!("CSV row {} has {} columns, expected {}",row + 1,values.len(),header.len())));
+            }
+            let value = |column: usize| values[order[column]];
+            if script.phases.last().is_some_and(|last| value(0) <= last.time) {
+                return Err(Error::Parse("CSV times are not strictly increasing".to_string()));
+            }
+            script = script.with_phase(WeatherPhase::new(value(0),Vector3::new(value(1),value(2),value(3)),value(4),value(5)));
+        }
+        if script.phases.is_empty() {
+            return Err(Error::Parse("CSV contains no data".to_string()));
+        }
+        Ok(script)
+    }
+
+    /// Return the keyframes in time order
+    pub fn phases(&self) -> &[WeatherPhase<T>] {
+        &self.phases
+    }
+
+    /// Return the interpolated conditions at `time` (s)
+    pub fn conditions(&self, time: T) -> WeatherPhase<T> {
+        let calm = WeatherPhase::new(time,Vector3::zeros(),T::zero(),T::zero());
+        let next = self.phases.iter().position(|phase| phase.time > time);
+        let phase = match next {
+            None => *self.phases.last().unwrap_or(&calm),
+            Some(0) => self.phases[0],
+            Some(index) => {
+                let (before, after) = (self.phases[index - 1], self.phases[index]);
+                let fraction = (time - before.time) / (after.time - before.time);
+                let lerp = |a: T, b: T| a + (b - a) * fraction;
+                WeatherPhase {
+                    time,
+                    wind: before.wind + (after.wind - before.wind) * fraction,
+                    turbulence: lerp(before.turbulence,after.turbulence),
+                    temperature_offset: lerp(before.temperature_offset,after.temperature_offset),
+                }
+            },
+        };
+        WeatherPhase { time, ..phase }
+    }
+
+    /// Return the wind and density models following the script from time zero, with turbulence seeded by `seed`
+    pub fn models(&self, seed: u64) -> (ScriptedWind<T>,ScriptedAtmosphere<T>) {
+        let script = Rc::new(self.clone());
+        let clock = Rc::new(Cell::new(T::zero()));
+        let mut turbulence = DrydenTurbulence::new(self.turbulence,seed);
+        turbulence.set_scale(script.conditions(T::zero()).turbulence);
+        (
+            ScriptedWind { script: script.clone(), clock: clock.clone(), turbulence },
+            ScriptedAtmosphere { script, clock },
+        )
+    }
+}
+
+/// [WindModel] following the mean wind and turbulence intensity of a [WeatherScript]
+#[derive(Clone)]
+pub struct ScriptedWind<T: Float> {
+    /// Weather timeline
+    script: Rc<WeatherScript<T>>,
+    /// Script time shared with the [ScriptedAtmosphere] (s)
+    clock: Rc<Cell<T>>,
+    /// Turbulence at unit intensity
+    turbulence: DrydenTurbulence<T>,
+}
+
+impl<T: Float> ScriptedWind<T> {
+    /// Return the current script time (s)
+    pub fn time(&self) -> T {
+        self.clock.get()
+    }
+
+    /// Return the current scripted conditions
+    pub fn conditions(&self) -> WeatherPhase<T> {
+        self.script.conditions(self.clock.get())
+    }
+}
+
+impl<T: Float> WindModel<T> for ScriptedWind<T> {
+    fn get_wind(&self, position: &Vector3<T>) -> Vector3<T> {
+        self.conditions().wind + self.turbulence.get_wind(position)
+    }
+
+    fn step(&mut self, delta_t: T) {
+        self.clock.set(self.clock.get() + delta_t);
+        self.turbulence.set_scale(self.conditions().turbulence);
+        self.turbulence.step(delta_t);
+    }
+
+    /// The script time is shared, so restoring it also restores the time of the paired [ScriptedAtmosphere]
+    fn snapshot(&self) -> WindState {
+        WindState { values: vec![export(self.clock.get())], words: vec![], parts: vec![self.turbulence.snapshot()] }
+    }
+
+    fn restore(&mut self, state: &WindState) -> Result<(),Error> {
+        state.check(1,0,1)?;
+        self.turbulence.restore(&state.parts[0])?;
+        self.clock.set(state.value(0));
+        Ok(())
+    }
+}
+
+/// [DensityModel] applying the temperature offset of a [WeatherScript] to the ISA
+///
+/// The script time is advanced by stepping the paired [ScriptedWind].
+#[derive(Clone)]
+pub struct ScriptedAtmosphere<T: Float> {
+    /// Weather timeline
+    script: Rc<WeatherScript<T>>,
+    /// Script time shared with the [ScriptedWind] (s)
+    clock: Rc<Cell<T>>,
+}
+
+impl<T: Float> ScriptedAtmosphere<T> {
+    /// Return the ISA atmosphere with the current scripted temperature offset
+    fn atmosphere(&self) -> StandardAtmosphere<T> {
+        self.script.atmosphere.with_temperature_offset(self.script.conditions(self.clock.get()).temperature_offset)
+    }
+}
+
+impl<T: Float> DensityModel<T> for ScriptedAtmosphere<T> {
+    fn get_density(&self, position: &Vector3<T>) -> T {
+        self.atmosphere().get_density(position)
+    }
+
+    fn get_temperature(&self, position: &Vector3<T>) -> T {
+        self.atmosphere().get_temperature(position)
+    }
+
+    fn get_pressure(&self, position: &Vector3<T>) -> T {
+        self.atmosphere().get_pressure(position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn script() -> WeatherScript<f64> {
+        let csv = "
+            time, wind_n, wind_e, wind_d, turbulence, temperature_offset
+            0, 0, 0, 0, 0, 0
+            100, -10, 0, 0, 2, 10
+            200, -10, 5, 0, 0, 10
+        ";
+        WeatherScript::from_csv(DrydenConfig::high_altitude(50.0,1.0),csv).unwrap()
+    }
+
+    #[test]
+    fn test_script() {
+        let script = script();
+        assert_eq!(script.phases().len(),3);
+        let halfway = script.conditions(50.0);
+        assert_eq!(halfway.wind,Vector3::new(-5.0,0.0,0.0));
+        assert_relative_eq!(halfway.turbulence,1.0);
+        assert_relative_eq!(halfway.temperature_offset,5.0);
+        assert_eq!(script.conditions(-1.0).wind,Vector3::zeros());
+        assert_eq!(script.conditions(1000.0).wind,Vector3::new(-10.0,5.0,0.0));
+
+        let turbulence = DrydenConfig::high_altitude(50.0,1.0);
+        assert_eq!(WeatherScript::<f64>::from_csv(turbulence,"time, wind_n\n0, 1").unwrap_err(),Error::Parse("Missing CSV column 'wind_e'".to_string()));
+        assert_eq!(WeatherScript::<f64>::from_csv(turbulence,"time,wind_n,wind_e,wind_d,turbulence,temperature_offset\n1,0,0,0,0,0\n0,0,0,0,0,0").unwrap_err(),Error::Parse("CSV times are not strictly increasing".to_string()));
+    }
+
+    #[test]
+    fn test_models() {
+        let (mut wind, atmosphere) = script().models(7);
+        let position = Vector3::new(0.0,0.0,-1000.0);
+        // Calm departure has no turbulence
+        assert_eq!(wind.get_wind(&position),Vector3::zeros());
+        let standard = StandardAtmosphere::new();
+        assert_relative_eq!(atmosphere.get_temperature(&position),standard.get_temperature(&position));
+
+        for _ in 0..1000 {
+            wind.step(0.1);
+        }
+        assert_relative_eq!(wind.time(),100.0,max_relative=1e-9);
+        // En-route the mean wind is reached with turbulence about it, in a warmer atmosphere
+        let gust = wind.get_wind(&position) - Vector3::new(-10.0,0.0,0.0);
+        assert!(gust.norm() > 0.0 && gust.norm() < 10.0);
+        assert_relative_eq!(atmosphere.get_temperature(&position),standard.get_temperature(&position) + 10.0,max_relative=1e-9);
+        assert!(atmosphere.get_density(&position) < standard.get_density(&position));
+    }
+}
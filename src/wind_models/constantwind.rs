@@ -2,6 +2,7 @@ use crate::WindModel;
 use crate::types::{Vector3,Float};
 
 /// Built-in [WindModel] to represent a constant wind
+#[derive(Copy,Clone)]
 pub struct ConstantWind<T: Float> {
     wind: Vector3<T>,
 }
@@ -29,6 +30,7 @@ impl<T: Float> WindModel<T> for ConstantWind<T> {
 
 #[cfg(test)]
 mod test {
+    #[allow(unused_imports)]
     use super::*;
     #[test]
     fn test_constant() {
@@ -0,0 +1,189 @@
+use crate::{WindModel,Error};
+use crate::snapshot::{WindState,export};
+use crate::random::Rng;
+use crate::types::{Vector3,Float,constant};
+
+/// Feet per metre, for the imperial MIL-F-8785C scaling laws
+const FEET_PER_METRE: f64 = 1.0 / 0.3048;
+
+/// Intensities and length scales of [DrydenTurbulence]
+///
+/// The longitudinal, lateral and vertical components are applied along North, East and Down.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct DrydenConfig<T: Float> {
+    /// Airspeed at which the frozen turbulence field is flown through (m·s<sup>-1</sup>)
+    pub airspeed: T,
+    /// Standard deviations of the gust components (m·s<sup>-1</sup>)
+    pub sigma: Vector3<T>,
+    /// Length scales of the gust components (m)
+    pub length: Vector3<T>,
+}
+
+impl<T: Float> DrydenConfig<T> {
+    /// Create a new [DrydenConfig] with the specified parameters
+    ///
+    /// # Arguments
+    ///
+    /// * `airspeed` - Airspeed through the turbulence (m·s<sup>-1</sup>)
+    /// * `sigma` - Standard deviations of the gust components (m·s<sup>-1</sup>)
+    /// * `length` - Length scales of the gust components (m)
+    pub fn new(airspeed: T, sigma: Vector3<T>, length: Vector3<T>) -> Self {
+        DrydenConfig { airspeed, sigma, length }
+    }
+
+    /// Create the MIL-F-8785C low-altitude configuration, valid below about 300 m
+    ///
+    /// # Arguments
+    ///
+    /// * `airspeed` - Airspeed through the turbulence (m·s<sup>-1</sup>)
+    /// * `altitude` - Altitude above ground (m), limited to at least 3 m
+    /// * `wind_speed_20ft` - Mean wind speed at 6 m (20 ft) (m·s<sup>-1</sup>)
+    pub fn low_altitude(airspeed: T, altitude: T, wind_speed_20ft: T) -> Self {
+        let feet = constant::<T>(FEET_PER_METRE);
+        let h = <T as num_traits::Float>::max(altitude,constant::<T>(3.0)) * feet;
+        let factor = constant::<T>(0.177) + constant::<T>(0.000823) * h;
+        let length_horizontal = h / <T as num_traits::Float>::powf(factor,constant::<T>(1.2)) / feet;
+        let sigma_vertical = constant::<T>(0.1) * wind_speed_20ft;
+        let sigma_horizontal = sigma_vertical / <T as num_traits::Float>::powf(factor,constant::<T>(0.4));
+        DrydenConfig {
+            airspeed,
+            sigma: Vector3::new(sigma_horizontal,sigma_horizontal,sigma_vertical),
+            length: Vector3::new(length_horizontal,length_horizontal,h / feet),
+        }
+    }
+
+    /// Create the MIL-F-8785C medium and high-altitude configuration, with isotropic intensity `sigma`
+    /// (m·s<sup>-1</sup>) and 533.4 m (1750 ft) length scales, valid above about 600 m
+    pub fn high_altitude(airspeed: T, sigma: T) -> Self {
+        let length = constant::<T>(1750.0 / FEET_PER_METRE);
+        DrydenConfig {
+            airspeed,
+            sigma: Vector3::new(sigma,sigma,sigma),
+            length: Vector3::new(length,length,length),
+        }
+    }
+}
+
+/// Built-in [WindModel] for continuous [Dryden](https://en.wikipedia.org/wiki/Dryden_Wind_Turbulence_Model)
+/// turbulence
+///
+/// Each gust component is a first-order Gauss-Markov process with the Dryden correlation time `L/V`,
+/// driven by a seeded [Rng] so runs are repeatable. The gusts are uniform in space and are added to no
+/// mean wind; the output can be scaled in time with [DrydenTurbulence::set_scale].
+#[derive(Clone)]
+pub struct DrydenTurbulence<T: Float> {
+    /// Turbulence configuration
+    config: DrydenConfig<T>,
+    /// Random number generator
+    rng: Rng,
+    /// Unscaled gust velocity (m·s<sup>-1</sup>)
+    gust: Vector3<T>,
+    /// Multiplier on the gust velocity
+    scale: T,
+}
+
+impl<T: Float> DrydenTurbulence<T> {
+    /// Create a new [DrydenTurbulence] model from `config`, starting from a random gust drawn with `seed`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the airspeed or any length scale is not positive.
+    pub fn new(config: DrydenConfig<T>, seed: u64) -> Self {
+        if config.airspeed <= T::zero() || config.length.iter().any(|&length| length <= T::zero()) {
+            panic!("Dryden airspeed and length scales must be positive");
+        }
+        let mut rng = Rng::new(seed);
+        let gust = config.sigma.map(|sigma| rng.gaussian(T::zero(),sigma));
+        DrydenTurbulence { config, rng, gust, scale: T::one() }
+    }
+
+    /// Return the turbulence configuration
+    pub fn config(&self) -> &DrydenConfig<T> {
+        &self.config
+    }
+
+    /// Set the multiplier on the gust velocity, such as a time-varying turbulence severity
+    pub fn set_scale(&mut self, scale: T) {
+        self.scale = scale;
+    }
+}
+
+impl<T: Float> WindModel<T> for DrydenTurbulence<T> {
+    fn get_wind(&self, _position: &Vector3<T>) -> Vector3<T> {
+        self.gust * self.scale
+    }
+
+    fn step(&mut self, delta_t: T) {
+        for axis in 0..3 {
+            let a = <T as num_traits::Float>::exp(-self.config.airspeed * delta_t / self.config.length[axis]);
+            let noise = self.config.sigma[axis] * <T as num_traits::Float>::sqrt(T::one() - a * a);
+            self.gust[axis] = a * self.gust[axis] + self.rng.gaussian(T::zero(),noise);
+        }
+    }
+
+    fn snapshot(&self) -> WindState {
+        WindState {
+            values: vec![export(self.gust[0]),export(self.gust[1]),export(self.gust[2]),export(self.scale)],
+            words: self.rng.state_words(),
+            parts: vec![],
+        }
+    }
+
+    fn restore(&mut self, state: &WindState) -> Result<(),Error> {
+        state.check(4,Rng::STATE_WORDS,0)?;
+        self.rng = Rng::from_state_words(&state.words).ok_or(Error::SnapshotMismatch)?;
+        self.gust = Vector3::new(state.value(0),state.value(1),state.value(2));
+        self.scale = state.value(3);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_statistics() {
+        let config = DrydenConfig::new(50.0,Vector3::new(2.0,1.0,0.5),Vector3::new(500.0,200.0,50.0));
+        let mut turbulence = DrydenTurbulence::new(config,3);
+        let delta_t = 0.05;
+        let samples: Vec<Vector3<f64>> = (0..400_000).map(|_| {
+            turbulence.step(delta_t);
+            turbulence.get_wind(&Vector3::zeros())
+        }).collect();
+        let n = samples.len() as f64;
+        for axis in 0..3 {
+            let variance = samples.iter().map(|gust| gust[axis].powi(2)).sum::<f64>() / n;
+            assert_relative_eq!(variance.sqrt(),config.sigma[axis],max_relative=0.05);
+        }
+        // Lag-one correlation matches exp(-V dt / L)
+        let correlation = samples.windows(2).map(|pair| pair[0][2] * pair[1][2]).sum::<f64>() / (n - 1.0) / 0.25;
+        assert_relative_eq!(correlation,(-50.0 * delta_t / 50.0_f64).exp(),max_relative=0.02);
+
+        turbulence.set_scale(0.0);
+        assert_eq!(turbulence.get_wind(&Vector3::zeros()),Vector3::zeros());
+    }
+
+    #[test]
+    fn test_configurations() {
+        // At 20 ft in a 15 m/s wind, sigma_w = 1.5 m/s and the lengths are set by altitude
+        let low = DrydenConfig::low_altitude(30.0,6.096,15.0);
+        assert_relative_eq!(low.sigma[2],1.5,max_relative=1e-9);
+        assert_relative_eq!(low.length[2],6.096,max_relative=1e-9);
+        assert_relative_eq!(low.sigma[0],1.5 / (0.177 + 0.000823 * 20.0_f64).powf(0.4),max_relative=1e-6);
+        assert_relative_eq!(low.length[0],6.096 / (0.177 + 0.000823 * 20.0_f64).powf(1.2),max_relative=1e-6);
+
+        let high = DrydenConfig::high_altitude(100.0,3.0);
+        assert_relative_eq!(high.length[0],533.4,max_relative=1e-9);
+
+        // Equal seeds give equal turbulence
+        let run = |seed| {
+            let mut turbulence = DrydenTurbulence::new(high,seed);
+            (0..100).for_each(|_| turbulence.step(0.01));
+            turbulence.get_wind(&Vector3::zeros())
+        };
+        assert_eq!(run(1),run(1));
+        assert_ne!(run(1),run(2));
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use crate::WindModel;
+use crate::lookup::Table;
+use crate::types::{Vector3,Float};
+
+/// Wind vectors on a rectilinear North-East-Down grid, shared read-only between [GriddedWind] handles
+///
+/// The wind is interpolated linearly between grid points and held at the nearest edge outside the grid.
+pub struct WindField<T: Float> {
+    /// North component of the wind (m·s<sup>-1</sup>)
+    north: Table<T>,
+    /// East component of the wind (m·s<sup>-1</sup>)
+    east: Table<T>,
+    /// Down component of the wind (m·s<sup>-1</sup>)
+    down: Table<T>,
+}
+
+impl<T: Float> WindField<T> {
+    /// Create a new [WindField] with the world-frame `winds` at the grid points of the breakpoints
+    ///
+    /// # Arguments
+    ///
+    /// * `north` - Strictly increasing North breakpoints (m)
+    /// * `east` - Strictly increasing East breakpoints (m)
+    /// * `down` - Strictly increasing Down breakpoints (m)
+    /// * `winds` - Flattened wind vectors in row-major order, so the Down axis varies fastest
+    ///
+    /// # Panics
+    ///
+    /// Panics if the breakpoints are not strictly increasing or the number of winds does not match the grid.
+    pub fn new(north: Vec<T>, east: Vec<T>, down: Vec<T>, winds: &[Vector3<T>]) -> Self {
+        let component = |axis: usize| Table::new(vec![north.clone(),east.clone(),down.clone()],winds.iter().map(|wind| wind[axis]).collect());
+        WindField { north: component(0), east: component(1), down: component(2) }
+    }
+
+    /// Return the world-frame wind at `position`
+    pub fn wind(&self, position: &Vector3<T>) -> Vector3<T> {
+        let point = [position[0],position[1],position[2]];
+        Vector3::new(self.north.lookup(&point),self.east.lookup(&point),self.down.lookup(&point))
+    }
+}
+
+/// Built-in [WindModel] interpolating a shared gridded [WindField]
+///
+/// The field is held behind an [Arc], so many vehicles and parallel Monte Carlo workers can read one copy
+/// of a large field. Cloning, or [GriddedWind::clone_handle], only copies the handle.
+#[derive(Clone)]
+pub struct GriddedWind<T: Float> {
+    /// Shared wind field
+    field: Arc<WindField<T>>,
+}
+
+impl<T: Float> GriddedWind<T> {
+    /// Create a new [GriddedWind] owning `field`
+    pub fn new(field: WindField<T>) -> Self {
+        GriddedWind { field: Arc::new(field) }
+    }
+
+    /// Create a new [GriddedWind] sharing `field`
+    pub fn from_shared(field: Arc<WindField<T>>) -> Self {
+        GriddedWind { field }
+    }
+
+    /// Return a new handle to the same field, without copying the data
+    pub fn clone_handle(&self) -> Self {
+        GriddedWind { field: Arc::clone(&self.field) }
+    }
+
+    /// Return the shared field
+    pub fn field(&self) -> &Arc<WindField<T>> {
+        &self.field
+    }
+
+    /// Return the number of handles sharing the field
+    pub fn handles(&self) -> usize {
+        Arc::strong_count(&self.field)
+    }
+}
+
+impl<T: Float> WindModel<T> for GriddedWind<T> {
+    fn get_wind(&self, position: &Vector3<T>) -> Vector3<T> {
+        self.field.wind(position)
+    }
+
+    fn step(&mut self, _delta_t: T) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn field() -> WindField<f64> {
+        // Wind from the West strengthening with height, with a downdraft to the East
+        let winds: Vec<Vector3<f64>> = [0.0,100.0].iter().flat_map(|_north| {
+            [0.0,100.0].iter().flat_map(move |&east| {
+                [-100.0,0.0].iter().map(move |&down| Vector3::new(0.0,-down / 10.0,east / 50.0))
+            })
+        }).collect();
+        WindField::new(vec![0.0,100.0],vec![0.0,100.0],vec![-100.0,0.0],&winds)
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let wind = GriddedWind::new(field());
+        let middle = wind.get_wind(&Vector3::new(50.0,50.0,-50.0));
+        assert_relative_eq!(middle[1],5.0,max_relative=1e-12);
+        assert_relative_eq!(middle[2],1.0,max_relative=1e-12);
+        // Held at the edges of the grid
+        let outside = wind.get_wind(&Vector3::new(-500.0,500.0,-1000.0));
+        assert_eq!(outside,Vector3::new(0.0,10.0,2.0));
+    }
+
+    #[test]
+    fn test_shared_handles() {
+        let wind = GriddedWind::new(field());
+        let handle = wind.clone_handle();
+        assert_eq!(wind.handles(),2);
+        assert!(Arc::ptr_eq(wind.field(),handle.field()));
+
+        // Handles can be sent to parallel workers
+        let workers: Vec<_> = (0..4).map(|worker| {
+            let handle = wind.clone_handle();
+            std::thread::spawn(move || handle.get_wind(&Vector3::new(0.0,worker as f64 * 25.0,0.0))[2])
+        }).collect();
+        let downdrafts: Vec<f64> = workers.into_iter().map(|worker| worker.join().unwrap()).collect();
+        assert_relative_eq!(downdrafts[2],1.0,max_relative=1e-12);
+        drop(handle);
+        assert_eq!(wind.handles(),1);
+    }
+}
@@ -1,7 +1,8 @@
 use crate::WindModel;
-use crate::types::{Vector3,Float};
+use crate::types::{Vector3,Float,constant};
 
 /// Built-in [WindModel] to represent a [log wind profile](https://en.wikipedia.org/wiki/Log_wind_profile)
+#[derive(Copy,Clone)]
 pub struct LogWind<T: Float> {
     d: T,
     z0: T,
@@ -26,12 +27,17 @@ impl<T: Float> LogWind<T> {
             bearing,
         }
     }
+    
+    /// Return the wind speed (m·s<sup>-1</sup>) of the profile at `height` (m)
+    pub fn speed_at(&self, height: T) -> T {
+        let k = constant::<T>(0.41);
+        self.u_star/k * <T as num_traits::Float>::ln((height - self.d) / self.z0)
+    }
 }
 
 impl<T: Float> WindModel<T> for LogWind<T> {
     fn get_wind(&self, position: &Vector3<T>) -> Vector3<T> {
-        let k = T::from(0.41).unwrap();
-        let velocity = self.u_star/k * <T as num_traits::Float>::ln((position.z - self.d) / self.z0);
+        let velocity = self.speed_at(position.z);
         let bearing_rad = self.bearing.to_radians();
         Vector3::new(
             velocity * <T as num_traits::Float>::cos(bearing_rad),
@@ -44,6 +50,7 @@ impl<T: Float> WindModel<T> for LogWind<T> {
 
 #[cfg(test)]
 mod test {
+    #[allow(unused_imports)]
     use super::*;
 
     #[test]
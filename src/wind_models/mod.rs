@@ -1,7 +1,13 @@
 mod constantwind;
 mod logwind;
 mod powerwind;
+mod dryden;
+mod gridded;
+mod turbulent;
 
 pub use constantwind::ConstantWind;
 pub use logwind::LogWind;
 pub use powerwind::PowerWind;
+pub use dryden::{DrydenConfig,DrydenTurbulence};
+pub use gridded::{GriddedWind,WindField};
+pub use turbulent::TurbulentWind;
@@ -1,7 +1,8 @@
 use crate::WindModel;
-use crate::types::{Vector3,Float};
+use crate::types::{Vector3,Float,constant};
 
 /// Built-in [WindModel] to represent a [wind profile power law](https://en.wikipedia.org/wiki/Wind_profile_power_law)
+#[derive(Copy,Clone)]
 pub struct PowerWind<T: Float> {
     u_r: T,
     z_r: T,
@@ -32,14 +33,19 @@ impl<T: Float> PowerWind<T> {
     /// * `z_r` - Reference wind height (m)
     /// * `bearing` - The bearing for the calculated wind vector (deg)
     pub fn new(u_r: T, z_r: T, bearing: T) -> Self {
-        let alpha_typical = T::from(0.143).unwrap();
+        let alpha_typical = constant::<T>(0.143);
         PowerWind::new_with_alpha(u_r, z_r, bearing, alpha_typical)
     }
+    
+    /// Return the wind speed (m·s<sup>-1</sup>) of the profile at `height` (m)
+    pub fn speed_at(&self, height: T) -> T {
+        self.u_r * <T as num_traits::Float>::powf(height / self.z_r,self.alpha)
+    }
 }
 
 impl<T: Float> WindModel<T> for PowerWind<T> {
     fn get_wind(&self, position: &Vector3<T>) -> Vector3<T> {
-        let velocity = self.u_r * <T as num_traits::Float>::powf(position.z / self.z_r,self.alpha);
+        let velocity = self.speed_at(position.z);
         let bearing_rad = self.bearing.to_radians();
         Vector3::new(
             velocity * <T as num_traits::Float>::cos(bearing_rad),
@@ -0,0 +1,165 @@
+use crate::{WindModel,Error};
+use crate::snapshot::WindState;
+use crate::types::{Vector3,Float,constant};
+
+use super::{DrydenConfig,DrydenTurbulence,PowerWind,LogWind,ConstantWind};
+
+/// Height of the MIL-F-8785C reference wind speed, 20 ft (m)
+const REFERENCE_HEIGHT: f64 = 6.096;
+
+/// Built-in [WindModel] adding [DrydenTurbulence] to a mean wind
+///
+/// Usually built with the `with_turbulence` and `with_low_altitude_turbulence` methods of the mean wind
+/// models, which take the low-altitude turbulence intensity from the mean wind speed at 20 ft so the shear
+/// and the turbulence are consistent.
+#[derive(Clone)]
+pub struct TurbulentWind<T: Float, W: WindModel<T>> {
+    /// Mean wind
+    mean: W,
+    /// Turbulence added to the mean wind
+    turbulence: DrydenTurbulence<T>,
+}
+
+impl<T: Float, W: WindModel<T>> TurbulentWind<T,W> {
+    /// Create a new [TurbulentWind] adding turbulence of `config`, seeded with `seed`, to the `mean` wind
+    ///
+    /// # Panics
+    ///
+    /// Panics if the turbulence airspeed or any length scale is not positive.
+    pub fn new(mean: W, config: DrydenConfig<T>, seed: u64) -> Self {
+        TurbulentWind { mean, turbulence: DrydenTurbulence::new(config,seed) }
+    }
+
+    /// Create a new [TurbulentWind] with MIL-F-8785C low-altitude turbulence scaled by `wind_speed_20ft`
+    ///
+    /// # Arguments
+    ///
+    /// * `mean` - Mean wind model
+    /// * `wind_speed_20ft` - Mean wind speed at 6 m (20 ft) (m·s<sup>-1</sup>)
+    /// * `airspeed` - Airspeed through the turbulence (m·s<sup>-1</sup>)
+    /// * `altitude` - Altitude above ground setting the length scales (m)
+    /// * `seed` - Seed of the turbulence
+    fn low_altitude(mean: W, wind_speed_20ft: T, airspeed: T, altitude: T, seed: u64) -> Self {
+        TurbulentWind::new(mean,DrydenConfig::low_altitude(airspeed,altitude,wind_speed_20ft),seed)
+    }
+
+    /// Return the mean wind model
+    pub fn mean(&self) -> &W {
+        &self.mean
+    }
+
+    /// Return the turbulence model
+    pub fn turbulence(&self) -> &DrydenTurbulence<T> {
+        &self.turbulence
+    }
+
+    /// Return the turbulence model for modification, such as to set its scale
+    pub fn turbulence_mut(&mut self) -> &mut DrydenTurbulence<T> {
+        &mut self.turbulence
+    }
+}
+
+impl<T: Float, W: WindModel<T>> WindModel<T> for TurbulentWind<T,W> {
+    fn get_wind(&self, position: &Vector3<T>) -> Vector3<T> {
+        self.mean.get_wind(position) + self.turbulence.get_wind(position)
+    }
+
+    fn step(&mut self, delta_t: T) {
+        self.mean.step(delta_t);
+        self.turbulence.step(delta_t);
+    }
+
+    fn snapshot(&self) -> WindState {
+        WindState { parts: vec![self.mean.snapshot(),self.turbulence.snapshot()], ..WindState::default() }
+    }
+
+    fn restore(&mut self, state: &WindState) -> Result<(),Error> {
+        state.check(0,0,2)?;
+        self.turbulence.restore(&state.parts[1])?;
+        self.mean.restore(&state.parts[0])
+    }
+}
+
+impl<T: Float> PowerWind<T> {
+    /// Add turbulence of `config`, seeded with `seed`
+    pub fn with_turbulence(self, config: DrydenConfig<T>, seed: u64) -> TurbulentWind<T,Self> {
+        TurbulentWind::new(self,config,seed)
+    }
+
+    /// Add MIL-F-8785C low-altitude turbulence scaled by the profile wind speed at 20 ft
+    ///
+    /// The length scales are those at `altitude` (m) for flight at `airspeed` (m·s<sup>-1</sup>).
+    pub fn with_low_altitude_turbulence(self, airspeed: T, altitude: T, seed: u64) -> TurbulentWind<T,Self> {
+        let wind_speed_20ft = self.speed_at(constant::<T>(REFERENCE_HEIGHT));
+        TurbulentWind::low_altitude(self,wind_speed_20ft,airspeed,altitude,seed)
+    }
+}
+
+impl<T: Float> LogWind<T> {
+    /// Add turbulence of `config`, seeded with `seed`
+    pub fn with_turbulence(self, config: DrydenConfig<T>, seed: u64) -> TurbulentWind<T,Self> {
+        TurbulentWind::new(self,config,seed)
+    }
+
+    /// Add MIL-F-8785C low-altitude turbulence scaled by the profile wind speed at 20 ft
+    ///
+    /// The length scales are those at `altitude` (m) for flight at `airspeed` (m·s<sup>-1</sup>).
+    pub fn with_low_altitude_turbulence(self, airspeed: T, altitude: T, seed: u64) -> TurbulentWind<T,Self> {
+        let wind_speed_20ft = self.speed_at(constant::<T>(REFERENCE_HEIGHT));
+        TurbulentWind::low_altitude(self,wind_speed_20ft,airspeed,altitude,seed)
+    }
+}
+
+impl<T: Float> ConstantWind<T> {
+    /// Add turbulence of `config`, seeded with `seed`
+    pub fn with_turbulence(self, config: DrydenConfig<T>, seed: u64) -> TurbulentWind<T,Self> {
+        TurbulentWind::new(self,config,seed)
+    }
+
+    /// Add MIL-F-8785C low-altitude turbulence scaled by the horizontal wind speed
+    ///
+    /// The length scales are those at `altitude` (m) for flight at `airspeed` (m·s<sup>-1</sup>).
+    pub fn with_low_altitude_turbulence(self, airspeed: T, altitude: T, seed: u64) -> TurbulentWind<T,Self> {
+        let wind = self.get_wind(&Vector3::zeros());
+        let wind_speed_20ft = <T as num_traits::Float>::hypot(wind[0],wind[1]);
+        TurbulentWind::low_altitude(self,wind_speed_20ft,airspeed,altitude,seed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_consistent_scaling() {
+        let profile = PowerWind::new(10.0,10.0,90.0);
+        let wind = profile.with_low_altitude_turbulence(30.0,50.0,1);
+        // Intensity follows the profile speed at 20 ft, and lengths follow the altitude
+        let expected = DrydenConfig::low_altitude(30.0,50.0,10.0 * (6.096_f64 / 10.0).powf(0.143));
+        assert_eq!(*wind.turbulence().config(),expected);
+        assert_relative_eq!(wind.turbulence().config().sigma[2],0.1 * profile.speed_at(6.096),max_relative=1e-12);
+
+        let log = LogWind::new(0.0,0.1,0.5,0.0);
+        let config = *log.with_low_altitude_turbulence(30.0,50.0,1).turbulence().config();
+        assert_relative_eq!(config.sigma[2],0.1 * 0.5 / 0.41 * (6.096_f64 / 0.1).ln(),max_relative=1e-12);
+        let constant = ConstantWind::new(Vector3::new(3.0,4.0,0.0)).with_low_altitude_turbulence(30.0,50.0,1);
+        assert_relative_eq!(constant.turbulence().config().sigma[2],0.5,max_relative=1e-12);
+    }
+
+    #[test]
+    fn test_combined_wind() {
+        let mean = ConstantWind::new(Vector3::new(5.0,0.0,0.0));
+        let config = DrydenConfig::high_altitude(50.0,2.0);
+        let mut wind = mean.with_turbulence(config,7);
+        let mut turbulence = DrydenTurbulence::new(config,7);
+        for _ in 0..10 {
+            wind.step(0.1);
+            turbulence.step(0.1);
+        }
+        let position = Vector3::zeros();
+        assert_eq!(wind.get_wind(&position),Vector3::new(5.0,0.0,0.0) + turbulence.get_wind(&position));
+        wind.turbulence_mut().set_scale(0.0);
+        assert_eq!(wind.get_wind(&position),wind.mean().get_wind(&position));
+    }
+}
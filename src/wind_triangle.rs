@@ -0,0 +1,142 @@
+//! Wind-triangle navigation quantities
+//!
+//! A [WindTriangle] relates the ground velocity of a vehicle to its velocity through the air and the
+//! wind, giving the ground speed, track, heading and drift angle used in navigation studies, and
+//! [wind_corrected_heading] solves the triangle for the heading which holds a desired track.
+//!
+//! Directions are in radians clockwise from North, in `[0, 2π)`. The heading is the direction of the
+//! horizontal velocity through the air, which equals the nose heading in flight without sideslip.
+
+use crate::{Body,AffectedBody,WindModel,DensityModel};
+use crate::types::{Vector3,StateVector,StateView,Float,DefaultFloatRepr};
+use crate::attitude::{wrap_pi,wrap_two_pi};
+
+/// Return the direction of the North-East vector `(north, east)`, clockwise from North in `[0, 2π)`
+fn direction<T: Float>(north: T, east: T) -> T {
+    wrap_two_pi(<T as num_traits::Float>::atan2(east,north))
+}
+
+/// Wind triangle of a vehicle at an instant
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct WindTriangle<T: Float = DefaultFloatRepr> {
+    /// Horizontal speed over the ground (m·s<sup>-1</sup>)
+    pub ground_speed: T,
+    /// Direction of the horizontal ground velocity (rad)
+    pub track: T,
+    /// Horizontal speed through the air (m·s<sup>-1</sup>)
+    pub air_speed: T,
+    /// Direction of the horizontal velocity through the air (rad)
+    pub heading: T,
+    /// Track minus heading, positive when the wind drifts the vehicle to the right, in `[-π, π)` (rad)
+    pub drift: T,
+    /// Horizontal wind speed (m·s<sup>-1</sup>)
+    pub wind_speed: T,
+    /// Direction the wind blows from (rad)
+    pub wind_from: T,
+    /// Wind component along the heading opposing the motion, negative for a tailwind (m·s<sup>-1</sup>)
+    pub headwind: T,
+    /// Wind component from the left of the heading, blowing to the right (m·s<sup>-1</sup>)
+    pub crosswind: T,
+}
+
+impl<T: Float> WindTriangle<T> {
+    /// Create the [WindTriangle] of a vehicle in `state` in the world-frame `wind`
+    pub fn new(state: &StateVector<T>, wind: &Vector3<T>) -> Self {
+        let ground: Vector3<T> = Body::get_dcm_body(state) * state.fixed_rows::<3>(3);
+        let air = ground - wind;
+        let heading = direction(air[0],air[1]);
+        let track = direction(ground[0],ground[1]);
+        let (sin_heading, cos_heading) = <T as num_traits::Float>::sin_cos(heading);
+        WindTriangle {
+            ground_speed: <T as num_traits::Float>::hypot(ground[0],ground[1]),
+            track,
+            air_speed: <T as num_traits::Float>::hypot(air[0],air[1]),
+            heading,
+            drift: wrap_pi(track - heading),
+            wind_speed: <T as num_traits::Float>::hypot(wind[0],wind[1]),
+            wind_from: direction(-wind[0],-wind[1]),
+            headwind: -(wind[0] * cos_heading + wind[1] * sin_heading),
+            crosswind: -wind[0] * sin_heading + wind[1] * cos_heading,
+        }
+    }
+
+    /// Create the [WindTriangle] of `vehicle` in the wind of its wind model
+    pub fn from_vehicle<I, W: WindModel<T>, D: DensityModel<T>>(vehicle: &AffectedBody<I,T,W,D>) -> Self {
+        Self::new(&vehicle.statevector(),&vehicle.body.get_wind())
+    }
+}
+
+/// Return the heading (rad) and ground speed (m·s<sup>-1</sup>) which hold `track` (rad) at the horizontal
+/// `air_speed` (m·s<sup>-1</sup>) in the world-frame `wind`
+///
+/// Returns `None` if the crosswind exceeds the air speed, or the headwind leaves no progress along the track.
+pub fn wind_corrected_heading<T: Float>(air_speed: T, track: T, wind: &Vector3<T>) -> Option<(T,T)> {
+    let (sin_track, cos_track) = <T as num_traits::Float>::sin_cos(track);
+    let along = wind[0] * cos_track + wind[1] * sin_track;
+    let across = -wind[0] * sin_track + wind[1] * cos_track;
+    if air_speed <= T::zero() || <T as num_traits::Float>::abs(across) > air_speed {
+        return None;
+    }
+    let correction = <T as num_traits::Float>::asin(across / air_speed);
+    let ground_speed = air_speed * <T as num_traits::Float>::cos(correction) + along;
+    if ground_speed <= T::zero() {
+        return None;
+    }
+    Some((direction(<T as num_traits::Float>::cos(track - correction),<T as num_traits::Float>::sin(track - correction)),ground_speed))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::UnitQuaternion;
+    use approx::assert_relative_eq;
+
+    /// Statevector flying level at `speed` on `heading` without sideslip
+    fn state(speed: f64, heading: f64) -> StateVector<f64> {
+        let attitude = UnitQuaternion::from_euler_angles(0.0,0.0,heading);
+        let mut state = StateVector::zeros();
+        state[3] = speed;
+        state.fixed_rows_mut::<4>(6).copy_from(&attitude.coords);
+        state
+    }
+
+    #[test]
+    fn test_triangle() {
+        // Heading North at 30 m/s through the air, in 10 m/s of wind blowing from the West
+        let wind = Vector3::new(0.0,10.0,0.0);
+        let body = state(30.0,0.0);
+        let wind_body = Body::get_dcm(&body) * wind;
+        let mut flying = body;
+        flying.fixed_rows_mut::<3>(3).copy_from(&(Vector3::new(30.0,0.0,0.0) + wind_body));
+        let triangle = WindTriangle::new(&flying,&wind);
+        assert_relative_eq!(triangle.heading,0.0,epsilon=1e-9);
+        assert_relative_eq!(triangle.air_speed,30.0,max_relative=1e-9);
+        assert_relative_eq!(triangle.track,(10.0_f64 / 30.0).atan(),max_relative=1e-9);
+        assert_relative_eq!(triangle.drift,triangle.track,max_relative=1e-9);
+        assert_relative_eq!(triangle.wind_from,1.5 * std::f64::consts::PI,max_relative=1e-9);
+        assert_relative_eq!(triangle.crosswind,10.0,max_relative=1e-9);
+        assert_relative_eq!(triangle.headwind,0.0,epsilon=1e-9);
+    }
+
+    #[test]
+    fn test_wind_correction() {
+        // Holding an Easterly track in a wind from the North, the heading turns into the wind
+        let wind = Vector3::new(-10.0,0.0,0.0);
+        let track = std::f64::consts::FRAC_PI_2;
+        let (heading, ground_speed) = wind_corrected_heading(20.0,track,&wind).unwrap();
+        assert_relative_eq!(heading,track - 0.5_f64.asin(),max_relative=1e-9);
+        assert_relative_eq!(ground_speed,300.0_f64.sqrt(),max_relative=1e-9);
+
+        // Flying the corrected heading makes good the track
+        let body = state(20.0,heading);
+        let mut flying = body;
+        flying.fixed_rows_mut::<3>(3).copy_from(&(Vector3::new(20.0,0.0,0.0) + Body::get_dcm(&body) * wind));
+        let triangle = WindTriangle::new(&flying,&wind);
+        assert_relative_eq!(triangle.track,track,max_relative=1e-9);
+        assert_relative_eq!(triangle.ground_speed,ground_speed,max_relative=1e-9);
+        assert_relative_eq!(triangle.headwind,10.0 * heading.cos(),max_relative=1e-9);
+
+        assert!(wind_corrected_heading(8.0,track,&wind).is_none());
+        assert!(wind_corrected_heading(8.0,0.0,&wind).is_none());
+    }
+}
@@ -0,0 +1,852 @@
+//! Multi-vehicle worlds
+//!
+//! A [World] steps several named vehicles together. Each vehicle may carry a [WakeModel], and the
+//! velocity induced by the wakes of all other vehicles is added to its wind input before every step,
+//! for formation-flight and close-proximity studies such as shipboard recovery.
+//!
+//! [World::relative_state] gives the [RelativeState] of one vehicle seen from another, in world axes or
+//! the body axes of the observer, for intercept, refuelling and collision-avoidance studies.
+//!
+//! Vehicles given a [CollisionShape] are checked for contact after every step. Each new contact between
+//! two vehicles raises a [Collision] event, collected until [World::take_collisions] is called.
+//!
+//! A [Towline] added with [World::add_towline] joins two vehicles, or trails a drogue behind one, and
+//! is stepped with them so its loads act on both. The line is flown in the wakes of the vehicles, sampled
+//! at the drogue.
+//!
+//! The induced velocity is sampled at the centre of gravity of the receiving vehicle and applied
+//! uniformly over it, through the [InteractionWind] wrapper around its own [WindModel].
+//!
+//! ```
+//! use aerso::{Body,AeroBody,AffectedBody};
+//! use aerso::density_models::StandardDensity;
+//! use aerso::types::{Vector3,Matrix3,UnitQuaternion};
+//! use aerso::wind_models::ConstantWind;
+//! use aerso::world::{World,InteractionWind,VortexWake};
+//!
+//! let vehicle = |position| {
+//!     let body = Body::new(10.0,Matrix3::identity(),position,Vector3::new(30.0,0.0,0.0),UnitQuaternion::identity(),Vector3::zeros());
+//!     let wind = InteractionWind::new(ConstantWind::new(Vector3::zeros()));
+//!     AffectedBody::<Vec<f64>,f64,_,_>::new(AeroBody::with_density_model(body,wind,StandardDensity{}),vec![])
+//! };
+//! let mut world = World::new();
+//! world.add("leader",vehicle(Vector3::new(20.0,0.0,0.0)),Some(Box::new(VortexWake::new(3.0,98.1))));
+//! world.add("wingman",vehicle(Vector3::new(0.0,2.5,0.0)),None);
+//! world.step(0.01,&[vec![],vec![]]);
+//! ```
+
+use crate::{AffectedBody,AirState,WindModel,DensityModel,Body,Error};
+use crate::contact::ContactModel;
+use crate::snapshot::{WindState,export};
+use crate::tether::Towline;
+use crate::types::{Vector3,Matrix3,StateVector,StateView,Frame,Force,Torque,Float,DefaultFloatRepr,constant};
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Model of the velocity induced in the surrounding air by a vehicle
+pub trait WakeModel<T: Float = DefaultFloatRepr> {
+    /// Return the world-frame velocity induced at the world `position` by a vehicle in `state` with `airstate`
+    fn induced_velocity(&self, state: &StateVector<T>, airstate: AirState<T>, position: &Vector3<T>) -> Vector3<T>;
+}
+
+/// [WindModel] adding an externally set induced velocity to a wrapped [WindModel]
+#[derive(Copy,Clone,Debug)]
+pub struct InteractionWind<T: Float, W: WindModel<T>> {
+    /// Ambient wind model
+    ambient: W,
+    /// World-frame induced velocity (m·s<sup>-1</sup>)
+    induced: Vector3<T>,
+}
+
+impl<T: Float, W: WindModel<T>> InteractionWind<T,W> {
+    /// Create a new [InteractionWind] around the `ambient` wind model, with no induced velocity
+    pub fn new(ambient: W) -> Self {
+        InteractionWind { ambient, induced: Vector3::zeros() }
+    }
+
+    /// Return the ambient wind model
+    pub fn ambient(&self) -> &W {
+        &self.ambient
+    }
+
+    /// Return the ambient wind model for modification
+    pub fn ambient_mut(&mut self) -> &mut W {
+        &mut self.ambient
+    }
+
+    /// Return the world-frame induced velocity (m·s<sup>-1</sup>)
+    pub fn induced(&self) -> Vector3<T> {
+        self.induced
+    }
+
+    /// Set the world-frame induced velocity (m·s<sup>-1</sup>)
+    pub fn set_induced(&mut self, induced: Vector3<T>) {
+        self.induced = induced;
+    }
+}
+
+impl<T: Float, W: WindModel<T>> WindModel<T> for InteractionWind<T,W> {
+    fn get_wind(&self, position: &Vector3<T>) -> Vector3<T> {
+        self.ambient.get_wind(position) + self.induced
+    }
+
+    fn step(&mut self, delta_t: T) {
+        self.ambient.step(delta_t);
+    }
+
+    fn snapshot(&self) -> WindState {
+        WindState {
+            values: vec![export(self.induced[0]),export(self.induced[1]),export(self.induced[2])],
+            words: vec![],
+            parts: vec![self.ambient.snapshot()],
+        }
+    }
+
+    fn restore(&mut self, state: &WindState) -> Result<(),Error> {
+        state.check(3,0,1)?;
+        self.ambient.restore(&state.parts[0])?;
+        self.induced = Vector3::new(state.value(0),state.value(1),state.value(2));
+        Ok(())
+    }
+}
+
+/// Return the velocity induced at `point` by a semi-infinite straight vortex of circulation `gamma` (m²·s<sup>-1</sup>)
+/// with vorticity along the unit `axis`, starting at `origin` and extending along `axis`, with a core of radius `core` (m)
+fn semi_infinite_vortex<T: Float>(origin: &Vector3<T>, axis: &Vector3<T>, gamma: T, core: T, point: &Vector3<T>) -> Vector3<T> {
+    let offset = point - origin;
+    let distance = offset.norm();
+    if distance == T::zero() {
+        return Vector3::zeros();
+    }
+    let radial = offset - axis * offset.dot(axis);
+    let four_pi = constant::<T>(4.0 * std::f64::consts::PI);
+    let extent = T::one() + offset.dot(axis) / distance;
+    axis.cross(&radial) * (gamma * extent / (four_pi * (radial.norm_squared() + core * core)))
+}
+
+/// Built-in [WakeModel] for the trailing vortex pair of a lifting wing
+///
+/// Two semi-infinite vortices with cores trail downstream from the wingtips of the elliptically loaded
+/// span `π/4 b`, with circulation `L / (ρ V π/4 b)` for lift `L`. They trail straight back along the
+/// airspeed direction, so wake roll-up, descent and decay are neglected.
+#[derive(Copy,Clone,Debug)]
+pub struct VortexWake<T: Float = DefaultFloatRepr> {
+    /// Wing span (m)
+    span: T,
+    /// Lift supported by the wing (N)
+    lift: T,
+    /// Vortex core radius (m)
+    core_radius: T,
+}
+
+impl<T: Float> VortexWake<T> {
+    /// Create a new [VortexWake] for a wing of `span` (m) supporting `lift` (N), with a core radius of 10% of the vortex spacing
+    pub fn new(span: T, lift: T) -> Self {
+        let spacing = Self::spacing(span);
+        VortexWake { span, lift, core_radius: constant::<T>(0.1) * spacing }
+    }
+
+    /// Set the vortex core radius (m)
+    pub fn with_core_radius(mut self, core_radius: T) -> Self {
+        self.core_radius = core_radius;
+        self
+    }
+
+    /// Set the lift supported by the wing (N)
+    pub fn set_lift(&mut self, lift: T) {
+        self.lift = lift;
+    }
+
+    /// Return the lateral spacing of the trailing vortices for a wing of `span` (m)
+    pub fn spacing(span: T) -> T {
+        constant::<T>(std::f64::consts::FRAC_PI_4) * span
+    }
+
+    /// Return the circulation of each trailing vortex (m²·s<sup>-1</sup>) at `airstate`
+    pub fn circulation(&self, airstate: AirState<T>) -> T {
+        if airstate.airspeed <= T::zero() || airstate.density <= T::zero() {
+            return T::zero();
+        }
+        self.lift / (airstate.density * airstate.airspeed * Self::spacing(self.span))
+    }
+}
+
+impl<T: Float> WakeModel<T> for VortexWake<T> {
+    fn induced_velocity(&self, state: &StateVector<T>, airstate: AirState<T>, position: &Vector3<T>) -> Vector3<T> {
+        let gamma = self.circulation(airstate);
+        if gamma == T::zero() {
+            return Vector3::zeros();
+        }
+        let to_world = Body::get_dcm_body(state);
+        let (sin_alpha, cos_alpha) = <T as num_traits::Float>::sin_cos(airstate.alpha);
+        let (sin_beta, cos_beta) = <T as num_traits::Float>::sin_cos(airstate.beta);
+        let downstream: Vector3<T> = -(to_world * Vector3::new(cos_alpha * cos_beta,sin_beta,sin_alpha * cos_beta));
+        let half_spacing = Self::spacing(self.span) / constant::<T>(2.0);
+        let centre: Vector3<T> = state.fixed_rows::<3>(0).into();
+        let tip: Vector3<T> = to_world * Vector3::new(T::zero(),half_spacing,T::zero());
+        // Vorticity points forward in the left vortex and aft in the right, giving downwash between them
+        semi_infinite_vortex(&(centre - tip),&downstream,-gamma,self.core_radius,position)
+            + semi_infinite_vortex(&(centre + tip),&downstream,gamma,self.core_radius,position)
+    }
+}
+
+/// Built-in [WakeModel] for the downwash below a hovering rotor
+///
+/// From momentum theory, the induced velocity `v_i = √(T / 2ρA)` at the disk grows to `2 v_i` far below it,
+/// as `v_i (1 + s / √(s² + R²))` at distance `s` along the rotor axis. The slipstream is a cylinder of the
+/// rotor radius along the body z axis through the centre of gravity, with no velocity outside it or above the disk.
+#[derive(Copy,Clone,Debug)]
+pub struct RotorDownwash<T: Float = DefaultFloatRepr> {
+    /// Rotor radius (m)
+    radius: T,
+    /// Rotor thrust (N)
+    thrust: T,
+}
+
+impl<T: Float> RotorDownwash<T> {
+    /// Create a new [RotorDownwash] for a rotor of `radius` (m) producing `thrust` (N)
+    pub fn new(radius: T, thrust: T) -> Self {
+        RotorDownwash { radius, thrust }
+    }
+
+    /// Set the rotor thrust (N)
+    pub fn set_thrust(&mut self, thrust: T) {
+        self.thrust = thrust;
+    }
+
+    /// Return the induced velocity at the rotor disk (m·s<sup>-1</sup>) in air of `density` (kg·m<sup>-3</sup>)
+    pub fn disk_velocity(&self, density: T) -> T {
+        let area = constant::<T>(std::f64::consts::PI) * self.radius * self.radius;
+        <T as num_traits::Float>::sqrt(<T as num_traits::Float>::max(self.thrust,T::zero()) / (constant::<T>(2.0) * density * area))
+    }
+}
+
+impl<T: Float> WakeModel<T> for RotorDownwash<T> {
+    fn induced_velocity(&self, state: &StateVector<T>, airstate: AirState<T>, position: &Vector3<T>) -> Vector3<T> {
+        let axis: Vector3<T> = Body::get_dcm_body(state) * Vector3::z();
+        let offset = position - state.fixed_rows::<3>(0);
+        let below = offset.dot(&axis);
+        if below < T::zero() || (offset - axis * below).norm() > self.radius {
+            return Vector3::zeros();
+        }
+        let growth = T::one() + below / <T as num_traits::Float>::hypot(below,self.radius);
+        axis * (self.disk_velocity(airstate.density) * growth)
+    }
+}
+
+/// State of a target vehicle relative to an observer, as given by [World::relative_state]
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct RelativeState<T: Float = DefaultFloatRepr> {
+    /// Position of the target relative to the observer (m)
+    pub position: Vector3<T>,
+    /// Velocity of the target relative to the observer (m·s<sup>-1</sup>)
+    pub velocity: Vector3<T>,
+    /// Distance from the observer to the target (m)
+    pub range: T,
+    /// Rate at which the range decreases (m·s<sup>-1</sup>), positive when closing
+    pub closure_rate: T,
+    /// Angle of the target clockwise from the frame X axis in the frame XY plane (rad)
+    ///
+    /// This is the true bearing in world axes, or the relative bearing from the nose in body axes.
+    pub bearing: T,
+    /// Angle of the target above the frame XY plane (rad)
+    pub elevation: T,
+}
+
+impl<T: Float> RelativeState<T> {
+    /// Create a new [RelativeState] from the relative `position` (m) and `velocity` (m·s<sup>-1</sup>) in one frame
+    ///
+    /// The closure rate is zero when the target is at the observer.
+    pub fn new(position: Vector3<T>, velocity: Vector3<T>) -> Self {
+        let range = position.norm();
+        RelativeState {
+            position,
+            velocity,
+            range,
+            closure_rate: if range > T::zero() { -position.dot(&velocity) / range } else { T::zero() },
+            bearing: <T as num_traits::Float>::atan2(position[1],position[0]),
+            elevation: <T as num_traits::Float>::atan2(-position[2],<T as num_traits::Float>::hypot(position[0],position[1])),
+        }
+    }
+
+    /// Return the time (s) to the closest point of approach at constant relative velocity, or zero if the range is opening
+    pub fn time_to_closest_approach(&self) -> T {
+        let speed_squared = self.velocity.norm_squared();
+        if speed_squared == T::zero() {
+            return T::zero();
+        }
+        <T as num_traits::Float>::max(-self.position.dot(&self.velocity) / speed_squared,T::zero())
+    }
+
+    /// Return the miss distance (m) at the closest point of approach at constant relative velocity
+    pub fn miss_distance(&self) -> T {
+        (self.position + self.velocity * self.time_to_closest_approach()).norm()
+    }
+}
+
+/// Collision volume of a vehicle in a [World], centred on its centre of gravity
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum CollisionShape<T: Float = DefaultFloatRepr> {
+    /// Sphere of the given radius (m)
+    Sphere(T),
+    /// Box aligned with the body axes, of the given half-lengths along each axis (m)
+    Box(Vector3<T>),
+}
+
+impl<T: Float> CollisionShape<T> {
+    /// Return the radius of the smallest sphere about the centre of gravity enclosing the shape (m)
+    pub fn bounding_radius(&self) -> T {
+        match self {
+            CollisionShape::Sphere(radius) => *radius,
+            CollisionShape::Box(half) => half.norm(),
+        }
+    }
+
+    /// Return the penetration depth (m) of this shape at `position` with body-to-world `axes` into the
+    /// `other` shape at `other_position` with `other_axes`, negative when they are apart
+    ///
+    /// Spheres use the exact separation. Boxes use the smallest overlap along the separating axes of
+    /// the pair, which is negative when they are apart but not the exact separation.
+    pub fn penetration(&self, position: &Vector3<T>, axes: &Matrix3<T>, other: &CollisionShape<T>, other_position: &Vector3<T>, other_axes: &Matrix3<T>) -> T {
+        match (self, other) {
+            (CollisionShape::Sphere(radius), CollisionShape::Sphere(other_radius)) => *radius + *other_radius - (other_position - position).norm(),
+            (CollisionShape::Sphere(radius), CollisionShape::Box(half)) => sphere_box_penetration(*radius,position,half,other_position,other_axes),
+            (CollisionShape::Box(half), CollisionShape::Sphere(radius)) => sphere_box_penetration(*radius,other_position,half,position,axes),
+            (CollisionShape::Box(half), CollisionShape::Box(other_half)) => {
+                let offset = other_position - position;
+                let mut candidates: Vec<Vector3<T>> = Vec::with_capacity(15);
+                for i in 0..3 {
+                    candidates.push(axes.column(i).into());
+                    candidates.push(other_axes.column(i).into());
+                    for j in 0..3 {
+                        candidates.push(axes.column(i).cross(&other_axes.column(j)));
+                    }
+                }
+                let tolerance = constant::<T>(1e-9);
+                candidates.iter()
+                    .filter(|axis| axis.norm() > tolerance)
+                    .map(|axis| {
+                        let axis = axis.normalize();
+                        let extent = |half: &Vector3<T>, axes: &Matrix3<T>| (0..3).fold(T::zero(),|total, i| total + half[i] * <T as num_traits::Float>::abs(axes.column(i).dot(&axis)));
+                        extent(half,axes) + extent(other_half,other_axes) - <T as num_traits::Float>::abs(offset.dot(&axis))
+                    })
+                    .fold(T::infinity(),|least, overlap| <T as num_traits::Float>::min(least,overlap))
+            },
+        }
+    }
+}
+
+/// Return the penetration depth (m) of a sphere of `radius` at `centre` into a box of half-lengths `half` at `position` with body-to-world `axes`
+fn sphere_box_penetration<T: Float>(radius: T, centre: &Vector3<T>, half: &Vector3<T>, position: &Vector3<T>, axes: &Matrix3<T>) -> T {
+    let local = axes.transpose() * (centre - position);
+    let closest = Vector3::from_fn(|i, _| <T as num_traits::Float>::max(-half[i],<T as num_traits::Float>::min(local[i],half[i])));
+    if closest == local {
+        // Centre inside the box, so the depth is to the nearest face
+        let inside = (0..3).fold(T::infinity(),|least, i| <T as num_traits::Float>::min(least,half[i] - <T as num_traits::Float>::abs(local[i])));
+        radius + inside
+    } else {
+        radius - (local - closest).norm()
+    }
+}
+
+/// Contact between two vehicles in a [World]
+#[derive(Clone,Debug,PartialEq)]
+pub struct Collision<T: Float = DefaultFloatRepr> {
+    /// Simulation time at which the contact was detected (s)
+    pub time: T,
+    /// Name of the vehicle earlier in the stepping order
+    pub first: String,
+    /// Name of the vehicle later in the stepping order
+    pub second: String,
+    /// Penetration depth when the contact was detected (m)
+    pub depth: T,
+}
+
+/// Body-frame force and torque of a [Towline] on a vehicle, set by the [World] before each step
+type TowlineLoad<T> = Rc<Cell<(Force<T>,Torque<T>)>>;
+
+/// [ContactModel] applying the load of a [Towline] set by the [World]
+struct TowlineContact<T: Float> {
+    /// Load for the current step
+    load: TowlineLoad<T>,
+}
+
+impl<T: Float> ContactModel<T> for TowlineContact<T> {
+    fn get_contact(&self, _state: &StateVector<T>) -> (Force<T>,Torque<T>) {
+        self.load.get()
+    }
+}
+
+/// A [Towline] between vehicles of a [World]
+struct WorldTowline<T: Float> {
+    /// The towline
+    towline: Towline<T>,
+    /// Name of the towing vehicle
+    tow: String,
+    /// Name of any towed vehicle
+    tail: Option<String>,
+    /// Load on the towing vehicle
+    tow_load: TowlineLoad<T>,
+    /// Load on any towed vehicle
+    tail_load: Option<TowlineLoad<T>>,
+}
+
+/// Return a load with no force or torque
+fn unloaded<T: Float>() -> (Force<T>,Torque<T>) {
+    (Force::body_vec(Vector3::zeros()),Torque::body_vec(Vector3::zeros()))
+}
+
+/// Vehicle in a [World], with its wind wrapped to receive the wakes of others
+pub type WorldVehicle<I,T,W,D> = AffectedBody<I,T,InteractionWind<T,W>,D>;
+
+/// A named vehicle in a [World]
+struct WorldEntry<I, T: Float, W: WindModel<T>, D: DensityModel<T>> {
+    /// Unique name of the vehicle
+    name: String,
+    /// The vehicle
+    vehicle: WorldVehicle<I,T,W,D>,
+    /// Wake of the vehicle, if it affects others
+    wake: Option<Box<dyn WakeModel<T>>>,
+    /// Collision volume of the vehicle, if it is checked for contact
+    shape: Option<CollisionShape<T>>,
+}
+
+/// Collection of named vehicles stepped together, interacting through their wakes
+pub struct World<I = Vec<DefaultFloatRepr>, T: Float = DefaultFloatRepr, W: WindModel<T> = crate::wind_models::ConstantWind<T>, D: DensityModel<T> = crate::density_models::StandardDensity> {
+    /// Vehicle entries in insertion order
+    entries: Vec<WorldEntry<I,T,W,D>>,
+    /// Elapsed simulation time (s), advanced by each step
+    time: T,
+    /// Names of the pairs of vehicles in contact after the latest step
+    contacts: Vec<(String,String)>,
+    /// Collisions raised since they were last taken
+    collisions: Vec<Collision<T>>,
+    /// Towlines between vehicles
+    towlines: Vec<WorldTowline<T>>,
+}
+
+impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> Default for World<I,T,W,D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, T: Float, W: WindModel<T>, D: DensityModel<T>> World<I,T,W,D> {
+    /// Create an empty [World]
+    pub fn new() -> Self {
+        World { entries: Vec::new(), time: T::zero(), contacts: Vec::new(), collisions: Vec::new(), towlines: Vec::new() }
+    }
+
+    /// Return the index of the vehicle called `name`
+    fn position(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+
+    /// Add `vehicle` called `name` with an optional `wake`, replacing and returning any vehicle of the same name
+    ///
+    /// A replaced vehicle keeps its place in the stepping order. The clock of the vehicle is set to the
+    /// world time, and it has no [CollisionShape] until one is set.
+    pub fn add(&mut self, name: &str, mut vehicle: WorldVehicle<I,T,W,D>, wake: Option<Box<dyn WakeModel<T>>>) -> Option<WorldVehicle<I,T,W,D>> {
+        vehicle.set_time(self.time);
+        let entry = WorldEntry { name: name.to_string(), vehicle, wake, shape: None };
+        match self.position(name) {
+            Some(idx) => Some(std::mem::replace(&mut self.entries[idx],entry).vehicle),
+            None => {
+                self.entries.push(entry);
+                None
+            },
+        }
+    }
+
+    /// Remove and return the vehicle called `name`
+    pub fn remove(&mut self, name: &str) -> Option<WorldVehicle<I,T,W,D>> {
+        self.position(name).map(|idx| self.entries.remove(idx).vehicle)
+    }
+
+    /// Set the wake of the vehicle called `name`, returning `false` if there is no such vehicle
+    pub fn set_wake(&mut self, name: &str, wake: Option<Box<dyn WakeModel<T>>>) -> bool {
+        self.position(name).map(|idx| self.entries[idx].wake = wake).is_some()
+    }
+
+    /// Set the collision shape of the vehicle called `name`, returning `false` if there is no such vehicle
+    ///
+    /// Vehicles without a shape are not checked for contact.
+    pub fn set_collision_shape(&mut self, name: &str, shape: Option<CollisionShape<T>>) -> bool {
+        self.position(name).map(|idx| self.entries[idx].shape = shape).is_some()
+    }
+
+    /// Return the wake of the vehicle called `name` for modification, such as to update its lift or thrust
+    pub fn wake_mut(&mut self, name: &str) -> Option<&mut (dyn WakeModel<T> + 'static)> {
+        let idx = self.position(name)?;
+        self.entries[idx].wake.as_deref_mut()
+    }
+
+    /// Return the vehicle called `name`
+    pub fn get(&self, name: &str) -> Option<&WorldVehicle<I,T,W,D>> {
+        self.position(name).map(|idx| &self.entries[idx].vehicle)
+    }
+
+    /// Return the vehicle called `name` for modification
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut WorldVehicle<I,T,W,D>> {
+        self.position(name).map(move |idx| &mut self.entries[idx].vehicle)
+    }
+
+    /// Return the names of the vehicles in stepping order
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.entries.iter().map(|entry| entry.name.as_str())
+    }
+
+    /// Return the names and vehicles in stepping order
+    pub fn vehicles(&self) -> impl Iterator<Item=(&str,&WorldVehicle<I,T,W,D>)> {
+        self.entries.iter().map(|entry| (entry.name.as_str(),&entry.vehicle))
+    }
+
+    /// Return the number of vehicles
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return `true` if the world has no vehicles
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return the [RelativeState] of the vehicle called `target` seen from the vehicle called `observer`
+    ///
+    /// In [Frame::World] the position and velocity are in world axes. In [Frame::Body] they are the world
+    /// quantities resolved in the body axes of the observer, without the velocity due to the rotation
+    /// of those axes. Returns `None` if either vehicle is not in the world.
+    pub fn relative_state(&self, observer: &str, target: &str, frame: Frame) -> Option<RelativeState<T>> {
+        let (observer, target) = (self.get(observer)?,self.get(target)?);
+        let position = target.position() - observer.position();
+        let velocity = target.velocity_in_frame(Frame::World) - observer.velocity_in_frame(Frame::World);
+        Some(match frame {
+            Frame::World => RelativeState::new(position,velocity),
+            Frame::Body => RelativeState::new(observer.dcm() * position,observer.dcm() * velocity),
+        })
+    }
+
+    /// Return the world-frame velocity induced at `position` by the wakes of all vehicles except `exclude`
+    pub fn induced_velocity(&self, position: &Vector3<T>, exclude: Option<&str>) -> Vector3<T> {
+        self.entries.iter()
+            .filter(|entry| Some(entry.name.as_str()) != exclude)
+            .filter_map(|entry| entry.wake.as_ref().map(|wake| wake.induced_velocity(&entry.vehicle.statevector(),entry.vehicle.get_airstate(),position)))
+            .fold(Vector3::zeros(),|total, velocity| total + velocity)
+    }
+
+    /// Update the induced velocity in the wind of every vehicle from the current wakes of the others
+    pub fn update_interactions(&mut self) {
+        let induced: Vec<Vector3<T>> = self.entries.iter()
+            .map(|entry| self.induced_velocity(&entry.vehicle.position(),Some(&entry.name)))
+            .collect();
+        for (entry, velocity) in self.entries.iter_mut().zip(induced) {
+            entry.vehicle.body.wind_model_mut().set_induced(velocity);
+        }
+    }
+
+    /// Update the interactions and step every vehicle by `delta_t` (s) with its inputstate in `inputs`, in stepping order
+    ///
+    /// The loads of each [Towline] are set from the states at the start of the step. Contacts are then
+    /// detected after the step, raising a [Collision] for each new one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not one inputstate per vehicle.
+    pub fn step(&mut self, delta_t: T, inputs: &[I]) {
+        if inputs.len() != self.entries.len() {
+            panic!("World has {} vehicles but {} inputstates were given",self.entries.len(),inputs.len());
+        }
+        self.update_interactions();
+        self.update_towlines(delta_t);
+        for (entry, inputstate) in self.entries.iter_mut().zip(inputs) {
+            entry.vehicle.step(delta_t,inputstate);
+        }
+        self.time += delta_t;
+        self.update_contacts();
+    }
+
+    /// Add `towline` from the vehicle called `tow`, to the vehicle called `tail` if given, returning its index
+    ///
+    /// The line is [deployed](Towline::deploy) behind the towing vehicle, and a [ContactModel] is added to
+    /// each vehicle to apply the loads of the line. The tail end is attached to the towed vehicle only
+    /// once [attached](Towline::attach_tail) or captured. Returns `None` if either vehicle is not in the world.
+    pub fn add_towline(&mut self, mut towline: Towline<T>, tow: &str, tail: Option<&str>) -> Option<usize> {
+        let tail_idx = match tail {
+            Some(name) => Some(self.position(name)?),
+            None => None,
+        };
+        let tow_idx = self.position(tow)?;
+        towline.deploy(&self.entries[tow_idx].vehicle.statevector());
+        let tow_load = Rc::new(Cell::new(unloaded()));
+        self.entries[tow_idx].vehicle.add_contact(Box::new(TowlineContact { load: tow_load.clone() }));
+        let tail_load = tail_idx.map(|idx| {
+            let load = Rc::new(Cell::new(unloaded()));
+            self.entries[idx].vehicle.add_contact(Box::new(TowlineContact { load: load.clone() }));
+            load
+        });
+        self.towlines.push(WorldTowline { towline, tow: tow.to_string(), tail: tail.map(str::to_string), tow_load, tail_load });
+        Some(self.towlines.len() - 1)
+    }
+
+    /// Return the towline at `index`
+    pub fn towline(&self, index: usize) -> Option<&Towline<T>> {
+        self.towlines.get(index).map(|line| &line.towline)
+    }
+
+    /// Return the towline at `index` for modification, such as to release the tail
+    pub fn towline_mut(&mut self, index: usize) -> Option<&mut Towline<T>> {
+        self.towlines.get_mut(index).map(|line| &mut line.towline)
+    }
+
+    /// Set the loads of each towline for the coming step from the vehicle states, and advance the lines by `delta_t` (s)
+    ///
+    /// The line sees the ambient wind at the towing vehicle, with the velocity induced at the drogue by the wakes of
+    /// all vehicles. A line whose towing vehicle has been removed gives no loads and is not advanced.
+    fn update_towlines(&mut self, delta_t: T) {
+        let induced: Vec<Vector3<T>> = self.towlines.iter().map(|line| self.induced_velocity(&line.towline.drogue_position(),None)).collect();
+        let entries = &self.entries;
+        for (line, induced) in self.towlines.iter_mut().zip(induced) {
+            let tow = match entries.iter().find(|entry| entry.name == line.tow) {
+                Some(entry) => &entry.vehicle,
+                None => {
+                    line.tow_load.set(unloaded());
+                    continue;
+                },
+            };
+            let tail = line.tail.as_ref().and_then(|name| entries.iter().find(|entry| &entry.name == name)).map(|entry| entry.vehicle.statevector());
+            let tow_state = tow.statevector();
+            line.tow_load.set(line.towline.tow_effect(&tow_state));
+            if let Some(load) = &line.tail_load {
+                load.set(tail.as_ref().and_then(|tail| line.towline.tail_effect(tail)).unwrap_or_else(unloaded));
+            }
+            line.towline.step(delta_t,&tow_state,tail.as_ref(),tow.body.get_wind() - tow.body.wind_model().induced() + induced,tow.get_airstate().density);
+        }
+    }
+
+    /// Return the names and penetration depths (m) of the pairs of vehicles with shapes which are currently in contact
+    ///
+    /// Pairs whose bounding spheres are apart are not checked further.
+    pub fn detect_contacts(&self) -> Vec<(&str,&str,T)> {
+        let shaped: Vec<_> = self.entries.iter().filter_map(|entry| entry.shape.as_ref().map(|shape| (entry,shape))).collect();
+        let mut contacts = Vec::new();
+        for (idx, (entry, shape)) in shaped.iter().enumerate() {
+            let position = entry.vehicle.position();
+            for (other, other_shape) in shaped[idx + 1..].iter() {
+                let other_position = other.vehicle.position();
+                if (other_position - position).norm() > shape.bounding_radius() + other_shape.bounding_radius() {
+                    continue;
+                }
+                let depth = shape.penetration(&position,&entry.vehicle.dcm().transpose(),other_shape,&other_position,&other.vehicle.dcm().transpose());
+                if depth >= T::zero() {
+                    contacts.push((entry.name.as_str(),other.name.as_str(),depth));
+                }
+            }
+        }
+        contacts
+    }
+
+    /// Return `true` if the vehicles called `first` and `second` were in contact after the latest step
+    pub fn in_contact(&self, first: &str, second: &str) -> bool {
+        self.contacts.iter().any(|(a, b)| (a == first && b == second) || (a == second && b == first))
+    }
+
+    /// Return and clear the [Collision]s raised since they were last taken
+    ///
+    /// A collision is raised when a pair of vehicles comes into contact, once per contact.
+    pub fn take_collisions(&mut self) -> Vec<Collision<T>> {
+        std::mem::take(&mut self.collisions)
+    }
+
+    /// Detect contacts after a step, raising a [Collision] for each new one
+    fn update_contacts(&mut self) {
+        let contacts: Vec<(String,String,T)> = self.detect_contacts().into_iter().map(|(first, second, depth)| (first.to_string(),second.to_string(),depth)).collect();
+        for (first, second, depth) in contacts.iter() {
+            if !self.in_contact(first,second) {
+                self.collisions.push(Collision { time: self.time, first: first.clone(), second: second.clone(), depth: *depth });
+            }
+        }
+        self.contacts = contacts.into_iter().map(|(first, second, _)| (first,second)).collect();
+    }
+
+    /// Return the elapsed simulation time (s), the authoritative clock of the vehicles in the world
+    pub fn time(&self) -> T {
+        self.time
+    }
+
+    /// Set the elapsed simulation time (s) of the world and its vehicles
+    pub fn set_time(&mut self, time: T) {
+        self.time = time;
+        for entry in self.entries.iter_mut() {
+            entry.vehicle.set_time(time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AeroBody;
+    use crate::density_models::StandardDensity;
+    use crate::wind_models::ConstantWind;
+    use crate::types::UnitQuaternion;
+    use approx::assert_relative_eq;
+
+    type Vehicle = AffectedBody<Vec<f64>,f64,InteractionWind<f64,ConstantWind<f64>>,StandardDensity>;
+
+    fn vehicle(position: Vector3<f64>, speed: f64) -> Vehicle {
+        let body = Body::new(10.0,Matrix3::identity(),position,Vector3::new(speed,0.0,0.0),UnitQuaternion::identity(),Vector3::zeros());
+        AffectedBody::new(AeroBody::with_density_model(body,InteractionWind::new(ConstantWind::new(Vector3::zeros())),StandardDensity{}),vec![])
+    }
+
+    #[test]
+    fn test_vortex_wake() {
+        let leader = vehicle(Vector3::zeros(),30.0);
+        let wake = VortexWake::new(4.0,100.0);
+        let airstate = leader.get_airstate();
+        let spacing = VortexWake::<f64>::spacing(4.0);
+        let induced = |position: Vector3<f64>| wake.induced_velocity(&leader.statevector(),airstate,&position);
+
+        // Downwash behind the leader, upwash outboard of its tips, and symmetric about the centreline
+        let behind = induced(Vector3::new(-1000.0,0.0,0.0));
+        assert!(behind[2] > 0.0);
+        let outboard = induced(Vector3::new(-1000.0,spacing,0.0));
+        assert!(outboard[2] < 0.0);
+        assert_relative_eq!(outboard[2],induced(Vector3::new(-1000.0,-spacing,0.0))[2],max_relative=1e-9);
+
+        // Far behind, the pair induces the two-dimensional downwash 2Γ/(π b') on the centreline
+        let gamma = wake.circulation(airstate);
+        let core = 0.1 * spacing;
+        let half = spacing / 2.0;
+        let expected = 2.0 * gamma / (2.0 * std::f64::consts::PI) * half / (half * half + core * core);
+        assert_relative_eq!(behind[2],expected,max_relative=1e-3);
+        // Half of that at the wing itself, and almost nothing far ahead
+        assert_relative_eq!(induced(Vector3::new(0.0,0.0,0.0))[2],expected / 2.0,max_relative=1e-6);
+        assert!(induced(Vector3::new(1000.0,0.0,0.0)).norm() < 1e-3 * expected);
+    }
+
+    #[test]
+    fn test_world() {
+        let mut world = World::new();
+        world.add("leader",vehicle(Vector3::new(20.0,0.0,0.0),30.0),Some(Box::new(VortexWake::new(4.0,100.0))));
+        world.add("wingman",vehicle(Vector3::new(0.0,VortexWake::<f64>::spacing(4.0),0.0),30.0),None);
+        world.add("helicopter",vehicle(Vector3::new(0.0,-50.0,-20.0),0.0),Some(Box::new(RotorDownwash::new(5.0,10_000.0))));
+        assert_eq!(world.names().collect::<Vec<_>>(),vec!["leader","wingman","helicopter"]);
+
+        world.step(0.01,&[vec![],vec![],vec![]]);
+        // The wingman flies in the upwash of the leader, which sees no wake of its own
+        let wingman = world.get("wingman").unwrap();
+        assert!(wingman.body.wind_model().induced()[2] < 0.0);
+        assert!(wingman.get_airstate().alpha > 0.0);
+        assert_eq!(world.get("leader").unwrap().body.wind_model().induced(),Vector3::zeros());
+
+        // Downwash below the rotor is between the disk and fully developed values
+        let density = 1.225;
+        let v_i = RotorDownwash::new(5.0,10_000.0).disk_velocity(density);
+        let below = world.induced_velocity(&Vector3::new(0.0,-50.0,0.0),None)[2];
+        assert!(below > v_i && below < 2.0 * v_i);
+        assert_eq!(world.induced_velocity(&Vector3::new(0.0,-50.0,-40.0),Some("leader")),Vector3::zeros());
+
+        assert!(world.remove("helicopter").is_some());
+        assert!(!world.set_wake("helicopter",None));
+        assert_eq!(world.len(),2);
+
+        // Vehicles share the clock of the world, including those added later
+        assert_eq!(world.time(),0.01);
+        assert_eq!(world.get("leader").unwrap().time(),0.01);
+        world.add("late",vehicle(Vector3::new(0.0,100.0,0.0),30.0),None);
+        assert_eq!(world.get("late").unwrap().time(),0.01);
+        world.set_time(5.0);
+        world.step(0.5,&[vec![],vec![],vec![]]);
+        assert!(world.vehicles().all(|(_, vehicle)| vehicle.time() == 5.5));
+    }
+
+    #[test]
+    fn test_relative_state() {
+        let mut world = World::new();
+        world.add("tanker",vehicle(Vector3::new(100.0,0.0,-1000.0),150.0),None);
+        world.add("receiver",vehicle(Vector3::new(0.0,0.0,-990.0),160.0),None);
+        let mut heading = vehicle(Vector3::new(0.0,0.0,-990.0),160.0);
+        heading.set_pose(&crate::types::Isometry3::from_parts(Vector3::new(0.0,0.0,-990.0).into(),UnitQuaternion::from_euler_angles(0.0,0.0,-std::f64::consts::FRAC_PI_2)));
+        world.add("crossing",heading,None);
+        assert!(world.relative_state("receiver","missing",Frame::World).is_none());
+
+        // The tanker is ahead and above, and the receiver closes at 10 m/s
+        let relative = world.relative_state("receiver","tanker",Frame::World).unwrap();
+        assert_relative_eq!(relative.range,(100.0f64 * 100.0 + 10.0 * 10.0).sqrt(),max_relative=1e-12);
+        assert_relative_eq!(relative.bearing,0.0);
+        assert_relative_eq!(relative.elevation,(10.0f64).atan2(100.0),max_relative=1e-12);
+        assert_relative_eq!(relative.closure_rate,10.0 * 100.0 / relative.range,max_relative=1e-12);
+        assert_relative_eq!(relative.time_to_closest_approach(),10.0,max_relative=1e-12);
+        assert_relative_eq!(relative.miss_distance(),10.0,max_relative=1e-12);
+
+        // Heading west, the tanker to the north is off the right wing
+        let relative = world.relative_state("crossing","tanker",Frame::Body).unwrap();
+        assert_relative_eq!(relative.bearing,std::f64::consts::FRAC_PI_2,max_relative=1e-12);
+        assert_relative_eq!(relative.range,world.relative_state("crossing","tanker",Frame::World).unwrap().range,max_relative=1e-12);
+    }
+
+    #[test]
+    fn test_collision_shapes() {
+        let identity = Matrix3::identity();
+        let sphere = CollisionShape::Sphere(1.0);
+        let slab = CollisionShape::Box(Vector3::new(2.0,2.0,0.5));
+        assert_relative_eq!(sphere.penetration(&Vector3::zeros(),&identity,&sphere,&Vector3::new(1.5,0.0,0.0),&identity),0.5);
+        // A sphere above the slab, and one sitting off its corner
+        assert_relative_eq!(sphere.penetration(&Vector3::new(0.0,0.0,-1.25),&identity,&slab,&Vector3::zeros(),&identity),0.25,max_relative=1e-12);
+        assert!(slab.penetration(&Vector3::zeros(),&identity,&sphere,&Vector3::new(2.8,2.8,0.0),&identity) < 0.0);
+        // A box rotated 45° about Z reaches further along X
+        let rotated = UnitQuaternion::from_euler_angles(0.0,0.0,std::f64::consts::FRAC_PI_4).to_rotation_matrix().into_inner();
+        let cube = CollisionShape::Box(Vector3::new(1.0,1.0,1.0));
+        assert!(cube.penetration(&Vector3::zeros(),&identity,&cube,&Vector3::new(2.2,0.0,0.0),&identity) < 0.0);
+        assert_relative_eq!(cube.penetration(&Vector3::zeros(),&identity,&cube,&Vector3::new(2.2,0.0,0.0),&rotated),2.0f64.sqrt() - 1.2,max_relative=1e-9);
+    }
+
+    #[test]
+    fn test_collisions() {
+        let mut world = World::new();
+        world.add("east",vehicle(Vector3::new(0.0,0.0,-100.0),10.0),None);
+        world.add("west",vehicle(Vector3::new(10.0,0.0,-100.0),-10.0),None);
+        world.add("ghost",vehicle(Vector3::new(5.0,0.0,-100.0),0.0),None);
+        assert!(world.set_collision_shape("east",Some(CollisionShape::Sphere(1.0))));
+        assert!(world.set_collision_shape("west",Some(CollisionShape::Box(Vector3::new(1.0,1.0,0.5)))));
+        let mut collisions = Vec::new();
+        for _ in 0..60 {
+            world.step(0.01,&[vec![],vec![],vec![]]);
+            collisions.extend(world.take_collisions());
+        }
+        // Closing at 20 m/s, the 8 m gap closes in 0.4 s and the contact is raised once
+        assert_eq!(collisions.len(),1);
+        assert_eq!((collisions[0].first.as_str(),collisions[0].second.as_str()),("east","west"));
+        assert_relative_eq!(collisions[0].time,0.4,epsilon=0.011);
+        assert!(world.in_contact("west","east"));
+        assert!(!world.in_contact("east","ghost"));
+    }
+
+    #[test]
+    fn test_towline() {
+        let mut world = World::new();
+        world.add("tug",vehicle(Vector3::new(42.0,0.0,-500.0),30.0),None);
+        world.add("glider",vehicle(Vector3::new(0.0,0.0,-500.0),25.0),None);
+        let mut towline = Towline::new(Vector3::new(-1.0,0.0,0.0),40.0,8,2e4);
+        towline.attach_tail(Vector3::new(1.0,0.0,0.0));
+        assert!(world.add_towline(towline.clone(),"tug",Some("missing")).is_none());
+        let index = world.add_towline(towline,"tug",Some("glider")).unwrap();
+        assert_eq!(world.get("glider").unwrap().contacts(),1);
+        for _ in 0..100 {
+            world.step(0.01,&[vec![],vec![]]);
+        }
+        // The slower glider is pulled along by the tug, which is held back
+        let tug = world.get("tug").unwrap().velocity();
+        let glider = world.get("glider").unwrap().velocity();
+        assert!(tug[0] < 30.0 && glider[0] > 25.0,"tug {} glider {}",tug,glider);
+        assert_eq!(world.towline(index).unwrap().nodes().len(),9);
+
+        // Released, the glider coasts
+        world.towline_mut(index).unwrap().release_tail();
+        world.step(0.01,&[vec![],vec![]]);
+        let speed = world.get("glider").unwrap().velocity_in_frame(Frame::World)[0];
+        world.step(0.01,&[vec![],vec![]]);
+        assert_relative_eq!(world.get("glider").unwrap().velocity_in_frame(Frame::World)[0],speed,max_relative=1e-9);
+    }
+}
@@ -0,0 +1,263 @@
+#![warn(clippy::all)]
+
+use aerso::{Body,AeroBody,AffectedBody,StaticAffectedBody,AeroEffect,AirState,VehicleGeometry};
+use aerso::types::{Vector3,Matrix3,UnitQuaternion,Force,Torque,StateView};
+use aerso::inputs::{ChannelRouter,ControlInputs,AircraftControls};
+use aerso::schedule::{InputSchedule,Signal};
+use aerso::effector_models::{Actuated,Actuator,ActuatorDynamics,MultirotorRotor,PropellerInput,Rotation,StallAero,DynamicStall};
+
+use approx::assert_relative_eq;
+
+use std::rc::Rc;
+use std::cell::Cell;
+
+/// Effect that applies the commanded X force
+struct Thrust;
+impl AeroEffect<Vec<f64>> for Thrust {
+    fn get_effect(&self, _airstate: AirState, _rates: Vector3, inputstate: &Vec<f64>) -> (Force,Torque) {
+        (Force::body(inputstate[0],0.0,0.0),Torque::body(0.0,0.0,0.0))
+    }
+}
+
+/// Effect that accumulates the time it has been stepped for
+struct StepCounter {
+    elapsed: Rc<Cell<f64>>,
+}
+impl AeroEffect<Vec<f64>> for StepCounter {
+    fn get_effect(&self, _airstate: AirState, _rates: Vector3, _inputstate: &Vec<f64>) -> (Force,Torque) {
+        (Force::body(0.0,0.0,0.0),Torque::body(0.0,0.0,0.0))
+    }
+
+    fn step(&mut self, _airstate: AirState, _rates: Vector3, _inputstate: &Vec<f64>, delta_t: f64) {
+        self.elapsed.set(self.elapsed.get() + delta_t);
+    }
+}
+
+fn vehicle(effectors: Vec<Box<dyn AeroEffect<Vec<f64>>>>) -> AffectedBody<Vec<f64>> {
+    let body = Body::new_at_origin(1.0,Matrix3::identity());
+    AffectedBody::new(AeroBody::new(body),effectors)
+}
+
+#[test]
+fn test_effector_step() {
+    let elapsed = Rc::new(Cell::new(0.0));
+    let mut vehicle = vehicle(vec![Box::new(StepCounter { elapsed: elapsed.clone() })]);
+
+    for _ in 0..10 {
+        vehicle.step(0.1,&vec![]);
+    }
+
+    assert_relative_eq!(elapsed.get(),1.0);
+}
+
+#[test]
+fn test_effector_effects() {
+    let mut vehicle = vehicle(vec![Box::new(Thrust),Box::new(Thrust)]);
+    assert_eq!(vehicle.effector_effects().count(),0);
+
+    vehicle.step(0.01,&vec![2.0]);
+    assert_eq!(vehicle.effector_effects().count(),2);
+    let (force,_) = vehicle.effector_effect("1").unwrap();
+    assert_relative_eq!(force.force.x,2.0);
+    assert!(vehicle.effector_effect("2").is_none());
+}
+
+#[test]
+fn test_named_effectors() {
+    let mut vehicle = vehicle(vec![]);
+    assert!(vehicle.add_effector("engine",Box::new(Thrust)).is_none());
+    assert!(vehicle.add_effector("booster",Box::new(Thrust)).is_none());
+    assert_eq!(vehicle.effectors.names().collect::<Vec<_>>(),vec!["engine","booster"]);
+
+    // A failed booster no longer contributes
+    assert!(vehicle.disable("booster"));
+    vehicle.step(1.0,&vec![1.0]);
+    assert!(vehicle.effector_effect("booster").is_none());
+    assert_relative_eq!(vehicle.velocity().x,1.0,max_relative=1e-9);
+
+    assert!(vehicle.enable("booster"));
+    assert_eq!(vehicle.effectors.is_enabled("booster"),Some(true));
+    assert!(vehicle.remove_effector("engine").is_some());
+    assert!(vehicle.remove_effector("engine").is_none());
+    assert!(!vehicle.disable("engine"));
+    assert_eq!(vehicle.effectors.len(),1);
+}
+
+#[test]
+fn test_shared_geometry() {
+    // Wing models built with disagreeing reference areas all adopt the vehicle geometry
+    let body = Body::new(1.0,Matrix3::identity(),Vector3::zeros(),Vector3::new(20.0,0.0,2.0),UnitQuaternion::identity(),Vector3::zeros());
+    let wing = |s: f64| StallAero::new(s,0.2,5.0,0.03,0.05,15.0f64.to_radians());
+    let stall = |s: f64, c: f64| DynamicStall::new(s,c,5.5,0.02,12.0f64.to_radians(),30.0,3.0,2.0);
+    let mut vehicle: AffectedBody<Vec<f64>> = AffectedBody::new(AeroBody::new(body),vec![Box::new(wing(1.0))])
+        .with_geometry(VehicleGeometry::new(2.0,6.0,0.35));
+    vehicle.add_effector("actuated",Box::new(Actuated::new(wing(5.0),0,Actuator::new(ActuatorDynamics::Ideal))));
+    vehicle.add_effector("dynamic",Box::new(stall(1.0,0.1)));
+    assert_relative_eq!(vehicle.geometry().unwrap().aspect_ratio(),18.0,max_relative=1e-12);
+
+    // The dynamic stall model also adopts the chord, which sets its separation lag
+    let mut reference = stall(2.0,0.35);
+    for _ in 0..10 {
+        let airstate = vehicle.get_airstate();
+        let (expected, _) = AeroEffect::<Vec<f64>>::get_effect(&wing(2.0),airstate,Vector3::zeros(),&vec![0.0]);
+        let (expected_stall, _) = AeroEffect::<Vec<f64>>::get_effect(&reference,airstate,Vector3::zeros(),&vec![0.0]);
+        AeroEffect::<Vec<f64>>::step(&mut reference,airstate,Vector3::zeros(),&vec![0.0],0.01);
+        vehicle.step(0.01,&vec![0.0]);
+        for name in ["0","actuated"].iter() {
+            let (force, _) = vehicle.effector_effect(name).unwrap();
+            assert!((force.force - expected.force).norm() < 1e-9);
+        }
+        let (force, _) = vehicle.effector_effect("dynamic").unwrap();
+        assert!((force.force - expected_stall.force).norm() < 1e-9);
+    }
+    assert!(reference.separation() < 1.0);
+
+    // A statically-dispatched vehicle passes the geometry to each effector of its set
+    let mut static_vehicle = StaticAffectedBody::new(AeroBody::new(body),(wing(1.0),stall(1.0,0.1)))
+        .with_geometry::<Vec<f64>>(VehicleGeometry::new(2.0,6.0,0.35));
+    let mut static_reference = StaticAffectedBody::new(AeroBody::new(body),(wing(2.0),stall(2.0,0.35)));
+    assert!(static_reference.geometry().is_none());
+    for _ in 0..10 {
+        static_vehicle.step(0.01,&vec![0.0]);
+        static_reference.step(0.01,&vec![0.0]);
+    }
+    assert_eq!(static_vehicle.statevector(),static_reference.statevector());
+}
+
+#[test]
+fn test_actuated_thrust() {
+    // An ideal, rate-limited actuator ramps thrust from zero at 1 N/s
+    let actuator = Actuator::new(ActuatorDynamics::Ideal).with_rate_limit(1.0);
+    let mut vehicle = vehicle(vec![Box::new(Actuated::new(Thrust,0,actuator))]);
+
+    let delta_t = 0.01;
+    let steps = 100;
+    for _ in 0..steps {
+        vehicle.step(delta_t,&vec![10.0]);
+    }
+
+    // Thrust at the start of step k is k*delta_t, giving a velocity of sum(k*delta_t*delta_t)
+    let expected_velocity = (0..steps).map(|k| k as f64 * delta_t * delta_t).sum::<f64>();
+    assert_relative_eq!(vehicle.velocity().x,expected_velocity,max_relative=1e-6);
+}
+
+#[test]
+fn test_quadrotor_hover() {
+    // Rotor thrust at hover balances the weight of a 1 kg quadrotor
+    let k_thrust: f64 = 1.2e-5;
+    let omega = (9.80665 / (4.0 * k_thrust)).sqrt();
+    let rpm = omega * 30.0 / std::f64::consts::PI;
+
+    let rotor = |x: f64, y: f64, rotation: Rotation, channel: usize| -> Box<dyn AeroEffect<Vec<f64>>> {
+        Box::new(MultirotorRotor::new(0.12,k_thrust,1.5e-7,Vector3::new(x,y,0.0),rotation,PropellerInput::Rpm(channel)))
+    };
+    let mut vehicle = vehicle(vec![
+        rotor(0.2,0.2,Rotation::CounterClockwise,0),
+        rotor(-0.2,-0.2,Rotation::CounterClockwise,1),
+        rotor(0.2,-0.2,Rotation::Clockwise,2),
+        rotor(-0.2,0.2,Rotation::Clockwise,3),
+    ]);
+
+    for _ in 0..100 {
+        vehicle.step(0.01,&vec![rpm; 4]);
+    }
+    assert_relative_eq!(vehicle.velocity().z,0.0,epsilon=1e-6);
+    assert_relative_eq!(vehicle.rates().norm(),0.0,epsilon=1e-9);
+
+    // Speeding up the clockwise pair yaws the vehicle to the left
+    vehicle.step(0.01,&vec![rpm,rpm,rpm*1.1,rpm*1.1]);
+    assert!(vehicle.rates().z < 0.0);
+}
+
+#[test]
+fn test_static_dispatch() {
+    // Statically-dispatched effectors match the boxed equivalent
+    let elapsed = Rc::new(Cell::new(0.0));
+    let body = Body::new_at_origin(1.0,Matrix3::identity());
+    let mut static_vehicle = StaticAffectedBody::new(AeroBody::new(body),(Thrust,Thrust,Thrust,StepCounter { elapsed: elapsed.clone() }));
+    let mut boxed_vehicle = vehicle(vec![Box::new(Thrust),Box::new(Thrust),Box::new(Thrust)]);
+
+    for _ in 0..10 {
+        static_vehicle.step(0.1,&vec![1.0]);
+        boxed_vehicle.step(0.1,&vec![1.0]);
+    }
+    assert_relative_eq!(elapsed.get(),1.0);
+    assert_relative_eq!(static_vehicle.velocity().x,3.0,max_relative=1e-9);
+    assert_relative_eq!(static_vehicle.velocity().x,boxed_vehicle.velocity().x);
+    assert_relative_eq!(static_vehicle.position().z,boxed_vehicle.position().z);
+
+    let mut array_vehicle = StaticAffectedBody::new(AeroBody::new(body),[Thrust,Thrust,Thrust]);
+    array_vehicle.step(1.0,&vec![1.0]);
+    assert_relative_eq!(array_vehicle.velocity().x,3.0,max_relative=1e-9);
+}
+
+#[test]
+fn test_routed_commands() {
+    let mut vehicle = vehicle(vec![Box::new(Thrust)])
+        .with_router(ChannelRouter::new(vec![0.0]).route_with_gain("thrust_kn",0,1000.0));
+    assert_eq!(vehicle.router().unwrap().commands(),vec!["thrust_kn"]);
+
+    vehicle.step_commands(0.001,&[("thrust_kn",0.002)]);
+    assert_relative_eq!(vehicle.velocity().x,0.002,max_relative=1e-9);
+}
+
+#[test]
+fn test_saturated_step() {
+    /// Effect that applies the commanded throttle as an X force
+    struct Throttle;
+    impl AeroEffect<AircraftControls> for Throttle {
+        fn get_effect(&self, _airstate: AirState, _rates: Vector3, inputstate: &AircraftControls) -> (Force,Torque) {
+            (Force::body(inputstate.throttle,0.0,0.0),Torque::body(0.0,0.0,0.0))
+        }
+    }
+    let body = Body::new_at_origin(1.0,Matrix3::identity());
+    let mut vehicle: AffectedBody<AircraftControls> = AffectedBody::new(AeroBody::new(body),vec![Box::new(Throttle)]);
+    vehicle.step_saturated(1.0,&AircraftControls { throttle: 2.0, ..AircraftControls::neutral() });
+    assert_relative_eq!(vehicle.velocity().x,1.0,max_relative=1e-9);
+}
+
+#[test]
+fn test_run_schedule() {
+    // A thrust doublet returns the vehicle to rest, with its edges between steps so that rounding of
+    // the accumulated body clock cannot move them by a step
+    let mut vehicle = vehicle(vec![Box::new(Thrust)]);
+    let schedule = InputSchedule::new(vec![0.0]).with_signal(0,Signal::Doublet { start: 0.495, width: 1.0, amplitude: 2.0 });
+
+    let history = vehicle.run_schedule(&schedule,3.0,0.01);
+    assert_eq!(history.len(),301);
+    assert_relative_eq!(history.last().unwrap().time,3.0,max_relative=1e-9);
+    assert_relative_eq!(history.samples()[150].state[3],2.0,max_relative=1e-6);
+    assert_relative_eq!(vehicle.velocity().x,0.0,epsilon=1e-9);
+}
+
+#[test]
+fn test_runs_continue_body_clock() {
+    // A second run carries on from the end of the first, in both the schedule and the recorded time
+    let mut vehicle = vehicle(vec![Box::new(Thrust)]);
+    let schedule = InputSchedule::new(vec![0.0]).with_signal(0,Signal::Step { start: 1.495, amplitude: 1.0 });
+    let first = vehicle.run_schedule(&schedule,1.0,0.01);
+    assert_relative_eq!(first.last().unwrap().time,1.0,max_relative=1e-9);
+    let second = vehicle.run_schedule(&schedule,1.0,0.01);
+    assert_relative_eq!(second.samples()[0].time,1.0,max_relative=1e-9);
+    assert_relative_eq!(second.last().unwrap().time,vehicle.time(),max_relative=1e-12);
+    assert_relative_eq!(vehicle.time(),2.0,max_relative=1e-9);
+    // The step fires half-way through the second run
+    assert_relative_eq!(vehicle.velocity().x,0.5,max_relative=1e-6);
+
+    let mut times = Vec::new();
+    vehicle.run(0.02,0.01,|_,_,time| {
+        times.push(time);
+        vec![0.0]
+    });
+    assert_relative_eq!(times[0],2.0,max_relative=1e-9);
+}
+
+#[test]
+fn test_closed_loop_run() {
+    // Proportional speed controller settles on the commanded speed
+    let mut vehicle = vehicle(vec![Box::new(Thrust)]);
+    let history = vehicle.run(10.0,0.01,|state,_airstate,_time| vec![2.0 * (5.0 - state.velocity().x)]);
+    assert_eq!(history.len(),1001);
+    assert_relative_eq!(vehicle.velocity().x,5.0,max_relative=1e-6);
+    assert_eq!(history.to_csv().lines().count(),1002);
+}